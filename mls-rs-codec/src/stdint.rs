@@ -34,14 +34,37 @@ impl_stdint!(u32);
 impl_stdint!(u64);
 impl_stdint!(u128);
 
+impl MlsSize for bool {
+    fn mls_encoded_len(&self) -> usize {
+        1
+    }
+}
+
+impl MlsEncode for bool {
+    fn mls_encode(&self, writer: &mut Vec<u8>) -> Result<(), crate::Error> {
+        u8::from(*self).mls_encode(writer)
+    }
+}
+
+impl MlsDecode for bool {
+    fn mls_decode(reader: &mut &[u8]) -> Result<Self, crate::Error> {
+        match u8::mls_decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(crate::Error::UnsupportedEnumDiscriminant),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
-    use crate::{MlsDecode, MlsEncode};
+    use crate::{Error, MlsDecode, MlsEncode};
 
     use alloc::vec;
+    use assert_matches::assert_matches;
 
     #[test]
     fn u8_round_trip() {
@@ -53,6 +76,22 @@ mod tests {
         assert_eq!(recovered, 42u8);
     }
 
+    #[test]
+    fn bool_round_trip() {
+        let serialized = true.mls_encode_to_vec().unwrap();
+        assert_eq!(serialized, vec![1u8]);
+
+        let recovered = bool::mls_decode(&mut &*serialized).unwrap();
+
+        assert!(recovered);
+    }
+
+    #[test]
+    fn invalid_bool_is_rejected() {
+        let res = bool::mls_decode(&mut [2u8].as_slice());
+        assert_matches!(res, Err(Error::UnsupportedEnumDiscriminant));
+    }
+
     #[test]
     fn u16_round_trip() {
         let serialized = 1024u16.mls_encode_to_vec().unwrap();
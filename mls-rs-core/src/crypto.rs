@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use crate::error::IntoAnyError;
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::{
@@ -15,6 +17,12 @@ use zeroize::{ZeroizeOnDrop, Zeroizing};
 mod cipher_suite;
 pub use self::cipher_suite::*;
 
+mod signer;
+pub use self::signer::*;
+
+mod self_test;
+pub use self::self_test::*;
+
 #[cfg(feature = "test_suite")]
 pub mod test_suite;
 
@@ -105,7 +113,7 @@ pub struct HpkeSecretKey(
 
 impl Debug for HpkeSecretKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        crate::debug::pretty_bytes(&self.0)
+        crate::debug::pretty_secret(&self.0)
             .named("HpkeSecretKey")
             .fmt(f)
     }
@@ -244,7 +252,7 @@ pub struct SignatureSecretKey {
 
 impl Debug for SignatureSecretKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        crate::debug::pretty_bytes(&self.bytes)
+        crate::debug::pretty_secret(&self.bytes)
             .named("SignatureSecretKey")
             .fmt(f)
     }
@@ -288,6 +296,12 @@ impl AsRef<[u8]> for SignatureSecretKey {
 }
 
 /// Provides implementations for several ciphersuites via [`CipherSuiteProvider`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
 pub trait CryptoProvider: Send + Sync {
     type CipherSuiteProvider: CipherSuiteProvider + Clone;
 
@@ -297,6 +311,16 @@ pub trait CryptoProvider: Send + Sync {
     /// Generate a [CipherSuiteProvider] for the given `cipher_suite`.
     fn cipher_suite_provider(&self, cipher_suite: CipherSuite)
         -> Option<Self::CipherSuiteProvider>;
+
+    /// Run known-answer tests of the algorithms of each supported ciphersuite,
+    /// as required for FIPS power-on self tests.
+    ///
+    /// The tests run again on each call. Wrap the provider in a
+    /// [`SelfTestedCryptoProvider`] to run them once at startup and only
+    /// expose the ciphersuites that passed.
+    async fn self_test(&self) -> SelfTestReport {
+        self_test::self_test_provider(self).await
+    }
 }
 
 /// Provides all cryptographic operations required by MLS for a given cipher suite.
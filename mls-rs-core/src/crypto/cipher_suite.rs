@@ -81,6 +81,41 @@ impl CipherSuite {
     pub fn all() -> impl Iterator<Item = CipherSuite> {
         (1..=7).map(CipherSuite)
     }
+
+    /// Determines if this ciphersuite is one of the default ciphersuites
+    /// defined by the MLS RFC.
+    pub const fn is_default(&self) -> bool {
+        matches!(self.0, 1..=7)
+    }
+
+    /// Determines if this is a GREASE value, as reserved by section 13.5 of
+    /// the MLS RFC. GREASE values are never used by actual ciphersuites.
+    pub const fn is_grease(&self) -> bool {
+        matches!(
+            self.0,
+            0x0A0A
+                | 0x1A1A
+                | 0x2A2A
+                | 0x3A3A
+                | 0x4A4A
+                | 0x5A5A
+                | 0x6A6A
+                | 0x7A7A
+                | 0x8A8A
+                | 0x9A9A
+                | 0xAAAA
+                | 0xBABA
+                | 0xCACA
+                | 0xDADA
+                | 0xEAEA
+        )
+    }
+
+    /// Determines if this ciphersuite is in the range reserved for private
+    /// use by the MLS RFC.
+    pub const fn is_private_use(&self) -> bool {
+        self.0 >= 0xF000
+    }
 }
 
 /// Modes of HPKE operation.
@@ -96,3 +131,38 @@ pub enum HpkeModeId {
     /// Authenticated variant that authenticates possession of a PSK as well as a KEM private key.
     AuthPsk = 0x03,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CipherSuite;
+
+    #[test]
+    fn cipher_suite_classification() {
+        assert!(CipherSuite::all().all(|cs| cs.is_default()));
+        assert!(!CipherSuite::new(0).is_default());
+        assert!(!CipherSuite::new(8).is_default());
+
+        let grease = (0..15).map(|i| CipherSuite::new(0x0A0A + i * 0x1010));
+        assert!(grease.clone().all(|cs| cs.is_grease() && !cs.is_default()));
+        assert_eq!(grease.count(), 15);
+
+        assert!(!CipherSuite::new(0xFAFA).is_grease());
+        assert!(!CipherSuite::new(0x0A1A).is_grease());
+        assert!(!CipherSuite::P256_AES128.is_grease());
+
+        assert!(CipherSuite::new(0xF000).is_private_use());
+        assert!(!CipherSuite::new(0xEFFF).is_private_use());
+    }
+
+    #[test]
+    fn cipher_suite_constants_can_be_matched() {
+        let name = |cs: CipherSuite| match cs {
+            CipherSuite::CURVE25519_AES128 => "curve25519",
+            CipherSuite::P256_AES128 => "p256",
+            _ => "other",
+        };
+
+        assert_eq!(name(CipherSuite::new(2)), "p256");
+        assert_eq!(name(CipherSuite::new(0xF001)), "other");
+    }
+}
@@ -0,0 +1,622 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use super::{
+    CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey,
+    SignaturePublicKey,
+};
+
+/// Algorithm of a cipher suite checked by a self test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelfTestAlgorithm {
+    Hash,
+    Mac,
+    Kdf,
+    Aead,
+    Hpke,
+    Signature,
+}
+
+/// Reason a cipher suite failed its self test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelfTestFailure {
+    /// The crypto provider returned no cipher suite provider for the cipher
+    /// suite.
+    Unavailable,
+    /// No known answer is available for the cipher suite, which isn't one of
+    /// the default cipher suites.
+    NoKnownAnswer,
+    /// The algorithm returned an error or an unexpected answer.
+    WrongAnswer(SelfTestAlgorithm),
+}
+
+/// Results of the self test of the cipher suites of a [`CryptoProvider`],
+/// returned by [`CryptoProvider::self_test`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    results: Vec<(CipherSuite, Result<(), SelfTestFailure>)>,
+}
+
+impl SelfTestReport {
+    /// Cipher suites that passed their self test.
+    pub fn passed_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(cipher_suite, _)| *cipher_suite)
+            .collect()
+    }
+
+    /// Whether `cipher_suite` was tested and passed its self test.
+    pub fn has_passed(&self, cipher_suite: CipherSuite) -> bool {
+        self.result(cipher_suite) == Some(Ok(()))
+    }
+
+    /// Whether all the tested cipher suites passed their self test.
+    pub fn all_passed(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Result of the self test of `cipher_suite`, or `None` if it wasn't
+    /// tested.
+    pub fn result(&self, cipher_suite: CipherSuite) -> Option<Result<(), SelfTestFailure>> {
+        self.results
+            .iter()
+            .find(|(tested, _)| *tested == cipher_suite)
+            .map(|(_, result)| *result)
+    }
+
+    /// Cipher suites that failed their self test, with the reason of the
+    /// failure.
+    pub fn failures(&self) -> Vec<(CipherSuite, SelfTestFailure)> {
+        self.results
+            .iter()
+            .filter_map(|(cipher_suite, result)| result.err().map(|e| (*cipher_suite, e)))
+            .collect()
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(super) async fn self_test_provider<C>(crypto_provider: &C) -> SelfTestReport
+where
+    C: CryptoProvider + ?Sized,
+{
+    let mut results = Vec::new();
+
+    for cipher_suite in crypto_provider.supported_cipher_suites() {
+        let result = match crypto_provider.cipher_suite_provider(cipher_suite) {
+            Some(cs) => self_test_cipher_suite(&cs).await,
+            None => Err(SelfTestFailure::Unavailable),
+        };
+
+        results.push((cipher_suite, result));
+    }
+
+    SelfTestReport { results }
+}
+
+/// Run known-answer tests of the hash, MAC, KDF, AEAD, HPKE and signature
+/// algorithms of `cs`, stopping at the first failure.
+///
+/// Known answers are only available for the default cipher suites.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn self_test_cipher_suite<P: CipherSuiteProvider>(cs: &P) -> Result<(), SelfTestFailure> {
+    let answers = KnownAnswers::new(cs.cipher_suite()).ok_or(SelfTestFailure::NoKnownAnswer)?;
+
+    check(answers.hash.run(cs).await, SelfTestAlgorithm::Hash)?;
+    check(answers.mac.run(cs).await, SelfTestAlgorithm::Mac)?;
+    check(answers.kdf.run(cs).await, SelfTestAlgorithm::Kdf)?;
+    check(answers.aead.run(cs).await, SelfTestAlgorithm::Aead)?;
+    check(answers.hpke.run(cs).await, SelfTestAlgorithm::Hpke)?;
+    check(
+        answers.signature.run(cs).await,
+        SelfTestAlgorithm::Signature,
+    )
+}
+
+fn check(passed: Option<()>, algorithm: SelfTestAlgorithm) -> Result<(), SelfTestFailure> {
+    passed.ok_or(SelfTestFailure::WrongAnswer(algorithm))
+}
+
+/// Crypto provider exposing only the cipher suites of the wrapped provider
+/// that passed their self test, run once when the provider is created.
+///
+/// This is meant for deployments that must run power-on self tests, such as
+/// FIPS validated ones: the cached [`SelfTestReport`] is returned by
+/// [`CryptoProvider::self_test`] without running the tests again.
+#[derive(Clone, Debug)]
+pub struct SelfTestedCryptoProvider<C> {
+    inner: C,
+    report: SelfTestReport,
+}
+
+impl<C: CryptoProvider> SelfTestedCryptoProvider<C> {
+    /// Run the self test of `inner` and wrap it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new(inner: C) -> Self {
+        let report = inner.self_test().await;
+        Self { inner, report }
+    }
+
+    /// Wrapped crypto provider.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Results of the self test run when the provider was created.
+    pub fn report(&self) -> &SelfTestReport {
+        &self.report
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<C: CryptoProvider> CryptoProvider for SelfTestedCryptoProvider<C> {
+    type CipherSuiteProvider = C::CipherSuiteProvider;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.report.passed_cipher_suites()
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        if !self.report.has_passed(cipher_suite) {
+            return None;
+        }
+
+        self.inner.cipher_suite_provider(cipher_suite)
+    }
+
+    async fn self_test(&self) -> SelfTestReport {
+        self.report.clone()
+    }
+}
+
+fn decode(value: &str) -> Vec<u8> {
+    // The known answers below are valid hex, a typo would fail the test.
+    hex::decode(value).unwrap_or_default()
+}
+
+struct HashTest {
+    input: &'static str,
+    output: &'static str,
+}
+
+impl HashTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let output = cs.hash(&decode(self.input)).await.ok()?;
+        (output == decode(self.output)).then_some(())
+    }
+}
+
+struct MacTest {
+    key: &'static str,
+    data: &'static str,
+    tag: &'static str,
+}
+
+impl MacTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let tag = cs.mac(&decode(self.key), &decode(self.data)).await.ok()?;
+
+        (tag == decode(self.tag)).then_some(())
+    }
+}
+
+struct KdfTest {
+    salt: &'static str,
+    ikm: &'static str,
+    info: &'static str,
+    prk: &'static str,
+    okm: &'static str,
+}
+
+impl KdfTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let prk = cs
+            .kdf_extract(&decode(self.salt), &decode(self.ikm))
+            .await
+            .ok()?;
+
+        let okm = decode(self.okm);
+
+        let expanded = cs
+            .kdf_expand(&prk, &decode(self.info), okm.len())
+            .await
+            .ok()?;
+
+        (*prk == decode(self.prk) && *expanded == okm).then_some(())
+    }
+}
+
+struct AeadTest {
+    key: &'static str,
+    nonce: &'static str,
+    aad: &'static str,
+    plaintext: &'static str,
+    ciphertext: &'static str,
+}
+
+impl AeadTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let (key, nonce, aad) = (decode(self.key), decode(self.nonce), decode(self.aad));
+        let plaintext = decode(self.plaintext);
+
+        let ciphertext = cs
+            .aead_seal(&key, &plaintext, Some(&aad), &nonce)
+            .await
+            .ok()?;
+
+        if ciphertext != decode(self.ciphertext) {
+            return None;
+        }
+
+        let opened = cs
+            .aead_open(&key, &ciphertext, Some(&aad), &nonce)
+            .await
+            .ok()?;
+
+        (*opened == plaintext).then_some(())
+    }
+}
+
+struct HpkeTest {
+    ikm: &'static str,
+    public: &'static str,
+    info: &'static str,
+    aad: &'static str,
+    kem_output: &'static str,
+    ciphertext: &'static str,
+    plaintext: &'static str,
+}
+
+impl HpkeTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let (secret, public) = cs.kem_derive(&decode(self.ikm)).await.ok()?;
+
+        if public != HpkePublicKey::from(decode(self.public)) {
+            return None;
+        }
+
+        let ciphertext = HpkeCiphertext {
+            kem_output: decode(self.kem_output),
+            ciphertext: decode(self.ciphertext),
+        };
+
+        let aad = decode(self.aad);
+
+        let opened = cs
+            .hpke_open(
+                &ciphertext,
+                &secret,
+                &public,
+                &decode(self.info),
+                Some(&aad),
+            )
+            .await
+            .ok()?;
+
+        (opened == decode(self.plaintext)).then_some(())
+    }
+}
+
+struct SignatureTest {
+    public: &'static str,
+    data: &'static str,
+    signature: &'static str,
+}
+
+impl SignatureTest {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run<P: CipherSuiteProvider>(&self, cs: &P) -> Option<()> {
+        let public = SignaturePublicKey::from(decode(self.public));
+        let signature = decode(self.signature);
+        let mut data = decode(self.data);
+
+        cs.verify(&public, &signature, &data).await.ok()?;
+
+        // A signature over other data must be rejected.
+        data.push(0);
+
+        cs.verify(&public, &signature, &data)
+            .await
+            .is_err()
+            .then_some(())
+    }
+}
+
+struct KnownAnswers {
+    hash: HashTest,
+    mac: MacTest,
+    kdf: KdfTest,
+    aead: AeadTest,
+    hpke: HpkeTest,
+    signature: SignatureTest,
+}
+
+impl KnownAnswers {
+    fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        let (hash, mac, kdf, aead, hpke, signature) = match cipher_suite {
+            CipherSuite::CURVE25519_AES128 => (
+                SHA256_HASH,
+                HMAC_SHA256,
+                HKDF_SHA256,
+                AES128GCM,
+                CURVE25519_AES128_HPKE,
+                CURVE25519_AES128_SIGNATURE,
+            ),
+            CipherSuite::P256_AES128 => (
+                SHA256_HASH,
+                HMAC_SHA256,
+                HKDF_SHA256,
+                AES128GCM,
+                P256_AES128_HPKE,
+                P256_AES128_SIGNATURE,
+            ),
+            CipherSuite::CURVE25519_CHACHA => (
+                SHA256_HASH,
+                HMAC_SHA256,
+                HKDF_SHA256,
+                CHACHA20POLY1305,
+                CURVE25519_CHACHA_HPKE,
+                CURVE25519_CHACHA_SIGNATURE,
+            ),
+            CipherSuite::CURVE448_AES256 => (
+                SHA512_HASH,
+                HMAC_SHA512,
+                HKDF_SHA512,
+                AES256GCM,
+                CURVE448_AES256_HPKE,
+                CURVE448_AES256_SIGNATURE,
+            ),
+            CipherSuite::P521_AES256 => (
+                SHA512_HASH,
+                HMAC_SHA512,
+                HKDF_SHA512,
+                AES256GCM,
+                P521_AES256_HPKE,
+                P521_AES256_SIGNATURE,
+            ),
+            CipherSuite::CURVE448_CHACHA => (
+                SHA512_HASH,
+                HMAC_SHA512,
+                HKDF_SHA512,
+                CHACHA20POLY1305,
+                CURVE448_CHACHA_HPKE,
+                CURVE448_CHACHA_SIGNATURE,
+            ),
+            CipherSuite::P384_AES256 => (
+                SHA384_HASH,
+                HMAC_SHA384,
+                HKDF_SHA384,
+                AES256GCM,
+                P384_AES256_HPKE,
+                P384_AES256_SIGNATURE,
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            hash,
+            mac,
+            kdf,
+            aead,
+            hpke,
+            signature,
+        })
+    }
+}
+
+// Known answers taken from the test data of the `test_suite` feature. The
+// MAC answers are from test case 1 of RFC 4231.
+
+const SHA256_HASH: HashTest = HashTest {
+    input: "294af4802e5e925eb1c6cc9c724f09",
+    output: "dcbaf335360de853b9cddfdafb90fa75567d0d3d58af8db9d764113aef570125",
+};
+
+const HMAC_SHA256: MacTest = MacTest {
+    key: "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+    data: "4869205468657265",
+    tag: "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+};
+
+const HKDF_SHA256: KdfTest = KdfTest {
+    salt: "ef",
+    ikm: "e9",
+    info: "7d",
+    prk: "1d8920b4c2bd5e2231b631cecfe63fb5485173159f8af4ec2a69b9f49c01c5de",
+    okm: "471679d84e357b83ce94b17bd256fb07",
+};
+
+const SHA384_HASH: HashTest = HashTest {
+    input: "695b9efe1809abd5d44eae957ddf9c2cd3c75fae2f522855712a07c639c0b9",
+    output: "3bb95d164d94595a1187f77fc26c280ffbb08e74ec7947aa3e5b38bec7c6f8115c4d880788c2402dbb3e5b94afd130ee",
+};
+
+const HMAC_SHA384: MacTest = MacTest {
+    key: "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+    data: "4869205468657265",
+    tag: "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6",
+};
+
+const HKDF_SHA384: KdfTest = KdfTest {
+    salt: "85",
+    ikm: "fb",
+    info: "b9",
+    prk: "9eae10d5f1b13223e2ad827a67b550ebd688e5210ca34a19495e47961563fb1d6119e1159ff2960a3ed244142d2a7992",
+    okm: "db4c14acc5a600fd224d3b8d84a46df7",
+};
+
+const SHA512_HASH: HashTest = HashTest {
+    input: "cfca05fd893c0f005f5ff796f4da19ba27a1e729956b8b715e67ce4b2d2a382a72ec7814f2f507b1825209a20fcc",
+    output: "d80969284a4565add4dad6ab9b3bdf53446142f84aaf92d4b23dd22ee7241e6c81489ac8b246edcb6df9bd7b23d91a0c517f546feba4ed5790a2be6e165c1709",
+};
+
+const HMAC_SHA512: MacTest = MacTest {
+    key: "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+    data: "4869205468657265",
+    tag: "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+};
+
+const HKDF_SHA512: KdfTest = KdfTest {
+    salt: "70",
+    ikm: "94",
+    info: "fc",
+    prk: "5a145cb32b79aaafdd953f478f99c0ef769d2af4f2d851ea1bbbf4db61103c45db563e22770326777b8c75a7cc7a5deba456897ff72c1020666fb45103fc7289",
+    okm: "88e9f6eaf1416fca0ca457d19ee08801",
+};
+
+const AES128GCM: AeadTest = AeadTest {
+    key: "387218b246c1a8257748b56980e50c94",
+    nonce: "dd7e014198672be39f95b69d",
+    aad: "",
+    plaintext: "48f5b426baca03064554cc2b30",
+    ciphertext: "cdba9e73eaf3d38eceb2b04a8decf90f4a47c9c626d6fb2c765d201556",
+};
+
+const CHACHA20POLY1305: AeadTest = AeadTest {
+    key: "1c9240a5eb55d38af333888604f6b5f0473917c1402b80099dca5cbc207075c0",
+    nonce: "000000000102030405060708",
+    aad: "",
+    plaintext: "496e7465726e65742d4472616674732061726520647261667420646f63756d656e74732076616c696420666f722061206d6178696d756d206f6620736978206d6f6e74687320616e64206d617920626520757064617465642c207265706c616365642c206f72206f62736f6c65746564206279206f7468657220646f63756d656e747320617420616e792074696d652e20497420697320696e617070726f70726961746520746f2075736520496e7465726e65742d447261667473206173207265666572656e6365206d6174657269616c206f7220746f2063697465207468656d206f74686572207468616e206173202fe2809c776f726b20696e2070726f67726573732e2fe2809d",
+    ciphertext: "64a0861575861af460f062c79be643bd5e805cfd345cf389f108670ac76c8cb24c6cfc18755d43eea09ee94e382d26b0bdb7b73c321b0100d4f03b7f355894cf332f830e710b97ce98c8a84abd0b948114ad176e008d33bd60f982b1ff37c8559797a06ef4f0ef61c186324e2b3506383606907b6a7c02b0f9f6157b53c867e4b9166c767b804d46a59b5216cde7a4e99040c5a40433225ee282a1b0a06c523eaf4534d7f83fa1155b0047718cbc546a0d072b04b3564eea1b422273f548271a0bb2316053fa76991955ebd63159434ecebb4e466dae5a1073a6727627097a1049e617d91d361094fa68f0ff77987130305beaba2eda04df997b714d6c6f2c29a6ad5cb4022b02709b6e3570b1acaaf1f24f2a644f01acd12b",
+};
+
+const AES256GCM: AeadTest = AeadTest {
+    key: "a71dac1377a3bf5d7fb1b5e36bee70d2e01de2a84a1c1009ba7448f7f26131dc",
+    nonce: "c5b60dda3f333b1146e9da7c",
+    aad: "",
+    plaintext: "5b87141335f2becac1a559e05f",
+    ciphertext: "43af49ec1ae3738a20755034d66f80b6ef2d8830a55eb63680a8dff9e0",
+};
+
+const CURVE25519_AES128_HPKE: HpkeTest = HpkeTest {
+    ikm: "fcc5e0fb78e3aa881f94a8de0e32b0868c61bcf1cd931beb807f26218f877a86",
+    public: "cd64282e186e5ef67f9400aba3d70991b2285ac70e581fb86e7b7660140eb975",
+    info: "5b",
+    aad: "0c",
+    kem_output: "528077a15761610123671088331723639ee4284d3677dc7733f57857b361267e",
+    ciphertext: "86d05ba2b5cb6fdb55510f038d2be2d232",
+    plaintext: "25",
+};
+
+const CURVE25519_AES128_SIGNATURE: SignatureTest = SignatureTest {
+    public: "ea3ece7e7b1704b59f5109437ea2d613eb0083889ea897dda53caf8520e18663",
+    data: "7d",
+    signature: "978fae427dc267fa565ef9ead87f7332f26eee11441408382c488ced22e6f6adb6859dca519b7fc4fbd69aceeedae888e98ae67dcfec82999086cd26b9500f0d",
+};
+
+const P256_AES128_HPKE: HpkeTest = HpkeTest {
+    ikm: "7649069b003cf27a25eb0d6b4b8a073b5e7b17bcb235faf991adfc48bedb0dc8",
+    public: "04822baacefc4a727c8933e47f61be0b77ea76c79cde0b391c42cd1366f51d8c3ef7dbb5abe85fb9193fb660bc0fdc512a462a31e7ab606da78b12cd5fb7c49b42",
+    info: "3a",
+    aad: "a4",
+    kem_output: "04d89f6eff0991d962f3a0e3bc20932be2313a21287ca46156b3a89a07aa8986fa0edac08283164248bd5d44553ae05ba216ee3bb285caf8806123c2e3da2005f3",
+    ciphertext: "1b04f610978c219614d9482d0f96a940af",
+    plaintext: "c9",
+};
+
+const P256_AES128_SIGNATURE: SignatureTest = SignatureTest {
+    public: "04cb6fdf8927a32aa47571e8fc652959ca9ed04794176bd3655d33ae2b67f786f983050b53de38a8a9da39629c12a43149dfd186f80f8699703272ab6bb7cc1c9a",
+    data: "d0",
+    signature: "30440220227bedf2cb06a250e46761c9fa590bbd4eabb3e3f87826acfa8cdc452ce02cad02201d332e68da133c6d23a46c517a67bd22d087bbf9675f0554cb0239a67ec362bc",
+};
+
+const CURVE25519_CHACHA_HPKE: HpkeTest = HpkeTest {
+    ikm: "d9b594758b882f454cf2133f41344b0d4a390875bdf016de01992347bc996505",
+    public: "4e6cfea03521dc2e7566614bdd5188db0cc1b67f821b9b1de4e6184024255c42",
+    info: "a8",
+    aad: "34",
+    kem_output: "f50b95fd5bd89d9aeb175eadd238f554b6b8873f718cee5f203b0ea783fcce3c",
+    ciphertext: "9c43380100386be61a4fc234d0e8df2549",
+    plaintext: "69",
+};
+
+const CURVE25519_CHACHA_SIGNATURE: SignatureTest = SignatureTest {
+    public: "06010fd855f061fc88934eb1b50fed30cb2146c9207074cb7557746e50e05ca7",
+    data: "a2",
+    signature: "67da8da67d91dbef547fb4ae0e3b8b309b500d4d632f10bfed548292f5897bd44ea76bcaf8da7fac41bbd13987da15fe46c047c24ab1b1440d9554fbc5a21602",
+};
+
+const CURVE448_AES256_HPKE: HpkeTest = HpkeTest {
+    ikm: "40234e8bc4e8e393f04c4ef1c6de036c1fafddaf413c41a202baf9c927b877ecfce2b4f7f6e02534050272bb84131811924bb27d6a6ffb933cec2c4a778366bb",
+    public: "48ae4c3724257c546f7d0143323d2e13157648ec5b8d2496be09ced983fa114f2c5441736610c4681e5c83de74a8a60acfab174dc7c87eb2",
+    info: "d2",
+    aad: "19",
+    kem_output: "de3a2e829da0867cb81241ec69a85d33a690555127c76a786b14bf24de61c22b7516a481343d639a6051a5794ba46b631f4198bf70b548e9",
+    ciphertext: "c5a5d5813f5155cf097f084b7e15611d74",
+    plaintext: "61",
+};
+
+const CURVE448_AES256_SIGNATURE: SignatureTest = SignatureTest {
+    public: "573a67329ae89ed54a971feb5b1d22ae405b1be39593eac51a99f423af64fad0fa063378d4f84cd6428c4c5eaf1951511c6b02acbdc6f4f000",
+    data: "21",
+    signature: "bafc9dc95483c06393b875074f12b9876a0ee44198f19188ea7ae1681f6b3f400e135a30420d4ac74e497bc2288fe4eae5598ed34928969200ce9212f28ce5952e95ed08c9499c6723759940fdd0f16841d8805ab868387e87f556b50d6db92dd9028d5fc95d55e5fecf1413d153b5e31500",
+};
+
+const P521_AES256_HPKE: HpkeTest = HpkeTest {
+    ikm: "52c42df844fe136c0cb8446275a0b399a1bb7f2d1f52fb44a075a36ac2a4cbcb2a3c78001d510ddb7e24e12fb78e76a677d55c4cb432948ad3543e4b2a1f7c3e",
+    public: "040093675ba7f9249e30c61c8c65bf2dc21d350bf904e3369f84aedf9aecb8192b374728d571045864aeb58d5f314e63825a55186f9f6feb4dea7bfada127ff36d65c2008766b88fc158d3d8261e24db599048299c39679f0c266f82386b627346e67330b12eb8d65c72f1cb33bb1c50593ea0c36d6ddae6c831a86724ad33f2e65295bac1",
+    info: "b6",
+    aad: "67",
+    kem_output: "0401ea81278283a468f1ff6ab2cc28a937aff29fa0b26aa1716573efea16ee3b45abf5c109fe660fa09b84620d1c9578c2960686162a90d504a3fec2522f2f717d309a00ac7890d726f22ddade6a67ca789e1e1e18dd26af934766301e9cc5195c6d98b57ea7bbc287b2ef2334603bbd67bf43689d94e7c5006ac592b5065ee688688888d6",
+    ciphertext: "b1606cce2d6f42590199fa25b20fdd45f3",
+    plaintext: "31",
+};
+
+const P521_AES256_SIGNATURE: SignatureTest = SignatureTest {
+    public: "0400bbe5827e01c76c8b0547136b192eadcf4a34d686b29373975def9fb7dac5c4468c546e81c342072c94f26a6ac9a352afae6331c3fdadee3ef5459cb01cebb37d8a00fdb9889ddf8251a923adc199613403854ee373fff32025b9100eda9b46fbe9c1e99e92f01bcd6a025839f663044118dcde40e0266b1eba82d1a996ae43780741c2",
+    data: "fa",
+    signature: "30818702420116a53f2e68767c71e818567837b2533cffee3860c1663fb7e69eeaf1a5150a69ed25b82e0ce1ecce881b1d646662cf53bf21c922dec2373c5f85259c2150aacd2f02414c6ac3a9b8f3a0eaea28420a999b0c2b8924fd30185b998d20a0408b1d2cc763981ac9e27a3400f1180e4284525648b76622e0abc76a3a7e75849eefb5bb18f805",
+};
+
+const CURVE448_CHACHA_HPKE: HpkeTest = HpkeTest {
+    ikm: "0128d0ea0f31b7d50fa3e1c706633301436ebd36681d31921281acbfe6555cdaa5d80c370f7f4837f6e7ba1d3cd8ca8071b429179c7ab8f01360e693f4a8c019",
+    public: "03c60d48049a92968d09b87795cda2874a52a5f0fa625938f3a21686226c4ea22b8e1efd0dc0f7ab4ad7947d1066d0088e83e1cb5dad4f20",
+    info: "4c",
+    aad: "c5",
+    kem_output: "885a8e7408d5d811476f5d53afb155a1c4241c9c4fb946b9448da186e1464b218a4f9c0ea9b8b83038c7c75738257ee9c9d18f6b22a87c47",
+    ciphertext: "b98b5ca30a10111ea209a8da26424c0dc2",
+    plaintext: "92",
+};
+
+const CURVE448_CHACHA_SIGNATURE: SignatureTest = SignatureTest {
+    public: "2d38283bfa502af8376e7223e14253449317ee926d61ba6a59e2c0c11b49553ad7a958d20f75d298326056558ac116d76ca9f12df32dd6fe80",
+    data: "bd",
+    signature: "4400d63bf9ba1f36c3a2f8f9d91345e2b26c4005135a0eb0cfe9966e378343890257f1ceae8b3ddd3106cc9670f6014704b4700f8ca184020019bd3901962160463328a6db06737bc24254d068eb37e06c55f78ce7e5894943efd7662f8f2ac73866ef087b4991063f03cdad6f7d35121f00",
+};
+
+const P384_AES256_HPKE: HpkeTest = HpkeTest {
+    ikm: "826fcad2bb71f907992d551e769f49768c2f0515b81753a11d19bde0c778b9596677bc978783d16cc05cb96aa5e564a5",
+    public: "04fae9245e7f525e50fd35af501cdfa7d47c01658edb3a49321e5ca7b7db0b24a67d30cb87e0a32bdccaed207ba4d83831f7620993bcf110e235ffffcfa8c3a451d976e8997b49e663b671cba4754697dfaea2a44b93dd5c995382b5cb8e8cbc25",
+    info: "97",
+    aad: "0f",
+    kem_output: "042901747fbba5956b3dffaca650253441d3acdf403fce3a79b41d4515af67e63ed4640928284058f3cf9f8b0b81c4c67f348964f8b677a035af3d5c92045b966af2b71ec393709426e2246fabad8ff6e44940f4b4fdeb328602082d85a84c4a03",
+    ciphertext: "56648c3e6c59f0de5b1398ee33dcdd1635",
+    plaintext: "1b",
+};
+
+const P384_AES256_SIGNATURE: SignatureTest = SignatureTest {
+    public: "047175d3aec10bea0bd105fd0337440c3c95ad380bbc8d6cc01c4a7413babf3ccaab56ed34ec87f9bf2cbe449a37cce4ec1abfadab469a92a089fcb503f1054cc58d42ccc999c5cf37e6f3a3ab84503d5251cb68864f6dec92c946f5c78e7c38df",
+    data: "b5",
+    signature: "30660231009f35d19417382d6f55db2eea9d266b2213a62cffcb2737e524d6cc9508bb6dea16b7d45a023a469fc6a75976adb34612023100c470e260b4ccb28dc0761e19128a754a005a2dc3d1f1cf864f307048bde218c3eec41eccfda2aa3875d698b38a6219c0",
+};
@@ -0,0 +1,346 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::error::IntoAnyError;
+
+use super::{
+    CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey, HpkeSecretKey,
+    SignaturePublicKey, SignatureSecretKey,
+};
+
+/// Produces signatures with keys that may be held outside of the process,
+/// for instance by a cloud KMS or a corporate signing service.
+///
+/// A signer is plugged into a crypto provider with [`SignerCryptoProvider`].
+/// The [`SignatureSecretKey`] given to MLS is then only a handle, such as a
+/// key identifier, which is passed back to the signer.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+pub trait Signer: Send + Sync {
+    type Error: IntoAnyError;
+
+    /// Sign `data` for `cipher_suite` with the key identified by `key`.
+    ///
+    /// `data` is the complete input of the signature algorithm of the cipher
+    /// suite, which must be applied as is, without prior hashing by the
+    /// caller.
+    async fn sign(
+        &self,
+        cipher_suite: CipherSuite,
+        key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Error returned by the cipher suite providers of a [`SignerCryptoProvider`].
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum SignerProviderError<C, S> {
+    /// The call failed in the wrapped cipher suite provider.
+    #[cfg_attr(feature = "std", error("crypto provider error: {0:?}"))]
+    Crypto(C),
+    /// The signature failed in the signer.
+    #[cfg_attr(feature = "std", error("signer error: {0:?}"))]
+    Signer(S),
+}
+
+impl<C, S> IntoAnyError for SignerProviderError<C, S>
+where
+    C: IntoAnyError + Send + Sync + 'static,
+    S: IntoAnyError + Send + Sync + 'static,
+{
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Crypto provider signing with a [`Signer`] and delegating all other
+/// operations to a wrapped crypto provider.
+///
+/// Signature keys generated by the provider, with
+/// [`CipherSuiteProvider::signature_key_generate`], are generated by the
+/// wrapped provider and can't be used with the signer. Keys held by the
+/// signer are created with the signer's own service.
+#[derive(Clone, Debug)]
+pub struct SignerCryptoProvider<C, S> {
+    inner: C,
+    signer: S,
+}
+
+impl<C, S> SignerCryptoProvider<C, S> {
+    /// Sign with `signer` and perform other operations with `inner`.
+    pub fn new(inner: C, signer: S) -> Self {
+        Self { inner, signer }
+    }
+
+    /// Wrapped crypto provider.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Signer used by the provider.
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+}
+
+impl<C, S> CryptoProvider for SignerCryptoProvider<C, S>
+where
+    C: CryptoProvider,
+    <C::CipherSuiteProvider as CipherSuiteProvider>::Error: Send + Sync + 'static,
+    S: Signer + Clone,
+    S::Error: Send + Sync + 'static,
+{
+    type CipherSuiteProvider = SignerCipherSuiteProvider<C::CipherSuiteProvider, S>;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.inner.supported_cipher_suites()
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        self.inner
+            .cipher_suite_provider(cipher_suite)
+            .map(|inner| SignerCipherSuiteProvider {
+                inner,
+                signer: self.signer.clone(),
+            })
+    }
+}
+
+/// Cipher suite provider of a [`SignerCryptoProvider`].
+#[derive(Clone, Debug)]
+pub struct SignerCipherSuiteProvider<P, S> {
+    inner: P,
+    signer: S,
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<P, S> CipherSuiteProvider for SignerCipherSuiteProvider<P, S>
+where
+    P: CipherSuiteProvider,
+    P::Error: Send + Sync + 'static,
+    S: Signer,
+    S::Error: Send + Sync + 'static,
+{
+    type Error = SignerProviderError<P::Error, S::Error>;
+
+    type HpkeContextS = P::HpkeContextS;
+    type HpkeContextR = P::HpkeContextR;
+
+    fn cipher_suite(&self) -> CipherSuite {
+        self.inner.cipher_suite()
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hash(data)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .mac(key, data)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn aead_seal(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .aead_seal(key, data, aad, nonce)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn aead_open(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .aead_open(key, ciphertext, aad, nonce)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    fn aead_key_size(&self) -> usize {
+        self.inner.aead_key_size()
+    }
+
+    fn aead_nonce_size(&self) -> usize {
+        self.inner.aead_nonce_size()
+    }
+
+    async fn kdf_extract(
+        &self,
+        salt: &[u8],
+        ikm: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .kdf_extract(salt, ikm)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn kdf_expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .kdf_expand(prk, info, len)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    fn kdf_extract_size(&self) -> usize {
+        self.inner.kdf_extract_size()
+    }
+
+    async fn hpke_seal(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+    ) -> Result<HpkeCiphertext, Self::Error> {
+        self.inner
+            .hpke_seal(remote_key, info, aad, pt)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn hpke_open(
+        &self,
+        ciphertext: &HpkeCiphertext,
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hpke_open(ciphertext, local_secret, local_public, info, aad)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn hpke_setup_s(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self::HpkeContextS), Self::Error> {
+        self.inner
+            .hpke_setup_s(remote_key, info)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn hpke_setup_r(
+        &self,
+        kem_output: &[u8],
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<Self::HpkeContextR, Self::Error> {
+        self.inner
+            .hpke_setup_r(kem_output, local_secret, local_public, info)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn kem_derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.inner
+            .kem_derive(ikm)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn kem_generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.inner
+            .kem_generate()
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    fn kem_public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        self.inner
+            .kem_public_key_validate(key)
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner
+            .random_bytes(out)
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        self.inner
+            .signature_key_generate()
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.inner
+            .signature_key_derive_public(secret_key)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.signer
+            .sign(self.inner.cipher_suite(), secret_key, data)
+            .await
+            .map_err(SignerProviderError::Signer)
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .verify(public_key, signature, data)
+            .await
+            .map_err(SignerProviderError::Crypto)
+    }
+}
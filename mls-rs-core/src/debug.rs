@@ -106,9 +106,52 @@ pub fn pretty_group_id(id: &[u8]) -> impl Debug + '_ {
     pretty_bytes(id).show_len(false).show_raw(true)
 }
 
+/// Debug representation of secret bytes, such as keys, pre-shared keys and
+/// secrets of the key schedule.
+///
+/// Secrets are shown as `[REDACTED; len]`, even with the alternate format,
+/// so that logs of applications can't leak them. With the `debug_secrets`
+/// feature, which can only be enabled in builds with debug assertions, they
+/// are shown like [`pretty_bytes`].
+pub fn pretty_secret(bytes: &[u8]) -> PrettySecret<'_> {
+    PrettySecret { ty: None, bytes }
+}
+
+pub struct PrettySecret<'a> {
+    ty: Option<&'a str>,
+    bytes: &'a [u8],
+}
+
+impl<'a> PrettySecret<'a> {
+    pub fn named(self, ty: &'a str) -> Self {
+        Self {
+            ty: Some(ty),
+            ..self
+        }
+    }
+}
+
+impl Debug for PrettySecret<'_> {
+    #[cfg(feature = "debug_secrets")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Some(ty) => pretty_bytes(self.bytes).named(ty).fmt(f),
+            None => pretty_bytes(self.bytes).fmt(f),
+        }
+    }
+
+    #[cfg(not(feature = "debug_secrets"))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Some(ty) => write!(f, "{ty}([REDACTED; {}])", self.bytes.len()),
+            None => write!(f, "[REDACTED; {}]", self.bytes.len()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::debug::pretty_bytes;
+    use crate::debug::{pretty_bytes, pretty_secret};
 
     #[test]
     fn default_format_contains_only_length() {
@@ -127,4 +170,13 @@ mod tests {
         assert!(output.contains("raw"));
         assert!(output.contains(&hex::encode(b"foobar")));
     }
+
+    #[cfg(not(feature = "debug_secrets"))]
+    #[test]
+    fn secrets_are_redacted_in_all_formats() {
+        let secret = pretty_secret(b"foobar").named("Secret");
+        assert_eq!(format!("{secret:?}"), "Secret([REDACTED; 6])");
+        assert_eq!(format!("{secret:#?}"), "Secret([REDACTED; 6])");
+        assert_eq!(format!("{:?}", pretty_secret(b"foo")), "[REDACTED; 3]");
+    }
 }
@@ -3,9 +3,16 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 mod group_state;
+mod master_key;
 mod proposal_type;
+mod replay;
 mod roster;
 
+#[cfg(feature = "test_suite")]
+pub mod test_suite;
+
 pub use group_state::*;
+pub use master_key::*;
 pub use proposal_type::*;
+pub use replay::*;
 pub use roster::*;
@@ -63,6 +63,20 @@ impl EpochRecord {
 /// group. It is up to the implementer of this trait to provide a mechanism
 /// to delete records that can be used by an application.
 ///
+/// # Transactions
+///
+/// When the state of a group is saved, its writes are made between calls to
+/// [`begin_transaction`](GroupStateStorage::begin_transaction) and
+/// [`commit_transaction`](GroupStateStorage::commit_transaction). This
+/// includes the tree records and the state and epochs written by
+/// [`write`](GroupStateStorage::write).
+///
+/// The key package used to join the group is deleted from the
+/// [`KeyPackageStorage`](crate::key_package::KeyPackageStorage) after these
+/// writes, before the transaction is committed. The deletion is only part of
+/// the transaction if both storages share it, e.g. because they use the same
+/// database connection. Otherwise, the key package is deleted even if
+/// committing the transaction later fails.
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
@@ -93,7 +107,8 @@ pub trait GroupStateStorage: Send + Sync {
     /// It is important to consider error recovery when creating an implementation
     /// of this trait. Calls to [`write`](GroupStateStorage::write) should
     /// optimally be a single atomic transaction in order to avoid partial writes
-    /// that may corrupt the group state.
+    /// that may corrupt the group state, unless they are made atomic by
+    /// [`begin_transaction`](GroupStateStorage::begin_transaction).
     async fn write(
         &mut self,
         state: GroupState,
@@ -104,4 +119,108 @@ pub trait GroupStateStorage: Send + Sync {
     /// The [`EpochRecord::id`] value that is associated with a stored
     /// prior epoch for a particular group.
     async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error>;
+
+    /// Delete the prior epochs of the group `group_id` with an id lower than
+    /// `epoch_id`, returning the ids of the deleted epochs, or `None` if
+    /// deleting epochs is not supported.
+    ///
+    /// Deleted records should be overwritten, or otherwise made
+    /// unrecoverable, as far as the underlying storage allows it. The
+    /// default implementation doesn't support deleting epochs.
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        let _ = (group_id, epoch_id);
+        Ok(None)
+    }
+
+    /// Ids of all the groups that have a state stored.
+    ///
+    /// The default implementation returns no group, in which case stored
+    /// groups can't be enumerated, for instance to garbage collect them.
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Delete the state and all prior epochs of the group `group_id`,
+    /// returning whether the group was deleted.
+    ///
+    /// Deleted records should be made unrecoverable like the ones deleted by
+    /// [`delete_epochs_before`](GroupStateStorage::delete_epochs_before).
+    /// The default implementation doesn't delete anything.
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        let _ = group_id;
+        Ok(false)
+    }
+
+    /// Fetch the ratchet tree record of the group `group_id` written for
+    /// the epoch `epoch_id` by
+    /// [`write_tree_records`](GroupStateStorage::write_tree_records).
+    async fn tree_record(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        let _ = (group_id, epoch_id);
+        Ok(None)
+    }
+
+    /// Write ratchet tree records of the group `group_id`, replacing the
+    /// records of the same epochs, and return whether tree records are
+    /// supported.
+    ///
+    /// Tree records are full trees or changes from the tree of a previous
+    /// epoch, written when tree checkpointing is enabled so that the state
+    /// written by [`write`](GroupStateStorage::write) doesn't hold the full
+    /// tree. They are written before that state, and must be deleted with
+    /// the group by [`delete_group`](GroupStateStorage::delete_group). The
+    /// default implementation doesn't support tree records, in which case
+    /// the state holds the full tree.
+    async fn write_tree_records(
+        &mut self,
+        group_id: &[u8],
+        records: Vec<EpochRecord>,
+    ) -> Result<bool, Self::Error> {
+        let _ = (group_id, records);
+        Ok(false)
+    }
+
+    /// Delete the ratchet tree records of the group `group_id` with an id
+    /// lower than `epoch_id`.
+    async fn delete_tree_records_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<(), Self::Error> {
+        let _ = (group_id, epoch_id);
+        Ok(())
+    }
+
+    /// Start a transaction, until the next call to
+    /// [`commit_transaction`](GroupStateStorage::commit_transaction) or
+    /// [`rollback_transaction`](GroupStateStorage::rollback_transaction).
+    ///
+    /// The writes made during a transaction must be applied atomically when
+    /// it is committed, and discarded when it is rolled back. Reads made
+    /// during a transaction must return the data written by it. Transactions
+    /// are not nested. The default implementation does nothing, in which case
+    /// each write is applied immediately.
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Apply the writes made since
+    /// [`begin_transaction`](GroupStateStorage::begin_transaction).
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Discard the writes made since
+    /// [`begin_transaction`](GroupStateStorage::begin_transaction). This is
+    /// called when one of them failed.
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
@@ -0,0 +1,33 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::error::IntoAnyError;
+
+/// Master keys wrapping the data keys that encrypt stored group states,
+/// following the envelope encryption scheme.
+///
+/// Master keys are identified by an id stored next to each wrapped data
+/// key, so that data keys wrapped by a previous master key can still be
+/// unwrapped after the master key is rotated.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait MasterKeyProvider: Send + Sync {
+    type Error: IntoAnyError;
+
+    /// Id of the master key new data keys are wrapped with.
+    fn current_key_id(&self) -> Vec<u8>;
+
+    /// Wrap `data_key` with the master key `key_id`.
+    async fn wrap(&self, key_id: &[u8], data_key: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+    /// Unwrap a data key wrapped with the master key `key_id`.
+    async fn unwrap(
+        &self,
+        key_id: &[u8],
+        wrapped_key: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error>;
+}
@@ -0,0 +1,39 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use crate::error::IntoAnyError;
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+/// Storage of the messages already processed by a group, used to detect
+/// replayed messages.
+///
+/// Messages are identified by a hash of their wire encoding. Implementations
+/// may forget entries, such as those of old epochs, in which case replays of
+/// the corresponding messages are no longer detected.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait ReplayStore: Send + Sync {
+    /// Error type that the underlying storage mechanism returns on internal
+    /// failure.
+    type Error: IntoAnyError;
+
+    /// Determines if `message_id`, sent in `epoch` of the group `group_id`,
+    /// was already processed.
+    async fn contains(
+        &self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<bool, Self::Error>;
+
+    /// Record that `message_id`, sent in `epoch` of the group `group_id`, was
+    /// processed.
+    async fn insert(
+        &mut self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<(), Self::Error>;
+}
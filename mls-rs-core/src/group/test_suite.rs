@@ -0,0 +1,132 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Conformance tests for implementations of [`GroupStateStorage`].
+//!
+//! [`verify_group_state_storage`] panics on the first deviation from the
+//! expected behavior, and is meant to be called from the tests of a storage
+//! provider with an empty storage. Implementations may delete prior epochs
+//! according to their retention policy, as long as the latest one is kept.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{EpochRecord, GroupState, GroupStateStorage};
+
+/// Size of the large records written to the storage.
+pub const LARGE_RECORD_SIZE: usize = 1 << 20;
+
+/// Check an empty [`GroupStateStorage`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_group_state_storage<S: GroupStateStorage>(storage: &mut S) {
+    let group_id = b"group".to_vec();
+    let other_group_id = b"other group".to_vec();
+
+    // Macros aren't rewritten by `maybe_async`, so results are read before
+    // being checked.
+
+    // Nothing is stored for unknown groups.
+    let state = storage.state(&group_id).await.unwrap();
+    let epoch = storage.epoch(&group_id, 0).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&group_id).await.unwrap();
+
+    assert_eq!(state, None);
+    assert_eq!(epoch, None);
+    assert_eq!(max_epoch_id, None);
+
+    // Zero-length records are stored as such.
+    let state = GroupState {
+        id: group_id.clone(),
+        data: Vec::new(),
+    };
+
+    let epoch_0 = EpochRecord::new(0, Vec::new());
+
+    storage.write(state, vec![epoch_0], vec![]).await.unwrap();
+
+    let state = storage.state(&group_id).await.unwrap();
+    let epoch_0 = storage.epoch(&group_id, 0).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&group_id).await.unwrap();
+    let epoch_1 = storage.epoch(&group_id, 1).await.unwrap();
+
+    assert_eq!(state, Some(Vec::new()));
+    assert_eq!(epoch_0, Some(Vec::new()));
+    assert_eq!(max_epoch_id, Some(0));
+    assert_eq!(epoch_1, None);
+
+    // Large records replace the current state and update prior epochs.
+    let large_state = vec![1u8; LARGE_RECORD_SIZE];
+    let large_epoch = vec![2u8; LARGE_RECORD_SIZE];
+
+    let state = GroupState {
+        id: group_id.clone(),
+        data: large_state.clone(),
+    };
+
+    let epoch_0 = EpochRecord::new(0, b"updated epoch 0".to_vec());
+    let epoch_1 = EpochRecord::new(1, large_epoch.clone());
+
+    storage
+        .write(state, vec![epoch_1], vec![epoch_0])
+        .await
+        .unwrap();
+
+    let state = storage.state(&group_id).await.unwrap();
+    let epoch_1 = storage.epoch(&group_id, 1).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&group_id).await.unwrap();
+    let epoch_0 = storage.epoch(&group_id, 0).await.unwrap();
+
+    assert_eq!(state, Some(large_state));
+    assert_eq!(epoch_1, Some(large_epoch));
+    assert_eq!(max_epoch_id, Some(1));
+    assert!(epoch_0.is_none() || epoch_0 == Some(b"updated epoch 0".to_vec()));
+
+    // Groups are stored independently.
+    let state = storage.state(&other_group_id).await.unwrap();
+    let epoch = storage.epoch(&other_group_id, 1).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&other_group_id).await.unwrap();
+
+    assert_eq!(state, None);
+    assert_eq!(epoch, None);
+    assert_eq!(max_epoch_id, None);
+
+    let state = GroupState {
+        id: other_group_id.clone(),
+        data: b"other state".to_vec(),
+    };
+
+    let epoch_5 = EpochRecord::new(5, b"other epoch".to_vec());
+
+    storage.write(state, vec![epoch_5], vec![]).await.unwrap();
+
+    let state = storage.state(&other_group_id).await.unwrap();
+    let epoch_5 = storage.epoch(&other_group_id, 5).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&other_group_id).await.unwrap();
+
+    assert_eq!(state, Some(b"other state".to_vec()));
+    assert_eq!(epoch_5, Some(b"other epoch".to_vec()));
+    assert_eq!(max_epoch_id, Some(5));
+
+    let max_epoch_id = storage.max_epoch_id(&group_id).await.unwrap();
+    let epoch_5 = storage.epoch(&group_id, 5).await.unwrap();
+
+    assert_eq!(max_epoch_id, Some(1));
+    assert_eq!(epoch_5, None);
+
+    // Writing a state without new epochs keeps the existing ones.
+    let state = GroupState {
+        id: group_id.clone(),
+        data: b"new state".to_vec(),
+    };
+
+    storage.write(state, vec![], vec![]).await.unwrap();
+
+    let state = storage.state(&group_id).await.unwrap();
+    let max_epoch_id = storage.max_epoch_id(&group_id).await.unwrap();
+    let epoch_1 = storage.epoch(&group_id, 1).await.unwrap();
+
+    assert_eq!(state, Some(b"new state".to_vec()));
+    assert_eq!(max_epoch_id, Some(1));
+    assert!(epoch_1.is_some());
+}
@@ -52,6 +52,36 @@ pub trait IdentityProvider: Send + Sync {
         extensions: &ExtensionList,
     ) -> Result<Vec<u8>, Self::Error>;
 
+    /// Identifier of the user holding `signing_identity`, shared by all the
+    /// devices of that user.
+    ///
+    /// Unlike [`identity`](Self::identity), this identifier doesn't need to
+    /// be unique among the members of a group: leaves with the same user
+    /// identity are devices of the same user. By default, it is the
+    /// [`identity`](Self::identity), making each leaf its own user.
+    async fn user_identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.identity(signing_identity, extensions).await
+    }
+
+    /// Determines if `new` can join a group where `existing` already uses
+    /// the same signature key.
+    ///
+    /// The MLS protocol requires signature keys to be unique among the
+    /// members of a group, as sharing a key lets each of the members sign
+    /// messages on behalf of the other. By default, sharing is rejected.
+    async fn allow_shared_signature_key(
+        &self,
+        _existing: &SigningIdentity,
+        _new: &SigningIdentity,
+        _extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
     /// Determines if `successor` can remove `predecessor` as part of an external commit.
     ///
     /// The MLS protocol allows for removal of an existing member when adding a
@@ -81,4 +81,13 @@ pub trait KeyPackageStorage: Send + Sync {
     /// `None` should be returned in the event that no key packages are found
     /// that match `id`.
     async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error>;
+
+    /// Ids of all the stored key packages.
+    ///
+    /// The default implementation returns no key package, in which case
+    /// stored key packages can't be enumerated, for instance to garbage
+    /// collect expired ones.
+    async fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(Vec::new())
+    }
 }
@@ -9,6 +9,9 @@ extern crate alloc;
 #[cfg(all(test, target_arch = "wasm32"))]
 wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 
+#[cfg(all(feature = "debug_secrets", not(debug_assertions)))]
+compile_error!("the debug_secrets feature is only allowed in builds with debug assertions");
+
 pub mod crypto;
 pub mod debug;
 pub mod error;
@@ -2,13 +2,15 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use crate::error::IntoAnyError;
+use crate::{crypto::CipherSuiteProvider, error::IntoAnyError};
 #[cfg(mls_build_async)]
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::{
+    convert::Infallible,
     fmt::{self, Debug},
     ops::Deref,
+    str::FromStr,
 };
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use zeroize::Zeroizing;
@@ -24,7 +26,7 @@ pub struct PreSharedKey(
 
 impl Debug for PreSharedKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        crate::debug::pretty_bytes(&self.0)
+        crate::debug::pretty_secret(&self.0)
             .named("PreSharedKey")
             .fmt(f)
     }
@@ -83,10 +85,11 @@ pub struct ExternalPskId(
 );
 
 impl Debug for ExternalPskId {
+    // Identifiers may be derived from application data, so only their length is shown.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        crate::debug::pretty_bytes(&self.0)
-            .named("ExternalPskId")
-            .fmt(f)
+        f.debug_struct("ExternalPskId")
+            .field("len", &self.0.len())
+            .finish_non_exhaustive()
     }
 }
 
@@ -95,6 +98,43 @@ impl ExternalPskId {
     pub fn new(id_data: Vec<u8>) -> Self {
         Self(id_data)
     }
+
+    /// Generate a random identifier of `kdf_extract_size` bytes for the
+    /// cipher suite of `cipher_suite_provider`.
+    pub fn random<P: CipherSuiteProvider>(cipher_suite_provider: &P) -> Result<Self, P::Error> {
+        cipher_suite_provider
+            .random_bytes_vec(cipher_suite_provider.kdf_extract_size())
+            .map(Self)
+    }
+
+    /// Deterministically derive an identifier from `label` and `context`
+    /// using the hash function of the cipher suite of `cipher_suite_provider`.
+    ///
+    /// Distinct `(label, context)` pairs always produce distinct inputs to the
+    /// hash function.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn derive_from<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        label: &[u8],
+        context: &[u8],
+    ) -> Result<Self, P::Error> {
+        let mut input = Vec::with_capacity(8 + label.len() + context.len());
+        input.extend_from_slice(&(label.len() as u64).to_be_bytes());
+        input.extend_from_slice(label);
+        input.extend_from_slice(context);
+
+        cipher_suite_provider.hash(&input).await.map(Self)
+    }
+
+    /// Raw bytes of this identifier.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Convert this identifier into its raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
 }
 
 impl AsRef<[u8]> for ExternalPskId {
@@ -117,6 +157,32 @@ impl From<Vec<u8>> for ExternalPskId {
     }
 }
 
+impl From<&[u8]> for ExternalPskId {
+    fn from(value: &[u8]) -> Self {
+        ExternalPskId(value.to_vec())
+    }
+}
+
+impl From<&str> for ExternalPskId {
+    fn from(value: &str) -> Self {
+        value.as_bytes().into()
+    }
+}
+
+impl From<ExternalPskId> for Vec<u8> {
+    fn from(value: ExternalPskId) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for ExternalPskId {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
 /// Storage trait to maintain a set of pre-shared key values.
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
@@ -0,0 +1,263 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::{ffi::c_void, fmt::Debug, mem::size_of, ptr};
+
+use mls_rs_core::{crypto::CipherSuite, error::IntoAnyError};
+use mls_rs_crypto_traits::{AeadId, AeadType, AES_TAG_LEN};
+use windows_sys::Win32::{
+    Foundation::STATUS_AUTH_TAG_MISMATCH,
+    Security::Cryptography::{
+        BCryptDecrypt, BCryptEncrypt, BCRYPT_AES_GCM_ALG_HANDLE,
+        BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO, BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO_VERSION,
+    },
+};
+
+use alloc::{vec, vec::Vec};
+
+use crate::bcrypt::{check, len32, Key, Status};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum AeadError {
+    #[cfg_attr(feature = "std", error("CNG error {0:#010x}"))]
+    CngError(i32),
+    #[cfg_attr(
+        feature = "std",
+        error("AEAD ciphertext of length {0} is too short to fit the tag")
+    )]
+    InvalidCipherLen(usize),
+    #[cfg_attr(
+        feature = "std",
+        error("AEAD key of invalid length {0}. Expected length {1}")
+    )]
+    InvalidKeyLen(usize, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("AEAD nonce of invalid length {0}. Expected length {1}")
+    )]
+    InvalidNonceLen(usize, usize),
+    #[cfg_attr(feature = "std", error("AEAD tag verification failed"))]
+    AuthenticationFailed,
+    #[cfg_attr(feature = "std", error("unsupported cipher suite"))]
+    UnsupportedCipherSuite,
+}
+
+impl From<Status> for AeadError {
+    fn from(status: Status) -> Self {
+        match status.0 {
+            STATUS_AUTH_TAG_MISMATCH => AeadError::AuthenticationFailed,
+            status => AeadError::CngError(status),
+        }
+    }
+}
+
+impl IntoAnyError for AeadError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// AES-GCM. CNG doesn't implement ChaCha20-Poly1305.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Aead(AeadId);
+
+impl Aead {
+    pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        AeadId::new(cipher_suite)
+            .filter(|id| matches!(id, AeadId::Aes128Gcm | AeadId::Aes256Gcm))
+            .map(Self)
+    }
+
+    fn import_key(&self, key: &[u8], nonce: &[u8]) -> Result<Key, AeadError> {
+        (key.len() == self.key_size())
+            .then_some(())
+            .ok_or_else(|| AeadError::InvalidKeyLen(key.len(), self.key_size()))?;
+
+        (nonce.len() == self.nonce_size())
+            .then_some(())
+            .ok_or_else(|| AeadError::InvalidNonceLen(nonce.len(), self.nonce_size()))?;
+
+        Ok(Key::symmetric(BCRYPT_AES_GCM_ALG_HANDLE, key)?)
+    }
+}
+
+fn auth_info(
+    nonce: &[u8],
+    aad: &[u8],
+    tag: &mut [u8],
+) -> Result<BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO, Status> {
+    Ok(BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO {
+        cbSize: size_of::<BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO>() as u32,
+        dwInfoVersion: BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO_VERSION,
+        pbNonce: nonce.as_ptr() as *mut u8,
+        cbNonce: len32(nonce)?,
+        pbAuthData: if aad.is_empty() {
+            ptr::null_mut()
+        } else {
+            aad.as_ptr() as *mut u8
+        },
+        cbAuthData: len32(aad)?,
+        pbTag: tag.as_mut_ptr(),
+        cbTag: len32(tag)?,
+        pbMacContext: ptr::null_mut(),
+        cbMacContext: 0,
+        cbAAD: 0,
+        cbData: 0,
+        dwFlags: 0,
+    })
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl AeadType for Aead {
+    type Error = AeadError;
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn seal<'a>(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&'a [u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let key = self.import_key(key, nonce)?;
+
+        let mut out = vec![0u8; data.len() + AES_TAG_LEN];
+        let (ciphertext, tag) = out.split_at_mut(data.len());
+        let info = auth_info(nonce, aad.unwrap_or_default(), tag)?;
+        let mut written = 0;
+
+        // SAFETY: `info` points to `nonce`, `aad` and `tag`, which outlive the
+        // call, `data` is valid for reads of its length and `ciphertext` for
+        // writes of the same length.
+        check(unsafe {
+            BCryptEncrypt(
+                key.handle(),
+                data.as_ptr(),
+                len32(data)?,
+                &info as *const _ as *const c_void,
+                ptr::null_mut(),
+                0,
+                ciphertext.as_mut_ptr(),
+                len32(ciphertext)?,
+                &mut written,
+                0,
+            )
+        })?;
+
+        Ok(out)
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn open<'a>(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&'a [u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let key = self.import_key(key, nonce)?;
+
+        let plaintext_len = ciphertext
+            .len()
+            .checked_sub(AES_TAG_LEN)
+            .ok_or(AeadError::InvalidCipherLen(ciphertext.len()))?;
+
+        let (ciphertext, tag) = ciphertext.split_at(plaintext_len);
+
+        // CNG only reads the tag when decrypting.
+        let mut tag = tag.to_vec();
+        let info = auth_info(nonce, aad.unwrap_or_default(), &mut tag)?;
+
+        let mut out = vec![0u8; plaintext_len];
+        let mut written = 0;
+
+        // SAFETY: `info` points to `nonce`, `aad` and `tag`, which outlive the
+        // call, `ciphertext` is valid for reads of its length and `out` for
+        // writes of the same length.
+        check(unsafe {
+            BCryptDecrypt(
+                key.handle(),
+                ciphertext.as_ptr(),
+                len32(ciphertext)?,
+                &info as *const _ as *const c_void,
+                ptr::null_mut(),
+                0,
+                out.as_mut_ptr(),
+                len32(&out)?,
+                &mut written,
+                0,
+            )
+        })?;
+
+        Ok(out)
+    }
+
+    #[inline(always)]
+    fn key_size(&self) -> usize {
+        self.0.key_size()
+    }
+
+    fn nonce_size(&self) -> usize {
+        self.0.nonce_size()
+    }
+
+    fn aead_id(&self) -> u16 {
+        self.0 as u16
+    }
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+mod test {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::CipherSuite;
+    use mls_rs_crypto_traits::{AeadType, AES_TAG_LEN};
+
+    use super::{Aead, AeadError};
+
+    fn get_aeads() -> impl Iterator<Item = Aead> {
+        [CipherSuite::P256_AES128, CipherSuite::P384_AES256]
+            .into_iter()
+            .map(|cs| Aead::new(cs).unwrap())
+    }
+
+    #[test]
+    fn chacha_is_not_supported() {
+        assert!(Aead::new(CipherSuite::CURVE25519_CHACHA).is_none());
+    }
+
+    #[test]
+    fn invalid_key() {
+        for aead in get_aeads() {
+            let res = aead.seal(&[0u8; 15], b"message", None, &[0u8; 12]);
+            assert_matches!(res, Err(AeadError::InvalidKeyLen(15, _)));
+        }
+    }
+
+    #[test]
+    fn invalid_ciphertext() {
+        for aead in get_aeads() {
+            let key = vec![0u8; aead.key_size()];
+            let nonce = [0u8; 12];
+
+            let res = aead.open(&key, &[0u8; AES_TAG_LEN - 1], None, &nonce);
+            assert_matches!(res, Err(AeadError::InvalidCipherLen(_)));
+
+            let mut ciphertext = aead
+                .seal(&key, b"message", Some(b"aad".as_slice()), &nonce)
+                .unwrap();
+            ciphertext[0] ^= 1;
+
+            let res = aead.open(&key, &ciphertext, Some(b"aad".as_slice()), &nonce);
+            assert_matches!(res, Err(AeadError::AuthenticationFailed));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Thin safe wrappers around the CNG primitives API (BCrypt).
+
+use core::{ffi::c_void, ptr};
+
+use alloc::{vec, vec::Vec};
+
+use windows_sys::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{NTSTATUS, STATUS_INVALID_PARAMETER},
+        Security::Cryptography::{
+            BCryptBuffer, BCryptBufferDesc, BCryptDeriveKey, BCryptDestroyKey, BCryptDestroySecret,
+            BCryptExportKey, BCryptFinalizeKeyPair, BCryptGenRandom, BCryptGenerateKeyPair,
+            BCryptGenerateSymmetricKey, BCryptHash, BCryptImportKeyPair, BCryptKeyDerivation,
+            BCryptSecretAgreement, BCryptSetProperty, BCryptSignHash, BCryptVerifySignature,
+            BCRYPTBUFFER_VERSION, BCRYPT_ALG_HANDLE, BCRYPT_KDF_RAW_SECRET, BCRYPT_KEY_HANDLE,
+            BCRYPT_SECRET_HANDLE, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+        },
+    },
+};
+use zeroize::Zeroizing;
+
+/// Status code of a failed CNG call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Status(pub(crate) NTSTATUS);
+
+pub(crate) fn check(status: NTSTATUS) -> Result<(), Status> {
+    (status >= 0).then_some(()).ok_or(Status(status))
+}
+
+pub(crate) fn len32(buf: &[u8]) -> Result<u32, Status> {
+    u32::try_from(buf.len()).map_err(|_| Status(STATUS_INVALID_PARAMETER))
+}
+
+/// Empty buffers are passed to CNG as null pointers.
+fn ptr_or_null(buf: &[u8]) -> *const u8 {
+    if buf.is_empty() {
+        ptr::null()
+    } else {
+        buf.as_ptr()
+    }
+}
+
+/// Size in bytes of the null terminated wide string `s`, including the
+/// terminator.
+fn wide_len(s: PCWSTR) -> usize {
+    let mut len = 0;
+
+    // SAFETY: `s` is one of the null terminated constants of `windows_sys`.
+    while unsafe { *s.add(len) } != 0 {
+        len += 1;
+    }
+
+    (len + 1) * 2
+}
+
+pub(crate) fn fill_random(out: &mut [u8]) -> Result<(), Status> {
+    // SAFETY: `out` is valid for writes of its length.
+    check(unsafe {
+        BCryptGenRandom(
+            0,
+            out.as_mut_ptr(),
+            len32(out)?,
+            BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+        )
+    })
+}
+
+/// Hash `data` with the hash or HMAC algorithm `alg`, keyed with `secret` for
+/// HMAC.
+pub(crate) fn hash(
+    alg: BCRYPT_ALG_HANDLE,
+    secret: &[u8],
+    data: &[u8],
+    size: usize,
+) -> Result<Vec<u8>, Status> {
+    let mut out = vec![0u8; size];
+
+    // SAFETY: the input buffers are valid for reads of their length, or null
+    // when empty, and `out` is valid for writes of its length.
+    check(unsafe {
+        BCryptHash(
+            alg,
+            ptr_or_null(secret),
+            len32(secret)?,
+            ptr_or_null(data),
+            len32(data)?,
+            out.as_mut_ptr(),
+            len32(&out)?,
+        )
+    })?;
+
+    Ok(out)
+}
+
+/// CNG key object, destroyed on drop.
+pub(crate) struct Key(BCRYPT_KEY_HANDLE);
+
+impl Key {
+    pub(crate) fn symmetric(alg: BCRYPT_ALG_HANDLE, secret: &[u8]) -> Result<Self, Status> {
+        let mut handle = 0;
+
+        // SAFETY: `secret` is valid for reads of its length and CNG allocates
+        // the key object itself when no buffer is given for it.
+        check(unsafe {
+            BCryptGenerateSymmetricKey(
+                alg,
+                &mut handle,
+                ptr::null_mut(),
+                0,
+                secret.as_ptr(),
+                len32(secret)?,
+                0,
+            )
+        })?;
+
+        Ok(Self(handle))
+    }
+
+    pub(crate) fn generate_pair(alg: BCRYPT_ALG_HANDLE, bits: u32) -> Result<Self, Status> {
+        let mut handle = 0;
+        // SAFETY: `handle` is valid for writes.
+        check(unsafe { BCryptGenerateKeyPair(alg, &mut handle, bits, 0) })?;
+
+        let key = Self(handle);
+        // SAFETY: `key` holds the handle of a key pair that isn't finalized
+        // yet.
+        check(unsafe { BCryptFinalizeKeyPair(key.0, 0) })?;
+
+        Ok(key)
+    }
+
+    pub(crate) fn import_pair(
+        alg: BCRYPT_ALG_HANDLE,
+        blob_type: PCWSTR,
+        blob: &[u8],
+    ) -> Result<Self, Status> {
+        let mut handle = 0;
+
+        // SAFETY: `blob_type` is a null terminated constant of `windows_sys`,
+        // `blob` is valid for reads of its length and `handle` for writes.
+        check(unsafe {
+            BCryptImportKeyPair(
+                alg,
+                0,
+                blob_type,
+                &mut handle,
+                blob.as_ptr(),
+                len32(blob)?,
+                0,
+            )
+        })?;
+
+        Ok(Self(handle))
+    }
+
+    pub(crate) fn export(&self, blob_type: PCWSTR) -> Result<Zeroizing<Vec<u8>>, Status> {
+        let mut size = 0;
+
+        // SAFETY: `self.0` is a valid key handle and a null output buffer only
+        // queries the size of the blob into `size`.
+        check(unsafe { BCryptExportKey(self.0, 0, blob_type, ptr::null_mut(), 0, &mut size, 0) })?;
+
+        let mut blob = Zeroizing::new(vec![0u8; size as usize]);
+
+        // SAFETY: `blob` is valid for writes of `size` bytes.
+        check(unsafe {
+            BCryptExportKey(self.0, 0, blob_type, blob.as_mut_ptr(), size, &mut size, 0)
+        })?;
+
+        blob.truncate(size as usize);
+
+        Ok(blob)
+    }
+
+    /// Set the property `name` to the string `value`.
+    pub(crate) fn set_str_property(&self, name: PCWSTR, value: PCWSTR) -> Result<(), Status> {
+        // SAFETY: `name` and `value` are null terminated constants of
+        // `windows_sys`, and `value` is read up to its terminator.
+        check(unsafe {
+            BCryptSetProperty(self.0, name, value as *const u8, wide_len(value) as u32, 0)
+        })
+    }
+
+    /// Set the property `name`, which takes no value.
+    pub(crate) fn set_empty_property(&self, name: PCWSTR) -> Result<(), Status> {
+        // SAFETY: `name` is a null terminated constant of `windows_sys` and the
+        // property takes no value.
+        check(unsafe { BCryptSetProperty(self.0, name, ptr::null(), 0, 0) })
+    }
+
+    /// Derive `len` bytes with the key derivation function of the key,
+    /// passing `info` as the parameter of type `info_type`.
+    pub(crate) fn derive(
+        &self,
+        info_type: u32,
+        info: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, Status> {
+        let mut buffer = BCryptBuffer {
+            cbBuffer: len32(info)?,
+            BufferType: info_type,
+            pvBuffer: ptr_or_null(info) as *mut c_void,
+        };
+
+        let params = BCryptBufferDesc {
+            ulVersion: BCRYPTBUFFER_VERSION,
+            cBuffers: 1,
+            pBuffers: &mut buffer,
+        };
+
+        let mut out = vec![0u8; len];
+        let mut written = 0;
+
+        // SAFETY: `params` points to `buffer`, which points to `info`, all of
+        // which outlive the call, and `out` is valid for writes of its length.
+        check(unsafe {
+            BCryptKeyDerivation(
+                self.0,
+                &params,
+                out.as_mut_ptr(),
+                len32(&out)?,
+                &mut written,
+                0,
+            )
+        })?;
+
+        out.truncate(written as usize);
+
+        Ok(out)
+    }
+
+    /// Raw ECDH shared secret between this private key and `public_key`, in
+    /// big endian.
+    pub(crate) fn agree(&self, public_key: &Key) -> Result<Zeroizing<Vec<u8>>, Status> {
+        let mut handle = 0;
+        // SAFETY: both handles are valid key handles and `handle` is valid for
+        // writes.
+        check(unsafe { BCryptSecretAgreement(self.0, public_key.0, &mut handle, 0) })?;
+
+        let secret = Secret(handle);
+        let mut size = 0;
+
+        // SAFETY: `secret.0` is a valid secret handle and a null output buffer
+        // only queries the size of the secret into `size`.
+        check(unsafe {
+            BCryptDeriveKey(
+                secret.0,
+                BCRYPT_KDF_RAW_SECRET,
+                ptr::null(),
+                ptr::null_mut(),
+                0,
+                &mut size,
+                0,
+            )
+        })?;
+
+        let mut out = Zeroizing::new(vec![0u8; size as usize]);
+
+        // SAFETY: `out` is valid for writes of `size` bytes.
+        check(unsafe {
+            BCryptDeriveKey(
+                secret.0,
+                BCRYPT_KDF_RAW_SECRET,
+                ptr::null(),
+                out.as_mut_ptr(),
+                size,
+                &mut size,
+                0,
+            )
+        })?;
+
+        // The raw secret is output in little endian.
+        out.truncate(size as usize);
+        out.reverse();
+
+        Ok(out)
+    }
+
+    /// Signature of `hash` in the IEEE P1363 format, i.e. `r || s`.
+    pub(crate) fn sign_hash(&self, hash: &[u8]) -> Result<Vec<u8>, Status> {
+        let mut size = 0;
+
+        // SAFETY: `hash` is valid for reads of its length and a null output
+        // buffer only queries the size of the signature into `size`.
+        check(unsafe {
+            BCryptSignHash(
+                self.0,
+                ptr::null(),
+                hash.as_ptr(),
+                len32(hash)?,
+                ptr::null_mut(),
+                0,
+                &mut size,
+                0,
+            )
+        })?;
+
+        let mut signature = vec![0u8; size as usize];
+
+        // SAFETY: `signature` is valid for writes of `size` bytes.
+        check(unsafe {
+            BCryptSignHash(
+                self.0,
+                ptr::null(),
+                hash.as_ptr(),
+                len32(hash)?,
+                signature.as_mut_ptr(),
+                size,
+                &mut size,
+                0,
+            )
+        })?;
+
+        signature.truncate(size as usize);
+
+        Ok(signature)
+    }
+
+    /// Verify the P1363 signature `signature` of `hash`.
+    pub(crate) fn verify_hash(&self, hash: &[u8], signature: &[u8]) -> Result<(), Status> {
+        // SAFETY: `hash` and `signature` are valid for reads of their length.
+        check(unsafe {
+            BCryptVerifySignature(
+                self.0,
+                ptr::null(),
+                hash.as_ptr(),
+                len32(hash)?,
+                signature.as_ptr(),
+                len32(signature)?,
+                0,
+            )
+        })
+    }
+
+    pub(crate) fn handle(&self) -> BCRYPT_KEY_HANDLE {
+        self.0
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        // SAFETY: `self.0` is a valid key handle that is never used again.
+        unsafe { BCryptDestroyKey(self.0) };
+    }
+}
+
+/// CNG secret agreement object, destroyed on drop.
+struct Secret(BCRYPT_SECRET_HANDLE);
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // SAFETY: `self.0` is a valid secret handle that is never used again.
+        unsafe { BCryptDestroySecret(self.0) };
+    }
+}
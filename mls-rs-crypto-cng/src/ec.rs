@@ -0,0 +1,319 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Conversion of NIST curve keys between the MLS encoding and CNG key
+//! objects, and of ECDSA signatures between DER and the P1363 format of CNG.
+
+use alloc::{vec, vec::Vec};
+
+use mls_rs_crypto_traits::Curve;
+use windows_sys::Win32::Security::Cryptography::{
+    BCRYPT_ALG_HANDLE, BCRYPT_ECCPRIVATE_BLOB, BCRYPT_ECCPUBLIC_BLOB, BCRYPT_ECDH_P256_ALG_HANDLE,
+    BCRYPT_ECDH_P384_ALG_HANDLE, BCRYPT_ECDH_P521_ALG_HANDLE, BCRYPT_ECDH_PRIVATE_P256_MAGIC,
+    BCRYPT_ECDH_PRIVATE_P384_MAGIC, BCRYPT_ECDH_PRIVATE_P521_MAGIC, BCRYPT_ECDH_PUBLIC_P256_MAGIC,
+    BCRYPT_ECDH_PUBLIC_P384_MAGIC, BCRYPT_ECDH_PUBLIC_P521_MAGIC, BCRYPT_ECDSA_P256_ALG_HANDLE,
+    BCRYPT_ECDSA_P384_ALG_HANDLE, BCRYPT_ECDSA_P521_ALG_HANDLE, BCRYPT_ECDSA_PRIVATE_P256_MAGIC,
+    BCRYPT_ECDSA_PRIVATE_P384_MAGIC, BCRYPT_ECDSA_PRIVATE_P521_MAGIC,
+    BCRYPT_ECDSA_PUBLIC_P256_MAGIC, BCRYPT_ECDSA_PUBLIC_P384_MAGIC, BCRYPT_ECDSA_PUBLIC_P521_MAGIC,
+};
+use zeroize::Zeroizing;
+
+use crate::bcrypt::{Key, Status};
+
+/// Size of the `BCRYPT_ECCKEY_BLOB` header of key blobs.
+const BLOB_HEADER_SIZE: usize = 8;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_SEQUENCE: u8 = 0x30;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum EcError {
+    #[cfg_attr(feature = "std", error("CNG error {0:#010x}"))]
+    CngError(i32),
+    #[cfg_attr(feature = "std", error("unsupported curve type"))]
+    UnsupportedCurve,
+    #[cfg_attr(feature = "std", error("invalid public key data"))]
+    InvalidPublicKeyData,
+    #[cfg_attr(feature = "std", error("invalid secret key bytes"))]
+    InvalidSecretKeyBytes,
+}
+
+impl From<Status> for EcError {
+    fn from(status: Status) -> Self {
+        EcError::CngError(status.0)
+    }
+}
+
+/// Algorithm a key is imported for. CNG keys are either ECDH or ECDSA keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyUsage {
+    Ecdh,
+    Ecdsa,
+}
+
+pub(crate) struct KeyPair {
+    pub secret: Zeroizing<Vec<u8>>,
+    pub public: Vec<u8>,
+}
+
+/// Whether CNG supports `curve`.
+pub(crate) fn is_supported(curve: Curve) -> bool {
+    matches!(curve, Curve::P256 | Curve::P384 | Curve::P521)
+}
+
+fn algorithm(curve: Curve, usage: KeyUsage) -> Result<BCRYPT_ALG_HANDLE, EcError> {
+    match (curve, usage) {
+        (Curve::P256, KeyUsage::Ecdh) => Ok(BCRYPT_ECDH_P256_ALG_HANDLE),
+        (Curve::P384, KeyUsage::Ecdh) => Ok(BCRYPT_ECDH_P384_ALG_HANDLE),
+        (Curve::P521, KeyUsage::Ecdh) => Ok(BCRYPT_ECDH_P521_ALG_HANDLE),
+        (Curve::P256, KeyUsage::Ecdsa) => Ok(BCRYPT_ECDSA_P256_ALG_HANDLE),
+        (Curve::P384, KeyUsage::Ecdsa) => Ok(BCRYPT_ECDSA_P384_ALG_HANDLE),
+        (Curve::P521, KeyUsage::Ecdsa) => Ok(BCRYPT_ECDSA_P521_ALG_HANDLE),
+        _ => Err(EcError::UnsupportedCurve),
+    }
+}
+
+fn magic(curve: Curve, usage: KeyUsage, private: bool) -> Result<u32, EcError> {
+    let magic = match (curve, usage, private) {
+        (Curve::P256, KeyUsage::Ecdh, false) => BCRYPT_ECDH_PUBLIC_P256_MAGIC,
+        (Curve::P256, KeyUsage::Ecdh, true) => BCRYPT_ECDH_PRIVATE_P256_MAGIC,
+        (Curve::P384, KeyUsage::Ecdh, false) => BCRYPT_ECDH_PUBLIC_P384_MAGIC,
+        (Curve::P384, KeyUsage::Ecdh, true) => BCRYPT_ECDH_PRIVATE_P384_MAGIC,
+        (Curve::P521, KeyUsage::Ecdh, false) => BCRYPT_ECDH_PUBLIC_P521_MAGIC,
+        (Curve::P521, KeyUsage::Ecdh, true) => BCRYPT_ECDH_PRIVATE_P521_MAGIC,
+        (Curve::P256, KeyUsage::Ecdsa, false) => BCRYPT_ECDSA_PUBLIC_P256_MAGIC,
+        (Curve::P256, KeyUsage::Ecdsa, true) => BCRYPT_ECDSA_PRIVATE_P256_MAGIC,
+        (Curve::P384, KeyUsage::Ecdsa, false) => BCRYPT_ECDSA_PUBLIC_P384_MAGIC,
+        (Curve::P384, KeyUsage::Ecdsa, true) => BCRYPT_ECDSA_PRIVATE_P384_MAGIC,
+        (Curve::P521, KeyUsage::Ecdsa, false) => BCRYPT_ECDSA_PUBLIC_P521_MAGIC,
+        (Curve::P521, KeyUsage::Ecdsa, true) => BCRYPT_ECDSA_PRIVATE_P521_MAGIC,
+        _ => return Err(EcError::UnsupportedCurve),
+    };
+
+    Ok(magic)
+}
+
+fn bits(curve: Curve) -> Result<u32, EcError> {
+    match curve {
+        Curve::P256 => Ok(256),
+        Curve::P384 => Ok(384),
+        Curve::P521 => Ok(521),
+        _ => Err(EcError::UnsupportedCurve),
+    }
+}
+
+fn blob_header(curve: Curve, usage: KeyUsage, private: bool) -> Result<Vec<u8>, EcError> {
+    let mut header = magic(curve, usage, private)?.to_le_bytes().to_vec();
+    header.extend_from_slice(&(curve.secret_key_size() as u32).to_le_bytes());
+
+    Ok(header)
+}
+
+/// `0x04 || X || Y` from the coordinates `X || Y` of a key blob.
+fn uncompressed_point(coordinates: &[u8]) -> Vec<u8> {
+    let mut point = vec![0x04];
+    point.extend_from_slice(coordinates);
+    point
+}
+
+pub(crate) fn generate_keypair(curve: Curve, usage: KeyUsage) -> Result<KeyPair, EcError> {
+    let key = Key::generate_pair(algorithm(curve, usage)?, bits(curve)?)?;
+    let blob = key.export(BCRYPT_ECCPRIVATE_BLOB)?;
+
+    let size = curve.secret_key_size();
+
+    let coordinates = blob
+        .get(BLOB_HEADER_SIZE..BLOB_HEADER_SIZE + 3 * size)
+        .ok_or(EcError::InvalidSecretKeyBytes)?;
+
+    let (public, secret) = coordinates.split_at(2 * size);
+
+    Ok(KeyPair {
+        secret: Zeroizing::new(secret.to_vec()),
+        public: uncompressed_point(public),
+    })
+}
+
+pub(crate) fn import_private_key(
+    secret: &[u8],
+    curve: Curve,
+    usage: KeyUsage,
+) -> Result<Key, EcError> {
+    let size = curve.secret_key_size();
+
+    if secret.len() != size {
+        return Err(EcError::InvalidSecretKeyBytes);
+    }
+
+    // CNG computes the public point of private key blobs with zero
+    // coordinates.
+    let mut blob = Zeroizing::new(blob_header(curve, usage, true)?);
+    blob.resize(BLOB_HEADER_SIZE + 2 * size, 0);
+    blob.extend_from_slice(secret);
+
+    Key::import_pair(algorithm(curve, usage)?, BCRYPT_ECCPRIVATE_BLOB, &blob)
+        .map_err(|_| EcError::InvalidSecretKeyBytes)
+}
+
+/// Import the uncompressed point `public`, which CNG checks to be on the
+/// curve.
+pub(crate) fn import_public_key(
+    public: &[u8],
+    curve: Curve,
+    usage: KeyUsage,
+) -> Result<Key, EcError> {
+    let coordinates = match public.split_first() {
+        Some((0x04, coordinates)) if coordinates.len() == 2 * curve.secret_key_size() => {
+            coordinates
+        }
+        _ => return Err(EcError::InvalidPublicKeyData),
+    };
+
+    let mut blob = blob_header(curve, usage, false)?;
+    blob.extend_from_slice(coordinates);
+
+    Key::import_pair(algorithm(curve, usage)?, BCRYPT_ECCPUBLIC_BLOB, &blob)
+        .map_err(|_| EcError::InvalidPublicKeyData)
+}
+
+pub(crate) fn private_key_bytes_to_public(
+    secret: &[u8],
+    curve: Curve,
+    usage: KeyUsage,
+) -> Result<Vec<u8>, EcError> {
+    let key = import_private_key(secret, curve, usage)?;
+    let blob = key.export(BCRYPT_ECCPUBLIC_BLOB)?;
+
+    let coordinates = blob
+        .get(BLOB_HEADER_SIZE..BLOB_HEADER_SIZE + 2 * curve.secret_key_size())
+        .ok_or(EcError::InvalidSecretKeyBytes)?;
+
+    Ok(uncompressed_point(coordinates))
+}
+
+fn push_der_length(out: &mut Vec<u8>, len: usize) {
+    // Signatures are shorter than 256 bytes.
+    if len >= 0x80 {
+        out.push(0x81);
+    }
+
+    out.push(len as u8);
+}
+
+fn push_der_integer(out: &mut Vec<u8>, int: &[u8]) {
+    let start = int
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(int.len().saturating_sub(1));
+
+    let int = &int[start..];
+    let pad = int.first().map_or(true, |b| b & 0x80 != 0);
+
+    out.push(TAG_INTEGER);
+    push_der_length(out, int.len() + pad as usize);
+
+    if pad {
+        out.push(0);
+    }
+
+    out.extend_from_slice(int);
+}
+
+/// DER encoding of the P1363 signature `r || s`.
+pub(crate) fn p1363_to_der(signature: &[u8]) -> Vec<u8> {
+    let (r, s) = signature.split_at(signature.len() / 2);
+
+    let mut integers = Vec::new();
+    push_der_integer(&mut integers, r);
+    push_der_integer(&mut integers, s);
+
+    let mut der = vec![TAG_SEQUENCE];
+    push_der_length(&mut der, integers.len());
+    der.extend_from_slice(&integers);
+    der
+}
+
+fn read_der<'a>(input: &mut &'a [u8], tag: u8) -> Option<&'a [u8]> {
+    let (&found, rest) = input.split_first()?;
+    (found == tag).then_some(())?;
+
+    let (len, rest) = match rest.split_first()? {
+        (&len, rest) if len < 0x80 => (len as usize, rest),
+        (0x81, rest) => {
+            let (&len, rest) = rest.split_first()?;
+            (len >= 0x80).then_some((len as usize, rest))?
+        }
+        _ => return None,
+    };
+
+    (rest.len() >= len).then_some(())?;
+
+    let (value, rest) = rest.split_at(len);
+    *input = rest;
+
+    Some(value)
+}
+
+/// P1363 encoding `r || s`, with integers of `size` bytes, of the DER
+/// signature `der`.
+pub(crate) fn der_to_p1363(der: &[u8], size: usize) -> Option<Vec<u8>> {
+    let mut input = der;
+    let mut integers = read_der(&mut input, TAG_SEQUENCE)?;
+    input.is_empty().then_some(())?;
+
+    let mut signature = vec![0u8; 2 * size];
+
+    for half in signature.chunks_mut(size) {
+        let int = read_der(&mut integers, TAG_INTEGER)?;
+
+        // Integers are positive, and may have a leading zero byte.
+        let int = match int {
+            [first, ..] if first & 0x80 != 0 => return None,
+            [0, rest @ ..] if !rest.is_empty() => rest,
+            [] => return None,
+            int => int,
+        };
+
+        (int.len() <= size).then_some(())?;
+        half[size - int.len()..].copy_from_slice(int);
+    }
+
+    integers.is_empty().then_some(signature)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use super::{der_to_p1363, p1363_to_der};
+
+    #[test]
+    fn der_signature_round_trip() {
+        for size in [32, 48, 66] {
+            let mut signature = vec![0u8; 2 * size];
+
+            // High bit set in r, leading zeros in s.
+            signature[0] = 0x80;
+            signature[2 * size - 1] = 1;
+
+            let der = p1363_to_der(&signature);
+
+            assert_eq!(der[0], 0x30);
+            assert_eq!(der_to_p1363(&der, size).unwrap(), signature);
+        }
+    }
+
+    #[test]
+    fn invalid_der_signature() {
+        let der = p1363_to_der(&[1u8; 64]);
+
+        assert!(der_to_p1363(&der[..der.len() - 1], 32).is_none());
+        assert!(der_to_p1363(&[der.as_slice(), &[0]].concat(), 32).is_none());
+        assert!(der_to_p1363(&der, 16).is_none());
+
+        // Negative integer
+        assert!(der_to_p1363(&[0x30, 0x06, 0x02, 0x01, 0x80, 0x02, 0x01, 0x01], 32).is_none());
+    }
+}
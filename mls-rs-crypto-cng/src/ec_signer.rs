@@ -0,0 +1,162 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use crate::{
+    bcrypt::Status,
+    ec::{
+        der_to_p1363, generate_keypair, import_private_key, import_public_key, is_supported,
+        p1363_to_der, private_key_bytes_to_public, EcError, KeyUsage,
+    },
+    mac::{Hash, HashError},
+};
+use alloc::vec::Vec;
+use core::ops::Deref;
+use mls_rs_core::crypto::{CipherSuite, SignaturePublicKey, SignatureSecretKey};
+use mls_rs_crypto_traits::Curve;
+use windows_sys::Win32::Foundation::STATUS_INVALID_SIGNATURE;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum EcSignerError {
+    #[cfg_attr(feature = "std", error(transparent))]
+    EcError(EcError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    HashError(HashError),
+    #[cfg_attr(feature = "std", error("invalid signature"))]
+    InvalidSignature,
+}
+
+impl From<EcError> for EcSignerError {
+    fn from(e: EcError) -> Self {
+        EcSignerError::EcError(e)
+    }
+}
+
+impl From<HashError> for EcSignerError {
+    fn from(e: HashError) -> Self {
+        EcSignerError::HashError(e)
+    }
+}
+
+impl From<Status> for EcSignerError {
+    fn from(status: Status) -> Self {
+        match status.0 {
+            STATUS_INVALID_SIGNATURE => EcSignerError::InvalidSignature,
+            _ => EcSignerError::EcError(status.into()),
+        }
+    }
+}
+
+/// ECDSA on the NIST curves, with DER encoded signatures. CNG doesn't
+/// implement EdDSA.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub struct EcSigner(Curve);
+
+impl Deref for EcSigner {
+    type Target = Curve;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl EcSigner {
+    pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        Curve::from_ciphersuite(cipher_suite, true)
+            .filter(|curve| is_supported(*curve))
+            .map(Self)
+    }
+
+    /// Hash function signed messages are hashed with, as set by RFC 9420 for
+    /// the cipher suites using the curve.
+    fn hash(&self) -> Hash {
+        match self.0 {
+            Curve::P384 => Hash::Sha384,
+            Curve::P521 => Hash::Sha512,
+            _ => Hash::Sha256,
+        }
+    }
+
+    pub fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), EcSignerError> {
+        let key_pair = generate_keypair(self.0, KeyUsage::Ecdsa)?;
+        Ok((key_pair.secret.to_vec().into(), key_pair.public.into()))
+    }
+
+    pub fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, EcSignerError> {
+        Ok(private_key_bytes_to_public(secret_key, self.0, KeyUsage::Ecdsa)?.into())
+    }
+
+    pub fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, EcSignerError> {
+        let secret_key = import_private_key(secret_key, self.0, KeyUsage::Ecdsa)?;
+        let signature = secret_key.sign_hash(&self.hash().hash(data)?)?;
+
+        Ok(p1363_to_der(&signature))
+    }
+
+    pub fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), EcSignerError> {
+        let public_key = import_public_key(public_key, self.0, KeyUsage::Ecdsa)?;
+
+        let signature = der_to_p1363(signature, self.0.secret_key_size())
+            .ok_or(EcSignerError::InvalidSignature)?;
+
+        Ok(public_key.verify_hash(&self.hash().hash(data)?, &signature)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::CipherSuite;
+
+    use super::{EcSigner, EcSignerError};
+
+    fn get_signers() -> impl Iterator<Item = EcSigner> {
+        [
+            CipherSuite::P256_AES128,
+            CipherSuite::P384_AES256,
+            CipherSuite::P521_AES256,
+        ]
+        .into_iter()
+        .map(|cs| EcSigner::new(cs).unwrap())
+    }
+
+    #[test]
+    fn eddsa_is_not_supported() {
+        assert!(EcSigner::new(CipherSuite::CURVE25519_AES128).is_none());
+    }
+
+    #[test]
+    fn signature_round_trip() {
+        for signer in get_signers() {
+            let (secret_key, public_key) = signer.signature_key_generate().unwrap();
+
+            assert_eq!(
+                signer.signature_key_derive_public(&secret_key).unwrap(),
+                public_key
+            );
+
+            let signature = signer.sign(&secret_key, b"message").unwrap();
+            signer.verify(&public_key, &signature, b"message").unwrap();
+
+            assert_matches!(
+                signer.verify(&public_key, &signature, b"other message"),
+                Err(EcSignerError::InvalidSignature)
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::Debug;
+
+use mls_rs_core::{crypto::CipherSuite, error::IntoAnyError};
+use mls_rs_crypto_traits::{KdfId, KdfType};
+use windows_sys::Win32::Security::Cryptography::{
+    BCRYPT_HKDF_ALG_HANDLE, BCRYPT_HKDF_HASH_ALGORITHM, BCRYPT_HKDF_PRK_AND_FINALIZE, KDF_HKDF_INFO,
+};
+
+use alloc::vec::Vec;
+
+use crate::{
+    bcrypt::{Key, Status},
+    mac::{Hash, HashError},
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum KdfError {
+    #[cfg_attr(feature = "std", error("CNG error {0:#010x}"))]
+    CngError(i32),
+    #[cfg_attr(
+        feature = "std",
+        error("the provided length of the key {0} is shorter than the minimum length {1}")
+    )]
+    TooShortKey(usize, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("requested output length {0} is outside of the allowed range 1..={1}")
+    )]
+    InvalidLength(usize, usize),
+    #[cfg_attr(feature = "std", error("unsupported cipher suite"))]
+    UnsupportedCipherSuite,
+}
+
+impl From<Status> for KdfError {
+    fn from(status: Status) -> Self {
+        KdfError::CngError(status.0)
+    }
+}
+
+impl From<HashError> for KdfError {
+    fn from(e: HashError) -> Self {
+        match e {
+            HashError::CngError(status) => KdfError::CngError(status),
+            HashError::UnsupportedCipherSuite => KdfError::UnsupportedCipherSuite,
+        }
+    }
+}
+
+impl IntoAnyError for KdfError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// HKDF, with the expand step performed by `BCryptKeyDerivation`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Kdf(KdfId);
+
+impl Kdf {
+    pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        KdfId::new(cipher_suite).map(Self)
+    }
+
+    fn hash(&self) -> Result<Hash, KdfError> {
+        match self.0 {
+            KdfId::HkdfSha256 => Ok(Hash::Sha256),
+            KdfId::HkdfSha384 => Ok(Hash::Sha384),
+            KdfId::HkdfSha512 => Ok(Hash::Sha512),
+            _ => Err(KdfError::UnsupportedCipherSuite),
+        }
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl KdfType for Kdf {
+    type Error = KdfError;
+
+    async fn expand(&self, prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, KdfError> {
+        if prk.len() < self.extract_size() {
+            return Err(KdfError::TooShortKey(prk.len(), self.extract_size()));
+        }
+
+        let max_len = 255 * self.extract_size();
+
+        if len == 0 || len > max_len {
+            return Err(KdfError::InvalidLength(len, max_len));
+        }
+
+        let key = Key::symmetric(BCRYPT_HKDF_ALG_HANDLE, prk)?;
+        key.set_str_property(BCRYPT_HKDF_HASH_ALGORITHM, self.hash()?.name())?;
+
+        // The key is used as the pseudorandom key, skipping the extract step.
+        key.set_empty_property(BCRYPT_HKDF_PRK_AND_FINALIZE)?;
+
+        Ok(key.derive(KDF_HKDF_INFO, info, len)?)
+    }
+
+    async fn extract(&self, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, KdfError> {
+        // RFC 5869 defines the extract step as HMAC keyed with the salt.
+        Ok(self.hash()?.mac(salt, ikm)?)
+    }
+
+    fn extract_size(&self) -> usize {
+        self.0.extract_size()
+    }
+
+    fn kdf_id(&self) -> u16 {
+        self.0 as u16
+    }
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+mod test {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::CipherSuite;
+    use mls_rs_crypto_traits::KdfType;
+
+    use super::{Kdf, KdfError};
+
+    #[test]
+    fn invalid_lengths_are_rejected() {
+        let kdf = Kdf::new(CipherSuite::P256_AES128).unwrap();
+
+        assert_matches!(
+            kdf.expand(&[0u8; 16], b"info", 32),
+            Err(KdfError::TooShortKey(16, 32))
+        );
+
+        assert_matches!(
+            kdf.expand(&[0u8; 32], b"info", 255 * 32 + 1),
+            Err(KdfError::InvalidLength(_, _))
+        );
+    }
+}
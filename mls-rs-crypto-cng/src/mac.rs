@@ -0,0 +1,128 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::crypto::CipherSuite;
+use windows_sys::{
+    core::PCWSTR,
+    Win32::Security::Cryptography::{
+        BCRYPT_ALG_HANDLE, BCRYPT_HMAC_SHA256_ALG_HANDLE, BCRYPT_HMAC_SHA384_ALG_HANDLE,
+        BCRYPT_HMAC_SHA512_ALG_HANDLE, BCRYPT_SHA256_ALGORITHM, BCRYPT_SHA256_ALG_HANDLE,
+        BCRYPT_SHA384_ALGORITHM, BCRYPT_SHA384_ALG_HANDLE, BCRYPT_SHA512_ALGORITHM,
+        BCRYPT_SHA512_ALG_HANDLE,
+    },
+};
+
+use crate::bcrypt::{self, Status};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum HashError {
+    #[cfg_attr(feature = "std", error("CNG error {0:#010x}"))]
+    CngError(i32),
+    #[cfg_attr(feature = "std", error("unsupported cipher suite"))]
+    UnsupportedCipherSuite,
+}
+
+impl From<Status> for HashError {
+    fn from(status: Status) -> Self {
+        HashError::CngError(status.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u16)]
+pub enum Hash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Hash {
+    pub fn new(cipher_suite: CipherSuite) -> Result<Self, HashError> {
+        match cipher_suite {
+            CipherSuite::CURVE25519_AES128
+            | CipherSuite::P256_AES128
+            | CipherSuite::CURVE25519_CHACHA => Ok(Hash::Sha256),
+            CipherSuite::P384_AES256 => Ok(Hash::Sha384),
+            CipherSuite::CURVE448_AES256
+            | CipherSuite::CURVE448_CHACHA
+            | CipherSuite::P521_AES256 => Ok(Hash::Sha512),
+            _ => Err(HashError::UnsupportedCipherSuite),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Hash::Sha256 => 32,
+            Hash::Sha384 => 48,
+            Hash::Sha512 => 64,
+        }
+    }
+
+    pub fn hash(&self, data: &[u8]) -> Result<Vec<u8>, HashError> {
+        let alg = match self {
+            Hash::Sha256 => BCRYPT_SHA256_ALG_HANDLE,
+            Hash::Sha384 => BCRYPT_SHA384_ALG_HANDLE,
+            Hash::Sha512 => BCRYPT_SHA512_ALG_HANDLE,
+        };
+
+        Ok(bcrypt::hash(alg, &[], data, self.size())?)
+    }
+
+    pub fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, HashError> {
+        // HMAC pads keys with zeros, and CNG rejects empty keys.
+        let key = if key.is_empty() { &[0u8][..] } else { key };
+
+        Ok(bcrypt::hash(self.hmac_alg(), key, data, self.size())?)
+    }
+
+    fn hmac_alg(&self) -> BCRYPT_ALG_HANDLE {
+        match self {
+            Hash::Sha256 => BCRYPT_HMAC_SHA256_ALG_HANDLE,
+            Hash::Sha384 => BCRYPT_HMAC_SHA384_ALG_HANDLE,
+            Hash::Sha512 => BCRYPT_HMAC_SHA512_ALG_HANDLE,
+        }
+    }
+
+    /// CNG name of the hash algorithm.
+    pub(crate) fn name(&self) -> PCWSTR {
+        match self {
+            Hash::Sha256 => BCRYPT_SHA256_ALGORITHM,
+            Hash::Sha384 => BCRYPT_SHA384_ALGORITHM,
+            Hash::Sha512 => BCRYPT_SHA512_ALGORITHM,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Hash;
+
+    // FIPS 180-2 test vector.
+    #[test]
+    fn sha256_of_abc() {
+        let hash = Hash::Sha256.hash(b"abc").unwrap();
+
+        assert_eq!(
+            hash,
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_mac_key_is_zero_padded() {
+        for hash in [Hash::Sha256, Hash::Sha384, Hash::Sha512] {
+            let zeros = [0u8; 16];
+            assert_eq!(
+                hash.mac(&[], b"data").unwrap(),
+                hash.mac(&zeros, b"data").unwrap()
+            );
+        }
+    }
+}
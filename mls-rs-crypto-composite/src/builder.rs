@@ -0,0 +1,196 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_core::crypto::CipherSuite;
+use mls_rs_crypto_hpke::hpke::Hpke;
+use mls_rs_crypto_traits::{
+    AeadId, AeadType, HashType, KdfId, KdfType, KemId, KemType, RandomType, SignatureType,
+};
+
+use crate::{CompositeCipherSuite, CompositeError};
+
+/// Placeholder for a part not yet given to a [`CompositeCipherSuiteBuilder`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Missing;
+
+/// Builder of a [`CompositeCipherSuite`].
+///
+/// [`build`](Self::build) is only available once all six parts are set, so
+/// that a cipher suite missing a part fails to compile. For the cipher suites
+/// of RFC 9420, `build` also checks that the KEM, KDF and AEAD are those of
+/// the cipher suite. Other cipher suites, e.g. with a post-quantum KEM, are
+/// not checked.
+#[must_use]
+pub struct CompositeCipherSuiteBuilder<
+    KEM = Missing,
+    KDF = Missing,
+    AEAD = Missing,
+    H = Missing,
+    S = Missing,
+    R = Missing,
+> {
+    cipher_suite: CipherSuite,
+    kem: KEM,
+    kdf: KDF,
+    aead: AEAD,
+    hash: H,
+    signature: S,
+    random: R,
+}
+
+impl CompositeCipherSuiteBuilder {
+    pub fn new(cipher_suite: CipherSuite) -> Self {
+        Self {
+            cipher_suite,
+            kem: Missing,
+            kdf: Missing,
+            aead: Missing,
+            hash: Missing,
+            signature: Missing,
+            random: Missing,
+        }
+    }
+}
+
+impl<KEM, KDF, AEAD, H, S, R> CompositeCipherSuiteBuilder<KEM, KDF, AEAD, H, S, R> {
+    /// Set the KEM, used with the KDF and AEAD for HPKE.
+    pub fn kem<K: KemType>(self, kem: K) -> CompositeCipherSuiteBuilder<K, KDF, AEAD, H, S, R> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem,
+            kdf: self.kdf,
+            aead: self.aead,
+            hash: self.hash,
+            signature: self.signature,
+            random: self.random,
+        }
+    }
+
+    /// Set the KDF.
+    pub fn kdf<K: KdfType>(self, kdf: K) -> CompositeCipherSuiteBuilder<KEM, K, AEAD, H, S, R> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem: self.kem,
+            kdf,
+            aead: self.aead,
+            hash: self.hash,
+            signature: self.signature,
+            random: self.random,
+        }
+    }
+
+    /// Set the AEAD.
+    pub fn aead<A: AeadType>(self, aead: A) -> CompositeCipherSuiteBuilder<KEM, KDF, A, H, S, R> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem: self.kem,
+            kdf: self.kdf,
+            aead,
+            hash: self.hash,
+            signature: self.signature,
+            random: self.random,
+        }
+    }
+
+    /// Set the hash function and MAC.
+    pub fn hash<T: HashType>(
+        self,
+        hash: T,
+    ) -> CompositeCipherSuiteBuilder<KEM, KDF, AEAD, T, S, R> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem: self.kem,
+            kdf: self.kdf,
+            aead: self.aead,
+            hash,
+            signature: self.signature,
+            random: self.random,
+        }
+    }
+
+    /// Set the signature scheme.
+    pub fn signature<T: SignatureType>(
+        self,
+        signature: T,
+    ) -> CompositeCipherSuiteBuilder<KEM, KDF, AEAD, H, T, R> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem: self.kem,
+            kdf: self.kdf,
+            aead: self.aead,
+            hash: self.hash,
+            signature,
+            random: self.random,
+        }
+    }
+
+    /// Set the source of random bytes.
+    pub fn random<T: RandomType>(
+        self,
+        random: T,
+    ) -> CompositeCipherSuiteBuilder<KEM, KDF, AEAD, H, S, T> {
+        CompositeCipherSuiteBuilder {
+            cipher_suite: self.cipher_suite,
+            kem: self.kem,
+            kdf: self.kdf,
+            aead: self.aead,
+            hash: self.hash,
+            signature: self.signature,
+            random,
+        }
+    }
+}
+
+impl<KEM, KDF, AEAD, H, S, R> CompositeCipherSuiteBuilder<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType,
+    KDF: KdfType + Clone,
+    AEAD: AeadType + Clone,
+    H: HashType,
+    S: SignatureType,
+    R: RandomType,
+{
+    pub fn build(self) -> Result<CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>, CompositeError> {
+        let cipher_suite = self.cipher_suite;
+
+        check_id(
+            KemId::new(cipher_suite).map(|id| id as u16),
+            self.kem.kem_id(),
+            CompositeError::MismatchedKem,
+        )?;
+
+        check_id(
+            KdfId::new(cipher_suite).map(|id| id as u16),
+            self.kdf.kdf_id(),
+            CompositeError::MismatchedKdf,
+        )?;
+
+        check_id(
+            AeadId::new(cipher_suite).map(|id| id as u16),
+            self.aead.aead_id(),
+            CompositeError::MismatchedAead,
+        )?;
+
+        Ok(CompositeCipherSuite {
+            cipher_suite,
+            hpke: Hpke::new(self.kem, self.kdf.clone(), Some(self.aead.clone())),
+            kdf: self.kdf,
+            aead: self.aead,
+            hash: self.hash,
+            signature: self.signature,
+            random: self.random,
+        })
+    }
+}
+
+fn check_id(
+    expected: Option<u16>,
+    found: u16,
+    error: fn(u16) -> CompositeError,
+) -> Result<(), CompositeError> {
+    match expected {
+        Some(expected) if expected != found => Err(error(found)),
+        _ => Ok(()),
+    }
+}
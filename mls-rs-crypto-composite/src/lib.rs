@@ -0,0 +1,462 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Crypto provider composed of algorithms taken from different backends,
+//! e.g. the AEAD and KDF of one library, a signature scheme backed by a
+//! platform keystore and a KEM from a post-quantum library.
+//!
+//! Each part implements one of the traits of `mls-rs-crypto-traits`, and the
+//! parts are combined into a [`CompositeCipherSuite`] with a
+//! [`CompositeCipherSuiteBuilder`]. HPKE is built from the KEM, KDF and AEAD
+//! by `mls-rs-crypto-hpke`. Parts of an existing cipher suite provider are
+//! reused with [`ProviderPart`].
+//!
+//! ```ignore
+//! let cipher_suite = CompositeCipherSuiteBuilder::new(CipherSuite::P256_AES128)
+//!     .kem(kem)
+//!     .kdf(kdf)
+//!     .aead(aead)
+//!     .hash(ProviderPart::new(backend.clone()))
+//!     .signature(keystore_signer)
+//!     .random(ProviderPart::new(backend))
+//!     .build()?;
+//!
+//! let crypto_provider = CompositeCryptoProvider::new(vec![cipher_suite]);
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+mod builder;
+mod part;
+
+pub use builder::{CompositeCipherSuiteBuilder, Missing};
+pub use part::ProviderPart;
+
+use alloc::vec::Vec;
+
+use mls_rs_core::{
+    crypto::{
+        CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey,
+        HpkeSecretKey, SignaturePublicKey, SignatureSecretKey,
+    },
+    error::{AnyError, IntoAnyError},
+};
+use mls_rs_crypto_hpke::{
+    context::{ContextR, ContextS},
+    hpke::{Hpke, HpkeError},
+};
+use mls_rs_crypto_traits::{AeadType, HashType, KdfType, KemType, RandomType, SignatureType};
+use zeroize::Zeroizing;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum CompositeError {
+    #[cfg_attr(feature = "std", error(transparent))]
+    AeadError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    HpkeError(HpkeError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    KdfError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    HashError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    RandError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    SignatureError(AnyError),
+    /// The KEM of the builder has an id different from the one of its cipher
+    /// suite.
+    #[cfg_attr(feature = "std", error("KEM {0:#06x} doesn't match the cipher suite"))]
+    MismatchedKem(u16),
+    /// The KDF of the builder has an id different from the one of its cipher
+    /// suite.
+    #[cfg_attr(feature = "std", error("KDF {0:#06x} doesn't match the cipher suite"))]
+    MismatchedKdf(u16),
+    /// The AEAD of the builder has an id different from the one of its cipher
+    /// suite.
+    #[cfg_attr(feature = "std", error("AEAD {0:#06x} doesn't match the cipher suite"))]
+    MismatchedAead(u16),
+}
+
+impl From<HpkeError> for CompositeError {
+    fn from(e: HpkeError) -> Self {
+        CompositeError::HpkeError(e)
+    }
+}
+
+impl IntoAnyError for CompositeError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Crypto provider exposing a set of [`CompositeCipherSuite`] sharing the
+/// same types of parts.
+#[derive(Clone)]
+pub struct CompositeCryptoProvider<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType,
+    KDF: KdfType,
+    AEAD: AeadType,
+{
+    cipher_suites: Vec<CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>>,
+}
+
+impl<KEM, KDF, AEAD, H, S, R> CompositeCryptoProvider<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType,
+    KDF: KdfType,
+    AEAD: AeadType,
+{
+    /// Provider of `cipher_suites`. If several of them implement the same
+    /// cipher suite, the first one is used.
+    pub fn new(cipher_suites: Vec<CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>>) -> Self {
+        Self { cipher_suites }
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<KEM, KDF, AEAD, H, S, R> CryptoProvider for CompositeCryptoProvider<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType + Clone,
+    KDF: KdfType + Clone,
+    AEAD: AeadType + Clone,
+    H: HashType + Clone,
+    S: SignatureType + Clone,
+    R: RandomType + Clone,
+{
+    type CipherSuiteProvider = CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        let mut cipher_suites = Vec::new();
+
+        for cs in self.cipher_suites.iter().map(|cs| cs.cipher_suite) {
+            if !cipher_suites.contains(&cs) {
+                cipher_suites.push(cs);
+            }
+        }
+
+        cipher_suites
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        self.cipher_suites
+            .iter()
+            .find(|cs| cs.cipher_suite == cipher_suite)
+            .cloned()
+    }
+}
+
+/// Cipher suite provider performing each operation with one of its parts,
+/// created with a [`CompositeCipherSuiteBuilder`].
+#[derive(Clone)]
+pub struct CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType,
+    KDF: KdfType,
+    AEAD: AeadType,
+{
+    cipher_suite: CipherSuite,
+    kdf: KDF,
+    aead: AEAD,
+    hash: H,
+    signature: S,
+    random: R,
+    hpke: Hpke<KEM, KDF, AEAD>,
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<KEM, KDF, AEAD, H, S, R> CipherSuiteProvider for CompositeCipherSuite<KEM, KDF, AEAD, H, S, R>
+where
+    KEM: KemType,
+    KDF: KdfType + Clone,
+    AEAD: AeadType + Clone,
+    H: HashType,
+    S: SignatureType,
+    R: RandomType,
+{
+    type Error = CompositeError;
+    type HpkeContextR = ContextR<KDF, AEAD>;
+    type HpkeContextS = ContextS<KDF, AEAD>;
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.hash
+            .hash(data)
+            .await
+            .map_err(|e| CompositeError::HashError(e.into_any_error()))
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.hash
+            .mac(key, data)
+            .await
+            .map_err(|e| CompositeError::HashError(e.into_any_error()))
+    }
+
+    async fn aead_seal(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.aead
+            .seal(key, data, aad, nonce)
+            .await
+            .map_err(|e| CompositeError::AeadError(e.into_any_error()))
+    }
+
+    async fn aead_open(
+        &self,
+        key: &[u8],
+        cipher_text: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.aead
+            .open(key, cipher_text, aad, nonce)
+            .await
+            .map_err(|e| CompositeError::AeadError(e.into_any_error()))
+            .map(Zeroizing::new)
+    }
+
+    fn aead_key_size(&self) -> usize {
+        self.aead.key_size()
+    }
+
+    fn aead_nonce_size(&self) -> usize {
+        self.aead.nonce_size()
+    }
+
+    async fn kdf_expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.kdf
+            .expand(prk, info, len)
+            .await
+            .map_err(|e| CompositeError::KdfError(e.into_any_error()))
+            .map(Zeroizing::new)
+    }
+
+    async fn kdf_extract(
+        &self,
+        salt: &[u8],
+        ikm: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.kdf
+            .extract(salt, ikm)
+            .await
+            .map_err(|e| CompositeError::KdfError(e.into_any_error()))
+            .map(Zeroizing::new)
+    }
+
+    fn kdf_extract_size(&self) -> usize {
+        self.kdf.extract_size()
+    }
+
+    async fn hpke_seal(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+    ) -> Result<HpkeCiphertext, Self::Error> {
+        Ok(self.hpke.seal(remote_key, info, None, aad, pt).await?)
+    }
+
+    async fn hpke_open(
+        &self,
+        ciphertext: &HpkeCiphertext,
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        Ok(self
+            .hpke
+            .open(ciphertext, local_secret, local_public, info, None, aad)
+            .await?)
+    }
+
+    async fn hpke_setup_r(
+        &self,
+        enc: &[u8],
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<Self::HpkeContextR, Self::Error> {
+        Ok(self
+            .hpke
+            .setup_receiver(enc, local_secret, local_public, info, None)
+            .await?)
+    }
+
+    async fn hpke_setup_s(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self::HpkeContextS), Self::Error> {
+        Ok(self.hpke.setup_sender(remote_key, info, None).await?)
+    }
+
+    async fn kem_derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        Ok(self.hpke.derive(ikm).await?)
+    }
+
+    async fn kem_generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        Ok(self.hpke.generate().await?)
+    }
+
+    fn kem_public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        Ok(self.hpke.public_key_validate(key)?)
+    }
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.random
+            .random_bytes(out)
+            .map_err(|e| CompositeError::RandError(e.into_any_error()))
+    }
+
+    fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.signature
+            .sign(secret_key, data)
+            .await
+            .map_err(|e| CompositeError::SignatureError(e.into_any_error()))
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.signature
+            .verify(public_key, signature, data)
+            .await
+            .map_err(|e| CompositeError::SignatureError(e.into_any_error()))
+    }
+
+    async fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        self.signature
+            .generate()
+            .await
+            .map_err(|e| CompositeError::SignatureError(e.into_any_error()))
+    }
+
+    async fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.signature
+            .derive_public(secret_key)
+            .await
+            .map_err(|e| CompositeError::SignatureError(e.into_any_error()))
+    }
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::{CipherSuite, CipherSuiteProvider};
+    use mls_rs_crypto_traits::{
+        mock::{FakeAead, FakeKdf, FakeKem, MockHashType, MockRandomType, MockSignatureType},
+        KemId,
+    };
+
+    use crate::{CompositeCipherSuiteBuilder, CompositeError};
+
+    const PRIVATE_CIPHER_SUITE: CipherSuite = CipherSuite::new(0xF000);
+
+    #[test]
+    fn composed_cipher_suite_uses_its_parts() {
+        let mut hash = MockHashType::new();
+        hash.expect_hash().returning(|data| Ok(data.to_vec()));
+
+        let mut random = MockRandomType::new();
+
+        random.expect_random_bytes().returning(|out| {
+            out.fill(7);
+            Ok(())
+        });
+
+        let cipher_suite = CompositeCipherSuiteBuilder::new(PRIVATE_CIPHER_SUITE)
+            .kem(FakeKem::new(32))
+            .kdf(FakeKdf::new(32))
+            .aead(FakeAead::new(16, 12))
+            .hash(hash)
+            .signature(MockSignatureType::new())
+            .random(random)
+            .build()
+            .unwrap();
+
+        assert_eq!(cipher_suite.cipher_suite(), PRIVATE_CIPHER_SUITE);
+        assert_eq!(cipher_suite.hash(b"data").unwrap(), b"data");
+        assert_eq!(cipher_suite.aead_key_size(), 16);
+        assert_eq!(cipher_suite.kdf_extract_size(), 32);
+
+        let mut bytes = [0u8; 4];
+        cipher_suite.random_bytes(&mut bytes).unwrap();
+        assert_eq!(bytes, [7; 4]);
+
+        let (secret_key, public_key) = cipher_suite.kem_generate().unwrap();
+
+        let ciphertext = cipher_suite
+            .hpke_seal(&public_key, b"info", None, b"message")
+            .unwrap();
+
+        let plaintext = cipher_suite
+            .hpke_open(&ciphertext, &secret_key, &public_key, b"info", None)
+            .unwrap();
+
+        assert_eq!(plaintext, b"message");
+    }
+
+    #[test]
+    fn parts_of_another_cipher_suite_are_rejected() {
+        let builder = || {
+            CompositeCipherSuiteBuilder::new(CipherSuite::P256_AES128)
+                .kdf(FakeKdf::new(32))
+                .aead(FakeAead::new(16, 12))
+                .hash(MockHashType::new())
+                .signature(MockSignatureType::new())
+                .random(MockRandomType::new())
+        };
+
+        let res = builder().kem(FakeKem::new(32)).build().map(|_| ());
+        assert_matches!(res, Err(CompositeError::MismatchedKem(0xFFFF)));
+
+        let mut kem = FakeKem::new(32);
+        kem.kem_id = KemId::DhKemP256Sha256 as u16;
+
+        let res = builder().kem(kem).build().map(|_| ());
+        assert_matches!(res, Err(CompositeError::MismatchedKdf(0xFFFF)));
+    }
+}
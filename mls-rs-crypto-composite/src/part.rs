@@ -0,0 +1,99 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+
+use mls_rs_core::crypto::{CipherSuiteProvider, SignaturePublicKey, SignatureSecretKey};
+use mls_rs_crypto_traits::{HashType, RandomType, SignatureType};
+
+/// Hash function, signature scheme or source of random bytes of a complete
+/// cipher suite provider, to be used as a part of a
+/// [`CompositeCipherSuite`](crate::CompositeCipherSuite).
+///
+/// Combined with [`SignerCryptoProvider`](mls_rs_core::crypto::SignerCryptoProvider),
+/// this allows signing with keys held by a keystore.
+#[derive(Clone, Debug)]
+pub struct ProviderPart<P>(P);
+
+impl<P: CipherSuiteProvider> ProviderPart<P> {
+    pub fn new(provider: P) -> Self {
+        Self(provider)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<P> HashType for ProviderPart<P>
+where
+    P: CipherSuiteProvider,
+    P::Error: Send + Sync,
+{
+    type Error = P::Error;
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.0.hash(data).await
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.0.mac(key, data).await
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<P> SignatureType for ProviderPart<P>
+where
+    P: CipherSuiteProvider,
+    P::Error: Send + Sync,
+{
+    type Error = P::Error;
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.0.sign(secret_key, data).await
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.0.verify(public_key, signature, data).await
+    }
+
+    async fn generate(&self) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        self.0.signature_key_generate().await
+    }
+
+    async fn derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.0.signature_key_derive_public(secret_key).await
+    }
+}
+
+impl<P> RandomType for ProviderPart<P>
+where
+    P: CipherSuiteProvider,
+    P::Error: Send + Sync,
+{
+    type Error = P::Error;
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.random_bytes(out)
+    }
+}
@@ -381,3 +381,24 @@ fn mls_core_tests() {
         mls_rs_core::crypto::test_suite::verify_hpke_encap_tests(&mut hpke, cs);
     }
 }
+
+#[cfg(not(mls_build_async))]
+#[test]
+fn provider_test_kit() {
+    use mls_rs_crypto_traits::test_suite::{verify_aead, verify_kdf, verify_kem};
+
+    for cs in NssCryptoProvider::all_supported_cipher_suites() {
+        let kdf = Kdf::new(cs).unwrap();
+        let kem_id = KemId::new(cs).unwrap();
+        let kem = DhKem::new(
+            Ecdh::new(cs).unwrap(),
+            kdf,
+            kem_id as u16,
+            kem_id.n_secret(),
+        );
+
+        verify_kdf(&kdf);
+        verify_aead(&Aead::new(cs).unwrap());
+        verify_kem(&kem);
+    }
+}
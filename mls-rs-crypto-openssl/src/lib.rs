@@ -376,3 +376,16 @@ fn mls_core_tests() {
         mls_rs_core::crypto::test_suite::verify_hpke_encap_tests(&mut hpke, cs);
     }
 }
+
+#[cfg(not(mls_build_async))]
+#[test]
+fn self_test_passes() {
+    let report = OpensslCryptoProvider::new().self_test();
+
+    assert!(report.all_passed());
+
+    assert_eq!(
+        report.passed_cipher_suites(),
+        OpensslCryptoProvider::all_supported_cipher_suites()
+    );
+}
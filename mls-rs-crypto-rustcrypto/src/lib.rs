@@ -387,3 +387,55 @@ fn mls_core_tests() {
         mls_rs_core::crypto::test_suite::verify_hpke_encap_tests(&mut hpke, cs);
     }
 }
+
+#[cfg(not(mls_build_async))]
+#[test]
+fn provider_test_kit() {
+    use mls_rs_crypto_traits::test_suite::{verify_aead, verify_kdf, verify_kem};
+
+    for cs in RustCryptoProvider::all_supported_cipher_suites() {
+        let kdf = Kdf::new(cs).unwrap();
+        let kem_id = KemId::new(cs).unwrap();
+        let kem = DhKem::new(
+            Ecdh::new(cs).unwrap(),
+            kdf,
+            kem_id as u16,
+            kem_id.n_secret(),
+        );
+
+        verify_kdf(&kdf);
+        verify_aead(&Aead::new(cs).unwrap());
+        verify_kem(&kem);
+    }
+}
+
+#[cfg(not(mls_build_async))]
+#[test]
+fn self_test_exposes_passed_cipher_suites() {
+    use mls_rs_core::crypto::{SelfTestFailure, SelfTestedCryptoProvider};
+
+    let provider = SelfTestedCryptoProvider::new(RustCryptoProvider::new());
+
+    assert!(provider.report().all_passed());
+
+    assert_eq!(
+        provider.supported_cipher_suites(),
+        RustCryptoProvider::all_supported_cipher_suites()
+    );
+
+    let mut enabled = RustCryptoProvider::all_supported_cipher_suites();
+    enabled.push(CipherSuite::P384_AES256);
+
+    let provider =
+        SelfTestedCryptoProvider::new(RustCryptoProvider::with_enabled_cipher_suites(enabled));
+
+    assert!(!provider.report().has_passed(CipherSuite::P384_AES256));
+    assert!(provider
+        .cipher_suite_provider(CipherSuite::P384_AES256)
+        .is_none());
+
+    assert_eq!(
+        provider.report().failures(),
+        vec![(CipherSuite::P384_AES256, SelfTestFailure::Unavailable)]
+    );
+}
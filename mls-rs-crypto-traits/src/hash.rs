@@ -0,0 +1,24 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(feature = "mock")]
+use mockall::automock;
+
+use alloc::vec::Vec;
+use mls_rs_core::error::IntoAnyError;
+
+/// Hash function of a cipher suite, and HMAC based on it.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+#[cfg_attr(feature = "mock", automock(type Error = crate::mock::TestError;))]
+pub trait HashType: Send + Sync {
+    type Error: IntoAnyError + Send + Sync;
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error>;
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
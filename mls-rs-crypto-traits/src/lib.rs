@@ -8,14 +8,23 @@ extern crate alloc;
 mod aead;
 mod dh;
 mod ec;
+mod hash;
 mod kdf;
 mod kem;
+mod random;
+mod signature;
 
 pub use aead::{AeadId, AeadType, AEAD_ID_EXPORT_ONLY, AES_TAG_LEN};
 pub use dh::DhType;
 pub use ec::Curve;
+pub use hash::HashType;
 pub use kdf::{KdfId, KdfType};
 pub use kem::{KemId, KemResult, KemType};
+pub use random::RandomType;
+pub use signature::SignatureType;
 
 #[cfg(feature = "mock")]
 pub mod mock;
+
+#[cfg(feature = "test_suite")]
+pub mod test_suite;
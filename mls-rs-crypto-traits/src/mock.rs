@@ -2,7 +2,19 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-pub use crate::{aead::MockAeadType, dh::MockDhType, kdf::MockKdfType, kem::MockKemType};
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use mls_rs_core::crypto::{HpkePublicKey, HpkeSecretKey};
+
+use crate::{AeadType, KdfType, KemResult, KemType, AES_TAG_LEN};
+
+pub use crate::{
+    aead::MockAeadType, dh::MockDhType, hash::MockHashType, kdf::MockKdfType, kem::MockKemType,
+    random::MockRandomType, signature::MockSignatureType,
+};
 
 #[derive(Debug)]
 pub struct TestError {}
@@ -20,3 +32,280 @@ impl mls_rs_core::error::IntoAnyError for TestError {
         Ok(self.into())
     }
 }
+
+/// Fold `data` into `len` bytes with XOR.
+fn xor_fold<'a>(data: impl IntoIterator<Item = &'a u8>, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+
+    data.into_iter()
+        .zip((0..len).cycle())
+        .for_each(|(byte, i)| out[i] ^= byte);
+
+    out
+}
+
+/// Fake [`KdfType`] with predictable outputs, for tests that don't need
+/// actual key derivation.
+///
+/// `extract` folds `salt || ikm` into `extract_size` bytes with XOR, and
+/// `expand` repeats `prk` XORed with the byte at the same position in the
+/// repeated `info`.
+#[derive(Clone, Debug)]
+pub struct FakeKdf {
+    pub kdf_id: u16,
+    pub extract_size: usize,
+}
+
+impl FakeKdf {
+    pub fn new(extract_size: usize) -> Self {
+        Self {
+            kdf_id: 0xFFFF,
+            extract_size,
+        }
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl KdfType for FakeKdf {
+    type Error = TestError;
+
+    fn kdf_id(&self) -> u16 {
+        self.kdf_id
+    }
+
+    async fn expand(&self, prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, TestError> {
+        if prk.is_empty() {
+            return Err(TestError {});
+        }
+
+        let info = info.iter().cycle().chain(core::iter::repeat(&0));
+
+        Ok(prk
+            .iter()
+            .cycle()
+            .zip(info)
+            .take(len)
+            .map(|(p, i)| p ^ i)
+            .collect())
+    }
+
+    async fn extract(&self, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, TestError> {
+        Ok(xor_fold(salt.iter().chain(ikm), self.extract_size))
+    }
+
+    fn extract_size(&self) -> usize {
+        self.extract_size
+    }
+}
+
+/// Fake [`AeadType`] XORing the plaintext with `key` and `nonce`.
+///
+/// The ciphertext is followed by a tag of [`AES_TAG_LEN`] bytes folding
+/// `key || nonce || aad || plaintext` with XOR, which `open` checks.
+#[derive(Clone, Debug)]
+pub struct FakeAead {
+    pub aead_id: u16,
+    pub key_size: usize,
+    pub nonce_size: usize,
+}
+
+impl FakeAead {
+    pub fn new(key_size: usize, nonce_size: usize) -> Self {
+        Self {
+            aead_id: 0xFFFE,
+            key_size,
+            nonce_size,
+        }
+    }
+
+    fn check_sizes(&self, key: &[u8], nonce: &[u8]) -> Result<(), TestError> {
+        (key.len() == self.key_size && nonce.len() == self.nonce_size)
+            .then_some(())
+            .ok_or(TestError {})
+    }
+
+    fn apply_key_stream(&self, data: &[u8], key: &[u8], nonce: &[u8]) -> Vec<u8> {
+        data.iter()
+            .zip(key.iter().cycle())
+            .zip(nonce.iter().cycle())
+            .map(|((d, k), n)| d ^ k ^ n)
+            .collect()
+    }
+
+    fn tag(key: &[u8], nonce: &[u8], aad: Option<&[u8]>, plaintext: &[u8]) -> Vec<u8> {
+        let data = key
+            .iter()
+            .chain(nonce)
+            .chain(aad.unwrap_or_default())
+            .chain(plaintext);
+
+        xor_fold(data, AES_TAG_LEN)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl AeadType for FakeAead {
+    type Error = TestError;
+
+    fn aead_id(&self) -> u16 {
+        self.aead_id
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn seal<'a>(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&'a [u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, TestError> {
+        self.check_sizes(key, nonce)?;
+
+        let mut ciphertext = self.apply_key_stream(data, key, nonce);
+        ciphertext.extend(Self::tag(key, nonce, aad, data));
+
+        Ok(ciphertext)
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn open<'a>(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&'a [u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, TestError> {
+        self.check_sizes(key, nonce)?;
+
+        let body_len = ciphertext
+            .len()
+            .checked_sub(AES_TAG_LEN)
+            .ok_or(TestError {})?;
+
+        let (body, tag) = ciphertext.split_at(body_len);
+        let plaintext = self.apply_key_stream(body, key, nonce);
+
+        (Self::tag(key, nonce, aad, &plaintext) == tag)
+            .then_some(plaintext)
+            .ok_or(TestError {})
+    }
+
+    fn key_size(&self) -> usize {
+        self.key_size
+    }
+
+    fn nonce_size(&self) -> usize {
+        self.nonce_size
+    }
+}
+
+/// Fake [`KemType`] with predictable key pairs and encapsulations.
+///
+/// Secret keys are `ikm` folded into `secret_size` bytes with XOR, and public
+/// keys are their bitwise complement. The `n`-th call to `generate` derives
+/// the key pair from the 8 bytes of `n` in big endian, and the `n`-th call to
+/// `encap` uses `enc = [n; secret_size]` and the shared secret
+/// `remote_key XOR enc`, starting at `n = 1`.
+#[derive(Debug)]
+pub struct FakeKem {
+    pub kem_id: u16,
+    pub secret_size: usize,
+    generate_count: AtomicU64,
+    encap_count: AtomicU8,
+}
+
+impl FakeKem {
+    pub fn new(secret_size: usize) -> Self {
+        Self {
+            kem_id: 0xFFFF,
+            secret_size,
+            generate_count: AtomicU64::new(0),
+            encap_count: AtomicU8::new(0),
+        }
+    }
+
+    /// Number of calls to `generate` so far.
+    pub fn generate_count(&self) -> u64 {
+        self.generate_count.load(Ordering::SeqCst)
+    }
+
+    /// Number of calls to `encap` so far, wrapping to 0 after 255.
+    pub fn encap_count(&self) -> u8 {
+        self.encap_count.load(Ordering::SeqCst)
+    }
+
+    fn key_pair(&self, ikm: &[u8]) -> (HpkeSecretKey, HpkePublicKey) {
+        let secret_key = xor_fold(ikm, self.secret_size);
+        let public_key = secret_key.iter().map(|b| !b).collect::<Vec<_>>();
+
+        (secret_key.into(), public_key.into())
+    }
+
+    fn shared_secret(public_key: &[u8], enc: &[u8]) -> Vec<u8> {
+        public_key.iter().zip(enc).map(|(p, e)| p ^ e).collect()
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl KemType for FakeKem {
+    type Error = TestError;
+
+    fn kem_id(&self) -> u16 {
+        self.kem_id
+    }
+
+    async fn derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), TestError> {
+        Ok(self.key_pair(ikm))
+    }
+
+    async fn generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), TestError> {
+        let n = self.generate_count.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(self.key_pair(&n.to_be_bytes()))
+    }
+
+    fn public_key_validate(&self, key: &HpkePublicKey) -> Result<(), TestError> {
+        (key.len() == self.secret_size)
+            .then_some(())
+            .ok_or(TestError {})
+    }
+
+    async fn encap(&self, remote_key: &HpkePublicKey) -> Result<KemResult, TestError> {
+        self.public_key_validate(remote_key)?;
+
+        let n = self
+            .encap_count
+            .fetch_add(1, Ordering::SeqCst)
+            .wrapping_add(1);
+        let enc = vec![n; self.secret_size];
+
+        Ok(KemResult::new(Self::shared_secret(remote_key, &enc), enc))
+    }
+
+    async fn decap(
+        &self,
+        enc: &[u8],
+        _secret_key: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+    ) -> Result<Vec<u8>, TestError> {
+        (enc.len() == self.secret_size)
+            .then_some(())
+            .ok_or(TestError {})?;
+
+        Ok(Self::shared_secret(local_public, enc))
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(feature = "mock")]
+use mockall::automock;
+
+use mls_rs_core::error::IntoAnyError;
+
+/// Source of cryptographically secure random bytes.
+#[cfg_attr(feature = "mock", automock(type Error = crate::mock::TestError;))]
+pub trait RandomType: Send + Sync {
+    type Error: IntoAnyError + Send + Sync;
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error>;
+}
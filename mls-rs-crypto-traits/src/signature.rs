@@ -0,0 +1,44 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(feature = "mock")]
+use mockall::automock;
+
+use alloc::vec::Vec;
+use mls_rs_core::{
+    crypto::{SignaturePublicKey, SignatureSecretKey},
+    error::IntoAnyError,
+};
+
+/// Signature scheme of a cipher suite.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+#[cfg_attr(feature = "mock", automock(type Error = crate::mock::TestError;))]
+pub trait SignatureType: Send + Sync {
+    type Error: IntoAnyError + Send + Sync;
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error>;
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error>;
+
+    async fn generate(&self) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error>;
+
+    async fn derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error>;
+}
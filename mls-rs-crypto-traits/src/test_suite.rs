@@ -0,0 +1,403 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Conformance tests for implementations of [`KdfType`], [`AeadType`] and
+//! [`KemType`].
+//!
+//! Each `verify_*` function panics on the first deviation from the expected
+//! behavior, and is meant to be called from the tests of a provider:
+//!
+//! ```ignore
+//! #[test]
+//! fn kdf_conformance() {
+//!     for cs in [CipherSuite::CURVE25519_AES128, CipherSuite::P384_AES256] {
+//!         mls_rs_crypto_traits::test_suite::verify_kdf(&MyKdf::new(cs).unwrap());
+//!     }
+//! }
+//! ```
+//!
+//! Besides the test vectors of RFC 5869 and RFC 9180, the checks cover
+//! zero-length and large inputs. Inputs that some providers reject, such as
+//! an empty plaintext, are only required to either be rejected or to behave
+//! correctly.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use mls_rs_core::crypto::HpkePublicKey;
+
+use crate::{AeadId, AeadType, KdfId, KdfType, KemId, KemType, AES_TAG_LEN};
+
+/// Size of the large inputs given to the primitives.
+pub const LARGE_INPUT_SIZE: usize = 1 << 16;
+
+struct HkdfTestCase {
+    kdf_id: KdfId,
+    ikm: &'static str,
+    salt: &'static str,
+    info: &'static str,
+    prk: &'static str,
+    okm: &'static str,
+}
+
+const IKM_BASIC: &str = "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b";
+const SALT_BASIC: &str = "000102030405060708090a0b0c";
+const INFO_BASIC: &str = "f0f1f2f3f4f5f6f7f8f9";
+
+const IKM_LONG: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                        202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+                        404142434445464748494a4b4c4d4e4f";
+const SALT_LONG: &str = "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+                         808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+                         a0a1a2a3a4a5a6a7a8a9aaabacadaeaf";
+const INFO_LONG: &str = "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecf\
+                         d0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeef\
+                         f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+
+/// Inputs of RFC 5869, Appendix A.1 to A.3. The outputs for SHA-384 and
+/// SHA-512 were computed with the same inputs.
+const HKDF_TEST_CASES: &[HkdfTestCase] = &[
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha256,
+        ikm: IKM_BASIC,
+        salt: SALT_BASIC,
+        info: INFO_BASIC,
+        prk: "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+        okm: "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+              34007208d5b887185865",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha256,
+        ikm: IKM_LONG,
+        salt: SALT_LONG,
+        info: INFO_LONG,
+        prk: "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+        okm: "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+              59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+              cc30c58179ec3e87c14c01d5c1f3434f1d87",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha256,
+        ikm: IKM_BASIC,
+        salt: "",
+        info: "",
+        prk: "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+        okm: "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+              9d201395faa4b61a96c8",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha384,
+        ikm: IKM_BASIC,
+        salt: SALT_BASIC,
+        info: INFO_BASIC,
+        prk: "704b39990779ce1dc548052c7dc39f303570dd13fb39f7acc564680bef80e8de\
+              c70ee9a7e1f3e293ef68eceb072a5ade",
+        okm: "9b5097a86038b805309076a44b3a9f38063e25b516dcbf369f394cfab43685f7\
+              48b6457763e4f0204fc5",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha384,
+        ikm: IKM_LONG,
+        salt: SALT_LONG,
+        info: INFO_LONG,
+        prk: "b319f6831dff9314efb643baa29263b30e4a8d779fe31e9c901efd7de737c85b\
+              62e676d4dc87b0895c6a7dc97b52cebb",
+        okm: "484ca052b8cc724fd1c4ec64d57b4e818c7e25a8e0f4569ed72a6a05fe0649ee\
+              bf69f8d5c832856bf4e4fbc17967d54975324a94987f7f41835817d8994fdbd6\
+              f4c09c5500dca24a56222fea53d8967a8b2e",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha384,
+        ikm: IKM_BASIC,
+        salt: "",
+        info: "",
+        prk: "10e40cf072a4c5626e43dd22c1cf727d4bb140975c9ad0cbc8e45b40068f8f0b\
+              a57cdb598af9dfa6963a96899af047e5",
+        okm: "c8c96e710f89b0d7990bca68bcdec8cf854062e54c73a7abc743fade9b242daa\
+              cc1cea5670415b52849c",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha512,
+        ikm: IKM_BASIC,
+        salt: SALT_BASIC,
+        info: INFO_BASIC,
+        prk: "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26\
+              c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237",
+        okm: "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793\
+              38da362cb8d9f925d7cb",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha512,
+        ikm: IKM_LONG,
+        salt: SALT_LONG,
+        info: INFO_LONG,
+        prk: "35672542907d4e142c00e84499e74e1de08be86535f924e022804ad775dde27e\
+              c86cd1e5b7d178c74489bdbeb30712beb82d4f97416c5a94ea81ebdf3e629e4a",
+        okm: "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1\
+              b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235\
+              f6a2056ce3af1de44d572097a8505d9e7a93",
+    },
+    HkdfTestCase {
+        kdf_id: KdfId::HkdfSha512,
+        ikm: IKM_BASIC,
+        salt: "",
+        info: "",
+        prk: "fd200c4987ac491313bd4a2a13287121247239e11c9ef82802044b66ef357e5b\
+              194498d0682611382348572a7b1611de54764094286320578a863f36562b0df6",
+        okm: "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90f\
+              ff22d04836d0e2343bac",
+    },
+];
+
+struct AeadTestCase {
+    aead_id: AeadId,
+    ciphertext: &'static str,
+}
+
+const AEAD_PLAINTEXT: &[u8] = b"mls-rs provider test kit";
+const AEAD_AAD: &[u8] = b"additional data";
+
+/// Ciphertexts of [`AEAD_PLAINTEXT`] with [`AEAD_AAD`], the key `00 01 02 ...`
+/// and the nonce `00 01 02 ... 0b`.
+const AEAD_TEST_CASES: &[AeadTestCase] = &[
+    AeadTestCase {
+        aead_id: AeadId::Aes128Gcm,
+        ciphertext: "fe00d4e31468d72439bd17e352c60228c7436992738694824124c183be4edfa5\
+                     974f8e6eea33e155",
+    },
+    AeadTestCase {
+        aead_id: AeadId::Aes256Gcm,
+        ciphertext: "2a6ea536b796e26bff2ee1e2d58c0a4df7b3f440d0103608620d6deb2f0b0fae\
+                     184903dcfe525800",
+    },
+    AeadTestCase {
+        aead_id: AeadId::Chacha20Poly1305,
+        ciphertext: "e4977b2d5b648530c5ec499afc787c43bd15c193711fc4cdb0a7b8206ddf27ed\
+                     9123efc08fdc5a03",
+    },
+];
+
+struct KemTestCase {
+    kem_id: KemId,
+    ikm: &'static str,
+    public_key: &'static str,
+}
+
+/// `ikmE` and `pkEm` of RFC 9180, Appendix A.1.1 and A.3.1.
+const KEM_TEST_CASES: &[KemTestCase] = &[
+    KemTestCase {
+        kem_id: KemId::DhKemX25519Sha256,
+        ikm: "7268600d403fce431561aef583ee1613527cff655c1343f29812e66706df3234",
+        public_key: "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431",
+    },
+    KemTestCase {
+        kem_id: KemId::DhKemP256Sha256,
+        ikm: "4270e54ffd08d79d5928020af4686d8f6b7d35dbe470265f1f5aa22816ce860e",
+        public_key: "04a92719c6195d5085104f469a8b9814d5838ff72b60501e2c4466e5e67b325a\
+                     c98536d7b61a1af4b78e5b7f951c0900be863c403ce65c9bfcb9382657222d18c4",
+    },
+];
+
+fn from_hex(data: &str) -> Vec<u8> {
+    hex::decode(data).unwrap()
+}
+
+/// Check an implementation of [`KdfType`] for any of the KDFs of [`KdfId`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_kdf<K: KdfType>(kdf: &K) {
+    let hash_len = kdf.extract_size();
+
+    for test_case in HKDF_TEST_CASES
+        .iter()
+        .filter(|test_case| test_case.kdf_id as u16 == kdf.kdf_id())
+    {
+        let prk = kdf
+            .extract(&from_hex(test_case.salt), &from_hex(test_case.ikm))
+            .await
+            .unwrap();
+
+        assert_eq!(prk, from_hex(test_case.prk));
+
+        let okm = from_hex(test_case.okm);
+
+        let res = kdf
+            .expand(&prk, &from_hex(test_case.info), okm.len())
+            .await
+            .unwrap();
+
+        assert_eq!(res, okm);
+    }
+
+    let prk = kdf.extract(&[], &[1u8; 32]).await.unwrap();
+    assert_eq!(prk.len(), hash_len);
+
+    // A missing salt is the same as a salt of `hash_len` zeros.
+    let zero_salt = kdf.extract(&vec![0u8; hash_len], &[1u8; 32]).await;
+    assert_eq!(zero_salt.unwrap(), prk);
+
+    let large_ikm = vec![1u8; LARGE_INPUT_SIZE];
+    let large_prk = kdf.extract(&prk, &large_ikm).await.unwrap();
+    assert_eq!(large_prk.len(), hash_len);
+
+    // The output of expand is a prefix of any longer output, up to the
+    // maximum of 255 blocks.
+    let longest = kdf.expand(&prk, &[], 255 * hash_len).await.unwrap();
+    assert_eq!(longest.len(), 255 * hash_len);
+
+    for len in [1, hash_len - 1, hash_len, hash_len + 1] {
+        let okm = kdf.expand(&prk, &[], len).await.unwrap();
+        assert_eq!(okm, longest[..len]);
+    }
+
+    let large_info = vec![2u8; LARGE_INPUT_SIZE];
+    let okm = kdf.expand(&prk, &large_info, hash_len).await.unwrap();
+    let other_okm = kdf.expand(&prk, &[], hash_len).await.unwrap();
+    assert_ne!(okm, other_okm);
+}
+
+/// Check an implementation of [`AeadType`] for any of the AEADs of
+/// [`AeadId`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_aead<A: AeadType>(aead: &A) {
+    let key = (0..aead.key_size()).map(|i| i as u8).collect::<Vec<_>>();
+    let nonce = (0..aead.nonce_size()).map(|i| i as u8).collect::<Vec<_>>();
+
+    for test_case in AEAD_TEST_CASES
+        .iter()
+        .filter(|test_case| test_case.aead_id as u16 == aead.aead_id())
+    {
+        let expected = from_hex(test_case.ciphertext);
+
+        let ciphertext = aead
+            .seal(&key, AEAD_PLAINTEXT, Some(AEAD_AAD), &nonce)
+            .await
+            .unwrap();
+
+        assert_eq!(ciphertext, expected);
+
+        let plaintext = aead
+            .open(&key, &expected, Some(AEAD_AAD), &nonce)
+            .await
+            .unwrap();
+
+        assert_eq!(plaintext, AEAD_PLAINTEXT);
+    }
+
+    // An empty AAD is the same as no AAD.
+    let ciphertext = aead.seal(&key, b"message", None, &nonce).await.unwrap();
+
+    let empty_aad = aead.seal(&key, b"message", Some(&[]), &nonce).await;
+    assert_eq!(empty_aad.unwrap(), ciphertext);
+
+    let plaintext = aead.open(&key, &ciphertext, Some(&[]), &nonce).await;
+    assert_eq!(plaintext.unwrap(), b"message");
+
+    // Tampering with the ciphertext, the key, the nonce or the AAD is detected.
+    let mut tampered = ciphertext.clone();
+    tampered[0] ^= 1;
+    let res = aead.open(&key, &tampered, None, &nonce).await;
+    assert!(res.is_err());
+
+    let mut tampered = ciphertext.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let res = aead.open(&key, &tampered, None, &nonce).await;
+    assert!(res.is_err());
+
+    let mut other_key = key.clone();
+    other_key[0] ^= 1;
+    let res = aead.open(&other_key, &ciphertext, None, &nonce).await;
+    assert!(res.is_err());
+
+    let mut other_nonce = nonce.clone();
+    other_nonce[0] ^= 1;
+    let res = aead.open(&key, &ciphertext, None, &other_nonce).await;
+    assert!(res.is_err());
+
+    let res = aead.open(&key, &ciphertext, Some(b"aad"), &nonce).await;
+    assert!(res.is_err());
+
+    let truncated = &ciphertext[..ciphertext.len() - 1];
+    let res = aead.open(&key, truncated, None, &nonce).await;
+    assert!(res.is_err());
+
+    let too_short = [0u8; AES_TAG_LEN];
+    let res = aead.open(&key, &too_short, None, &nonce).await;
+    assert!(res.is_err());
+
+    let res = aead.open(&key, &[], None, &nonce).await;
+    assert!(res.is_err());
+
+    // Inputs of the wrong size are rejected.
+    for len in [0, aead.key_size() - 1, aead.key_size() + 1] {
+        let res = aead.seal(&vec![0u8; len], b"message", None, &nonce).await;
+        assert!(res.is_err());
+    }
+
+    // An empty plaintext is either rejected or encrypted to a tag only.
+    if let Ok(ciphertext) = aead.seal(&key, &[], None, &nonce).await {
+        assert_eq!(ciphertext.len(), AES_TAG_LEN);
+
+        let plaintext = aead.open(&key, &ciphertext, None, &nonce).await;
+        assert_eq!(plaintext.unwrap(), b"");
+    }
+
+    let large = vec![3u8; LARGE_INPUT_SIZE];
+
+    let ciphertext = aead.seal(&key, &large, Some(&large), &nonce).await.unwrap();
+
+    assert_eq!(ciphertext.len(), LARGE_INPUT_SIZE + AES_TAG_LEN);
+
+    let plaintext = aead.open(&key, &ciphertext, Some(&large), &nonce).await;
+    assert_eq!(plaintext.unwrap(), large);
+}
+
+/// Check an implementation of [`KemType`] for any of the KEMs of [`KemId`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_kem<K: KemType>(kem: &K) {
+    for test_case in KEM_TEST_CASES
+        .iter()
+        .filter(|test_case| test_case.kem_id as u16 == kem.kem_id())
+    {
+        let (_, public_key) = kem.derive(&from_hex(test_case.ikm)).await.unwrap();
+        assert_eq!(*public_key, from_hex(test_case.public_key));
+    }
+
+    // Derivation is deterministic and the derived key pair is usable.
+    let (secret_key, public_key) = kem.derive(&[4u8; 64]).await.unwrap();
+    let (other_secret, other_public) = kem.derive(&[4u8; 64]).await.unwrap();
+
+    assert_eq!(secret_key, other_secret);
+    assert_eq!(public_key, other_public);
+    assert!(kem.public_key_validate(&public_key).is_ok());
+
+    let large_ikm = vec![4u8; LARGE_INPUT_SIZE];
+    let (_, large_public) = kem.derive(&large_ikm).await.unwrap();
+    assert_ne!(large_public, public_key);
+
+    let (secret_key, public_key) = kem.generate().await.unwrap();
+    assert!(kem.public_key_validate(&public_key).is_ok());
+
+    let kem_result = kem.encap(&public_key).await.unwrap();
+
+    let shared_secret = kem
+        .decap(&kem_result.enc, &secret_key, &public_key)
+        .await
+        .unwrap();
+
+    assert_eq!(shared_secret, kem_result.shared_secret);
+
+    // Encapsulation is randomized.
+    let other_result = kem.encap(&public_key).await.unwrap();
+    assert_ne!(other_result.enc, kem_result.enc);
+    assert_ne!(other_result.shared_secret, kem_result.shared_secret);
+
+    // Malformed public keys are rejected.
+    let empty = HpkePublicKey::from(Vec::new());
+    assert!(kem.public_key_validate(&empty).is_err());
+    let res = kem.encap(&empty).await;
+    assert!(res.is_err());
+
+    let truncated = HpkePublicKey::from(public_key[..public_key.len() - 1].to_vec());
+    assert!(kem.public_key_validate(&truncated).is_err());
+}
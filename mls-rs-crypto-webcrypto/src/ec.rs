@@ -2,9 +2,158 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+#[cfg(feature = "curve25519")]
+mod curve25519;
 mod der_private_key;
 mod ecdh;
 mod ecdsa;
 
+use mls_rs_core::crypto::{
+    CipherSuite, HpkePublicKey, HpkeSecretKey, SignaturePublicKey, SignatureSecretKey,
+};
+use mls_rs_crypto_traits::DhType;
+
+use crate::CryptoError;
+
 pub(crate) use ecdh::Ecdh;
 pub(crate) use ecdsa::EcSigner;
+
+#[cfg(feature = "curve25519")]
+pub(crate) use curve25519::{Ed25519Signer, X25519};
+
+/// DH backend for HPKE. NIST curves are delegated to SubtleCrypto.
+#[derive(Clone)]
+pub(crate) enum Dh {
+    Subtle(Ecdh),
+    #[cfg(feature = "curve25519")]
+    X25519(X25519),
+}
+
+impl Dh {
+    pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        match cipher_suite {
+            #[cfg(feature = "curve25519")]
+            CipherSuite::CURVE25519_AES128 => Some(Self::X25519(X25519)),
+            _ => Ecdh::new(cipher_suite).map(Self::Subtle),
+        }
+    }
+}
+
+#[maybe_async::must_be_async(?Send)]
+impl DhType for Dh {
+    type Error = CryptoError;
+
+    async fn dh(
+        &self,
+        secret_key: &HpkeSecretKey,
+        public_key: &HpkePublicKey,
+    ) -> Result<Vec<u8>, Self::Error> {
+        match self {
+            Self::Subtle(dh) => DhType::dh(dh, secret_key, public_key).await,
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => DhType::dh(dh, secret_key, public_key).await,
+        }
+    }
+
+    async fn generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        match self {
+            Self::Subtle(dh) => DhType::generate(dh).await,
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => DhType::generate(dh).await,
+        }
+    }
+
+    async fn to_public(&self, secret_key: &HpkeSecretKey) -> Result<HpkePublicKey, Self::Error> {
+        match self {
+            Self::Subtle(dh) => DhType::to_public(dh, secret_key).await,
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => DhType::to_public(dh, secret_key).await,
+        }
+    }
+
+    fn bitmask_for_rejection_sampling(&self) -> Option<u8> {
+        match self {
+            Self::Subtle(dh) => dh.bitmask_for_rejection_sampling(),
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => dh.bitmask_for_rejection_sampling(),
+        }
+    }
+
+    fn secret_key_size(&self) -> usize {
+        match self {
+            Self::Subtle(dh) => dh.secret_key_size(),
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => dh.secret_key_size(),
+        }
+    }
+
+    fn public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        match self {
+            Self::Subtle(dh) => dh.public_key_validate(key),
+            #[cfg(feature = "curve25519")]
+            Self::X25519(dh) => dh.public_key_validate(key),
+        }
+    }
+}
+
+/// Signature backend. ECDSA is delegated to SubtleCrypto.
+#[derive(Clone, Debug)]
+pub(crate) enum Signer {
+    Subtle(EcSigner),
+    #[cfg(feature = "curve25519")]
+    Ed25519(Ed25519Signer),
+}
+
+impl Signer {
+    pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
+        match cipher_suite {
+            #[cfg(feature = "curve25519")]
+            CipherSuite::CURVE25519_AES128 => Some(Self::Ed25519(Ed25519Signer)),
+            _ => EcSigner::new(cipher_suite).map(Self::Subtle),
+        }
+    }
+
+    pub async fn generate(&self) -> Result<(SignatureSecretKey, SignaturePublicKey), CryptoError> {
+        match self {
+            Self::Subtle(signer) => signer.generate().await,
+            #[cfg(feature = "curve25519")]
+            Self::Ed25519(signer) => signer.generate(),
+        }
+    }
+
+    pub async fn sign(
+        &self,
+        key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        match self {
+            Self::Subtle(signer) => signer.sign(key, data).await,
+            #[cfg(feature = "curve25519")]
+            Self::Ed25519(signer) => signer.sign(key, data),
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        key: &SignaturePublicKey,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), CryptoError> {
+        match self {
+            Self::Subtle(signer) => signer.verify(key, data, signature).await,
+            #[cfg(feature = "curve25519")]
+            Self::Ed25519(signer) => signer.verify(key, data, signature),
+        }
+    }
+
+    pub fn derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, CryptoError> {
+        match self {
+            Self::Subtle(signer) => signer.derive_public(secret_key),
+            #[cfg(feature = "curve25519")]
+            Self::Ed25519(signer) => signer.derive_public(secret_key),
+        }
+    }
+}
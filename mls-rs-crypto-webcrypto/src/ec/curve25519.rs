@@ -0,0 +1,165 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Curve25519 is not uniformly available through SubtleCrypto, so X25519 and
+//! Ed25519 are computed in wasm. Everything else (AES-GCM, HKDF) for the
+//! corresponding cipher suites still goes through the browser.
+
+use ed25519_dalek::{Signer, Verifier};
+use mls_rs_core::crypto::{HpkePublicKey, HpkeSecretKey, SignaturePublicKey, SignatureSecretKey};
+use mls_rs_crypto_traits::{Curve, DhType};
+use zeroize::Zeroizing;
+
+use crate::{random_bytes, CryptoError};
+
+const KEY_SIZE: usize = 32;
+
+fn key_array(bytes: &[u8]) -> Result<[u8; KEY_SIZE], CryptoError> {
+    bytes.try_into().map_err(|_| CryptoError::WrongKeyLength)
+}
+
+fn random_seed() -> Result<Zeroizing<[u8; KEY_SIZE]>, CryptoError> {
+    let mut seed = Zeroizing::new([0u8; KEY_SIZE]);
+    random_bytes(seed.as_mut())?;
+    Ok(seed)
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct X25519;
+
+impl X25519 {
+    fn secret(secret_key: &[u8]) -> Result<x25519_dalek::StaticSecret, CryptoError> {
+        Ok(x25519_dalek::StaticSecret::from(key_array(secret_key)?))
+    }
+}
+
+#[maybe_async::must_be_async(?Send)]
+impl DhType for X25519 {
+    type Error = CryptoError;
+
+    async fn dh(
+        &self,
+        secret_key: &HpkeSecretKey,
+        public_key: &HpkePublicKey,
+    ) -> Result<Vec<u8>, Self::Error> {
+        let secret = Self::secret(secret_key)?;
+        let public = x25519_dalek::PublicKey::from(key_array(public_key)?);
+
+        Ok(secret.diffie_hellman(&public).to_bytes().to_vec())
+    }
+
+    async fn generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        let secret = x25519_dalek::StaticSecret::from(*random_seed()?);
+        let public = x25519_dalek::PublicKey::from(&secret);
+
+        Ok((
+            secret.to_bytes().to_vec().into(),
+            public.to_bytes().to_vec().into(),
+        ))
+    }
+
+    async fn to_public(&self, secret_key: &HpkeSecretKey) -> Result<HpkePublicKey, Self::Error> {
+        let secret = Self::secret(secret_key)?;
+        let public = x25519_dalek::PublicKey::from(&secret);
+
+        Ok(public.to_bytes().to_vec().into())
+    }
+
+    fn bitmask_for_rejection_sampling(&self) -> Option<u8> {
+        Curve::X25519.curve_bitmask()
+    }
+
+    fn secret_key_size(&self) -> usize {
+        Curve::X25519.secret_key_size()
+    }
+
+    fn public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        key_array(key).map(|_| ())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Ed25519Signer;
+
+impl Ed25519Signer {
+    // Secret keys use the same 64 byte keypair encoding as the other providers so that
+    // identities can be moved between them.
+    fn signing_key(secret_key: &[u8]) -> Result<ed25519_dalek::SigningKey, CryptoError> {
+        let bytes: &[u8; 64] = secret_key
+            .try_into()
+            .map_err(|_| CryptoError::WrongKeyLength)?;
+
+        ed25519_dalek::SigningKey::from_keypair_bytes(bytes)
+            .map_err(|_| CryptoError::CouldNotComputePublicKey)
+    }
+
+    pub fn generate(&self) -> Result<(SignatureSecretKey, SignaturePublicKey), CryptoError> {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&random_seed()?);
+        let public = signing_key.verifying_key().to_bytes().to_vec();
+
+        Ok((
+            signing_key.to_keypair_bytes().to_vec().into(),
+            public.into(),
+        ))
+    }
+
+    pub fn sign(&self, key: &SignatureSecretKey, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        Ok(Self::signing_key(key)?.sign(data).to_bytes().to_vec())
+    }
+
+    pub fn verify(
+        &self,
+        key: &SignaturePublicKey,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), CryptoError> {
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&key_array(key)?)
+            .map_err(|_| CryptoError::InvalidSignature)?;
+
+        let signature = ed25519_dalek::Signature::from_slice(signature)
+            .map_err(|_| CryptoError::InvalidSignature)?;
+
+        key.verify(data, &signature)
+            .map_err(|_| CryptoError::InvalidSignature)
+    }
+
+    pub fn derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, CryptoError> {
+        let signing_key = Self::signing_key(secret_key)?;
+        Ok(signing_key.verifying_key().to_bytes().to_vec().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mls_rs_crypto_traits::DhType;
+
+    use super::{Ed25519Signer, X25519};
+
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    async fn x25519_round_trip() {
+        let (alice_secret, alice_public) = X25519.generate().await.unwrap();
+        let (bob_secret, bob_public) = X25519.generate().await.unwrap();
+
+        assert_eq!(X25519.to_public(&alice_secret).await.unwrap(), alice_public);
+
+        assert_eq!(
+            X25519.dh(&alice_secret, &bob_public).await.unwrap(),
+            X25519.dh(&bob_secret, &alice_public).await.unwrap()
+        );
+    }
+
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn ed25519_round_trip() {
+        let signer = Ed25519Signer;
+        let (secret, public) = signer.generate().unwrap();
+        let signature = signer.sign(&secret, b"data").unwrap();
+
+        signer.verify(&public, b"data", &signature).unwrap();
+        assert!(signer.verify(&public, b"other", &signature).is_err());
+        assert_eq!(signer.derive_public(&secret).unwrap(), public);
+    }
+}
@@ -31,7 +31,7 @@ use zeroize::Zeroizing;
 
 use crate::{
     aead::Aead,
-    ec::{EcSigner, Ecdh},
+    ec::{Dh, Signer},
     hkdf::Hkdf,
 };
 
@@ -69,6 +69,20 @@ pub(crate) fn get_crypto() -> Result<SubtleCrypto, CryptoError> {
         .subtle())
 }
 
+pub(crate) fn random_bytes(out: &mut [u8]) -> Result<(), CryptoError> {
+    web_sys::window()
+        .ok_or(CryptoError::WindowNotFound)?
+        .crypto()?
+        .get_random_values_with_u8_array(out)?;
+
+    Ok(())
+}
+
+/// Crypto provider backed by the browser's SubtleCrypto API.
+///
+/// AES-GCM, HKDF, ECDH and ECDSA over the NIST curves are delegated to
+/// SubtleCrypto. With the `curve25519` feature enabled, X25519 and Ed25519 are
+/// computed in wasm so that `CURVE25519_AES128` can be offered as well.
 #[derive(Clone, Default, Debug)]
 pub struct WebCryptoProvider;
 
@@ -79,6 +93,8 @@ impl WebCryptoProvider {
 
     pub fn all_supported_cipher_suites() -> Vec<CipherSuite> {
         vec![
+            #[cfg(feature = "curve25519")]
+            CipherSuite::CURVE25519_AES128,
             CipherSuite::P256_AES128,
             CipherSuite::P384_AES256,
             CipherSuite::P521_AES256,
@@ -90,8 +106,8 @@ impl WebCryptoProvider {
 pub struct WebCryptoCipherSuite {
     aead: Aead,
     hkdf: Hkdf,
-    ec_signer: EcSigner,
-    hpke: Hpke<DhKem<Ecdh, Hkdf>, Hkdf, Aead>,
+    ec_signer: Signer,
+    hpke: Hpke<DhKem<Dh, Hkdf>, Hkdf, Aead>,
     cipher_suite: CipherSuite,
 }
 
@@ -99,7 +115,7 @@ impl WebCryptoCipherSuite {
     pub fn new(cipher_suite: CipherSuite) -> Option<Self> {
         let kem_id = KemId::new(cipher_suite)?;
         let hkdf = Hkdf::new(cipher_suite)?;
-        let dh = Ecdh::new(cipher_suite)?;
+        let dh = Dh::new(cipher_suite)?;
 
         let dhkem = DhKem::new(dh, hkdf.clone(), kem_id as u16, kem_id.n_secret());
         let aead = Aead::new(cipher_suite)?;
@@ -107,7 +123,7 @@ impl WebCryptoCipherSuite {
         Some(Self {
             aead: aead.clone(),
             hkdf: hkdf.clone(),
-            ec_signer: EcSigner::new(cipher_suite)?,
+            ec_signer: Signer::new(cipher_suite)?,
             hpke: Hpke::new(dhkem, hkdf, Some(aead)),
             cipher_suite,
         })
@@ -271,12 +287,7 @@ impl CipherSuiteProvider for WebCryptoCipherSuite {
     }
 
     fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
-        web_sys::window()
-            .ok_or(CryptoError::WindowNotFound)?
-            .crypto()?
-            .get_random_values_with_u8_array(out)?;
-
-        Ok(())
+        random_bytes(out)
     }
 
     async fn signature_key_generate(
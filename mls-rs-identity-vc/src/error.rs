@@ -0,0 +1,50 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::string::String;
+use mls_rs_core::{error::AnyError, identity::CredentialType};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum VcIdentityError {
+    #[cfg_attr(feature = "std", error("unsupported credential type {0:?}"))]
+    UnsupportedCredentialType(CredentialType),
+    #[cfg_attr(feature = "std", error("credential is not a valid SD-JWT"))]
+    InvalidEncoding,
+    #[cfg_attr(feature = "std", error("malformed disclosure"))]
+    InvalidDisclosure,
+    #[cfg_attr(feature = "std", error("disclosure is present more than once"))]
+    DuplicateDisclosure,
+    #[cfg_attr(feature = "std", error("disclosure is not referenced by the issuer"))]
+    UnreferencedDisclosure,
+    #[cfg_attr(feature = "std", error("disclosed claim {0} is already present"))]
+    DuplicateClaim(String),
+    #[cfg_attr(feature = "std", error("credential is expired"))]
+    Expired,
+    #[cfg_attr(feature = "std", error("credential is not yet valid"))]
+    NotYetValid,
+    #[cfg_attr(feature = "std", error("credential is not bound to a holder key"))]
+    MissingHolderBinding,
+    #[cfg_attr(
+        feature = "std",
+        error("signing identity public key does not match the credential holder key")
+    )]
+    SignatureKeyMismatch,
+    #[cfg_attr(feature = "std", error("required claim {0} is missing"))]
+    MissingClaim(String),
+    #[cfg_attr(
+        feature = "std",
+        error("credential does not contain an identity claim")
+    )]
+    MissingIdentity,
+    #[cfg_attr(feature = "std", error(transparent))]
+    VerifierError(AnyError),
+}
+
+impl mls_rs_core::error::IntoAnyError for VcIdentityError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Verifiable credentials as MLS credentials.
+//!
+//! This crate provides an [`IdentityProvider`](mls_rs_core::identity::IdentityProvider)
+//! for custom MLS credentials carrying an [SD-JWT] in compact serialization.
+//! W3C Verifiable Credentials secured as JWTs are SD-JWTs without
+//! disclosures and are supported as well.
+//!
+//! Cryptographic operations are delegated to a [`VcVerifier`], which
+//! verifies the issuer signature, computes disclosure digests and checks
+//! the binding of the credential to the MLS signature key of its holder.
+//!
+//! [SD-JWT]: https://datatracker.ietf.org/doc/draft-ietf-oauth-selective-disclosure-jwt/
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+mod error;
+mod provider;
+mod sd_jwt;
+mod traits;
+
+pub use error::*;
+pub use provider::*;
+pub use sd_jwt::*;
+pub use traits::*;
+
+pub use serde_json::{Map, Value};
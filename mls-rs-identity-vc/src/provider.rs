@@ -0,0 +1,417 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{string::String, vec, vec::Vec};
+use mls_rs_core::{
+    error::IntoAnyError,
+    extension::ExtensionList,
+    identity::{Credential, CredentialType, IdentityProvider, SigningIdentity},
+    time::MlsTime,
+};
+use serde_json::{Map, Value};
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+use crate::{SdJwt, VcIdentityError, VcVerifier};
+
+/// Credential type of verifiable credentials, taken from the private use
+/// range.
+pub const VC_CREDENTIAL_TYPE: CredentialType = CredentialType::new(0xF1C0);
+
+/// Identity provider for custom credentials containing a verifiable
+/// credential, in the form of an SD-JWT in compact serialization.
+///
+/// A credential is valid if:
+/// * the issuer signature is valid according to the [`VcVerifier`],
+/// * every disclosure is referenced by the issuer,
+/// * the validation time, if any, is within the `nbf` and `exp` claims,
+/// * the `cnf` claim designates the signature key of the signing identity,
+/// * every [required claim](Self::with_required_claim) is present after
+///   applying the disclosures.
+///
+/// The identity of the holder is the `sub` claim or, for W3C Verifiable
+/// Credentials, the `id` of the `credentialSubject`, unless another claim is
+/// configured with [`with_identity_claim`](Self::with_identity_claim).
+#[derive(Clone, Debug)]
+pub struct VcIdentityProvider<V> {
+    verifier: V,
+    credential_type: CredentialType,
+    identity_claim: Option<String>,
+    required_claims: Vec<String>,
+}
+
+impl<V: VcVerifier> VcIdentityProvider<V> {
+    /// Create a provider for credentials of type [`VC_CREDENTIAL_TYPE`].
+    pub fn new(verifier: V) -> Self {
+        Self {
+            verifier,
+            credential_type: VC_CREDENTIAL_TYPE,
+            identity_claim: None,
+            required_claims: Vec::new(),
+        }
+    }
+
+    /// Handle credentials of type `credential_type` instead of
+    /// [`VC_CREDENTIAL_TYPE`].
+    pub fn with_credential_type(self, credential_type: CredentialType) -> Self {
+        Self {
+            credential_type,
+            ..self
+        }
+    }
+
+    /// Use the top-level claim `claim` as the identity of the holder.
+    pub fn with_identity_claim(self, claim: impl Into<String>) -> Self {
+        Self {
+            identity_claim: Some(claim.into()),
+            ..self
+        }
+    }
+
+    /// Require the top-level claim `claim` to be present, for instance to
+    /// require the holder to disclose it.
+    pub fn with_required_claim(mut self, claim: impl Into<String>) -> Self {
+        self.required_claims.push(claim.into());
+        self
+    }
+
+    /// Verify the credential of `signing_identity` and return its claims,
+    /// with the disclosures applied.
+    ///
+    /// Expiry is only checked if a `timestamp` is provided.
+    pub fn verify(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+    ) -> Result<Map<String, Value>, VcIdentityError> {
+        let sd_jwt = self.sd_jwt(&signing_identity.credential)?;
+
+        self.verifier
+            .verify_issuer_signature(&sd_jwt)
+            .map_err(|e| VcIdentityError::VerifierError(e.into_any_error()))?;
+
+        let sd_alg = sd_jwt.sd_alg();
+
+        let claims = sd_jwt.disclosed_claims(|data| {
+            self.verifier
+                .digest(sd_alg, data)
+                .map_err(|e| VcIdentityError::VerifierError(e.into_any_error()))
+        })?;
+
+        if let Some(time) = timestamp.map(|t| t.seconds_since_epoch()) {
+            if matches!(numeric_claim(&claims, "exp"), Some(exp) if time >= exp) {
+                return Err(VcIdentityError::Expired);
+            }
+
+            if matches!(numeric_claim(&claims, "nbf"), Some(nbf) if time < nbf) {
+                return Err(VcIdentityError::NotYetValid);
+            }
+        }
+
+        let cnf = claims
+            .get("cnf")
+            .ok_or(VcIdentityError::MissingHolderBinding)?;
+
+        let bound = self
+            .verifier
+            .holder_key_matches(cnf, &signing_identity.signature_key)
+            .map_err(|e| VcIdentityError::VerifierError(e.into_any_error()))?;
+
+        if !bound {
+            return Err(VcIdentityError::SignatureKeyMismatch);
+        }
+
+        if let Some(missing) = self
+            .required_claims
+            .iter()
+            .find(|claim| !claims.contains_key(claim.as_str()))
+        {
+            return Err(VcIdentityError::MissingClaim(missing.clone()));
+        }
+
+        Ok(claims)
+    }
+
+    /// Produce a unique identity value for the holder of the credential of
+    /// `signing_identity`.
+    pub fn identity(&self, signing_identity: &SigningIdentity) -> Result<Vec<u8>, VcIdentityError> {
+        let claims = self.verify(signing_identity, None)?;
+
+        let identity = match &self.identity_claim {
+            Some(claim) => claims.get(claim),
+            None => claims.get("sub").or_else(|| {
+                claims
+                    .get("vc")
+                    .and_then(|vc| vc.get("credentialSubject"))
+                    .and_then(|subject| subject.get("id"))
+            }),
+        };
+
+        identity
+            .and_then(Value::as_str)
+            .map(|identity| identity.as_bytes().to_vec())
+            .ok_or(VcIdentityError::MissingIdentity)
+    }
+
+    /// Supported credential types.
+    pub fn supported_types(&self) -> Vec<CredentialType> {
+        vec![self.credential_type]
+    }
+
+    fn sd_jwt(&self, credential: &Credential) -> Result<SdJwt, VcIdentityError> {
+        let custom = match credential {
+            Credential::Custom(custom) if custom.credential_type == self.credential_type => custom,
+            _ => {
+                return Err(VcIdentityError::UnsupportedCredentialType(
+                    credential.credential_type(),
+                ))
+            }
+        };
+
+        let serialized =
+            core::str::from_utf8(&custom.data).map_err(|_| VcIdentityError::InvalidEncoding)?;
+
+        SdJwt::parse(serialized)
+    }
+}
+
+fn numeric_claim(claims: &Map<String, Value>, name: &str) -> Option<u64> {
+    claims.get(name).and_then(Value::as_u64)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<V> IdentityProvider for VcIdentityProvider<V>
+where
+    V: VcVerifier + Send + Sync,
+{
+    type Error = VcIdentityError;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        _extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        self.verify(signing_identity, timestamp).map(|_| ())
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        _extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        self.verify(signing_identity, timestamp).map(|_| ())
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        _extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.identity(signing_identity)
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        _extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        Ok(self.identity(predecessor)? == self.identity(successor)?)
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        self.supported_types()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use mls_rs_core::{
+        crypto::SignaturePublicKey,
+        identity::{BasicCredential, Credential, CustomCredential, SigningIdentity},
+        time::MlsTime,
+    };
+    use serde_json::{json, Value};
+
+    use crate::{
+        sd_jwt::test_utils::{disclosure_digest, encode_disclosure, encode_sd_jwt},
+        SdJwt, VcIdentityError, VcIdentityProvider, VcVerifier, VC_CREDENTIAL_TYPE,
+    };
+
+    #[derive(Debug)]
+    struct TestError;
+
+    impl mls_rs_core::error::IntoAnyError for TestError {}
+
+    // The issuer signs by reversing the signing input, digests are the data
+    // itself and the holder key is the base64url `key` member of `cnf`.
+    struct TestVerifier;
+
+    impl VcVerifier for TestVerifier {
+        type Error = TestError;
+
+        fn verify_issuer_signature(&self, sd_jwt: &SdJwt) -> Result<(), Self::Error> {
+            let expected = sd_jwt.signing_input().iter().rev().copied();
+
+            expected
+                .eq(sd_jwt.signature().iter().copied())
+                .then_some(())
+                .ok_or(TestError)
+        }
+
+        fn digest(&self, algorithm: &str, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+            (algorithm == "sha-256")
+                .then(|| data.to_vec())
+                .ok_or(TestError)
+        }
+
+        fn holder_key_matches(
+            &self,
+            cnf: &Value,
+            signature_key: &SignaturePublicKey,
+        ) -> Result<bool, Self::Error> {
+            Ok(cnf["key"] == URL_SAFE_NO_PAD.encode(signature_key.as_bytes()))
+        }
+    }
+
+    fn holder_key() -> SignaturePublicKey {
+        SignaturePublicKey::new(b"holder key".to_vec())
+    }
+
+    fn signing_identity(serialized: String) -> SigningIdentity {
+        let credential = CustomCredential::new(VC_CREDENTIAL_TYPE, serialized.into_bytes());
+        SigningIdentity::new(Credential::Custom(credential), holder_key())
+    }
+
+    fn credential(extra_claims: Value, disclosures: &[String]) -> SigningIdentity {
+        let mut claims = json!({
+            "iss": "https://issuer.example.com",
+            "sub": "alice",
+            "nbf": 1000,
+            "exp": 2000,
+            "cnf": { "key": URL_SAFE_NO_PAD.encode(holder_key().as_bytes()) },
+        });
+
+        if let (Value::Object(claims), Value::Object(extra)) = (&mut claims, extra_claims) {
+            claims.extend(extra);
+        }
+
+        signing_identity(encode_sd_jwt(claims, disclosures))
+    }
+
+    fn provider() -> VcIdentityProvider<TestVerifier> {
+        VcIdentityProvider::new(TestVerifier)
+    }
+
+    #[test]
+    fn valid_credential_is_accepted() {
+        let email = encode_disclosure(json!(["salt", "email", "alice@example.com"]));
+        let extra = json!({ "_sd": [disclosure_digest(&email)] });
+
+        let identity = credential(extra, &[email]);
+        let provider = provider().with_required_claim("email");
+
+        let claims = provider
+            .verify(&identity, Some(MlsTime::from(1500)))
+            .unwrap();
+
+        assert_eq!(claims["email"], "alice@example.com");
+        assert_eq!(provider.identity(&identity).unwrap(), b"alice");
+    }
+
+    #[test]
+    fn undisclosed_required_claim_is_rejected() {
+        let email = encode_disclosure(json!(["salt", "email", "alice@example.com"]));
+        let extra = json!({ "_sd": [disclosure_digest(&email)] });
+
+        let identity = credential(extra, &[]);
+        let res = provider()
+            .with_required_claim("email")
+            .verify(&identity, None);
+
+        assert_matches!(res, Err(VcIdentityError::MissingClaim(claim)) if claim == "email");
+    }
+
+    #[test]
+    fn credential_validity_period_is_enforced() {
+        let identity = credential(json!({}), &[]);
+
+        let res = provider().verify(&identity, Some(MlsTime::from(2000)));
+        assert_matches!(res, Err(VcIdentityError::Expired));
+
+        let res = provider().verify(&identity, Some(MlsTime::from(999)));
+        assert_matches!(res, Err(VcIdentityError::NotYetValid));
+
+        provider().verify(&identity, None).unwrap();
+    }
+
+    #[test]
+    fn credential_must_be_bound_to_signature_key() {
+        let mut identity = credential(json!({}), &[]);
+        identity.signature_key = SignaturePublicKey::new(b"other key".to_vec());
+
+        let res = provider().verify(&identity, None);
+        assert_matches!(res, Err(VcIdentityError::SignatureKeyMismatch));
+    }
+
+    #[test]
+    fn tampered_credential_is_rejected() {
+        let identity = credential(json!({}), &[]);
+
+        let Credential::Custom(custom) = &identity.credential else {
+            unreachable!()
+        };
+
+        let serialized = String::from_utf8(custom.data.clone()).unwrap();
+        let (signed, signature) = serialized.rsplit_once('.').unwrap();
+
+        let mut claims = SdJwt::parse(&serialized).unwrap().claims().clone();
+        claims.insert("sub".into(), "mallory".into());
+
+        let payload = URL_SAFE_NO_PAD.encode(Value::Object(claims).to_string());
+        let header = signed.split('.').next().unwrap();
+
+        let tampered = signing_identity(format!("{header}.{payload}.{signature}"));
+
+        let res = provider().verify(&tampered, None);
+        assert_matches!(res, Err(VcIdentityError::VerifierError(_)));
+    }
+
+    #[test]
+    fn w3c_credential_subject_is_identity() {
+        let claims = json!({
+            "iss": "https://issuer.example.com",
+            "cnf": { "key": URL_SAFE_NO_PAD.encode(holder_key().as_bytes()) },
+            "vc": {
+                "type": ["VerifiableCredential"],
+                "credentialSubject": { "id": "did:example:alice" },
+            },
+        });
+
+        // A W3C credential secured as a JWT, without disclosures.
+        let serialized = encode_sd_jwt(claims, &[]);
+        let identity = signing_identity(serialized.trim_end_matches('~').to_string());
+
+        assert_eq!(
+            provider().identity(&identity).unwrap(),
+            b"did:example:alice"
+        );
+    }
+
+    #[test]
+    fn other_credential_types_are_rejected() {
+        let basic = BasicCredential::new(b"alice".to_vec()).into_credential();
+        let identity = SigningIdentity::new(basic, holder_key());
+
+        let res = provider().verify(&identity, None);
+        assert_matches!(res, Err(VcIdentityError::UnsupportedCredentialType(_)));
+    }
+}
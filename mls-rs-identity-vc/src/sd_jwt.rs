@@ -0,0 +1,472 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec::Vec,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{Map, Value};
+
+use crate::VcIdentityError;
+
+/// Hash algorithm of disclosure digests when `_sd_alg` is absent.
+pub const DEFAULT_SD_ALG: &str = "sha-256";
+
+const SD_CLAIM: &str = "_sd";
+const SD_ALG_CLAIM: &str = "_sd_alg";
+const ARRAY_ELEMENT_CLAIM: &str = "...";
+
+/// Disclosure of a selectively disclosable claim or array element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disclosure {
+    encoded: String,
+    salt: String,
+    name: Option<String>,
+    value: Value,
+}
+
+impl Disclosure {
+    /// Decode a base64url encoded disclosure.
+    pub fn parse(encoded: &str) -> Result<Self, VcIdentityError> {
+        let decoded = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| VcIdentityError::InvalidDisclosure)?;
+
+        let Ok(Value::Array(items)) = serde_json::from_slice(&decoded) else {
+            return Err(VcIdentityError::InvalidDisclosure);
+        };
+
+        let (salt, name, value) = match <[Value; 3]>::try_from(items) {
+            Ok([Value::String(salt), Value::String(name), value]) => (salt, Some(name), value),
+            Ok(_) => return Err(VcIdentityError::InvalidDisclosure),
+            Err(items) => match <[Value; 2]>::try_from(items) {
+                Ok([Value::String(salt), value]) => (salt, None, value),
+                _ => return Err(VcIdentityError::InvalidDisclosure),
+            },
+        };
+
+        if matches!(name.as_deref(), Some(SD_CLAIM | ARRAY_ELEMENT_CLAIM)) {
+            return Err(VcIdentityError::InvalidDisclosure);
+        }
+
+        Ok(Self {
+            encoded: encoded.to_string(),
+            salt,
+            name,
+            value,
+        })
+    }
+
+    /// Disclosure as found in the SD-JWT, over which its digest is computed.
+    pub fn encoded(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Salt of the disclosure.
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    /// Name of the disclosed claim, or `None` for an array element.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Disclosed value.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+/// SD-JWT in compact serialization, as issued and presented by its holder.
+///
+/// A key binding JWT at the end of the serialization is kept but not
+/// verified, since possession of the holder key is proven by the MLS
+/// signatures of the holder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdJwt {
+    header: Map<String, Value>,
+    claims: Map<String, Value>,
+    signing_input: String,
+    signature: Vec<u8>,
+    disclosures: Vec<Disclosure>,
+    key_binding_jwt: Option<String>,
+}
+
+impl SdJwt {
+    /// Parse the compact serialization of an SD-JWT, or of a plain JWT.
+    pub fn parse(serialized: &str) -> Result<Self, VcIdentityError> {
+        let mut parts = serialized.split('~');
+        let jwt = parts.next().unwrap_or_default();
+        let mut disclosures = parts.collect::<Vec<_>>();
+
+        let key_binding_jwt = disclosures
+            .pop()
+            .filter(|kb_jwt| !kb_jwt.is_empty())
+            .map(ToString::to_string);
+
+        let disclosures = disclosures
+            .into_iter()
+            .map(Disclosure::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut jwt_parts = jwt.split('.');
+
+        let (Some(header), Some(payload), Some(signature), None) = (
+            jwt_parts.next(),
+            jwt_parts.next(),
+            jwt_parts.next(),
+            jwt_parts.next(),
+        ) else {
+            return Err(VcIdentityError::InvalidEncoding);
+        };
+
+        let signing_input = jwt[..header.len() + 1 + payload.len()].to_string();
+
+        Ok(Self {
+            header: decode_object(header)?,
+            claims: decode_object(payload)?,
+            signing_input,
+            signature: URL_SAFE_NO_PAD
+                .decode(signature)
+                .map_err(|_| VcIdentityError::InvalidEncoding)?,
+            disclosures,
+            key_binding_jwt,
+        })
+    }
+
+    /// JOSE header of the issuer-signed JWT.
+    pub fn header(&self) -> &Map<String, Value> {
+        &self.header
+    }
+
+    /// Claims signed by the issuer, without the disclosures.
+    pub fn claims(&self) -> &Map<String, Value> {
+        &self.claims
+    }
+
+    /// Data signed by the issuer.
+    pub fn signing_input(&self) -> &[u8] {
+        self.signing_input.as_bytes()
+    }
+
+    /// Signature of the issuer.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Disclosures selected by the holder.
+    pub fn disclosures(&self) -> &[Disclosure] {
+        &self.disclosures
+    }
+
+    /// Key binding JWT, if any.
+    pub fn key_binding_jwt(&self) -> Option<&str> {
+        self.key_binding_jwt.as_deref()
+    }
+
+    /// Hash algorithm of the disclosure digests.
+    pub fn sd_alg(&self) -> &str {
+        self.claims
+            .get(SD_ALG_CLAIM)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_SD_ALG)
+    }
+
+    /// Claims signed by the issuer with the disclosures applied.
+    ///
+    /// `digest` hashes data with the [`sd_alg`](Self::sd_alg) algorithm. Every
+    /// disclosure must be referenced by a digest signed by the issuer, and
+    /// at most once. Digests without a matching disclosure are ignored.
+    pub fn disclosed_claims<F>(&self, mut digest: F) -> Result<Map<String, Value>, VcIdentityError>
+    where
+        F: FnMut(&[u8]) -> Result<Vec<u8>, VcIdentityError>,
+    {
+        let mut disclosures = BTreeMap::new();
+
+        for disclosure in &self.disclosures {
+            let digest = URL_SAFE_NO_PAD.encode(digest(disclosure.encoded.as_bytes())?);
+
+            if disclosures.insert(digest, disclosure).is_some() {
+                return Err(VcIdentityError::DuplicateDisclosure);
+            }
+        }
+
+        let mut resolver = DisclosureResolver {
+            disclosures,
+            used: BTreeSet::new(),
+        };
+
+        let mut claims = self.claims.clone();
+        claims.remove(SD_ALG_CLAIM);
+        resolver.resolve_object(&mut claims)?;
+
+        if resolver.used.len() != resolver.disclosures.len() {
+            return Err(VcIdentityError::UnreferencedDisclosure);
+        }
+
+        Ok(claims)
+    }
+}
+
+fn decode_object(encoded: &str) -> Result<Map<String, Value>, VcIdentityError> {
+    let decoded = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| VcIdentityError::InvalidEncoding)?;
+
+    match serde_json::from_slice(&decoded) {
+        Ok(Value::Object(object)) => Ok(object),
+        _ => Err(VcIdentityError::InvalidEncoding),
+    }
+}
+
+struct DisclosureResolver<'a> {
+    disclosures: BTreeMap<String, &'a Disclosure>,
+    used: BTreeSet<String>,
+}
+
+impl<'a> DisclosureResolver<'a> {
+    fn take(&mut self, digest: &str) -> Result<Option<&'a Disclosure>, VcIdentityError> {
+        let Some(disclosure) = self.disclosures.get(digest).copied() else {
+            return Ok(None);
+        };
+
+        if !self.used.insert(digest.to_string()) {
+            return Err(VcIdentityError::DuplicateDisclosure);
+        }
+
+        Ok(Some(disclosure))
+    }
+
+    fn resolve_object(&mut self, object: &mut Map<String, Value>) -> Result<(), VcIdentityError> {
+        let digests = object.remove(SD_CLAIM);
+
+        for value in object.values_mut() {
+            self.resolve_value(value)?;
+        }
+
+        let Some(digests) = digests else {
+            return Ok(());
+        };
+
+        let Value::Array(digests) = digests else {
+            return Err(VcIdentityError::InvalidDisclosure);
+        };
+
+        for digest in digests {
+            let Value::String(digest) = digest else {
+                return Err(VcIdentityError::InvalidDisclosure);
+            };
+
+            let Some(disclosure) = self.take(&digest)? else {
+                continue;
+            };
+
+            let Some(name) = disclosure.name.clone() else {
+                return Err(VcIdentityError::InvalidDisclosure);
+            };
+
+            if object.contains_key(&name) {
+                return Err(VcIdentityError::DuplicateClaim(name));
+            }
+
+            let mut value = disclosure.value.clone();
+            self.resolve_value(&mut value)?;
+            object.insert(name, value);
+        }
+
+        Ok(())
+    }
+
+    fn resolve_value(&mut self, value: &mut Value) -> Result<(), VcIdentityError> {
+        match value {
+            Value::Object(object) => self.resolve_object(object),
+            Value::Array(items) => {
+                let mut resolved = Vec::with_capacity(items.len());
+
+                for mut item in core::mem::take(items) {
+                    match array_element_digest(&item) {
+                        Some(digest) => {
+                            let Some(disclosure) = self.take(&digest)? else {
+                                continue;
+                            };
+
+                            if disclosure.name.is_some() {
+                                return Err(VcIdentityError::InvalidDisclosure);
+                            }
+
+                            let mut value = disclosure.value.clone();
+                            self.resolve_value(&mut value)?;
+                            resolved.push(value);
+                        }
+                        None => {
+                            self.resolve_value(&mut item)?;
+                            resolved.push(item);
+                        }
+                    }
+                }
+
+                *items = resolved;
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn array_element_digest(item: &Value) -> Option<String> {
+    let Value::Object(object) = item else {
+        return None;
+    };
+
+    match (object.len(), object.get(ARRAY_ELEMENT_CLAIM)) {
+        (1, Some(Value::String(digest))) => Some(digest.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use alloc::{format, string::String, vec::Vec};
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use serde_json::{json, Value};
+
+    pub fn encode_disclosure(disclosure: Value) -> String {
+        URL_SAFE_NO_PAD.encode(disclosure.to_string())
+    }
+
+    // Digests of the tests are the data itself, see the test verifier.
+    pub fn disclosure_digest(encoded: &str) -> String {
+        URL_SAFE_NO_PAD.encode(encoded)
+    }
+
+    pub fn encode_sd_jwt(claims: Value, disclosures: &[String]) -> String {
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "test" }).to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{header}.{payload}");
+
+        let signature = signing_input.bytes().rev().collect::<Vec<_>>();
+        let signature = URL_SAFE_NO_PAD.encode(signature);
+
+        let mut serialized = format!("{signing_input}.{signature}~");
+
+        for disclosure in disclosures {
+            serialized.push_str(disclosure);
+            serialized.push('~');
+        }
+
+        serialized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use serde_json::json;
+
+    use crate::VcIdentityError;
+
+    use super::{
+        test_utils::{disclosure_digest, encode_disclosure, encode_sd_jwt},
+        SdJwt,
+    };
+
+    fn identity_digest(data: &[u8]) -> Result<Vec<u8>, VcIdentityError> {
+        Ok(data.to_vec())
+    }
+
+    #[test]
+    fn disclosures_are_applied() {
+        let email = encode_disclosure(json!(["salt1", "email", "alice@example.com"]));
+        let role = encode_disclosure(json!(["salt2", "admin"]));
+
+        let claims = json!({
+            "sub": "alice",
+            "_sd": [disclosure_digest(&email), "decoy"],
+            "roles": ["user", { "...": disclosure_digest(&role) }],
+        });
+
+        let sd_jwt = SdJwt::parse(&encode_sd_jwt(claims, &[email, role])).unwrap();
+
+        assert_eq!(sd_jwt.disclosures().len(), 2);
+        assert_eq!(sd_jwt.sd_alg(), "sha-256");
+        assert_eq!(sd_jwt.key_binding_jwt(), None);
+
+        let disclosed = sd_jwt.disclosed_claims(identity_digest).unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(disclosed),
+            json!({
+                "sub": "alice",
+                "email": "alice@example.com",
+                "roles": ["user", "admin"],
+            })
+        );
+    }
+
+    #[test]
+    fn undisclosed_claims_are_hidden() {
+        let email = encode_disclosure(json!(["salt1", "email", "alice@example.com"]));
+
+        let claims = json!({ "sub": "alice", "_sd": [disclosure_digest(&email)] });
+        let sd_jwt = SdJwt::parse(&encode_sd_jwt(claims, &[])).unwrap();
+
+        let disclosed = sd_jwt.disclosed_claims(identity_digest).unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(disclosed),
+            json!({ "sub": "alice" })
+        );
+    }
+
+    #[test]
+    fn unreferenced_disclosures_are_rejected() {
+        let email = encode_disclosure(json!(["salt1", "email", "alice@example.com"]));
+
+        let claims = json!({ "sub": "alice" });
+        let sd_jwt = SdJwt::parse(&encode_sd_jwt(claims, &[email.clone(), email])).unwrap();
+
+        let res = sd_jwt.disclosed_claims(identity_digest);
+        assert_matches!(res, Err(VcIdentityError::DuplicateDisclosure));
+
+        let other = encode_disclosure(json!(["salt2", "name", "Alice"]));
+
+        let claims = json!({ "sub": "alice" });
+        let sd_jwt = SdJwt::parse(&encode_sd_jwt(claims, &[other])).unwrap();
+
+        let res = sd_jwt.disclosed_claims(identity_digest);
+        assert_matches!(res, Err(VcIdentityError::UnreferencedDisclosure));
+    }
+
+    #[test]
+    fn disclosures_cannot_override_claims() {
+        let sub = encode_disclosure(json!(["salt1", "sub", "mallory"]));
+
+        let claims = json!({ "sub": "alice", "_sd": [disclosure_digest(&sub)] });
+        let sd_jwt = SdJwt::parse(&encode_sd_jwt(claims, &[sub])).unwrap();
+
+        let res = sd_jwt.disclosed_claims(identity_digest);
+        assert_matches!(res, Err(VcIdentityError::DuplicateClaim(name)) if name == "sub");
+    }
+
+    #[test]
+    fn malformed_sd_jwt_is_rejected() {
+        assert_matches!(
+            SdJwt::parse("not a jwt"),
+            Err(VcIdentityError::InvalidEncoding)
+        );
+
+        let claims = json!({ "sub": "alice" });
+        let serialized = encode_sd_jwt(claims, &[]) + "bm90IGpzb24~";
+
+        assert_matches!(
+            SdJwt::parse(&serialized),
+            Err(VcIdentityError::InvalidDisclosure)
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{crypto::SignaturePublicKey, error::IntoAnyError};
+use serde_json::Value;
+
+use crate::SdJwt;
+
+/// Cryptographic verification of verifiable credentials.
+pub trait VcVerifier {
+    type Error: IntoAnyError;
+
+    /// Verify the signature of the issuer over
+    /// [`SdJwt::signing_input`], using the algorithm and key identified by
+    /// the [`header`](SdJwt::header) and [`claims`](SdJwt::claims).
+    ///
+    /// This is where the issuer must be checked to be trusted.
+    fn verify_issuer_signature(&self, sd_jwt: &SdJwt) -> Result<(), Self::Error>;
+
+    /// Hash `data` with the SD-JWT hash `algorithm`, such as `sha-256`.
+    fn digest(&self, algorithm: &str, data: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+    /// Determine if the confirmation claim `cnf` of a credential designates
+    /// `signature_key` as the key of its holder.
+    fn holder_key_matches(
+        &self,
+        cnf: &Value,
+        signature_key: &SignaturePublicKey,
+    ) -> Result<bool, Self::Error>;
+}
@@ -0,0 +1,432 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Node.js bindings for mls-rs.
+//!
+//! The bindings are built with [napi-rs] and follow the same opinionated
+//! surface as `mls-rs-uniffi`: basic credentials, the OpenSSL crypto provider
+//! and a group state storage that can be implemented in JavaScript.
+//!
+//! This crate must be built with `--cfg mls_build_async` so that storage
+//! callbacks can return promises.
+//!
+//! [napi-rs]: https://napi.rs
+
+#![cfg(mls_build_async)]
+
+mod storage;
+
+use std::sync::Arc;
+
+use mls_rs::client_builder::{self, WithGroupStateStorage};
+use mls_rs::error::{IntoAnyError, MlsError};
+use mls_rs::group::{self, ExportedTree};
+use mls_rs::identity::basic;
+use mls_rs::mls_rules;
+use mls_rs::storage_provider::in_memory::InMemoryGroupStateStorage;
+use mls_rs::{CipherSuiteProvider, CryptoProvider, MlsMessage};
+use mls_rs_core::identity::{BasicCredential, SigningIdentity};
+use mls_rs_crypto_openssl::OpensslCryptoProvider;
+use napi::bindgen_prelude::Buffer;
+use napi::JsObject;
+use napi_derive::napi;
+use tokio::sync::Mutex;
+
+use storage::{ClientGroupStorage, JsGroupStateStorage};
+
+type NodeConfig = client_builder::WithIdentityProvider<
+    basic::BasicIdentityProvider,
+    client_builder::WithCryptoProvider<
+        OpensslCryptoProvider,
+        WithGroupStateStorage<ClientGroupStorage, client_builder::BaseConfig>,
+    >,
+>;
+
+const CIPHER_SUITE: mls_rs::CipherSuite = mls_rs::CipherSuite::CURVE25519_AES128;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("A mls-rs error occurred: {0}")]
+    MlsError(#[from] MlsError),
+    #[error("An unknown error occurred: {0}")]
+    AnyError(#[from] mls_rs::error::AnyError),
+    #[error("A data encoding error occurred: {0}")]
+    MlsCodecError(#[from] mls_rs_core::mls_rs_codec::Error),
+    #[error("A JavaScript callback failed: {0}")]
+    JsError(String),
+    #[error("An epoch id is not a safe JavaScript integer")]
+    EpochIdOutOfRange,
+}
+
+impl IntoAnyError for Error {}
+
+impl From<napi::Error> for Error {
+    fn from(e: napi::Error) -> Self {
+        Self::JsError(e.reason)
+    }
+}
+
+impl From<Error> for napi::Error {
+    fn from(e: Error) -> Self {
+        napi::Error::from_reason(e.to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// `Number.MAX_SAFE_INTEGER`, above which JavaScript numbers can't represent
+/// every integer.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Convert an epoch id to a JavaScript number, failing if it can't be
+/// represented exactly.
+fn epoch_id_to_js(epoch_id: u64) -> Result<i64> {
+    i64::try_from(epoch_id)
+        .ok()
+        .filter(|id| *id <= MAX_SAFE_INTEGER)
+        .ok_or(Error::EpochIdOutOfRange)
+}
+
+/// Convert a JavaScript number to an epoch id, failing unless it is a
+/// non-negative safe integer.
+fn epoch_id_from_js(epoch_id: f64) -> Result<u64> {
+    let valid = epoch_id.fract() == 0.0 && (0.0..=MAX_SAFE_INTEGER as f64).contains(&epoch_id);
+    valid
+        .then_some(epoch_id as u64)
+        .ok_or(Error::EpochIdOutOfRange)
+}
+
+/// A signature key pair for the default cipher suite.
+#[napi(object)]
+pub struct SignatureKeypair {
+    pub public_key: Buffer,
+    pub secret_key: Buffer,
+}
+
+/// Generate a signature key pair for `Curve25519Aes128`.
+#[napi]
+pub async fn generate_signature_keypair() -> napi::Result<SignatureKeypair> {
+    let cipher_suite_provider = OpensslCryptoProvider::new()
+        .cipher_suite_provider(CIPHER_SUITE)
+        .ok_or(Error::from(MlsError::UnsupportedCipherSuite(CIPHER_SUITE)))?;
+
+    let (secret_key, public_key) = cipher_suite_provider
+        .signature_key_generate()
+        .await
+        .map_err(|e| Error::from(MlsError::CryptoProviderError(e.into_any_error())))?;
+
+    Ok(SignatureKeypair {
+        public_key: public_key.to_vec().into(),
+        secret_key: secret_key.as_bytes().to_vec().into(),
+    })
+}
+
+/// Output of a commit.
+#[napi(object)]
+pub struct CommitOutput {
+    /// Commit message to send to other group members.
+    pub commit_message: Buffer,
+    /// Welcome message to send to new group members, if any were added.
+    pub welcome_message: Option<Buffer>,
+    /// Ratchet tree to send out of band if the ratchet tree extension is not used.
+    pub ratchet_tree: Option<Buffer>,
+}
+
+impl TryFrom<group::CommitOutput> for CommitOutput {
+    type Error = Error;
+
+    fn try_from(output: group::CommitOutput) -> Result<Self> {
+        let welcome_message = output
+            .welcome_messages
+            .into_iter()
+            .next()
+            .map(|welcome| welcome.to_bytes().map(Into::into))
+            .transpose()?;
+
+        let ratchet_tree = output
+            .ratchet_tree
+            .map(|tree| tree.to_bytes().map(Into::into))
+            .transpose()?;
+
+        Ok(Self {
+            commit_message: output.commit_message.to_bytes()?.into(),
+            welcome_message,
+            ratchet_tree,
+        })
+    }
+}
+
+/// Processed incoming message.
+///
+/// `kind` is one of `application`, `commit`, `proposal`, `groupInfo`,
+/// `welcome` or `keyPackage`.
+#[napi(object)]
+pub struct ReceivedMessage {
+    pub kind: String,
+    /// Index of the sender for application messages, commits and member proposals.
+    pub sender: Option<u32>,
+    /// Decrypted payload for application messages.
+    pub data: Option<Buffer>,
+}
+
+impl ReceivedMessage {
+    fn new(kind: &str, sender: Option<u32>, data: Option<Buffer>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            sender,
+            data,
+        }
+    }
+}
+
+fn decode_message(bytes: &[u8]) -> Result<MlsMessage> {
+    Ok(MlsMessage::from_bytes(bytes)?)
+}
+
+/// An MLS client used to create key packages and manage groups.
+#[napi]
+pub struct Client {
+    inner: mls_rs::Client<NodeConfig>,
+}
+
+#[napi]
+impl Client {
+    /// Create a new client identified by a basic credential containing `id`.
+    ///
+    /// If `storage` is not given, group state is kept in memory.
+    #[napi(constructor)]
+    pub fn new(
+        id: Buffer,
+        signature_keypair: SignatureKeypair,
+        storage: Option<JsObject>,
+    ) -> napi::Result<Self> {
+        let storage = match storage {
+            Some(storage) => ClientGroupStorage::Js(JsGroupStateStorage::new(storage)?),
+            None => ClientGroupStorage::InMemory(InMemoryGroupStateStorage::new()),
+        };
+
+        let signing_identity = SigningIdentity::new(
+            BasicCredential::new(id.to_vec()).into_credential(),
+            signature_keypair.public_key.to_vec().into(),
+        );
+
+        let commit_options = mls_rules::CommitOptions::default().with_single_welcome_message(true);
+
+        let inner = mls_rs::Client::builder()
+            .crypto_provider(OpensslCryptoProvider::new())
+            .identity_provider(basic::BasicIdentityProvider::new())
+            .signing_identity(
+                signing_identity,
+                signature_keypair.secret_key.to_vec().into(),
+                CIPHER_SUITE,
+            )
+            .group_state_storage(storage)
+            .mls_rules(mls_rules::DefaultMlsRules::new().with_commit_options(commit_options))
+            .build();
+
+        Ok(Self { inner })
+    }
+
+    /// Generate a new key package message for this client.
+    #[napi]
+    pub async fn generate_key_package_message(&self) -> napi::Result<Buffer> {
+        let message = self
+            .inner
+            .generate_key_package_message()
+            .await
+            .map_err(Error::from)?;
+
+        Ok(message.to_bytes().map_err(Error::from)?.into())
+    }
+
+    /// Create and join a new group.
+    #[napi]
+    pub async fn create_group(&self, group_id: Option<Buffer>) -> napi::Result<Group> {
+        let extensions = mls_rs::ExtensionList::new();
+
+        let group = match group_id {
+            Some(id) => {
+                self.inner
+                    .create_group_with_id(id.to_vec(), extensions)
+                    .await
+            }
+            None => self.inner.create_group(extensions).await,
+        }
+        .map_err(Error::from)?;
+
+        Ok(Group::new(group))
+    }
+
+    /// Join a group using a welcome message.
+    #[napi]
+    pub async fn join_group(
+        &self,
+        welcome_message: Buffer,
+        ratchet_tree: Option<Buffer>,
+    ) -> napi::Result<Group> {
+        let welcome_message = decode_message(&welcome_message)?;
+
+        let ratchet_tree = ratchet_tree
+            .map(|tree| ExportedTree::from_bytes(&tree))
+            .transpose()
+            .map_err(Error::from)?;
+
+        let (group, _) = self
+            .inner
+            .join_group(ratchet_tree, &welcome_message)
+            .await
+            .map_err(Error::from)?;
+
+        Ok(Group::new(group))
+    }
+
+    /// Load a group from storage.
+    #[napi]
+    pub async fn load_group(&self, group_id: Buffer) -> napi::Result<Group> {
+        let group = self
+            .inner
+            .load_group(&group_id)
+            .await
+            .map_err(Error::from)?;
+        Ok(Group::new(group))
+    }
+}
+
+/// An MLS group.
+#[napi]
+pub struct Group {
+    inner: Arc<Mutex<mls_rs::Group<NodeConfig>>>,
+}
+
+impl Group {
+    fn new(group: mls_rs::Group<NodeConfig>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(group)),
+        }
+    }
+}
+
+#[napi]
+impl Group {
+    /// The group id.
+    #[napi]
+    pub async fn group_id(&self) -> Buffer {
+        self.inner.lock().await.group_id().to_vec().into()
+    }
+
+    /// The current epoch.
+    #[napi]
+    pub async fn epoch(&self) -> napi::Result<i64> {
+        Ok(epoch_id_to_js(self.inner.lock().await.current_epoch())?)
+    }
+
+    /// Write the current state of the group to storage.
+    #[napi]
+    pub async fn write_to_storage(&self) -> napi::Result<()> {
+        let mut group = self.inner.lock().await;
+        group.write_to_storage().await.map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Export the ratchet tree of the current epoch.
+    #[napi]
+    pub async fn export_tree(&self) -> napi::Result<Buffer> {
+        let group = self.inner.lock().await;
+        Ok(group.export_tree().to_bytes().map_err(Error::from)?.into())
+    }
+
+    /// Commit all pending proposals, or an empty commit.
+    #[napi]
+    pub async fn commit(&self) -> napi::Result<CommitOutput> {
+        let mut group = self.inner.lock().await;
+        let output = group.commit(Vec::new()).await.map_err(Error::from)?;
+        Ok(output.try_into()?)
+    }
+
+    /// Commit adding the members owning the given key package messages.
+    #[napi]
+    pub async fn add_members(&self, key_packages: Vec<Buffer>) -> napi::Result<CommitOutput> {
+        let mut group = self.inner.lock().await;
+        let mut builder = group.commit_builder();
+
+        for key_package in key_packages {
+            builder = builder
+                .add_member(decode_message(&key_package)?)
+                .map_err(Error::from)?;
+        }
+
+        let output = builder.build().await.map_err(Error::from)?;
+        Ok(output.try_into()?)
+    }
+
+    /// Commit removing the members at the given leaf indices.
+    #[napi]
+    pub async fn remove_members(&self, indices: Vec<u32>) -> napi::Result<CommitOutput> {
+        let mut group = self.inner.lock().await;
+        let mut builder = group.commit_builder();
+
+        for index in indices {
+            builder = builder.remove_member(index).map_err(Error::from)?;
+        }
+
+        let output = builder.build().await.map_err(Error::from)?;
+        Ok(output.try_into()?)
+    }
+
+    /// Apply the commit created by the last call to a commit function.
+    #[napi]
+    pub async fn apply_pending_commit(&self) -> napi::Result<()> {
+        let mut group = self.inner.lock().await;
+        group.apply_pending_commit().await.map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Encrypt an application message.
+    #[napi]
+    pub async fn encrypt_application_message(&self, message: Buffer) -> napi::Result<Buffer> {
+        let mut group = self.inner.lock().await;
+
+        let message = group
+            .encrypt_application_message(&message, Vec::new())
+            .await
+            .map_err(Error::from)?;
+
+        Ok(message.to_bytes().map_err(Error::from)?.into())
+    }
+
+    /// Process an incoming message for this group.
+    #[napi]
+    pub async fn process_incoming_message(&self, message: Buffer) -> napi::Result<ReceivedMessage> {
+        let message = decode_message(&message)?;
+        let mut group = self.inner.lock().await;
+
+        let received = group
+            .process_incoming_message(message)
+            .await
+            .map_err(Error::from)?;
+
+        Ok(match received {
+            group::ReceivedMessage::ApplicationMessage(message) => ReceivedMessage::new(
+                "application",
+                Some(message.sender_index),
+                Some(message.data().to_vec().into()),
+            ),
+            group::ReceivedMessage::Commit(commit) => {
+                ReceivedMessage::new("commit", Some(commit.committer), None)
+            }
+            group::ReceivedMessage::Proposal(proposal) => {
+                let sender = match proposal.sender {
+                    group::ProposalSender::Member(index) => Some(index),
+                    _ => None,
+                };
+
+                ReceivedMessage::new("proposal", sender, None)
+            }
+            group::ReceivedMessage::GroupInfo(_) => ReceivedMessage::new("groupInfo", None, None),
+            group::ReceivedMessage::Welcome => ReceivedMessage::new("welcome", None, None),
+            group::ReceivedMessage::KeyPackage(_) => ReceivedMessage::new("keyPackage", None, None),
+        })
+    }
+}
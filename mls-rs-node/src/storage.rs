@@ -0,0 +1,260 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use mls_rs::storage_provider::in_memory::InMemoryGroupStateStorage;
+use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+use napi::bindgen_prelude::{Buffer, Promise};
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction};
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+
+use crate::{epoch_id_from_js, epoch_id_to_js, Error};
+
+/// Arguments of the JS `write` callback, with epoch ids already converted to
+/// JS numbers.
+struct WriteArgs {
+    group_id: Vec<u8>,
+    state: Vec<u8>,
+    inserts: Vec<(i64, Vec<u8>)>,
+    updates: Vec<(i64, Vec<u8>)>,
+}
+
+type Callback<T> = ThreadsafeFunction<T, ErrorStrategy::Fatal>;
+
+/// Group state storage implemented by a JS object.
+///
+/// The object must provide `state`, `epoch`, `write` and `maxEpochId`
+/// methods, and may provide a `deleteEpochsBefore` method. Each of them may
+/// return a value or a promise.
+#[derive(Clone)]
+pub(crate) struct JsGroupStateStorage {
+    state: Callback<Vec<u8>>,
+    epoch: Callback<(Vec<u8>, i64)>,
+    write: Callback<Arc<WriteArgs>>,
+    max_epoch_id: Callback<Vec<u8>>,
+    delete_epochs_before: Option<Callback<(Vec<u8>, i64)>>,
+}
+
+impl Debug for JsGroupStateStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsGroupStateStorage").finish()
+    }
+}
+
+fn buffer(env: &Env, data: &[u8]) -> napi::Result<JsUnknown> {
+    Ok(env.create_buffer_with_data(data.to_vec())?.into_unknown())
+}
+
+fn js_epoch_records(records: Vec<EpochRecord>) -> Result<Vec<(i64, Vec<u8>)>, Error> {
+    records
+        .into_iter()
+        .map(|record| epoch_id_to_js(record.id).map(|id| (id, record.data)))
+        .collect()
+}
+
+fn epoch_records(env: &Env, records: &[(i64, Vec<u8>)]) -> napi::Result<JsUnknown> {
+    let mut array = env.create_array_with_length(records.len())?;
+
+    for (i, (id, data)) in records.iter().enumerate() {
+        let mut object = env.create_object()?;
+        object.set_named_property("id", env.create_int64(*id)?)?;
+        object.set_named_property("data", buffer(env, data)?)?;
+        array.set_element(i as u32, object)?;
+    }
+
+    Ok(array.into_unknown())
+}
+
+fn group_and_epoch(ctx: ThreadSafeCallContext<(Vec<u8>, i64)>) -> napi::Result<Vec<JsUnknown>> {
+    let (group_id, epoch_id) = ctx.value;
+
+    Ok(vec![
+        buffer(&ctx.env, &group_id)?,
+        ctx.env.create_int64(epoch_id)?.into_unknown(),
+    ])
+}
+
+fn method(object: &JsObject, name: &str) -> napi::Result<JsFunction> {
+    let function: JsObject = object.get_named_property(name)?;
+
+    // Bind the method so that `this` refers to the storage object when called from Rust.
+    let bind: JsFunction = function.get_named_property("bind")?;
+    bind.call(Some(&function), &[object])?.try_into()
+}
+
+impl JsGroupStateStorage {
+    pub(crate) fn new(object: JsObject) -> napi::Result<Self> {
+        let state = method(&object, "state")?
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<u8>>| {
+                Ok(vec![buffer(&ctx.env, &ctx.value)?])
+            })?;
+
+        let epoch = method(&object, "epoch")?.create_threadsafe_function(0, group_and_epoch)?;
+
+        let write = method(&object, "write")?.create_threadsafe_function(
+            0,
+            |ctx: ThreadSafeCallContext<Arc<WriteArgs>>| {
+                Ok(vec![
+                    buffer(&ctx.env, &ctx.value.group_id)?,
+                    buffer(&ctx.env, &ctx.value.state)?,
+                    epoch_records(&ctx.env, &ctx.value.inserts)?,
+                    epoch_records(&ctx.env, &ctx.value.updates)?,
+                ])
+            },
+        )?;
+
+        let max_epoch_id = method(&object, "maxEpochId")?
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<u8>>| {
+                Ok(vec![buffer(&ctx.env, &ctx.value)?])
+            })?;
+
+        let delete_epochs_before = if object.has_named_property("deleteEpochsBefore")? {
+            Some(
+                method(&object, "deleteEpochsBefore")?
+                    .create_threadsafe_function(0, group_and_epoch)?,
+            )
+        } else {
+            None
+        };
+
+        Ok(Self {
+            state,
+            epoch,
+            write,
+            max_epoch_id,
+            delete_epochs_before,
+        })
+    }
+}
+
+#[maybe_async::must_be_async]
+impl GroupStateStorage for JsGroupStateStorage {
+    type Error = Error;
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let state: Promise<Option<Buffer>> = self.state.call_async(group_id.to_vec()).await?;
+        Ok(state.await?.map(Into::into))
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        let epoch: Promise<Option<Buffer>> = self
+            .epoch
+            .call_async((group_id.to_vec(), epoch_id_to_js(epoch_id)?))
+            .await?;
+
+        Ok(epoch.await?.map(Into::into))
+    }
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        inserts: Vec<EpochRecord>,
+        updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        let args = Arc::new(WriteArgs {
+            group_id: state.id,
+            state: state.data,
+            inserts: js_epoch_records(inserts)?,
+            updates: js_epoch_records(updates)?,
+        });
+
+        let done: Promise<()> = self.write.call_async(args).await?;
+        Ok(done.await?)
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        // Ids are read as floating point numbers, so that values that are
+        // not safe integers, such as `-Infinity`, are rejected rather than
+        // truncated.
+        let id: Promise<Option<f64>> = self.max_epoch_id.call_async(group_id.to_vec()).await?;
+        id.await?.map(epoch_id_from_js).transpose()
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        let Some(delete_epochs_before) = &self.delete_epochs_before else {
+            return Ok(None);
+        };
+
+        let ids: Promise<Vec<f64>> = delete_epochs_before
+            .call_async((group_id.to_vec(), epoch_id_to_js(epoch_id)?))
+            .await?;
+
+        ids.await?
+            .into_iter()
+            .map(epoch_id_from_js)
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+/// Storage used by a [`Client`](crate::Client), either provided by JS or in memory.
+#[derive(Clone, Debug)]
+pub(crate) enum ClientGroupStorage {
+    Js(JsGroupStateStorage),
+    InMemory(InMemoryGroupStateStorage),
+}
+
+#[maybe_async::must_be_async]
+impl GroupStateStorage for ClientGroupStorage {
+    type Error = Error;
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self {
+            Self::Js(storage) => storage.state(group_id).await,
+            Self::InMemory(storage) => storage.state(group_id).await.map_err(|e| match e {}),
+        }
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self {
+            Self::Js(storage) => storage.epoch(group_id, epoch_id).await,
+            Self::InMemory(storage) => storage
+                .epoch(group_id, epoch_id)
+                .await
+                .map_err(|e| match e {}),
+        }
+    }
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        inserts: Vec<EpochRecord>,
+        updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        match self {
+            Self::Js(storage) => storage.write(state, inserts, updates).await,
+            Self::InMemory(storage) => storage
+                .write(state, inserts, updates)
+                .await
+                .map_err(|e| match e {}),
+        }
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        match self {
+            Self::Js(storage) => storage.max_epoch_id(group_id).await,
+            Self::InMemory(storage) => storage.max_epoch_id(group_id).await.map_err(|e| match e {}),
+        }
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        match self {
+            Self::Js(storage) => storage.delete_epochs_before(group_id, epoch_id).await,
+            Self::InMemory(storage) => storage
+                .delete_epochs_before(group_id, epoch_id)
+                .await
+                .map_err(|e| match e {}),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+import { test } from "node:test";
+import assert from "node:assert/strict";
+
+import { Client, generateSignatureKeypair } from "../index.js";
+
+class MemoryStorage {
+  constructor() {
+    this.groups = new Map();
+  }
+
+  async state(groupId) {
+    return this.groups.get(groupId.toString("hex"))?.state ?? null;
+  }
+
+  async epoch(groupId, epochId) {
+    const group = this.groups.get(groupId.toString("hex"));
+    return group?.epochs.get(epochId) ?? null;
+  }
+
+  async write(groupId, state, epochInserts, epochUpdates) {
+    const key = groupId.toString("hex");
+    const group = this.groups.get(key) ?? { epochs: new Map() };
+
+    group.state = state;
+
+    for (const { id, data } of [...epochInserts, ...epochUpdates]) {
+      group.epochs.set(id, data);
+    }
+
+    this.groups.set(key, group);
+  }
+
+  async maxEpochId(groupId) {
+    const ids = [...(this.groups.get(groupId.toString("hex"))?.epochs.keys() ?? [])];
+    return ids.length > 0 ? Math.max(...ids) : null;
+  }
+
+  async deleteEpochsBefore(groupId, epochId) {
+    const group = this.groups.get(groupId.toString("hex"));
+    const deleted = group ? [...group.epochs.keys()].filter((id) => id < epochId) : [];
+
+    for (const id of deleted) {
+      group.epochs.delete(id);
+    }
+
+    return deleted;
+  }
+}
+
+test("alice and bob exchange a message", async () => {
+  const aliceStorage = new MemoryStorage();
+  const alice = new Client(Buffer.from("alice"), await generateSignatureKeypair(), aliceStorage);
+  const bob = new Client(Buffer.from("bob"), await generateSignatureKeypair());
+
+  const aliceGroup = await alice.createGroup();
+  const commit = await aliceGroup.addMembers([await bob.generateKeyPackageMessage()]);
+  await aliceGroup.applyPendingCommit();
+
+  const bobGroup = await bob.joinGroup(commit.welcomeMessage);
+  const message = await aliceGroup.encryptApplicationMessage(Buffer.from("hello, bob"));
+  const received = await bobGroup.processIncomingMessage(message);
+
+  assert.equal(received.kind, "application");
+  assert.equal(received.sender, 0);
+  assert.equal(received.data.toString(), "hello, bob");
+
+  await aliceGroup.writeToStorage();
+  const loaded = await alice.loadGroup(await aliceGroup.groupId());
+  assert.equal(await loaded.epoch(), 1);
+});
+
+test("max epoch id is null without epochs", async () => {
+  const storage = new MemoryStorage();
+  const groupId = Buffer.from("group");
+
+  assert.equal(await storage.maxEpochId(groupId), null);
+
+  await storage.write(groupId, Buffer.from("state"), [], []);
+  assert.equal(await storage.maxEpochId(groupId), null);
+
+  await storage.write(groupId, Buffer.from("state"), [{ id: 3, data: Buffer.from("epoch") }], []);
+  assert.equal(await storage.maxEpochId(groupId), 3);
+});
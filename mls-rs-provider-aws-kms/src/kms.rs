@@ -0,0 +1,50 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::future::Future;
+
+use aws_sdk_kms::Client;
+
+use crate::KmsError;
+
+/// KMS client able to run its requests in both the async and sync builds of
+/// mls-rs.
+#[derive(Clone, Debug)]
+pub(crate) struct Kms {
+    pub(crate) client: Client,
+    #[cfg(not(mls_build_async))]
+    runtime: tokio::runtime::Handle,
+}
+
+impl Kms {
+    #[cfg(mls_build_async)]
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    #[cfg(not(mls_build_async))]
+    pub(crate) fn new(client: Client, runtime: tokio::runtime::Handle) -> Self {
+        Self { client, runtime }
+    }
+
+    #[cfg(mls_build_async)]
+    pub(crate) async fn run<F, T, E>(&self, request: F) -> Result<T, KmsError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        request.await.map_err(|e| KmsError::ServiceError(e.into()))
+    }
+
+    #[cfg(not(mls_build_async))]
+    pub(crate) fn run<F, T, E>(&self, request: F) -> Result<T, KmsError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.runtime
+            .block_on(request)
+            .map_err(|e| KmsError::ServiceError(e.into()))
+    }
+}
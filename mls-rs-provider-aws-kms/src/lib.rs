@@ -0,0 +1,56 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Providers keeping the secrets of mls-rs clients in
+//! [AWS KMS](https://aws.amazon.com/kms/).
+//!
+//! - [`KmsSigner`] signs with asymmetric KMS keys, so that the signature keys
+//!   of members never leave KMS. It is plugged into a crypto provider with
+//!   [`SignerCryptoProvider`](mls_rs_core::crypto::SignerCryptoProvider).
+//! - [`KmsPreSharedKeyStorage`] stores external pre-shared keys encrypted
+//!   with a symmetric KMS key in another pre-shared key storage, such as the
+//!   SQLite one, and decrypts them when they are used.
+//!
+//! The AWS SDK is asynchronous. When mls-rs is built without `mls_build_async`,
+//! the providers block on a handle to a Tokio runtime given to their
+//! constructors, so they must not be called from a thread of that runtime.
+
+use thiserror::Error;
+
+mod kms;
+mod psk;
+mod signer;
+
+pub use psk::KmsPreSharedKeyStorage;
+pub use signer::KmsSigner;
+
+#[derive(Debug, Error)]
+/// AWS KMS provider error.
+pub enum KmsError {
+    #[error(transparent)]
+    /// Error returned by the KMS service or the AWS SDK.
+    ServiceError(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("KMS response is missing {0}")]
+    /// The response of KMS doesn't contain the expected field.
+    MissingResponseField(&'static str),
+    #[error("signature key handle is not a valid KMS key id")]
+    /// The signature secret key given to mls-rs isn't a UTF-8 key id.
+    InvalidKeyId,
+    #[error("cipher suite {0:?} is not supported by KMS")]
+    /// KMS has no signing algorithm for the signature scheme of the cipher
+    /// suite.
+    UnsupportedCipherSuite(mls_rs_core::crypto::CipherSuite),
+    #[error("KMS public key is not a valid key for the cipher suite")]
+    /// The public key returned by KMS doesn't match the cipher suite.
+    InvalidPublicKey,
+    #[error(transparent)]
+    /// Error returned by the wrapped pre-shared key storage.
+    StorageError(mls_rs_core::error::AnyError),
+}
+
+impl mls_rs_core::error::IntoAnyError for KmsError {
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use aws_sdk_kms::{primitives::Blob, Client};
+use mls_rs_core::{
+    error::IntoAnyError,
+    psk::{ExternalPskId, PreSharedKey, PreSharedKeyStorage},
+};
+
+use crate::{kms::Kms, KmsError};
+
+const PSK_ID_CONTEXT_KEY: &str = "mls-rs-psk-id";
+
+/// Pre-shared key storage keeping external pre-shared keys encrypted by a
+/// symmetric AWS KMS key in another storage.
+///
+/// The wrapped storage holds the ciphertexts created by
+/// [`KmsPreSharedKeyStorage::encrypt`], which are decrypted by KMS every
+/// time mls-rs needs the pre-shared key. Ciphertexts are bound to the id of
+/// their pre-shared key through the KMS encryption context, so a ciphertext
+/// stored under another id fails to decrypt.
+#[derive(Clone, Debug)]
+pub struct KmsPreSharedKeyStorage<S> {
+    kms: Kms,
+    key_id: String,
+    inner: S,
+}
+
+impl<S> KmsPreSharedKeyStorage<S> {
+    /// Decrypt the ciphertexts stored in `inner` with the KMS key `key_id`
+    /// of `client`.
+    #[cfg(mls_build_async)]
+    pub fn new(client: Client, key_id: String, inner: S) -> Self {
+        Self {
+            kms: Kms::new(client),
+            key_id,
+            inner,
+        }
+    }
+
+    /// Decrypt the ciphertexts stored in `inner` with the KMS key `key_id`
+    /// of `client`, blocking on `runtime` for requests to KMS.
+    #[cfg(not(mls_build_async))]
+    pub fn new(client: Client, runtime: tokio::runtime::Handle, key_id: String, inner: S) -> Self {
+        Self {
+            kms: Kms::new(client, runtime),
+            key_id,
+            inner,
+        }
+    }
+
+    /// Wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Encrypt `psk` with the KMS key of the storage, returning the
+    /// ciphertext to insert in the wrapped storage under `id`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn encrypt(
+        &self,
+        id: &ExternalPskId,
+        psk: &PreSharedKey,
+    ) -> Result<PreSharedKey, KmsError> {
+        let request = self
+            .kms
+            .client
+            .encrypt()
+            .key_id(&self.key_id)
+            .plaintext(Blob::new(psk.raw_value()))
+            .encryption_context(PSK_ID_CONTEXT_KEY, hex::encode(id.as_bytes()))
+            .send();
+
+        let output = self.kms.run(request).await?;
+
+        output
+            .ciphertext_blob()
+            .map(|ciphertext| PreSharedKey::new(ciphertext.as_ref().to_vec()))
+            .ok_or(KmsError::MissingResponseField("ciphertext"))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn decrypt(
+        &self,
+        id: &ExternalPskId,
+        ciphertext: &PreSharedKey,
+    ) -> Result<PreSharedKey, KmsError> {
+        let request = self
+            .kms
+            .client
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(Blob::new(ciphertext.raw_value()))
+            .encryption_context(PSK_ID_CONTEXT_KEY, hex::encode(id.as_bytes()))
+            .send();
+
+        let output = self.kms.run(request).await?;
+
+        output
+            .plaintext()
+            .map(|plaintext| PreSharedKey::new(plaintext.as_ref().to_vec()))
+            .ok_or(KmsError::MissingResponseField("plaintext"))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<S> PreSharedKeyStorage for KmsPreSharedKeyStorage<S>
+where
+    S: PreSharedKeyStorage,
+{
+    type Error = KmsError;
+
+    async fn get(&self, id: &ExternalPskId) -> Result<Option<PreSharedKey>, Self::Error> {
+        let ciphertext = self
+            .inner
+            .get(id)
+            .await
+            .map_err(|e| KmsError::StorageError(e.into_any_error()))?;
+
+        match ciphertext {
+            Some(ciphertext) => self.decrypt(id, &ciphertext).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn contains(&self, id: &ExternalPskId) -> Result<bool, Self::Error> {
+        self.inner
+            .contains(id)
+            .await
+            .map_err(|e| KmsError::StorageError(e.into_any_error()))
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use aws_sdk_kms::{
+    primitives::Blob,
+    types::{MessageType, SigningAlgorithmSpec},
+    Client,
+};
+use mls_rs_core::crypto::{CipherSuite, SignaturePublicKey, SignatureSecretKey, Signer};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::{kms::Kms, KmsError};
+
+/// Signer using asymmetric AWS KMS keys of the `SIGN_VERIFY` usage.
+///
+/// The [`SignatureSecretKey`] given to mls-rs is the UTF-8 encoded id, ARN
+/// or alias of the KMS key, created with [`KmsSigner::secret_key`]. Only the
+/// cipher suites using ECDSA with the NIST curves are supported, with keys
+/// of the `ECC_NIST_P256`, `ECC_NIST_P384` and `ECC_NIST_P521` specs
+/// respectively.
+///
+/// Data is hashed locally and only its digest is sent to KMS, which keeps
+/// requests small and lifts the size limit KMS puts on signed messages.
+#[derive(Clone, Debug)]
+pub struct KmsSigner {
+    kms: Kms,
+}
+
+impl KmsSigner {
+    /// Sign with the keys of `client`.
+    #[cfg(mls_build_async)]
+    pub fn new(client: Client) -> Self {
+        Self {
+            kms: Kms::new(client),
+        }
+    }
+
+    /// Sign with the keys of `client`, blocking on `runtime` for requests
+    /// to KMS.
+    #[cfg(not(mls_build_async))]
+    pub fn new(client: Client, runtime: tokio::runtime::Handle) -> Self {
+        Self {
+            kms: Kms::new(client, runtime),
+        }
+    }
+
+    /// Handle of the KMS key `key_id` to give to mls-rs as signature
+    /// secret key.
+    pub fn secret_key(key_id: &str) -> SignatureSecretKey {
+        SignatureSecretKey::new_slice(key_id.as_bytes())
+    }
+
+    /// Public key of the KMS key `key_id`, in the format used by
+    /// `cipher_suite`, to create the signing identity of a member.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn public_key(
+        &self,
+        key_id: &str,
+        cipher_suite: CipherSuite,
+    ) -> Result<SignaturePublicKey, KmsError> {
+        let scheme = SignatureScheme::new(cipher_suite)?;
+
+        let output = self
+            .kms
+            .run(self.kms.client.get_public_key().key_id(key_id).send())
+            .await?;
+
+        let spki = output
+            .public_key()
+            .ok_or(KmsError::MissingResponseField("public key"))?;
+
+        scheme.public_key_from_spki(spki.as_ref())
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl Signer for KmsSigner {
+    type Error = KmsError;
+
+    async fn sign(
+        &self,
+        cipher_suite: CipherSuite,
+        key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        let scheme = SignatureScheme::new(cipher_suite)?;
+        let key_id = std::str::from_utf8(key.as_bytes()).map_err(|_| KmsError::InvalidKeyId)?;
+
+        let request = self
+            .kms
+            .client
+            .sign()
+            .key_id(key_id)
+            .message(Blob::new(scheme.digest(data)))
+            .message_type(MessageType::Digest)
+            .signing_algorithm(scheme.algorithm.clone())
+            .send();
+
+        let output = self.kms.run(request).await?;
+
+        // KMS returns DER encoded ECDSA signatures, as used by MLS.
+        output
+            .signature()
+            .map(|signature| signature.as_ref().to_vec())
+            .ok_or(KmsError::MissingResponseField("signature"))
+    }
+}
+
+#[derive(Debug)]
+struct SignatureScheme {
+    algorithm: SigningAlgorithmSpec,
+    digest: fn(&[u8]) -> Vec<u8>,
+    point_len: usize,
+}
+
+impl SignatureScheme {
+    fn new(cipher_suite: CipherSuite) -> Result<Self, KmsError> {
+        match cipher_suite {
+            CipherSuite::P256_AES128 => Ok(Self {
+                algorithm: SigningAlgorithmSpec::EcdsaSha256,
+                digest: |data| Sha256::digest(data).to_vec(),
+                point_len: 65,
+            }),
+            CipherSuite::P384_AES256 => Ok(Self {
+                algorithm: SigningAlgorithmSpec::EcdsaSha384,
+                digest: |data| Sha384::digest(data).to_vec(),
+                point_len: 97,
+            }),
+            CipherSuite::P521_AES256 => Ok(Self {
+                algorithm: SigningAlgorithmSpec::EcdsaSha512,
+                digest: |data| Sha512::digest(data).to_vec(),
+                point_len: 133,
+            }),
+            _ => Err(KmsError::UnsupportedCipherSuite(cipher_suite)),
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        (self.digest)(data)
+    }
+
+    // KMS returns a DER encoded SubjectPublicKeyInfo, which ends with the
+    // uncompressed point used by MLS, in a bit string without unused bits.
+    fn public_key_from_spki(&self, spki: &[u8]) -> Result<SignaturePublicKey, KmsError> {
+        let start = spki
+            .len()
+            .checked_sub(self.point_len)
+            .filter(|&start| start > 0 && spki[start - 1] == 0 && spki[start] == 0x04)
+            .ok_or(KmsError::InvalidPublicKey)?;
+
+        Ok(SignaturePublicKey::new_slice(&spki[start..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::CipherSuite;
+
+    use crate::KmsError;
+
+    use super::SignatureScheme;
+
+    const P256_SPKI_PREFIX: &str = "3059301306072a8648ce3d020106082a8648ce3d030107034200";
+
+    #[test]
+    fn public_key_is_extracted_from_spki() {
+        let point = [[0x04].as_slice(), &[7; 64]].concat();
+        let spki = [hex::decode(P256_SPKI_PREFIX).unwrap(), point.clone()].concat();
+
+        let scheme = SignatureScheme::new(CipherSuite::P256_AES128).unwrap();
+        let public_key = scheme.public_key_from_spki(&spki).unwrap();
+
+        assert_eq!(public_key.as_bytes(), point);
+        assert_eq!(scheme.digest(b"data").len(), 32);
+
+        let res = SignatureScheme::new(CipherSuite::P384_AES256)
+            .unwrap()
+            .public_key_from_spki(&spki);
+
+        assert_matches!(res, Err(KmsError::InvalidPublicKey));
+    }
+
+    #[test]
+    fn edwards_curves_are_not_supported() {
+        let res = SignatureScheme::new(CipherSuite::CURVE25519_AES128);
+        assert_matches!(res, Err(KmsError::UnsupportedCipherSuite(_)));
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use postgres::{Client, Config, NoTls};
+
+use crate::PostgresDataStorageError;
+
+/// Trait that helps to set up a PostgreSQL database connection.
+///
+/// Implement it to connect with TLS, or to set session parameters such as
+/// the `search_path` the tables are created in.
+pub trait ConnectionStrategy {
+    /// Connect to the PostgreSQL database.
+    fn make_connection(&self) -> Result<Client, PostgresDataStorageError>;
+}
+
+#[derive(Clone, Debug)]
+/// Connection strategy that connects without TLS, e.g. to a database on the
+/// same host.
+pub struct NoTlsConnectionStrategy {
+    config: Config,
+}
+
+impl NoTlsConnectionStrategy {
+    pub fn new(config: Config) -> NoTlsConnectionStrategy {
+        NoTlsConnectionStrategy { config }
+    }
+}
+
+impl ConnectionStrategy for NoTlsConnectionStrategy {
+    fn make_connection(&self) -> Result<Client, PostgresDataStorageError> {
+        Ok(self.config.connect(NoTls)?)
+    }
+}
@@ -0,0 +1,580 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+use postgres::Client;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    sync::{Arc, Mutex},
+};
+
+use crate::PostgresDataStorageError;
+
+pub(crate) const DEFAULT_EPOCH_RETENTION_LIMIT: u64 = 3;
+
+const SAVEPOINT: &str = "mls_rs";
+
+struct Connection {
+    client: Client,
+    /// Version of each group as last read or written through this connection.
+    versions: HashMap<Vec<u8>, i64>,
+    /// Versions before the current transaction, restored on rollback.
+    saved_versions: Option<HashMap<Vec<u8>, i64>>,
+}
+
+impl Connection {
+    /// Run `f` in a database transaction, or in a savepoint of the current
+    /// one, so that it is applied entirely or not at all.
+    fn atomically<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, PostgresDataStorageError>,
+    ) -> Result<T, PostgresDataStorageError> {
+        let (begin, commit, rollback) = if self.saved_versions.is_some() {
+            (
+                format!("SAVEPOINT {SAVEPOINT}"),
+                format!("RELEASE SAVEPOINT {SAVEPOINT}"),
+                format!("ROLLBACK TO SAVEPOINT {SAVEPOINT}"),
+            )
+        } else {
+            ("BEGIN".into(), "COMMIT".into(), "ROLLBACK".into())
+        };
+
+        self.client.batch_execute(&begin)?;
+
+        match f(self) {
+            Ok(res) => {
+                self.client.batch_execute(&commit)?;
+                Ok(res)
+            }
+            Err(e) => {
+                let _ = self.client.batch_execute(&rollback);
+                Err(e)
+            }
+        }
+    }
+
+    /// Lock the row of the group until the end of the transaction, and
+    /// return its version.
+    fn lock_group(&mut self, group_id: &[u8]) -> Result<Option<i64>, PostgresDataStorageError> {
+        self.client
+            .query_opt(
+                "SELECT version FROM mls_group WHERE group_id = $1 FOR UPDATE",
+                &[&group_id],
+            )?
+            .map(|row| row.try_get(0).map_err(data_conversion_error))
+            .transpose()
+    }
+
+    /// Lock the row of the group and check that its version is the one last
+    /// read or written through this connection. Groups that were neither
+    /// read nor written through this connection are not checked.
+    fn lock_known_group(
+        &mut self,
+        group_id: &[u8],
+    ) -> Result<Option<i64>, PostgresDataStorageError> {
+        let current = self.lock_group(group_id)?;
+
+        match self.versions.get(group_id) {
+            Some(expected) if current != Some(*expected) => {
+                Err(PostgresDataStorageError::ConcurrentModification)
+            }
+            _ => Ok(current),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// PostgreSQL Storage for MLS group states.
+///
+/// Each group has a version, incremented by every write, including the
+/// deletion of prior epochs. A write fails with
+/// [`PostgresDataStorageError::ConcurrentModification`] unless the version of
+/// the group is the one this storage last read or wrote, the row of the group
+/// being locked with `SELECT ... FOR UPDATE` until the write is committed.
+/// A group loaded from this storage can thus only be saved if no other
+/// storage saved it in the meantime. Deletions are checked the same way,
+/// except for groups that this storage never read nor wrote, which can be
+/// deleted without loading them first.
+pub struct PostgresGroupStateStorage {
+    connection: Arc<Mutex<Connection>>,
+    max_epoch_retention: u64,
+}
+
+impl Debug for PostgresGroupStateStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostgresGroupStateStorage")
+            .field("max_epoch_retention", &self.max_epoch_retention)
+            .finish()
+    }
+}
+
+impl PostgresGroupStateStorage {
+    pub(crate) fn new(client: Client) -> PostgresGroupStateStorage {
+        PostgresGroupStateStorage {
+            connection: Arc::new(Mutex::new(Connection {
+                client,
+                versions: HashMap::new(),
+                saved_versions: None,
+            })),
+            max_epoch_retention: DEFAULT_EPOCH_RETENTION_LIMIT,
+        }
+    }
+
+    pub fn with_max_epoch_retention(self, max_epoch_retention: u64) -> Self {
+        Self {
+            connection: self.connection,
+            max_epoch_retention,
+        }
+    }
+
+    pub fn max_epoch_retention(&self) -> u64 {
+        self.max_epoch_retention
+    }
+
+    /// List all the group ids for groups that are stored.
+    pub fn group_ids(&self) -> Result<Vec<Vec<u8>>, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        connection
+            .client
+            .query("SELECT group_id FROM mls_group", &[])?
+            .iter()
+            .map(|row| row.try_get(0).map_err(data_conversion_error))
+            .collect()
+    }
+
+    /// Delete a group from storage, returning whether it existed.
+    pub fn delete_group(&self, group_id: &[u8]) -> Result<bool, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let deleted = connection.atomically(|connection| {
+            connection.lock_known_group(group_id)?;
+
+            Ok(connection
+                .client
+                .execute("DELETE FROM mls_group WHERE group_id = $1", &[&group_id])?)
+        })?;
+
+        connection.versions.remove(group_id);
+
+        Ok(deleted > 0)
+    }
+
+    fn get_snapshot_data(
+        &self,
+        group_id: &[u8],
+    ) -> Result<Option<Vec<u8>>, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let row = connection.client.query_opt(
+            "SELECT snapshot, version FROM mls_group WHERE group_id = $1",
+            &[&group_id],
+        )?;
+
+        let Some(row) = row else {
+            connection.versions.remove(group_id);
+            return Ok(None);
+        };
+
+        let snapshot = row.try_get(0).map_err(data_conversion_error)?;
+        let version = row.try_get(1).map_err(data_conversion_error)?;
+
+        connection.versions.insert(group_id.to_vec(), version);
+
+        Ok(Some(snapshot))
+    }
+
+    fn get_epoch_data(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u8>>, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        connection
+            .client
+            .query_opt(
+                "SELECT epoch_data FROM mls_epoch WHERE group_id = $1 AND epoch_id = $2",
+                &[&group_id, &to_sql_epoch_id(epoch_id)?],
+            )?
+            .map(|row| row.try_get(0).map_err(data_conversion_error))
+            .transpose()
+    }
+
+    fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let max_epoch_id: Option<i64> = connection
+            .client
+            .query_one(
+                "SELECT MAX(epoch_id) FROM mls_epoch WHERE group_id = $1",
+                &[&group_id],
+            )?
+            .try_get(0)
+            .map_err(data_conversion_error)?;
+
+        max_epoch_id.map(from_sql_epoch_id).transpose()
+    }
+
+    fn update_group_state(
+        &self,
+        group_id: &[u8],
+        group_snapshot: Vec<u8>,
+        inserts: Vec<EpochRecord>,
+        updates: Vec<EpochRecord>,
+    ) -> Result<(), PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let version = connection.atomically(|connection| {
+            let expected = connection.versions.get(group_id).copied();
+            let current = connection.lock_group(group_id)?;
+
+            if current != expected {
+                return Err(PostgresDataStorageError::ConcurrentModification);
+            }
+
+            let version = current.map_or(1, |version| version + 1);
+
+            let written = if current.is_some() {
+                connection.client.execute(
+                    "UPDATE mls_group SET snapshot = $2, version = $3 WHERE group_id = $1",
+                    &[&group_id, &group_snapshot, &version],
+                )?
+            } else {
+                // A concurrent writer may insert the group before us, as
+                // there is no row to lock yet.
+                connection.client.execute(
+                    "INSERT INTO mls_group (group_id, snapshot, version) VALUES ($1, $2, $3) \
+                     ON CONFLICT (group_id) DO NOTHING",
+                    &[&group_id, &group_snapshot, &version],
+                )?
+            };
+
+            if written == 0 {
+                return Err(PostgresDataStorageError::ConcurrentModification);
+            }
+
+            let mut max_epoch_id = None;
+
+            // Insert new epochs as needed
+            for epoch in inserts {
+                max_epoch_id = Some(epoch.id);
+
+                connection.client.execute(
+                    "INSERT INTO mls_epoch (group_id, epoch_id, epoch_data) VALUES ($1, $2, $3)",
+                    &[&group_id, &to_sql_epoch_id(epoch.id)?, &epoch.data],
+                )?;
+            }
+
+            // Update existing epochs as needed
+            for epoch in updates {
+                connection.client.execute(
+                    "UPDATE mls_epoch SET epoch_data = $3 WHERE group_id = $1 AND epoch_id = $2",
+                    &[&group_id, &to_sql_epoch_id(epoch.id)?, &epoch.data],
+                )?;
+            }
+
+            // Delete old epochs as needed
+            if let Some(max_epoch_id) = max_epoch_id {
+                if max_epoch_id >= self.max_epoch_retention {
+                    let delete_under = max_epoch_id - self.max_epoch_retention;
+
+                    connection.client.execute(
+                        "DELETE FROM mls_epoch WHERE group_id = $1 AND epoch_id <= $2",
+                        &[&group_id, &to_sql_epoch_id(delete_under)?],
+                    )?;
+                }
+            }
+
+            Ok(version)
+        })?;
+
+        connection.versions.insert(group_id.to_vec(), version);
+
+        Ok(())
+    }
+
+    fn delete_epochs_before(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Vec<u64>, PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let (mut deleted, version) = connection.atomically(|connection| {
+            let current = connection.lock_known_group(group_id)?;
+
+            let deleted = connection
+                .client
+                .query(
+                    "DELETE FROM mls_epoch WHERE group_id = $1 AND epoch_id < $2 \
+                     RETURNING epoch_id",
+                    &[&group_id, &to_sql_epoch_id(epoch_id)?],
+                )?
+                .iter()
+                .map(|row| {
+                    row.try_get(0)
+                        .map_err(data_conversion_error)
+                        .and_then(from_sql_epoch_id)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Other storages must not write a state still referencing the
+            // deleted epochs.
+            let version = match current {
+                Some(version) if !deleted.is_empty() => {
+                    connection.client.execute(
+                        "UPDATE mls_group SET version = $2 WHERE group_id = $1",
+                        &[&group_id, &(version + 1)],
+                    )?;
+
+                    Some(version + 1)
+                }
+                version => version,
+            };
+
+            Ok((deleted, version))
+        })?;
+
+        if let Some(version) = version {
+            connection.versions.insert(group_id.to_vec(), version);
+        }
+
+        deleted.sort_unstable();
+
+        Ok(deleted)
+    }
+
+    fn begin_transaction(&self) -> Result<(), PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        connection.client.batch_execute("BEGIN")?;
+        connection.saved_versions = Some(connection.versions.clone());
+
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        match connection.client.batch_execute("COMMIT") {
+            Ok(()) => {
+                connection.saved_versions = None;
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(versions) = connection.saved_versions.take() {
+                    connection.versions = versions;
+                }
+
+                Err(e.into())
+            }
+        }
+    }
+
+    fn rollback_transaction(&self) -> Result<(), PostgresDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        if let Some(versions) = connection.saved_versions.take() {
+            connection.versions = versions;
+        }
+
+        Ok(connection.client.batch_execute("ROLLBACK")?)
+    }
+}
+
+fn data_conversion_error(e: postgres::Error) -> PostgresDataStorageError {
+    PostgresDataStorageError::DataConversionError(e.into())
+}
+
+// Epoch ids are stored as `BIGINT`, PostgreSQL having no unsigned integers.
+fn to_sql_epoch_id(epoch_id: u64) -> Result<i64, PostgresDataStorageError> {
+    i64::try_from(epoch_id).map_err(|e| PostgresDataStorageError::DataConversionError(e.into()))
+}
+
+fn from_sql_epoch_id(epoch_id: i64) -> Result<u64, PostgresDataStorageError> {
+    u64::try_from(epoch_id).map_err(|e| PostgresDataStorageError::DataConversionError(e.into()))
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl GroupStateStorage for PostgresGroupStateStorage {
+    type Error = PostgresDataStorageError;
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        inserts: Vec<EpochRecord>,
+        updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        let group_id = state.id;
+        let snapshot_data = state.data;
+
+        self.update_group_state(&group_id, snapshot_data, inserts, updates)
+    }
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.get_snapshot_data(group_id)
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        self.max_epoch_id(group_id)
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.get_epoch_data(group_id, epoch_id)
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        PostgresGroupStateStorage::delete_epochs_before(self, group_id, epoch_id).map(Some)
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        PostgresGroupStateStorage::group_ids(self)
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        PostgresGroupStateStorage::delete_group(self, group_id)
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        PostgresGroupStateStorage::begin_transaction(self)
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        PostgresGroupStateStorage::commit_transaction(self)
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        PostgresGroupStateStorage::rollback_transaction(self)
+    }
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{test_utils::TestStrategy, PostgresDataStorageEngine};
+
+    use super::*;
+
+    fn test_state(data: &[u8]) -> GroupState {
+        GroupState {
+            id: b"group".to_vec(),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a PostgreSQL database set by MLS_RS_POSTGRES_URL"]
+    fn storage_test_suite() {
+        let strategy = TestStrategy::new();
+
+        let mut storage = PostgresDataStorageEngine::new(strategy)
+            .unwrap()
+            .group_state_storage()
+            .unwrap();
+
+        mls_rs_core::group::test_suite::verify_group_state_storage(&mut storage);
+    }
+
+    #[test]
+    #[ignore = "requires a PostgreSQL database set by MLS_RS_POSTGRES_URL"]
+    fn concurrent_writes_are_rejected() {
+        let strategy = TestStrategy::new();
+
+        let engine = PostgresDataStorageEngine::new(strategy).unwrap();
+        let mut storage = engine.group_state_storage().unwrap();
+        let mut other_storage = engine.group_state_storage().unwrap();
+
+        storage.write(test_state(b"0"), vec![], vec![]).unwrap();
+
+        assert_eq!(other_storage.state(b"group").unwrap(), Some(b"0".to_vec()));
+        other_storage
+            .write(test_state(b"1"), vec![], vec![])
+            .unwrap();
+
+        // `storage` writes a state based on the one it wrote, not the latest.
+        let res = storage.write(test_state(b"2"), vec![], vec![]);
+        assert_matches!(res, Err(PostgresDataStorageError::ConcurrentModification));
+
+        assert_eq!(storage.state(b"group").unwrap(), Some(b"1".to_vec()));
+        storage.write(test_state(b"2"), vec![], vec![]).unwrap();
+
+        // Creating a group that was already created is also rejected.
+        let mut new_storage = engine.group_state_storage().unwrap();
+        let res = new_storage.write(test_state(b"3"), vec![], vec![]);
+        assert_matches!(res, Err(PostgresDataStorageError::ConcurrentModification));
+
+        assert_eq!(storage.state(b"group").unwrap(), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    #[ignore = "requires a PostgreSQL database set by MLS_RS_POSTGRES_URL"]
+    fn rolled_back_writes_are_discarded() {
+        let strategy = TestStrategy::new();
+
+        let mut storage = PostgresDataStorageEngine::new(strategy)
+            .unwrap()
+            .group_state_storage()
+            .unwrap();
+
+        storage.write(test_state(b"0"), vec![], vec![]).unwrap();
+
+        storage.begin_transaction().unwrap();
+
+        storage
+            .write(test_state(b"1"), vec![EpochRecord::new(1, vec![])], vec![])
+            .unwrap();
+
+        assert_eq!(storage.state(b"group").unwrap(), Some(b"1".to_vec()));
+
+        storage.rollback_transaction().unwrap();
+
+        assert_eq!(storage.state(b"group").unwrap(), Some(b"0".to_vec()));
+        assert_eq!(storage.max_epoch_id(b"group").unwrap(), None);
+
+        // The version of the group is the one from before the transaction.
+        storage.write(test_state(b"2"), vec![], vec![]).unwrap();
+    }
+
+    #[test]
+    #[ignore = "requires a PostgreSQL database set by MLS_RS_POSTGRES_URL"]
+    fn concurrent_deletions_are_rejected() {
+        let strategy = TestStrategy::new();
+
+        let engine = PostgresDataStorageEngine::new(strategy).unwrap();
+        let mut storage = engine.group_state_storage().unwrap();
+        let mut other_storage = engine.group_state_storage().unwrap();
+
+        let epochs = vec![EpochRecord::new(1, vec![]), EpochRecord::new(2, vec![])];
+        storage.write(test_state(b"0"), epochs, vec![]).unwrap();
+
+        assert_eq!(other_storage.state(b"group").unwrap(), Some(b"0".to_vec()));
+
+        // Deleting epochs is a write of the group.
+        assert_eq!(storage.delete_epochs_before(b"group", 2).unwrap(), vec![1]);
+
+        let res = other_storage.write(test_state(b"1"), vec![], vec![]);
+        assert_matches!(res, Err(PostgresDataStorageError::ConcurrentModification));
+
+        let res = other_storage.delete_epochs_before(b"group", 3);
+        assert_matches!(res, Err(PostgresDataStorageError::ConcurrentModification));
+
+        let res = other_storage.delete_group(b"group");
+        assert_matches!(res, Err(PostgresDataStorageError::ConcurrentModification));
+
+        storage.write(test_state(b"1"), vec![], vec![]).unwrap();
+
+        // A storage that never read the group can delete it.
+        let new_storage = engine.group_state_storage().unwrap();
+        assert!(new_storage.delete_group(b"group").unwrap());
+        assert_eq!(storage.state(b"group").unwrap(), None);
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! PostgreSQL based [`GroupStateStorage`](mls_rs_core::group::GroupStateStorage)
+//! for servers running many instances against the same database.
+//!
+//! Concurrent writes of the same group by different instances are detected
+//! with row-level locks: a write fails with
+//! [`PostgresDataStorageError::ConcurrentModification`] if the group was
+//! written since the state it is based on was read. The group should then be
+//! reloaded and the message processed again.
+//!
+//! This crate uses the blocking [`postgres`] client, which must not be called
+//! from within a Tokio runtime.
+
+use connection_strategy::ConnectionStrategy;
+use group_state::PostgresGroupStateStorage;
+use thiserror::Error;
+
+mod group_state;
+mod migrations;
+
+/// Connection strategies.
+pub mod connection_strategy;
+
+/// PostgreSQL storage components.
+pub mod storage {
+    pub use crate::group_state::PostgresGroupStateStorage;
+}
+
+#[derive(Debug, Error)]
+/// PostgreSQL data storage error.
+pub enum PostgresDataStorageError {
+    #[error(transparent)]
+    /// PostgreSQL error.
+    SqlEngineError(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error(transparent)]
+    /// Stored data is not compatible with the expected data type.
+    DataConversionError(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("the group was modified by another writer since it was read")]
+    /// The group was written or deleted since its state was read.
+    ConcurrentModification,
+    #[error("unsupported schema version {0}")]
+    /// The database was migrated by a newer version of this crate.
+    UnsupportedSchemaVersion(i32),
+}
+
+impl mls_rs_core::error::IntoAnyError for PostgresDataStorageError {
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+impl From<postgres::Error> for PostgresDataStorageError {
+    fn from(e: postgres::Error) -> Self {
+        PostgresDataStorageError::SqlEngineError(e.into())
+    }
+}
+
+#[derive(Clone, Debug)]
+/// PostgreSQL data storage engine.
+pub struct PostgresDataStorageEngine<CS>
+where
+    CS: ConnectionStrategy,
+{
+    connection_strategy: CS,
+}
+
+impl<CS> PostgresDataStorageEngine<CS>
+where
+    CS: ConnectionStrategy,
+{
+    pub fn new(
+        connection_strategy: CS,
+    ) -> Result<PostgresDataStorageEngine<CS>, PostgresDataStorageError> {
+        Ok(PostgresDataStorageEngine {
+            connection_strategy,
+        })
+    }
+
+    fn create_connection(&self) -> Result<postgres::Client, PostgresDataStorageError> {
+        let mut client = self.connection_strategy.make_connection()?;
+        migrations::migrate(&mut client)?;
+        Ok(client)
+    }
+
+    /// Returns a struct that implements the `GroupStateStorage` trait for use in MLS.
+    ///
+    /// Each call opens a new connection. Clones of the returned storage share
+    /// its connection, and thus its transactions, so concurrent workers
+    /// should each use their own storage.
+    pub fn group_state_storage(
+        &self,
+    ) -> Result<PostgresGroupStateStorage, PostgresDataStorageError> {
+        Ok(PostgresGroupStateStorage::new(self.create_connection()?))
+    }
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+pub(crate) mod test_utils {
+    use std::{
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use postgres::{Client, NoTls};
+
+    use crate::{connection_strategy::ConnectionStrategy, PostgresDataStorageError};
+
+    /// Connection strategy using a fresh schema of the database set by the
+    /// `MLS_RS_POSTGRES_URL` environment variable. Tests using it are ignored
+    /// by default, and run with `cargo test -- --ignored`.
+    pub struct TestStrategy {
+        url: String,
+        schema: String,
+    }
+
+    impl TestStrategy {
+        pub fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+            let url = std::env::var("MLS_RS_POSTGRES_URL")
+                .expect("MLS_RS_POSTGRES_URL must be set to run this test");
+
+            let schema = format!(
+                "mls_rs_test_{}_{}",
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+
+            let mut client = Client::connect(&url, NoTls).unwrap();
+
+            client
+                .batch_execute(&format!(
+                    "DROP SCHEMA IF EXISTS {schema} CASCADE; CREATE SCHEMA {schema}"
+                ))
+                .unwrap();
+
+            Self { url, schema }
+        }
+    }
+
+    impl ConnectionStrategy for TestStrategy {
+        fn make_connection(&self) -> Result<Client, PostgresDataStorageError> {
+            let mut client = Client::connect(&self.url, NoTls)?;
+            client.batch_execute(&format!("SET search_path TO {}", self.schema))?;
+            Ok(client)
+        }
+    }
+
+    impl Drop for TestStrategy {
+        fn drop(&mut self) {
+            if let Ok(mut client) = Client::connect(&self.url, NoTls) {
+                let _ = client.batch_execute(&format!("DROP SCHEMA {} CASCADE", self.schema));
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Schema migrations, applied in order when connecting. The version of the
+//! schema is the number of migrations applied.
+
+use postgres::Client;
+
+use crate::PostgresDataStorageError;
+
+/// Key of the advisory lock serializing the migrations of the instances
+/// connecting at the same time.
+const MIGRATION_LOCK_KEY: i64 = 0x6d6c_735f_7273;
+
+const MIGRATIONS: &[&str] = &[
+    // Version 1
+    "CREATE TABLE mls_group (
+        group_id BYTEA PRIMARY KEY,
+        snapshot BYTEA NOT NULL,
+        version BIGINT NOT NULL
+    );
+    CREATE TABLE mls_epoch (
+        group_id BYTEA NOT NULL REFERENCES mls_group (group_id) ON DELETE CASCADE,
+        epoch_id BIGINT NOT NULL,
+        epoch_data BYTEA NOT NULL,
+        PRIMARY KEY (group_id, epoch_id)
+    );",
+];
+
+pub(crate) fn migrate(client: &mut Client) -> Result<(), PostgresDataStorageError> {
+    let mut transaction = client.transaction()?;
+
+    transaction.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_KEY])?;
+
+    transaction.batch_execute(
+        "CREATE TABLE IF NOT EXISTS mls_schema_version (version INTEGER NOT NULL)",
+    )?;
+
+    let current = transaction
+        .query_opt("SELECT version FROM mls_schema_version", &[])?
+        .map_or(0, |row| row.get::<_, i32>(0));
+
+    let applied = usize::try_from(current)
+        .ok()
+        .filter(|applied| *applied <= MIGRATIONS.len())
+        .ok_or(PostgresDataStorageError::UnsupportedSchemaVersion(current))?;
+
+    if applied == MIGRATIONS.len() {
+        return Ok(transaction.commit()?);
+    }
+
+    for migration in &MIGRATIONS[applied..] {
+        transaction.batch_execute(migration)?;
+    }
+
+    let version = MIGRATIONS.len() as i32;
+
+    transaction.execute("DELETE FROM mls_schema_version", &[])?;
+
+    transaction.execute(
+        "INSERT INTO mls_schema_version (version) VALUES ($1)",
+        &[&version],
+    )?;
+
+    Ok(transaction.commit()?)
+}
+
+#[cfg(all(test, not(mls_build_async)))]
+mod tests {
+    use crate::{connection_strategy::ConnectionStrategy, test_utils::TestStrategy};
+
+    use super::{migrate, MIGRATIONS};
+
+    #[test]
+    #[ignore = "requires a PostgreSQL database set by MLS_RS_POSTGRES_URL"]
+    fn migrations_are_applied_once() {
+        let strategy = TestStrategy::new();
+
+        let mut client = strategy.make_connection().unwrap();
+
+        migrate(&mut client).unwrap();
+        migrate(&mut client).unwrap();
+
+        let version: i32 = client
+            .query_one("SELECT version FROM mls_schema_version", &[])
+            .unwrap()
+            .get(0);
+
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+}
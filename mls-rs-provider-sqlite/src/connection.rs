@@ -0,0 +1,103 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use rusqlite::Connection;
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Condvar, Mutex, MutexGuard},
+    thread::{self, ThreadId},
+};
+
+use crate::SqLiteDataStorageError;
+
+/// Connection that can be shared between storages, on which a transaction
+/// belongs to the thread that began it.
+///
+/// While a transaction is open, other threads wait for it to end before using
+/// the connection, so that only the writes of the thread owning the
+/// transaction are committed or rolled back with it.
+#[derive(Debug)]
+pub(crate) struct SharedConnection {
+    state: Mutex<ConnectionState>,
+    transaction_ended: Condvar,
+}
+
+#[derive(Debug)]
+struct ConnectionState {
+    connection: Connection,
+    transaction_owner: Option<ThreadId>,
+}
+
+/// Exclusive access to the connection, until dropped.
+pub(crate) struct ConnectionGuard<'a>(MutexGuard<'a, ConnectionState>);
+
+impl Deref for ConnectionGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.0.connection
+    }
+}
+
+impl DerefMut for ConnectionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.0.connection
+    }
+}
+
+impl SharedConnection {
+    pub(crate) fn new(connection: Connection) -> Self {
+        Self {
+            state: Mutex::new(ConnectionState {
+                connection,
+                transaction_owner: None,
+            }),
+            transaction_ended: Condvar::new(),
+        }
+    }
+
+    /// Lock the connection, after waiting for the transaction of another
+    /// thread to end, if any.
+    pub(crate) fn lock(&self) -> ConnectionGuard<'_> {
+        let current = thread::current().id();
+        let mut state = self.state.lock().unwrap();
+
+        while matches!(state.transaction_owner, Some(owner) if owner != current) {
+            state = self.transaction_ended.wait(state).unwrap();
+        }
+
+        ConnectionGuard(state)
+    }
+
+    /// Begin a transaction belonging to the current thread.
+    pub(crate) fn begin_transaction(&self) -> Result<(), SqLiteDataStorageError> {
+        let mut connection = self.lock();
+
+        connection
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        connection.0.transaction_owner = Some(thread::current().id());
+
+        Ok(())
+    }
+
+    /// End the transaction of the current thread with `sql`, i.e. `COMMIT` or
+    /// `ROLLBACK`. If it fails and the transaction remains open, as after a
+    /// busy `COMMIT`, the current thread keeps owning it.
+    pub(crate) fn end_transaction(&self, sql: &str) -> Result<(), SqLiteDataStorageError> {
+        let mut connection = self.lock();
+
+        let res = connection
+            .execute_batch(sql)
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()));
+
+        if connection.is_autocommit() {
+            connection.0.transaction_owner = None;
+            self.transaction_ended.notify_all();
+        }
+
+        res
+    }
+}
@@ -7,21 +7,19 @@ use mls_rs_core::{
     mls_rs_codec::MlsEncode,
 };
 use rusqlite::{params, Connection, OptionalExtension};
-use std::{
-    fmt::Debug,
-    sync::{Arc, Mutex},
-};
+use std::{fmt::Debug, sync::Arc};
 
-use crate::SqLiteDataStorageError;
+use crate::{connection::SharedConnection, SqLiteDataStorageError};
 
 pub(crate) const DEFAULT_EPOCH_RETENTION_LIMIT: u64 = 3;
 
 #[derive(Debug, Clone)]
 /// SQLite Storage for MLS group states.
 pub struct SqLiteGroupStateStorage {
-    connection: Arc<Mutex<Connection>>,
+    connection: Arc<SharedConnection>,
     max_epoch_retention: u64,
     state_context: Option<Vec<u8>>,
+    transactions: bool,
 }
 
 impl SqLiteGroupStateStorage {
@@ -30,23 +28,43 @@ impl SqLiteGroupStateStorage {
         state_context: Option<Vec<u8>>,
     ) -> SqLiteGroupStateStorage {
         SqLiteGroupStateStorage {
-            connection: Arc::new(Mutex::new(connection)),
+            connection: Arc::new(SharedConnection::new(connection)),
+            max_epoch_retention: DEFAULT_EPOCH_RETENTION_LIMIT,
+            state_context,
+            transactions: false,
+        }
+    }
+
+    /// Storage using a connection shared with a key package storage.
+    ///
+    /// Only such a storage makes the transactions of
+    /// [`GroupStateStorage::begin_transaction`] database transactions: with
+    /// separate connections, the key package storage couldn't write to the
+    /// database while the transaction is open. The transaction belongs to
+    /// the thread that began it, and storages sharing the connection wait for
+    /// it to end when used from other threads.
+    pub(crate) fn shared(
+        connection: Arc<SharedConnection>,
+        state_context: Option<Vec<u8>>,
+    ) -> SqLiteGroupStateStorage {
+        SqLiteGroupStateStorage {
+            connection,
             max_epoch_retention: DEFAULT_EPOCH_RETENTION_LIMIT,
             state_context,
+            transactions: true,
         }
     }
 
     pub fn with_max_epoch_retention(self, max_epoch_retention: u64) -> Self {
         Self {
-            connection: self.connection,
             max_epoch_retention,
-            state_context: self.state_context,
+            ..self
         }
     }
 
     /// List all the group ids for groups that are stored.
     pub fn group_ids(&self) -> Result<Vec<Vec<u8>>, SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let mut statement = connection
             .prepare("SELECT group_id FROM mls_group")
@@ -66,7 +84,7 @@ impl SqLiteGroupStateStorage {
 
     /// Delete a group from storage.
     pub fn delete_group(&self, group_id: &[u8]) -> Result<(), SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let alternative_gid = self.alternative_group_id(group_id)?;
         let group_id = alternative_gid.as_deref().unwrap_or(group_id);
@@ -88,7 +106,7 @@ impl SqLiteGroupStateStorage {
         &self,
         group_id: &[u8],
     ) -> Result<Option<Vec<u8>>, SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let alternative_gid = self.alternative_group_id(group_id)?;
         let group_id = alternative_gid.as_deref().unwrap_or(group_id);
@@ -110,7 +128,7 @@ impl SqLiteGroupStateStorage {
         group_id: &[u8],
         epoch_id: u64,
     ) -> Result<Option<Vec<u8>>, SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let alternative_gid = self.alternative_group_id(group_id)?;
         let group_id = alternative_gid.as_deref().unwrap_or(group_id);
@@ -126,7 +144,7 @@ impl SqLiteGroupStateStorage {
     }
 
     fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         connection
             .query_row(
@@ -153,9 +171,12 @@ impl SqLiteGroupStateStorage {
 
         // println!("alternative gid {:?}", group_id);
 
-        let mut connection = self.connection.lock().unwrap();
+        let mut connection = self.connection.lock();
+
+        // A savepoint is a transaction of its own, or nested in the one
+        // started by `begin_transaction`.
         let transaction = connection
-            .transaction()
+            .savepoint()
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
 
         // Upsert into the group table to set the most recent snapshot
@@ -208,6 +229,59 @@ impl SqLiteGroupStateStorage {
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
 
+    fn delete_epochs_before(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Vec<u64>, SqLiteDataStorageError> {
+        let alternative_gid = self.alternative_group_id(group_id)?;
+        let group_id = alternative_gid.as_deref().unwrap_or(group_id);
+
+        let mut connection = self.connection.lock();
+
+        // Have SQLite overwrite deleted content instead of only unlinking it.
+        connection
+            .pragma_update(None, "secure_delete", true)
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let transaction = connection
+            .savepoint()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let deleted = transaction
+            .prepare(
+                "SELECT epoch_id FROM epoch WHERE group_id = ? AND epoch_id < ? ORDER BY epoch_id",
+            )
+            .and_then(|mut statement| {
+                let deleted = statement
+                    .query_map(params![group_id, epoch_id], |row| row.get(0))?
+                    .collect::<Result<Vec<u64>, _>>();
+
+                deleted
+            })
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        transaction
+            .execute(
+                "UPDATE epoch SET epoch_data = zeroblob(length(epoch_data)) WHERE group_id = ? AND epoch_id < ?",
+                params![group_id, epoch_id],
+            )
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        transaction
+            .execute(
+                "DELETE FROM epoch WHERE group_id = ? AND epoch_id < ?",
+                params![group_id, epoch_id],
+            )
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        transaction
+            .commit()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        Ok(deleted)
+    }
+
     fn alternative_group_id(
         &self,
         group_id: &[u8],
@@ -251,6 +325,48 @@ impl GroupStateStorage for SqLiteGroupStateStorage {
     async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
         self.get_epoch_data(group_id, epoch_id)
     }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        SqLiteGroupStateStorage::delete_epochs_before(self, group_id, epoch_id).map(Some)
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        SqLiteGroupStateStorage::group_ids(self)
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        let exists = self.get_snapshot_data(group_id)?.is_some();
+        SqLiteGroupStateStorage::delete_group(self, group_id)?;
+        Ok(exists)
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        if !self.transactions {
+            return Ok(());
+        }
+
+        self.connection.begin_transaction()
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        if !self.transactions {
+            return Ok(());
+        }
+
+        self.connection.end_transaction("COMMIT")
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        if !self.transactions {
+            return Ok(());
+        }
+
+        self.connection.end_transaction("ROLLBACK")
+    }
 }
 
 #[cfg(test)]
@@ -440,6 +556,130 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn epochs_can_be_deleted() {
+        let test_data = setup_group_storage_test();
+
+        test_data
+            .storage
+            .update_group_state(
+                &test_data.group_id,
+                test_snapshot(),
+                vec![test_epoch(1), test_epoch(2)],
+                vec![],
+            )
+            .unwrap();
+
+        let deleted = test_data
+            .storage
+            .delete_epochs_before(&test_data.group_id, 2)
+            .unwrap();
+
+        assert_eq!(deleted, vec![0, 1]);
+
+        assert!(test_data
+            .storage
+            .get_epoch_data(&test_data.group_id, 1)
+            .unwrap()
+            .is_none());
+
+        assert!(test_data
+            .storage
+            .get_epoch_data(&test_data.group_id, 2)
+            .unwrap()
+            .is_some());
+    }
+
+    #[cfg(not(mls_build_async))]
+    #[test]
+    fn transaction_includes_key_package_deletion() {
+        use mls_rs_core::key_package::{KeyPackageData, KeyPackageStorage};
+
+        let (mut storage, mut key_packages) = SqLiteDataStorageEngine::new(MemoryStrategy)
+            .unwrap()
+            .group_state_and_key_package_storage()
+            .unwrap();
+
+        let key_package = KeyPackageData::new(vec![], vec![].into(), vec![].into(), 0);
+        KeyPackageStorage::insert(&mut key_packages, b"key package".to_vec(), key_package).unwrap();
+
+        storage.begin_transaction().unwrap();
+
+        storage
+            .write(
+                GroupState {
+                    id: b"group".to_vec(),
+                    data: test_snapshot(),
+                },
+                vec![test_epoch(0)],
+                vec![],
+            )
+            .unwrap();
+
+        KeyPackageStorage::delete(&mut key_packages, b"key package").unwrap();
+
+        storage.rollback_transaction().unwrap();
+
+        assert!(storage.state(b"group").unwrap().is_none());
+        assert!(KeyPackageStorage::get(&key_packages, b"key package")
+            .unwrap()
+            .is_some());
+    }
+
+    #[cfg(not(mls_build_async))]
+    #[test]
+    fn transactions_of_interleaved_groups_are_isolated() {
+        use std::{thread, time::Duration};
+
+        let (mut storage, _) = SqLiteDataStorageEngine::new(MemoryStrategy)
+            .unwrap()
+            .group_state_and_key_package_storage()
+            .unwrap();
+
+        let group_state = |id: &[u8]| GroupState {
+            id: id.to_vec(),
+            data: test_snapshot(),
+        };
+
+        storage.begin_transaction().unwrap();
+
+        storage
+            .write(group_state(b"group a"), vec![test_epoch(0)], vec![])
+            .unwrap();
+
+        // Another group writes from another thread while the transaction of
+        // the first one is open.
+        let mut other_storage = storage.clone();
+
+        let other = thread::spawn(move || {
+            other_storage.begin_transaction().unwrap();
+
+            other_storage
+                .write(group_state(b"group b"), vec![test_epoch(0)], vec![])
+                .unwrap();
+
+            other_storage.commit_transaction().unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        // The other thread waits for the transaction to end.
+        assert!(storage.state(b"group b").unwrap().is_none());
+
+        storage.rollback_transaction().unwrap();
+        other.join().unwrap();
+
+        assert!(storage.state(b"group a").unwrap().is_none());
+        assert!(storage.state(b"group b").unwrap().is_some());
+    }
+
+    #[cfg(not(mls_build_async))]
+    #[test]
+    fn storage_test_kit() {
+        let mut storage = get_test_storage();
+        mls_rs_core::group::test_suite::verify_group_state_storage(&mut storage);
+    }
+
     #[test]
     fn max_epoch_is_none_for_non_persisted_group() {
         let storage = get_test_storage();
@@ -8,21 +8,23 @@ use mls_rs_core::{
     time::MlsTime,
 };
 use rusqlite::{params, Connection, OptionalExtension};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::SqLiteDataStorageError;
+use crate::{connection::SharedConnection, SqLiteDataStorageError};
 
 #[derive(Debug, Clone)]
 /// SQLite storage for MLS Key Packages.
 pub struct SqLiteKeyPackageStorage {
-    connection: Arc<Mutex<Connection>>,
+    connection: Arc<SharedConnection>,
 }
 
 impl SqLiteKeyPackageStorage {
     pub(crate) fn new(connection: Connection) -> SqLiteKeyPackageStorage {
-        SqLiteKeyPackageStorage {
-            connection: Arc::new(Mutex::new(connection)),
-        }
+        Self::shared(Arc::new(SharedConnection::new(connection)))
+    }
+
+    pub(crate) fn shared(connection: Arc<SharedConnection>) -> SqLiteKeyPackageStorage {
+        SqLiteKeyPackageStorage { connection }
     }
 
     fn insert(
@@ -30,7 +32,7 @@ impl SqLiteKeyPackageStorage {
         id: &[u8],
         key_package: KeyPackageData,
     ) -> Result<(), SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         connection
             .execute(
@@ -48,7 +50,7 @@ impl SqLiteKeyPackageStorage {
     }
 
     fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         connection
             .query_row(
@@ -67,7 +69,7 @@ impl SqLiteKeyPackageStorage {
 
     /// Delete a specific key package from storage based on it's id.
     pub fn delete(&self, id: &[u8]) -> Result<(), SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         connection
             .execute("DELETE FROM key_package where id = ?", params![id])
@@ -75,12 +77,29 @@ impl SqLiteKeyPackageStorage {
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
 
+    /// List the ids of all stored key packages.
+    pub fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, SqLiteDataStorageError> {
+        let connection = self.connection.lock();
+
+        let mut statement = connection
+            .prepare("SELECT id FROM key_package")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let ids = statement
+            .query_map([], |row| row.get(0))
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SqLiteDataStorageError::DataConversionError(e.into()))?;
+
+        Ok(ids)
+    }
+
     pub fn delete_expired(&self) -> Result<(), SqLiteDataStorageError> {
         self.delete_expired_by_time(MlsTime::now().seconds_since_epoch())
     }
 
     pub fn delete_expired_by_time(&self, time: u64) -> Result<(), SqLiteDataStorageError> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         connection
             .execute(
@@ -108,6 +127,10 @@ impl KeyPackageStorage for SqLiteKeyPackageStorage {
     async fn delete(&mut self, id: &[u8]) -> Result<(), Self::Error> {
         (*self).delete(id)
     }
+
+    async fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        SqLiteKeyPackageStorage::key_package_ids(self)
+    }
 }
 
 #[cfg(test)]
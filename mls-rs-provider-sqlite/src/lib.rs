@@ -2,17 +2,22 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+use connection::SharedConnection;
 use connection_strategy::ConnectionStrategy;
 use group_state::SqLiteGroupStateStorage;
 use psk::SqLitePreSharedKeyStorage;
+use replay::SqLiteReplayStore;
 use rusqlite::Connection;
+use std::sync::Arc;
 use storage::{SqLiteApplicationStorage, SqLiteKeyPackageStorage};
 use thiserror::Error;
 
 mod application;
+mod connection;
 mod group_state;
 mod key_package;
 mod psk;
+mod replay;
 
 #[cfg(any(feature = "sqlcipher", feature = "sqlcipher-bundled"))]
 mod cipher;
@@ -30,6 +35,7 @@ pub mod storage {
         crate::group_state::SqLiteGroupStateStorage,
         crate::key_package::SqLiteKeyPackageStorage,
         crate::psk::SqLitePreSharedKeyStorage,
+        crate::replay::SqLiteReplayStore,
     };
 }
 
@@ -92,10 +98,14 @@ where
             .pragma_query_value(None, "user_version", |rows| rows.get::<_, u32>(0))
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
 
-        if current_schema != 1 {
+        if current_schema < 1 {
             create_tables_v1(&connection)?;
         }
 
+        if current_schema < 2 {
+            create_tables_v2(&connection)?;
+        }
+
         Ok(connection)
     }
 
@@ -112,6 +122,20 @@ where
         Ok(SqLiteKeyPackageStorage::new(self.create_connection()?))
     }
 
+    /// Returns a `GroupStateStorage` and a `KeyPackageStorage` sharing a
+    /// connection, so that the state of a group and the deletion of the key
+    /// package used to join it are written in a single transaction.
+    pub fn group_state_and_key_package_storage(
+        &self,
+    ) -> Result<(SqLiteGroupStateStorage, SqLiteKeyPackageStorage), SqLiteDataStorageError> {
+        let connection = Arc::new(SharedConnection::new(self.create_connection()?));
+
+        Ok((
+            SqLiteGroupStateStorage::shared(connection.clone(), self.group_state_context.clone()),
+            SqLiteKeyPackageStorage::shared(connection),
+        ))
+    }
+
     /// Returns a struct that implements the `PreSharedKeyStorage` trait for use in MLS.
     pub fn pre_shared_key_storage(
         &self,
@@ -119,6 +143,11 @@ where
         Ok(SqLitePreSharedKeyStorage::new(self.create_connection()?))
     }
 
+    /// Returns a struct that implements the `ReplayStore` trait for use in MLS.
+    pub fn replay_store(&self) -> Result<SqLiteReplayStore, SqLiteDataStorageError> {
+        Ok(SqLiteReplayStore::new(self.create_connection()?))
+    }
+
     /// Returns a key value store that can be used to store application specific data.
     pub fn application_data_storage(
         &self,
@@ -162,6 +191,22 @@ fn create_tables_v1(connection: &Connection) -> Result<(), SqLiteDataStorageErro
         .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
 }
 
+fn create_tables_v2(connection: &Connection) -> Result<(), SqLiteDataStorageError> {
+    connection
+        .execute_batch(
+            "BEGIN;
+            CREATE TABLE replay (
+                group_id BLOB,
+                epoch_id INTEGER,
+                message_id BLOB,
+                PRIMARY KEY (group_id, epoch_id, message_id)
+            ) WITHOUT ROWID;
+            PRAGMA user_version = 2;
+            COMMIT;",
+        )
+        .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{connection_strategy::MemoryStrategy, SqLiteDataStorageEngine};
@@ -180,6 +225,6 @@ mod tests {
             .pragma_query_value(None, "user_version", |rows| rows.get::<_, u32>(0))
             .unwrap();
 
-        assert_eq!(current_schema, 1);
+        assert_eq!(current_schema, 2);
     }
 }
@@ -0,0 +1,136 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use crate::SqLiteDataStorageError;
+use mls_rs_core::group::ReplayStore;
+use rusqlite::{params, Connection};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+/// SQLite storage for the messages already processed by MLS groups.
+pub struct SqLiteReplayStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqLiteReplayStore {
+    pub(crate) fn new(connection: Connection) -> SqLiteReplayStore {
+        SqLiteReplayStore {
+            connection: Arc::new(Mutex::new(connection)),
+        }
+    }
+
+    /// Delete the entries of `group_id` for epochs older than `epoch`.
+    pub fn delete_before(&self, group_id: &[u8], epoch: u64) -> Result<(), SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .execute(
+                "DELETE FROM replay WHERE group_id = ? AND epoch_id < ?",
+                params![group_id, epoch],
+            )
+            .map(|_| ())
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+
+    /// Determines if `message_id`, sent in `epoch` of `group_id`, is stored.
+    pub fn contains(
+        &self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<bool, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM replay WHERE group_id = ? AND epoch_id = ? AND message_id = ?)",
+                params![group_id, epoch, message_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+
+    /// Insert `message_id`, sent in `epoch` of `group_id`, into storage.
+    pub fn insert(
+        &self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<(), SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO replay (group_id, epoch_id, message_id) VALUES (?,?,?)",
+                params![group_id, epoch, message_id],
+            )
+            .map(|_| ())
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl ReplayStore for SqLiteReplayStore {
+    type Error = SqLiteDataStorageError;
+
+    async fn contains(
+        &self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<bool, Self::Error> {
+        SqLiteReplayStore::contains(self, group_id, epoch, message_id)
+    }
+
+    async fn insert(
+        &mut self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<(), Self::Error> {
+        SqLiteReplayStore::insert(self, group_id, epoch, message_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{connection_strategy::MemoryStrategy, SqLiteDataStorageEngine};
+
+    use super::SqLiteReplayStore;
+
+    fn test_storage() -> SqLiteReplayStore {
+        SqLiteDataStorageEngine::new(MemoryStrategy)
+            .unwrap()
+            .replay_store()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_insert() {
+        let storage = test_storage();
+
+        storage.insert(b"group", 1, b"message").unwrap();
+        storage.insert(b"group", 1, b"message").unwrap();
+
+        assert!(storage.contains(b"group", 1, b"message").unwrap());
+        assert!(!storage.contains(b"group", 2, b"message").unwrap());
+        assert!(!storage.contains(b"other group", 1, b"message").unwrap());
+    }
+
+    #[test]
+    fn test_delete_before() {
+        let storage = test_storage();
+
+        storage.insert(b"group", 1, b"message").unwrap();
+        storage.insert(b"group", 2, b"message").unwrap();
+        storage.insert(b"other group", 1, b"message").unwrap();
+
+        storage.delete_before(b"group", 2).unwrap();
+
+        assert!(!storage.contains(b"group", 1, b"message").unwrap());
+        assert!(storage.contains(b"group", 2, b"message").unwrap());
+        assert!(storage.contains(b"other group", 1, b"message").unwrap());
+    }
+}
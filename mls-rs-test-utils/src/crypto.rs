@@ -0,0 +1,270 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::sync::{Arc, Mutex};
+
+use mls_rs_core::crypto::{
+    CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey, HpkeSecretKey,
+    SignaturePublicKey, SignatureSecretKey,
+};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use zeroize::Zeroizing;
+
+/// Crypto provider drawing random bytes from a seeded generator.
+///
+/// The random bytes requested by mls-rs, for instance for path secrets or
+/// group ids, and the HPKE keys generated with
+/// [`kem_generate`](CipherSuiteProvider::kem_generate), are derived from the
+/// `seed`. Cipher suite providers obtained from clones of the provider share
+/// the same generator, so a test creating its groups in the same order gets
+/// the same groups on every run.
+///
+/// Signature keys and the ephemeral keys of HPKE encryption are still
+/// generated by the wrapped provider, which doesn't offer a way to derive
+/// them.
+#[derive(Clone, Debug)]
+pub struct DeterministicCryptoProvider<C> {
+    inner: C,
+    rng: Arc<Mutex<ChaCha20Rng>>,
+}
+
+impl<C> DeterministicCryptoProvider<C> {
+    /// Wrap `inner`, seeding the generator with `seed`.
+    pub fn new(inner: C, seed: u64) -> Self {
+        Self {
+            inner,
+            rng: Arc::new(Mutex::new(ChaCha20Rng::seed_from_u64(seed))),
+        }
+    }
+
+    /// Wrapped crypto provider.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: CryptoProvider> CryptoProvider for DeterministicCryptoProvider<C> {
+    type CipherSuiteProvider = DeterministicCipherSuiteProvider<C::CipherSuiteProvider>;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.inner.supported_cipher_suites()
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        self.inner.cipher_suite_provider(cipher_suite).map(|inner| {
+            DeterministicCipherSuiteProvider {
+                inner,
+                rng: self.rng.clone(),
+            }
+        })
+    }
+}
+
+/// Cipher suite provider of a [`DeterministicCryptoProvider`].
+#[derive(Clone, Debug)]
+pub struct DeterministicCipherSuiteProvider<P> {
+    inner: P,
+    rng: Arc<Mutex<ChaCha20Rng>>,
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<P: CipherSuiteProvider> CipherSuiteProvider for DeterministicCipherSuiteProvider<P> {
+    type Error = P::Error;
+
+    type HpkeContextS = P::HpkeContextS;
+    type HpkeContextR = P::HpkeContextR;
+
+    fn cipher_suite(&self) -> CipherSuite {
+        self.inner.cipher_suite()
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner.hash(data).await
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner.mac(key, data).await
+    }
+
+    async fn aead_seal(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner.aead_seal(key, data, aad, nonce).await
+    }
+
+    async fn aead_open(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner.aead_open(key, ciphertext, aad, nonce).await
+    }
+
+    fn aead_key_size(&self) -> usize {
+        self.inner.aead_key_size()
+    }
+
+    fn aead_nonce_size(&self) -> usize {
+        self.inner.aead_nonce_size()
+    }
+
+    async fn kdf_extract(
+        &self,
+        salt: &[u8],
+        ikm: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner.kdf_extract(salt, ikm).await
+    }
+
+    async fn kdf_expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner.kdf_expand(prk, info, len).await
+    }
+
+    fn kdf_extract_size(&self) -> usize {
+        self.inner.kdf_extract_size()
+    }
+
+    async fn hpke_seal(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+    ) -> Result<HpkeCiphertext, Self::Error> {
+        self.inner.hpke_seal(remote_key, info, aad, pt).await
+    }
+
+    async fn hpke_open(
+        &self,
+        ciphertext: &HpkeCiphertext,
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hpke_open(ciphertext, local_secret, local_public, info, aad)
+            .await
+    }
+
+    async fn hpke_setup_s(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self::HpkeContextS), Self::Error> {
+        self.inner.hpke_setup_s(remote_key, info).await
+    }
+
+    async fn hpke_setup_r(
+        &self,
+        kem_output: &[u8],
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<Self::HpkeContextR, Self::Error> {
+        self.inner
+            .hpke_setup_r(kem_output, local_secret, local_public, info)
+            .await
+    }
+
+    async fn kem_derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.inner.kem_derive(ikm).await
+    }
+
+    async fn kem_generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        let ikm = Zeroizing::new(self.random_bytes_vec(self.inner.kdf_extract_size())?);
+        self.inner.kem_derive(&ikm).await
+    }
+
+    fn kem_public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        self.inner.kem_public_key_validate(key)
+    }
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.rng.lock().unwrap().fill_bytes(out);
+        Ok(())
+    }
+
+    async fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        self.inner.signature_key_generate().await
+    }
+
+    async fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.inner.signature_key_derive_public(secret_key).await
+    }
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner.sign(secret_key, data).await
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.inner.verify(public_key, signature, data).await
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use mls_rs_core::crypto::{CipherSuite, CipherSuiteProvider, CryptoProvider};
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use super::DeterministicCryptoProvider;
+
+    fn provider(seed: u64) -> impl CipherSuiteProvider {
+        DeterministicCryptoProvider::new(OpensslCryptoProvider::new(), seed)
+            .cipher_suite_provider(CipherSuite::CURVE25519_AES128)
+            .unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn same_seed_gives_same_keys() {
+        let (first, second, other) = (provider(1), provider(1), provider(2));
+
+        assert_eq!(
+            first.random_bytes_vec(32).unwrap(),
+            second.random_bytes_vec(32).unwrap()
+        );
+
+        let first_key = first.kem_generate().await.unwrap().1;
+        let second_key = second.kem_generate().await.unwrap().1;
+        assert_eq!(first_key, second_key);
+
+        other.random_bytes_vec(32).unwrap();
+        let other_key = other.kem_generate().await.unwrap().1;
+        assert_ne!(first_key, other_key);
+    }
+}
@@ -0,0 +1,855 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mls_rs_core::{
+    crypto::{
+        CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey,
+        HpkeSecretKey, SignaturePublicKey, SignatureSecretKey,
+    },
+    error::IntoAnyError,
+    group::{EpochRecord, GroupState, GroupStateStorage},
+    key_package::{KeyPackageData, KeyPackageStorage},
+};
+use zeroize::Zeroizing;
+
+/// Error returned by the providers of this module.
+#[derive(Debug, thiserror::Error)]
+pub enum FaultError<E> {
+    /// The call failed because of a fault set with a [`FaultInjector`].
+    #[error("injected fault")]
+    Injected,
+    /// The call failed in the wrapped provider.
+    #[error("{0:?}")]
+    Inner(E),
+}
+
+impl<E> IntoAnyError for FaultError<E>
+where
+    E: IntoAnyError + Send + Sync + 'static,
+{
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Faults {
+    failing_write: Option<usize>,
+    failing_crypto_call: Option<usize>,
+    corrupted_reads: usize,
+    delay: Option<Duration>,
+    writes: usize,
+}
+
+/// Faults injected in the storage and crypto providers wrapped with it.
+///
+/// The same injector, or clones of it, can be given to several providers,
+/// for instance to the group state storage and the key package storage of a
+/// client, so that writes to both are counted together. This allows testing
+/// how an application recovers when a write fails in the middle of a commit,
+/// or when its storage returns data that was only partially written.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    faults: Arc<Mutex<Faults>>,
+}
+
+impl FaultInjector {
+    /// Create an injector with no faults.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Make the `n`-th write from now fail, `1` being the next one. Writes
+    /// are calls that modify the storage.
+    pub fn fail_write(&self, n: usize) {
+        self.faults.lock().unwrap().failing_write = Some(n);
+    }
+
+    /// Make the `n`-th fallible call to a cipher suite provider from now
+    /// fail, `1` being the next one.
+    pub fn fail_crypto_call(&self, n: usize) {
+        self.faults.lock().unwrap().failing_crypto_call = Some(n);
+    }
+
+    /// Truncate the data returned by the next read that finds data, as a torn
+    /// write would leave it.
+    pub fn corrupt_next_read(&self) {
+        self.faults.lock().unwrap().corrupted_reads += 1;
+    }
+
+    /// Make all calls wait for `delay` before being forwarded.
+    pub fn delay_calls(&self, delay: Duration) {
+        self.faults.lock().unwrap().delay = Some(delay);
+    }
+
+    /// Remove all faults that haven't been triggered yet, and stop delaying
+    /// calls.
+    pub fn clear(&self) {
+        let mut faults = self.faults.lock().unwrap();
+
+        *faults = Faults {
+            writes: faults.writes,
+            ..Default::default()
+        };
+    }
+
+    /// Number of writes made so far, including failed ones.
+    pub fn writes(&self) -> usize {
+        self.faults.lock().unwrap().writes
+    }
+
+    fn delay(&self) {
+        let delay = self.faults.lock().unwrap().delay;
+
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+        }
+    }
+
+    fn on_read<E>(&self) -> Result<(), FaultError<E>> {
+        self.delay();
+        Ok(())
+    }
+
+    fn on_write<E>(&self) -> Result<(), FaultError<E>> {
+        self.delay();
+
+        let mut faults = self.faults.lock().unwrap();
+        faults.writes += 1;
+
+        Self::countdown(&mut faults.failing_write)
+    }
+
+    fn on_crypto_call<E>(&self) -> Result<(), FaultError<E>> {
+        self.delay();
+        Self::countdown(&mut self.faults.lock().unwrap().failing_crypto_call)
+    }
+
+    fn corrupt(&self, data: &mut Vec<u8>) {
+        let mut faults = self.faults.lock().unwrap();
+
+        if faults.corrupted_reads > 0 {
+            faults.corrupted_reads -= 1;
+            data.truncate(data.len() / 2);
+        }
+    }
+
+    fn countdown<E>(remaining: &mut Option<usize>) -> Result<(), FaultError<E>> {
+        match remaining {
+            Some(n) if *n <= 1 => {
+                *remaining = None;
+                Err(FaultError::Injected)
+            }
+            Some(n) => {
+                *n -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Group state storage with faults injected by a [`FaultInjector`].
+///
+/// Calls to [`write`](GroupStateStorage::write) and
+/// [`delete_epochs_before`](GroupStateStorage::delete_epochs_before) are
+/// writes. A failing write isn't forwarded to the wrapped storage.
+#[derive(Clone, Debug)]
+pub struct FaultyGroupStateStorage<S> {
+    inner: S,
+    faults: FaultInjector,
+}
+
+impl<S> FaultyGroupStateStorage<S> {
+    /// Wrap `inner`, injecting the faults set with `faults`.
+    pub fn new(inner: S, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    /// Wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<S> GroupStateStorage for FaultyGroupStateStorage<S>
+where
+    S: GroupStateStorage,
+    S::Error: Send + Sync + 'static,
+{
+    type Error = FaultError<S::Error>;
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.faults.on_read()?;
+
+        let mut state = self
+            .inner
+            .state(group_id)
+            .await
+            .map_err(FaultError::Inner)?;
+
+        if let Some(state) = &mut state {
+            self.faults.corrupt(state);
+        }
+
+        Ok(state)
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.faults.on_read()?;
+
+        let mut epoch = self
+            .inner
+            .epoch(group_id, epoch_id)
+            .await
+            .map_err(FaultError::Inner)?;
+
+        if let Some(epoch) = &mut epoch {
+            self.faults.corrupt(epoch);
+        }
+
+        Ok(epoch)
+    }
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        epoch_inserts: Vec<EpochRecord>,
+        epoch_updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        self.faults.on_write()?;
+
+        self.inner
+            .write(state, epoch_inserts, epoch_updates)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        self.faults.on_read()?;
+        self.inner
+            .max_epoch_id(group_id)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        self.faults.on_write()?;
+
+        self.inner
+            .delete_epochs_before(group_id, epoch_id)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        self.faults.on_read()?;
+        self.inner.group_ids().await.map_err(FaultError::Inner)
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        self.faults.on_write()?;
+
+        self.inner
+            .delete_group(group_id)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn tree_record(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.faults.on_read()?;
+
+        let mut record = self
+            .inner
+            .tree_record(group_id, epoch_id)
+            .await
+            .map_err(FaultError::Inner)?;
+
+        if let Some(record) = &mut record {
+            self.faults.corrupt(record);
+        }
+
+        Ok(record)
+    }
+
+    async fn write_tree_records(
+        &mut self,
+        group_id: &[u8],
+        records: Vec<EpochRecord>,
+    ) -> Result<bool, Self::Error> {
+        self.faults.on_write()?;
+
+        self.inner
+            .write_tree_records(group_id, records)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn delete_tree_records_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<(), Self::Error> {
+        self.faults.on_write()?;
+
+        self.inner
+            .delete_tree_records_before(group_id, epoch_id)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .begin_transaction()
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .commit_transaction()
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .rollback_transaction()
+            .await
+            .map_err(FaultError::Inner)
+    }
+}
+
+/// Key package storage with faults injected by a [`FaultInjector`].
+///
+/// Calls to [`insert`](KeyPackageStorage::insert) and
+/// [`delete`](KeyPackageStorage::delete) are writes. A failing write isn't
+/// forwarded to the wrapped storage, and a corrupted read truncates the
+/// serialized key package.
+#[derive(Clone, Debug)]
+pub struct FaultyKeyPackageStorage<S> {
+    inner: S,
+    faults: FaultInjector,
+}
+
+impl<S> FaultyKeyPackageStorage<S> {
+    /// Wrap `inner`, injecting the faults set with `faults`.
+    pub fn new(inner: S, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    /// Wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<S> KeyPackageStorage for FaultyKeyPackageStorage<S>
+where
+    S: KeyPackageStorage,
+    S::Error: Send + Sync + 'static,
+{
+    type Error = FaultError<S::Error>;
+
+    async fn delete(&mut self, id: &[u8]) -> Result<(), Self::Error> {
+        self.faults.on_write()?;
+        self.inner.delete(id).await.map_err(FaultError::Inner)
+    }
+
+    async fn insert(&mut self, id: Vec<u8>, pkg: KeyPackageData) -> Result<(), Self::Error> {
+        self.faults.on_write()?;
+        self.inner.insert(id, pkg).await.map_err(FaultError::Inner)
+    }
+
+    async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error> {
+        self.faults.on_read()?;
+
+        let mut pkg = self.inner.get(id).await.map_err(FaultError::Inner)?;
+
+        if let Some(pkg) = &mut pkg {
+            self.faults.corrupt(&mut pkg.key_package_bytes);
+        }
+
+        Ok(pkg)
+    }
+
+    async fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        self.faults.on_read()?;
+        self.inner
+            .key_package_ids()
+            .await
+            .map_err(FaultError::Inner)
+    }
+}
+
+/// Crypto provider with faults injected by a [`FaultInjector`] in its
+/// cipher suite providers.
+#[derive(Clone, Debug)]
+pub struct FaultyCryptoProvider<C> {
+    inner: C,
+    faults: FaultInjector,
+}
+
+impl<C> FaultyCryptoProvider<C> {
+    /// Wrap `inner`, injecting the faults set with `faults`.
+    pub fn new(inner: C, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    /// Wrapped crypto provider.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C> CryptoProvider for FaultyCryptoProvider<C>
+where
+    C: CryptoProvider,
+    <C::CipherSuiteProvider as CipherSuiteProvider>::Error: Send + Sync + 'static,
+{
+    type CipherSuiteProvider = FaultyCipherSuiteProvider<C::CipherSuiteProvider>;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.inner.supported_cipher_suites()
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        self.inner
+            .cipher_suite_provider(cipher_suite)
+            .map(|inner| FaultyCipherSuiteProvider {
+                inner,
+                faults: self.faults.clone(),
+            })
+    }
+}
+
+/// Cipher suite provider of a [`FaultyCryptoProvider`].
+///
+/// All fallible calls but the ones made on HPKE contexts count as crypto
+/// calls for [`FaultInjector::fail_crypto_call`].
+#[derive(Clone, Debug)]
+pub struct FaultyCipherSuiteProvider<P> {
+    inner: P,
+    faults: FaultInjector,
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<P> CipherSuiteProvider for FaultyCipherSuiteProvider<P>
+where
+    P: CipherSuiteProvider,
+    P::Error: Send + Sync + 'static,
+{
+    type Error = FaultError<P::Error>;
+
+    type HpkeContextS = P::HpkeContextS;
+    type HpkeContextR = P::HpkeContextR;
+
+    fn cipher_suite(&self) -> CipherSuite {
+        self.inner.cipher_suite()
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner.hash(data).await.map_err(FaultError::Inner)
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner.mac(key, data).await.map_err(FaultError::Inner)
+    }
+
+    async fn aead_seal(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .aead_seal(key, data, aad, nonce)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn aead_open(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .aead_open(key, ciphertext, aad, nonce)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    fn aead_key_size(&self) -> usize {
+        self.inner.aead_key_size()
+    }
+
+    fn aead_nonce_size(&self) -> usize {
+        self.inner.aead_nonce_size()
+    }
+
+    async fn kdf_extract(
+        &self,
+        salt: &[u8],
+        ikm: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .kdf_extract(salt, ikm)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn kdf_expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .kdf_expand(prk, info, len)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    fn kdf_extract_size(&self) -> usize {
+        self.inner.kdf_extract_size()
+    }
+
+    async fn hpke_seal(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+    ) -> Result<HpkeCiphertext, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .hpke_seal(remote_key, info, aad, pt)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn hpke_open(
+        &self,
+        ciphertext: &HpkeCiphertext,
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .hpke_open(ciphertext, local_secret, local_public, info, aad)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn hpke_setup_s(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self::HpkeContextS), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .hpke_setup_s(remote_key, info)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn hpke_setup_r(
+        &self,
+        kem_output: &[u8],
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<Self::HpkeContextR, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .hpke_setup_r(kem_output, local_secret, local_public, info)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn kem_derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner.kem_derive(ikm).await.map_err(FaultError::Inner)
+    }
+
+    async fn kem_generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner.kem_generate().await.map_err(FaultError::Inner)
+    }
+
+    fn kem_public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .kem_public_key_validate(key)
+            .map_err(FaultError::Inner)
+    }
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner.random_bytes(out).map_err(FaultError::Inner)
+    }
+
+    async fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .signature_key_generate()
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .signature_key_derive_public(secret_key)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .sign(secret_key, data)
+            .await
+            .map_err(FaultError::Inner)
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.faults.on_crypto_call()?;
+        self.inner
+            .verify(public_key, signature, data)
+            .await
+            .map_err(FaultError::Inner)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use assert_matches::assert_matches;
+    use mls_rs::{
+        error::MlsError,
+        identity::{
+            basic::{BasicCredential, BasicIdentityProvider},
+            SigningIdentity,
+        },
+        storage_provider::in_memory::{InMemoryGroupStateStorage, InMemoryKeyPackageStorage},
+        CipherSuite, CipherSuiteProvider, Client, CryptoProvider,
+    };
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use super::{
+        FaultInjector, FaultyCryptoProvider, FaultyGroupStateStorage, FaultyKeyPackageStorage,
+    };
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn faulty_client(
+        faults: &FaultInjector,
+    ) -> Client<impl mls_rs::client_builder::MlsConfig> {
+        let crypto = FaultyCryptoProvider::new(OpensslCryptoProvider::new(), faults.clone());
+        let cs = crypto.cipher_suite_provider(CIPHER_SUITE).unwrap();
+        let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+
+        let credential = BasicCredential::new(b"alice".to_vec()).into_credential();
+        let storage =
+            FaultyGroupStateStorage::new(InMemoryGroupStateStorage::new(), faults.clone());
+
+        Client::builder()
+            .crypto_provider(crypto)
+            .identity_provider(BasicIdentityProvider::new())
+            .group_state_storage(storage)
+            .signing_identity(
+                SigningIdentity::new(credential, public_key),
+                secret_key,
+                CIPHER_SUITE,
+            )
+            .build()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn failed_write_keeps_previous_state() {
+        let faults = FaultInjector::new();
+        let client = faulty_client(&faults).await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+        group.write_to_storage().await.unwrap();
+
+        group.commit(Vec::new()).await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+
+        faults.fail_write(1);
+        let res = group.write_to_storage().await;
+        assert_matches!(res, Err(MlsError::GroupStorageError(_)));
+        assert_eq!(faults.writes(), 2);
+
+        let reloaded = client.load_group(group.group_id()).await.unwrap();
+        assert_eq!(reloaded.current_epoch(), 0);
+
+        group.write_to_storage().await.unwrap();
+        let reloaded = client.load_group(group.group_id()).await.unwrap();
+        assert_eq!(reloaded.current_epoch(), 1);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn failed_key_package_deletion_discards_joined_state() {
+        let alice = faulty_client(&FaultInjector::new()).await;
+
+        let crypto = OpensslCryptoProvider::new();
+        let cs = crypto.cipher_suite_provider(CIPHER_SUITE).unwrap();
+        let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+        let credential = BasicCredential::new(b"bob".to_vec()).into_credential();
+
+        let faults = FaultInjector::new();
+        let key_packages = InMemoryKeyPackageStorage::new();
+
+        let bob = Client::builder()
+            .crypto_provider(crypto)
+            .identity_provider(BasicIdentityProvider::new())
+            .key_package_repo(FaultyKeyPackageStorage::new(
+                key_packages.clone(),
+                faults.clone(),
+            ))
+            .signing_identity(
+                SigningIdentity::new(credential, public_key),
+                secret_key,
+                CIPHER_SUITE,
+            )
+            .build();
+
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        faults.fail_write(1);
+        let res = bob_group.write_to_storage().await;
+        assert_matches!(res, Err(MlsError::KeyPackageRepoError(_)));
+
+        // The state written before the deletion was rolled back.
+        let res = bob.load_group(bob_group.group_id()).await;
+        assert!(res.is_err());
+        assert_eq!(key_packages.key_packages().len(), 1);
+
+        bob_group.write_to_storage().await.unwrap();
+
+        let res = bob.load_group(bob_group.group_id()).await;
+        assert!(res.is_ok());
+        assert!(key_packages.key_packages().is_empty());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn corrupted_read_fails_loading() {
+        let faults = FaultInjector::new();
+        let client = faulty_client(&faults).await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+        group.write_to_storage().await.unwrap();
+
+        faults.corrupt_next_read();
+
+        let res = client.load_group(group.group_id()).await;
+        assert!(res.is_err());
+
+        let res = client.load_group(group.group_id()).await;
+        assert!(res.is_ok());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn failed_crypto_call_fails_commit() {
+        let faults = FaultInjector::new();
+        let client = faulty_client(&faults).await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+
+        faults.fail_crypto_call(3);
+        let res = group.commit(Vec::new()).await;
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+
+        group.commit(Vec::new()).await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+        assert_eq!(group.current_epoch(), 1);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn calls_are_delayed() {
+        let faults = FaultInjector::new();
+        let client = faulty_client(&faults).await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+
+        faults.delay_calls(Duration::from_millis(20));
+        let start = Instant::now();
+        group.write_to_storage().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        faults.clear();
+        let start = Instant::now();
+        group.write_to_storage().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::fmt::{self, Display};
+
+use mls_rs::{
+    client_builder::{BaseConfig, MlsConfig, WithCryptoProvider, WithIdentityProvider},
+    error::{IntoAnyError, MlsError},
+    group::CommitOutput,
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider, Group, MlsMessage,
+};
+
+/// State of a group on which two members disagree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Divergence {
+    /// The members are in different epochs.
+    Epoch,
+    /// The members have different group contexts, for instance because
+    /// they have different ratchet trees.
+    GroupContext,
+    /// The members derived different key schedules.
+    EpochAuthenticator,
+}
+
+impl Divergence {
+    pub(crate) fn between<C: MlsConfig>(a: &Group<C>, b: &Group<C>) -> Option<Self> {
+        if a.current_epoch() != b.current_epoch() {
+            Some(Self::Epoch)
+        } else if a.context() != b.context() {
+            Some(Self::GroupContext)
+        } else if a.epoch_authenticator().ok() != b.epoch_authenticator().ok() {
+            Some(Self::EpochAuthenticator)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Epoch => "epoch",
+            Self::GroupContext => "group context",
+            Self::EpochAuthenticator => "epoch authenticator",
+        })
+    }
+}
+
+/// Configuration of the clients created by [`test_client`], storing their
+/// state in memory.
+pub type TestClientConfig<C> =
+    WithIdentityProvider<BasicIdentityProvider, WithCryptoProvider<C, BaseConfig>>;
+
+/// Create a client with a basic credential holding `name` and a fresh
+/// signature key for `cipher_suite`.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn test_client<C>(
+    crypto: C,
+    cipher_suite: CipherSuite,
+    name: &[u8],
+) -> Result<Client<TestClientConfig<C>>, MlsError>
+where
+    C: CryptoProvider + Clone,
+{
+    let cs = crypto
+        .cipher_suite_provider(cipher_suite)
+        .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+
+    let (secret_key, public_key) = cs
+        .signature_key_generate()
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    let credential = BasicCredential::new(name.to_vec()).into_credential();
+    let signing_identity = SigningIdentity::new(credential, public_key);
+
+    Ok(Client::builder()
+        .crypto_provider(crypto)
+        .identity_provider(BasicIdentityProvider::new())
+        .signing_identity(signing_identity, secret_key, cipher_suite)
+        .build())
+}
+
+/// Members of a test group, each with its own client.
+///
+/// Members are addressed by their position in [`groups`](Self::groups),
+/// which is the order in which they joined, minus the members that were
+/// removed. It matches their leaf index until a member is removed.
+///
+/// Helpers making a member commit deliver the commit to all other members,
+/// and fail if any of them fails to process it.
+pub struct TestGroups<C: CryptoProvider + Clone> {
+    crypto: C,
+    cipher_suite: CipherSuite,
+    groups: Vec<Group<TestClientConfig<C>>>,
+    joined: usize,
+}
+
+impl<C: CryptoProvider + Clone> TestGroups<C> {
+    /// Create a group of `n` members, named `member 0` to `member n-1`, all
+    /// of them being added by the first member in a single commit.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new(crypto: C, cipher_suite: CipherSuite, n: usize) -> Result<Self, MlsError> {
+        let creator = test_client(crypto.clone(), cipher_suite, b"member 0").await?;
+        let group = creator.create_group(Default::default()).await?;
+
+        let mut groups = Self {
+            crypto,
+            cipher_suite,
+            groups: vec![group],
+            joined: 1,
+        };
+
+        let mut clients = Vec::new();
+        let mut key_packages = Vec::new();
+
+        for _ in 1..n {
+            let client = groups.next_client().await?;
+            key_packages.push(client.generate_key_package_message().await?);
+            clients.push(client);
+        }
+
+        if clients.is_empty() {
+            return Ok(groups);
+        }
+
+        let mut builder = groups.groups[0].commit_builder();
+
+        for key_package in key_packages {
+            builder = builder.add_member(key_package)?;
+        }
+
+        let output = builder.build().await?;
+        groups.apply_commit(0, &output).await?;
+        groups.join(clients, &output).await?;
+
+        Ok(groups)
+    }
+
+    /// Groups of all members.
+    pub fn groups(&self) -> &[Group<TestClientConfig<C>>] {
+        &self.groups
+    }
+
+    /// Group of member `member`, for instance to send an application message
+    /// or to create a commit with custom proposals.
+    pub fn group_mut(&mut self, member: usize) -> &mut Group<TestClientConfig<C>> {
+        &mut self.groups[member]
+    }
+
+    /// Consume the test groups, returning the groups of all members.
+    pub fn into_groups(self) -> Vec<Group<TestClientConfig<C>>> {
+        self.groups
+    }
+
+    /// Make `committer` commit all pending proposals, or just update its
+    /// path if there are none.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn commit(&mut self, committer: usize) -> Result<(), MlsError> {
+        let output = self.groups[committer].commit(Vec::new()).await?;
+        self.apply_commit(committer, &output).await
+    }
+
+    /// Make `committer` add a new member, which becomes the last member.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn add_member(&mut self, committer: usize) -> Result<(), MlsError> {
+        let client = self.next_client().await?;
+        let key_package = client.generate_key_package_message().await?;
+
+        let output = self.groups[committer]
+            .commit_builder()
+            .add_member(key_package)?
+            .build()
+            .await?;
+
+        self.apply_commit(committer, &output).await?;
+        self.join(vec![client], &output).await
+    }
+
+    /// Make `committer` remove `removed`, whose group is dropped.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn remove_member(
+        &mut self,
+        committer: usize,
+        removed: usize,
+    ) -> Result<(), MlsError> {
+        let index = self.groups[removed].current_member_index();
+
+        let output = self.groups[committer]
+            .commit_builder()
+            .remove_member(index)?
+            .build()
+            .await?;
+
+        self.groups.remove(removed);
+
+        let committer = if committer > removed {
+            committer - 1
+        } else {
+            committer
+        };
+
+        self.apply_commit(committer, &output).await
+    }
+
+    /// Make all members but `sender` process `message`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn deliver(&mut self, sender: usize, message: &MlsMessage) -> Result<(), MlsError> {
+        for (i, group) in self.groups.iter_mut().enumerate() {
+            if i != sender {
+                group.process_incoming_message(message.clone()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Panic unless all members are in the same epoch and agree on its
+    /// group context and epoch authenticator.
+    pub fn assert_converged(&self) {
+        for (i, group) in self.groups.iter().enumerate().skip(1) {
+            if let Some(divergence) = Divergence::between(&self.groups[0], group) {
+                panic!("member {i} disagrees with member 0 on the {divergence}");
+            }
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn next_client(&mut self) -> Result<Client<TestClientConfig<C>>, MlsError> {
+        let name = format!("member {}", self.joined);
+        self.joined += 1;
+
+        test_client(self.crypto.clone(), self.cipher_suite, name.as_bytes()).await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn apply_commit(
+        &mut self,
+        committer: usize,
+        output: &CommitOutput,
+    ) -> Result<(), MlsError> {
+        self.groups[committer].apply_pending_commit().await?;
+        self.deliver(committer, &output.commit_message).await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn join(
+        &mut self,
+        clients: Vec<Client<TestClientConfig<C>>>,
+        output: &CommitOutput,
+    ) -> Result<(), MlsError> {
+        let tree = self.groups[0].export_tree().into_owned();
+
+        for client in clients {
+            let (group, _) = client
+                .join_group(Some(tree.clone()), &output.welcome_messages[0])
+                .await?;
+
+            self.groups.push(group);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs::{error::MlsError, CipherSuite};
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use crate::DeterministicCryptoProvider;
+
+    use super::TestGroups;
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_converge() {
+        let mut groups = TestGroups::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 3)
+            .await
+            .unwrap();
+
+        groups.assert_converged();
+        assert_eq!(groups.groups()[0].roster().members().len(), 3);
+
+        groups.commit(1).await.unwrap();
+        groups.add_member(2).await.unwrap();
+        groups.remove_member(3, 0).await.unwrap();
+
+        groups.assert_converged();
+        assert_eq!(groups.groups().len(), 3);
+        assert_eq!(groups.groups()[0].current_epoch(), 4);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn same_seed_gives_same_group() {
+        let crypto = |seed| DeterministicCryptoProvider::new(OpensslCryptoProvider::new(), seed);
+
+        let first = TestGroups::new(crypto(1), CIPHER_SUITE, 2).await.unwrap();
+        let second = TestGroups::new(crypto(1), CIPHER_SUITE, 2).await.unwrap();
+
+        assert_eq!(first.groups()[0].group_id(), second.groups()[0].group_id());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    #[should_panic(expected = "member 1 disagrees with member 0 on the epoch")]
+    async fn diverging_members_are_detected() {
+        let mut groups = TestGroups::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 2)
+            .await
+            .unwrap();
+
+        groups.group_mut(0).commit(Vec::new()).await.unwrap();
+        groups.group_mut(0).apply_pending_commit().await.unwrap();
+        groups.assert_converged();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unknown_messages_fail_delivery() {
+        let mut groups = TestGroups::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 2)
+            .await
+            .unwrap();
+
+        let other = TestGroups::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 1)
+            .await
+            .unwrap();
+
+        let mut other = other.into_groups();
+        let commit = other[0].commit(Vec::new()).await.unwrap().commit_message;
+
+        let res = groups.deliver(0, &commit).await;
+        assert_matches!(res, Err(MlsError::GroupIdMismatch));
+    }
+}
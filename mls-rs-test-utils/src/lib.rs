@@ -0,0 +1,60 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Helpers to test applications built on [mls-rs](mls_rs).
+//!
+//! [`TestGroups`] creates a group of `n` members, each with its own client
+//! using in-memory storage and a basic credential, lets members commit and
+//! delivers the commits to all other members, and checks that all members
+//! agree on the state of the group.
+//!
+//! [`Simulation`] runs scripted or random scenarios of group churn, such as
+//! members joining and leaving, network partitions, lost application
+//! messages and concurrent commits, over an in-memory delivery service, and
+//! checks that members keep converging and decrypting each other's messages.
+//!
+//! [`DeterministicCryptoProvider`] wraps a crypto provider so that the
+//! randomness used by mls-rs comes from a seed, making the groups created by
+//! a test the same on every run.
+//!
+//! [`FaultInjector`] makes the storage and crypto providers wrapped with it
+//! fail, return corrupted data or respond slowly, to test how applications
+//! recover from failures in the middle of a commit.
+//!
+//! [`SizeReport`] measures the size of key packages, welcome messages and
+//! commits for a given cipher suite, group size, credential size and set of
+//! extensions, to check that they fit the budget of a transport.
+//!
+//! ```ignore
+//! use mls_rs::CipherSuite;
+//! use mls_rs_crypto_openssl::OpensslCryptoProvider;
+//! use mls_rs_test_utils::{DeterministicCryptoProvider, TestGroups};
+//!
+//! let crypto = DeterministicCryptoProvider::new(OpensslCryptoProvider::new(), 42);
+//! let mut groups = TestGroups::new(crypto, CipherSuite::CURVE25519_AES128, 3).unwrap();
+//!
+//! groups.commit(0).unwrap();
+//! groups.remove_member(1, 2).unwrap();
+//! groups.assert_converged();
+//! ```
+
+#![allow(clippy::result_large_err)]
+
+#[cfg(all(test, mls_build_async))]
+use futures_test::test as futures_test;
+
+mod crypto;
+mod fault;
+mod group;
+mod simulation;
+mod size_report;
+
+pub use crypto::{DeterministicCipherSuiteProvider, DeterministicCryptoProvider};
+pub use fault::{
+    FaultError, FaultInjector, FaultyCipherSuiteProvider, FaultyCryptoProvider,
+    FaultyGroupStateStorage, FaultyKeyPackageStorage,
+};
+pub use group::{test_client, Divergence, TestClientConfig, TestGroups};
+pub use simulation::{Simulation, SimulationError, Step};
+pub use size_report::{SizeProfile, SizeReport};
@@ -0,0 +1,505 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::collections::{BTreeMap, VecDeque};
+
+use mls_rs::{
+    error::MlsError, group::ReceivedMessage, CipherSuite, CryptoProvider, Group, MlsMessage,
+};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+
+use crate::group::{test_client, Divergence, TestClientConfig, TestGroups};
+
+const MAX_RANDOM_MEMBERS: usize = 10;
+
+/// Step of a [`Simulation`].
+///
+/// Members are identified by the order in which they joined, starting at 0
+/// for the creator of the group. Identifiers of removed members aren't
+/// reused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `committer` commits, updating its path.
+    Commit { committer: usize },
+    /// `committer` adds a new member, which joins right away.
+    Add { committer: usize },
+    /// `committer` removes `removed`.
+    Remove { committer: usize, removed: usize },
+    /// `sender` sends an application message, which is lost for the
+    /// members in `lost`.
+    Send { sender: usize, lost: Vec<usize> },
+    /// All `committers` commit in the same epoch. The delivery service
+    /// accepts the commit of the first one, which all others receive instead
+    /// of applying their own.
+    Race { committers: Vec<usize> },
+    /// `members` get disconnected from the delivery service. They can't
+    /// take any action, and the messages sent to them are queued until
+    /// [`Heal`](Self::Heal).
+    Partition { members: Vec<usize> },
+    /// All partitioned members get reconnected and process the messages
+    /// queued for them.
+    Heal,
+}
+
+/// Failure of a [`Simulation`].
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    #[error(transparent)]
+    /// Error returned by mls-rs while running a step.
+    MlsError(#[from] MlsError),
+    #[error("member {0} is not in the group")]
+    /// A step refers to a member that isn't in the group.
+    UnknownMember(usize),
+    #[error("member {0} is partitioned")]
+    /// A step makes a partitioned member take an action.
+    PartitionedMember(usize),
+    #[error("member {member} failed to process a message: {error}")]
+    /// A member failed to process a message delivered to it, for instance
+    /// because it failed to decrypt it.
+    ProcessingFailed { member: usize, error: MlsError },
+    #[error("member {member} received an unexpected message")]
+    /// A member decrypted an application message different from the one
+    /// that was sent, or got something else than an application message.
+    UnexpectedMessage { member: usize },
+    #[error("member {member} disagrees with member {reference} on the {divergence}")]
+    /// Two connected members don't have the same group state after a step.
+    Diverged {
+        member: usize,
+        reference: usize,
+        divergence: Divergence,
+    },
+}
+
+struct Delivery {
+    message: MlsMessage,
+    application_data: Option<Vec<u8>>,
+}
+
+struct SimulatedMember<C: CryptoProvider + Clone> {
+    group: Group<TestClientConfig<C>>,
+    inbox: VecDeque<Delivery>,
+    partitioned: bool,
+}
+
+/// Members of a group exchanging messages through an in-memory delivery
+/// service, driven by scripted or random [`Step`]s.
+///
+/// The delivery service delivers messages to each member in the order it
+/// received them, except that application messages can be lost. After each
+/// step, connected members process all messages queued for them, and the
+/// simulation fails if any of them fails to process a message or
+/// disagrees with the other connected members on the state of the group.
+/// The steps run so far are kept in [`history`](Self::history), so that a
+/// failing random run can be replayed as a script.
+pub struct Simulation<C: CryptoProvider + Clone> {
+    crypto: C,
+    cipher_suite: CipherSuite,
+    members: BTreeMap<usize, SimulatedMember<C>>,
+    joined: usize,
+    sent: usize,
+    history: Vec<Step>,
+}
+
+impl<C: CryptoProvider + Clone> Simulation<C> {
+    /// Start a simulation with a group of `n` members.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new(crypto: C, cipher_suite: CipherSuite, n: usize) -> Result<Self, MlsError> {
+        let groups = TestGroups::new(crypto.clone(), cipher_suite, n).await?;
+
+        let members = groups
+            .into_groups()
+            .into_iter()
+            .map(|group| SimulatedMember {
+                group,
+                inbox: VecDeque::new(),
+                partitioned: false,
+            })
+            .enumerate()
+            .collect();
+
+        Ok(Self {
+            crypto,
+            cipher_suite,
+            members,
+            joined: n,
+            sent: 0,
+            history: Vec::new(),
+        })
+    }
+
+    /// Identifiers of the current members.
+    pub fn members(&self) -> Vec<usize> {
+        self.members.keys().copied().collect()
+    }
+
+    /// Group of `member`, if it is in the group.
+    pub fn group(&self, member: usize) -> Option<&Group<TestClientConfig<C>>> {
+        self.members.get(&member).map(|m| &m.group)
+    }
+
+    /// Steps run so far, including the step that failed, if any.
+    pub fn history(&self) -> &[Step] {
+        &self.history
+    }
+
+    /// Run all `steps` in order.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn run<I>(&mut self, steps: I) -> Result<(), SimulationError>
+    where
+        I: IntoIterator<Item = Step>,
+    {
+        for step in steps {
+            self.step(step).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Run `count` random steps, drawn from a generator seeded with `seed`.
+    ///
+    /// Members may still be partitioned at the end of the run. Running
+    /// [`Step::Heal`] afterwards checks that they catch up.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn run_random(&mut self, seed: u64, count: usize) -> Result<(), SimulationError> {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+
+        for _ in 0..count {
+            let step = self.random_step(&mut rng);
+            self.step(step).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Run `step`, deliver the resulting messages to connected members and
+    /// check that they converge.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn step(&mut self, step: Step) -> Result<(), SimulationError> {
+        self.history.push(step.clone());
+
+        match step {
+            Step::Commit { committer } => self.commit(&[committer]).await?,
+            Step::Add { committer } => self.add(committer).await?,
+            Step::Remove { committer, removed } => self.remove(committer, removed).await?,
+            Step::Send { sender, lost } => self.send(sender, &lost).await?,
+            Step::Race { committers } => self.commit(&committers).await?,
+            Step::Partition { members } => {
+                for member in members {
+                    self.member_mut(member)?.partitioned = true;
+                }
+            }
+            Step::Heal => self
+                .members
+                .values_mut()
+                .for_each(|member| member.partitioned = false),
+        }
+
+        self.deliver().await?;
+        self.check_convergence()
+    }
+
+    /// Fail unless all connected members agree on the state of the group.
+    pub fn check_convergence(&self) -> Result<(), SimulationError> {
+        let mut connected = self.members.iter().filter(|(_, m)| !m.partitioned);
+
+        let Some((&reference, first)) = connected.next() else {
+            return Ok(());
+        };
+
+        connected.try_for_each(
+            |(&member, m)| match Divergence::between(&first.group, &m.group) {
+                Some(divergence) => Err(SimulationError::Diverged {
+                    member,
+                    reference,
+                    divergence,
+                }),
+                None => Ok(()),
+            },
+        )
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn commit(&mut self, committers: &[usize]) -> Result<(), SimulationError> {
+        let mut accepted = None;
+
+        for &committer in committers {
+            let output = self.actor(committer)?.group.commit(Vec::new()).await?;
+            accepted.get_or_insert((committer, output.commit_message));
+        }
+
+        if let Some((committer, message)) = accepted {
+            self.actor(committer)?.group.apply_pending_commit().await?;
+            self.post(committer, message, None, &[]);
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn add(&mut self, committer: usize) -> Result<(), SimulationError> {
+        let id = self.joined;
+        let name = format!("member {id}");
+        let client = test_client(self.crypto.clone(), self.cipher_suite, name.as_bytes()).await?;
+        let key_package = client.generate_key_package_message().await?;
+
+        let group = &mut self.actor(committer)?.group;
+
+        let output = group
+            .commit_builder()
+            .add_member(key_package)?
+            .build()
+            .await?;
+
+        group.apply_pending_commit().await?;
+        let tree = group.export_tree().into_owned();
+
+        let (group, _) = client
+            .join_group(Some(tree), &output.welcome_messages[0])
+            .await?;
+
+        self.post(committer, output.commit_message, None, &[]);
+        self.joined += 1;
+
+        self.members.insert(
+            id,
+            SimulatedMember {
+                group,
+                inbox: VecDeque::new(),
+                partitioned: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn remove(&mut self, committer: usize, removed: usize) -> Result<(), SimulationError> {
+        let index = self.member_mut(removed)?.group.current_member_index();
+        let group = &mut self.actor(committer)?.group;
+
+        let output = group.commit_builder().remove_member(index)?.build().await?;
+
+        group.apply_pending_commit().await?;
+
+        self.members.remove(&removed);
+        self.post(committer, output.commit_message, None, &[]);
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn send(&mut self, sender: usize, lost: &[usize]) -> Result<(), SimulationError> {
+        let data = format!("message {} from member {sender}", self.sent).into_bytes();
+        self.sent += 1;
+
+        let message = self
+            .actor(sender)?
+            .group
+            .encrypt_application_message(&data, Vec::new())
+            .await?;
+
+        self.post(sender, message, Some(data), lost);
+
+        Ok(())
+    }
+
+    fn post(
+        &mut self,
+        sender: usize,
+        message: MlsMessage,
+        application_data: Option<Vec<u8>>,
+        lost: &[usize],
+    ) {
+        for (id, member) in self.members.iter_mut() {
+            if *id != sender && !lost.contains(id) {
+                member.inbox.push_back(Delivery {
+                    message: message.clone(),
+                    application_data: application_data.clone(),
+                });
+            }
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn deliver(&mut self) -> Result<(), SimulationError> {
+        for (&id, member) in self.members.iter_mut() {
+            if member.partitioned {
+                continue;
+            }
+
+            while let Some(delivery) = member.inbox.pop_front() {
+                let received = member
+                    .group
+                    .process_incoming_message(delivery.message)
+                    .await
+                    .map_err(|error| SimulationError::ProcessingFailed { member: id, error })?;
+
+                let expected = match (received, delivery.application_data) {
+                    (ReceivedMessage::ApplicationMessage(message), Some(data)) => {
+                        message.data() == data
+                    }
+                    (ReceivedMessage::Commit(_), None) => true,
+                    _ => false,
+                };
+
+                if !expected {
+                    return Err(SimulationError::UnexpectedMessage { member: id });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn member_mut(&mut self, member: usize) -> Result<&mut SimulatedMember<C>, SimulationError> {
+        self.members
+            .get_mut(&member)
+            .ok_or(SimulationError::UnknownMember(member))
+    }
+
+    fn actor(&mut self, member: usize) -> Result<&mut SimulatedMember<C>, SimulationError> {
+        let actor = self.member_mut(member)?;
+
+        if actor.partitioned {
+            return Err(SimulationError::PartitionedMember(member));
+        }
+
+        Ok(actor)
+    }
+
+    fn random_step(&self, rng: &mut ChaCha20Rng) -> Step {
+        let all = self.members();
+
+        let connected = self
+            .members
+            .iter()
+            .filter(|(_, m)| !m.partitioned)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        let pick = |rng: &mut ChaCha20Rng, ids: &[usize]| ids[rng.next_u32() as usize % ids.len()];
+        let actor = pick(rng, &connected);
+
+        loop {
+            match rng.next_u32() % 7 {
+                0 => return Step::Commit { committer: actor },
+                1 if all.len() < MAX_RANDOM_MEMBERS => return Step::Add { committer: actor },
+                2 if all.len() > 2 => {
+                    let others = all.iter().copied().filter(|id| *id != actor);
+                    let removed = pick(rng, &others.collect::<Vec<_>>());
+
+                    return Step::Remove {
+                        committer: actor,
+                        removed,
+                    };
+                }
+                3 => {
+                    let lost = all
+                        .iter()
+                        .copied()
+                        .filter(|id| *id != actor && rng.next_u32() % 4 == 0)
+                        .collect();
+
+                    return Step::Send {
+                        sender: actor,
+                        lost,
+                    };
+                }
+                4 if connected.len() > 1 => {
+                    let others = connected.iter().copied().filter(|id| *id != actor);
+                    let other = pick(rng, &others.collect::<Vec<_>>());
+
+                    return Step::Race {
+                        committers: vec![actor, other],
+                    };
+                }
+                5 if connected.len() > 1 => {
+                    let others = connected.iter().copied().filter(|id| *id != actor);
+                    let member = pick(rng, &others.collect::<Vec<_>>());
+
+                    return Step::Partition {
+                        members: vec![member],
+                    };
+                }
+                6 if connected.len() < all.len() => return Step::Heal,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs::CipherSuite;
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use super::{Simulation, SimulationError, Step};
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn scripted_churn_converges() {
+        let mut simulation = Simulation::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 3)
+            .await
+            .unwrap();
+
+        let steps = [
+            Step::Partition { members: vec![2] },
+            Step::Send {
+                sender: 0,
+                lost: vec![1],
+            },
+            Step::Race {
+                committers: vec![1, 0],
+            },
+            Step::Add { committer: 0 },
+            Step::Send {
+                sender: 3,
+                lost: vec![],
+            },
+            Step::Heal,
+            Step::Remove {
+                committer: 2,
+                removed: 1,
+            },
+        ];
+
+        simulation.run(steps).await.unwrap();
+
+        assert_eq!(simulation.members(), vec![0, 2, 3]);
+        assert_eq!(simulation.group(0).unwrap().current_epoch(), 4);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn random_churn_converges() {
+        let mut simulation = Simulation::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 4)
+            .await
+            .unwrap();
+
+        simulation.run_random(7, 30).await.unwrap();
+        simulation.step(Step::Heal).await.unwrap();
+
+        assert_eq!(simulation.history().len(), 31);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn partitioned_members_cannot_act() {
+        let mut simulation = Simulation::new(OpensslCryptoProvider::new(), CIPHER_SUITE, 2)
+            .await
+            .unwrap();
+
+        simulation
+            .step(Step::Partition { members: vec![1] })
+            .await
+            .unwrap();
+
+        let res = simulation.step(Step::Commit { committer: 1 }).await;
+        assert_matches!(res, Err(SimulationError::PartitionedMember(1)));
+
+        let res = simulation.step(Step::Commit { committer: 5 }).await;
+        assert_matches!(res, Err(SimulationError::UnknownMember(5)));
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs::{
+    client_builder::MlsConfig,
+    error::{IntoAnyError, MlsError},
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider, ExtensionList,
+};
+
+/// Parameters of the group whose messages are measured by
+/// [`SizeReport::measure`].
+#[derive(Clone, Debug)]
+pub struct SizeProfile {
+    /// Cipher suite of the group.
+    pub cipher_suite: CipherSuite,
+    /// Number of members of the group, at least 1.
+    pub group_size: usize,
+    /// Size of the basic credential of each member, at least 8 bytes.
+    pub credential_size: usize,
+    /// Extensions of the group context.
+    pub group_extensions: ExtensionList,
+    /// Extensions of the leaf node of each member.
+    pub leaf_node_extensions: ExtensionList,
+}
+
+impl SizeProfile {
+    /// Profile of a group of `group_size` members without extensions, with
+    /// 32-byte credentials.
+    pub fn new(cipher_suite: CipherSuite, group_size: usize) -> Self {
+        Self {
+            cipher_suite,
+            group_size,
+            credential_size: 32,
+            group_extensions: Default::default(),
+            leaf_node_extensions: Default::default(),
+        }
+    }
+}
+
+/// Sizes, in bytes, of the messages exchanged in a group described by a
+/// [`SizeProfile`], as sent on the wire.
+///
+/// Commits are public messages, which is the default. Welcome messages
+/// include the ratchet tree, which is also the default, so a delivery
+/// service distributing the tree separately saves about
+/// [`ratchet_tree`](Self::ratchet_tree) bytes per welcome message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SizeReport {
+    /// Key package of a new member.
+    pub key_package: usize,
+    /// Welcome message for a new member joining the group.
+    pub welcome: usize,
+    /// Exported ratchet tree of the group.
+    pub ratchet_tree: usize,
+    /// Commit adding a new member.
+    pub add_commit: usize,
+    /// Commit just updating the path of the committer, right after all
+    /// members were added. As the tree has no parent nodes yet, path
+    /// secrets are encrypted to every other member, which makes this the
+    /// largest update commit for the group.
+    pub update_commit: usize,
+    /// Commit removing a member, or 0 if the group has a single member.
+    pub remove_commit: usize,
+}
+
+impl SizeReport {
+    /// Measure sizes by creating a group described by `profile` with
+    /// `crypto`.
+    ///
+    /// Only the creator of the group processes the commits, so this takes
+    /// time linear in the size of the group.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn measure<C>(crypto: C, profile: &SizeProfile) -> Result<Self, MlsError>
+    where
+        C: CryptoProvider + Clone,
+    {
+        let creator = client(crypto.clone(), profile, 0).await?;
+
+        let mut group = creator
+            .create_group(profile.group_extensions.clone())
+            .await?;
+
+        if profile.group_size > 1 {
+            let mut builder = group.commit_builder();
+
+            for i in 1..profile.group_size {
+                let member = client(crypto.clone(), profile, i).await?;
+                builder = builder.add_member(member.generate_key_package_message().await?)?;
+            }
+
+            builder.build().await?;
+            group.apply_pending_commit().await?;
+        }
+
+        let update_commit = group.commit(Vec::new()).await?.commit_message.to_bytes()?;
+        group.apply_pending_commit().await?;
+
+        let ratchet_tree = group.export_tree().to_bytes()?;
+
+        let new_member = client(crypto, profile, profile.group_size).await?;
+        let key_package = new_member.generate_key_package_message().await?;
+        let key_package_size = key_package.to_bytes()?.len();
+
+        let output = group
+            .commit_builder()
+            .add_member(key_package)?
+            .build()
+            .await?;
+
+        let welcome = output.welcome_messages[0].to_bytes()?;
+        let add_commit = output.commit_message.to_bytes()?;
+        group.clear_pending_commit();
+
+        let remove_commit = match profile.group_size {
+            1 => 0,
+            _ => group
+                .commit_builder()
+                .remove_member(1)?
+                .build()
+                .await?
+                .commit_message
+                .to_bytes()?
+                .len(),
+        };
+
+        Ok(Self {
+            key_package: key_package_size,
+            welcome: welcome.len(),
+            ratchet_tree: ratchet_tree.len(),
+            add_commit: add_commit.len(),
+            update_commit: update_commit.len(),
+            remove_commit,
+        })
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn client<C>(
+    crypto: C,
+    profile: &SizeProfile,
+    index: usize,
+) -> Result<Client<impl MlsConfig>, MlsError>
+where
+    C: CryptoProvider + Clone,
+{
+    let cs = crypto
+        .cipher_suite_provider(profile.cipher_suite)
+        .ok_or(MlsError::UnsupportedCipherSuite(profile.cipher_suite))?;
+
+    let (secret_key, public_key) = cs
+        .signature_key_generate()
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    // Credentials must be unique, so they end with the index of the member.
+    let mut name = vec![0; profile.credential_size.max(8)];
+    let suffix = name.len() - 8;
+    name[suffix..].copy_from_slice(&(index as u64).to_be_bytes());
+
+    let credential = BasicCredential::new(name).into_credential();
+
+    let extension_types = profile
+        .group_extensions
+        .iter()
+        .chain(profile.leaf_node_extensions.iter())
+        .map(|extension| extension.extension_type);
+
+    Ok(Client::builder()
+        .crypto_provider(crypto)
+        .identity_provider(BasicIdentityProvider::new())
+        .extension_types(extension_types)
+        .leaf_node_extensions(profile.leaf_node_extensions.clone())
+        .signing_identity(
+            SigningIdentity::new(credential, public_key),
+            secret_key,
+            profile.cipher_suite,
+        )
+        .build())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use mls_rs::{CipherSuite, Extension, ExtensionList};
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use super::{SizeProfile, SizeReport};
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sizes_grow_with_the_group() {
+        let crypto = OpensslCryptoProvider::new();
+
+        let small = SizeReport::measure(crypto.clone(), &SizeProfile::new(CIPHER_SUITE, 2))
+            .await
+            .unwrap();
+
+        let large = SizeReport::measure(crypto, &SizeProfile::new(CIPHER_SUITE, 16))
+            .await
+            .unwrap();
+
+        assert_eq!(small.key_package, large.key_package);
+        assert!(small.ratchet_tree < large.ratchet_tree);
+        assert!(small.welcome < large.welcome);
+        assert!(small.update_commit < large.update_commit);
+        assert!(large.welcome > large.ratchet_tree);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn credentials_and_extensions_are_counted() {
+        let crypto = OpensslCryptoProvider::new();
+        let base = SizeProfile::new(CIPHER_SUITE, 1);
+
+        let report = SizeReport::measure(crypto.clone(), &base).await.unwrap();
+        assert_eq!(report.remove_commit, 0);
+
+        let mut profile = base.clone();
+        profile.credential_size += 100;
+
+        let larger = SizeReport::measure(crypto.clone(), &profile).await.unwrap();
+        assert!(larger.key_package >= report.key_package + 100);
+
+        let mut profile = base;
+        profile.leaf_node_extensions =
+            ExtensionList::from(vec![Extension::new(65000.into(), vec![0; 50])]);
+
+        let larger = SizeReport::measure(crypto, &profile).await.unwrap();
+        assert!(larger.key_package >= report.key_package + 50);
+    }
+}
@@ -54,6 +54,16 @@ impl mls_rs_core::group::GroupStateStorage for ClientGroupStorage {
     async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
         self.0.max_epoch_id(group_id.to_vec()).await
     }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        self.0
+            .delete_epochs_before(group_id.to_vec(), epoch_id)
+            .await
+    }
 }
 
 pub type UniFFIConfig = client_builder::WithIdentityProvider<
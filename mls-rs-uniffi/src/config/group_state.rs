@@ -52,6 +52,15 @@ pub trait GroupStateStorage: Send + Sync + Debug {
     ) -> Result<(), Error>;
 
     async fn max_epoch_id(&self, group_id: Vec<u8>) -> Result<Option<u64>, Error>;
+
+    /// Delete the prior epochs of the group with an id lower than
+    /// `epoch_id`, returning the ids of the deleted epochs, or `None` if
+    /// deleting epochs is not supported.
+    async fn delete_epochs_before(
+        &self,
+        group_id: Vec<u8>,
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Error>;
 }
 
 /// Adapt a mls-rs `GroupStateStorage` implementation.
@@ -126,4 +135,16 @@ where
             .await
             .map_err(|err| err.into_any_error().into())
     }
+
+    async fn delete_epochs_before(
+        &self,
+        group_id: Vec<u8>,
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Error> {
+        self.inner()
+            .await
+            .delete_epochs_before(&group_id, epoch_id)
+            .await
+            .map_err(|err| err.into_any_error().into())
+    }
 }
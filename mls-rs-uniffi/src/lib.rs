@@ -577,7 +577,7 @@ fn index_to_identity(
 ) -> Result<identity::SigningIdentity, Error> {
     let member = group
         .member_at_index(index)
-        .ok_or(MlsError::InvalidNodeIndex(index))?;
+        .ok_or(MlsError::LeafNotFound(index))?;
     Ok(member.signing_identity)
 }
 
@@ -869,6 +869,27 @@ mod tests {
                     .and_then(|GroupStateData { epoch_data, .. }| epoch_data.last())
                     .map(|last| last.id))
             }
+
+            fn delete_epochs_before(
+                &self,
+                group_id: Vec<u8>,
+                epoch_id: u64,
+            ) -> Result<Option<Vec<u64>>, Error> {
+                let mut groups = self.lock();
+
+                let Some(group) = groups.get_mut(&group_id) else {
+                    return Ok(Some(Vec::new()));
+                };
+
+                let (deleted, kept) = group
+                    .epoch_data
+                    .drain(..)
+                    .partition::<Vec<_>, _>(|record| record.id < epoch_id);
+
+                group.epoch_data = kept;
+
+                Ok(Some(deleted.into_iter().map(|record| record.id).collect()))
+            }
         }
 
         let alice_config = ClientConfig {
@@ -60,6 +60,17 @@ class PythonGroupStateStorage(GroupStateStorage):
 
         return last.id
 
+    def delete_epochs_before(self, group_id: bytes, epoch_id: int):
+        group = self.groups.get(group_id.hex())
+        if group == None:
+            return []
+
+        deleted = [epoch.id for epoch in group.epoch_data if epoch.id < epoch_id]
+        group.epoch_data = [epoch for epoch in group.epoch_data
+                            if epoch.id >= epoch_id]
+
+        return deleted
+
 
 group_state_storage = PythonGroupStateStorage()
 client_config = ClientConfig(group_state_storage=group_state_storage,
@@ -0,0 +1,42 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use mls_rs::{test_utils::benchmarks::TreeHashBench, CipherSuite};
+
+fn bench(c: &mut Criterion) {
+    let cipher_suite = CipherSuite::CURVE25519_AES128;
+    let mut bench_group = c.benchmark_group("tree_hash");
+    bench_group.sample_size(10);
+
+    for leaf_count in [1_000, 10_000, 50_000] {
+        let tree = TreeHashBench::new(cipher_suite, leaf_count);
+
+        bench_group.bench_with_input(BenchmarkId::new("full", leaf_count), &leaf_count, |b, _| {
+            b.iter_batched_ref(
+                || tree.clone(),
+                |tree| tree.tree_hash(),
+                BatchSize::LargeInput,
+            )
+        });
+
+        let mut cached = tree.clone();
+        cached.tree_hash();
+
+        // A commit adding or updating a few members only rehashes their
+        // direct paths.
+        let updated = [0, leaf_count / 3, leaf_count / 2, leaf_count - 1];
+
+        bench_group.bench_with_input(
+            BenchmarkId::new("incremental", leaf_count),
+            &leaf_count,
+            |b, _| b.iter(|| cached.update_leaves(&updated)),
+        );
+    }
+
+    bench_group.finish();
+}
+
+criterion::criterion_group!(benches, bench);
+criterion::criterion_main!(benches);
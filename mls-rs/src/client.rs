@@ -7,17 +7,23 @@ use crate::client_builder::{recreate_config, BaseConfig, ClientBuilder, MakeConf
 use crate::client_config::ClientConfig;
 use crate::group::framing::MlsMessage;
 
+use crate::group::framing::MlsMessagePayload;
 #[cfg(feature = "by_ref_proposal")]
 use crate::group::{
-    framing::{Content, MlsMessagePayload, PublicMessage, Sender, WireFormat},
+    framing::{Content, PublicMessage, Sender, WireFormat},
     message_signature::AuthenticatedContent,
     proposal::{AddProposal, Proposal},
 };
-use crate::group::{snapshot::Snapshot, ExportedTree, Group, NewMemberInfo};
+use crate::group::{
+    snapshot::Snapshot, CreateGroupOptions, ExportedTree, Group, GroupTranscript, NewMemberInfo,
+    ReceivedMessage, StateCorruption,
+};
 use crate::identity::SigningIdentity;
-use crate::key_package::{KeyPackageGeneration, KeyPackageGenerator};
+pub use crate::key_package::KeyPackageBuilder;
+use crate::key_package::{KeyPackage, KeyPackageRef};
 use crate::protocol_version::ProtocolVersion;
 use crate::tree_kem::node::NodeIndex;
+use crate::tree_kem::parent_hash::ParentHashViolation;
 use alloc::vec::Vec;
 use mls_rs_codec::MlsDecode;
 use mls_rs_core::crypto::{CryptoProvider, SignatureSecretKey};
@@ -32,6 +38,12 @@ use crate::group::external_commit::ExternalCommitBuilder;
 #[cfg(feature = "by_ref_proposal")]
 use alloc::boxed::Box;
 
+#[cfg(feature = "by_ref_proposal")]
+use crate::extension::ExternalSendersExt;
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::TreeAttestation;
+
 #[derive(Debug)]
 #[cfg_attr(feature = "std", derive(thiserror::Error))]
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::enum_to_error_code)]
@@ -48,11 +60,19 @@ pub enum MlsError {
     #[cfg_attr(feature = "std", error(transparent))]
     PskStoreError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
+    ReplayStoreError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
     MlsRulesError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
     SerializationError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
     ExtensionError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    CredentialRefreshError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    MasterKeyProviderError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    CommitDeliveryError(AnyError),
     #[cfg_attr(feature = "std", error("Cipher suite does not match"))]
     CipherSuiteMismatch,
     #[cfg_attr(feature = "std", error("Invalid commit, missing required path"))]
@@ -131,6 +151,16 @@ pub enum MlsError {
         )
     )]
     GroupUsedAfterReInit,
+    #[cfg_attr(
+        feature = "std",
+        error("This member was removed from the group, which can no longer be used.")
+    )]
+    GroupUsedAfterRemoval,
+    #[cfg_attr(
+        feature = "std",
+        error("Group metadata was rejected by the validator.")
+    )]
+    InvalidGroupMetadata,
     #[cfg_attr(feature = "std", error("Pending ReIinit not found."))]
     PendingReInitNotFound,
     #[cfg_attr(
@@ -138,6 +168,26 @@ pub enum MlsError {
         error("The extensions in the welcome message and in the reinit do not match.")
     )]
     ReInitExtensionsMismatch,
+    #[cfg_attr(
+        feature = "std",
+        error("The members of the reinitialized group and of the new group do not match.")
+    )]
+    ReInitRosterMismatch,
+    #[cfg_attr(
+        feature = "std",
+        error("The members of the recovered group and of the lost group do not match.")
+    )]
+    RecoveryRosterMismatch,
+    #[cfg_attr(
+        feature = "std",
+        error("The recovery policy doesn't allow the creator to recover the lost group.")
+    )]
+    RecoveryNotAllowed,
+    #[cfg_attr(
+        feature = "std",
+        error("The members of the subgroup are not all members of the group.")
+    )]
+    SubgroupRosterMismatch,
     #[cfg_attr(feature = "std", error("signer not found for given identity"))]
     SignerNotFound,
     #[cfg_attr(feature = "std", error("commit already pending"))]
@@ -155,6 +205,8 @@ pub enum MlsError {
     MemberNotFound,
     #[cfg_attr(feature = "std", error("group not found"))]
     GroupNotFound,
+    #[cfg_attr(feature = "std", error("corrupted group state: {0}"))]
+    CorruptedGroupState(StateCorruption),
     #[cfg_attr(feature = "std", error("unexpected PSK ID"))]
     UnexpectedPskId,
     #[cfg_attr(feature = "std", error("invalid sender for content type"))]
@@ -173,6 +225,80 @@ pub enum MlsError {
     InvalidLeafConsumption,
     #[cfg_attr(feature = "std", error("key not available, invalid generation {0}"))]
     KeyMissing(u32),
+    #[cfg_attr(feature = "std", error("message was already processed"))]
+    ReplayedMessage,
+    #[cfg_attr(feature = "std", error("application message has expired"))]
+    MessageExpired,
+    #[cfg_attr(feature = "std", error("datagram size {0} is too small"))]
+    DatagramSizeTooSmall(usize),
+    #[cfg_attr(feature = "std", error("message is too large to be sent in datagrams"))]
+    MessageTooLargeForDatagrams,
+    #[cfg_attr(feature = "std", error("invalid datagram"))]
+    InvalidDatagram,
+    #[cfg_attr(
+        feature = "std",
+        error("no common cipher suite, incompatible key packages: {0:?}")
+    )]
+    NoCommonCipherSuite(Vec<usize>),
+    #[cfg_attr(feature = "std", error("group can't have more than {0} members"))]
+    GroupSizeLimitExceeded(u32),
+    #[cfg_attr(feature = "std", error("signature label is reserved by the protocol"))]
+    ReservedSignatureLabel,
+    #[cfg_attr(feature = "std", error("envelope is meant for another member"))]
+    InvalidEnvelopeRecipient,
+    #[cfg_attr(
+        feature = "std",
+        error("HPKE key of node {1} is already used by node {0}")
+    )]
+    DuplicateHpkeKey(NodeIndex, NodeIndex),
+    #[cfg_attr(
+        feature = "std",
+        error("signature key of leaf {1} is already used by leaf {0}")
+    )]
+    DuplicateSignatureKey(u32, u32),
+    #[cfg_attr(
+        feature = "std",
+        error("update from leaf {0} reuses the encryption key of its current leaf node")
+    )]
+    ReusedLeafNode(u32),
+    #[cfg_attr(feature = "std", error("session key not found"))]
+    SessionKeyNotFound,
+    #[cfg_attr(feature = "std", error("state response doesn't answer the challenge"))]
+    UnexpectedStateResponse,
+    #[cfg_attr(
+        feature = "std",
+        error("application message from leaf {0} carries no sequence number")
+    )]
+    MissingSequenceNumber(u32),
+    #[cfg_attr(
+        feature = "std",
+        error("sequence number {1} from leaf {0} is not greater than the last one received")
+    )]
+    StaleSequenceNumber(u32, u64),
+    #[cfg_attr(feature = "std", error("commit still conflicting after {0} retries"))]
+    CommitRetriesExhausted(u32),
+    #[cfg_attr(feature = "std", error("key package is bound to a different channel"))]
+    ChannelBindingMismatch,
+    #[cfg_attr(feature = "std", error("tree record of epoch {0} not found"))]
+    TreeRecordNotFound(u64),
+    #[cfg_attr(feature = "std", error("group state storage can't delete epochs"))]
+    EpochDeletionUnsupported,
+    #[cfg_attr(feature = "std", error("unsupported archive version {0}"))]
+    UnsupportedArchiveVersion(u16),
+    #[cfg_attr(feature = "std", error("archive is inconsistent"))]
+    InvalidArchive,
+    #[cfg_attr(
+        feature = "std",
+        error("external commit replacing a member has no rejoin proof")
+    )]
+    MissingRejoinProof,
+    #[cfg_attr(feature = "std", error("rejoin proof is invalid"))]
+    InvalidRejoinProof,
+    #[cfg_attr(
+        feature = "std",
+        error("member {0} is not permitted to send application messages")
+    )]
+    SenderNotPermitted(u32),
     #[cfg_attr(
         feature = "std",
         error("requested generation {0} is too far ahead of current generation")
@@ -246,8 +372,8 @@ pub enum MlsError {
     UpdateErrorNoSecretKey,
     #[cfg_attr(feature = "std", error("invalid lca, not found on direct path"))]
     LcaNotFoundInDirectPath,
-    #[cfg_attr(feature = "std", error("update path parent hash mismatch"))]
-    ParentHashMismatch,
+    #[cfg_attr(feature = "std", error("update path parent hash mismatch: {0}"))]
+    ParentHashMismatch(ParentHashViolation),
     #[cfg_attr(feature = "std", error("unexpected pattern of unmerged leaves"))]
     UnmergedLeavesMismatch,
     #[cfg_attr(feature = "std", error("empty tree"))]
@@ -383,6 +509,10 @@ impl Client<()> {
     }
 }
 
+/// Group restored by [`Client::replay_transcript`], with the result of
+/// processing each message of the transcript.
+type ReplayedTranscript<C> = (Group<C>, Vec<Result<ReceivedMessage, MlsError>>);
+
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
 impl<C> Client<C>
 where
@@ -427,45 +557,71 @@ where
     /// A key package message may only be used once.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn generate_key_package_message(&self) -> Result<MlsMessage, MlsError> {
-        Ok(self.generate_key_package().await?.key_package_message())
+        self.key_package_builder().build().await
     }
 
+    /// Creates a [`KeyPackageBuilder`] to generate a key package with
+    /// properties that differ from the configuration of the client, such as
+    /// its lifetime, extensions or signer.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn key_package_builder(&self) -> KeyPackageBuilder<'_, C> {
+        KeyPackageBuilder::new(self)
+    }
+
+    /// References of the key packages of this client that can still be used
+    /// to join a group, i.e. that are in the
+    /// [KeyPackageStorage](crate::KeyPackageStorage) and haven't expired
+    /// according to the time provider of the client.
+    ///
+    /// A delivery service indexing welcome messages by
+    /// [`MlsMessage::welcome_key_package_references`] can be polled for
+    /// these references to find the welcome messages sent to this client.
+    ///
+    /// Key packages can only be found if the storage in use enumerates them
+    /// with [`KeyPackageStorage::key_package_ids`].
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn generate_key_package(&self) -> Result<KeyPackageGeneration, MlsError> {
-        let (signing_identity, cipher_suite) = self.signing_identity()?;
+    pub async fn welcome_key_package_references(&self) -> Result<Vec<KeyPackageRef>, MlsError> {
+        let repo = self.config.key_package_repo();
+        let now = self.config.current_time().map(|t| t.seconds_since_epoch());
 
-        let cipher_suite_provider = self
-            .config
-            .crypto_provider()
-            .cipher_suite_provider(cipher_suite)
-            .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+        let ids = repo
+            .key_package_ids()
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
 
-        let key_package_generator = KeyPackageGenerator {
-            protocol_version: self.version,
-            cipher_suite_provider: &cipher_suite_provider,
-            signing_key: self.signer()?,
-            signing_identity,
-            identity_provider: &self.config.identity_provider(),
-        };
+        let mut references = Vec::new();
 
-        let key_pkg_gen = key_package_generator
-            .generate(
-                self.config.lifetime(),
-                self.config.capabilities(),
-                self.config.key_package_extensions(),
-                self.config.leaf_node_extensions(),
-            )
-            .await?;
+        for id in ids {
+            let data = repo
+                .get(&id)
+                .await
+                .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
 
-        let (id, key_package_data) = key_pkg_gen.to_storage()?;
+            let Some(data) = data else {
+                continue;
+            };
 
-        self.config
-            .key_package_repo()
-            .insert(id, key_package_data)
-            .await
-            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+            if matches!(now, Some(now) if data.expiration < now) {
+                continue;
+            }
+
+            let key_package = KeyPackage::mls_decode(&mut &*data.key_package_bytes)?;
+
+            // Key packages of cipher suites that are no longer supported can't
+            // be used to join a group.
+            let Some(cipher_suite_provider) = self
+                .config
+                .crypto_provider()
+                .cipher_suite_provider(key_package.cipher_suite)
+            else {
+                continue;
+            };
+
+            references.push(key_package.to_reference(&cipher_suite_provider).await?);
+        }
 
-        Ok(key_pkg_gen)
+        Ok(references)
     }
 
     /// Create a group with a specific group_id.
@@ -523,6 +679,40 @@ where
         .await
     }
 
+    /// Create a MLS group with the given [`CreateGroupOptions`].
+    ///
+    /// The options are turned into group context extensions, which are
+    /// validated against the capabilities of this client and, for external
+    /// senders, by the [`IdentityProvider`](crate::IdentityProvider) before
+    /// the group is created.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn create_group_with_options(
+        &self,
+        options: CreateGroupOptions,
+    ) -> Result<Group<C>, MlsError> {
+        let (signing_identity, cipher_suite) = self.signing_identity()?;
+        let extensions = options.group_context_extensions()?;
+
+        #[cfg(feature = "by_ref_proposal")]
+        if let Some(external_senders) = extensions.get_as::<ExternalSendersExt>()? {
+            external_senders
+                .verify_all(&self.config.identity_provider(), None, &extensions)
+                .await
+                .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+        }
+
+        Group::new(
+            self.config.clone(),
+            options.group_id,
+            cipher_suite,
+            self.version,
+            signing_identity.clone(),
+            extensions,
+            self.signer()?.clone(),
+        )
+        .await
+    }
+
     /// Join a MLS group via a welcome message created by a
     /// [Commit](crate::group::CommitOutput).
     ///
@@ -547,6 +737,35 @@ where
         .await
     }
 
+    /// Join a MLS group like [`Client::join_group`], with a ratchet tree
+    /// attested by an external sender of the group.
+    ///
+    /// If the client was configured with
+    /// [`LeafSignatureVerification::SkipForTreesAttestedByExternalSender`](crate::group::LeafSignatureVerification::SkipForTreesAttestedByExternalSender),
+    /// the attestation is verified and the leaves of the tree are not
+    /// validated. Otherwise, the attestation is ignored and the tree is fully
+    /// validated.
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn join_group_with_tree_attestation(
+        &self,
+        tree_data: Option<ExportedTree<'_>>,
+        welcome_message: &MlsMessage,
+        tree_attestation: &TreeAttestation,
+    ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
+        Group::from_welcome_message(
+            welcome_message,
+            tree_data,
+            self.config.clone(),
+            self.signer()?.clone(),
+            #[cfg(feature = "psk")]
+            None,
+            Some(tree_attestation),
+        )
+        .await
+    }
+
     /// 0-RTT add to an existing [group](crate::group::Group)
     ///
     /// External commits allow for immediate entry into a
@@ -604,6 +823,11 @@ where
     /// Load an existing group state into this client using the
     /// [GroupStateStorage](crate::GroupStateStorage) that
     /// this client was configured to use.
+    ///
+    /// The state is checked for consistency when it is loaded, the hashes
+    /// of the ratchet tree being computed again. An inconsistent state is
+    /// reported with [`MlsError::CorruptedGroupState`], whose
+    /// [`StateCorruption::recovery`] suggests how to recover from it.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     #[inline(never)]
     pub async fn load_group(&self, group_id: &[u8]) -> Result<Group<C>, MlsError> {
@@ -615,9 +839,58 @@ where
             .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
             .ok_or(MlsError::GroupNotFound)?;
 
-        let snapshot = Snapshot::mls_decode(&mut &*snapshot)?;
+        let snapshot = Snapshot::from_bytes(&snapshot)?;
+
+        let mut group = Group::from_snapshot(self.config.clone(), snapshot).await?;
+        group.verify_integrity().await?;
+
+        Ok(group)
+    }
+
+    /// Replay a [`GroupTranscript`] recorded by a member of a group, for
+    /// instance in a test reproducing a failure observed in the field.
+    ///
+    /// The group is restored in the state it was in when recording started,
+    /// with the secrets and signature key of the recording member, using the
+    /// configuration of this client rather than the one of the recording
+    /// client. Each message is then processed at the time it was processed
+    /// originally, returning the result of processing each of them along
+    /// with the resulting group.
+    ///
+    /// Using a time provider and a crypto provider that draws random bytes
+    /// from a fixed seed makes the replay reproducible. Messages from epochs
+    /// prior to the one in which recording started can't be processed, as
+    /// they are not part of the transcript.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn replay_transcript(
+        &self,
+        transcript: &GroupTranscript,
+    ) -> Result<ReplayedTranscript<C>, MlsError> {
+        let mut group =
+            Group::from_snapshot(self.config.clone(), transcript.snapshot.clone()).await?;
+
+        let mut results = Vec::with_capacity(transcript.entries().len());
+
+        for entry in transcript.entries() {
+            let message = entry.message().clone();
+
+            let res = match entry.time_sent() {
+                Some(time) => {
+                    group
+                        .process_incoming_message_with_time(message, time)
+                        .await
+                }
+                None => {
+                    group
+                        .process_incoming_message_at(message, entry.received_at())
+                        .await
+                }
+            };
+
+            results.push(res);
+        }
 
-        Group::from_snapshot(self.config.clone(), snapshot).await
+        Ok((group, results))
     }
 
     /// Request to join an existing [group](crate::group::Group).
@@ -657,10 +930,16 @@ where
             tree_data,
             &self.config.identity_provider(),
             &cipher_suite_provider,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
         )
         .await?;
 
-        let key_package = self.generate_key_package().await?.key_package;
+        let key_package = self
+            .key_package_builder()
+            .build_generation()
+            .await?
+            .key_package;
 
         (key_package.cipher_suite == cipher_suite)
             .then_some(())
@@ -703,6 +982,76 @@ where
             .ok_or(MlsError::SignerNotFound)
     }
 
+    /// Select the cipher suite of a new group that will have the owners of
+    /// `key_packages` as members.
+    ///
+    /// The selected cipher suite is the first one, in the order of
+    /// [`ClientBuilder::cipher_suite_preferences`], that is supported by the
+    /// [CryptoProvider] of this client, is the cipher suite of the signing
+    /// identity of this client, which groups are created with, and is the
+    /// cipher suite of all `key_packages`, which can only be used in groups
+    /// of their own cipher suite. Without preferences, the order of
+    /// [`CryptoProvider::supported_cipher_suites`] is used.
+    ///
+    /// If there is no such cipher suite, [`MlsError::NoCommonCipherSuite`]
+    /// returns the indexes in `key_packages` of the members that can't join a
+    /// group of the candidate cipher suite suitable for most of them.
+    pub fn select_cipher_suite(
+        &self,
+        key_packages: &[MlsMessage],
+    ) -> Result<CipherSuite, MlsError> {
+        let (_, signing_cipher_suite) = self.signing_identity()?;
+        let supported = self.config.crypto_provider().supported_cipher_suites();
+        let preferences = self.config.cipher_suite_preferences();
+
+        let candidates = if preferences.is_empty() {
+            supported
+        } else {
+            preferences
+                .into_iter()
+                .filter(|cs| supported.contains(cs))
+                .collect()
+        };
+
+        let peer_key_packages = key_packages
+            .iter()
+            .map(|message| match &message.payload {
+                MlsMessagePayload::KeyPackage(key_package) => Ok(key_package),
+                _ => Err(MlsError::UnexpectedMessageType),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let best = candidates
+            .into_iter()
+            .filter(|cipher_suite| *cipher_suite == signing_cipher_suite)
+            .map(|cipher_suite| {
+                let incompatible = peer_key_packages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, key_package)| {
+                        key_package.cipher_suite != cipher_suite
+                            || !key_package
+                                .leaf_node
+                                .capabilities
+                                .cipher_suites
+                                .contains(&cipher_suite)
+                    })
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+
+                (cipher_suite, incompatible)
+            })
+            .min_by_key(|(_, incompatible)| incompatible.len());
+
+        match best {
+            Some((cipher_suite, incompatible)) if incompatible.is_empty() => Ok(cipher_suite),
+            Some((_, incompatible)) => Err(MlsError::NoCommonCipherSuite(incompatible)),
+            None => Err(MlsError::NoCommonCipherSuite(
+                (0..key_packages.len()).collect(),
+            )),
+        }
+    }
+
     /// Returns key package extensions used by this client
     pub fn key_package_extensions(&self) -> ExtensionList {
         self.config.key_package_extensions()
@@ -780,7 +1129,7 @@ mod tests {
 
     use super::*;
     use crate::{
-        crypto::test_utils::TestCryptoProvider,
+        crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
         identity::test_utils::{get_test_basic_credential, get_test_signing_identity},
         tree_kem::leaf_node::LeafNodeSource,
     };
@@ -791,9 +1140,10 @@ mod tests {
             message_processor::ProposalMessageDescription,
             proposal::Proposal,
             test_utils::{test_group, test_group_custom_config},
-            ReceivedMessage,
+            Capabilities,
         },
         psk::{ExternalPskId, PreSharedKey},
+        time::{MlsTime, TimeProvider},
     };
 
     use alloc::vec;
@@ -837,6 +1187,70 @@ mod tests {
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_package_lifetime_uses_time_provider() {
+        struct FixedTime;
+
+        impl TimeProvider for FixedTime {
+            fn current_time(&self) -> MlsTime {
+                MlsTime::from(1_000_000)
+            }
+        }
+
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+
+        let client = TestClientBuilder::new_for_test()
+            .time_provider(FixedTime)
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build();
+
+        let key_package = client.generate_key_package_message().await.unwrap();
+        let key_package = key_package.into_key_package().unwrap();
+
+        assert_matches!(
+            key_package.leaf_node.leaf_node_source,
+            LeafNodeSource::KeyPackage(lifetime) if lifetime.not_before == 1_000_000
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn welcome_key_package_references_skip_expired_key_packages() {
+        let (client, expired) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let valid = client.generate_key_package_message().await.unwrap();
+
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let expired = expired
+            .key_package_reference(&cipher_suite_provider)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let valid = valid
+            .key_package_reference(&cipher_suite_provider)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut references = client.welcome_key_package_references().await.unwrap();
+        references.sort();
+
+        let mut expected = vec![expired.clone(), valid.clone()];
+        expected.sort();
+
+        assert_eq!(references, expected);
+
+        let store = client.key_package_store();
+        let mut data = store.get(&expired).unwrap();
+        data.expiration = 0;
+        store.insert(expired.to_vec(), data);
+
+        let references = client.welcome_key_package_references().await.unwrap();
+        assert_eq!(references, vec![valid]);
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn new_member_add_proposal_adds_to_group() {
@@ -1046,4 +1460,149 @@ mod tests {
         let bob = alice.to_builder().extension_type(34.into()).build();
         assert_eq!(bob.config.supported_extensions(), [33, 34].map(Into::into));
     }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn key_package_with_cipher_suites(cipher_suites: Vec<CipherSuite>) -> MlsMessage {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, cipher_suites[0], "peer").await;
+
+        client
+            .key_package_builder()
+            .capabilities(Capabilities {
+                cipher_suites,
+                ..Default::default()
+            })
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn cipher_suite_is_selected_by_preference() {
+        let cs_a = TEST_CIPHER_SUITE;
+
+        let Some(cs_b) = TestCryptoProvider::all_supported_cipher_suites()
+            .into_iter()
+            .find(|cs| *cs != cs_a)
+        else {
+            return;
+        };
+
+        let (identity, secret_key) = get_test_signing_identity(cs_a, b"alice").await;
+
+        let alice = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, cs_a)
+            .cipher_suite_preferences([cs_b, cs_a])
+            .build();
+
+        // Key packages of `cs_a` and `cs_b` respectively, both supporting
+        // both cipher suites.
+        let bob = key_package_with_cipher_suites(vec![cs_a, cs_b]).await;
+        let carol = key_package_with_cipher_suites(vec![cs_b, cs_a]).await;
+        let dave = key_package_with_cipher_suites(vec![cs_a]).await;
+
+        assert_eq!(
+            alice
+                .select_cipher_suite(core::slice::from_ref(&bob))
+                .unwrap(),
+            cs_a
+        );
+        assert_eq!(alice.select_cipher_suite(&[]).unwrap(), cs_a);
+
+        assert_eq!(
+            alice
+                .select_cipher_suite(&[bob.clone(), dave.clone()])
+                .unwrap(),
+            cs_a
+        );
+
+        let res = alice.select_cipher_suite(core::slice::from_ref(&carol));
+        assert_matches!(res, Err(MlsError::NoCommonCipherSuite(i)) if i == [0]);
+
+        let res = alice.select_cipher_suite(&[bob.clone(), carol.clone(), dave]);
+        assert_matches!(res, Err(MlsError::NoCommonCipherSuite(i)) if i == [1]);
+
+        let (identity, secret_key) = get_test_signing_identity(cs_b, b"alice").await;
+
+        let alice = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, cs_b)
+            .cipher_suite_preferences([cs_a, cs_b])
+            .build();
+
+        assert_eq!(alice.select_cipher_suite(&[carol]).unwrap(), cs_b);
+
+        let res = alice.select_cipher_suite(&[bob]);
+        assert_matches!(res, Err(MlsError::NoCommonCipherSuite(i)) if i == [0]);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_created_with_options_enforces_max_members() {
+        use crate::extension::group_policy::{GroupPolicyExt, GROUP_POLICY_EXTENSION};
+
+        let add_policy_extension =
+            |c: &mut TestClientConfig| c.0.settings.extension_types.push(GROUP_POLICY_EXTENSION);
+
+        let (alice, _) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "alice",
+            add_policy_extension,
+        )
+        .await;
+
+        let res = alice
+            .create_group_with_options(CreateGroupOptions::new().with_max_members(0))
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::GroupSizeLimitExceeded(0)));
+
+        let options = CreateGroupOptions::new()
+            .with_group_id(b"group".to_vec())
+            .with_max_members(2)
+            .with_max_epoch_retention(3);
+
+        let mut group = alice.create_group_with_options(options).await.unwrap();
+
+        assert_eq!(group.group_id(), b"group");
+
+        let policy = group.group_policy().unwrap().unwrap();
+        assert_eq!(policy.max_members(), Some(2));
+        assert_eq!(policy.max_epoch_retention(), Some(3));
+        assert_eq!(policy.padding_mode(), None);
+        assert_ne!(policy, GroupPolicyExt::default());
+
+        let (_, bob) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "bob",
+            add_policy_extension,
+        )
+        .await;
+
+        let (_, carol) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "carol",
+            add_policy_extension,
+        )
+        .await;
+
+        group
+            .commit_builder()
+            .add_member(bob)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        group.apply_pending_commit().await.unwrap();
+
+        let res = group
+            .commit_builder()
+            .add_member(carol)
+            .unwrap()
+            .build()
+            .await;
+        assert_matches!(res, Err(MlsError::GroupSizeLimitExceeded(2)));
+    }
 }
@@ -19,18 +19,35 @@ use crate::{
     identity::SigningIdentity,
     protocol_version::ProtocolVersion,
     psk::{ExternalPskId, PreSharedKey},
+    security_event::{SecurityEventSink, SharedSecurityEventSink},
     storage_provider::in_memory::{
         InMemoryGroupStateStorage, InMemoryKeyPackageStorage, InMemoryPreSharedKeyStorage,
     },
+    time::{SharedTimeProvider, TimeProvider},
     tree_kem::{Capabilities, Lifetime},
     Sealed,
 };
 
-#[cfg(feature = "std")]
-use crate::time::MlsTime;
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::{
+    external_removal::{ExternalRemovalPolicy, SharedExternalRemovalPolicy},
+    LeafSignatureVerification,
+};
+
+#[cfg(feature = "private_message")]
+use crate::group::message_expiry::{MessageExpiryPolicy, SharedMessageExpiryPolicy};
+
+#[cfg(feature = "psk")]
+use crate::group::recovery::{RecoveryPolicy, SharedRecoveryPolicy};
 
 use alloc::vec::Vec;
 
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
 #[cfg(feature = "sqlite")]
 use mls_rs_provider_sqlite::{
     SqLiteDataStorageEngine, SqLiteDataStorageError,
@@ -532,6 +549,125 @@ impl<C: IntoConfig> ClientBuilder<C> {
         ClientBuilder(c)
     }
 
+    /// Set the cipher suites to select from in
+    /// [`Client::select_cipher_suite`](crate::Client::select_cipher_suite),
+    /// in order of preference.
+    ///
+    /// By default, all cipher suites of the crypto provider are used in the
+    /// order of
+    /// [`CryptoProvider::supported_cipher_suites`](crate::CryptoProvider::supported_cipher_suites).
+    pub fn cipher_suite_preferences<I>(self, cipher_suites: I) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        I: IntoIterator<Item = CipherSuite>,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.cipher_suite_preferences = cipher_suites.into_iter().collect();
+        ClientBuilder(c)
+    }
+
+    /// Set the sink receiving a [`SecurityEvent`](crate::security_event::SecurityEvent)
+    /// each time an incoming message is rejected because of a protocol violation.
+    ///
+    /// By default, no events are emitted.
+    pub fn security_event_sink<S>(self, sink: S) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        S: SecurityEventSink + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.security_event_sink = Some(SharedSecurityEventSink(Arc::new(sink)));
+        ClientBuilder(c)
+    }
+
+    /// Set the source of the current time, used instead of the system clock.
+    ///
+    /// By default, the system clock is used. See [`TimeProvider`].
+    pub fn time_provider<T>(self, provider: T) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        T: TimeProvider + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.time_provider = Some(SharedTimeProvider(Arc::new(provider)));
+        ClientBuilder(c)
+    }
+
+    /// Set the policy applied to member removals proposed by external senders.
+    ///
+    /// By default, such proposals are only cached and no commit deadline is
+    /// recorded. See [`ExternalRemovalPolicy`].
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn external_removal_policy<P>(self, policy: P) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        P: ExternalRemovalPolicy + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.external_removal_policy = Some(SharedExternalRemovalPolicy(Arc::new(policy)));
+        ClientBuilder(c)
+    }
+
+    /// Set the policy deciding whether application messages carrying an
+    /// expiry have expired.
+    ///
+    /// By default, expired messages are not rejected. See
+    /// [`MessageExpiryPolicy`].
+    #[cfg(feature = "private_message")]
+    pub fn message_expiry_policy<P>(self, policy: P) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        P: MessageExpiryPolicy + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.message_expiry_policy = Some(SharedMessageExpiryPolicy(Arc::new(policy)));
+        ClientBuilder(c)
+    }
+
+    /// Set the policy deciding who may replace a lost group with
+    /// [`Client::recover_group`](crate::Client::recover_group).
+    ///
+    /// By default, groups can't be recovered. See [`RecoveryPolicy`].
+    #[cfg(feature = "psk")]
+    pub fn recovery_policy<P>(self, policy: P) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        P: RecoveryPolicy + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.recovery_policy = Some(SharedRecoveryPolicy(Arc::new(policy)));
+        ClientBuilder(c)
+    }
+
+    /// Set whether the leaves of ratchet trees attested by an external
+    /// sender are validated when joining a group with
+    /// [`Client::join_group_with_tree_attestation`](crate::Client::join_group_with_tree_attestation).
+    ///
+    /// By default, all leaves are validated. See
+    /// [`LeafSignatureVerification`] for the security implications.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn leaf_signature_verification(
+        self,
+        verification: LeafSignatureVerification,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.leaf_signature_verification = verification;
+        ClientBuilder(c)
+    }
+
+    /// Store the ratchet trees of groups apart from their state, as a full
+    /// tree every `interval` epochs and as changes from the tree of the
+    /// previous epoch in between.
+    ///
+    /// Group states then only grow with the number of prior epochs kept,
+    /// instead of holding a full tree each. Loading a group rebuilds its
+    /// tree from the last full tree, applying at most `interval - 1`
+    /// changes. An interval of 0 is treated as 1, storing a full tree
+    /// every epoch.
+    ///
+    /// By default, and if the [`GroupStateStorage`] doesn't support tree
+    /// records, the tree is stored with the group state.
+    #[cfg(feature = "prior_epoch")]
+    pub fn tree_checkpoint_interval(self, interval: u32) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.tree_checkpoint_interval = Some(interval.max(1));
+        ClientBuilder(c)
+    }
+
     #[cfg(any(test, feature = "test_util"))]
     pub(crate) fn key_package_not_before(
         self,
@@ -742,11 +878,9 @@ where
     }
 
     fn lifetime(&self) -> Lifetime {
-        #[cfg(feature = "std")]
-        let now_timestamp = MlsTime::now().seconds_since_epoch();
-
-        #[cfg(not(feature = "std"))]
-        let now_timestamp = 0;
+        let now_timestamp = self
+            .current_time()
+            .map_or(0, |time| time.seconds_since_epoch());
 
         #[cfg(test)]
         let now_timestamp = self
@@ -763,6 +897,58 @@ where
     fn supported_custom_proposals(&self) -> Vec<crate::group::proposal::ProposalType> {
         self.settings.custom_proposal_types.clone()
     }
+
+    fn cipher_suite_preferences(&self) -> Vec<CipherSuite> {
+        self.settings.cipher_suite_preferences.clone()
+    }
+
+    fn security_event_sink(&self) -> Option<Arc<dyn SecurityEventSink>> {
+        self.settings
+            .security_event_sink
+            .as_ref()
+            .map(|sink| sink.0.clone())
+    }
+
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        self.settings
+            .time_provider
+            .as_ref()
+            .map(|provider| provider.0.clone())
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn external_removal_policy(&self) -> Option<Arc<dyn ExternalRemovalPolicy>> {
+        self.settings
+            .external_removal_policy
+            .as_ref()
+            .map(|policy| policy.0.clone())
+    }
+
+    #[cfg(feature = "private_message")]
+    fn message_expiry_policy(&self) -> Option<Arc<dyn MessageExpiryPolicy>> {
+        self.settings
+            .message_expiry_policy
+            .as_ref()
+            .map(|policy| policy.0.clone())
+    }
+
+    #[cfg(feature = "psk")]
+    fn recovery_policy(&self) -> Option<Arc<dyn RecoveryPolicy>> {
+        self.settings
+            .recovery_policy
+            .as_ref()
+            .map(|policy| policy.0.clone())
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn leaf_signature_verification(&self) -> LeafSignatureVerification {
+        self.settings.leaf_signature_verification
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    fn tree_checkpoint_interval(&self) -> Option<u32> {
+        self.settings.tree_checkpoint_interval
+    }
 }
 
 impl<Kpr, Ps, Gss, Ip, Pr, Cp> Sealed for Config<Kpr, Ps, Gss, Ip, Pr, Cp> {}
@@ -863,6 +1049,43 @@ impl<T: MlsConfig> ClientConfig for T {
     fn supported_credential_types(&self) -> Vec<CredentialType> {
         self.get().supported_credential_types()
     }
+
+    fn cipher_suite_preferences(&self) -> Vec<CipherSuite> {
+        self.get().cipher_suite_preferences()
+    }
+
+    fn security_event_sink(&self) -> Option<Arc<dyn SecurityEventSink>> {
+        self.get().security_event_sink()
+    }
+
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        self.get().time_provider()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn external_removal_policy(&self) -> Option<Arc<dyn ExternalRemovalPolicy>> {
+        self.get().external_removal_policy()
+    }
+
+    #[cfg(feature = "private_message")]
+    fn message_expiry_policy(&self) -> Option<Arc<dyn MessageExpiryPolicy>> {
+        self.get().message_expiry_policy()
+    }
+
+    #[cfg(feature = "psk")]
+    fn recovery_policy(&self) -> Option<Arc<dyn RecoveryPolicy>> {
+        self.get().recovery_policy()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn leaf_signature_verification(&self) -> LeafSignatureVerification {
+        self.get().leaf_signature_verification()
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    fn tree_checkpoint_interval(&self) -> Option<u32> {
+        self.get().tree_checkpoint_interval()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -873,6 +1096,19 @@ pub(crate) struct Settings {
     pub(crate) key_package_extensions: ExtensionList,
     pub(crate) leaf_node_extensions: ExtensionList,
     pub(crate) lifetime_in_s: u64,
+    pub(crate) cipher_suite_preferences: Vec<CipherSuite>,
+    pub(crate) security_event_sink: Option<SharedSecurityEventSink>,
+    pub(crate) time_provider: Option<SharedTimeProvider>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) external_removal_policy: Option<SharedExternalRemovalPolicy>,
+    #[cfg(feature = "private_message")]
+    pub(crate) message_expiry_policy: Option<SharedMessageExpiryPolicy>,
+    #[cfg(feature = "psk")]
+    pub(crate) recovery_policy: Option<SharedRecoveryPolicy>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) leaf_signature_verification: LeafSignatureVerification,
+    #[cfg(feature = "prior_epoch")]
+    pub(crate) tree_checkpoint_interval: Option<u32>,
     #[cfg(any(test, feature = "test_util"))]
     pub(crate) key_package_not_before: Option<u64>,
 }
@@ -886,6 +1122,19 @@ impl Default for Settings {
             leaf_node_extensions: Default::default(),
             lifetime_in_s: 365 * 24 * 3600,
             custom_proposal_types: Default::default(),
+            cipher_suite_preferences: Default::default(),
+            security_event_sink: None,
+            time_provider: None,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_policy: None,
+            #[cfg(feature = "private_message")]
+            message_expiry_policy: None,
+            #[cfg(feature = "psk")]
+            recovery_policy: None,
+            #[cfg(feature = "by_ref_proposal")]
+            leaf_signature_verification: LeafSignatureVerification::Always,
+            #[cfg(feature = "prior_epoch")]
+            tree_checkpoint_interval: None,
             #[cfg(any(test, feature = "test_util"))]
             key_package_not_before: None,
         }
@@ -909,6 +1158,19 @@ pub(crate) fn recreate_config<T: ClientConfig>(
                 let l = c.lifetime();
                 l.not_after - l.not_before
             },
+            cipher_suite_preferences: c.cipher_suite_preferences(),
+            security_event_sink: c.security_event_sink().map(SharedSecurityEventSink),
+            time_provider: c.time_provider().map(SharedTimeProvider),
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_policy: c.external_removal_policy().map(SharedExternalRemovalPolicy),
+            #[cfg(feature = "private_message")]
+            message_expiry_policy: c.message_expiry_policy().map(SharedMessageExpiryPolicy),
+            #[cfg(feature = "psk")]
+            recovery_policy: c.recovery_policy().map(SharedRecoveryPolicy),
+            #[cfg(feature = "by_ref_proposal")]
+            leaf_signature_verification: c.leaf_signature_verification(),
+            #[cfg(feature = "prior_epoch")]
+            tree_checkpoint_interval: c.tree_checkpoint_interval(),
             #[cfg(any(test, feature = "test_util"))]
             key_package_not_before: None,
         },
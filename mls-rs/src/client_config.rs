@@ -3,18 +3,36 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use crate::{
+    cipher_suite::CipherSuite,
     extension::ExtensionType,
     group::{mls_rules::MlsRules, proposal::ProposalType},
     identity::CredentialType,
     protocol_version::ProtocolVersion,
+    security_event::SecurityEventSink,
+    time::{MlsTime, TimeProvider},
     tree_kem::{leaf_node::ConfigProperties, Capabilities, Lifetime},
     ExtensionList,
 };
 use alloc::vec::Vec;
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::{external_removal::ExternalRemovalPolicy, LeafSignatureVerification};
+
+#[cfg(feature = "private_message")]
+use crate::group::message_expiry::MessageExpiryPolicy;
+
+#[cfg(feature = "psk")]
+use crate::group::recovery::RecoveryPolicy;
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
 use mls_rs_core::{
     crypto::CryptoProvider, group::GroupStateStorage, identity::IdentityProvider,
     key_package::KeyPackageStorage, psk::PreSharedKeyStorage,
 };
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
 
 pub trait ClientConfig: Send + Sync + Clone {
     type KeyPackageRepository: KeyPackageStorage + Clone;
@@ -41,6 +59,47 @@ pub trait ClientConfig: Send + Sync + Clone {
     fn leaf_node_extensions(&self) -> ExtensionList;
     fn lifetime(&self) -> Lifetime;
 
+    fn cipher_suite_preferences(&self) -> Vec<CipherSuite> {
+        Vec::new()
+    }
+
+    fn security_event_sink(&self) -> Option<Arc<dyn SecurityEventSink>> {
+        None
+    }
+
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        None
+    }
+
+    fn current_time(&self) -> Option<MlsTime> {
+        crate::time::current_time(self.time_provider())
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn external_removal_policy(&self) -> Option<Arc<dyn ExternalRemovalPolicy>> {
+        None
+    }
+
+    #[cfg(feature = "private_message")]
+    fn message_expiry_policy(&self) -> Option<Arc<dyn MessageExpiryPolicy>> {
+        None
+    }
+
+    #[cfg(feature = "psk")]
+    fn recovery_policy(&self) -> Option<Arc<dyn RecoveryPolicy>> {
+        None
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn leaf_signature_verification(&self) -> LeafSignatureVerification {
+        LeafSignatureVerification::Always
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    fn tree_checkpoint_interval(&self) -> Option<u32> {
+        None
+    }
+
     fn capabilities(&self) -> Capabilities {
         Capabilities {
             protocol_versions: self.supported_protocol_versions(),
@@ -5,12 +5,16 @@
 pub(crate) use mls_rs_core::crypto::CipherSuiteProvider;
 
 pub use mls_rs_core::crypto::{
-    HpkeCiphertext, HpkeContextR, HpkeContextS, HpkePublicKey, HpkeSecretKey, SignaturePublicKey,
-    SignatureSecretKey,
+    HpkeCiphertext, HpkeContextR, HpkeContextS, HpkePublicKey, HpkeSecretKey, SelfTestAlgorithm,
+    SelfTestFailure, SelfTestReport, SelfTestedCryptoProvider, SignaturePublicKey,
+    SignatureSecretKey, Signer, SignerCipherSuiteProvider, SignerCryptoProvider,
+    SignerProviderError,
 };
 
 pub use mls_rs_core::secret::Secret;
 
+pub use crate::signer::{sign_with_label, verify_with_label};
+
 #[cfg(test)]
 pub(crate) mod test_utils {
     use cfg_if::cfg_if;
@@ -41,3 +45,144 @@ pub(crate) mod test_utils {
         TestCryptoProvider::new().cipher_suite_provider(CipherSuite::from(cipher_suite))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{sync::Arc, vec, vec::Vec};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use mls_rs_core::{crypto::CipherSuite, error::IntoAnyError, identity::SigningIdentity};
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client_builder::{ClientBuilder, MlsConfig},
+        identity::basic::{BasicCredential, BasicIdentityProvider},
+        CipherSuiteProvider, Client,
+    };
+
+    use super::{
+        test_utils::{test_cipher_suite_provider, TestCryptoProvider},
+        SignatureSecretKey, Signer, SignerCryptoProvider,
+    };
+
+    const KEY_ID: &[u8] = b"remote key";
+
+    #[derive(Debug)]
+    struct UnknownKey;
+
+    impl IntoAnyError for UnknownKey {}
+
+    // Signer standing for a remote service holding the key identified by
+    // `KEY_ID`.
+    #[derive(Clone)]
+    struct RemoteSigner {
+        secret_key: SignatureSecretKey,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+    #[cfg_attr(
+        all(not(target_arch = "wasm32"), mls_build_async),
+        maybe_async::must_be_async
+    )]
+    impl Signer for RemoteSigner {
+        type Error = UnknownKey;
+
+        async fn sign(
+            &self,
+            cipher_suite: CipherSuite,
+            key: &SignatureSecretKey,
+            data: &[u8],
+        ) -> Result<Vec<u8>, Self::Error> {
+            if key.as_bytes() != KEY_ID {
+                return Err(UnknownKey);
+            }
+
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            test_cipher_suite_provider(cipher_suite)
+                .sign(&self.secret_key, data)
+                .await
+                .map_err(|_| UnknownKey)
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn remote_signing_client(name: &[u8]) -> (Client<impl MlsConfig>, Arc<AtomicUsize>) {
+        let (secret_key, public_key) = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        let signer = RemoteSigner {
+            secret_key,
+            calls: Default::default(),
+        };
+
+        let calls = signer.calls.clone();
+
+        let client = ClientBuilder::new()
+            .crypto_provider(SignerCryptoProvider::new(TestCryptoProvider::new(), signer))
+            .identity_provider(BasicIdentityProvider::new())
+            .signing_identity(
+                SigningIdentity::new(
+                    BasicCredential::new(name.to_vec()).into_credential(),
+                    public_key,
+                ),
+                SignatureSecretKey::new_slice(KEY_ID),
+                TEST_CIPHER_SUITE,
+            )
+            .used_protocol_version(TEST_PROTOCOL_VERSION)
+            .build();
+
+        (client, calls)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn remote_signer_signs_key_packages_and_commits() {
+        let (alice, _) = remote_signing_client(b"alice").await;
+        let (bob, bob_calls) = remote_signing_client(b"bob").await;
+
+        let key_package = bob.generate_key_package_message().await.unwrap();
+        assert_eq!(bob_calls.load(Ordering::SeqCst), 2);
+
+        let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        let commit = bob_group.commit(vec![]).await.unwrap().commit_message;
+        bob_group.apply_pending_commit().await.unwrap();
+        alice_group.process_incoming_message(commit).await.unwrap();
+
+        assert_eq!(
+            alice_group.epoch_authenticator().unwrap(),
+            bob_group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commits_without_new_members_sign_no_group_info() {
+        let (alice, calls) = remote_signing_client(b"alice").await;
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+
+        let before = calls.load(Ordering::SeqCst);
+        group.commit(vec![]).await.unwrap();
+
+        // The leaf node of the update path and the commit itself.
+        assert_eq!(calls.load(Ordering::SeqCst) - before, 2);
+    }
+}
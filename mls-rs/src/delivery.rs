@@ -0,0 +1,113 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Minimal interface to a delivery service, the untrusted server relaying
+//! the messages of MLS clients.
+//!
+//! [`DeliveryService`] covers what clients need from the delivery service to
+//! run a group end to end: publishing and claiming key packages, sending
+//! messages to a group, receiving welcome messages and catching up with the
+//! messages of a range of epochs. Commits are accepted in order: only the
+//! first commit sent for an epoch is accepted, and a member whose commit is
+//! rejected must catch up before committing again.
+//!
+//! [`InMemoryDeliveryService`] keeps everything in memory and can be shared
+//! by the clients of a process, which is enough for tests and examples.
+//! With the `http_delivery` feature, `http::HttpDeliveryService` talks to a
+//! delivery service over HTTP, such as the one served by
+//! `http::HttpDeliveryServer`.
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+use mls_rs_core::error::{AnyError, IntoAnyError};
+
+use crate::MlsMessage;
+
+#[cfg(feature = "http_delivery")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http_delivery")))]
+pub mod http;
+mod in_memory;
+
+pub use in_memory::InMemoryDeliveryService;
+
+#[derive(Debug, thiserror::Error)]
+/// Error returned by a [`DeliveryService`].
+pub enum DeliveryServiceError {
+    #[error("message is not sent to a group")]
+    /// The message sent to a group is a welcome message, a group info or a
+    /// key package.
+    NotAGroupMessage,
+    #[error("unexpected response from the delivery service")]
+    /// The response of the delivery service is malformed.
+    UnexpectedResponse,
+    #[error(transparent)]
+    /// Error encoding or decoding a message.
+    SerializationError(AnyError),
+    #[error(transparent)]
+    /// Error of the connection to the delivery service.
+    TransportError(std::io::Error),
+}
+
+impl IntoAnyError for DeliveryServiceError {
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+impl From<crate::client::MlsError> for DeliveryServiceError {
+    fn from(e: crate::client::MlsError) -> Self {
+        Self::SerializationError(e.into_any_error())
+    }
+}
+
+/// Delivery service relaying messages between MLS clients.
+///
+/// Clients are addressed by an opaque identity chosen by the application,
+/// under which they publish their key packages and receive their welcome
+/// messages.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait DeliveryService: Send + Sync {
+    type Error: IntoAnyError;
+
+    /// Publish `key_package` for other clients to add `identity` to their
+    /// groups.
+    async fn publish_key_package(
+        &mut self,
+        identity: &[u8],
+        key_package: MlsMessage,
+    ) -> Result<(), Self::Error>;
+
+    /// Claim a key package published for `identity`, which is then no
+    /// longer handed out.
+    async fn fetch_key_package(
+        &mut self,
+        identity: &[u8],
+    ) -> Result<Option<MlsMessage>, Self::Error>;
+
+    /// Send `message` to the group it belongs to, returning whether it was
+    /// accepted. Commits are rejected if a commit was already accepted for
+    /// their epoch.
+    async fn send_to_group(&mut self, message: MlsMessage) -> Result<bool, Self::Error>;
+
+    /// Send `welcome` to the client `identity`.
+    async fn send_welcome(
+        &mut self,
+        identity: &[u8],
+        welcome: MlsMessage,
+    ) -> Result<(), Self::Error>;
+
+    /// Take the welcome messages sent to `identity`.
+    async fn fetch_welcomes(&mut self, identity: &[u8]) -> Result<Vec<MlsMessage>, Self::Error>;
+
+    /// Messages accepted for the group `group_id` in the epochs of
+    /// `epochs`, in the order they were accepted.
+    async fn fetch_messages(
+        &mut self,
+        group_id: &[u8],
+        epochs: Range<u64>,
+    ) -> Result<Vec<MlsMessage>, Self::Error>;
+}
@@ -0,0 +1,459 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Reference delivery service over HTTP/1.1.
+//!
+//! The API maps each method of [`DeliveryService`] onto a request, with
+//! identities and group ids hex encoded in paths and messages carried as
+//! `message/mls` bodies:
+//!
+//! | Method | Request | Response |
+//! |--------|---------|----------|
+//! | `publish_key_package` | `PUT /key-packages/{identity}` | `204` |
+//! | `fetch_key_package` | `POST /key-packages/{identity}/claim` | `200` with the key package, or `404` |
+//! | `send_to_group` | `POST /messages` | `204`, or `409` for a rejected commit |
+//! | `send_welcome` | `POST /welcomes/{identity}` | `204` |
+//! | `fetch_welcomes` | `POST /welcomes/{identity}/claim` | `200` with a vector of messages |
+//! | `fetch_messages` | `GET /groups/{group id}/messages?from={start}&to={end}` | `200` with a vector of messages |
+//!
+//! Vectors of messages are encoded as MLS vectors. Each request is sent on
+//! its own connection, with blocking I/O even in async builds, which makes
+//! the implementation only suitable for tests and examples.
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::Range;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+};
+
+use mls_rs_codec::{MlsDecode, MlsEncode};
+use mls_rs_core::error::IntoAnyError;
+
+use crate::MlsMessage;
+
+use super::{DeliveryService, DeliveryServiceError, InMemoryDeliveryService};
+
+const MLS_MEDIA_TYPE: &str = "message/mls";
+
+/// Maximum size of the body of the requests accepted by
+/// [`HttpDeliveryServer`] and of the responses accepted by
+/// [`HttpDeliveryService`].
+pub const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Client of a delivery service served over HTTP.
+#[derive(Clone, Debug)]
+pub struct HttpDeliveryService {
+    addr: SocketAddr,
+}
+
+impl HttpDeliveryService {
+    /// Client of the delivery service listening on `addr`.
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<(u16, Vec<u8>), DeliveryServiceError> {
+        let mut stream =
+            TcpStream::connect(self.addr).map_err(DeliveryServiceError::TransportError)?;
+
+        let head = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {}\r\nContent-Type: {MLS_MEDIA_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.addr,
+            body.len()
+        );
+
+        stream
+            .write_all(head.as_bytes())
+            .and_then(|_| stream.write_all(body))
+            .map_err(DeliveryServiceError::TransportError)?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+
+        reader
+            .read_line(&mut status_line)
+            .map_err(DeliveryServiceError::TransportError)?;
+
+        let status = status_line
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or(DeliveryServiceError::UnexpectedResponse)?;
+
+        let body = read_body(&mut reader).map_err(DeliveryServiceError::TransportError)?;
+
+        Ok((status, body))
+    }
+
+    fn expect_status(status: u16, expected: u16) -> Result<(), DeliveryServiceError> {
+        (status == expected)
+            .then_some(())
+            .ok_or(DeliveryServiceError::UnexpectedResponse)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl DeliveryService for HttpDeliveryService {
+    type Error = DeliveryServiceError;
+
+    async fn publish_key_package(
+        &mut self,
+        identity: &[u8],
+        key_package: MlsMessage,
+    ) -> Result<(), Self::Error> {
+        let path = format!("/key-packages/{}", hex::encode(identity));
+        let (status, _) = self.request("PUT", &path, &key_package.to_bytes()?)?;
+
+        Self::expect_status(status, 204)
+    }
+
+    async fn fetch_key_package(
+        &mut self,
+        identity: &[u8],
+    ) -> Result<Option<MlsMessage>, Self::Error> {
+        let path = format!("/key-packages/{}/claim", hex::encode(identity));
+
+        match self.request("POST", &path, &[])? {
+            (200, body) => Ok(Some(MlsMessage::from_bytes(&body)?)),
+            (404, _) => Ok(None),
+            _ => Err(DeliveryServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn send_to_group(&mut self, message: MlsMessage) -> Result<bool, Self::Error> {
+        match self.request("POST", "/messages", &message.to_bytes()?)? {
+            (204, _) => Ok(true),
+            (409, _) => Ok(false),
+            (400, _) => Err(DeliveryServiceError::NotAGroupMessage),
+            _ => Err(DeliveryServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn send_welcome(
+        &mut self,
+        identity: &[u8],
+        welcome: MlsMessage,
+    ) -> Result<(), Self::Error> {
+        let path = format!("/welcomes/{}", hex::encode(identity));
+        let (status, _) = self.request("POST", &path, &welcome.to_bytes()?)?;
+
+        Self::expect_status(status, 204)
+    }
+
+    async fn fetch_welcomes(&mut self, identity: &[u8]) -> Result<Vec<MlsMessage>, Self::Error> {
+        let path = format!("/welcomes/{}/claim", hex::encode(identity));
+        let (status, body) = self.request("POST", &path, &[])?;
+
+        Self::expect_status(status, 200)?;
+        decode_messages(&body)
+    }
+
+    async fn fetch_messages(
+        &mut self,
+        group_id: &[u8],
+        epochs: Range<u64>,
+    ) -> Result<Vec<MlsMessage>, Self::Error> {
+        let path = format!(
+            "/groups/{}/messages?from={}&to={}",
+            hex::encode(group_id),
+            epochs.start,
+            epochs.end
+        );
+
+        let (status, body) = self.request("GET", &path, &[])?;
+
+        Self::expect_status(status, 200)?;
+        decode_messages(&body)
+    }
+}
+
+/// Server exposing an [`InMemoryDeliveryService`] over HTTP, to be used by
+/// [`HttpDeliveryService`] clients.
+#[derive(Clone, Debug, Default)]
+pub struct HttpDeliveryServer {
+    delivery_service: InMemoryDeliveryService,
+}
+
+impl HttpDeliveryServer {
+    /// Serve the state of `delivery_service`, which can still be used
+    /// directly by in-process clients.
+    pub fn new(delivery_service: InMemoryDeliveryService) -> Self {
+        Self { delivery_service }
+    }
+
+    /// Answer the requests of the connections accepted on `listener`, one
+    /// at a time, until accepting a connection fails.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            // A client failing in the middle of its request must not stop
+            // the server.
+            let _ = self.handle(stream?);
+        }
+
+        Ok(())
+    }
+
+    /// Answer the request sent on `stream`.
+    pub fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default().to_string();
+
+        let body = read_body(&mut reader)?;
+
+        let (status, body) = match self.route(&method, &target, &body) {
+            Ok(response) => response,
+            Err(_) => (400, Vec::new()),
+        };
+
+        let reason = match status {
+            200 => "OK",
+            204 => "No Content",
+            404 => "Not Found",
+            409 => "Conflict",
+            _ => "Bad Request",
+        };
+
+        let mut stream = reader.into_inner();
+
+        write!(
+            stream,
+            "HTTP/1.1 {status} {reason}\r\nContent-Type: {MLS_MEDIA_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+
+        stream.write_all(&body)?;
+        stream.flush()
+    }
+
+    fn route(
+        &self,
+        method: &str,
+        target: &str,
+        body: &[u8],
+    ) -> Result<(u16, Vec<u8>), DeliveryServiceError> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+        let ds = &self.delivery_service;
+
+        match (method, segments.as_slice()) {
+            ("PUT", ["key-packages", identity]) => {
+                ds.publish(&decode_hex(identity)?, MlsMessage::from_bytes(body)?);
+                Ok((204, Vec::new()))
+            }
+            ("POST", ["key-packages", identity, "claim"]) => {
+                match ds.claim(&decode_hex(identity)?) {
+                    Some(key_package) => Ok((200, key_package.to_bytes()?)),
+                    None => Ok((404, Vec::new())),
+                }
+            }
+            ("POST", ["messages"]) => {
+                let status = if ds.send(MlsMessage::from_bytes(body)?)? {
+                    204
+                } else {
+                    409
+                };
+
+                Ok((status, Vec::new()))
+            }
+            ("POST", ["welcomes", identity]) => {
+                ds.deliver_welcome(&decode_hex(identity)?, MlsMessage::from_bytes(body)?);
+                Ok((204, Vec::new()))
+            }
+            ("POST", ["welcomes", identity, "claim"]) => {
+                let welcomes = ds.take_welcomes(&decode_hex(identity)?);
+                Ok((200, encode_messages(&welcomes)?))
+            }
+            ("GET", ["groups", group_id, "messages"]) => {
+                let epochs = parse_epochs(query).ok_or(DeliveryServiceError::UnexpectedResponse)?;
+                let messages = ds.messages(&decode_hex(group_id)?, epochs);
+                Ok((200, encode_messages(&messages)?))
+            }
+            _ => Ok((404, Vec::new())),
+        }
+    }
+}
+
+/// Skip the headers of a request or a response whose first line was already
+/// read, and read its body, which must be at most [`MAX_BODY_SIZE`] bytes.
+fn read_body<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut content_length = 0;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    let mut body = Vec::new();
+    reader.take(content_length as u64).read_to_end(&mut body)?;
+
+    if body.len() < content_length {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+
+    Ok(body)
+}
+
+fn parse_epochs(query: &str) -> Option<Range<u64>> {
+    let mut start = None;
+    let mut end = None;
+
+    for pair in query.split('&') {
+        match pair.split_once('=')? {
+            ("from", value) => start = value.parse().ok(),
+            ("to", value) => end = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(start?..end?)
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, DeliveryServiceError> {
+    hex::decode(value).map_err(|_| DeliveryServiceError::UnexpectedResponse)
+}
+
+fn encode_messages(messages: &[MlsMessage]) -> Result<Vec<u8>, DeliveryServiceError> {
+    messages
+        .mls_encode_to_vec()
+        .map_err(|e| DeliveryServiceError::SerializationError(e.into_any_error()))
+}
+
+fn decode_messages(bytes: &[u8]) -> Result<Vec<MlsMessage>, DeliveryServiceError> {
+    Vec::<MlsMessage>::mls_decode(&mut &*bytes)
+        .map_err(|e| DeliveryServiceError::SerializationError(e.into_any_error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use std::{
+        io::{BufReader, ErrorKind},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::{
+        client::test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        delivery::{DeliveryService, InMemoryDeliveryService},
+        group::test_utils::test_n_member_group,
+    };
+
+    use super::{read_body, HttpDeliveryServer, HttpDeliveryService, MAX_BODY_SIZE};
+
+    fn test_server() -> (InMemoryDeliveryService, HttpDeliveryService) {
+        let ds = InMemoryDeliveryService::new();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpDeliveryServer::new(ds.clone());
+
+        thread::spawn(move || server.serve(listener));
+
+        (ds, HttpDeliveryService::new(addr))
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_packages_and_welcomes_go_through_http() {
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let (_, mut client) = test_server();
+
+        client
+            .publish_key_package(b"bob", key_package.clone())
+            .await
+            .unwrap();
+
+        let claimed = client.fetch_key_package(b"bob").await.unwrap();
+        assert_eq!(claimed, Some(key_package.clone()));
+        assert_eq!(client.fetch_key_package(b"bob").await.unwrap(), None);
+
+        client
+            .send_welcome(b"bob", key_package.clone())
+            .await
+            .unwrap();
+
+        let welcomes = client.fetch_welcomes(b"bob").await.unwrap();
+        assert_eq!(welcomes, vec![key_package]);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_messages_go_through_http() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        let (ds, mut client) = test_server();
+
+        let group_id = groups[0].group.group_id().to_vec();
+        let epoch = groups[0].group.current_epoch();
+
+        let first = groups[0].group.commit(vec![]).await.unwrap();
+        let second = groups[1].group.commit(vec![]).await.unwrap();
+
+        assert!(client
+            .send_to_group(first.commit_message.clone())
+            .await
+            .unwrap());
+
+        assert!(!client.send_to_group(second.commit_message).await.unwrap());
+        assert_eq!(ds.group_epoch(&group_id), Some(epoch + 1));
+
+        let messages = client
+            .fetch_messages(&group_id, epoch..epoch + 1)
+            .await
+            .unwrap();
+
+        assert_eq!(messages, vec![first.commit_message]);
+    }
+
+    #[test]
+    fn oversized_and_truncated_bodies_are_rejected() {
+        let head = format!("Content-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1);
+        let res = read_body(&mut BufReader::new(head.as_bytes()));
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let res = read_body(&mut BufReader::new(&b"Content-Length: 4\r\n\r\nab"[..]));
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+        let body = read_body(&mut BufReader::new(&b"Content-Length: 2\r\n\r\nab"[..]));
+        assert_eq!(body.unwrap(), b"ab");
+    }
+}
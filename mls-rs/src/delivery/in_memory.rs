@@ -0,0 +1,258 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::ops::Range;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{group::ContentType, MlsMessage};
+
+use super::{DeliveryService, DeliveryServiceError};
+
+#[derive(Debug, Default)]
+struct GroupLog {
+    /// Epoch of the next commit to accept.
+    epoch: u64,
+    messages: Vec<(u64, MlsMessage)>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    key_packages: HashMap<Vec<u8>, VecDeque<MlsMessage>>,
+    welcomes: HashMap<Vec<u8>, Vec<MlsMessage>>,
+    groups: HashMap<Vec<u8>, GroupLog>,
+}
+
+/// Delivery service keeping all messages in memory.
+///
+/// All clones of an instance share the same state, so that each client of a
+/// test or an example can own a handle to the same delivery service. The
+/// epoch of a group is learned from the first message sent to it, and
+/// messages are never deleted.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryDeliveryService {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryDeliveryService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Epoch of the next commit accepted for the group `group_id`, if any
+    /// message was sent to it.
+    pub fn group_epoch(&self, group_id: &[u8]) -> Option<u64> {
+        self.lock().groups.get(group_id).map(|log| log.epoch)
+    }
+
+    pub(crate) fn publish(&self, identity: &[u8], key_package: MlsMessage) {
+        self.lock()
+            .key_packages
+            .entry(identity.to_vec())
+            .or_default()
+            .push_back(key_package);
+    }
+
+    pub(crate) fn claim(&self, identity: &[u8]) -> Option<MlsMessage> {
+        self.lock()
+            .key_packages
+            .get_mut(identity)
+            .and_then(VecDeque::pop_front)
+    }
+
+    pub(crate) fn send(&self, message: MlsMessage) -> Result<bool, DeliveryServiceError> {
+        let (Some(group_id), Some(epoch)) = (message.group_id(), message.epoch()) else {
+            return Err(DeliveryServiceError::NotAGroupMessage);
+        };
+
+        if message.content_type().is_none() {
+            return Err(DeliveryServiceError::NotAGroupMessage);
+        }
+
+        let mut state = self.lock();
+
+        let log = state
+            .groups
+            .entry(group_id.to_vec())
+            .or_insert_with(|| GroupLog {
+                epoch,
+                messages: Vec::new(),
+            });
+
+        if message.content_type() == Some(ContentType::Commit) {
+            if epoch != log.epoch {
+                return Ok(false);
+            }
+
+            log.epoch += 1;
+        }
+
+        log.messages.push((epoch, message));
+
+        Ok(true)
+    }
+
+    pub(crate) fn deliver_welcome(&self, identity: &[u8], welcome: MlsMessage) {
+        self.lock()
+            .welcomes
+            .entry(identity.to_vec())
+            .or_default()
+            .push(welcome);
+    }
+
+    pub(crate) fn take_welcomes(&self, identity: &[u8]) -> Vec<MlsMessage> {
+        self.lock().welcomes.remove(identity).unwrap_or_default()
+    }
+
+    pub(crate) fn messages(&self, group_id: &[u8], epochs: Range<u64>) -> Vec<MlsMessage> {
+        self.lock()
+            .groups
+            .get(group_id)
+            .map(|log| {
+                log.messages
+                    .iter()
+                    .filter(|(epoch, _)| epochs.contains(epoch))
+                    .map(|(_, message)| message.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl DeliveryService for InMemoryDeliveryService {
+    type Error = DeliveryServiceError;
+
+    async fn publish_key_package(
+        &mut self,
+        identity: &[u8],
+        key_package: MlsMessage,
+    ) -> Result<(), Self::Error> {
+        self.publish(identity, key_package);
+        Ok(())
+    }
+
+    async fn fetch_key_package(
+        &mut self,
+        identity: &[u8],
+    ) -> Result<Option<MlsMessage>, Self::Error> {
+        Ok(self.claim(identity))
+    }
+
+    async fn send_to_group(&mut self, message: MlsMessage) -> Result<bool, Self::Error> {
+        self.send(message)
+    }
+
+    async fn send_welcome(
+        &mut self,
+        identity: &[u8],
+        welcome: MlsMessage,
+    ) -> Result<(), Self::Error> {
+        self.deliver_welcome(identity, welcome);
+        Ok(())
+    }
+
+    async fn fetch_welcomes(&mut self, identity: &[u8]) -> Result<Vec<MlsMessage>, Self::Error> {
+        Ok(self.take_welcomes(identity))
+    }
+
+    async fn fetch_messages(
+        &mut self,
+        group_id: &[u8],
+        epochs: Range<u64>,
+    ) -> Result<Vec<MlsMessage>, Self::Error> {
+        Ok(self.messages(group_id, epochs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        delivery::{DeliveryService, DeliveryServiceError},
+        group::test_utils::test_n_member_group,
+    };
+
+    use super::InMemoryDeliveryService;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_packages_are_claimed_once() {
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut ds = InMemoryDeliveryService::new();
+
+        ds.publish_key_package(b"bob", key_package.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ds.fetch_key_package(b"bob").await.unwrap(),
+            Some(key_package)
+        );
+        assert_eq!(ds.fetch_key_package(b"bob").await.unwrap(), None);
+        assert_eq!(ds.fetch_key_package(b"alice").await.unwrap(), None);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn only_first_commit_of_epoch_is_accepted() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        let mut ds = InMemoryDeliveryService::new();
+
+        let group_id = groups[0].group.group_id().to_vec();
+        let epoch = groups[0].group.current_epoch();
+
+        let first = groups[0].group.commit(vec![]).await.unwrap();
+        let second = groups[1].group.commit(vec![]).await.unwrap();
+
+        assert!(ds
+            .send_to_group(first.commit_message.clone())
+            .await
+            .unwrap());
+        assert!(!ds.send_to_group(second.commit_message).await.unwrap());
+        assert_eq!(ds.group_epoch(&group_id), Some(epoch + 1));
+
+        let messages = ds
+            .fetch_messages(&group_id, epoch..epoch + 1)
+            .await
+            .unwrap();
+        assert_eq!(messages, vec![first.commit_message]);
+
+        let messages = ds.fetch_messages(&group_id, 0..epoch).await.unwrap();
+        assert!(messages.is_empty());
+
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let res = ds.send_to_group(key_package).await;
+        assert_matches!(res, Err(DeliveryServiceError::NotAGroupMessage));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn welcomes_are_taken() {
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut ds = InMemoryDeliveryService::new();
+
+        // Any message can stand in for a welcome message.
+        ds.send_welcome(b"bob", key_package.clone()).await.unwrap();
+
+        assert_eq!(ds.fetch_welcomes(b"bob").await.unwrap(), vec![key_package]);
+        assert!(ds.fetch_welcomes(b"bob").await.unwrap().is_empty());
+    }
+}
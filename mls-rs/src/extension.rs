@@ -8,6 +8,19 @@ pub(crate) use built_in::*;
 
 /// Default extension types required by the MLS RFC.
 pub mod built_in;
+/// Typed key package extension binding a key package to the channel it is
+/// uploaded over.
+pub mod channel_binding;
+/// Typed group context extension for common group metadata.
+pub mod group_metadata;
+/// Typed group context extension for limits enforced by all members.
+pub mod group_policy;
+/// Typed leaf node extension proving that an external commit may replace the
+/// leaf of an existing member.
+pub mod rejoin_proof;
+/// Typed group context extension restricting which members may send
+/// application messages.
+pub mod send_permissions;
 
 #[cfg(test)]
 pub(crate) mod test_utils {
@@ -66,6 +66,45 @@ impl MlsCodecExtension for ApplicationIdExt {
     }
 }
 
+/// Key package extension marking a last resort key package.
+///
+/// A last resort key package is used when the other key packages of a
+/// client have been exhausted. It isn't deleted from the
+/// [`KeyPackageStorage`](crate::KeyPackageStorage) when it is used to join
+/// a group, and should be replaced regularly by the client. The extension
+/// type is the one of draft-ietf-mls-extensions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LastResortExt;
+
+impl LastResortExt {
+    /// Extension type of the last resort extension.
+    pub const EXTENSION_TYPE: ExtensionType = ExtensionType::new(0x000A);
+}
+
+impl MlsSize for LastResortExt {
+    fn mls_encoded_len(&self) -> usize {
+        0
+    }
+}
+
+impl MlsEncode for LastResortExt {
+    fn mls_encode(&self, _writer: &mut Vec<u8>) -> Result<(), mls_rs_codec::Error> {
+        Ok(())
+    }
+}
+
+impl MlsDecode for LastResortExt {
+    fn mls_decode(_reader: &mut &[u8]) -> Result<Self, mls_rs_codec::Error> {
+        Ok(Self)
+    }
+}
+
+impl MlsCodecExtension for LastResortExt {
+    fn extension_type() -> ExtensionType {
+        Self::EXTENSION_TYPE
+    }
+}
+
 /// Representation of an MLS ratchet tree.
 ///
 /// Used to provide new members
@@ -0,0 +1,155 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::CipherSuiteProvider,
+    error::IntoAnyError,
+    extension::{ExtensionType, MlsCodecExtension},
+};
+
+use crate::{client::MlsError, KeyPackage};
+
+/// Extension type of [`ChannelBindingExt`], taken from the private use range.
+pub const CHANNEL_BINDING_EXTENSION: ExtensionType = ExtensionType::new(0xF6B3);
+
+/// Label to use with the exporter of the TLS (RFC 8446, section 7.5) or QUIC
+/// connection a key package is uploaded over, with an empty context. Both
+/// the client and the directory derive the exporter value with this label.
+pub const CHANNEL_BINDING_EXPORTER_LABEL: &str = "EXPORTER-mls-rs-key-package-binding";
+
+const CHANNEL_BINDING_LABEL: &[u8] = b"MLS 1.0 key package channel binding";
+
+/// Key package extension binding a key package to the channel it is uploaded
+/// over.
+///
+/// The extension holds a MAC keyed with the exporter value of the TLS or QUIC
+/// connection between the client and the directory, so that it doesn't
+/// reveal the exporter value. Being part of the signed content of the key
+/// package, it can't be changed without the signature key of the client. An
+/// on-path attacker terminating the connection sees a different exporter
+/// value on each side, and the directory rejects the key packages it relays
+/// with [`MlsError::ChannelBindingMismatch`].
+///
+/// The binding is set with
+/// [`KeyPackageBuilder::channel_binding`](crate::KeyPackageBuilder::channel_binding)
+/// and checked by the directory with
+/// [`ChannelBindingExt::verify_key_package`].
+#[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ChannelBindingExt {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    binding: Vec<u8>,
+}
+
+impl Debug for ChannelBindingExt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelBindingExt")
+            .field("binding", &mls_rs_core::debug::pretty_bytes(&self.binding))
+            .finish()
+    }
+}
+
+impl ChannelBindingExt {
+    /// Compute the binding to the channel with exporter value
+    /// `exporter_value`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        exporter_value: &[u8],
+    ) -> Result<Self, MlsError> {
+        let binding = cipher_suite_provider
+            .mac(exporter_value, CHANNEL_BINDING_LABEL)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        Ok(Self { binding })
+    }
+
+    /// Value of the binding.
+    pub fn binding(&self) -> &[u8] {
+        &self.binding
+    }
+
+    /// Check that `key_package` is bound to the channel with exporter value
+    /// `exporter_value`. The signature and other properties of the key
+    /// package are not validated.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_key_package<P: CipherSuiteProvider>(
+        key_package: &KeyPackage,
+        cipher_suite_provider: &P,
+        exporter_value: &[u8],
+    ) -> Result<(), MlsError> {
+        let not_found = MlsError::RequiredExtensionNotFound(CHANNEL_BINDING_EXTENSION);
+        let binding = key_package.extensions.get_as::<Self>()?.ok_or(not_found)?;
+
+        if binding != Self::new(cipher_suite_provider, exporter_value).await? {
+            return Err(MlsError::ChannelBindingMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+impl MlsCodecExtension for ChannelBindingExt {
+    fn extension_type() -> ExtensionType {
+        CHANNEL_BINDING_EXTENSION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        crypto::test_utils::test_cipher_suite_provider,
+    };
+
+    use super::{ChannelBindingExt, CHANNEL_BINDING_EXTENSION};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_package_is_bound_to_channel() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let key_package = client
+            .key_package_builder()
+            .channel_binding(b"exporter".to_vec())
+            .build()
+            .await
+            .unwrap()
+            .into_key_package()
+            .unwrap();
+
+        ChannelBindingExt::verify_key_package(&key_package, &cs, b"exporter")
+            .await
+            .unwrap();
+
+        let res = ChannelBindingExt::verify_key_package(&key_package, &cs, b"other").await;
+        assert_matches!(res, Err(MlsError::ChannelBindingMismatch));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unbound_key_package_is_rejected() {
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let key_package = key_package.into_key_package().unwrap();
+
+        let res = ChannelBindingExt::verify_key_package(&key_package, &cs, b"exporter").await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::RequiredExtensionNotFound(t)) if t == CHANNEL_BINDING_EXTENSION
+        );
+    }
+}
@@ -0,0 +1,391 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    error::IntoAnyError,
+    extension::{ExtensionList, ExtensionType, MlsCodecExtension},
+};
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+use crate::{
+    client::MlsError,
+    group::{
+        mls_rules::{CommitDirection, CommitOptions, CommitSource, EncryptionOptions, MlsRules},
+        proposal_filter::ProposalBundle,
+        Roster,
+    },
+};
+
+/// Extension type of [`GroupMetadataExt`], taken from the private use range.
+pub const GROUP_METADATA_EXTENSION: ExtensionType = ExtensionType::new(0xF6B1);
+
+/// Group context extension describing a group to its members.
+///
+/// Since the extension is part of the group context, all members agree on
+/// its content. It is changed with a group context extensions proposal, for
+/// instance with
+/// [`CommitBuilder::set_group_context_ext`](crate::group::CommitBuilder::set_group_context_ext).
+#[derive(Clone, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct GroupMetadataExt {
+    name: Option<String>,
+    description: Option<String>,
+    avatar_hash: Option<Vec<u8>>,
+}
+
+impl Debug for GroupMetadataExt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupMetadataExt")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field(
+                "avatar_hash",
+                &self
+                    .avatar_hash
+                    .as_deref()
+                    .map(mls_rs_core::debug::pretty_bytes),
+            )
+            .finish()
+    }
+}
+
+impl GroupMetadataExt {
+    /// Create empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the display name of the group.
+    pub fn with_name(self, name: Option<String>) -> Self {
+        Self { name, ..self }
+    }
+
+    /// Set the description of the group.
+    pub fn with_description(self, description: Option<String>) -> Self {
+        Self {
+            description,
+            ..self
+        }
+    }
+
+    /// Set the hash of the group avatar. The avatar itself is expected to be
+    /// distributed out of band.
+    pub fn with_avatar_hash(self, avatar_hash: Option<Vec<u8>>) -> Self {
+        Self {
+            avatar_hash,
+            ..self
+        }
+    }
+
+    /// Display name of the group.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Description of the group.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Hash of the group avatar.
+    pub fn avatar_hash(&self) -> Option<&[u8]> {
+        self.avatar_hash.as_deref()
+    }
+}
+
+impl MlsCodecExtension for GroupMetadataExt {
+    fn extension_type() -> ExtensionType {
+        GROUP_METADATA_EXTENSION
+    }
+}
+
+/// Change of the [`GroupMetadataExt`] of a group made by a commit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupMetadataChange {
+    /// Metadata before the commit.
+    pub prior: Option<GroupMetadataExt>,
+    /// Metadata after the commit.
+    pub new: Option<GroupMetadataExt>,
+}
+
+impl GroupMetadataChange {
+    /// Compare the metadata in group context extensions `prior` and `new`,
+    /// returning `None` if it did not change.
+    pub fn between(prior: &ExtensionList, new: &ExtensionList) -> Result<Option<Self>, MlsError> {
+        let prior = prior.get_as::<GroupMetadataExt>()?;
+        let new = new.get_as::<GroupMetadataExt>()?;
+
+        Ok((prior != new).then_some(Self { prior, new }))
+    }
+
+    /// Determine if the name of the group changed.
+    pub fn name_changed(&self) -> bool {
+        self.field_changed(GroupMetadataExt::name)
+    }
+
+    /// Determine if the description of the group changed.
+    pub fn description_changed(&self) -> bool {
+        self.field_changed(GroupMetadataExt::description)
+    }
+
+    /// Determine if the avatar of the group changed.
+    pub fn avatar_changed(&self) -> bool {
+        self.field_changed(GroupMetadataExt::avatar_hash)
+    }
+
+    fn field_changed<'a, T, F>(&'a self, field: F) -> bool
+    where
+        T: PartialEq + ?Sized + 'a,
+        F: Fn(&'a GroupMetadataExt) -> Option<&'a T>,
+    {
+        self.prior.as_ref().and_then(&field) != self.new.as_ref().and_then(&field)
+    }
+}
+
+/// Validation of new group metadata.
+///
+/// Used by [`GroupMetadataRules`] to accept or reject commits changing the
+/// [`GroupMetadataExt`] of a group. This trait is implemented for
+/// [`GroupMetadataLimits`] and for closures with the same signature as
+/// [`validate`](GroupMetadataValidator::validate).
+pub trait GroupMetadataValidator: Send + Sync {
+    /// Return `true` if the metadata can change from `prior` to `new`.
+    fn validate(&self, prior: Option<&GroupMetadataExt>, new: &GroupMetadataExt) -> bool;
+}
+
+impl<F> GroupMetadataValidator for F
+where
+    F: Fn(Option<&GroupMetadataExt>, &GroupMetadataExt) -> bool + Send + Sync,
+{
+    fn validate(&self, prior: Option<&GroupMetadataExt>, new: &GroupMetadataExt) -> bool {
+        self(prior, new)
+    }
+}
+
+/// Maximum sizes, in bytes, of the fields of a [`GroupMetadataExt`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GroupMetadataLimits {
+    pub max_name_len: usize,
+    pub max_description_len: usize,
+    pub max_avatar_hash_len: usize,
+}
+
+impl Default for GroupMetadataLimits {
+    fn default() -> Self {
+        Self {
+            max_name_len: 256,
+            max_description_len: 4096,
+            max_avatar_hash_len: 64,
+        }
+    }
+}
+
+impl GroupMetadataValidator for GroupMetadataLimits {
+    fn validate(&self, _prior: Option<&GroupMetadataExt>, new: &GroupMetadataExt) -> bool {
+        new.name().map_or(0, str::len) <= self.max_name_len
+            && new.description().map_or(0, str::len) <= self.max_description_len
+            && new.avatar_hash().map_or(0, <[u8]>::len) <= self.max_avatar_hash_len
+    }
+}
+
+/// [`MlsRules`] rejecting commits that change the [`GroupMetadataExt`] of
+/// the group to metadata refused by a [`GroupMetadataValidator`].
+///
+/// All other decisions are delegated to the wrapped rules, which are called
+/// first.
+#[derive(Clone, Debug)]
+pub struct GroupMetadataRules<R, V> {
+    inner: R,
+    validator: V,
+}
+
+impl<R, V> GroupMetadataRules<R, V> {
+    /// Validate metadata changes with `validator` on top of `inner`.
+    pub fn new(inner: R, validator: V) -> Self {
+        Self { inner, validator }
+    }
+
+    /// Access the wrapped rules.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<R, V> MlsRules for GroupMetadataRules<R, V>
+where
+    R: MlsRules,
+    V: GroupMetadataValidator,
+{
+    type Error = MlsError;
+
+    async fn filter_proposals(
+        &self,
+        direction: CommitDirection,
+        source: CommitSource,
+        current_roster: &Roster,
+        extension_list: &ExtensionList,
+        proposals: ProposalBundle,
+    ) -> Result<ProposalBundle, Self::Error> {
+        let proposals = self
+            .inner
+            .filter_proposals(direction, source, current_roster, extension_list, proposals)
+            .await
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        let Some(gce) = proposals.group_context_extensions_proposal() else {
+            return Ok(proposals);
+        };
+
+        if let Some(change) = GroupMetadataChange::between(extension_list, &gce.proposal)? {
+            if let Some(new) = &change.new {
+                self.validator
+                    .validate(change.prior.as_ref(), new)
+                    .then_some(())
+                    .ok_or(MlsError::InvalidGroupMetadata)?;
+            }
+        }
+
+        Ok(proposals)
+    }
+
+    fn commit_options(
+        &self,
+        new_roster: &Roster,
+        new_extension_list: &ExtensionList,
+        proposals: &ProposalBundle,
+    ) -> Result<CommitOptions, Self::Error> {
+        self.inner
+            .commit_options(new_roster, new_extension_list, proposals)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
+
+    fn encryption_options(
+        &self,
+        current_roster: &Roster,
+        current_extension_list: &ExtensionList,
+    ) -> Result<EncryptionOptions, Self::Error> {
+        self.inner
+            .encryption_options(current_roster, current_extension_list)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use assert_matches::assert_matches;
+    use mls_rs_core::extension::ExtensionList;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        client_builder::{ClientBuilder, MlsConfig},
+        crypto::test_utils::TestCryptoProvider,
+        group::mls_rules::DefaultMlsRules,
+        identity::{
+            basic::BasicIdentityProvider,
+            test_utils::{get_test_signing_identity, BasicWithCustomProvider},
+        },
+        Client,
+    };
+
+    use super::{
+        GroupMetadataChange, GroupMetadataExt, GroupMetadataLimits, GroupMetadataRules,
+        GROUP_METADATA_EXTENSION,
+    };
+
+    fn metadata_extensions(metadata: GroupMetadataExt) -> ExtensionList {
+        let mut extensions = ExtensionList::new();
+        extensions.set_from(metadata).unwrap();
+        extensions
+    }
+
+    #[test]
+    fn metadata_change_is_detected() {
+        let prior = GroupMetadataExt::new().with_name(Some("team".to_string()));
+
+        let new = prior
+            .clone()
+            .with_avatar_hash(Some(b"hash".to_vec()))
+            .with_description(Some("a group".to_string()));
+
+        let prior = metadata_extensions(prior);
+        let new = metadata_extensions(new);
+
+        assert_eq!(GroupMetadataChange::between(&prior, &prior).unwrap(), None);
+
+        let change = GroupMetadataChange::between(&prior, &new).unwrap().unwrap();
+
+        assert!(!change.name_changed());
+        assert!(change.description_changed());
+        assert!(change.avatar_changed());
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_client(name: &[u8]) -> Client<impl MlsConfig> {
+        let (signing_identity, signer) = get_test_signing_identity(TEST_CIPHER_SUITE, name).await;
+
+        let limits = GroupMetadataLimits {
+            max_name_len: 8,
+            ..Default::default()
+        };
+
+        ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(BasicWithCustomProvider::new(BasicIdentityProvider::new()))
+            .signing_identity(signing_identity, signer, TEST_CIPHER_SUITE)
+            .extension_type(GROUP_METADATA_EXTENSION)
+            .mls_rules(GroupMetadataRules::new(DefaultMlsRules::new(), limits))
+            .used_protocol_version(TEST_PROTOCOL_VERSION)
+            .build()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn invalid_metadata_is_rejected() {
+        let mut group = test_client(b"alice")
+            .await
+            .create_group(Default::default())
+            .await
+            .unwrap();
+
+        let valid = GroupMetadataExt::new().with_name(Some("team".to_string()));
+
+        group
+            .commit_builder()
+            .set_group_context_ext(metadata_extensions(valid))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let _description = group.apply_pending_commit().await.unwrap();
+
+        #[cfg(feature = "state_update")]
+        {
+            let change = _description.state_update.group_metadata_change().unwrap();
+            assert!(change.name_changed());
+            assert_eq!(change.prior, None);
+        }
+
+        let invalid = GroupMetadataExt::new().with_name(Some("a very long name".to_string()));
+
+        let res = group
+            .commit_builder()
+            .set_group_context_ext(metadata_extensions(invalid))
+            .unwrap()
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::MlsRulesError(_)));
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::extension::{ExtensionType, MlsCodecExtension};
+
+use crate::group::padding::PaddingMode;
+
+use super::rejoin_proof::RejoinProofPolicy;
+
+/// Extension type of [`GroupPolicyExt`], taken from the private use range.
+pub const GROUP_POLICY_EXTENSION: ExtensionType = ExtensionType::new(0xF6B2);
+
+/// Group context extension with limits and settings enforced by all members.
+///
+/// The policy is usually set at creation with
+/// [`CreateGroupOptions`](crate::group::CreateGroupOptions). Like any group
+/// context extension, it requires all members to list
+/// [`GROUP_POLICY_EXTENSION`] in their capabilities.
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct GroupPolicyExt {
+    max_members: Option<u32>,
+    padding_mode: Option<PaddingMode>,
+    max_epoch_retention: Option<u32>,
+    rejoin_proof: Option<RejoinProofPolicy>,
+}
+
+impl GroupPolicyExt {
+    /// Create a policy without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of members. Commits growing the group beyond
+    /// this number are rejected with
+    /// [`MlsError::GroupSizeLimitExceeded`](crate::error::MlsError::GroupSizeLimitExceeded).
+    pub fn with_max_members(self, max_members: Option<u32>) -> Self {
+        Self {
+            max_members,
+            ..self
+        }
+    }
+
+    /// Set the padding of encrypted messages, overriding the one returned by
+    /// [`MlsRules::encryption_options`](crate::MlsRules::encryption_options).
+    pub fn with_padding_mode(self, padding_mode: Option<PaddingMode>) -> Self {
+        Self {
+            padding_mode,
+            ..self
+        }
+    }
+
+    /// Set the number of prior epochs members keep in storage. Older epochs
+    /// are deleted by [`Group::write_to_storage`](crate::Group::write_to_storage).
+    pub fn with_max_epoch_retention(self, max_epoch_retention: Option<u32>) -> Self {
+        Self {
+            max_epoch_retention,
+            ..self
+        }
+    }
+
+    /// Set the proof required from external commits replacing the leaf of
+    /// an existing member. Commits without one are rejected with
+    /// [`MlsError::MissingRejoinProof`](crate::error::MlsError::MissingRejoinProof).
+    pub fn with_rejoin_proof(self, rejoin_proof: Option<RejoinProofPolicy>) -> Self {
+        Self {
+            rejoin_proof,
+            ..self
+        }
+    }
+
+    /// Maximum number of members.
+    pub fn max_members(&self) -> Option<u32> {
+        self.max_members
+    }
+
+    /// Padding of encrypted messages.
+    pub fn padding_mode(&self) -> Option<PaddingMode> {
+        self.padding_mode
+    }
+
+    /// Number of prior epochs kept in storage.
+    pub fn max_epoch_retention(&self) -> Option<u32> {
+        self.max_epoch_retention
+    }
+
+    /// Proof required from external commits replacing a member.
+    pub fn rejoin_proof(&self) -> Option<RejoinProofPolicy> {
+        self.rejoin_proof
+    }
+}
+
+impl MlsCodecExtension for GroupPolicyExt {
+    fn extension_type() -> ExtensionType {
+        GROUP_POLICY_EXTENSION
+    }
+}
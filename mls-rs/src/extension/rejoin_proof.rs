@@ -0,0 +1,425 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::{CipherSuiteProvider, SignaturePublicKey, SignatureSecretKey},
+    extension::{ExtensionList, ExtensionType, MlsCodecExtension},
+};
+
+use crate::{client::MlsError, signer::Signable, tree_kem::leaf_node::LeafNode};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::extension::ExternalSendersExt;
+
+/// Extension type of [`RejoinProofExt`], taken from the private use range.
+pub const REJOIN_PROOF_EXTENSION: ExtensionType = ExtensionType::new(0xF6B4);
+
+/// Proof required from an external commit replacing the leaf of an existing
+/// member, set with
+/// [`GroupPolicyExt::with_rejoin_proof`](crate::extension::group_policy::GroupPolicyExt::with_rejoin_proof).
+///
+/// Without a policy, anyone able to produce a credential accepted by the
+/// identity provider for a member can remove that member with an external
+/// commit and take its place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[repr(u8)]
+pub enum RejoinProofPolicy {
+    /// The new leaf must be signed with the signature key of the leaf it
+    /// replaces.
+    OldLeafKey = 1u8,
+    /// The new leaf must be attested by one of the external senders of the
+    /// group.
+    ExternalSender = 2u8,
+    /// Either proof is accepted.
+    OldLeafKeyOrExternalSender = 3u8,
+}
+
+impl RejoinProofPolicy {
+    fn accepts(&self, signer: &RejoinProofSigner) -> bool {
+        matches!(
+            (self, signer),
+            (Self::OldLeafKey, RejoinProofSigner::OldLeaf)
+                | (Self::ExternalSender, RejoinProofSigner::ExternalSender(_))
+                | (Self::OldLeafKeyOrExternalSender, _)
+        )
+    }
+}
+
+/// Signer of a [`RejoinProofExt`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[repr(u8)]
+pub enum RejoinProofSigner {
+    /// The member whose leaf is replaced.
+    OldLeaf = 1u8,
+    /// The external sender at this index in the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// of the group.
+    ExternalSender(u32) = 2u8,
+}
+
+/// Leaf node extension of an external commit proving that the committer may
+/// replace the leaf it removes.
+///
+/// The proof is a signature over the group identifier and the signature keys
+/// of the old and the new leaf, made either by the old leaf, with
+/// [`ExternalCommitBuilder::with_rejoin_signer`](crate::group::external_commit::ExternalCommitBuilder::with_rejoin_signer),
+/// or by an external sender of the group, which hands the proof to the
+/// client out of band, e.g. after authenticating its user again, to be used
+/// with
+/// [`ExternalCommitBuilder::with_rejoin_attestation`](crate::group::external_commit::ExternalCommitBuilder::with_rejoin_attestation).
+#[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct RejoinProofExt {
+    signer: RejoinProofSigner,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for RejoinProofExt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RejoinProofExt")
+            .field("signer", &self.signer)
+            .field(
+                "signature",
+                &mls_rs_core::debug::pretty_bytes(&self.signature),
+            )
+            .finish()
+    }
+}
+
+impl RejoinProofExt {
+    /// Sign, with `secret_key` of `signer`, the replacement in the group
+    /// `group_id` of the leaf with signature key `old_signature_key` by a
+    /// leaf with signature key `new_signature_key`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        signer: RejoinProofSigner,
+        secret_key: &SignatureSecretKey,
+        group_id: &[u8],
+        old_signature_key: &SignaturePublicKey,
+        new_signature_key: &SignaturePublicKey,
+    ) -> Result<Self, MlsError> {
+        let mut proof = Self {
+            signer,
+            signature: Vec::new(),
+        };
+
+        let context = RejoinProofTBS {
+            group_id,
+            old_signature_key,
+            new_signature_key,
+        };
+
+        proof
+            .sign(cipher_suite_provider, secret_key, &context)
+            .await?;
+
+        Ok(proof)
+    }
+
+    /// Signer of the proof.
+    pub fn signer(&self) -> RejoinProofSigner {
+        self.signer
+    }
+}
+
+impl MlsCodecExtension for RejoinProofExt {
+    fn extension_type() -> ExtensionType {
+        REJOIN_PROOF_EXTENSION
+    }
+}
+
+#[derive(MlsEncode, MlsSize)]
+pub(crate) struct RejoinProofTBS<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    old_signature_key: &'a SignaturePublicKey,
+    new_signature_key: &'a SignaturePublicKey,
+}
+
+impl<'a> Signable<'a> for RejoinProofExt {
+    const SIGN_LABEL: &'static str = "RejoinProofTBS";
+
+    type SigningContext = RejoinProofTBS<'a>;
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        context.mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
+
+/// Check that `new_leaf`, committed externally, carries a proof satisfying
+/// `policy` for the replacement of `old_leaf`.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn verify_rejoin_proof<P: CipherSuiteProvider>(
+    policy: RejoinProofPolicy,
+    group_id: &[u8],
+    group_extensions: &ExtensionList,
+    old_leaf: &LeafNode,
+    new_leaf: &LeafNode,
+    cipher_suite_provider: &P,
+) -> Result<(), MlsError> {
+    let proof = new_leaf
+        .extensions
+        .get_as::<RejoinProofExt>()?
+        .ok_or(MlsError::MissingRejoinProof)?;
+
+    if !policy.accepts(&proof.signer) {
+        return Err(MlsError::InvalidRejoinProof);
+    }
+
+    let public_key = match proof.signer {
+        RejoinProofSigner::OldLeaf => old_leaf.signing_identity.signature_key.clone(),
+        RejoinProofSigner::ExternalSender(index) => external_sender_key(group_extensions, index)?,
+    };
+
+    let context = RejoinProofTBS {
+        group_id,
+        old_signature_key: &old_leaf.signing_identity.signature_key,
+        new_signature_key: &new_leaf.signing_identity.signature_key,
+    };
+
+    proof
+        .verify(cipher_suite_provider, &public_key, &context)
+        .await
+        .map_err(|_| MlsError::InvalidRejoinProof)
+}
+
+#[cfg(feature = "by_ref_proposal")]
+fn external_sender_key(
+    group_extensions: &ExtensionList,
+    index: u32,
+) -> Result<SignaturePublicKey, MlsError> {
+    group_extensions
+        .get_as::<ExternalSendersExt>()?
+        .and_then(|ext| ext.allowed_senders.get(index as usize).cloned())
+        .map(|sender| sender.signature_key)
+        .ok_or(MlsError::InvalidRejoinProof)
+}
+
+#[cfg(not(feature = "by_ref_proposal"))]
+fn external_sender_key(_: &ExtensionList, _: u32) -> Result<SignaturePublicKey, MlsError> {
+    Err(MlsError::InvalidRejoinProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TestClientBuilder, TestClientConfig, TEST_CIPHER_SUITE},
+            MlsError,
+        },
+        crypto::SignatureSecretKey,
+        extension::group_policy::{GroupPolicyExt, GROUP_POLICY_EXTENSION},
+        group::CreateGroupOptions,
+        identity::test_utils::get_test_signing_identity,
+        Client,
+    };
+
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::{
+        crypto::test_utils::test_cipher_suite_provider, extension::built_in::ExternalSendersExt,
+    };
+
+    #[cfg(feature = "by_ref_proposal")]
+    use alloc::vec;
+
+    use super::{RejoinProofPolicy, REJOIN_PROOF_EXTENSION};
+
+    #[cfg(feature = "by_ref_proposal")]
+    use super::{RejoinProofExt, RejoinProofSigner};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_client(name: &str) -> (Client<TestClientConfig>, SignatureSecretKey) {
+        let (identity, secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, name.as_bytes()).await;
+
+        let mut client = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key.clone(), TEST_CIPHER_SUITE)
+            .build();
+
+        client
+            .config
+            .0
+            .settings
+            .extension_types
+            .extend([GROUP_POLICY_EXTENSION, REJOIN_PROOF_EXTENSION]);
+
+        (client, secret_key)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn rejoin_is_signed_by_old_leaf() {
+        let (alice, _) = test_client("alice").await;
+        let (bob, bob_secret_key) = test_client("bob").await;
+
+        let options = CreateGroupOptions::new().with_rejoin_proof(RejoinProofPolicy::OldLeafKey);
+        let mut alice_group = alice.create_group_with_options(options).await.unwrap();
+
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let group_info = alice_group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        // Bob lost his state and rejoins with a new signature key.
+        let (new_bob, _) = test_client("bob").await;
+
+        let res = new_bob
+            .external_commit_builder()
+            .unwrap()
+            .with_removal(1)
+            .build(group_info.clone())
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::MissingRejoinProof));
+
+        let (_, commit) = new_bob
+            .external_commit_builder()
+            .unwrap()
+            .with_removal(1)
+            .with_rejoin_signer(bob_secret_key)
+            .build(group_info)
+            .await
+            .unwrap();
+
+        alice_group.process_incoming_message(commit).await.unwrap();
+        assert_eq!(alice_group.roster().members_iter().count(), 2);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn proof_signed_with_other_key_is_rejected() {
+        let (alice, _) = test_client("alice").await;
+        let (bob, _) = test_client("bob").await;
+
+        let policy = GroupPolicyExt::new().with_rejoin_proof(Some(RejoinProofPolicy::OldLeafKey));
+        assert_eq!(policy.rejoin_proof(), Some(RejoinProofPolicy::OldLeafKey));
+
+        let options = CreateGroupOptions::new().with_rejoin_proof(RejoinProofPolicy::OldLeafKey);
+        let mut alice_group = alice.create_group_with_options(options).await.unwrap();
+
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let group_info = alice_group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        // Another key than the one of the old leaf signs the proof.
+        let (new_bob, new_secret_key) = test_client("bob").await;
+
+        let res = new_bob
+            .external_commit_builder()
+            .unwrap()
+            .with_removal(1)
+            .with_rejoin_signer(new_secret_key)
+            .build(group_info)
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::InvalidRejoinProof));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn rejoin_is_attested_by_external_sender() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (alice, _) = test_client("alice").await;
+        let (bob, _) = test_client("bob").await;
+
+        let (server_identity, server_secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, b"server").await;
+
+        let options = CreateGroupOptions::new()
+            .with_rejoin_proof(RejoinProofPolicy::ExternalSender)
+            .with_external_senders(ExternalSendersExt::new(vec![server_identity]));
+
+        let mut alice_group = alice.create_group_with_options(options).await.unwrap();
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let group_info = alice_group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let old_key = alice_group
+            .roster()
+            .member_with_index(1)
+            .unwrap()
+            .signing_identity
+            .signature_key;
+
+        let (new_bob, _) = test_client("bob").await;
+        let new_key = &new_bob.signing_identity().unwrap().0.signature_key;
+
+        let proof = RejoinProofExt::new(
+            &cs,
+            RejoinProofSigner::ExternalSender(0),
+            &server_secret_key,
+            alice_group.group_id(),
+            &old_key,
+            new_key,
+        )
+        .await
+        .unwrap();
+
+        let (_, commit) = new_bob
+            .external_commit_builder()
+            .unwrap()
+            .with_removal(1)
+            .with_rejoin_attestation(proof)
+            .build(group_info)
+            .await
+            .unwrap();
+
+        alice_group.process_incoming_message(commit).await.unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::SignaturePublicKey,
+    extension::{ExtensionType, MlsCodecExtension},
+};
+
+#[cfg(feature = "private_message")]
+use mls_rs_core::extension::ExtensionList;
+
+#[cfg(feature = "private_message")]
+use crate::{
+    client::MlsError,
+    group::{
+        framing::Content,
+        message_signature::AuthenticatedContent,
+        message_verifier::{signing_identity_for_member, SignaturePublicKeysContainer},
+        Sender,
+    },
+    tree_kem::node::LeafIndex,
+};
+
+/// Extension type of [`SendPermissionsExt`], taken from the private use range.
+pub const SEND_PERMISSIONS_EXTENSION: ExtensionType = ExtensionType::new(0xF6B5);
+
+/// Group context extension listing the members allowed to send application
+/// messages, e.g. the administrators of an announcement channel.
+///
+/// Members remain able to send proposals and commits. Application messages
+/// from other members are rejected by receivers with
+/// [`MlsError::SenderNotPermitted`](crate::error::MlsError::SenderNotPermitted),
+/// according to the list of the epoch the message was sent in. The list is
+/// changed with a group context extensions proposal, and requires all members
+/// to list [`SEND_PERMISSIONS_EXTENSION`] in their capabilities.
+///
+/// Members are identified by the signature key of their leaf, so that a new
+/// member taking the leaf of a removed allowed sender doesn't inherit its
+/// permission. An allowed sender changing its signature key must be listed
+/// again under its new key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct SendPermissionsExt {
+    allowed_senders: Vec<SignaturePublicKey>,
+}
+
+impl SendPermissionsExt {
+    /// Allow the members with signature keys `allowed_senders` to send
+    /// application messages.
+    pub fn new(allowed_senders: Vec<SignaturePublicKey>) -> Self {
+        Self { allowed_senders }
+    }
+
+    /// Signature keys of the members allowed to send application messages.
+    pub fn allowed_senders(&self) -> &[SignaturePublicKey] {
+        &self.allowed_senders
+    }
+
+    /// Whether the member with signature key `signature_key` may send
+    /// application messages.
+    pub fn may_send(&self, signature_key: &SignaturePublicKey) -> bool {
+        self.allowed_senders.contains(signature_key)
+    }
+}
+
+impl MlsCodecExtension for SendPermissionsExt {
+    fn extension_type() -> ExtensionType {
+        SEND_PERMISSIONS_EXTENSION
+    }
+}
+
+#[cfg(feature = "private_message")]
+/// Check that the member at `sender`, using `signature_key`, may send
+/// application messages in an epoch with group context extensions
+/// `extensions`.
+pub(crate) fn check_send_permission(
+    extensions: &ExtensionList,
+    sender: u32,
+    signature_key: &SignaturePublicKey,
+) -> Result<(), MlsError> {
+    match extensions.get_as::<SendPermissionsExt>()? {
+        Some(permissions) if !permissions.may_send(signature_key) => {
+            Err(MlsError::SenderNotPermitted(sender))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "private_message")]
+/// Check that the sender of `auth_content`, whose signature key is found in
+/// `signature_keys`, may send it, if it is an application message.
+pub(crate) fn check_application_sender(
+    extensions: &ExtensionList,
+    signature_keys: SignaturePublicKeysContainer<'_>,
+    auth_content: &AuthenticatedContent,
+) -> Result<(), MlsError> {
+    match (&auth_content.content.content, auth_content.content.sender) {
+        (Content::Application(_), Sender::Member(sender)) => {
+            let signature_key = signing_identity_for_member(signature_keys, LeafIndex(sender))?;
+            check_send_permission(extensions, sender, &signature_key)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, feature = "private_message"))]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            framing::Content,
+            test_utils::{test_group_custom, TestGroup},
+            ReceivedMessage,
+        },
+    };
+
+    use super::{SendPermissionsExt, SEND_PERMISSIONS_EXTENSION};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn announcement_channel() -> (TestGroup, TestGroup) {
+        let mut alice = test_group_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            vec![SEND_PERMISSIONS_EXTENSION],
+            None,
+            None,
+        )
+        .await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", true, |c| {
+                c.0.settings
+                    .extension_types
+                    .push(SEND_PERMISSIONS_EXTENSION)
+            })
+            .await
+            .unwrap();
+
+        let alice_key = alice
+            .group
+            .current_member_signing_identity()
+            .unwrap()
+            .signature_key
+            .clone();
+
+        let mut extensions = alice.group.context().extensions.clone();
+        extensions
+            .set_from(SendPermissionsExt::new(vec![alice_key]))
+            .unwrap();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        alice.process_pending_commit().await.unwrap();
+        bob.process_message(commit).await.unwrap();
+
+        (alice, bob)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn allowed_sender_is_received() {
+        let (mut alice, mut bob) = announcement_channel().await;
+
+        let message = alice
+            .group
+            .encrypt_application_message(b"announcement", vec![])
+            .await
+            .unwrap();
+
+        let received = bob.process_message(message).await.unwrap();
+
+        assert_matches!(
+            received,
+            ReceivedMessage::ApplicationMessage(m) if m.data() == b"announcement"
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn other_sender_is_rejected() {
+        let (mut alice, mut bob) = announcement_channel().await;
+
+        let res = bob
+            .group
+            .encrypt_application_message(b"reply", vec![])
+            .await;
+
+        assert_matches!(res, Err(MlsError::SenderNotPermitted(1)));
+
+        // A sender ignoring the list is rejected by receivers.
+        let message = bob
+            .make_ciphertext(Content::Application(b"reply".to_vec().into()))
+            .await;
+
+        let res = alice.process_message(message).await;
+
+        assert_matches!(res, Err(MlsError::SenderNotPermitted(1)));
+
+        // Commits are still allowed.
+        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
+        alice.process_message(commit).await.unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn new_member_in_leaf_of_allowed_sender_is_rejected() {
+        let (mut alice, bob) = announcement_channel().await;
+
+        let bob_key = bob
+            .group
+            .current_member_signing_identity()
+            .unwrap()
+            .signature_key
+            .clone();
+
+        let mut extensions = alice.group.context().extensions.clone();
+        extensions
+            .set_from(SendPermissionsExt::new(vec![bob_key]))
+            .unwrap();
+
+        alice
+            .group
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.process_pending_commit().await.unwrap();
+
+        let (mut carol, _) = alice
+            .join_with_custom_config("carol", true, |c| {
+                c.0.settings
+                    .extension_types
+                    .push(SEND_PERMISSIONS_EXTENSION)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(carol.group.current_member_index(), 1);
+
+        let res = carol
+            .group
+            .encrypt_application_message(b"takeover", vec![])
+            .await;
+
+        assert_matches!(res, Err(MlsError::SenderNotPermitted(1)));
+
+        let message = carol
+            .make_ciphertext(Content::Application(b"takeover".to_vec().into()))
+            .await;
+
+        let res = alice.process_message(message).await;
+
+        assert_matches!(res, Err(MlsError::SenderNotPermitted(1)));
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::{
     client::MlsError,
+    extension::channel_binding::ChannelBindingExt,
     group::{framing::MlsMessage, message_processor::validate_key_package, ExportedTree},
     KeyPackage,
 };
@@ -116,7 +117,33 @@ where
 
         let id = self.config.identity_provider();
 
-        validate_key_package(&key_package, version, &cs, &id).await?;
+        let time = self.config.current_time();
+        validate_key_package(&key_package, version, &cs, &id, time).await?;
+
+        Ok(key_package)
+    }
+
+    /// Validate `key_package` like
+    /// [`validate_key_package`](Self::validate_key_package) and check that
+    /// it is bound to the channel it was uploaded over, given the exporter
+    /// value of that channel.
+    ///
+    /// See [`ChannelBindingExt`] for a description of the binding.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn validate_channel_bound_key_package(
+        &self,
+        key_package: MlsMessage,
+        exporter_value: &[u8],
+    ) -> Result<KeyPackage, MlsError> {
+        let key_package = self.validate_key_package(key_package).await?;
+
+        let cs = self
+            .config
+            .crypto_provider()
+            .cipher_suite_provider(key_package.cipher_suite)
+            .ok_or(MlsError::UnsupportedCipherSuite(key_package.cipher_suite))?;
+
+        ChannelBindingExt::verify_key_package(&key_package, &cs, exporter_value).await?;
 
         Ok(key_package)
     }
@@ -125,9 +152,13 @@ where
 #[cfg(test)]
 pub(crate) mod tests_utils {
     use crate::{
-        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
         key_package::test_utils::test_key_package_message,
     };
+    use assert_matches::assert_matches;
 
     pub use super::builder::test_utils::*;
 
@@ -139,4 +170,32 @@ pub(crate) mod tests_utils {
 
         assert_eq!(kp.into_key_package().unwrap(), validated_kp);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_client_can_validate_channel_binding() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "john").await;
+
+        let kp = client
+            .key_package_builder()
+            .channel_binding(b"exporter".to_vec())
+            .build()
+            .await
+            .unwrap();
+
+        let server = TestExternalClientBuilder::new_for_test().build();
+
+        let validated_kp = server
+            .validate_channel_bound_key_package(kp.clone(), b"exporter")
+            .await
+            .unwrap();
+
+        assert_eq!(kp.clone().into_key_package().unwrap(), validated_kp);
+
+        let res = server
+            .validate_channel_bound_key_package(kp, b"other exporter")
+            .await;
+
+        assert_matches!(res, Err(MlsError::ChannelBindingMismatch));
+    }
 }
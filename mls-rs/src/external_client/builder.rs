@@ -16,16 +16,24 @@ use crate::{
     },
     identity::CredentialType,
     protocol_version::ProtocolVersion,
+    storage_provider::in_memory::InMemoryReplayStore,
+    time::{SharedTimeProvider, TimeProvider},
     tree_kem::Capabilities,
-    CryptoProvider, Sealed,
+    CryptoProvider, ReplayStore, Sealed,
 };
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
 
+#[cfg(target_has_atomic = "ptr")]
+use std::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
 /// Base client configuration type when instantiating `ExternalClientBuilder`
-pub type ExternalBaseConfig = Config<Missing, DefaultMlsRules, Missing>;
+pub type ExternalBaseConfig = Config<Missing, DefaultMlsRules, Missing, InMemoryReplayStore>;
 
 /// Builder for [`ExternalClient`]
 ///
@@ -112,6 +120,7 @@ impl ExternalClientBuilder<ExternalBaseConfig> {
             identity_provider: Missing,
             mls_rules: DefaultMlsRules::new(),
             crypto_provider: Missing,
+            replay_store: InMemoryReplayStore::new(),
             signing_data: None,
         }))
     }
@@ -210,6 +219,18 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
         ExternalClientBuilder(c)
     }
 
+    /// Set the source of the current time, used instead of the system clock.
+    ///
+    /// By default, the system clock is used. See [`TimeProvider`].
+    pub fn time_provider<T>(self, provider: T) -> ExternalClientBuilder<IntoConfigOutput<C>>
+    where
+        T: TimeProvider + 'static,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.time_provider = Some(SharedTimeProvider(Arc::new(provider)));
+        ExternalClientBuilder(c)
+    }
+
     /// Set the identity validator to be used by the client.
     pub fn identity_provider<I>(
         self,
@@ -224,6 +245,7 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider,
             mls_rules: c.mls_rules,
             crypto_provider: c.crypto_provider,
+            replay_store: c.replay_store,
             signing_data: c.signing_data,
         }))
     }
@@ -244,6 +266,7 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider: c.identity_provider,
             mls_rules: c.mls_rules,
             crypto_provider,
+            replay_store: c.replay_store,
             signing_data: c.signing_data,
         }))
     }
@@ -265,6 +288,28 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider: c.identity_provider,
             mls_rules,
             crypto_provider: c.crypto_provider,
+            replay_store: c.replay_store,
+            signing_data: c.signing_data,
+        }))
+    }
+
+    /// Set the replay store used to detect public messages that were already
+    /// processed.
+    ///
+    /// By default, an [`InMemoryReplayStore`] is used, which is lost when
+    /// the process restarts. A persistent store allows detection of replays
+    /// across restarts.
+    pub fn replay_store<Rs>(self, replay_store: Rs) -> ExternalClientBuilder<WithReplayStore<Rs, C>>
+    where
+        Rs: ReplayStore,
+    {
+        let Config(c) = self.0.into_config();
+        ExternalClientBuilder(Config(ConfigInner {
+            settings: c.settings,
+            identity_provider: c.identity_provider,
+            mls_rules: c.mls_rules,
+            crypto_provider: c.crypto_provider,
+            replay_store,
             signing_data: c.signing_data,
         }))
     }
@@ -286,6 +331,7 @@ where
     C::IdentityProvider: IdentityProvider + Clone,
     C::MlsRules: MlsRules + Clone,
     C::CryptoProvider: CryptoProvider + Clone,
+    C::ReplayStore: ReplayStore + Clone,
 {
     pub(crate) fn build_config(self) -> IntoConfigOutput<C> {
         let mut c = self.0.into_config();
@@ -315,37 +361,62 @@ pub struct Missing;
 /// Change the identity validator used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::identity_provider`].
-pub type WithIdentityProvider<I, C> =
-    Config<I, <C as IntoConfig>::MlsRules, <C as IntoConfig>::CryptoProvider>;
+pub type WithIdentityProvider<I, C> = Config<
+    I,
+    <C as IntoConfig>::MlsRules,
+    <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::ReplayStore,
+>;
 
 /// Change the proposal filter used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::mls_rules`].
-pub type WithMlsRules<Pr, C> =
-    Config<<C as IntoConfig>::IdentityProvider, Pr, <C as IntoConfig>::CryptoProvider>;
+pub type WithMlsRules<Pr, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    Pr,
+    <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::ReplayStore,
+>;
 
 /// Change the crypto provider used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::crypto_provider`].
-pub type WithCryptoProvider<Cp, C> =
-    Config<<C as IntoConfig>::IdentityProvider, <C as IntoConfig>::MlsRules, Cp>;
+pub type WithCryptoProvider<Cp, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    <C as IntoConfig>::MlsRules,
+    Cp,
+    <C as IntoConfig>::ReplayStore,
+>;
+
+/// Change the replay store used by a client configuration.
+///
+/// See [`ExternalClientBuilder::replay_store`].
+pub type WithReplayStore<Rs, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    <C as IntoConfig>::MlsRules,
+    <C as IntoConfig>::CryptoProvider,
+    Rs,
+>;
 
 /// Helper alias for `Config`.
 pub type IntoConfigOutput<C> = Config<
     <C as IntoConfig>::IdentityProvider,
     <C as IntoConfig>::MlsRules,
     <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::ReplayStore,
 >;
 
-impl<Ip, Pr, Cp> ExternalClientConfig for ConfigInner<Ip, Pr, Cp>
+impl<Ip, Pr, Cp, Rs> ExternalClientConfig for ConfigInner<Ip, Pr, Cp, Rs>
 where
     Ip: IdentityProvider + Clone,
     Pr: MlsRules + Clone,
     Cp: CryptoProvider + Clone,
+    Rs: ReplayStore + Clone,
 {
     type IdentityProvider = Ip;
     type MlsRules = Pr;
     type CryptoProvider = Cp;
+    type ReplayStore = Rs;
 
     fn supported_extensions(&self) -> Vec<ExtensionType> {
         self.settings.extension_types.clone()
@@ -374,6 +445,10 @@ where
         self.mls_rules.clone()
     }
 
+    fn replay_store(&self) -> Self::ReplayStore {
+        self.replay_store.clone()
+    }
+
     fn max_epoch_jitter(&self) -> Option<u64> {
         self.settings.max_epoch_jitter
     }
@@ -382,20 +457,28 @@ where
         self.settings.cache_proposals
     }
 
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        self.settings
+            .time_provider
+            .as_ref()
+            .map(|provider| provider.0.clone())
+    }
+
     fn supported_custom_proposals(&self) -> Vec<ProposalType> {
         self.settings.custom_proposal_types.clone()
     }
 }
 
-impl<Ip, Mpf, Cp> Sealed for Config<Ip, Mpf, Cp> {}
+impl<Ip, Mpf, Cp, Rs> Sealed for Config<Ip, Mpf, Cp, Rs> {}
 
-impl<Ip, Pr, Cp> MlsConfig for Config<Ip, Pr, Cp>
+impl<Ip, Pr, Cp, Rs> MlsConfig for Config<Ip, Pr, Cp, Rs>
 where
     Ip: IdentityProvider + Clone,
     Pr: MlsRules + Clone,
     Cp: CryptoProvider + Clone,
+    Rs: ReplayStore + Clone,
 {
-    type Output = ConfigInner<Ip, Pr, Cp>;
+    type Output = ConfigInner<Ip, Pr, Cp, Rs>;
 
     fn get(&self) -> &Self::Output {
         &self.0
@@ -420,6 +503,7 @@ impl<T: MlsConfig> ExternalClientConfig for T {
     type IdentityProvider = <T::Output as ExternalClientConfig>::IdentityProvider;
     type MlsRules = <T::Output as ExternalClientConfig>::MlsRules;
     type CryptoProvider = <T::Output as ExternalClientConfig>::CryptoProvider;
+    type ReplayStore = <T::Output as ExternalClientConfig>::ReplayStore;
 
     fn supported_extensions(&self) -> Vec<ExtensionType> {
         self.get().supported_extensions()
@@ -449,6 +533,10 @@ impl<T: MlsConfig> ExternalClientConfig for T {
         self.get().mls_rules()
     }
 
+    fn replay_store(&self) -> Self::ReplayStore {
+        self.get().replay_store()
+    }
+
     fn cache_proposals(&self) -> bool {
         self.get().cache_proposals()
     }
@@ -457,6 +545,10 @@ impl<T: MlsConfig> ExternalClientConfig for T {
         self.get().max_epoch_jitter()
     }
 
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        self.get().time_provider()
+    }
+
     fn capabilities(&self) -> Capabilities {
         self.get().capabilities()
     }
@@ -478,6 +570,7 @@ pub(crate) struct Settings {
     pub(crate) external_signing_keys: HashMap<Vec<u8>, SignaturePublicKey>,
     pub(crate) max_epoch_jitter: Option<u64>,
     pub(crate) cache_proposals: bool,
+    pub(crate) time_provider: Option<SharedTimeProvider>,
 }
 
 impl Debug for Settings {
@@ -500,6 +593,7 @@ impl Debug for Settings {
             )
             .field("max_epoch_jitter", &self.max_epoch_jitter)
             .field("cache_proposals", &self.cache_proposals)
+            .field("time_provider", &self.time_provider)
             .finish()
     }
 }
@@ -513,6 +607,7 @@ impl Default for Settings {
             external_signing_keys: Default::default(),
             max_epoch_jitter: None,
             custom_proposal_types: vec![],
+            time_provider: None,
         }
     }
 }
@@ -525,14 +620,15 @@ mod private {
     use super::{IntoConfigOutput, Settings};
 
     #[derive(Clone, Debug)]
-    pub struct Config<Ip, Pr, Cp>(pub(crate) ConfigInner<Ip, Pr, Cp>);
+    pub struct Config<Ip, Pr, Cp, Rs>(pub(crate) ConfigInner<Ip, Pr, Cp, Rs>);
 
     #[derive(Clone, Debug)]
-    pub struct ConfigInner<Ip, Mpf, Cp> {
+    pub struct ConfigInner<Ip, Mpf, Cp, Rs> {
         pub(crate) settings: Settings,
         pub(crate) identity_provider: Ip,
         pub(crate) mls_rules: Mpf,
         pub(crate) crypto_provider: Cp,
+        pub(crate) replay_store: Rs,
         pub(crate) signing_data: Option<(SignatureSecretKey, SigningIdentity)>,
     }
 
@@ -540,14 +636,16 @@ mod private {
         type IdentityProvider;
         type MlsRules;
         type CryptoProvider;
+        type ReplayStore;
 
         fn into_config(self) -> IntoConfigOutput<Self>;
     }
 
-    impl<Ip, Pr, Cp> IntoConfig for Config<Ip, Pr, Cp> {
+    impl<Ip, Pr, Cp, Rs> IntoConfig for Config<Ip, Pr, Cp, Rs> {
         type IdentityProvider = Ip;
         type MlsRules = Pr;
         type CryptoProvider = Cp;
+        type ReplayStore = Rs;
 
         fn into_config(self) -> Self {
             self
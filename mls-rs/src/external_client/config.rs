@@ -10,14 +10,22 @@ use crate::{
     group::{mls_rules::MlsRules, proposal::ProposalType},
     identity::CredentialType,
     protocol_version::ProtocolVersion,
+    time::{MlsTime, TimeProvider},
     tree_kem::Capabilities,
-    CryptoProvider,
+    CryptoProvider, ReplayStore,
 };
 
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
 pub trait ExternalClientConfig: Send + Sync + Clone {
     type IdentityProvider: IdentityProvider + Clone;
     type MlsRules: MlsRules + Clone;
     type CryptoProvider: CryptoProvider;
+    type ReplayStore: ReplayStore + Clone;
 
     fn supported_extensions(&self) -> Vec<ExtensionType>;
     fn supported_custom_proposals(&self) -> Vec<ProposalType>;
@@ -28,12 +36,22 @@ pub trait ExternalClientConfig: Send + Sync + Clone {
 
     fn mls_rules(&self) -> Self::MlsRules;
 
+    fn replay_store(&self) -> Self::ReplayStore;
+
     fn cache_proposals(&self) -> bool;
 
     fn max_epoch_jitter(&self) -> Option<u64> {
         None
     }
 
+    fn time_provider(&self) -> Option<Arc<dyn TimeProvider>> {
+        None
+    }
+
+    fn current_time(&self) -> Option<MlsTime> {
+        crate::time::current_time(self.time_provider())
+    }
+
     fn capabilities(&self) -> Capabilities {
         Capabilities {
             protocol_versions: self.supported_protocol_versions(),
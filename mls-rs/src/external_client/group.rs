@@ -4,7 +4,10 @@
 
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use mls_rs_core::{
-    crypto::SignatureSecretKey, error::IntoAnyError, extension::ExtensionList, group::Member,
+    crypto::SignatureSecretKey,
+    error::IntoAnyError,
+    extension::ExtensionList,
+    group::{Member, ReplayStore},
     identity::IdentityProvider,
 };
 
@@ -15,7 +18,7 @@ use crate::{
     group::{
         cipher_suite_provider,
         confirmation_tag::ConfirmationTag,
-        framing::PublicMessage,
+        framing::{MlsMessagePayload, PublicMessage},
         member_from_leaf_node,
         message_processor::{
             ApplicationMessageDescription, CommitMessageDescription, EventOrContent,
@@ -24,12 +27,14 @@ use crate::{
         snapshot::RawGroupState,
         state::GroupState,
         transcript_hash::InterimTranscriptHash,
-        validate_group_info_joiner, ContentType, ExportedTree, GroupContext, GroupInfo, Roster,
-        Welcome,
+        validate_group_info_joiner, ContentType, ExportedTree, GroupContext, GroupInfo,
+        MembershipProof, Roster, Welcome,
     },
+    hash_reference::HashReference,
     identity::SigningIdentity,
     protocol_version::ProtocolVersion,
     psk::AlwaysFoundPskStorage,
+    time::MlsTime,
     tree_kem::{node::LeafIndex, path_secret::PathSecret, TreeKemPrivate},
     CryptoProvider, KeyPackage, MlsMessage,
 };
@@ -37,11 +42,8 @@ use crate::{
 #[cfg(feature = "by_ref_proposal")]
 use crate::{
     group::{
-        framing::{Content, MlsMessagePayload},
-        message_processor::CachedProposal,
-        message_signature::AuthenticatedContent,
-        proposal::Proposal,
-        proposal_ref::ProposalRef,
+        framing::Content, message_processor::CachedProposal,
+        message_signature::AuthenticatedContent, proposal::Proposal, proposal_ref::ProposalRef,
         Sender,
     },
     WireFormat,
@@ -56,7 +58,10 @@ use mls_rs_core::{crypto::CipherSuiteProvider, psk::ExternalPskId};
 #[cfg(feature = "by_ref_proposal")]
 use crate::{
     extension::ExternalSendersExt,
-    group::proposal::{AddProposal, ReInitProposal, RemoveProposal},
+    group::{
+        proposal::{AddProposal, ReInitProposal, RemoveProposal},
+        ExternalNotice, TreeAttestation,
+    },
 };
 
 #[cfg(all(feature = "by_ref_proposal", feature = "psk"))]
@@ -133,6 +138,8 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
             tree_data,
             &config.identity_provider(),
             &cipher_suite_provider,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
         )
         .await?;
 
@@ -180,13 +187,61 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         &mut self,
         message: MlsMessage,
     ) -> Result<ExternalReceivedMessage, MlsError> {
-        MessageProcessor::process_incoming_message(
+        let replay_entry = self.replay_entry(&message).await?;
+        let mut replay_store = self.config.replay_store();
+
+        if let Some((group_id, epoch, message_id)) = &replay_entry {
+            let replayed = replay_store
+                .contains(group_id, *epoch, message_id)
+                .await
+                .map_err(|e| MlsError::ReplayStoreError(e.into_any_error()))?;
+
+            if replayed {
+                return Err(MlsError::ReplayedMessage);
+            }
+        }
+
+        let received = MessageProcessor::process_incoming_message(
             self,
             message,
             #[cfg(feature = "by_ref_proposal")]
             self.config.cache_proposals(),
         )
-        .await
+        .await?;
+
+        if let Some((group_id, epoch, message_id)) = replay_entry {
+            replay_store
+                .insert(&group_id, epoch, &message_id)
+                .await
+                .map_err(|e| MlsError::ReplayStoreError(e.into_any_error()))?;
+        }
+
+        Ok(received)
+    }
+
+    // Public messages are recorded in the replay store under the hash of
+    // their wire encoding.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn replay_entry(
+        &self,
+        message: &MlsMessage,
+    ) -> Result<Option<(Vec<u8>, u64, Vec<u8>)>, MlsError> {
+        let MlsMessagePayload::Plain(plaintext) = &message.payload else {
+            return Ok(None);
+        };
+
+        let message_id = HashReference::compute(
+            &message.to_bytes()?,
+            b"MLS 1.0 Message Reference",
+            &self.cipher_suite_provider,
+        )
+        .await?;
+
+        Ok(Some((
+            plaintext.content.group_id.clone(),
+            plaintext.content.epoch,
+            message_id.to_vec(),
+        )))
     }
 
     /// Replay a proposal message into the group skipping all validation steps.
@@ -500,6 +555,91 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         self.group_state().public_tree.roster()
     }
 
+    /// Attest that the leaves of the ratchet tree of the current epoch were
+    /// validated, so that joiners configured with
+    /// [`LeafSignatureVerification::SkipForTreesAttestedByExternalSender`](crate::group::LeafSignatureVerification::SkipForTreesAttestedByExternalSender)
+    /// can skip their validation.
+    ///
+    /// The signing identity of this external group must be one of the
+    /// external senders of the group.
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn tree_attestation(&self) -> Result<TreeAttestation, MlsError> {
+        let (signer, signing_identity) =
+            self.signing_data.as_ref().ok_or(MlsError::SignerNotFound)?;
+
+        let external_senders_ext = self
+            .state
+            .context
+            .extensions
+            .get_as::<ExternalSendersExt>()?
+            .ok_or(MlsError::ExternalProposalsDisabled)?;
+
+        let sender_index = external_senders_ext
+            .allowed_senders
+            .iter()
+            .position(|allowed_signer| signing_identity == allowed_signer)
+            .ok_or(MlsError::InvalidExternalSigningIdentity)?;
+
+        TreeAttestation::new(
+            &self.cipher_suite_provider,
+            self.group_context(),
+            sender_index as u32,
+            signer,
+        )
+        .await
+    }
+
+    /// Create an [`ExternalNotice`] with `content`, for members of the group
+    /// to verify that this external sender sent it in the current epoch.
+    ///
+    /// The signing identity of this external group must be one of the
+    /// external senders of the group.
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn external_notice(&self, content: Vec<u8>) -> Result<ExternalNotice, MlsError> {
+        let (signer, signing_identity) =
+            self.signing_data.as_ref().ok_or(MlsError::SignerNotFound)?;
+
+        let external_senders_ext = self
+            .state
+            .context
+            .extensions
+            .get_as::<ExternalSendersExt>()?
+            .ok_or(MlsError::ExternalProposalsDisabled)?;
+
+        let sender_index = external_senders_ext
+            .allowed_senders
+            .iter()
+            .position(|allowed_signer| signing_identity == allowed_signer)
+            .ok_or(MlsError::InvalidExternalSigningIdentity)?;
+
+        ExternalNotice::new(
+            &self.cipher_suite_provider,
+            self.group_context(),
+            sender_index as u32,
+            signer,
+            content,
+        )
+        .await
+    }
+
+    /// Verify a [`MembershipProof`] created by a member of the group in the
+    /// current epoch, returning the member that created it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_membership_proof(
+        &self,
+        proof: &MembershipProof,
+    ) -> Result<Member, MlsError> {
+        proof
+            .verify(
+                &self.cipher_suite_provider,
+                self.group_context(),
+                &self.group_state().public_tree,
+            )
+            .await
+    }
+
     /// Get the
     /// [transcript hash](https://messaginglayersecurity.rocks/mls-protocol/draft-ietf-mls-protocol.html#name-transcript-hashes)
     /// for the current epoch that the group is in.
@@ -637,6 +777,14 @@ where
         true
     }
 
+    fn self_index(&self) -> Option<LeafIndex> {
+        None
+    }
+
+    fn current_time(&self) -> Option<MlsTime> {
+        self.config.current_time()
+    }
+
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64> {
         self.config
@@ -819,6 +967,9 @@ mod tests {
     use assert_matches::assert_matches;
     use mls_rs_codec::{MlsDecode, MlsEncode};
 
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::group::ExternalNotice;
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn test_group_with_one_commit(v: ProtocolVersion, cs: CipherSuite) -> TestGroup {
         let mut group = test_group(v, cs).await;
@@ -1142,6 +1293,119 @@ mod tests {
         test_external_proposal(&mut server, &mut alice, external_proposal).await
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn joiner_accepts_tree_attested_by_external_sender() {
+        use crate::{
+            client::test_utils::test_client_with_key_pkg_custom, group::LeafSignatureVerification,
+        };
+
+        let (server_identity, server_key, mut alice) = setup_extern_proposal_test(true).await;
+
+        let mut server = make_external_group(&alice).await;
+        server.signing_data = Some((server_key, server_identity));
+
+        let stale_attestation = server.tree_attestation().await.unwrap();
+
+        let (carol, carol_key_package) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "carol",
+            |config| {
+                config.0.settings.leaf_signature_verification =
+                    LeafSignatureVerification::SkipForTreesAttestedByExternalSender
+            },
+        )
+        .await;
+
+        let commit_output = alice
+            .group
+            .commit_builder()
+            .add_member(carol_key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.process_pending_commit().await.unwrap();
+
+        server
+            .process_incoming_message(commit_output.commit_message)
+            .await
+            .unwrap();
+
+        let attestation = server.tree_attestation().await.unwrap();
+        let welcome = &commit_output.welcome_messages[0];
+        let tree = alice.group.export_tree();
+
+        let res = carol
+            .join_group_with_tree_attestation(Some(tree.clone()), welcome, &stale_attestation)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+
+        let (carol_group, _) = carol
+            .join_group_with_tree_attestation(Some(tree), welcome, &attestation)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            carol_group.epoch_authenticator().unwrap(),
+            alice.group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_verify_notices_of_external_sender() {
+        let (server_identity, server_key, mut alice) = setup_extern_proposal_test(true).await;
+
+        let mut server = make_external_group(&alice).await;
+        server.signing_data = Some((server_key, server_identity.clone()));
+
+        let notice = server
+            .external_notice(b"maintenance".to_vec())
+            .await
+            .unwrap();
+        let mut bytes = notice.to_bytes().unwrap();
+
+        let notice = ExternalNotice::from_bytes(&bytes).unwrap();
+        let (sender, content) = alice.group.verify_external_notice(&notice).await.unwrap();
+
+        assert_eq!(sender, server_identity);
+        assert_eq!(content, b"maintenance");
+
+        let content_start = bytes
+            .windows(content.len())
+            .position(|window| window == content)
+            .unwrap();
+
+        bytes[content_start] = b'M';
+
+        let tampered = ExternalNotice::from_bytes(&bytes).unwrap();
+        let res = alice.group.verify_external_notice(&tampered).await;
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.process_pending_commit().await.unwrap();
+
+        let res = alice.group.verify_external_notice(&notice).await;
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn non_external_sender_cannot_create_notice() {
+        let (_, _, alice) = setup_extern_proposal_test(true).await;
+        let (eve_identity, eve_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"eve").await;
+
+        let mut server = make_external_group(&alice).await;
+        server.signing_data = Some((eve_key, eve_identity));
+
+        let res = server.external_notice(b"maintenance".to_vec()).await;
+        assert_matches!(res, Err(MlsError::InvalidExternalSigningIdentity));
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn external_group_can_propose_remove() {
@@ -1156,6 +1420,78 @@ mod tests {
         test_external_proposal(&mut server, &mut alice, external_proposal).await
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_removal_is_committed_after_policy_delay() {
+        use crate::{group::external_removal::SharedExternalRemovalPolicy, time::MlsTime};
+        use core::time::Duration;
+
+        let (server_identity, server_key, mut alice) = setup_extern_proposal_test(true).await;
+
+        let policy = |removal: &crate::group::ExternalRemoval<'_>| {
+            (removal.removed.index == 1).then_some(Duration::from_secs(60))
+        };
+
+        let (mut carol, _) = alice
+            .join_with_custom_config("carol", false, |config| {
+                config.0.settings.external_removal_policy =
+                    Some(SharedExternalRemovalPolicy(alloc::sync::Arc::new(policy)))
+            })
+            .await
+            .unwrap();
+
+        let mut server = make_external_group(&alice).await;
+        server.signing_data = Some((server_key, server_identity));
+
+        let external_proposal = server.propose_remove(1, vec![]).await.unwrap();
+
+        carol
+            .group
+            .process_incoming_message_with_time(external_proposal, MlsTime::from(1000))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            carol.group.external_removal_deadline(),
+            Some(MlsTime::from(1060))
+        );
+
+        // The deadline survives a reload of the group from storage.
+        carol.group =
+            crate::group::Group::from_snapshot(carol.group.config.clone(), carol.group.snapshot())
+                .await
+                .unwrap();
+
+        assert_eq!(
+            carol.group.external_removal_deadline(),
+            Some(MlsTime::from(1060))
+        );
+
+        let res = carol
+            .group
+            .commit_external_removals_if_due(MlsTime::from(1059))
+            .await
+            .unwrap();
+
+        assert!(res.is_none());
+
+        let commit_output = carol
+            .group
+            .commit_external_removals_if_due(MlsTime::from(1060))
+            .await
+            .unwrap()
+            .unwrap();
+
+        carol.group.apply_pending_commit().await.unwrap();
+        alice
+            .process_message(commit_output.commit_message)
+            .await
+            .unwrap();
+
+        assert!(alice.group.member_at_index(1).is_none());
+        assert_eq!(carol.group.external_removal_deadline(), None);
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn external_group_external_proposal_not_allowed() {
@@ -1346,4 +1682,28 @@ mod tests {
 
         assert_matches!(update, ExternalReceivedMessage::Welcome);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn replayed_messages_are_rejected() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let config = TestExternalClientBuilder::new_for_test().build_config();
+        let mut server = make_external_group_with_config(&alice, config.clone()).await;
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+
+        server
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+
+        let res = server.process_incoming_message(commit.clone()).await;
+        assert_matches!(res, Err(MlsError::ReplayedMessage));
+
+        // A server restarted with the same replay store still detects the replay.
+        let mut restarted = make_external_group_with_config(&alice, config).await;
+
+        let res = restarted.process_incoming_message(commit).await;
+        assert_matches!(res, Err(MlsError::ReplayedMessage));
+    }
 }
@@ -0,0 +1,756 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{error::IntoAnyError, identity::SigningIdentity, secret::Secret};
+use zeroize::Zeroizing;
+
+use crate::{
+    cipher_suite::CipherSuite,
+    client::{Client, MlsError},
+    client_config::ClientConfig,
+    crypto::CipherSuiteProvider,
+    signer::Signable,
+    time::MlsTime,
+    MlsMessage,
+};
+
+use super::{
+    cipher_suite_provider, ConfirmationTag, ConfirmedTranscriptHash, Group, GroupContext,
+    GroupTranscript, InterimTranscriptHash, ReceivedMessage,
+};
+
+/// Version of the [`GroupArchive`] format written by this crate.
+pub const GROUP_ARCHIVE_VERSION: u16 = 1;
+
+const CONTENT_KEY_LABEL: &[u8] = b"mls-rs archive content key";
+
+/// Epoch of a group recorded in a [`GroupArchive`].
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ArchivedEpoch {
+    context: GroupContext,
+    confirmation_tag: ConfirmationTag,
+    commit: Option<Vec<u8>>,
+    wrapped_content_key: Option<Vec<u8>>,
+}
+
+impl Debug for ArchivedEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedEpoch")
+            .field("context", &self.context)
+            .field("confirmation_tag", &self.confirmation_tag)
+            .field("has_content_key", &self.has_content_key())
+            .finish()
+    }
+}
+
+impl ArchivedEpoch {
+    /// Group context of the epoch.
+    pub fn context(&self) -> &GroupContext {
+        &self.context
+    }
+
+    /// Encoded `ConfirmedTranscriptHashInput` of the commit that started the
+    /// epoch, which chains its confirmed transcript hash to the one of the
+    /// previous epoch. It is `None` for the first epoch of the archive.
+    ///
+    /// The content of the commit is thus archived in the clear, even if the
+    /// commit was sent as a private message.
+    pub fn commit(&self) -> Option<&[u8]> {
+        self.commit.as_deref()
+    }
+
+    /// Whether the key of the content of the application messages of the
+    /// epoch is archived.
+    pub fn has_content_key(&self) -> bool {
+        self.wrapped_content_key.is_some()
+    }
+}
+
+/// Message of a group recorded in a [`GroupArchive`].
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ArchivedMessage {
+    epoch: u64,
+    message: MlsMessage,
+    time_sent: Option<MlsTime>,
+    received_at: Option<MlsTime>,
+    sealed_content: Option<Vec<u8>>,
+}
+
+impl Debug for ArchivedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedMessage")
+            .field("epoch", &self.epoch)
+            .field("message", &self.message)
+            .field("time_sent", &self.time_sent)
+            .field("received_at", &self.received_at)
+            .field("has_content", &self.sealed_content.is_some())
+            .finish()
+    }
+}
+
+impl ArchivedMessage {
+    /// Epoch in which the message was processed.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Message as it was received, still encrypted if it was a private
+    /// message.
+    pub fn message(&self) -> &MlsMessage {
+        &self.message
+    }
+
+    /// Time given to
+    /// [`Group::process_incoming_message_with_time`](crate::Group::process_incoming_message_with_time),
+    /// if the message was processed with it.
+    pub fn time_sent(&self) -> Option<MlsTime> {
+        self.time_sent
+    }
+
+    /// Time at which the message was processed, according to the
+    /// [`TimeProvider`](crate::time::TimeProvider) of the group.
+    pub fn received_at(&self) -> Option<MlsTime> {
+        self.received_at
+    }
+}
+
+/// Signed, versioned record of the history of a group, for legal hold or
+/// to hand the data of a user over to them.
+///
+/// An archive is created with [`Client::export_archive`] from a
+/// [`GroupTranscript`], and holds the public group context of every epoch
+/// of the transcript along with the messages processed in it, as they were
+/// received. It is signed by the member that exported it, and
+/// [`Client::import_archive`] checks the signature and the consistency of
+/// the archive.
+///
+/// Archived messages stay encrypted with the keys of the group, which the
+/// archive doesn't contain. Optionally, the content of application messages
+/// is also archived, sealed with a key of each epoch derived from its
+/// exporter. These content keys are wrapped with an archive key chosen by
+/// the exporter, which is needed to read the content with
+/// [`GroupArchive::open_contents`].
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct GroupArchive {
+    version: u16,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    cipher_suite: CipherSuite,
+    signing_identity: SigningIdentity,
+    epochs: Vec<ArchivedEpoch>,
+    messages: Vec<ArchivedMessage>,
+    skipped_entries: Vec<u64>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for GroupArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupArchive")
+            .field("version", &self.version)
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("cipher_suite", &self.cipher_suite)
+            .field("signing_identity", &self.signing_identity)
+            .field("epochs", &self.epochs)
+            .field("messages", &self.messages)
+            .field("skipped_entries", &self.skipped_entries)
+            .finish()
+    }
+}
+
+impl GroupArchive {
+    /// Version of the archive format.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Identifier of the archived group.
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Cipher suite of the archived group.
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    /// Signing identity of the member that exported the archive. It should
+    /// be validated by the application, which [`Client::import_archive`]
+    /// doesn't do.
+    pub fn signing_identity(&self) -> &SigningIdentity {
+        &self.signing_identity
+    }
+
+    /// Archived epochs, in order.
+    pub fn epochs(&self) -> &[ArchivedEpoch] {
+        &self.epochs
+    }
+
+    /// Archived messages, in the order they were processed.
+    pub fn messages(&self) -> &[ArchivedMessage] {
+        &self.messages
+    }
+
+    /// Indices in the [`GroupTranscript`] the archive was exported from of
+    /// the messages that failed to be processed, and are therefore not in
+    /// [`messages`](Self::messages).
+    pub fn skipped_entries(&self) -> &[u64] {
+        &self.skipped_entries
+    }
+
+    /// Serialize the archive.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Deserialize an archive serialized with [`to_bytes`](Self::to_bytes).
+    /// The archive must then be verified with [`Client::import_archive`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Content of the archived messages, in the order of
+    /// [`messages`](Self::messages), unwrapping the content keys with
+    /// `archive_key`. The content is `None` for messages that aren't
+    /// application messages, or whose content wasn't archived.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn open_contents<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        archive_key: &[u8],
+    ) -> Result<Vec<Option<Zeroizing<Vec<u8>>>>, MlsError> {
+        let mut content_keys = Vec::with_capacity(self.epochs.len());
+
+        for epoch in &self.epochs {
+            let content_key = match &epoch.wrapped_content_key {
+                Some(wrapped) => {
+                    let aad = content_aad(&self.group_id, epoch.context.epoch, None)?;
+                    Some(open(cipher_suite_provider, archive_key, wrapped, &aad).await?)
+                }
+                None => None,
+            };
+
+            content_keys.push((epoch.context.epoch, content_key));
+        }
+
+        let mut contents = Vec::with_capacity(self.messages.len());
+
+        for (index, message) in self.messages.iter().enumerate() {
+            let content_key = content_keys
+                .iter()
+                .find_map(|(epoch, key)| (*epoch == message.epoch).then_some(key.as_ref()))
+                .flatten();
+
+            let content = match (&message.sealed_content, content_key) {
+                (Some(sealed), Some(key)) => {
+                    let aad = content_aad(&self.group_id, message.epoch, Some(index))?;
+                    Some(open(cipher_suite_provider, key, sealed, &aad).await?)
+                }
+                (Some(_), None) => return Err(MlsError::InvalidArchive),
+                (None, _) => None,
+            };
+
+            contents.push(content);
+        }
+
+        Ok(contents)
+    }
+
+    fn check_consistency(&self) -> Result<(), MlsError> {
+        let first_epoch = self.epochs.first().ok_or(MlsError::InvalidArchive)?;
+        let first_epoch = first_epoch.context.epoch;
+
+        let epochs_consistent = self.epochs.iter().enumerate().all(|(i, epoch)| {
+            epoch.context.group_id == self.group_id
+                && epoch.context.cipher_suite == self.cipher_suite
+                && epoch.context.epoch == first_epoch + i as u64
+        });
+
+        let last_epoch = first_epoch + self.epochs.len() as u64 - 1;
+
+        let messages_consistent = self
+            .messages
+            .windows(2)
+            .all(|pair| pair[0].epoch <= pair[1].epoch)
+            && self
+                .messages
+                .iter()
+                .all(|m| (first_epoch..=last_epoch).contains(&m.epoch));
+
+        (epochs_consistent && messages_consistent)
+            .then_some(())
+            .ok_or(MlsError::InvalidArchive)
+    }
+
+    /// Check that the confirmed transcript hash of each epoch follows from
+    /// the one of the previous epoch and the commit that started it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn check_transcript_hashes<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+    ) -> Result<(), MlsError> {
+        for pair in self.epochs.windows(2) {
+            let (previous, epoch) = (&pair[0], &pair[1]);
+
+            let interim_transcript_hash = InterimTranscriptHash::create(
+                cipher_suite_provider,
+                &previous.context.confirmed_transcript_hash,
+                &previous.confirmation_tag,
+            )
+            .await?;
+
+            let commit = epoch.commit.as_ref().ok_or(MlsError::InvalidArchive)?;
+
+            let confirmed_transcript_hash = ConfirmedTranscriptHash::from_hash_input(
+                cipher_suite_provider,
+                &interim_transcript_hash,
+                commit,
+            )
+            .await?;
+
+            if confirmed_transcript_hash != epoch.context.confirmed_transcript_hash {
+                return Err(MlsError::InvalidArchive);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct GroupArchiveTBS<'a> {
+    version: u16,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    cipher_suite: CipherSuite,
+    signing_identity: &'a SigningIdentity,
+    epochs: &'a [ArchivedEpoch],
+    messages: &'a [ArchivedMessage],
+    skipped_entries: &'a [u64],
+}
+
+impl<'a> Signable<'a> for GroupArchive {
+    const SIGN_LABEL: &'static str = "GroupArchiveTBS";
+
+    type SigningContext = ();
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        _context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        GroupArchiveTBS {
+            version: self.version,
+            group_id: &self.group_id,
+            cipher_suite: self.cipher_suite,
+            signing_identity: &self.signing_identity,
+            epochs: &self.epochs,
+            messages: &self.messages,
+            skipped_entries: &self.skipped_entries,
+        }
+        .mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct ContentAad<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    message_index: Option<u64>,
+}
+
+/// Additional data of the content key of `epoch`, with no message index,
+/// or of the content of the message at `index`.
+fn content_aad(group_id: &[u8], epoch: u64, index: Option<usize>) -> Result<Vec<u8>, MlsError> {
+    ContentAad {
+        group_id,
+        epoch,
+        message_index: index.map(|i| i as u64),
+    }
+    .mls_encode_to_vec()
+    .map_err(Into::into)
+}
+
+/// Seal `data` with `key`, prefixing the result with a random nonce.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn seal<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    key: &[u8],
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, MlsError> {
+    let mut sealed = cipher_suite_provider
+        .random_bytes_vec(cipher_suite_provider.aead_nonce_size())
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    let ciphertext = cipher_suite_provider
+        .aead_seal(key, data, Some(aad), &sealed)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    sealed.extend(ciphertext);
+
+    Ok(sealed)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn open<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    key: &[u8],
+    sealed: &[u8],
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    let nonce_size = cipher_suite_provider.aead_nonce_size();
+
+    if sealed.len() < nonce_size {
+        return Err(MlsError::InvalidArchive);
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(nonce_size);
+
+    cipher_suite_provider
+        .aead_open(key, ciphertext, Some(aad), nonce)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+/// Add the current epoch of `group`, started by `commit`, to `archive`,
+/// returning the content key of the epoch if `archive_key` is given.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn archive_epoch<C>(
+    archive: &mut GroupArchive,
+    group: &Group<C>,
+    commit: Option<Vec<u8>>,
+    archive_key: Option<&[u8]>,
+) -> Result<Option<Secret>, MlsError>
+where
+    C: ClientConfig + Clone,
+{
+    let epoch = group.current_epoch();
+    let cs = &group.cipher_suite_provider;
+
+    let (content_key, wrapped_content_key) = match archive_key {
+        Some(archive_key) => {
+            let content_key = group
+                .export_secret(CONTENT_KEY_LABEL, &[], cs.aead_key_size())
+                .await?;
+
+            let aad = content_aad(&archive.group_id, epoch, None)?;
+            let wrapped = seal(cs, archive_key, &content_key, &aad).await?;
+
+            (Some(content_key), Some(wrapped))
+        }
+        None => (None, None),
+    };
+
+    archive.epochs.push(ArchivedEpoch {
+        context: group.context().clone(),
+        confirmation_tag: group.state.confirmation_tag.clone(),
+        commit,
+        wrapped_content_key,
+    });
+
+    Ok(content_key)
+}
+
+impl<C> Client<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a [`GroupArchive`] of the epochs and messages of `transcript`,
+    /// signed by the member that recorded it.
+    ///
+    /// The transcript is replayed as with
+    /// [`Client::replay_transcript`]. Messages that fail to be processed are
+    /// left out of the archive, and their indices in the transcript are
+    /// listed in [`GroupArchive::skipped_entries`]. If `archive_key` is given, the
+    /// content of application messages is archived as well, and can be read
+    /// with [`GroupArchive::open_contents`] given the same key. It must be
+    /// a key of the AEAD of the cipher suite of the group.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_archive(
+        &self,
+        transcript: &GroupTranscript,
+        archive_key: Option<&[u8]>,
+    ) -> Result<GroupArchive, MlsError> {
+        let mut group =
+            Group::from_snapshot(self.config.clone(), transcript.snapshot.clone()).await?;
+
+        let cs = group.cipher_suite_provider.clone();
+
+        let mut archive = GroupArchive {
+            version: GROUP_ARCHIVE_VERSION,
+            group_id: group.group_id().to_vec(),
+            cipher_suite: group.cipher_suite(),
+            signing_identity: group.current_member_signing_identity()?.clone(),
+            epochs: Vec::new(),
+            messages: Vec::new(),
+            skipped_entries: Vec::new(),
+            signature: Vec::new(),
+        };
+
+        let mut content_key = archive_epoch(&mut archive, &group, None, archive_key).await?;
+
+        group.observed_commits = Some(Vec::new());
+
+        for (index, entry) in transcript.entries().iter().enumerate() {
+            let epoch = group.current_epoch();
+
+            let res = match entry.time_sent() {
+                Some(time) => {
+                    group
+                        .process_incoming_message_with_time(entry.message().clone(), time)
+                        .await
+                }
+                None => {
+                    group
+                        .process_incoming_message_at(entry.message().clone(), entry.received_at())
+                        .await
+                }
+            };
+
+            let sealed_content = match (&res, &content_key) {
+                (Ok(ReceivedMessage::ApplicationMessage(message)), Some(content_key)) => {
+                    let index = archive.messages.len();
+                    let aad = content_aad(&archive.group_id, epoch, Some(index))?;
+                    Some(seal(&cs, content_key, message.data(), &aad).await?)
+                }
+                _ => None,
+            };
+
+            let commits = group.observed_commits.replace(Vec::new());
+
+            let Ok(received) = res else {
+                archive.skipped_entries.push(index as u64);
+                continue;
+            };
+
+            archive.messages.push(ArchivedMessage {
+                epoch,
+                message: entry.message().clone(),
+                time_sent: entry.time_sent(),
+                received_at: entry.received_at(),
+                sealed_content,
+            });
+
+            if let ReceivedMessage::Commit(_) = received {
+                let commit = commits
+                    .as_ref()
+                    .and_then(|commits| commits.last())
+                    .map(ConfirmedTranscriptHash::hash_input)
+                    .transpose()?;
+
+                content_key = archive_epoch(&mut archive, &group, commit, archive_key).await?;
+            }
+        }
+
+        archive.sign(&cs, &group.signer, &()).await?;
+
+        Ok(archive)
+    }
+
+    /// Deserialize an archive created with [`Client::export_archive`] and
+    /// serialized with [`GroupArchive::to_bytes`], checking its version, its
+    /// signature by [`GroupArchive::signing_identity`], that its epochs and
+    /// messages are consistent and that the confirmed transcript hashes of
+    /// its epochs are chained by their commits.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn import_archive(&self, bytes: &[u8]) -> Result<GroupArchive, MlsError> {
+        let archive = GroupArchive::from_bytes(bytes)?;
+
+        if archive.version != GROUP_ARCHIVE_VERSION {
+            return Err(MlsError::UnsupportedArchiveVersion(archive.version));
+        }
+
+        let cs = cipher_suite_provider(self.config.crypto_provider(), archive.cipher_suite)?;
+
+        Signable::verify(&archive, &cs, &archive.signing_identity.signature_key, &()).await?;
+
+        archive.check_consistency()?;
+        archive.check_transcript_hashes(&cs).await?;
+
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TestClientBuilder, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+    };
+
+    #[cfg(feature = "private_message")]
+    use crate::CipherSuiteProvider;
+
+    use crate::{crypto::test_utils::test_cipher_suite_provider, signer::Signable};
+
+    use super::GroupArchive;
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn archive_round_trips_and_opens_contents() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        groups[1].group.start_recording();
+
+        for text in [&b"hello"[..], b"bye"] {
+            let message = groups[0]
+                .group
+                .encrypt_application_message(text, vec![])
+                .await
+                .unwrap();
+
+            groups[1].process_message(message).await.unwrap();
+
+            let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+            groups[0].group.apply_pending_commit().await.unwrap();
+            groups[1].process_message(commit).await.unwrap();
+        }
+
+        let transcript = groups[1].group.stop_recording().unwrap();
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let archive_key = cs.random_bytes_vec(cs.aead_key_size()).unwrap();
+
+        let client = TestClientBuilder::new_for_test().build();
+
+        let archive = client
+            .export_archive(&transcript, Some(&archive_key))
+            .await
+            .unwrap();
+
+        assert_eq!(archive.epochs().len(), 3);
+        assert_eq!(archive.messages().len(), 4);
+        assert_eq!(
+            archive.messages()[2].epoch(),
+            archive.epochs()[1].context().epoch
+        );
+
+        let archive = client
+            .import_archive(&archive.to_bytes().unwrap())
+            .await
+            .unwrap();
+
+        let contents = archive.open_contents(&cs, &archive_key).await.unwrap();
+        let contents = contents
+            .iter()
+            .map(|c| c.as_ref().map(|c| c.as_slice()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            contents,
+            [Some(&b"hello"[..]), None, Some(&b"bye"[..]), None]
+        );
+
+        let wrong_key = cs.random_bytes_vec(cs.aead_key_size()).unwrap();
+        let res = archive.open_contents(&cs, &wrong_key).await;
+        assert!(res.is_err());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tampered_archive_is_rejected() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        groups[1].group.start_recording();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+        groups[1].process_message(commit).await.unwrap();
+
+        let transcript = groups[1].group.stop_recording().unwrap();
+        let client = TestClientBuilder::new_for_test().build();
+        let archive = client.export_archive(&transcript, None).await.unwrap();
+
+        assert!(archive.epochs().iter().all(|e| !e.has_content_key()));
+
+        let mut tampered = archive.clone();
+        tampered.messages.clear();
+
+        let res = client.import_archive(&tampered.to_bytes().unwrap()).await;
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+
+        let mut future = archive;
+        future.version = 2;
+
+        let res = client.import_archive(&future.to_bytes().unwrap()).await;
+        assert_matches!(res, Err(MlsError::UnsupportedArchiveVersion(2)));
+
+        let res = GroupArchive::from_bytes(&[]);
+        assert!(res.is_err());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn failed_messages_are_listed_as_skipped() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        groups[1].group.start_recording();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+        groups[0].group.apply_pending_commit().await.unwrap();
+        groups[1].process_message(commit.clone()).await.unwrap();
+
+        let res = groups[1].process_message(commit).await;
+        assert!(res.is_err());
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+        groups[1].process_message(commit).await.unwrap();
+
+        let transcript = groups[1].group.stop_recording().unwrap();
+        let client = TestClientBuilder::new_for_test().build();
+        let archive = client.export_archive(&transcript, None).await.unwrap();
+
+        assert_eq!(archive.skipped_entries(), [1]);
+        assert_eq!(archive.messages().len(), 2);
+        assert_eq!(archive.epochs().len(), 3);
+        assert!(archive.epochs()[0].commit().is_none());
+        assert!(archive.epochs()[1..].iter().all(|e| e.commit().is_some()));
+
+        let res = client.import_archive(&archive.to_bytes().unwrap()).await;
+        assert!(res.is_ok());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unchained_transcript_hashes_are_rejected() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        groups[1].group.start_recording();
+
+        for _ in 0..2 {
+            let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+            groups[0].group.apply_pending_commit().await.unwrap();
+            groups[1].process_message(commit).await.unwrap();
+        }
+
+        let transcript = groups[1].group.stop_recording().unwrap();
+        let client = TestClientBuilder::new_for_test().build();
+        let mut archive = client.export_archive(&transcript, None).await.unwrap();
+
+        archive.epochs[1].commit = archive.epochs[2].commit.clone();
+
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        archive
+            .sign(&cs, &groups[1].group.signer, &())
+            .await
+            .unwrap();
+
+        let res = client.import_archive(&archive.to_bytes().unwrap()).await;
+        assert_matches!(res, Err(MlsError::InvalidArchive));
+    }
+}
@@ -16,10 +16,7 @@ use super::{
     secret_tree::{KeyType, MessageKeyData},
     GroupContext,
 };
-use crate::{
-    client::MlsError,
-    tree_kem::node::{LeafIndex, NodeIndex},
-};
+use crate::{client::MlsError, tree_kem::node::LeafIndex};
 use mls_rs_codec::MlsEncode;
 use mls_rs_core::{crypto::CipherSuiteProvider, error::IntoAnyError};
 use zeroize::Zeroizing;
@@ -70,7 +67,7 @@ where
         &mut self,
         key_type: KeyType,
     ) -> Result<MessageKeyData, MlsError> {
-        let self_index = NodeIndex::from(self.group_state.self_index());
+        let self_index = *self.group_state.self_index().to_node_index();
 
         self.group_state
             .epoch_secrets_mut()
@@ -86,7 +83,7 @@ where
         key_type: KeyType,
         generation: u32,
     ) -> Result<MessageKeyData, MlsError> {
-        let sender = NodeIndex::from(sender);
+        let sender = *sender.to_node_index();
 
         self.group_state
             .epoch_secrets_mut()
@@ -54,8 +54,8 @@ pub(crate) struct SenderDataKey<'a, CP: CipherSuiteProvider> {
 impl<CP: CipherSuiteProvider + Debug> Debug for SenderDataKey<'_, CP> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SenderDataKey")
-            .field("key", &mls_rs_core::debug::pretty_bytes(&self.key))
-            .field("nonce", &mls_rs_core::debug::pretty_bytes(&self.nonce))
+            .field("key", &mls_rs_core::debug::pretty_secret(&self.key))
+            .field("nonce", &mls_rs_core::debug::pretty_secret(&self.nonce))
             .field("cipher_suite_provider", self.cipher_suite_provider)
             .finish()
     }
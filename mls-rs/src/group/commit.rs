@@ -28,7 +28,7 @@ use crate::{
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
 use {crate::iter::ParallelIteratorExt, rayon::prelude::*};
 
-use crate::tree_kem::leaf_node::LeafNode;
+use crate::tree_kem::leaf_node::{ConfigProperties, LeafNode};
 
 #[cfg(not(feature = "private_message"))]
 use crate::WireFormat;
@@ -47,8 +47,8 @@ use super::{
     message_signature::AuthenticatedContent,
     mls_rules::CommitDirection,
     proposal::{Proposal, ProposalOrRef},
-    ConfirmedTranscriptHash, EncryptedGroupSecrets, ExportedTree, Group, GroupContext, GroupInfo,
-    Welcome,
+    CommitStats, ConfirmedTranscriptHash, EncryptedGroupSecrets, ExportedTree, Group, GroupContext,
+    GroupInfo, Welcome,
 };
 
 #[cfg(not(feature = "by_ref_proposal"))]
@@ -120,7 +120,8 @@ pub struct CommitOutput {
     /// set to true, then this list contains a single message sent to all members. Else, the list
     /// contains one message for each added member. Recipients of each message can be identified using
     /// [`MlsMessage::key_package_reference`] of their key packages and
-    /// [`MlsMessage::welcome_key_package_references`].
+    /// [`MlsMessage::welcome_key_package_references`]. A single message can also be split
+    /// per recipient with [`MlsMessage::split_welcome`].
     pub welcome_messages: Vec<MlsMessage>,
     /// Ratchet tree that can be sent out of band if
     /// `ratchet_tree_extension` is not used according to
@@ -133,6 +134,11 @@ pub struct CommitOutput {
     /// Proposals that were received in the prior epoch but not included in the following commit.
     #[cfg(feature = "by_ref_proposal")]
     pub unused_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    /// Identifier linking the commit message to the welcome messages and
+    /// group info created with it. See [`CommitArtifacts::commit_id`].
+    pub commit_id: Vec<u8>,
+    /// Statistics about the creation of the commit.
+    pub stats: CommitStats,
 }
 
 #[cfg_attr(all(feature = "ffi", not(test)), ::safer_ffi_gen::safer_ffi_gen)]
@@ -170,6 +176,65 @@ impl CommitOutput {
     pub fn unused_proposals(&self) -> &[crate::mls_rules::ProposalInfo<Proposal>] {
         &self.unused_proposals
     }
+
+    /// Identifier linking the commit message to the welcome messages and
+    /// group info created with it.
+    #[cfg(feature = "ffi")]
+    pub fn commit_id(&self) -> &[u8] {
+        &self.commit_id
+    }
+
+    /// Statistics about the creation of the commit.
+    pub fn stats(&self) -> &CommitStats {
+        &self.stats
+    }
+}
+
+/// Messages created by a commit, as returned by
+/// [`Group::pending_commit_artifacts`](crate::group::Group::pending_commit_artifacts).
+///
+/// Each artifact can be delivered on its own, for instance to separate
+/// endpoints of a delivery service, with [`commit_id`](Self::commit_id)
+/// relating them to each other.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CommitArtifacts {
+    /// Hash of the encoded commit message. It is available to anyone who
+    /// receives the commit message, so a delivery service can compute it
+    /// itself to check the linking of the other artifacts.
+    pub commit_id: Vec<u8>,
+    /// Commit message to send to other group members.
+    pub commit_message: MlsMessage,
+    /// Welcome messages to send to new group members, as in
+    /// [`CommitOutput::welcome_messages`].
+    pub welcome_messages: Vec<MlsMessage>,
+    /// Group info for the new epoch allowing external commits, if enabled
+    /// by [`MlsRules::commit_options`].
+    pub group_info: Option<MlsMessage>,
+    /// Ratchet tree of the new epoch, if it is not included in the welcome
+    /// messages and group info.
+    pub ratchet_tree: Option<ExportedTree<'static>>,
+}
+
+impl CommitArtifacts {
+    fn new(output: &CommitOutput) -> Self {
+        Self {
+            commit_id: output.commit_id.clone(),
+            commit_message: output.commit_message.clone(),
+            welcome_messages: output.welcome_messages.clone(),
+            group_info: output.external_commit_group_info.clone(),
+            ratchet_tree: output.ratchet_tree.clone(),
+        }
+    }
+}
+
+/// Options of a single commit overriding the ones given by the
+/// [`MlsRules`](crate::MlsRules) of the group.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct CommitOverrides {
+    path_required: Option<bool>,
+    #[cfg(feature = "private_message")]
+    encrypt: Option<bool>,
 }
 
 /// Build a commit with multiple proposals by-value.
@@ -179,6 +244,11 @@ impl CommitOutput {
 /// commit by-reference automatically so long as they pass the rules defined
 /// in the current
 /// [proposal rules](crate::client_builder::ClientBuilder::mls_rules).
+///
+/// Whether the commit includes a path update and how it is framed are given
+/// by the [`MlsRules`](crate::MlsRules) of the group, and can be chosen for
+/// this commit only with [`path_update`](Self::path_update) and
+/// `encrypted`.
 pub struct CommitBuilder<'a, C>
 where
     C: ClientConfig + Clone,
@@ -189,6 +259,7 @@ where
     group_info_extensions: ExtensionList,
     new_signer: Option<SignatureSecretKey>,
     new_signing_identity: Option<SigningIdentity>,
+    overrides: CommitOverrides,
 }
 
 impl<'a, C> CommitBuilder<'a, C>
@@ -337,6 +408,29 @@ where
         }
     }
 
+    /// Set whether the commit includes a path update, instead of the
+    /// [`path_required`](crate::mls_rules::CommitOptions::with_path_required)
+    /// option returned by
+    /// [`MlsRules::commit_options`](crate::MlsRules::commit_options).
+    ///
+    /// A path update is included regardless if one of the proposals of the
+    /// commit requires it, as described in [`Group::commit`].
+    pub fn path_update(mut self, path_update: bool) -> Self {
+        self.overrides.path_required = Some(path_update);
+        self
+    }
+
+    /// Set whether the commit is sent as a
+    /// [`PrivateMessage`](crate::group::PrivateMessage) rather than a
+    /// public message, instead of the `encrypt_control_messages` option
+    /// returned by
+    /// [`MlsRules::encryption_options`](crate::MlsRules::encryption_options).
+    #[cfg(feature = "private_message")]
+    pub fn encrypted(mut self, encrypted: bool) -> Self {
+        self.overrides.encrypt = Some(encrypted);
+        self
+    }
+
     /// Finalize the commit to send.
     ///
     /// # Errors
@@ -355,6 +449,7 @@ where
                 self.group_info_extensions,
                 self.new_signer,
                 self.new_signing_identity,
+                self.overrides,
             )
             .await
     }
@@ -413,6 +508,7 @@ where
             Default::default(),
             None,
             None,
+            Default::default(),
         )
         .await
     }
@@ -427,6 +523,7 @@ where
             group_info_extensions: Default::default(),
             new_signer: Default::default(),
             new_signing_identity: Default::default(),
+            overrides: Default::default(),
         }
     }
 
@@ -442,6 +539,7 @@ where
         mut welcome_group_info_extensions: ExtensionList,
         new_signer: Option<SignatureSecretKey>,
         new_signing_identity: Option<SigningIdentity>,
+        overrides: CommitOverrides,
     ) -> Result<CommitOutput, MlsError> {
         if self.pending_commit.is_some() {
             return Err(MlsError::ExistingPendingCommit);
@@ -451,6 +549,10 @@ where
             return Err(MlsError::GroupUsedAfterReInit);
         }
 
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
         let mls_rules = self.config.mls_rules();
 
         let is_external = external_leaf.is_some();
@@ -467,11 +569,7 @@ where
         let new_signer_ref = new_signer.as_ref().unwrap_or(&self.signer);
         let old_signer = &self.signer;
 
-        #[cfg(feature = "std")]
-        let time = Some(crate::time::MlsTime::now());
-
-        #[cfg(not(feature = "std"))]
-        let time = None;
+        let time = self.config.current_time();
 
         #[cfg(feature = "by_ref_proposal")]
         let proposals = self.state.proposals.prepare_commit(sender, proposals);
@@ -518,9 +616,21 @@ where
             )
             .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
 
-        let perform_path_update = commit_options.path_required
+        let perform_path_update = overrides
+            .path_required
+            .unwrap_or(commit_options.path_required)
             || path_update_required(&provisional_state.applied_proposals);
 
+        // The leaf of an external commit keeps the properties it was generated with, such as a
+        // rejoin proof, rather than the default ones of the configuration.
+        let leaf_properties = external_leaf.map_or_else(
+            || self.config.leaf_properties(),
+            |leaf| ConfigProperties {
+                capabilities: leaf.capabilities.clone(),
+                extensions: leaf.extensions.clone(),
+            },
+        );
+
         let (update_path, path_secrets, commit_secret) = if perform_path_update {
             // If populating the path field: Create an UpdatePath using the new tree. Any new
             // member (from an add proposal) MUST be excluded from the resolution during the
@@ -536,7 +646,7 @@ where
                 &mut provisional_group_context,
                 &provisional_state.indexes_of_added_kpkgs,
                 new_signer_ref,
-                self.config.leaf_properties(),
+                leaf_properties,
                 new_signing_identity,
                 &self.cipher_suite_provider,
                 #[cfg(test)]
@@ -582,11 +692,31 @@ where
             .map(|info| info.proposal.key_package.clone())
             .collect();
 
+        let mut stats = CommitStats::new(
+            &provisional_state.applied_proposals,
+            update_path.as_ref().map_or(0, |path| path.nodes.len()),
+        );
+
+        stats.path_secret_encryptions = update_path.as_ref().map_or(0, |path| {
+            path.nodes
+                .iter()
+                .map(|node| node.encrypted_path_secret.len())
+                .sum()
+        });
+
         let commit = Commit {
             proposals: provisional_state.applied_proposals.into_proposals_or_refs(),
             path: update_path,
         };
 
+        #[cfg(feature = "private_message")]
+        let mut encryption_options = self.encryption_options()?;
+
+        #[cfg(feature = "private_message")]
+        if let Some(encrypt) = overrides.encrypt {
+            encryption_options.encrypt_control_messages = encrypt;
+        }
+
         let mut auth_content = AuthenticatedContent::new_signed(
             &self.cipher_suite_provider,
             self.context(),
@@ -594,7 +724,7 @@ where
             Content::Commit(alloc::boxed::Box::new(commit)),
             old_signer,
             #[cfg(feature = "private_message")]
-            self.encryption_options()?.control_wire_format(sender),
+            encryption_options.control_wire_format(sender),
             #[cfg(not(feature = "private_message"))]
             WireFormat::PublicMessage,
             authenticated_data,
@@ -654,50 +784,63 @@ where
                     extensions.set_from(ratchet_tree_ext.clone())?;
                 }
 
-                let info = self
-                    .make_group_info(
-                        &provisional_group_context,
-                        extensions,
-                        &confirmation_tag,
-                        new_signer_ref,
-                    )
-                    .await?;
-
-                let msg =
-                    MlsMessage::new(self.protocol_version(), MlsMessagePayload::GroupInfo(info));
-
-                Some(msg)
+                Some(self.make_group_info(
+                    &provisional_group_context,
+                    extensions,
+                    &confirmation_tag,
+                )?)
             }
             false => None,
         };
 
         // Build the group info that will be placed into the welcome messages.
         // Add the ratchet tree extension if necessary
-        if let Some(ratchet_tree_ext) = ratchet_tree_ext {
-            welcome_group_info_extensions.set_from(ratchet_tree_ext)?;
-        }
+        let welcome_group_info = match added_key_pkgs.is_empty() {
+            true => None,
+            false => {
+                if let Some(ratchet_tree_ext) = ratchet_tree_ext {
+                    welcome_group_info_extensions.set_from(ratchet_tree_ext)?;
+                }
+
+                Some(self.make_group_info(
+                    &provisional_group_context,
+                    welcome_group_info_extensions,
+                    &confirmation_tag,
+                )?)
+            }
+        };
 
-        let welcome_group_info = self
-            .make_group_info(
-                &provisional_group_context,
-                welcome_group_info_extensions,
-                &confirmation_tag,
+        // Everything else depends on the signatures of the group infos, so
+        // they are made together, concurrently when signing is asynchronous.
+        let (external_commit_group_info, welcome_group_info) = self
+            .sign_group_infos(
+                external_commit_group_info,
+                welcome_group_info,
                 new_signer_ref,
             )
             .await?;
 
+        let external_commit_group_info = external_commit_group_info.map(|info| {
+            MlsMessage::new(self.protocol_version(), MlsMessagePayload::GroupInfo(info))
+        });
+
         // Encrypt the GroupInfo using the key and nonce derived from the joiner_secret for
         // the new epoch
-        let welcome_secret = WelcomeSecret::from_joiner_secret(
-            &self.cipher_suite_provider,
-            &key_schedule_result.joiner_secret,
-            &psk_secret,
-        )
-        .await?;
+        let encrypted_group_info = match welcome_group_info {
+            Some(welcome_group_info) => {
+                let welcome_secret = WelcomeSecret::from_joiner_secret(
+                    &self.cipher_suite_provider,
+                    &key_schedule_result.joiner_secret,
+                    &psk_secret,
+                )
+                .await?;
 
-        let encrypted_group_info = welcome_secret
-            .encrypt(&welcome_group_info.mls_encode_to_vec()?)
-            .await?;
+                welcome_secret
+                    .encrypt(&welcome_group_info.mls_encode_to_vec()?)
+                    .await?
+            }
+            None => Vec::new(),
+        };
 
         // Encrypt path secrets and joiner secret to new members
         let path_secrets = path_secrets.as_ref();
@@ -744,6 +887,8 @@ where
             secrets
         };
 
+        stats.welcome_secret_encryptions = encrypted_path_secrets.len();
+
         let welcome_messages =
             if commit_options.single_welcome_message && !encrypted_path_secrets.is_empty() {
                 vec![self.make_welcome_message(encrypted_path_secrets, encrypted_group_info)]
@@ -754,14 +899,28 @@ where
                     .collect()
             };
 
+        stats.welcome_size = welcome_messages.iter().map(|m| m.mls_encoded_len()).sum();
+
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        let serialize_start = std::time::Instant::now();
+
         let commit_message = self.format_for_wire(auth_content.clone()).await?;
 
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        {
+            stats.serialize_time = Some(serialize_start.elapsed());
+        }
+
+        let commit_message_hash =
+            CommitHash::compute(&self.cipher_suite_provider, &commit_message).await?;
+
+        let commit_id = commit_message_hash.0.clone();
+
         let pending_commit = CommitGeneration {
             content: auth_content,
             pending_private_tree: provisional_private_tree,
             pending_commit_secret: commit_secret,
-            commit_message_hash: CommitHash::compute(&self.cipher_suite_provider, &commit_message)
-                .await?,
+            commit_message_hash,
         };
 
         self.pending_commit = Some(pending_commit);
@@ -773,25 +932,29 @@ where
             self.signer = signer;
         }
 
-        Ok(CommitOutput {
+        let output = CommitOutput {
             commit_message,
             welcome_messages,
             ratchet_tree,
             external_commit_group_info,
             #[cfg(feature = "by_ref_proposal")]
             unused_proposals: provisional_state.unused_proposals,
-        })
+            commit_id,
+            stats,
+        };
+
+        self.pending_commit_artifacts = Some(CommitArtifacts::new(&output));
+
+        Ok(output)
     }
 
     // Construct a GroupInfo reflecting the new state
     // Group ID, epoch, tree, and confirmed transcript hash from the new state
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn make_group_info(
+    fn make_group_info(
         &self,
         group_context: &GroupContext,
         extensions: ExtensionList,
         confirmation_tag: &ConfirmationTag,
-        signer: &SignatureSecretKey,
     ) -> Result<GroupInfo, MlsError> {
         let mut group_info = GroupInfo {
             group_context: group_context.clone(),
@@ -803,14 +966,50 @@ where
 
         group_info.grease(self.cipher_suite_provider())?;
 
-        // Sign the GroupInfo using the member's private signing key
-        group_info
-            .sign(&self.cipher_suite_provider, signer, &())
-            .await?;
-
         Ok(group_info)
     }
 
+    // Sign the GroupInfos using the member's private signing key
+    #[cfg(not(mls_build_async))]
+    fn sign_group_infos(
+        &self,
+        mut external: Option<GroupInfo>,
+        mut welcome: Option<GroupInfo>,
+        signer: &SignatureSecretKey,
+    ) -> Result<(Option<GroupInfo>, Option<GroupInfo>), MlsError> {
+        for group_info in external.iter_mut().chain(welcome.iter_mut()) {
+            group_info.sign(&self.cipher_suite_provider, signer, &())?;
+        }
+
+        Ok((external, welcome))
+    }
+
+    // Sign the GroupInfos using the member's private signing key, without
+    // waiting for one signature before requesting the other, which matters
+    // when the signer is a remote service.
+    #[cfg(mls_build_async)]
+    async fn sign_group_infos(
+        &self,
+        external: Option<GroupInfo>,
+        welcome: Option<GroupInfo>,
+        signer: &SignatureSecretKey,
+    ) -> Result<(Option<GroupInfo>, Option<GroupInfo>), MlsError> {
+        let sign = |group_info: Option<GroupInfo>| async move {
+            match group_info {
+                Some(mut group_info) => {
+                    group_info
+                        .sign(&self.cipher_suite_provider, signer, &())
+                        .await?;
+
+                    Ok::<_, MlsError>(Some(group_info))
+                }
+                None => Ok(None),
+            }
+        };
+
+        futures::future::try_join(sign(external), sign(welcome)).await
+    }
+
     fn make_welcome_message(
         &self,
         secrets: Vec<EncryptedGroupSecrets>,
@@ -885,7 +1084,8 @@ mod tests {
         extension::test_utils::{TestExtension, TEST_EXTENSION_TYPE},
         group::{
             proposal::ProposalType,
-            test_utils::{test_group_custom_config, test_n_member_group},
+            test_utils::{test_group, test_group_custom_config, test_n_member_group},
+            ReceivedMessage,
         },
         identity::test_utils::get_test_signing_identity,
         identity::{basic::BasicIdentityProvider, test_utils::get_test_basic_credential},
@@ -1204,6 +1404,95 @@ mod tests {
         );
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_commit_builder_path_update() {
+        let mut group = test_commit_builder_group().await;
+
+        let commit_path = |output: CommitOutput| match output
+            .commit_message
+            .into_plaintext()
+            .unwrap()
+            .content
+            .content
+        {
+            Content::Commit(commit) => commit.path,
+            #[cfg(any(feature = "private_message", feature = "by_ref_proposal"))]
+            _ => panic!("Found non-commit data"),
+        };
+
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let output = group
+            .commit_builder()
+            .add_member(key_package.clone())
+            .unwrap()
+            .path_update(true)
+            .build()
+            .await
+            .unwrap();
+
+        assert!(commit_path(output).is_some());
+
+        group.clear_pending_commit();
+
+        let output = group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .path_update(false)
+            .build()
+            .await
+            .unwrap();
+
+        assert!(commit_path(output).is_none());
+
+        group.clear_pending_commit();
+
+        // Empty commits always include a path update.
+        let output = group
+            .commit_builder()
+            .path_update(false)
+            .build()
+            .await
+            .unwrap();
+
+        assert!(commit_path(output).is_some());
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_commit_builder_framing() {
+        use crate::WireFormat;
+
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        for encrypted in [true, false] {
+            let output = groups[0]
+                .group
+                .commit_builder()
+                .encrypted(encrypted)
+                .build()
+                .await
+                .unwrap();
+
+            let expected = if encrypted {
+                WireFormat::PrivateMessage
+            } else {
+                WireFormat::PublicMessage
+            };
+
+            assert_eq!(output.commit_message.wire_format(), expected);
+
+            groups[0].group.apply_pending_commit().await.unwrap();
+
+            groups[1]
+                .process_message(output.commit_message)
+                .await
+                .unwrap();
+        }
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_commit_builder_multiple_welcome_messages() {
@@ -1250,6 +1539,145 @@ mod tests {
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_stats_count_hpke_encryptions() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        // One path secret is encrypted to the second member, and the other to
+        // the resolution of the blank copath node of the root, i.e. the third
+        // member.
+        let output = groups[0].group.commit(Vec::new()).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(output.stats.path_secret_encryptions(), 2);
+        assert_eq!(output.stats.welcome_secret_encryptions(), 0);
+        assert_eq!(output.stats.hpke_encryptions(), 2);
+
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "d").await;
+
+        let output = groups[0]
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(output.stats.path_secret_encryptions(), 0);
+        assert_eq!(output.stats.welcome_secret_encryptions(), 1);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_stats_describe_created_and_processed_commits() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "d").await;
+
+        let output = groups[0]
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let stats = &output.stats;
+
+        assert_eq!(stats.proposal_count(ProposalType::ADD), 1);
+        assert_eq!(stats.proposal_count(ProposalType::REMOVE), 1);
+        assert_eq!(stats.proposal_count(ProposalType::UPDATE), 0);
+        assert_eq!(stats.total_proposals(), 2);
+        assert_eq!(stats.path_length(), 2);
+        assert_eq!(stats.hpke_decryptions(), 0);
+
+        assert_eq!(
+            stats.welcome_size(),
+            output.welcome_messages[0].to_bytes().unwrap().len()
+        );
+
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        assert!(stats.serialize_time().is_some());
+
+        let received = groups[1]
+            .process_message(output.commit_message.clone())
+            .await
+            .unwrap();
+
+        let ReceivedMessage::Commit(description) = received else {
+            panic!("expected a commit");
+        };
+
+        let stats = &description.stats;
+
+        assert_eq!(stats.proposal_counts(), output.stats.proposal_counts());
+        assert_eq!(stats.path_length(), 2);
+        assert_eq!(stats.hpke_encryptions(), 0);
+        assert_eq!(stats.hpke_decryptions(), 1);
+        assert_eq!(stats.welcome_size(), 0);
+        assert_eq!(stats.serialize_time(), None);
+
+        let description = groups[0].group.apply_pending_commit().await.unwrap();
+        assert_eq!(description.stats.hpke_decryptions(), 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn combined_welcome_can_be_split_per_recipient() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (alice, alice_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "a").await;
+
+        let (bob, bob_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "b").await;
+
+        let output = group
+            .group
+            .commit_builder()
+            .add_member(alice_kp)
+            .unwrap()
+            .add_member(bob_kp)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let combined = &output.welcome_messages[0];
+        let combined_sizes = combined.welcome_sizes().unwrap();
+
+        assert_eq!(combined_sizes.secrets.len(), 2);
+        assert_eq!(combined_sizes.total, combined.to_bytes().unwrap().len());
+
+        let welcomes = combined.split_welcome();
+
+        for (i, (client, welcome)) in [alice, bob].into_iter().zip(&welcomes).enumerate() {
+            assert_eq!(
+                welcome.welcome_key_package_references(),
+                [combined.welcome_key_package_references()[i]]
+            );
+
+            let sizes = welcome.welcome_sizes().unwrap();
+
+            assert_eq!(
+                sizes.encrypted_group_info,
+                combined_sizes.encrypted_group_info
+            );
+
+            assert_eq!(sizes.secrets, [combined_sizes.secrets[i]]);
+            assert!(sizes.total < combined_sizes.total);
+
+            client.join_group(None, welcome).await.unwrap();
+        }
+
+        assert!(output.commit_message.split_welcome().is_empty());
+        assert_eq!(output.commit_message.welcome_sizes(), None);
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn commit_can_change_credential() {
         let cs = TEST_CIPHER_SUITE;
@@ -0,0 +1,157 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+
+use crate::{client::MlsError, client_config::ClientConfig, MlsMessage};
+
+use super::{
+    message_processor::{CommitMessageDescription, MessageProcessor, ReceivedMessage},
+    CommitHash, Group, GroupContext, Member,
+};
+
+/// Outcome of a commit evaluated with [`Group::evaluate_commit`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CommitEvaluation {
+    /// Description of the commit, as returned by
+    /// [`Group::process_incoming_message`] once applied.
+    pub commit: CommitMessageDescription,
+    /// Group context of the epoch the commit creates.
+    pub context: GroupContext,
+    /// Members of the group in the epoch the commit creates.
+    pub members: Vec<Member>,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Validate the commit `message` as [`Group::process_incoming_message`]
+    /// would, and return the epoch it would create, without changing the
+    /// state of the group or its storage.
+    ///
+    /// This lets a server check a commit before accepting it, or a client
+    /// ask for the consent of its user before applying changes such as new
+    /// permissions. The commit is processed on a copy of the group, so
+    /// applying it afterwards with [`Group::process_incoming_message`]
+    /// processes it again. Messages other than commits are rejected with
+    /// [`MlsError::UnexpectedMessageType`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn evaluate_commit(&self, message: MlsMessage) -> Result<CommitEvaluation, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
+        let mut group = self.clone();
+
+        let own_commit = match &self.pending_commit {
+            Some(pending) => {
+                let message_hash =
+                    CommitHash::compute(&self.cipher_suite_provider, &message).await?;
+                message_hash == pending.commit_message_hash
+            }
+            None => false,
+        };
+
+        let commit = if own_commit {
+            group.apply_pending_commit().await?
+        } else {
+            let received = MessageProcessor::process_incoming_message(
+                &mut group,
+                message,
+                #[cfg(feature = "by_ref_proposal")]
+                true,
+            )
+            .await?;
+
+            let ReceivedMessage::Commit(commit) = received else {
+                return Err(MlsError::UnexpectedMessageType);
+            };
+
+            commit
+        };
+
+        Ok(CommitEvaluation {
+            commit,
+            context: group.context().clone(),
+            members: group.roster().members(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    #[cfg(feature = "private_message")]
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::{test_group, test_n_member_group},
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_is_evaluated_without_applying_it() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let commit = groups[0]
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let before = groups[1].group.snapshot();
+        let evaluation = groups[1]
+            .group
+            .evaluate_commit(commit.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(evaluation.context.epoch, before.state.context.epoch + 1);
+        assert_eq!(evaluation.members.len(), 2);
+        assert_eq!(evaluation.commit.committer, 0);
+        assert_eq!(groups[1].group.snapshot(), before);
+
+        groups[1]
+            .group
+            .process_incoming_message(commit)
+            .await
+            .unwrap();
+
+        assert_eq!(groups[1].group.context(), &evaluation.context);
+        assert_eq!(groups[1].group.roster().members(), evaluation.members);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn own_pending_commit_is_evaluated() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let commit = group.group.commit(vec![]).await.unwrap().commit_message;
+
+        let evaluation = group.group.evaluate_commit(commit).await.unwrap();
+
+        assert_eq!(evaluation.context.epoch, group.group.current_epoch() + 1);
+        assert!(group.group.has_pending_commit());
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_message_is_not_evaluated() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let message = groups[0]
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let res = groups[1].group.evaluate_commit(message).await;
+
+        assert_matches!(res, Err(crate::client::MlsError::UnexpectedMessageType));
+    }
+}
@@ -0,0 +1,448 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::time::Duration;
+use mls_rs_core::{crypto::CipherSuiteProvider, error::IntoAnyError};
+
+use crate::{client::MlsError, MlsMessage};
+
+use super::{
+    ClientConfig, CommitBuilder, CommitMessageDescription, CommitOutput, Group, ReceivedMessage,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use super::{framing::Sender, proposal::Proposal};
+
+/// Answer of the delivery service to a commit sent with
+/// [`CommitDelivery::send_commit`].
+#[derive(Clone, Debug)]
+pub enum CommitDeliveryResult {
+    /// The commit was accepted for the current epoch of the group.
+    Accepted,
+    /// Another commit was accepted first. The messages of the group that
+    /// were not received yet, including the competing commit, must be
+    /// returned so that the group can catch up before committing again.
+    Conflict(Vec<MlsMessage>),
+}
+
+/// Channel to the delivery service used by [`Group::commit_with_retry`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait CommitDelivery: Send + Sync {
+    type Error: IntoAnyError;
+
+    /// Send `commit` to the delivery service, returning whether it was
+    /// accepted.
+    async fn send_commit(
+        &mut self,
+        commit: &MlsMessage,
+    ) -> Result<CommitDeliveryResult, Self::Error>;
+
+    /// Wait for `delay` before the next attempt.
+    async fn backoff(&mut self, delay: Duration) -> Result<(), Self::Error>;
+}
+
+/// Policy of [`Group::commit_with_retry`] when commits conflict.
+///
+/// The delay before retry `n`, starting at 0, is `base_delay * 2^n`, capped
+/// at `max_delay`. With `jitter`, a random delay between zero and that value
+/// is used instead, so that members racing each other don't retry in
+/// lockstep.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CommitRetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+    pub preserve_proposals: bool,
+}
+
+impl Default for CommitRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            preserve_proposals: true,
+        }
+    }
+}
+
+impl CommitRetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times a conflicting commit is created again before giving
+    /// up with [`MlsError::CommitRetriesExhausted`].
+    pub fn with_max_retries(self, max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..self
+        }
+    }
+
+    /// Delays of the exponential backoff between attempts.
+    pub fn with_delays(self, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            ..self
+        }
+    }
+
+    pub fn with_jitter(self, jitter: bool) -> Self {
+        Self { jitter, ..self }
+    }
+
+    /// Include the proposals sent by this member that the competing commit
+    /// didn't commit to in the next attempt, by value, instead of losing
+    /// them with the epoch they were sent in.
+    pub fn with_preserve_proposals(self, preserve_proposals: bool) -> Self {
+        Self {
+            preserve_proposals,
+            ..self
+        }
+    }
+
+    /// Delay before retry `retry`, starting at 0, given a random value.
+    fn delay(&self, retry: u32, random: u64) -> Duration {
+        let delay = 2u32
+            .checked_pow(retry)
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+
+        if !self.jitter {
+            return delay;
+        }
+
+        let nanos = u64::try_from(delay.as_nanos()).unwrap_or(u64::MAX);
+
+        Duration::from_nanos(random % nanos.saturating_add(1))
+    }
+}
+
+/// Statistics about the races lost by a commit created with
+/// [`Group::commit_with_retry`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CommitRaceStats {
+    /// Number of commits sent, including the accepted one.
+    pub attempts: u32,
+    /// Total time spent in backoff.
+    pub backoff: Duration,
+    /// Number of own proposals included by value after being left out by a
+    /// competing commit.
+    pub preserved_proposals: usize,
+}
+
+/// Commit accepted by [`Group::commit_with_retry`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CommitRetryOutput {
+    /// Output of the accepted commit, whose welcome messages must still be
+    /// sent to new members.
+    pub output: CommitOutput,
+    /// Description of the accepted commit once applied.
+    pub description: CommitMessageDescription,
+    /// Messages processed to catch up with competing commits, in order.
+    pub received: Vec<ReceivedMessage>,
+    /// Statistics about the lost races.
+    pub stats: CommitRaceStats,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a commit with `build`, send it with `delivery`, and commit
+    /// again if another commit was accepted first, following `policy`.
+    ///
+    /// `build` is called with a new [`CommitBuilder`] for every attempt, so
+    /// the commit is recreated for the epoch the group caught up with. The
+    /// commit is applied once accepted. This gives at-least-once semantics
+    /// to the changes made by `build`, as long as they are still valid after
+    /// the competing commits: a change that fails to apply to the new epoch,
+    /// e.g. removing a member that was already removed, is returned as an
+    /// error.
+    ///
+    /// Errors of the delivery service are returned without retrying.
+    /// Messages received while catching up are returned in the output, even
+    /// though the commit itself may eventually fail.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn commit_with_retry<D, F>(
+        &mut self,
+        policy: &CommitRetryPolicy,
+        delivery: &mut D,
+        mut build: F,
+    ) -> Result<CommitRetryOutput, MlsError>
+    where
+        D: CommitDelivery,
+        F: for<'a> FnMut(CommitBuilder<'a, C>) -> Result<CommitBuilder<'a, C>, MlsError>,
+    {
+        let mut stats = CommitRaceStats::default();
+        let mut received = Vec::new();
+
+        #[cfg(feature = "by_ref_proposal")]
+        let mut preserved: Vec<Proposal> = Vec::new();
+
+        loop {
+            let builder = build(self.commit_builder())?;
+
+            #[cfg(feature = "by_ref_proposal")]
+            let builder = builder.raw_proposals(preserved.clone());
+
+            let output = builder.build().await?;
+            stats.attempts += 1;
+
+            let result = delivery
+                .send_commit(&output.commit_message)
+                .await
+                .map_err(|e| MlsError::CommitDeliveryError(e.into_any_error()));
+
+            let messages = match result {
+                Ok(CommitDeliveryResult::Accepted) => {
+                    let description = self.apply_pending_commit().await?;
+
+                    #[cfg(feature = "by_ref_proposal")]
+                    {
+                        stats.preserved_proposals = preserved.len();
+                    }
+
+                    return Ok(CommitRetryOutput {
+                        output,
+                        description,
+                        received,
+                        stats,
+                    });
+                }
+                Ok(CommitDeliveryResult::Conflict(messages)) => messages,
+                Err(e) => {
+                    self.clear_pending_commit();
+                    return Err(e);
+                }
+            };
+
+            self.clear_pending_commit();
+
+            #[cfg(feature = "by_ref_proposal")]
+            let self_index = self.current_member_index();
+
+            for message in messages {
+                let message = self.process_incoming_message(message).await?;
+
+                #[cfg(feature = "by_ref_proposal")]
+                if let ReceivedMessage::Commit(description) = &message {
+                    if policy.preserve_proposals {
+                        let own = description
+                            .state_update
+                            .unused_proposals()
+                            .iter()
+                            .filter(|p| p.sender == Sender::Member(self_index))
+                            .filter(|p| !matches!(p.proposal, Proposal::Update(_)))
+                            .map(|p| p.proposal.clone());
+
+                        preserved.extend(own);
+                    }
+                }
+
+                received.push(message);
+            }
+
+            let retry = stats.attempts - 1;
+
+            if retry >= policy.max_retries {
+                return Err(MlsError::CommitRetriesExhausted(policy.max_retries));
+            }
+
+            let delay = policy.delay(retry, self.backoff_random()?);
+
+            delivery
+                .backoff(delay)
+                .await
+                .map_err(|e| MlsError::CommitDeliveryError(e.into_any_error()))?;
+
+            stats.backoff += delay;
+        }
+    }
+
+    fn backoff_random(&self) -> Result<u64, MlsError> {
+        let mut random = [0u8; 8];
+
+        self.cipher_suite_provider
+            .random_bytes(&mut random)
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        Ok(u64::from_be_bytes(random))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(mls_build_async)]
+    use alloc::boxed::Box;
+    use alloc::{vec, vec::Vec};
+    use assert_matches::assert_matches;
+    use core::{convert::Infallible, time::Duration};
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+        MlsMessage,
+    };
+
+    use super::{CommitDelivery, CommitDeliveryResult, CommitRetryPolicy};
+
+    #[derive(Default)]
+    struct TestDelivery {
+        competing: Vec<Vec<MlsMessage>>,
+        backoffs: Vec<Duration>,
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl CommitDelivery for TestDelivery {
+        type Error = Infallible;
+
+        async fn send_commit(
+            &mut self,
+            _commit: &MlsMessage,
+        ) -> Result<CommitDeliveryResult, Self::Error> {
+            if self.competing.is_empty() {
+                Ok(CommitDeliveryResult::Accepted)
+            } else {
+                Ok(CommitDeliveryResult::Conflict(self.competing.remove(0)))
+            }
+        }
+
+        async fn backoff(&mut self, delay: Duration) -> Result<(), Self::Error> {
+            self.backoffs.push(delay);
+            Ok(())
+        }
+    }
+
+    fn test_policy() -> CommitRetryPolicy {
+        CommitRetryPolicy::new()
+            .with_delays(Duration::from_millis(10), Duration::from_millis(15))
+            .with_jitter(false)
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let policy = test_policy();
+
+        assert_eq!(policy.delay(0, 0), Duration::from_millis(10));
+        assert_eq!(policy.delay(1, 0), Duration::from_millis(15));
+        assert_eq!(policy.delay(40, 0), Duration::from_millis(15));
+
+        let policy = policy.with_jitter(true);
+
+        assert_eq!(policy.delay(0, 0), Duration::ZERO);
+        assert!(policy.delay(1, u64::MAX) <= Duration::from_millis(15));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn conflicting_commit_is_retried() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let competing = groups[1].group.commit(vec![]).await.unwrap();
+        groups[1].group.apply_pending_commit().await.unwrap();
+
+        let mut delivery = TestDelivery {
+            competing: vec![vec![competing.commit_message]],
+            ..Default::default()
+        };
+
+        let output = groups[0]
+            .group
+            .commit_with_retry(&test_policy(), &mut delivery, |builder| {
+                Ok(builder.authenticated_data(b"retried".to_vec()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(output.description.authenticated_data, b"retried");
+        assert_eq!(output.stats.attempts, 2);
+        assert_eq!(output.stats.backoff, Duration::from_millis(10));
+        assert_eq!(output.received.len(), 1);
+        assert_eq!(delivery.backoffs, vec![Duration::from_millis(10)]);
+        assert_eq!(groups[0].group.current_epoch(), 3);
+
+        groups[1]
+            .group
+            .process_incoming_message(output.output.commit_message)
+            .await
+            .unwrap();
+
+        assert_eq!(groups[1].group.current_epoch(), 3);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn retries_are_limited() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let competing = groups[1].group.commit(vec![]).await.unwrap();
+
+        let mut delivery = TestDelivery {
+            competing: vec![vec![competing.commit_message]],
+            ..Default::default()
+        };
+
+        let policy = test_policy().with_max_retries(0);
+
+        let res = groups[0]
+            .group
+            .commit_with_retry(&policy, &mut delivery, |builder| Ok(builder))
+            .await;
+
+        assert_matches!(res, Err(MlsError::CommitRetriesExhausted(0)));
+        assert!(groups[0].group.pending_commit.is_none());
+        assert!(delivery.backoffs.is_empty());
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn race_with_own_proposal(policy: CommitRetryPolicy) -> (usize, usize) {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        groups[0].group.propose_remove(2, vec![]).await.unwrap();
+
+        let competing = groups[1].group.commit(vec![]).await.unwrap();
+
+        let mut delivery = TestDelivery {
+            competing: vec![vec![competing.commit_message]],
+            ..Default::default()
+        };
+
+        let output = groups[0]
+            .group
+            .commit_with_retry(&policy, &mut delivery, |builder| Ok(builder))
+            .await
+            .unwrap();
+
+        (
+            output.stats.preserved_proposals,
+            groups[0].group.roster().members_iter().count(),
+        )
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn own_proposals_are_preserved() {
+        let counts = race_with_own_proposal(test_policy()).await;
+        assert_eq!(counts, (1, 2));
+
+        let policy = test_policy().with_preserve_proposals(false);
+        let counts = race_with_own_proposal(policy).await;
+        assert_eq!(counts, (0, 3));
+    }
+}
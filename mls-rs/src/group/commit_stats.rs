@@ -0,0 +1,120 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::group::{proposal::ProposalType, proposal_filter::ProposalBundle};
+
+/// Statistics about a commit, as returned in
+/// [`CommitOutput::stats`](crate::group::CommitOutput::stats) when creating
+/// it and in
+/// [`CommitMessageDescription::stats`](crate::group::CommitMessageDescription::stats)
+/// when processing it.
+///
+/// Applications can log them or alert on anomalous commits, e.g. commits with
+/// an unusual number of proposals or a large welcome message.
+///
+/// Each HPKE operation performs a KEM encapsulation or decapsulation. RFC 9420
+/// requires a separate single-shot encryption for every recipient, and a node
+/// of the tree receives at most one secret per commit: the resolutions of the
+/// copath nodes are disjoint, and new members are excluded from them. The
+/// number of encapsulations is therefore the number of recipients, and no
+/// HPKE context can be shared between two encryptions of the same commit.
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CommitStats {
+    pub(crate) proposal_counts: Vec<(ProposalType, usize)>,
+    pub(crate) path_length: usize,
+    pub(crate) path_secret_encryptions: usize,
+    pub(crate) welcome_secret_encryptions: usize,
+    pub(crate) path_secret_decryptions: usize,
+    pub(crate) welcome_size: usize,
+    pub(crate) serialize_time: Option<Duration>,
+}
+
+#[cfg_attr(all(feature = "ffi", not(test)), ::safer_ffi_gen::safer_ffi_gen)]
+impl CommitStats {
+    /// Number of proposals of type `proposal_type` applied by the commit.
+    pub fn proposal_count(&self, proposal_type: ProposalType) -> usize {
+        self.proposal_counts
+            .iter()
+            .find_map(|(ty, count)| (*ty == proposal_type).then_some(*count))
+            .unwrap_or_default()
+    }
+
+    /// Total number of proposals applied by the commit.
+    pub fn total_proposals(&self) -> usize {
+        self.proposal_counts.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Number of nodes in the update path of the commit, or zero if the
+    /// commit has no path.
+    pub fn path_length(&self) -> usize {
+        self.path_length
+    }
+
+    /// Number of path secrets encrypted to the resolution of the copath of
+    /// the committer.
+    pub fn path_secret_encryptions(&self) -> usize {
+        self.path_secret_encryptions
+    }
+
+    /// Number of group secrets encrypted to the init keys of new members.
+    pub fn welcome_secret_encryptions(&self) -> usize {
+        self.welcome_secret_encryptions
+    }
+
+    /// Total number of HPKE encryptions, each performing one encapsulation.
+    pub fn hpke_encryptions(&self) -> usize {
+        self.path_secret_encryptions + self.welcome_secret_encryptions
+    }
+
+    /// Number of HPKE decryptions, each performing one decapsulation. This
+    /// is one when processing a commit with a path from another member, and
+    /// zero otherwise.
+    pub fn hpke_decryptions(&self) -> usize {
+        self.path_secret_decryptions
+    }
+
+    /// Total encoded size of the welcome messages created by the commit.
+    pub fn welcome_size(&self) -> usize {
+        self.welcome_size
+    }
+}
+
+impl CommitStats {
+    pub(crate) fn new(proposals: &ProposalBundle, path_length: usize) -> Self {
+        let mut proposal_counts = BTreeMap::new();
+
+        for proposal in proposals.iter_proposals() {
+            *proposal_counts
+                .entry(proposal.proposal.proposal_type())
+                .or_default() += 1;
+        }
+
+        Self {
+            proposal_counts: proposal_counts.into_iter().collect(),
+            path_length,
+            ..Default::default()
+        }
+    }
+
+    /// Number of proposals applied by the commit for each proposal type.
+    pub fn proposal_counts(&self) -> &[(ProposalType, usize)] {
+        &self.proposal_counts
+    }
+
+    /// Time spent framing the commit message, including its encryption if it
+    /// is sent as a private message. It is only measured when creating a
+    /// commit with the `std` feature.
+    pub fn serialize_time(&self) -> Option<Duration> {
+        self.serialize_time
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+
+use crate::{
+    client::MlsError,
+    extension::{
+        group_policy::GroupPolicyExt, rejoin_proof::RejoinProofPolicy, RequiredCapabilitiesExt,
+    },
+    group::padding::PaddingMode,
+    ExtensionList,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::extension::ExternalSendersExt;
+
+/// Options of a group created with
+/// [`Client::create_group_with_options`](crate::Client::create_group_with_options).
+///
+/// All options end up in the group context extensions of the new group, so
+/// that they are agreed upon and enforced by all members. They are validated
+/// against the capabilities of the creator before the group is created.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CreateGroupOptions {
+    pub group_id: Option<Vec<u8>>,
+    pub extensions: ExtensionList,
+    pub required_capabilities: Option<RequiredCapabilitiesExt>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub external_senders: Option<ExternalSendersExt>,
+    pub max_members: Option<u32>,
+    pub padding_mode: Option<PaddingMode>,
+    pub max_epoch_retention: Option<u32>,
+    pub rejoin_proof: Option<RejoinProofPolicy>,
+}
+
+impl CreateGroupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `group_id` instead of a random identifier. See
+    /// [`Client::create_group_with_id`](crate::Client::create_group_with_id).
+    pub fn with_group_id(self, group_id: Vec<u8>) -> Self {
+        Self {
+            group_id: Some(group_id),
+            ..self
+        }
+    }
+
+    /// Initial group context extensions. Extensions set by the other options
+    /// replace those of the same type.
+    pub fn with_extensions(self, extensions: ExtensionList) -> Self {
+        Self { extensions, ..self }
+    }
+
+    pub fn with_required_capabilities(
+        self,
+        required_capabilities: RequiredCapabilitiesExt,
+    ) -> Self {
+        Self {
+            required_capabilities: Some(required_capabilities),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn with_external_senders(self, external_senders: ExternalSendersExt) -> Self {
+        Self {
+            external_senders: Some(external_senders),
+            ..self
+        }
+    }
+
+    /// See [`GroupPolicyExt::with_max_members`].
+    pub fn with_max_members(self, max_members: u32) -> Self {
+        Self {
+            max_members: Some(max_members),
+            ..self
+        }
+    }
+
+    /// See [`GroupPolicyExt::with_padding_mode`].
+    pub fn with_padding_mode(self, padding_mode: PaddingMode) -> Self {
+        Self {
+            padding_mode: Some(padding_mode),
+            ..self
+        }
+    }
+
+    /// See [`GroupPolicyExt::with_max_epoch_retention`].
+    pub fn with_max_epoch_retention(self, max_epoch_retention: u32) -> Self {
+        Self {
+            max_epoch_retention: Some(max_epoch_retention),
+            ..self
+        }
+    }
+
+    /// See [`GroupPolicyExt::with_rejoin_proof`].
+    pub fn with_rejoin_proof(self, rejoin_proof: RejoinProofPolicy) -> Self {
+        Self {
+            rejoin_proof: Some(rejoin_proof),
+            ..self
+        }
+    }
+
+    pub(crate) fn group_context_extensions(&self) -> Result<ExtensionList, MlsError> {
+        if self.max_members == Some(0) {
+            return Err(MlsError::GroupSizeLimitExceeded(0));
+        }
+
+        if self.max_epoch_retention == Some(0) {
+            return Err(MlsError::NonZeroRetentionRequired);
+        }
+
+        let mut extensions = self.extensions.clone();
+
+        if let Some(required_capabilities) = &self.required_capabilities {
+            extensions.set_from(required_capabilities.clone())?;
+        }
+
+        #[cfg(feature = "by_ref_proposal")]
+        if let Some(external_senders) = &self.external_senders {
+            extensions.set_from(external_senders.clone())?;
+        }
+
+        let policy = GroupPolicyExt::new()
+            .with_max_members(self.max_members)
+            .with_padding_mode(self.padding_mode)
+            .with_max_epoch_retention(self.max_epoch_retention)
+            .with_rejoin_proof(self.rejoin_proof);
+
+        if policy != GroupPolicyExt::default() {
+            extensions.set_from(policy)?;
+        }
+
+        Ok(extensions)
+    }
+}
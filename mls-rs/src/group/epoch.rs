@@ -5,7 +5,6 @@
 #[cfg(feature = "psk")]
 use crate::psk::PreSharedKey;
 #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-use crate::tree_kem::node::NodeIndex;
 #[cfg(feature = "prior_epoch")]
 use crate::{crypto::SignaturePublicKey, group::GroupContext, tree_kem::node::LeafIndex};
 use alloc::vec::Vec;
@@ -64,6 +63,16 @@ impl GroupStateProvider for PriorEpoch {
     }
 }
 
+/// Summary of the prior epochs deleted by
+/// [`Group::purge_epochs_before`](crate::Group::purge_epochs_before).
+#[cfg(feature = "prior_epoch")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EpochPurgeSummary {
+    /// Ids of the epochs whose secrets were destroyed, in increasing order.
+    pub epoch_ids: Vec<u64>,
+}
+
 #[derive(Debug, Clone, PartialEq, MlsEncode, MlsDecode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct EpochSecrets {
@@ -73,7 +82,9 @@ pub(crate) struct EpochSecrets {
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
     pub(crate) sender_data_secret: SenderDataSecret,
     #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-    pub(crate) secret_tree: SecretTree<NodeIndex>,
+    /// Keyed by the value of the [`NodeIndex`](crate::tree_math::NodeIndex)
+    /// of each node.
+    pub(crate) secret_tree: SecretTree<u32>,
 }
 
 #[derive(Clone, PartialEq, MlsEncode, MlsDecode, MlsSize)]
@@ -86,7 +97,7 @@ pub(crate) struct SenderDataSecret(
 
 impl Debug for SenderDataSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        mls_rs_core::debug::pretty_bytes(&self.0)
+        mls_rs_core::debug::pretty_secret(&self.0)
             .named("SenderDataSecret")
             .fmt(f)
     }
@@ -5,7 +5,12 @@
 use alloc::{borrow::Cow, vec::Vec};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
-use crate::{client::MlsError, tree_kem::node::NodeVec};
+use mls_rs_core::crypto::CipherSuiteProvider;
+
+use crate::{
+    client::MlsError,
+    tree_kem::{node::NodeVec, parent_hash::ValidationReport, TreeKemPublic},
+};
 
 #[cfg_attr(
     all(feature = "ffi", not(test)),
@@ -44,6 +49,25 @@ impl ExportedTree<'static> {
     }
 }
 
+impl ExportedTree<'_> {
+    /// Verify the parent hashes of the tree, reporting all the nodes that
+    /// violate the rules of RFC 9420, section 7.9.2.
+    ///
+    /// This is meant to diagnose a tree that failed validation. Joining a
+    /// group stops at the first violation, which is returned in
+    /// [`MlsError::ParentHashMismatch`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn parent_hash_report<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+    ) -> Result<ValidationReport, MlsError> {
+        let mut tree = TreeKemPublic::new();
+        tree.nodes = self.0.clone().into_owned();
+
+        tree.parent_hash_report(cipher_suite_provider).await
+    }
+}
+
 impl From<ExportedTree<'_>> for NodeVec {
     fn from(value: ExportedTree) -> Self {
         value.0.into_owned()
@@ -0,0 +1,209 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::extension::{Extension, ExtensionList, ExtensionType, MlsExtension};
+
+use crate::client::MlsError;
+
+/// Part of the group state an [`ExtensionChange`] applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtensionScope {
+    /// Group context extensions, changed by a group context extensions
+    /// proposal.
+    GroupContext,
+    /// Leaf node extensions of the member at this leaf index, changed by an
+    /// update of its leaf.
+    Leaf(u32),
+}
+
+/// Addition, removal or modification of an extension made by a commit,
+/// reported by [`StateUpdate::extension_changes`](crate::group::StateUpdate::extension_changes).
+///
+/// Extensions of members added or removed by the commit are not reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExtensionChange {
+    /// Extensions the change applies to.
+    pub scope: ExtensionScope,
+    /// Type of the changed extension.
+    pub extension_type: ExtensionType,
+    /// Extension before the commit, or `None` if it was added.
+    pub prior: Option<Extension>,
+    /// Extension after the commit, or `None` if it was removed.
+    pub new: Option<Extension>,
+    /// Leaf index of the member that made the change, i.e. the committer for
+    /// group context extensions and the member itself for leaf extensions.
+    pub changed_by: u32,
+}
+
+impl ExtensionChange {
+    /// Parse the extension before the commit as `E`.
+    pub fn prior_as<E: MlsExtension>(&self) -> Result<Option<E>, MlsError> {
+        Self::parse(self.prior.as_ref())
+    }
+
+    /// Parse the extension after the commit as `E`.
+    pub fn new_as<E: MlsExtension>(&self) -> Result<Option<E>, MlsError> {
+        Self::parse(self.new.as_ref())
+    }
+
+    fn parse<E: MlsExtension>(extension: Option<&Extension>) -> Result<Option<E>, MlsError> {
+        Ok(extension.map(E::from_extension).transpose()?)
+    }
+
+    /// Changes from the extensions `prior` to `new`, ordered by extension
+    /// type.
+    pub(crate) fn between(
+        scope: ExtensionScope,
+        changed_by: u32,
+        prior: &ExtensionList,
+        new: &ExtensionList,
+    ) -> Vec<Self> {
+        let mut extension_types = prior
+            .iter()
+            .chain(new.iter())
+            .map(|ext| ext.extension_type)
+            .collect::<Vec<_>>();
+
+        extension_types.sort_unstable();
+        extension_types.dedup();
+
+        extension_types
+            .into_iter()
+            .filter_map(|extension_type| {
+                let prior = prior.get(extension_type);
+                let new = new.get(extension_type);
+
+                (prior != new).then_some(Self {
+                    scope,
+                    extension_type,
+                    prior,
+                    new,
+                    changed_by,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use mls_rs_core::extension::{Extension, ExtensionList, ExtensionType};
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        extension::test_utils::{TestExtension, TEST_EXTENSION_TYPE},
+        group::{test_utils::test_n_member_group, ReceivedMessage},
+    };
+
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::{
+        extension::{ExternalSendersExt, RequiredCapabilitiesExt},
+        identity::test_utils::get_test_signing_identity,
+    };
+
+    use super::{ExtensionChange, ExtensionScope};
+
+    #[test]
+    fn changes_are_listed_by_type() {
+        let ext = |t: u16, data: u8| Extension::new(ExtensionType::new(t), vec![data]);
+
+        let prior = ExtensionList::from(vec![ext(3, 0), ext(1, 0), ext(2, 0)]);
+        let new = ExtensionList::from(vec![ext(4, 0), ext(2, 1), ext(1, 0)]);
+
+        let changes = ExtensionChange::between(ExtensionScope::Leaf(1), 1, &prior, &new);
+
+        let summary = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.extension_type.raw_value(),
+                    c.prior.is_some(),
+                    c.new.is_some(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![(2, true, true), (3, true, false), (4, false, true)]
+        );
+        assert!(ExtensionChange::between(ExtensionScope::GroupContext, 0, &new, &new).is_empty());
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_context_change_is_reported() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let (identity, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"server").await;
+        let mut extensions = groups[0].group.context().extensions.clone();
+
+        extensions
+            .set_from(ExternalSendersExt::new(vec![identity.clone()]))
+            .unwrap();
+
+        let commit = groups[0]
+            .group
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let ReceivedMessage::Commit(description) = groups[1].process_message(commit).await.unwrap()
+        else {
+            panic!("expected a commit");
+        };
+
+        let changes = description.state_update.extension_changes();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].scope, ExtensionScope::GroupContext);
+        assert_eq!(changes[0].changed_by, 0);
+        assert_eq!(changes[0].prior_as::<ExternalSendersExt>().unwrap(), None);
+
+        assert_eq!(
+            changes[0].new_as::<ExternalSendersExt>().unwrap(),
+            Some(ExternalSendersExt::new(vec![identity]))
+        );
+
+        assert!(changes[0].new_as::<RequiredCapabilitiesExt>().is_err());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn leaf_change_is_reported() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let mut leaf_extensions = ExtensionList::new();
+        leaf_extensions.set_from(TestExtension::from(1)).unwrap();
+
+        let settings = &mut groups[1].group.config.0.settings;
+        settings.extension_types.push(TEST_EXTENSION_TYPE.into());
+        settings.leaf_node_extensions = leaf_extensions;
+
+        let commit = groups[1].group.commit(vec![]).await.unwrap().commit_message;
+
+        let ReceivedMessage::Commit(description) = groups[0].process_message(commit).await.unwrap()
+        else {
+            panic!("expected a commit");
+        };
+
+        let changes = description.state_update.extension_changes();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].scope, ExtensionScope::Leaf(1));
+        assert_eq!(changes[0].changed_by, 1);
+
+        assert_eq!(
+            changes[0].new_as::<TestExtension>().unwrap(),
+            Some(TestExtension::from(1))
+        );
+    }
+}
@@ -6,6 +6,7 @@ use mls_rs_core::{crypto::SignatureSecretKey, identity::SigningIdentity};
 
 use crate::{
     client_config::ClientConfig,
+    extension::rejoin_proof::{RejoinProofExt, RejoinProofSigner, REJOIN_PROOF_EXTENSION},
     group::{
         cipher_suite_provider,
         epoch::SenderDataSecret,
@@ -41,6 +42,11 @@ use crate::group::{
 
 use super::{validate_group_info_joiner, ExportedTree};
 
+enum RejoinProof {
+    OldLeafKey(SignatureSecretKey),
+    Attestation(RejoinProofExt),
+}
+
 /// A builder that aids with the construction of an external commit.
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::ffi_type(opaque))]
 pub struct ExternalCommitBuilder<C: ClientConfig> {
@@ -49,6 +55,7 @@ pub struct ExternalCommitBuilder<C: ClientConfig> {
     config: C,
     tree_data: Option<ExportedTree<'static>>,
     to_remove: Option<u32>,
+    rejoin_proof: Option<RejoinProof>,
     #[cfg(feature = "psk")]
     external_psks: Vec<ExternalPskId>,
     authenticated_data: Vec<u8>,
@@ -67,6 +74,7 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         Self {
             tree_data: None,
             to_remove: None,
+            rejoin_proof: None,
             authenticated_data: Vec::new(),
             signer,
             signing_identity,
@@ -100,6 +108,29 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         }
     }
 
+    #[must_use]
+    /// Prove, with the signature key of the client version removed with
+    /// [`Self::with_removal`], that the new version may replace it, as
+    /// required by a [`RejoinProofPolicy`](crate::extension::rejoin_proof::RejoinProofPolicy)
+    /// of the group. The key is ignored if no member is removed.
+    pub fn with_rejoin_signer(self, old_signer: SignatureSecretKey) -> Self {
+        Self {
+            rejoin_proof: Some(RejoinProof::OldLeafKey(old_signer)),
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// Prove, with an attestation of an external sender of the group, that
+    /// the new version of the client may replace the one removed with
+    /// [`Self::with_removal`]. The proof is ignored if no member is removed.
+    pub fn with_rejoin_attestation(self, proof: RejoinProofExt) -> Self {
+        Self {
+            rejoin_proof: Some(RejoinProof::Attestation(proof)),
+            ..self
+        }
+    }
+
     #[must_use]
     /// Add plaintext authenticated data to the resulting commit message.
     pub fn with_authenticated_data(self, data: Vec<u8>) -> Self {
@@ -169,12 +200,43 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
             self.tree_data,
             &self.config.identity_provider(),
             &cipher_suite,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
         )
         .await?;
 
+        let mut leaf_properties = self.config.leaf_properties();
+
+        if let (Some(to_remove), Some(rejoin_proof)) = (self.to_remove, self.rejoin_proof) {
+            let proof = match rejoin_proof {
+                RejoinProof::OldLeafKey(old_signer) => {
+                    let old_leaf = public_tree.get_leaf_node(LeafIndex(to_remove))?;
+
+                    RejoinProofExt::new(
+                        &cipher_suite,
+                        RejoinProofSigner::OldLeaf,
+                        &old_signer,
+                        &group_info.group_context.group_id,
+                        &old_leaf.signing_identity.signature_key,
+                        &self.signing_identity.signature_key,
+                    )
+                    .await?
+                }
+                RejoinProof::Attestation(proof) => proof,
+            };
+
+            leaf_properties.extensions.set_from(proof)?;
+
+            let extensions = &mut leaf_properties.capabilities.extensions;
+
+            if !extensions.contains(&REJOIN_PROOF_EXTENSION) {
+                extensions.push(REJOIN_PROOF_EXTENSION);
+            }
+        }
+
         let (leaf_node, _) = LeafNode::generate(
             &cipher_suite,
-            self.config.leaf_properties(),
+            leaf_properties,
             self.signing_identity,
             &self.signer,
             self.config.lifetime(),
@@ -256,6 +318,7 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
                 Default::default(),
                 None,
                 None,
+                Default::default(),
             )
             .await?;
 
@@ -0,0 +1,184 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{
+    client::MlsError, crypto::CipherSuiteProvider, extension::ExternalSendersExt,
+    identity::SigningIdentity, signer::Signable,
+};
+
+#[cfg(all(feature = "external_client", feature = "by_ref_proposal"))]
+use crate::crypto::SignatureSecretKey;
+
+use super::GroupContext;
+
+/// Signed but unencrypted notice sent into a group by one of its
+/// [external senders](crate::extension::built_in::ExternalSendersExt).
+///
+/// MLS only lets members send application messages, but a delivery service
+/// or another system component may need to send notifications to the group,
+/// e.g. about maintenance, that members can attribute to it. A notice is
+/// created with
+/// [`ExternalGroup::external_notice`](crate::external_client::ExternalGroup::external_notice),
+/// distributed along with the messages of the group, and verified by members
+/// with [`Group::verify_external_notice`](crate::Group::verify_external_notice)
+/// against the external senders of the group.
+///
+/// The content of a notice is readable by anyone who gets it. Notices are
+/// bound to the group and epoch they were created in, but are not
+/// protected against replays within that epoch.
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ExternalNotice {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    sender_index: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    content: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for ExternalNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalNotice")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("sender_index", &self.sender_index)
+            .field("content", &mls_rs_core::debug::pretty_bytes(&self.content))
+            .field(
+                "signature",
+                &mls_rs_core::debug::pretty_bytes(&self.signature),
+            )
+            .finish()
+    }
+}
+
+impl ExternalNotice {
+    /// Identifier of the group the notice was created for.
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Epoch the notice was created in.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Index of the sender in the external senders of the group.
+    pub fn sender_index(&self) -> u32 {
+        self.sender_index
+    }
+
+    /// Unverified content of the notice.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Serialize the notice.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Deserialize a notice serialized with [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    #[cfg(all(feature = "external_client", feature = "by_ref_proposal"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        sender_index: u32,
+        signer: &SignatureSecretKey,
+        content: Vec<u8>,
+    ) -> Result<Self, MlsError> {
+        let mut notice = Self {
+            group_id: group_context.group_id.clone(),
+            epoch: group_context.epoch,
+            sender_index,
+            content,
+            signature: Vec::new(),
+        };
+
+        notice.sign(cipher_suite_provider, signer, &()).await?;
+
+        Ok(notice)
+    }
+
+    /// Verify the notice against the external senders of `group_context`,
+    /// returning the sender and the content.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+    ) -> Result<(SigningIdentity, Vec<u8>), MlsError> {
+        if self.group_id != group_context.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        if self.epoch != group_context.epoch {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        let external_senders = group_context
+            .extensions
+            .get_as::<ExternalSendersExt>()?
+            .ok_or(MlsError::UnknownSigningIdentityForExternalSender)?;
+
+        let sender = external_senders
+            .allowed_senders
+            .get(self.sender_index as usize)
+            .ok_or(MlsError::UnknownSigningIdentityForExternalSender)?;
+
+        Signable::verify(self, cipher_suite_provider, &sender.signature_key, &()).await?;
+
+        Ok((sender.clone(), self.content.clone()))
+    }
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct ExternalNoticeTBS<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    sender_index: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    content: &'a [u8],
+}
+
+impl<'a> Signable<'a> for ExternalNotice {
+    const SIGN_LABEL: &'static str = "ExternalNoticeTBS";
+
+    type SigningContext = ();
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        _context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        ExternalNoticeTBS {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            sender_index: self.sender_index,
+            content: &self.content,
+        }
+        .mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::{
+    fmt::{self, Debug},
+    time::Duration,
+};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use mls_rs_core::group::Member;
+
+/// Removal of a member proposed by an external sender, for instance by a
+/// delivery service enforcing a blocklist.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExternalRemoval<'a> {
+    /// Identifier of the group the removal was proposed in.
+    pub group_id: &'a [u8],
+    /// Epoch the removal was proposed in.
+    pub epoch: u64,
+    /// Index of the proposer within the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// of the group.
+    pub sender_index: u32,
+    /// Member that is proposed to be removed.
+    pub removed: Member,
+}
+
+/// Policy applied to removals proposed by external senders.
+///
+/// A policy can be configured with
+/// [`ClientBuilder::external_removal_policy`](crate::client_builder::ClientBuilder::external_removal_policy).
+/// It is called each time a valid remove proposal from an external sender is
+/// received, which makes it the place to surface the removal to the user.
+///
+/// If the policy returns a delay, the group records a deadline by which the
+/// proposal should be committed. The deadline can be read with
+/// [`Group::external_removal_deadline`](crate::Group::external_removal_deadline)
+/// and the commit made with
+/// [`Group::commit_external_removals_if_due`](crate::Group::commit_external_removals_if_due).
+///
+/// This trait is implemented for closures taking an [`ExternalRemoval`] and
+/// returning an `Option<Duration>`.
+pub trait ExternalRemovalPolicy: Send + Sync {
+    /// Delay within which the removal should be committed, or `None` to leave
+    /// it entirely to the application.
+    fn commit_delay(&self, removal: &ExternalRemoval<'_>) -> Option<Duration>;
+}
+
+impl<F> ExternalRemovalPolicy for F
+where
+    F: Fn(&ExternalRemoval<'_>) -> Option<Duration> + Send + Sync,
+{
+    fn commit_delay(&self, removal: &ExternalRemoval<'_>) -> Option<Duration> {
+        self(removal)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedExternalRemovalPolicy(pub(crate) Arc<dyn ExternalRemovalPolicy>);
+
+impl Debug for SharedExternalRemovalPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedExternalRemovalPolicy").finish()
+    }
+}
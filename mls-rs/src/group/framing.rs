@@ -4,13 +4,17 @@
 
 use core::ops::Deref;
 
-use crate::{client::MlsError, tree_kem::node::LeafIndex, KeyPackage, KeyPackageRef};
+use crate::{
+    client::MlsError, hash_reference::HashReference, tree_kem::node::LeafIndex, KeyPackage,
+    KeyPackageRef,
+};
 
-use super::{Commit, FramedContentAuthData, GroupInfo, MembershipTag, Welcome};
+use super::{Commit, FramedContentAuthData, GroupInfo, MembershipTag, Welcome, WelcomeSizes};
 
 #[cfg(feature = "by_ref_proposal")]
 use crate::{group::Proposal, mls_rules::ProposalRef};
 
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
@@ -457,6 +461,31 @@ impl MlsMessage {
         }
     }
 
+    /// The content type of a public or private message, which is visible
+    /// to the delivery service.
+    pub fn content_type(&self) -> Option<ContentType> {
+        match &self.payload {
+            MlsMessagePayload::Plain(plaintext) => Some(plaintext.content.content_type()),
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => Some(ciphertext.content_type),
+            _ => None,
+        }
+    }
+
+    /// Whether this message carries application data.
+    pub(crate) fn is_application_message(&self) -> bool {
+        match &self.payload {
+            MlsMessagePayload::Plain(plaintext) => {
+                plaintext.content.content_type() == ContentType::Application
+            }
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => {
+                ciphertext.content_type == ContentType::Application
+            }
+            _ => false,
+        }
+    }
+
     /// Deserialize a message from transport.
     #[inline(never)]
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
@@ -491,6 +520,50 @@ impl MlsMessage {
         welcome.secrets.iter().map(|s| &s.new_member).collect()
     }
 
+    /// If this is a welcome message, split it into one welcome message per
+    /// recipient, in the order of [`MlsMessage::welcome_key_package_references`].
+    ///
+    /// All messages share the encrypted group info of this message, so each
+    /// recipient only receives its own group secrets. This returns an empty
+    /// list if this is not a welcome message.
+    pub fn split_welcome(&self) -> Vec<MlsMessage> {
+        let MlsMessagePayload::Welcome(welcome) = &self.payload else {
+            return Vec::new();
+        };
+
+        welcome
+            .secrets
+            .iter()
+            .map(|secrets| {
+                MlsMessage::new(
+                    self.version,
+                    MlsMessagePayload::Welcome(Welcome {
+                        cipher_suite: welcome.cipher_suite,
+                        secrets: vec![secrets.clone()],
+                        encrypted_group_info: welcome.encrypted_group_info.clone(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    /// If this is a welcome message, return the sizes of its parts.
+    pub fn welcome_sizes(&self) -> Option<WelcomeSizes> {
+        let MlsMessagePayload::Welcome(welcome) = &self.payload else {
+            return None;
+        };
+
+        Some(WelcomeSizes {
+            encrypted_group_info: welcome.encrypted_group_info.len(),
+            secrets: welcome
+                .secrets
+                .iter()
+                .map(|s| s.mls_encoded_len())
+                .collect(),
+            total: self.mls_encoded_len(),
+        })
+    }
+
     /// If this is a key package, return its key package reference.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn key_package_reference<C: CipherSuiteProvider>(
@@ -520,6 +593,55 @@ impl MlsMessage {
             .await
             .map(|r| Some(r.to_vec()))
     }
+
+    /// If this is a plaintext commit, return its confirmation tag.
+    ///
+    /// The confirmation tag is unique to the epoch created by the commit,
+    /// allowing a delivery service to detect retransmissions of a commit
+    /// without processing it.
+    pub fn commit_confirmation_tag(&self) -> Option<&[u8]> {
+        let MlsMessagePayload::Plain(plaintext) = &self.payload else {
+            return None;
+        };
+
+        match plaintext.content.content {
+            Content::Commit(_) => plaintext
+                .auth
+                .confirmation_tag
+                .as_deref()
+                .map(Vec::as_slice),
+            _ => None,
+        }
+    }
+
+    /// If this is a commit, plaintext or encrypted, return the hash of the
+    /// message.
+    ///
+    /// Computing the hash doesn't require decrypting the message, so a
+    /// delivery service can use it to deduplicate retransmitted commits.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn commit_hash<C: CipherSuiteProvider>(
+        &self,
+        cipher_suite: &C,
+    ) -> Result<Option<Vec<u8>>, MlsError> {
+        let is_commit = match &self.payload {
+            MlsMessagePayload::Plain(plaintext) => {
+                matches!(plaintext.content.content, Content::Commit(_))
+            }
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => ciphertext.content_type == ContentType::Commit,
+            _ => false,
+        };
+
+        if !is_commit {
+            return Ok(None);
+        }
+
+        let hash =
+            HashReference::compute(&self.to_bytes()?, b"MLS 1.0 Commit Hash", cipher_suite).await?;
+
+        Ok(Some(hash.to_vec()))
+    }
 }
 
 #[cfg(feature = "custom_proposal")]
@@ -668,6 +790,7 @@ pub(crate) mod test_utils {
 #[cfg(feature = "private_message")]
 #[cfg(test)]
 mod tests {
+    use alloc::vec;
     use assert_matches::assert_matches;
 
     use crate::{
@@ -675,7 +798,8 @@ mod tests {
         crypto::test_utils::test_cipher_suite_provider,
         group::{
             framing::test_utils::get_test_ciphertext_content,
-            proposal_ref::test_utils::auth_content_from_proposal, RemoveProposal,
+            proposal_ref::test_utils::auth_content_from_proposal, test_utils::test_group,
+            RemoveProposal,
         },
     };
 
@@ -738,4 +862,41 @@ mod tests {
 
         assert_eq!(computed_ref, expected_ref.to_vec());
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_identifiers() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let commit = group.group.commit(vec![]).await.unwrap().commit_message;
+        let retransmitted = MlsMessage::from_bytes(&commit.to_bytes().unwrap()).unwrap();
+
+        assert!(commit.commit_confirmation_tag().is_some());
+
+        assert_eq!(
+            commit.commit_confirmation_tag(),
+            retransmitted.commit_confirmation_tag()
+        );
+
+        let hash = commit.commit_hash(&cs).await.unwrap();
+
+        assert!(hash.is_some());
+
+        let retransmitted_hash = retransmitted.commit_hash(&cs).await.unwrap();
+        assert_eq!(hash, retransmitted_hash);
+
+        group.group.clear_pending_commit();
+
+        let other_commit = group.group.commit(vec![]).await.unwrap().commit_message;
+
+        let other_hash = other_commit.commit_hash(&cs).await.unwrap();
+        assert_ne!(hash, other_hash);
+
+        let group_info = group.group.group_info_message(true).await.unwrap();
+
+        assert_eq!(group_info.commit_confirmation_tag(), None);
+
+        let group_info_hash = group_info.commit_hash(&cs).await.unwrap();
+        assert_eq!(group_info_hash, None);
+    }
 }
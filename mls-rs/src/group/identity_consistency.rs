@@ -0,0 +1,208 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_core::identity::SigningIdentity;
+
+use crate::client::MlsError;
+
+use super::{ClientConfig, Group};
+
+/// Signing identity used by a member in one group.
+#[derive(Clone, PartialEq)]
+#[non_exhaustive]
+pub struct IdentityObservation {
+    /// Identifier of the group.
+    pub group_id: Vec<u8>,
+    /// Epoch of the group when the observation was made.
+    pub epoch: u64,
+    /// Leaf index of the member in the group.
+    pub member_index: u32,
+    /// Credential and signature key used by the member.
+    pub signing_identity: SigningIdentity,
+}
+
+impl Debug for IdentityObservation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityObservation")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("member_index", &self.member_index)
+            .field("signing_identity", &self.signing_identity)
+            .finish()
+    }
+}
+
+/// Signing identities used by the holder of an identity across several
+/// groups of a client.
+///
+/// A member is expected to use the same credential and signature key in all
+/// groups it shares with the client. Divergence means that at least one of
+/// the groups contains a member that was added with another key, which is
+/// the case when a device was replaced without the other groups being
+/// updated, but also when the delivery service or a compromised member
+/// impersonates the holder of the identity in some of the groups. The
+/// report can be compared with the keys published by a key transparency
+/// service to find out which groups are affected.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct IdentityConsistencyReport {
+    /// Identity that was checked, as returned by
+    /// [`IdentityProvider::identity`](mls_rs_core::identity::IdentityProvider::identity).
+    pub identity: Vec<u8>,
+    /// Observations of the identity, in the order of the groups that contain
+    /// it. Groups without a member with the identity are skipped.
+    pub observations: Vec<IdentityObservation>,
+}
+
+impl IdentityConsistencyReport {
+    /// Find the member with identity `identity` in each of `groups` and
+    /// collect the signing identities they use.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn check<C>(groups: &[&Group<C>], identity: &[u8]) -> Result<Self, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let mut observations = Vec::new();
+
+        for group in groups {
+            let member = match group.member_with_identity(identity).await {
+                Ok(member) => member,
+                Err(MlsError::MemberNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+
+            observations.push(IdentityObservation {
+                group_id: group.group_id().to_vec(),
+                epoch: group.current_epoch(),
+                member_index: member.index,
+                signing_identity: member.signing_identity,
+            });
+        }
+
+        Ok(Self {
+            identity: identity.to_vec(),
+            observations,
+        })
+    }
+
+    /// True if the same signing identity is used in all groups.
+    pub fn is_consistent(&self) -> bool {
+        self.signing_identities().len() <= 1
+    }
+
+    /// Distinct signing identities used across the groups, in the order
+    /// they were first observed.
+    pub fn signing_identities(&self) -> Vec<&SigningIdentity> {
+        let mut identities: Vec<&SigningIdentity> = Vec::new();
+
+        for observation in &self.observations {
+            if !identities.contains(&&observation.signing_identity) {
+                identities.push(&observation.signing_identity);
+            }
+        }
+
+        identities
+    }
+
+    /// Observations using another signing identity than `expected`, for
+    /// instance the one published by a key transparency service.
+    pub fn diverging_from<'a>(
+        &'a self,
+        expected: &'a SigningIdentity,
+    ) -> impl Iterator<Item = &'a IdentityObservation> + 'a {
+        self.observations
+            .iter()
+            .filter(move |observation| &observation.signing_identity != expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        client::test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client::Client,
+        client_builder::test_utils::TestClientConfig,
+        Group, MlsMessage,
+    };
+
+    use super::IdentityConsistencyReport;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn group_with(
+        alice: &Client<TestClientConfig>,
+        key_package: MlsMessage,
+    ) -> Group<TestClientConfig> {
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+
+        group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        group.apply_pending_commit().await.unwrap();
+
+        group
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn same_key_in_all_groups_is_consistent() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+        let (bob, bob_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let first = group_with(&alice, bob_kp).await;
+        let second = group_with(&alice, bob.generate_key_package_message().await.unwrap()).await;
+
+        let report = IdentityConsistencyReport::check(&[&first, &second], b"bob")
+            .await
+            .unwrap();
+
+        assert_eq!(report.observations.len(), 2);
+        assert!(report.is_consistent());
+
+        let (bob_identity, _) = bob.signing_identity().unwrap();
+        assert_eq!(report.diverging_from(bob_identity).count(), 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn impersonation_in_one_group_is_flagged() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+        let (bob, bob_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+        let (_, fake_bob_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let first = group_with(&alice, bob_kp).await;
+        let second = group_with(&alice, fake_bob_kp).await;
+        let unrelated = alice.create_group(Default::default()).await.unwrap();
+
+        let report = IdentityConsistencyReport::check(&[&first, &unrelated, &second], b"bob")
+            .await
+            .unwrap();
+
+        assert!(!report.is_consistent());
+        assert_eq!(report.signing_identities().len(), 2);
+
+        let (bob_identity, _) = bob.signing_identity().unwrap();
+
+        let diverging = report
+            .diverging_from(bob_identity)
+            .map(|observation| observation.group_id.as_slice())
+            .collect::<Vec<_>>();
+
+        assert_eq!(diverging, [second.group_id()]);
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::{self, Display};
+
+use mls_rs_codec::MlsDecode;
+
+use crate::{client::MlsError, client_config::ClientConfig};
+
+use super::{
+    snapshot::{Snapshot, SNAPSHOT_VERSION},
+    Group,
+};
+
+/// Inconsistency found in the persisted state of a group when loading it,
+/// reported by [`MlsError::CorruptedGroupState`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateCorruption {
+    /// The state was written by a newer version of this library, in a format
+    /// this version doesn't support, or is not a group state. States written
+    /// in older formats are migrated instead.
+    UnsupportedSnapshotVersion(u16),
+    /// The ratchet tree doesn't match the tree hash of the group context.
+    TreeHashMismatch,
+    /// Prior epochs newer than the state are stored, meaning that the state
+    /// was overwritten by an older copy of it.
+    EpochDiscontinuity {
+        /// Epoch of the state.
+        epoch: u64,
+        /// Latest prior epoch stored for the group.
+        latest_prior_epoch: u64,
+    },
+}
+
+/// Way to recover from a [`StateCorruption`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CorruptionRecovery {
+    /// Join the group again, for instance with an external commit, as the
+    /// secrets of the latest epochs are lost.
+    Resync,
+    /// Restore the state from a backup made in an older epoch, then process
+    /// the commits made since then.
+    RestoreOlderEpoch,
+    /// Load the state with the version of this library that wrote it, or a
+    /// newer one. The state itself may be intact.
+    UpgradeLibrary,
+}
+
+impl StateCorruption {
+    /// Suggested way to recover from the corruption.
+    pub fn recovery(&self) -> CorruptionRecovery {
+        match self {
+            // Reusing the secrets of an older state risks reusing keys
+            // already used in the epochs that followed it.
+            StateCorruption::EpochDiscontinuity { .. } => CorruptionRecovery::Resync,
+            StateCorruption::UnsupportedSnapshotVersion(_) => CorruptionRecovery::UpgradeLibrary,
+            StateCorruption::TreeHashMismatch => CorruptionRecovery::RestoreOlderEpoch,
+        }
+    }
+}
+
+impl Display for StateCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateCorruption::UnsupportedSnapshotVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            StateCorruption::TreeHashMismatch => f.write_str("tree hash mismatch"),
+            StateCorruption::EpochDiscontinuity {
+                epoch,
+                latest_prior_epoch,
+            } => write!(
+                f,
+                "state of epoch {epoch} older than stored prior epoch {latest_prior_epoch}"
+            ),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Decode a snapshot, migrating snapshots of older versions and
+    /// reporting snapshots of newer versions as such rather than as a
+    /// decoding error.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        match u16::mls_decode(&mut &*bytes) {
+            Ok(version) if version > SNAPSHOT_VERSION => Err(MlsError::CorruptedGroupState(
+                StateCorruption::UnsupportedSnapshotVersion(version),
+            )),
+            _ => Ok(Snapshot::mls_decode(&mut &*bytes)?),
+        }
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Check the consistency of a group loaded from storage, computing the
+    /// hashes of its ratchet tree again.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify_integrity(&mut self) -> Result<(), MlsError> {
+        let corrupted = |corruption| Err(MlsError::CorruptedGroupState(corruption));
+
+        let tree_hash = self
+            .state
+            .public_tree
+            .recompute_tree_hash(&self.cipher_suite_provider)
+            .await?;
+
+        if tree_hash != self.state.context.tree_hash {
+            return corrupted(StateCorruption::TreeHashMismatch);
+        }
+
+        #[cfg(feature = "prior_epoch")]
+        if let Some(latest_prior_epoch) = self.state_repo.find_max_id().await? {
+            let epoch = self.state.context.epoch;
+
+            if latest_prior_epoch >= epoch {
+                return corrupted(StateCorruption::EpochDiscontinuity {
+                    epoch,
+                    latest_prior_epoch,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use mls_rs_codec::MlsEncode;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            snapshot::{Snapshot, SNAPSHOT_VERSION},
+            test_utils::test_group,
+        },
+    };
+
+    #[cfg(feature = "prior_epoch")]
+    use crate::group::Group;
+
+    use super::{CorruptionRecovery, StateCorruption};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn consistent_group_is_verified() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        group.group.commit(vec![]).await.unwrap();
+        group.group.apply_pending_commit().await.unwrap();
+        group.group.write_to_storage().await.unwrap();
+
+        group.group.verify_integrity().await.unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tree_hash_mismatch_is_detected() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        group.group.state.context.tree_hash = vec![0; 32];
+
+        let res = group.group.verify_integrity().await;
+        let corruption = assert_matches!(res, Err(MlsError::CorruptedGroupState(c)) => c);
+
+        assert_eq!(corruption, StateCorruption::TreeHashMismatch);
+        assert_eq!(corruption.recovery(), CorruptionRecovery::RestoreOlderEpoch);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn snapshot_of_newer_version_is_detected() {
+        let group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let mut snapshot = group.group.snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+
+        let mut bytes = snapshot.mls_encode_to_vec().unwrap();
+
+        let res = Snapshot::from_bytes(&bytes);
+        let corruption = assert_matches!(res, Err(MlsError::CorruptedGroupState(c)) => c);
+
+        assert_eq!(
+            corruption,
+            StateCorruption::UnsupportedSnapshotVersion(SNAPSHOT_VERSION + 1)
+        );
+
+        assert_eq!(corruption.recovery(), CorruptionRecovery::UpgradeLibrary);
+
+        // A snapshot in a newer format may not be decodable at all.
+        bytes.truncate(16);
+
+        assert_matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(MlsError::CorruptedGroupState(
+                StateCorruption::UnsupportedSnapshotVersion(v)
+            )) if v == SNAPSHOT_VERSION + 1
+        );
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn stale_state_is_detected() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let stale_snapshot = group.group.snapshot();
+
+        for _ in 0..2 {
+            group.group.commit(vec![]).await.unwrap();
+            group.group.apply_pending_commit().await.unwrap();
+            group.group.write_to_storage().await.unwrap();
+        }
+
+        let mut stale_group = Group::from_snapshot(group.group.config.clone(), stale_snapshot)
+            .await
+            .unwrap();
+
+        let res = stale_group.verify_integrity().await;
+        let corruption = assert_matches!(res, Err(MlsError::CorruptedGroupState(c)) => c);
+
+        assert_eq!(
+            corruption,
+            StateCorruption::EpochDiscontinuity {
+                epoch: 0,
+                latest_prior_epoch: 1,
+            }
+        );
+
+        assert_eq!(corruption.recovery(), CorruptionRecovery::Resync);
+    }
+}
@@ -49,19 +49,19 @@ impl Debug for KeySchedule {
         f.debug_struct("KeySchedule")
             .field(
                 "exporter_secret",
-                &mls_rs_core::debug::pretty_bytes(&self.exporter_secret),
+                &mls_rs_core::debug::pretty_secret(&self.exporter_secret),
             )
             .field(
                 "authentication_secret",
-                &mls_rs_core::debug::pretty_bytes(&self.authentication_secret),
+                &mls_rs_core::debug::pretty_secret(&self.authentication_secret),
             )
             .field(
                 "external_secret",
-                &mls_rs_core::debug::pretty_bytes(&self.external_secret),
+                &mls_rs_core::debug::pretty_secret(&self.external_secret),
             )
             .field(
                 "membership_key",
-                &mls_rs_core::debug::pretty_bytes(&self.membership_key),
+                &mls_rs_core::debug::pretty_secret(&self.membership_key),
             )
             .field("init_secret", &self.init_secret)
             .finish()
@@ -333,7 +333,7 @@ pub(crate) struct JoinerSecret(#[mls_codec(with = "mls_rs_codec::byte_vec")] Zer
 
 impl Debug for JoinerSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        mls_rs_core::debug::pretty_bytes(&self.0)
+        mls_rs_core::debug::pretty_secret(&self.0)
             .named("JoinerSecret")
             .fmt(f)
     }
@@ -391,7 +391,7 @@ pub struct InitSecret(
 
 impl Debug for InitSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        mls_rs_core::debug::pretty_bytes(&self.0)
+        mls_rs_core::debug::pretty_secret(&self.0)
             .named("InitSecret")
             .fmt(f)
     }
@@ -593,7 +593,7 @@ mod tests {
     use zeroize::Zeroizing;
 
     use super::test_utils::get_test_key_schedule;
-    use super::KeySchedule;
+    use super::{KeySchedule, Label};
 
     #[derive(serde::Deserialize, serde::Serialize)]
     struct TestCase {
@@ -985,4 +985,17 @@ mod tests {
             }
         }
     }
+
+    // Secret tree derivations use the generation, a `uint32`, as context.
+    // Changing this encoding breaks interoperability and existing groups.
+    #[test]
+    fn kdf_label_is_pinned() {
+        let generation = 1u32.mls_encode_to_vec().unwrap();
+        let label = Label::new(32, b"tree", &generation);
+
+        assert_eq!(
+            hex::encode(label.mls_encode_to_vec().unwrap()),
+            "00200c4d4c5320312e3020747265650400000001"
+        );
+    }
 }
@@ -0,0 +1,453 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Group-synchronized key-value store.
+//!
+//! The store is kept in the group context as a [`KeyValueStoreExt`], which
+//! means all members agree on its content in every epoch, and new members
+//! receive it when joining. Members change the store by sending
+//! [`KeyValueUpdate`] custom proposals, either by reference or by value in a
+//! commit.
+//!
+//! Applying the changes is the job of [`KeyValueRules`], which wraps the
+//! [`MlsRules`] of each member. When a commit contains key-value updates, the
+//! rules derive the new store from the current one and apply it with a
+//! group context extensions proposal. All members of the group must therefore
+//! use [`KeyValueRules`] and support both [`KEY_VALUE_STORE_EXTENSION`] and
+//! [`KEY_VALUE_UPDATE_PROPOSAL`] in their capabilities.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    error::IntoAnyError,
+    extension::{ExtensionList, ExtensionType, MlsCodecExtension},
+    group::ProposalType,
+};
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+use crate::{
+    client::MlsError,
+    group::{
+        mls_rules::{CommitDirection, CommitOptions, CommitSource, EncryptionOptions, MlsRules},
+        proposal::{MlsCustomProposal, Proposal},
+        proposal_filter::{ProposalBundle, ProposalSource},
+        Roster, Sender,
+    },
+};
+
+/// Extension type of [`KeyValueStoreExt`], taken from the private use range.
+pub const KEY_VALUE_STORE_EXTENSION: ExtensionType = ExtensionType::new(0xF6B0);
+
+/// Proposal type of [`KeyValueUpdate`], taken from the private use range.
+pub const KEY_VALUE_UPDATE_PROPOSAL: ProposalType = ProposalType::new(0xF6B0);
+
+/// Group context extension holding the key-value store of a group.
+#[derive(Clone, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct KeyValueStoreExt {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Debug for KeyValueStoreExt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(k, v)| {
+                (
+                    mls_rs_core::debug::pretty_bytes(k),
+                    mls_rs_core::debug::pretty_bytes(v),
+                )
+            }))
+            .finish()
+    }
+}
+
+impl KeyValueStoreExt {
+    /// Value stored for `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// Iterate over all entries, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.entries
+            .iter()
+            .map(|(k, v)| (k.as_slice(), v.as_slice()))
+    }
+
+    /// Number of entries in the store.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determine if the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Apply the changes of `update`, in order.
+    pub fn apply(&mut self, update: &KeyValueUpdate) {
+        for change in &update.changes {
+            match &change.value {
+                Some(value) => self.entries.insert(change.key.clone(), value.clone()),
+                None => self.entries.remove(&change.key),
+            };
+        }
+    }
+}
+
+impl MlsCodecExtension for KeyValueStoreExt {
+    fn extension_type() -> ExtensionType {
+        KEY_VALUE_STORE_EXTENSION
+    }
+}
+
+/// Change to a single key of the store.
+#[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct KeyValueChange {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+}
+
+impl Debug for KeyValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyValueChange")
+            .field("key", &mls_rs_core::debug::pretty_bytes(&self.key))
+            .field(
+                "value",
+                &self.value.as_deref().map(mls_rs_core::debug::pretty_bytes),
+            )
+            .finish()
+    }
+}
+
+impl KeyValueChange {
+    /// Key being changed.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// New value of the key, or `None` if the key is deleted.
+    pub fn value(&self) -> Option<&[u8]> {
+        self.value.as_deref()
+    }
+}
+
+/// Custom proposal changing the key-value store of a group.
+///
+/// Changes are applied in order when the proposal is committed. Proposals
+/// in the same commit are applied in the order they appear in the commit.
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct KeyValueUpdate {
+    changes: Vec<KeyValueChange>,
+}
+
+impl KeyValueUpdate {
+    /// Create an update with no changes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn set(mut self, key: Vec<u8>, value: Vec<u8>) -> Self {
+        self.changes.push(KeyValueChange {
+            key,
+            value: Some(value),
+        });
+
+        self
+    }
+
+    /// Delete `key`.
+    pub fn delete(mut self, key: Vec<u8>) -> Self {
+        self.changes.push(KeyValueChange { key, value: None });
+        self
+    }
+
+    /// Changes made by this update.
+    pub fn changes(&self) -> &[KeyValueChange] {
+        &self.changes
+    }
+}
+
+impl MlsCustomProposal for KeyValueUpdate {
+    fn proposal_type() -> ProposalType {
+        KEY_VALUE_UPDATE_PROPOSAL
+    }
+}
+
+/// [`MlsRules`] applying committed [`KeyValueUpdate`] proposals to the
+/// [`KeyValueStoreExt`] of the group.
+///
+/// All other decisions are delegated to the wrapped rules, which are called
+/// first.
+#[derive(Clone, Debug, Default)]
+pub struct KeyValueRules<R> {
+    inner: R,
+}
+
+impl<R> KeyValueRules<R> {
+    /// Apply key-value updates on top of `inner`.
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// Access the wrapped rules.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+fn apply_updates(
+    source: &CommitSource,
+    extension_list: &ExtensionList,
+    mut proposals: ProposalBundle,
+) -> Result<ProposalBundle, MlsError> {
+    let updates = proposals
+        .custom_proposals()
+        .iter()
+        .filter(|p| p.proposal.proposal_type() == KEY_VALUE_UPDATE_PROPOSAL)
+        .map(|p| KeyValueUpdate::from_custom_proposal(&p.proposal))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if updates.is_empty() {
+        return Ok(proposals);
+    }
+
+    // RFC 9420 doesn't allow group context extensions proposals in external
+    // commits, so the store can't be updated by a new member.
+    let sender = match source {
+        CommitSource::ExistingMember(member) => Sender::Member(member.index),
+        CommitSource::NewMember(_) => {
+            return Err(MlsError::InvalidProposalTypeInExternalCommit(
+                ProposalType::GROUP_CONTEXT_EXTENSIONS,
+            ))
+        }
+    };
+
+    // Updates are applied on top of the extensions this commit sets, if any.
+    let mut new_extensions = proposals
+        .group_context_extensions
+        .first()
+        .map_or_else(|| extension_list.clone(), |p| p.proposal.clone());
+
+    let mut store = match new_extensions.get_as::<KeyValueStoreExt>()? {
+        Some(store) => store,
+        None => extension_list.get_as()?.unwrap_or_default(),
+    };
+
+    updates.iter().for_each(|update| store.apply(update));
+    new_extensions.set_from(store)?;
+
+    match proposals.group_context_extensions.first_mut() {
+        Some(p) => p.proposal = new_extensions,
+        None => proposals.add(
+            Proposal::GroupContextExtensions(new_extensions),
+            sender,
+            ProposalSource::Local,
+        ),
+    }
+
+    Ok(proposals)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<R: MlsRules> MlsRules for KeyValueRules<R> {
+    type Error = MlsError;
+
+    async fn filter_proposals(
+        &self,
+        direction: CommitDirection,
+        source: CommitSource,
+        current_roster: &Roster,
+        extension_list: &ExtensionList,
+        proposals: ProposalBundle,
+    ) -> Result<ProposalBundle, Self::Error> {
+        let proposals = self
+            .inner
+            .filter_proposals(
+                direction,
+                source.clone(),
+                current_roster,
+                extension_list,
+                proposals,
+            )
+            .await
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        apply_updates(&source, extension_list, proposals)
+    }
+
+    fn commit_options(
+        &self,
+        new_roster: &Roster,
+        new_extension_list: &ExtensionList,
+        proposals: &ProposalBundle,
+    ) -> Result<CommitOptions, Self::Error> {
+        self.inner
+            .commit_options(new_roster, new_extension_list, proposals)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
+
+    fn encryption_options(
+        &self,
+        current_roster: &Roster,
+        current_extension_list: &ExtensionList,
+    ) -> Result<EncryptionOptions, Self::Error> {
+        self.inner
+            .encryption_options(current_roster, current_extension_list)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
+}
+
+#[cfg(all(test, feature = "by_ref_proposal"))]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use mls_rs_core::{extension::ExtensionList, group::ProposalType};
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        client_builder::{ClientBuilder, MlsConfig},
+        crypto::test_utils::TestCryptoProvider,
+        group::{
+            mls_rules::{CommitSource, DefaultMlsRules},
+            proposal::{MlsCustomProposal, Proposal},
+            proposal_filter::{ProposalBundle, ProposalSource},
+            Group, Sender,
+        },
+        identity::{
+            basic::BasicIdentityProvider,
+            test_utils::{get_test_signing_identity, BasicWithCustomProvider},
+        },
+        Client,
+    };
+
+    use super::{
+        apply_updates, KeyValueRules, KeyValueStoreExt, KeyValueUpdate, KEY_VALUE_STORE_EXTENSION,
+        KEY_VALUE_UPDATE_PROPOSAL,
+    };
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_client(name: &[u8]) -> Client<impl MlsConfig> {
+        let (signing_identity, signer) = get_test_signing_identity(TEST_CIPHER_SUITE, name).await;
+
+        ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(BasicWithCustomProvider::new(BasicIdentityProvider::new()))
+            .signing_identity(signing_identity, signer, TEST_CIPHER_SUITE)
+            .extension_type(KEY_VALUE_STORE_EXTENSION)
+            .custom_proposal_type(KEY_VALUE_UPDATE_PROPOSAL)
+            .mls_rules(KeyValueRules::new(DefaultMlsRules::new()))
+            .used_protocol_version(TEST_PROTOCOL_VERSION)
+            .build()
+    }
+
+    fn store<C: crate::client_config::ClientConfig>(group: &Group<C>) -> KeyValueStoreExt {
+        group
+            .context()
+            .extensions
+            .get_as::<KeyValueStoreExt>()
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_value_updates_are_applied_by_all_members() {
+        let alice = test_client(b"alice").await;
+        let bob = test_client(b"bob").await;
+
+        let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(bob.generate_key_package_message().await.unwrap())
+            .unwrap()
+            .custom_proposal(
+                KeyValueUpdate::new()
+                    .set(b"name".to_vec(), b"team".to_vec())
+                    .set(b"topic".to_vec(), b"mls".to_vec())
+                    .to_custom_proposal()
+                    .unwrap(),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        assert_eq!(store(&bob_group).get(b"name"), Some(&b"team"[..]));
+        assert_eq!(store(&alice_group), store(&bob_group));
+
+        let proposal = bob_group
+            .propose_custom(
+                KeyValueUpdate::new()
+                    .delete(b"topic".to_vec())
+                    .to_custom_proposal()
+                    .unwrap(),
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        alice_group
+            .process_incoming_message(proposal)
+            .await
+            .unwrap();
+
+        let commit = alice_group.commit(vec![]).await.unwrap();
+        alice_group.apply_pending_commit().await.unwrap();
+
+        bob_group
+            .process_incoming_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        assert_eq!(store(&bob_group).get(b"topic"), None);
+        assert_eq!(store(&bob_group).len(), 1);
+        assert_eq!(store(&alice_group), store(&bob_group));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_value_updates_are_rejected_in_external_commits() {
+        let (signing_identity, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let mut proposals = ProposalBundle::default();
+
+        proposals.add(
+            Proposal::Custom(
+                KeyValueUpdate::new()
+                    .set(b"name".to_vec(), b"team".to_vec())
+                    .to_custom_proposal()
+                    .unwrap(),
+            ),
+            Sender::NewMemberCommit,
+            ProposalSource::ByValue,
+        );
+
+        let res = apply_updates(
+            &CommitSource::NewMember(signing_identity),
+            &ExtensionList::new(),
+            proposals,
+        );
+
+        assert_matches!(
+            res,
+            Err(MlsError::InvalidProposalTypeInExternalCommit(ty))
+                if ty == ProposalType::GROUP_CONTEXT_EXTENSIONS
+        );
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::{HpkeCiphertext, HpkePublicKey, HpkeSecretKey},
+    group::Member,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    client::MlsError,
+    crypto::{CipherSuiteProvider, SignatureSecretKey},
+    signer::Signable,
+    tree_kem::{hpke_encryption::HpkeEncryptable, node::LeafIndex, TreeKemPublic},
+};
+
+use super::{member_from_leaf_node, GroupContext};
+
+/// Payload sent by a member to another specific member of the group.
+///
+/// An envelope is created with
+/// [`Group::seal_for_member`](crate::Group::seal_for_member) and opened by
+/// its recipient with
+/// [`Group::open_member_envelope`](crate::Group::open_member_envelope). It is
+/// meant for control messages between two members, such as requests and
+/// responses, that the rest of the group shouldn't be able to read.
+///
+/// The payload is encrypted with HPKE to the encryption key in the leaf of
+/// the recipient, using the epoch authenticator of the current epoch as
+/// context, so that only the recipient can decrypt it and only while it is a
+/// member of the group at the same epoch. The envelope is signed by the
+/// sender.
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MemberEnvelope {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    sender: LeafIndex,
+    recipient: LeafIndex,
+    ciphertext: HpkeCiphertext,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for MemberEnvelope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemberEnvelope")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("sender", &self.sender)
+            .field("recipient", &self.recipient)
+            .field("ciphertext", &self.ciphertext)
+            .field(
+                "signature",
+                &mls_rs_core::debug::pretty_bytes(&self.signature),
+            )
+            .finish()
+    }
+}
+
+impl MemberEnvelope {
+    /// Identifier of the group the envelope was created for.
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Epoch the envelope was created in.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Leaf index of the member that created the envelope.
+    pub fn sender_index(&self) -> u32 {
+        *self.sender
+    }
+
+    /// Leaf index of the member the envelope is meant for.
+    pub fn recipient_index(&self) -> u32 {
+        *self.recipient
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn seal<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        epoch_authenticator: &[u8],
+        sender: LeafIndex,
+        signer: &SignatureSecretKey,
+        recipient: LeafIndex,
+        recipient_key: &HpkePublicKey,
+        payload: &[u8],
+    ) -> Result<Self, MlsError> {
+        let context = EnvelopeContext {
+            group_id: &group_context.group_id,
+            epoch: group_context.epoch,
+            sender,
+            recipient,
+            epoch_authenticator,
+        }
+        .mls_encode_to_vec()?;
+
+        let ciphertext = EnvelopePayload(Zeroizing::new(payload.to_vec()))
+            .encrypt(cipher_suite_provider, recipient_key, &context)
+            .await?;
+
+        let mut envelope = Self {
+            group_id: group_context.group_id.clone(),
+            epoch: group_context.epoch,
+            sender,
+            recipient,
+            ciphertext,
+            signature: Vec::new(),
+        };
+
+        envelope.sign(cipher_suite_provider, signer, &()).await?;
+
+        Ok(envelope)
+    }
+
+    /// Verify and decrypt the envelope for the member at `recipient`,
+    /// returning the sender and the payload.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn open<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        epoch_authenticator: &[u8],
+        tree: &TreeKemPublic,
+        recipient: LeafIndex,
+        recipient_secret_key: &HpkeSecretKey,
+    ) -> Result<(Member, Vec<u8>), MlsError> {
+        if self.group_id != group_context.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        if self.epoch != group_context.epoch {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        if self.recipient != recipient {
+            return Err(MlsError::InvalidEnvelopeRecipient);
+        }
+
+        let sender_leaf = tree.get_leaf_node(self.sender)?;
+
+        Signable::verify(
+            self,
+            cipher_suite_provider,
+            &sender_leaf.signing_identity.signature_key,
+            &(),
+        )
+        .await?;
+
+        let context = EnvelopeContext {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            sender: self.sender,
+            recipient: self.recipient,
+            epoch_authenticator,
+        }
+        .mls_encode_to_vec()?;
+
+        let recipient_leaf = tree.get_leaf_node(recipient)?;
+
+        let payload = EnvelopePayload::decrypt(
+            cipher_suite_provider,
+            recipient_secret_key,
+            &recipient_leaf.public_key,
+            &context,
+            &self.ciphertext,
+        )
+        .await?;
+
+        Ok((
+            member_from_leaf_node(sender_leaf, self.sender),
+            payload.0.to_vec(),
+        ))
+    }
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct EnvelopeContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    sender: LeafIndex,
+    recipient: LeafIndex,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    epoch_authenticator: &'a [u8],
+}
+
+struct EnvelopePayload(Zeroizing<Vec<u8>>);
+
+impl HpkeEncryptable for EnvelopePayload {
+    const ENCRYPT_LABEL: &'static str = "MemberEnvelope";
+
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, MlsError> {
+        Ok(Self(Zeroizing::new(bytes)))
+    }
+
+    fn get_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        Ok(self.0.to_vec())
+    }
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct MemberEnvelopeTBS<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    sender: LeafIndex,
+    recipient: LeafIndex,
+    ciphertext: &'a HpkeCiphertext,
+}
+
+impl<'a> Signable<'a> for MemberEnvelope {
+    const SIGN_LABEL: &'static str = "MemberEnvelopeTBS";
+
+    type SigningContext = ();
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        _context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        MemberEnvelopeTBS {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            sender: self.sender,
+            recipient: self.recipient,
+            ciphertext: &self.ciphertext,
+        }
+        .mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn envelope_is_opened_by_recipient_only() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let envelope = groups[0]
+            .group
+            .seal_for_member(groups[1].group.current_member_index(), b"request")
+            .await
+            .unwrap();
+
+        let (sender, payload) = groups[1]
+            .group
+            .open_member_envelope(&envelope)
+            .await
+            .unwrap();
+
+        assert_eq!(sender.index, groups[0].group.current_member_index());
+        assert_eq!(payload, b"request");
+
+        let res = groups[2].group.open_member_envelope(&envelope).await;
+        assert_matches!(res, Err(MlsError::InvalidEnvelopeRecipient));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tampered_envelope_is_rejected() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let mut envelope = groups[0]
+            .group
+            .seal_for_member(groups[1].group.current_member_index(), b"request")
+            .await
+            .unwrap();
+
+        envelope.sender = groups[2].group.private_tree.self_index;
+
+        let res = groups[1].group.open_member_envelope(&envelope).await;
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn envelope_is_bound_to_epoch() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let envelope = groups[0]
+            .group
+            .seal_for_member(groups[1].group.current_member_index(), &[1; 8])
+            .await
+            .unwrap();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        groups[1]
+            .process_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        let res = groups[1].group.open_member_envelope(&envelope).await;
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+}
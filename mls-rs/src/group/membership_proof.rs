@@ -0,0 +1,264 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{error::IntoAnyError, group::Member};
+
+use crate::{
+    client::MlsError,
+    crypto::{CipherSuiteProvider, SignatureSecretKey},
+    signer::Signable,
+    tree_kem::{node::LeafIndex, TreeKemPublic},
+};
+
+use super::{member_from_leaf_node, GroupContext};
+
+/// Proof that a member belongs to a group at a given epoch.
+///
+/// A proof is created with
+/// [`Group::membership_proof`](crate::Group::membership_proof) and contains a
+/// signature, made with the member's signing key, over the hash of the
+/// group context and the member's leaf index. It is bound to an
+/// application-provided `context` which can be used to restrict the proof to
+/// a specific resource or to include a challenge.
+///
+/// A proof can be verified by any party that holds the public state of the
+/// group at the same epoch, such as another member with
+/// [`Group::verify_membership_proof`](crate::Group::verify_membership_proof)
+/// or an external group with
+/// [`ExternalGroup::verify_membership_proof`](crate::external_client::ExternalGroup::verify_membership_proof).
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MembershipProof {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    leaf_index: LeafIndex,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    context: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for MembershipProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MembershipProof")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("leaf_index", &self.leaf_index)
+            .field("context", &mls_rs_core::debug::pretty_bytes(&self.context))
+            .field(
+                "signature",
+                &mls_rs_core::debug::pretty_bytes(&self.signature),
+            )
+            .finish()
+    }
+}
+
+impl MembershipProof {
+    /// Identifier of the group the proof was created for.
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Epoch the proof was created in.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Leaf index of the member that created the proof.
+    pub fn leaf_index(&self) -> u32 {
+        *self.leaf_index
+    }
+
+    /// Application-provided context the proof is bound to.
+    pub fn context(&self) -> &[u8] {
+        &self.context
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        leaf_index: LeafIndex,
+        signer: &SignatureSecretKey,
+        context: Vec<u8>,
+    ) -> Result<Self, MlsError> {
+        let mut proof = Self {
+            group_id: group_context.group_id.clone(),
+            epoch: group_context.epoch,
+            leaf_index,
+            context,
+            signature: Vec::new(),
+        };
+
+        let context_hash = group_context_hash(cipher_suite_provider, group_context).await?;
+
+        proof
+            .sign(cipher_suite_provider, signer, &context_hash)
+            .await?;
+
+        Ok(proof)
+    }
+
+    /// Verify the proof against the group state given by `group_context` and
+    /// `tree`, returning the member that created it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        tree: &TreeKemPublic,
+    ) -> Result<Member, MlsError> {
+        if self.group_id != group_context.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        if self.epoch != group_context.epoch {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        let leaf = tree.get_leaf_node(self.leaf_index)?;
+        let context_hash = group_context_hash(cipher_suite_provider, group_context).await?;
+
+        Signable::verify(
+            self,
+            cipher_suite_provider,
+            &leaf.signing_identity.signature_key,
+            &context_hash,
+        )
+        .await?;
+
+        Ok(member_from_leaf_node(leaf, self.leaf_index))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn group_context_hash<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    group_context: &GroupContext,
+) -> Result<Vec<u8>, MlsError> {
+    cipher_suite_provider
+        .hash(&group_context.mls_encode_to_vec()?)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct MembershipProofTBS<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_context_hash: &'a [u8],
+    leaf_index: LeafIndex,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    context: &'a [u8],
+}
+
+impl<'a> Signable<'a> for MembershipProof {
+    const SIGN_LABEL: &'static str = "MembershipProofTBS";
+
+    type SigningContext = Vec<u8>;
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        MembershipProofTBS {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            group_context_hash: context,
+            leaf_index: self.leaf_index,
+            context: &self.context,
+        }
+        .mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn membership_proof_is_verified_by_other_member() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let proof = groups[0]
+            .group
+            .membership_proof(b"resource".to_vec())
+            .await
+            .unwrap();
+
+        let member = groups[1]
+            .group
+            .verify_membership_proof(&proof)
+            .await
+            .unwrap();
+
+        assert_eq!(member.index, groups[0].group.current_member_index());
+        assert_eq!(proof.context(), b"resource");
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tampered_membership_proof_is_rejected() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let mut proof = groups[0]
+            .group
+            .membership_proof(b"resource".to_vec())
+            .await
+            .unwrap();
+
+        proof.context = b"other resource".to_vec();
+
+        let res = groups[1].group.verify_membership_proof(&proof).await;
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+
+        proof.context = b"resource".to_vec();
+        proof.leaf_index = groups[1].group.private_tree.self_index;
+
+        let res = groups[1].group.verify_membership_proof(&proof).await;
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn membership_proof_is_bound_to_epoch() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let proof = groups[0].group.membership_proof(vec![]).await.unwrap();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        groups[1]
+            .process_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        let res = groups[1].group.verify_membership_proof(&proof).await;
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{client::MlsError, time::MlsTime};
+
+const EXPIRY_LABEL: &[u8] = b"mls-rs message expiry";
+
+/// Authenticated data of an application message that carries the time after
+/// which the message should be discarded.
+///
+/// The authenticated data of a private message is covered by the AEAD and
+/// the signature of the sender, so the expiry can't be removed or changed by
+/// the delivery service without the message being rejected.
+///
+/// Messages carrying an expiry are created with
+/// [`Group::encrypt_application_message_with_expiry`](crate::Group::encrypt_application_message_with_expiry)
+/// and checked on receipt by the [`MessageExpiryPolicy`] of the client.
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[non_exhaustive]
+pub struct ExpiringAuthenticatedData {
+    /// Seconds since the unix epoch after which the message expires.
+    pub expires_at: u64,
+    /// Authenticated data provided by the application.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub authenticated_data: Vec<u8>,
+}
+
+impl ExpiringAuthenticatedData {
+    pub fn new(expires_at: MlsTime, authenticated_data: Vec<u8>) -> Self {
+        Self {
+            expires_at: expires_at.seconds_since_epoch(),
+            authenticated_data,
+        }
+    }
+
+    /// Time after which the message expires.
+    pub fn expires_at(&self) -> MlsTime {
+        MlsTime::from(self.expires_at)
+    }
+
+    /// Serialize to the authenticated data of a message.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        let mut bytes = EXPIRY_LABEL.to_vec();
+        self.mls_encode(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parse the authenticated data of a message, returning `None` if it
+    /// carries no expiry.
+    pub fn from_bytes(authenticated_data: &[u8]) -> Result<Option<Self>, MlsError> {
+        let Some(mut data) = authenticated_data.strip_prefix(EXPIRY_LABEL) else {
+            return Ok(None);
+        };
+
+        Self::mls_decode(&mut data).map(Some).map_err(Into::into)
+    }
+}
+
+/// Application message with an expiry, received by a member.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExpiringMessage<'a> {
+    /// Identifier of the group the message was sent in.
+    pub group_id: &'a [u8],
+    /// Epoch the message was sent in.
+    pub epoch: u64,
+    /// Index of the sender in the group.
+    pub sender_index: u32,
+    /// Time after which the message expires.
+    pub expires_at: MlsTime,
+    /// Time the message was received at, which is the time given to
+    /// [`Group::process_incoming_message_with_time`](crate::Group::process_incoming_message_with_time)
+    /// or the current system time. It is `None` without the `std` feature.
+    pub received_at: Option<MlsTime>,
+}
+
+/// Policy enforcing the expiry of application messages.
+///
+/// A policy can be configured with
+/// [`ClientBuilder::message_expiry_policy`](crate::client_builder::ClientBuilder::message_expiry_policy).
+/// It is called for each application message carrying an
+/// [`ExpiringAuthenticatedData`], and expired messages are rejected with
+/// [`MlsError::MessageExpired`]. Without a policy, the expiry is not
+/// enforced and can be read with
+/// [`ApplicationMessageDescription::expires_at`](crate::group::ApplicationMessageDescription::expires_at).
+///
+/// This trait is implemented for closures taking an [`ExpiringMessage`] and
+/// returning a `bool`.
+pub trait MessageExpiryPolicy: Send + Sync {
+    /// Whether `message` has expired and must be rejected.
+    fn is_expired(&self, message: &ExpiringMessage<'_>) -> bool;
+}
+
+impl<F> MessageExpiryPolicy for F
+where
+    F: Fn(&ExpiringMessage<'_>) -> bool + Send + Sync,
+{
+    fn is_expired(&self, message: &ExpiringMessage<'_>) -> bool {
+        self(message)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedMessageExpiryPolicy(pub(crate) Arc<dyn MessageExpiryPolicy>);
+
+impl Debug for SharedMessageExpiryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedMessageExpiryPolicy").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn expiry_round_trips_through_authenticated_data() {
+        let data = ExpiringAuthenticatedData::new(MlsTime::from(1000), b"aad".to_vec());
+        let bytes = data.to_bytes().unwrap();
+
+        assert_eq!(
+            ExpiringAuthenticatedData::from_bytes(&bytes).unwrap(),
+            Some(data)
+        );
+    }
+
+    #[test]
+    fn authenticated_data_without_expiry_is_ignored() {
+        assert_eq!(ExpiringAuthenticatedData::from_bytes(b"aad").unwrap(), None);
+        assert_eq!(ExpiringAuthenticatedData::from_bytes(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_expiry_is_rejected() {
+        let mut bytes = EXPIRY_LABEL.to_vec();
+        bytes.extend(vec![0, 1]);
+
+        let res = ExpiringAuthenticatedData::from_bytes(&bytes);
+        assert_matches!(res, Err(MlsError::SerializationError(_)));
+    }
+}
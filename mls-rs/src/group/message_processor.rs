@@ -13,7 +13,7 @@ use super::{
     proposal_filter::ProposalBundle,
     state::GroupState,
     transcript_hash::InterimTranscriptHash,
-    transcript_hashes, validate_group_info_member, GroupContext, GroupInfo, Welcome,
+    transcript_hashes, validate_group_info_member, CommitStats, GroupContext, GroupInfo, Welcome,
 };
 use crate::{
     client::MlsError,
@@ -59,6 +59,12 @@ use mls_rs_core::psk::ExternalPskId;
 #[cfg(feature = "state_update")]
 use crate::tree_kem::UpdatePath;
 
+#[cfg(feature = "state_update")]
+use crate::extension::group_metadata::GroupMetadataChange;
+
+#[cfg(feature = "state_update")]
+use super::extension_change::{ExtensionChange, ExtensionScope};
+
 #[cfg(feature = "state_update")]
 use super::{member_from_key_package, member_from_leaf_node};
 
@@ -68,6 +74,9 @@ use super::proposal::CustomProposal;
 #[cfg(feature = "private_message")]
 use crate::group::framing::PrivateMessage;
 
+#[cfg(feature = "private_message")]
+use super::message_expiry::ExpiringAuthenticatedData;
+
 #[cfg(feature = "by_ref_proposal")]
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
@@ -117,6 +126,8 @@ pub struct StateUpdate {
     pub(crate) custom_proposals: Vec<ProposalInfo<CustomProposal>>,
     #[cfg(feature = "by_ref_proposal")]
     pub(crate) unused_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    pub(crate) group_metadata_change: Option<GroupMetadataChange>,
+    pub(crate) extension_changes: Vec<ExtensionChange>,
 }
 
 #[cfg(not(feature = "state_update"))]
@@ -170,6 +181,61 @@ impl StateUpdate {
     pub fn pending_reinit_ciphersuite(&self) -> Option<CipherSuite> {
         self.pending_reinit
     }
+
+    /// Change of the [`GroupMetadataExt`](crate::extension::group_metadata::GroupMetadataExt)
+    /// of the group made by the commit, if any.
+    pub fn group_metadata_change(&self) -> Option<&GroupMetadataChange> {
+        self.group_metadata_change.as_ref()
+    }
+
+    /// Changes of group context extensions and of the leaf extensions of
+    /// updated members made by the commit.
+    pub fn extension_changes(&self) -> &[ExtensionChange] {
+        &self.extension_changes
+    }
+}
+
+/// Lifecycle state of a [`Group`](crate::group::Group) from the point of view
+/// of the local member.
+///
+/// The current state is available with
+/// [`Group::lifecycle`](crate::group::Group::lifecycle) and the state reached
+/// after processing a commit is returned in
+/// [`CommitMessageDescription::lifecycle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GroupLifecycle {
+    /// The group can be used to send and receive messages.
+    Active,
+    /// A commit was created and is waiting to be applied with
+    /// [`Group::apply_pending_commit`](crate::group::Group::apply_pending_commit)
+    /// or discarded with
+    /// [`Group::clear_pending_commit`](crate::group::Group::clear_pending_commit).
+    /// Creating another commit is not allowed in this state.
+    PendingCommit,
+    /// A [`ReInit`](crate::group::proposal::Proposal::ReInit) proposal was
+    /// committed. The group can only be used to create or join its successor.
+    PendingReInit,
+    /// The local member was removed from the group. The group can no longer
+    /// be used.
+    Removed,
+}
+
+impl GroupLifecycle {
+    /// Whether a new commit can be created in this state.
+    pub fn can_commit(&self) -> bool {
+        matches!(self, Self::Active)
+    }
+
+    /// Whether application messages and proposals can be sent in this state.
+    pub fn can_send(&self) -> bool {
+        matches!(self, Self::Active | Self::PendingCommit)
+    }
+
+    /// Whether the group can still process incoming messages in this state.
+    pub fn can_receive(&self) -> bool {
+        !matches!(self, Self::Removed)
+    }
 }
 
 // #[cfg_attr(
@@ -243,7 +309,7 @@ pub struct ApplicationMessageDescription {
     /// Index of this user in the group state.
     pub sender_index: u32,
     /// Received application data.
-    data: ApplicationData,
+    pub(crate) data: ApplicationData,
     /// Plaintext authenticated data in the received MLS packet.
     pub authenticated_data: Vec<u8>,
 }
@@ -266,13 +332,23 @@ impl ApplicationMessageDescription {
     pub fn data(&self) -> &[u8] {
         self.data.as_bytes()
     }
+
+    /// Expiry bound into the [authenticated data](Self::authenticated_data)
+    /// by the sender, if any.
+    #[cfg(feature = "private_message")]
+    pub fn expires_at(&self) -> Result<Option<MlsTime>, MlsError> {
+        Ok(
+            ExpiringAuthenticatedData::from_bytes(&self.authenticated_data)?
+                .map(|data| data.expires_at()),
+        )
+    }
 }
 
 // #[cfg_attr(
 //     all(feature = "ffi", not(test)),
 //     safer_ffi_gen::ffi_type(clone, opaque)
 // )]
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 #[non_exhaustive]
 /// Description of a processed MLS commit message.
 pub struct CommitMessageDescription {
@@ -284,6 +360,10 @@ pub struct CommitMessageDescription {
     pub state_update: StateUpdate,
     /// Plaintext authenticated data in the received MLS packet.
     pub authenticated_data: Vec<u8>,
+    /// Lifecycle state of the group after this commit.
+    pub lifecycle: GroupLifecycle,
+    /// Statistics about the processing of this commit.
+    pub stats: CommitStats,
 }
 
 impl Debug for CommitMessageDescription {
@@ -296,10 +376,24 @@ impl Debug for CommitMessageDescription {
                 "authenticated_data",
                 &mls_rs_core::debug::pretty_bytes(&self.authenticated_data),
             )
+            .field("lifecycle", &self.lifecycle)
+            .field("stats", &self.stats)
             .finish()
     }
 }
 
+// Statistics describe the local processing of the commit, which differs
+// between the committer and the other members, so they are not compared.
+impl PartialEq for CommitMessageDescription {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_external == other.is_external
+            && self.committer == other.committer
+            && self.state_update == other.state_update
+            && self.authenticated_data == other.authenticated_data
+            && self.lifecycle == other.lifecycle
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Proposal sender type.
 pub enum ProposalSender {
@@ -688,6 +782,25 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .filter_map(|psk| psk.proposal.external_psk_id().cloned())
             .collect::<Vec<_>>();
 
+        let prior_extensions = &self.group_state().context.extensions;
+        let new_extensions = &provisional.group_context.extensions;
+
+        let mut extension_changes = ExtensionChange::between(
+            ExtensionScope::GroupContext,
+            *sender,
+            prior_extensions,
+            new_extensions,
+        );
+
+        extension_changes.extend(updated.iter().flat_map(|update| {
+            ExtensionChange::between(
+                ExtensionScope::Leaf(update.index()),
+                update.index(),
+                &update.prior.extensions,
+                &update.new.extensions,
+            )
+        }));
+
         let roster_update = RosterUpdate::new(added, removed, updated);
 
         let update = StateUpdate {
@@ -705,6 +818,8 @@ pub(crate) trait MessageProcessor: Send + Sync {
             custom_proposals: provisional.applied_proposals.custom_proposals.clone(),
             #[cfg(feature = "by_ref_proposal")]
             unused_proposals: provisional.unused_proposals.clone(),
+            group_metadata_change: GroupMetadataChange::between(prior_extensions, new_extensions)?,
+            extension_changes,
         };
 
         Ok(update)
@@ -719,6 +834,8 @@ pub(crate) trait MessageProcessor: Send + Sync {
             return Err(MlsError::GroupUsedAfterReInit);
         }
 
+        self.observe_commit(&auth_content);
+
         // Update the new GroupContext's confirmed and interim transcript hashes using the new Commit.
         let (interim_transcript_hash, confirmed_transcript_hash) = transcript_hashes(
             self.cipher_suite_provider(),
@@ -763,6 +880,11 @@ pub(crate) trait MessageProcessor: Send + Sync {
 
         let sender = commit_sender(&auth_content.content.sender, &provisional_state)?;
 
+        let mut stats = CommitStats::new(
+            &provisional_state.applied_proposals,
+            commit.path.as_ref().map_or(0, |path| path.nodes.len()),
+        );
+
         #[cfg(feature = "state_update")]
         let mut state_update = self
             .make_state_update(&provisional_state, commit.path.as_ref(), sender)
@@ -788,6 +910,8 @@ pub(crate) trait MessageProcessor: Send + Sync {
                 authenticated_data: auth_content.content.authenticated_data,
                 committer: *sender,
                 state_update,
+                lifecycle: GroupLifecycle::Removed,
+                stats,
             });
         }
 
@@ -814,6 +938,10 @@ pub(crate) trait MessageProcessor: Send + Sync {
             None => Ok(None),
         }?;
 
+        // The path secret is decrypted unless the commit is our own.
+        stats.path_secret_decryptions =
+            usize::from(new_secrets.is_some() && self.self_index() != Some(sender));
+
         // Update the transcript hash to get the new context.
         provisional_state.group_context.confirmed_transcript_hash = confirmed_transcript_hash;
 
@@ -829,8 +957,11 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .tree_hash(self.cipher_suite_provider())
             .await?;
 
+        let mut lifecycle = GroupLifecycle::Active;
+
         if let Some(reinit) = provisional_state.applied_proposals.reinitializations.pop() {
             self.group_state_mut().pending_reinit = Some(reinit.proposal);
+            lifecycle = GroupLifecycle::PendingReInit;
 
             #[cfg(feature = "state_update")]
             {
@@ -853,6 +984,8 @@ pub(crate) trait MessageProcessor: Send + Sync {
                 authenticated_data: auth_content.content.authenticated_data,
                 committer: *sender,
                 state_update,
+                lifecycle,
+                stats,
             })
         } else {
             Err(MlsError::InvalidConfirmationTag)
@@ -866,6 +999,11 @@ pub(crate) trait MessageProcessor: Send + Sync {
     fn cipher_suite_provider(&self) -> &Self::CipherSuiteProvider;
     fn psk_storage(&self) -> Self::PreSharedKeyStorage;
     fn can_continue_processing(&self, provisional_state: &ProvisionalState) -> bool;
+    fn self_index(&self) -> Option<LeafIndex>;
+    fn current_time(&self) -> Option<MlsTime>;
+
+    /// Called with the content of every commit before it is processed.
+    fn observe_commit(&mut self, _auth_content: &AuthenticatedContent) {}
 
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64>;
@@ -877,6 +1015,33 @@ pub(crate) trait MessageProcessor: Send + Sync {
             return Err(MlsError::ProtocolVersionMismatch);
         }
 
+        // Check the parameters of the group context before any signature, so
+        // that a version or cipher suite confusion is reported as such.
+        let parameters = match &message.payload {
+            MlsMessagePayload::KeyPackage(key_package) => {
+                Some((key_package.version, key_package.cipher_suite))
+            }
+            MlsMessagePayload::GroupInfo(group_info)
+                if group_info.group_context.group_id == context.group_id =>
+            {
+                Some((
+                    group_info.group_context.protocol_version,
+                    group_info.group_context.cipher_suite,
+                ))
+            }
+            _ => None,
+        };
+
+        if let Some((version, cipher_suite)) = parameters {
+            if version != context.protocol_version {
+                return Err(MlsError::ProtocolVersionMismatch);
+            }
+
+            if cipher_suite != context.cipher_suite {
+                return Err(MlsError::CipherSuiteMismatch);
+            }
+        }
+
         if let Some((group_id, epoch, content_type)) = match &message.payload {
             MlsMessagePayload::Plain(plaintext) => Some((
                 &plaintext.content.group_id,
@@ -968,7 +1133,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
         let cs = self.cipher_suite_provider();
         let id = self.identity_provider();
 
-        validate_key_package(key_package, version, cs, &id).await
+        validate_key_package(key_package, version, cs, &id, self.current_time()).await
     }
 
     #[cfg(feature = "private_message")]
@@ -1016,16 +1181,10 @@ pub(crate) async fn validate_key_package<C: CipherSuiteProvider, I: IdentityProv
     version: ProtocolVersion,
     cs: &C,
     id: &I,
+    time: Option<MlsTime>,
 ) -> Result<(), MlsError> {
     let validator = LeafNodeValidator::new(cs, id, None);
-
-    #[cfg(feature = "std")]
-    let context = Some(MlsTime::now());
-
-    #[cfg(not(feature = "std"))]
-    let context = None;
-
-    let context = ValidationContext::Add(context);
+    let context = ValidationContext::Add(time);
 
     validator
         .check_if_valid(&key_package.leaf_node, context)
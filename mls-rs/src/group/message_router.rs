@@ -0,0 +1,282 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::client::MlsError;
+
+use super::ApplicationMessageDescription;
+
+const MESSAGE_KIND_LABEL: &[u8] = b"mls-rs message kind";
+
+/// Kind of an application message, chosen by the application, which a
+/// [`MessageRouter`] dispatches messages on.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, MlsSize, MlsEncode, MlsDecode,
+)]
+pub struct MessageKind(u16);
+
+impl MessageKind {
+    pub const fn new(raw_value: u16) -> Self {
+        Self(raw_value)
+    }
+
+    pub const fn raw_value(&self) -> u16 {
+        self.0
+    }
+}
+
+impl From<u16> for MessageKind {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+/// Authenticated data of an application message that carries the
+/// [`MessageKind`] of the message as a prefix.
+///
+/// Like any authenticated data, the kind is covered by the AEAD and the
+/// signature of the sender, so a message can't be routed to another handler
+/// than the one its sender intended.
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[non_exhaustive]
+pub struct RoutedAuthenticatedData {
+    /// Kind of the message.
+    pub kind: MessageKind,
+    /// Authenticated data provided by the application.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub authenticated_data: Vec<u8>,
+}
+
+impl RoutedAuthenticatedData {
+    pub fn new(kind: MessageKind, authenticated_data: Vec<u8>) -> Self {
+        Self {
+            kind,
+            authenticated_data,
+        }
+    }
+
+    /// Serialize to the authenticated data of a message.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        let mut bytes = MESSAGE_KIND_LABEL.to_vec();
+        self.mls_encode(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parse the authenticated data of a message, returning `None` if it
+    /// carries no message kind.
+    pub fn from_bytes(authenticated_data: &[u8]) -> Result<Option<Self>, MlsError> {
+        let Some(mut data) = authenticated_data.strip_prefix(MESSAGE_KIND_LABEL) else {
+            return Ok(None);
+        };
+
+        Self::mls_decode(&mut data).map(Some).map_err(Into::into)
+    }
+}
+
+/// Application message dispatched by a [`MessageRouter`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RoutedMessage {
+    /// Kind of the message, or `None` if its authenticated data carries no
+    /// kind.
+    pub kind: Option<MessageKind>,
+    /// Authenticated data provided by the application, without the kind.
+    pub authenticated_data: Vec<u8>,
+    /// The decrypted message, with its full authenticated data.
+    pub message: ApplicationMessageDescription,
+}
+
+type Handler<T> = Box<dyn FnMut(RoutedMessage) -> T + Send>;
+
+/// Dispatcher of decrypted application messages to handlers registered for
+/// their [`MessageKind`].
+///
+/// Senders tag their messages by passing the serialized
+/// [`RoutedAuthenticatedData`] as the authenticated data of
+/// [`Group::encrypt_application_message`](crate::Group::encrypt_application_message).
+/// Receivers pass the messages returned by
+/// [`Group::process_incoming_message`](crate::Group::process_incoming_message)
+/// to [`MessageRouter::route`], which calls the handler registered for the
+/// kind of the message. Messages of a kind no handler is registered for, and
+/// messages carrying no kind, are handed to the fallback handler, if any.
+///
+/// Handlers return a value of type `T`, for instance a `Result` to report
+/// the errors of the application.
+pub struct MessageRouter<T> {
+    handlers: BTreeMap<MessageKind, Handler<T>>,
+    fallback: Option<Handler<T>>,
+}
+
+impl<T> Debug for MessageRouter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageRouter")
+            .field("kinds", &self.handlers.keys())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl<T> Default for MessageRouter<T> {
+    fn default() -> Self {
+        Self {
+            handlers: Default::default(),
+            fallback: None,
+        }
+    }
+}
+
+impl<T> MessageRouter<T> {
+    /// Create a router with no handler.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register `handler` for the messages of kind `kind`, replacing the
+    /// handler previously registered for it, if any.
+    pub fn with_handler<F>(mut self, kind: MessageKind, handler: F) -> Self
+    where
+        F: FnMut(RoutedMessage) -> T + Send + 'static,
+    {
+        self.handlers.insert(kind, Box::new(handler));
+        self
+    }
+
+    /// Register `handler` for the messages no other handler is registered
+    /// for.
+    pub fn with_fallback<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(RoutedMessage) -> T + Send + 'static,
+    {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Dispatch `message` to the handler of its kind, returning the value
+    /// returned by the handler, or `None` if no handler applies to it.
+    ///
+    /// An error is returned if the authenticated data of `message` starts
+    /// with a message kind that can't be parsed.
+    pub fn route(&mut self, message: ApplicationMessageDescription) -> Result<Option<T>, MlsError> {
+        let data = RoutedAuthenticatedData::from_bytes(&message.authenticated_data)?;
+
+        let (kind, authenticated_data) = match data {
+            Some(data) => (Some(data.kind), data.authenticated_data),
+            None => (None, message.authenticated_data.clone()),
+        };
+
+        let handler = kind
+            .and_then(|kind| self.handlers.get_mut(&kind))
+            .or(self.fallback.as_mut());
+
+        let Some(handler) = handler else {
+            return Ok(None);
+        };
+
+        Ok(Some(handler(RoutedMessage {
+            kind,
+            authenticated_data,
+            message,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            test_utils::{test_n_member_group, TestGroup},
+            ApplicationMessageDescription, ReceivedMessage,
+        },
+    };
+
+    use super::{MessageKind, MessageRouter, RoutedAuthenticatedData, MESSAGE_KIND_LABEL};
+
+    const CHAT: MessageKind = MessageKind::new(1);
+    const TYPING: MessageKind = MessageKind::new(2);
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn send_and_receive(
+        groups: &mut [TestGroup],
+        authenticated_data: Vec<u8>,
+    ) -> ApplicationMessageDescription {
+        let message = groups[0]
+            .group
+            .encrypt_application_message(b"hello", authenticated_data)
+            .await
+            .unwrap();
+
+        match groups[1].process_message(message).await.unwrap() {
+            ReceivedMessage::ApplicationMessage(description) => description,
+            _ => panic!("expected an application message"),
+        }
+    }
+
+    #[test]
+    fn kind_round_trips_through_authenticated_data() {
+        let data = RoutedAuthenticatedData::new(CHAT, b"aad".to_vec());
+        let bytes = data.to_bytes().unwrap();
+
+        assert_eq!(
+            RoutedAuthenticatedData::from_bytes(&bytes).unwrap(),
+            Some(data)
+        );
+
+        assert_eq!(RoutedAuthenticatedData::from_bytes(b"aad").unwrap(), None);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn messages_are_routed_by_kind() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let mut router = MessageRouter::new()
+            .with_handler(CHAT, |m| ("chat", m.authenticated_data))
+            .with_handler(TYPING, |m| ("typing", m.authenticated_data))
+            .with_fallback(|m| ("fallback", m.authenticated_data));
+
+        let aad = RoutedAuthenticatedData::new(TYPING, b"aad".to_vec());
+        let message = send_and_receive(&mut groups, aad.to_bytes().unwrap()).await;
+
+        assert_eq!(
+            router.route(message).unwrap(),
+            Some(("typing", b"aad".to_vec()))
+        );
+
+        let aad = RoutedAuthenticatedData::new(MessageKind::new(3), vec![]);
+        let message = send_and_receive(&mut groups, aad.to_bytes().unwrap()).await;
+        assert_eq!(router.route(message).unwrap(), Some(("fallback", vec![])));
+
+        let message = send_and_receive(&mut groups, b"untagged".to_vec()).await;
+
+        assert_eq!(
+            router.route(message).unwrap(),
+            Some(("fallback", b"untagged".to_vec()))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unknown_kind_without_fallback_is_not_routed() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        let mut router = MessageRouter::new().with_handler(CHAT, |m| m.kind);
+
+        let aad = RoutedAuthenticatedData::new(TYPING, vec![]);
+        let message = send_and_receive(&mut groups, aad.to_bytes().unwrap()).await;
+        assert_eq!(router.route(message).unwrap(), None);
+
+        let message = send_and_receive(&mut groups, MESSAGE_KIND_LABEL.to_vec()).await;
+        assert_matches!(router.route(message), Err(MlsError::SerializationError(_)));
+    }
+}
@@ -150,7 +150,7 @@ fn signing_identity_for_sender(
     }
 }
 
-fn signing_identity_for_member(
+pub(crate) fn signing_identity_for_member(
     signature_keys_container: SignaturePublicKeysContainer,
     leaf_index: LeafIndex,
 ) -> Result<SignaturePublicKey, MlsError> {
@@ -14,12 +14,13 @@ use crate::cipher_suite::CipherSuite;
 use crate::client::MlsError;
 use crate::client_config::ClientConfig;
 use crate::crypto::{HpkeCiphertext, SignatureSecretKey};
-use crate::extension::RatchetTreeExt;
+use crate::extension::{group_policy::GroupPolicyExt, LastResortExt, RatchetTreeExt};
 use crate::identity::SigningIdentity;
 use crate::key_package::{KeyPackage, KeyPackageRef};
 use crate::protocol_version::ProtocolVersion;
 use crate::psk::secret::PskSecret;
 use crate::psk::PreSharedKeyID;
+use crate::security_event::{SecurityEvent, SecurityEventCode};
 use crate::signer::Signable;
 use crate::tree_kem::hpke_encryption::HpkeEncryptable;
 use crate::tree_kem::kem::TreeKem;
@@ -37,20 +38,38 @@ use crate::{CipherSuiteProvider, CryptoProvider};
 #[cfg(feature = "by_ref_proposal")]
 use crate::crypto::{HpkePublicKey, HpkeSecretKey};
 
+#[cfg(feature = "by_ref_proposal")]
+use crate::identity::refresh::CredentialRefresher;
+
+#[cfg(feature = "by_ref_proposal")]
+use core::time::Duration;
+
 use crate::extension::ExternalPubExt;
 
 #[cfg(feature = "private_message")]
 use self::mls_rules::{EncryptionOptions, MlsRules};
 
+#[cfg(feature = "private_message")]
+use crate::extension::send_permissions::{check_application_sender, check_send_permission};
+
+#[cfg(feature = "psk")]
+pub use self::resumption::{LostGroup, ReinitBridge, ReinitClient};
+
 #[cfg(feature = "psk")]
-pub use self::resumption::ReinitClient;
+pub use recovery::{GroupRecovery, RecoveryPolicy};
 
 #[cfg(feature = "psk")]
 use crate::psk::{
     resolver::PskResolver, secret::PskSecretInput, ExternalPskId, JustPreSharedKeyID, PskGroupId,
-    ResumptionPSKUsage, ResumptionPsk,
+    PskUsage, ResumptionPSKUsage, ResumptionPsk, UsedPsk,
 };
 
+#[cfg(feature = "psk")]
+use alloc::collections::VecDeque;
+
+#[cfg(feature = "psk")]
+const MAX_PSK_USAGE_HISTORY: usize = 64;
+
 #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
 use std::collections::HashMap;
 
@@ -84,21 +103,58 @@ use secret_tree::*;
 #[cfg(feature = "prior_epoch")]
 use self::epoch::PriorEpoch;
 
+#[cfg(feature = "prior_epoch")]
+pub use self::epoch::EpochPurgeSummary;
 use self::epoch::EpochSecrets;
 pub use self::message_processor::{
-    ApplicationMessageDescription, CommitMessageDescription, ProposalMessageDescription,
-    ProposalSender, ReceivedMessage, StateUpdate,
+    ApplicationMessageDescription, CommitMessageDescription, GroupLifecycle,
+    ProposalMessageDescription, ProposalSender, ReceivedMessage, StateUpdate,
 };
 use self::message_processor::{EventOrContent, MessageProcessor, ProvisionalState};
 #[cfg(feature = "by_ref_proposal")]
 use self::proposal_ref::ProposalRef;
 use self::state_repo::GroupStateRepository;
 pub use group_info::GroupInfo;
+pub use identity_consistency::{IdentityConsistencyReport, IdentityObservation};
+pub use integrity::{CorruptionRecovery, StateCorruption};
+pub use member_envelope::MemberEnvelope;
+pub use membership_proof::MembershipProof;
+
+#[cfg(feature = "by_ref_proposal")]
+pub use external_notice::ExternalNotice;
+
+#[cfg(feature = "by_ref_proposal")]
+pub use external_removal::{ExternalRemoval, ExternalRemovalPolicy};
+
+#[cfg(feature = "private_message")]
+pub use message_expiry::{ExpiringAuthenticatedData, ExpiringMessage, MessageExpiryPolicy};
+
+#[cfg(feature = "private_message")]
+pub use message_router::{MessageKind, MessageRouter, RoutedAuthenticatedData, RoutedMessage};
+
+#[cfg(feature = "private_message")]
+pub use sequence::{MessageSequencer, SequencedAuthenticatedData, SequencedMessage};
 
 pub use self::framing::{ContentType, Sender};
+pub use archive::{ArchivedEpoch, ArchivedMessage, GroupArchive, GROUP_ARCHIVE_VERSION};
 pub use commit::*;
+pub use commit_evaluation::CommitEvaluation;
+pub use commit_retry::{
+    CommitDelivery, CommitDeliveryResult, CommitRaceStats, CommitRetryOutput, CommitRetryPolicy,
+};
+pub use commit_stats::CommitStats;
 pub use context::GroupContext;
+pub use create_options::CreateGroupOptions;
+#[cfg(feature = "state_update")]
+pub use extension_change::{ExtensionChange, ExtensionScope};
+pub use recording::{GroupTranscript, TranscriptEntry};
 pub use roster::*;
+pub use session_key::{SessionKey, SessionKeyId, SessionKeyRing};
+pub use state_check::{GroupArtifact, StateDiagnosis, StateDigest};
+#[cfg(feature = "by_ref_proposal")]
+pub use tree_attestation::{LeafSignatureVerification, TreeAttestation};
+pub use users::{User, UserRosterUpdate};
+pub use verification_code::VerificationCode;
 
 pub(crate) use transcript_hash::ConfirmedTranscriptHash;
 pub(crate) use util::*;
@@ -107,21 +163,41 @@ pub(crate) use util::*;
 pub use self::message_processor::CachedProposal;
 
 #[cfg(feature = "private_message")]
+mod archive;
 mod ciphertext_processor;
 
 mod commit;
+mod commit_evaluation;
+mod commit_retry;
+mod commit_stats;
 pub(crate) mod confirmation_tag;
 mod context;
+mod create_options;
 pub(crate) mod epoch;
+#[cfg(feature = "state_update")]
+mod extension_change;
+#[cfg(feature = "by_ref_proposal")]
+mod external_notice;
+#[cfg(feature = "by_ref_proposal")]
+pub(crate) mod external_removal;
 pub(crate) mod framing;
 mod group_info;
+mod identity_consistency;
+mod integrity;
 pub(crate) mod key_schedule;
+#[cfg(feature = "custom_proposal")]
+pub mod key_value;
+mod member_envelope;
+mod membership_proof;
 mod membership_tag;
+#[cfg(feature = "private_message")]
+pub(crate) mod message_expiry;
 pub(crate) mod message_processor;
+#[cfg(feature = "private_message")]
+mod message_router;
 pub(crate) mod message_signature;
 pub(crate) mod message_verifier;
 pub mod mls_rules;
-#[cfg(feature = "private_message")]
 pub(crate) mod padding;
 /// Proposals to evolve a MLS [`Group`]
 pub mod proposal;
@@ -129,21 +205,34 @@ mod proposal_cache;
 pub(crate) mod proposal_filter;
 #[cfg(feature = "by_ref_proposal")]
 pub(crate) mod proposal_ref;
+mod recording;
+#[cfg(feature = "psk")]
+pub(crate) mod recovery;
 #[cfg(feature = "psk")]
 mod resumption;
 mod roster;
+#[cfg(feature = "private_message")]
+mod sequence;
+mod session_key;
 pub(crate) mod snapshot;
 pub(crate) mod state;
+mod state_check;
 
 #[cfg(feature = "prior_epoch")]
 pub(crate) mod state_repo;
 #[cfg(not(feature = "prior_epoch"))]
 pub(crate) mod state_repo_light;
+#[cfg(feature = "prior_epoch")]
+mod tree_checkpoint;
 #[cfg(not(feature = "prior_epoch"))]
 pub(crate) use state_repo_light as state_repo;
 
 pub(crate) mod transcript_hash;
+#[cfg(feature = "by_ref_proposal")]
+mod tree_attestation;
+mod users;
 mod util;
+mod verification_code;
 
 /// External commit building.
 pub mod external_commit;
@@ -161,6 +250,10 @@ mod exported_tree;
 
 pub use exported_tree::ExportedTree;
 
+pub use crate::tree_kem::parent_hash::{
+    ChildSide, ParentHashRule, ParentHashViolation, ValidationReport,
+};
+
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 struct GroupSecrets {
     joiner_secret: JoinerSecret,
@@ -196,6 +289,20 @@ pub(crate) struct Welcome {
     pub encrypted_group_info: Vec<u8>,
 }
 
+/// Sizes in bytes of the parts of a welcome message, as returned by
+/// [`MlsMessage::welcome_sizes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WelcomeSizes {
+    /// Size of the encrypted group info, shared by all recipients.
+    pub encrypted_group_info: usize,
+    /// Size of the group secrets encrypted to each recipient, in the order of
+    /// [`MlsMessage::welcome_key_package_references`].
+    pub secrets: Vec<usize>,
+    /// Size of the encoded message.
+    pub total: usize,
+}
+
 impl Debug for Welcome {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Welcome")
@@ -270,8 +377,17 @@ where
     #[cfg(all(not(feature = "std"), feature = "by_ref_proposal"))]
     pending_updates: Vec<(HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>))>,
     pending_commit: Option<CommitGeneration>,
+    pending_commit_artifacts: Option<CommitArtifacts>,
+    reissuable_welcomes: Vec<MlsMessage>,
+    pub(crate) removed: bool,
+    transcript: Option<GroupTranscript>,
+    observed_commits: Option<Vec<AuthenticatedContent>>,
+    #[cfg(feature = "by_ref_proposal")]
+    external_removal_deadline: Option<(u64, MlsTime)>,
     #[cfg(feature = "psk")]
     previous_psk: Option<PskSecretInput>,
+    #[cfg(feature = "psk")]
+    psk_usage: VecDeque<PskUsage>,
     #[cfg(test)]
     pub(crate) commit_modifiers: CommitModifiers,
     pub(crate) signer: SignatureSecretKey,
@@ -347,6 +463,10 @@ where
             None,
         )?;
 
+        #[cfg(feature = "prior_epoch")]
+        let state_repo =
+            state_repo.with_tree_checkpoint_interval(config.tree_checkpoint_interval());
+
         let key_schedule_result = KeySchedule::from_random_epoch_secret(
             &cipher_suite_provider,
             #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
@@ -376,6 +496,13 @@ where
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: Default::default(),
             pending_commit: None,
+            pending_commit_artifacts: None,
+            reissuable_welcomes: Vec::new(),
+            removed: false,
+            transcript: None,
+            observed_commits: None,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline: None,
             #[cfg(test)]
             commit_modifiers: Default::default(),
             epoch_secrets: key_schedule_result.epoch_secrets,
@@ -383,6 +510,8 @@ where
             cipher_suite_provider,
             #[cfg(feature = "psk")]
             previous_psk: None,
+            #[cfg(feature = "psk")]
+            psk_usage: Default::default(),
             signer,
         })
     }
@@ -401,17 +530,20 @@ where
             signer,
             #[cfg(feature = "psk")]
             None,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
         )
         .await
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn from_welcome_message(
+    pub(crate) async fn from_welcome_message(
         welcome: &MlsMessage,
         tree_data: Option<ExportedTree<'_>>,
         config: C,
         signer: SignatureSecretKey,
         #[cfg(feature = "psk")] additional_psk: Option<PskSecretInput>,
+        #[cfg(feature = "by_ref_proposal")] tree_attestation: Option<&TreeAttestation>,
     ) -> Result<(Self, NewMemberInfo), MlsError> {
         let protocol_version = welcome.version;
 
@@ -448,6 +580,11 @@ where
         )
         .await?;
 
+        #[cfg(feature = "psk")]
+        for psk_id in &group_secrets.psks {
+            psk_id.psk_nonce.validate(&cipher_suite_provider)?;
+        }
+
         #[cfg(feature = "psk")]
         let psk_secret = if let Some(psk) = additional_psk {
             let psk_id = group_secrets
@@ -500,12 +637,20 @@ where
 
         let group_info = GroupInfo::mls_decode(&mut &**decrypted_group_info)?;
 
+        #[cfg(feature = "by_ref_proposal")]
+        let tree_attestation = tree_attestation.filter(|_| {
+            config.leaf_signature_verification()
+                == LeafSignatureVerification::SkipForTreesAttestedByExternalSender
+        });
+
         let public_tree = validate_group_info_joiner(
             protocol_version,
             &group_info,
             tree_data,
             &config.identity_provider(),
             &cipher_suite_provider,
+            #[cfg(feature = "by_ref_proposal")]
+            tree_attestation,
         )
         .await?;
 
@@ -517,7 +662,12 @@ where
             .find_leaf_node(&key_package_generation.key_package.leaf_node)
             .ok_or(MlsError::WelcomeKeyPackageNotFound)?;
 
-        let used_key_package_ref = key_package_generation.reference;
+        // Last resort key packages are kept to be used again.
+        let used_key_package_ref = (!key_package_generation
+            .key_package
+            .extensions
+            .has_extension(LastResortExt::EXTENSION_TYPE))
+        .then_some(key_package_generation.reference);
 
         let mut private_tree =
             TreeKemPrivate::new_self_leaf(self_index, key_package_generation.leaf_node_secret_key);
@@ -560,17 +710,23 @@ where
             return Err(MlsError::InvalidConfirmationTag);
         }
 
-        Self::join_with(
+        #[allow(unused_mut)]
+        let (mut group, new_member_info) = Self::join_with(
             config,
             group_info,
             public_tree,
             key_schedule_result.key_schedule,
             key_schedule_result.epoch_secrets,
             private_tree,
-            Some(used_key_package_ref),
+            used_key_package_ref,
             signer,
         )
-        .await
+        .await?;
+
+        #[cfg(feature = "psk")]
+        group.record_psk_usage(&group_secrets.psks);
+
+        Ok((group, new_member_info))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -609,6 +765,10 @@ where
             used_key_package_ref,
         )?;
 
+        #[cfg(feature = "prior_epoch")]
+        let state_repo =
+            state_repo.with_tree_checkpoint_interval(config.tree_checkpoint_interval());
+
         let group = Group {
             config,
             state: GroupState::new(
@@ -622,6 +782,13 @@ where
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: Default::default(),
             pending_commit: None,
+            pending_commit_artifacts: None,
+            reissuable_welcomes: Vec::new(),
+            removed: false,
+            transcript: None,
+            observed_commits: None,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline: None,
             #[cfg(test)]
             commit_modifiers: Default::default(),
             epoch_secrets,
@@ -629,6 +796,8 @@ where
             cipher_suite_provider: cs,
             #[cfg(feature = "psk")]
             previous_psk: None,
+            #[cfg(feature = "psk")]
+            psk_usage: Default::default(),
             signer,
         };
 
@@ -647,6 +816,31 @@ where
         self.context().epoch
     }
 
+    /// Delete the prior epochs older than `epoch` from storage, destroying
+    /// their secrets.
+    ///
+    /// The state of the group is written to storage first. Messages sent in
+    /// the deleted epochs can no longer be decrypted, which allows
+    /// applications to enforce message retention policies at the key level.
+    /// The returned summary lists the deleted epochs. This fails with
+    /// [`MlsError::EpochDeletionUnsupported`] if the
+    /// [`GroupStateStorage`](crate::GroupStateStorage) in use doesn't
+    /// implement
+    /// [`delete_epochs_before`](crate::GroupStateStorage::delete_epochs_before).
+    #[cfg(feature = "prior_epoch")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn purge_epochs_before(&mut self, epoch: u64) -> Result<EpochPurgeSummary, MlsError> {
+        if epoch > self.current_epoch() {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        self.write_to_storage().await?;
+
+        let epoch_ids = self.state_repo.delete_epochs_before(epoch).await?;
+
+        Ok(EpochPurgeSummary { epoch_ids })
+    }
+
     /// Index within the group's state for the local group instance.
     ///
     /// This index corresponds to indexes in content descriptions within
@@ -656,7 +850,7 @@ where
         self.private_tree.self_index.0
     }
 
-    fn current_user_leaf_node(&self) -> Result<&LeafNode, MlsError> {
+    pub(crate) fn current_user_leaf_node(&self) -> Result<&LeafNode, MlsError> {
         self.current_epoch_tree()
             .get_leaf_node(self.private_tree.self_index)
     }
@@ -687,6 +881,10 @@ where
         proposal: Proposal,
         authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
         let sender = Sender::Member(*self.private_tree.self_index);
 
         let auth_content = AuthenticatedContent::new_signed(
@@ -897,6 +1095,55 @@ where
         self.proposal_message(proposal, authenticated_data).await
     }
 
+    /// Create a proposal message that updates your own public keys as well
+    /// as your credential, if your credential expires within `threshold` of
+    /// `time` according to `refresher`.
+    ///
+    /// The new credential is obtained from
+    /// [`CredentialRefresher::refresh`](crate::identity::refresh::CredentialRefresher::refresh)
+    /// and proposed as with [`Group::propose_update_with_identity`].
+    /// Returns `None` if your credential doesn't need to be refreshed yet.
+    ///
+    /// `authenticated_data` will be sent unencrypted along with the contents
+    /// of the proposal message.
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn propose_credential_refresh<R>(
+        &mut self,
+        refresher: &R,
+        time: MlsTime,
+        threshold: Duration,
+        authenticated_data: Vec<u8>,
+    ) -> Result<Option<MlsMessage>, MlsError>
+    where
+        R: CredentialRefresher,
+    {
+        let current = self.current_member_signing_identity()?;
+
+        let expiration = refresher
+            .expiration(current)
+            .map_err(MlsError::CredentialRefreshError)?;
+
+        let expiring = expiration.map_or(false, |expiration| {
+            time.seconds_since_epoch()
+                .saturating_add(threshold.as_secs())
+                >= expiration.seconds_since_epoch()
+        });
+
+        if !expiring {
+            return Ok(None);
+        }
+
+        let (signing_identity, signer) = refresher
+            .refresh(current)
+            .await
+            .map_err(MlsError::CredentialRefreshError)?;
+
+        self.propose_update_with_identity(signer, signing_identity, authenticated_data)
+            .await
+            .map(Some)
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn update_proposal(
@@ -1170,6 +1417,10 @@ where
         message: &[u8],
         authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
         // A group member that has observed one or more proposals within an epoch MUST send a Commit message
         // before sending application data
         #[cfg(feature = "by_ref_proposal")]
@@ -1177,6 +1428,15 @@ where
             return Err(MlsError::CommitRequired);
         }
 
+        check_send_permission(
+            &self.context().extensions,
+            *self.private_tree.self_index,
+            &self
+                .current_user_leaf_node()?
+                .signing_identity
+                .signature_key,
+        )?;
+
         let auth_content = AuthenticatedContent::new_signed(
             &self.cipher_suite_provider,
             self.context(),
@@ -1191,6 +1451,26 @@ where
         self.format_for_wire(auth_content).await
     }
 
+    /// Encrypt an application message that expires at `expires_at`.
+    ///
+    /// The expiry is bound into the authenticated data of the message along
+    /// with `authenticated_data`, see [`ExpiringAuthenticatedData`]. It is
+    /// enforced by receivers having a [`MessageExpiryPolicy`].
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn encrypt_application_message_with_expiry(
+        &mut self,
+        message: &[u8],
+        expires_at: MlsTime,
+        authenticated_data: Vec<u8>,
+    ) -> Result<MlsMessage, MlsError> {
+        let authenticated_data =
+            ExpiringAuthenticatedData::new(expires_at, authenticated_data).to_bytes()?;
+
+        self.encrypt_application_message(message, authenticated_data)
+            .await
+    }
+
     #[cfg(feature = "private_message")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn decrypt_incoming_ciphertext(
@@ -1214,6 +1494,12 @@ where
             )
             .await?;
 
+            check_application_sender(
+                &self.context().extensions,
+                SignaturePublicKeysContainer::RatchetTree(&self.state.public_tree),
+                &content,
+            )?;
+
             Ok::<_, MlsError>(content)
         } else {
             #[cfg(feature = "prior_epoch")]
@@ -1238,6 +1524,12 @@ where
                 )
                 .await?;
 
+                check_application_sender(
+                    &epoch.context.extensions,
+                    SignaturePublicKeysContainer::List(&epoch.signature_public_keys),
+                    &content,
+                )?;
+
                 Ok(content)
             }
 
@@ -1248,6 +1540,73 @@ where
         Ok(auth_content)
     }
 
+    /// Decrypt an application message sent in a prior epoch without
+    /// modifying the group.
+    ///
+    /// The epoch of the message is read from the group state storage, or
+    /// from the epochs not yet written to it, and only a copy of it is used.
+    /// This allows indexing or archiving stored history while the group is
+    /// shared behind a read lock. As a consequence, the keys used for
+    /// decryption are not deleted and the message can be decrypted again.
+    ///
+    /// Messages from the current epoch must be processed with
+    /// [`Group::process_incoming_message`], which returns
+    /// [`MlsError::EpochNotFound`] here.
+    #[cfg(all(feature = "private_message", feature = "prior_epoch"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn decrypt_historic(
+        &self,
+        message: MlsMessage,
+    ) -> Result<ApplicationMessageDescription, MlsError> {
+        let MlsMessagePayload::Cipher(ciphertext) = message.payload else {
+            return Err(MlsError::UnexpectedMessageType);
+        };
+
+        if ciphertext.group_id != self.context().group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        let mut epoch = self
+            .state_repo
+            .get_epoch(ciphertext.epoch)
+            .await?
+            .ok_or(MlsError::EpochNotFound)?;
+
+        let auth_content = CiphertextProcessor::new(&mut epoch, self.cipher_suite_provider.clone())
+            .open(&ciphertext)
+            .await?;
+
+        verify_auth_content_signature(
+            &self.cipher_suite_provider,
+            SignaturePublicKeysContainer::List(&epoch.signature_public_keys),
+            &epoch.context,
+            &auth_content,
+            #[cfg(feature = "by_ref_proposal")]
+            &[],
+        )
+        .await?;
+
+        check_application_sender(
+            &epoch.context.extensions,
+            SignaturePublicKeysContainer::List(&epoch.signature_public_keys),
+            &auth_content,
+        )?;
+
+        let Content::Application(data) = auth_content.content.content else {
+            return Err(MlsError::UnexpectedMessageType);
+        };
+
+        let Sender::Member(sender_index) = auth_content.content.sender else {
+            return Err(MlsError::InvalidSender);
+        };
+
+        Ok(ApplicationMessageDescription {
+            sender_index,
+            data,
+            authenticated_data: auth_content.content.authenticated_data,
+        })
+    }
+
     /// Apply a pending commit that was created by [`Group::commit`] or
     /// [`CommitBuilder::build`].
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -1257,7 +1616,16 @@ where
             .clone()
             .ok_or(MlsError::PendingCommitNotFound)?;
 
-        self.process_commit(pending_commit.content, None).await
+        let welcome_messages = self
+            .pending_commit_artifacts
+            .as_ref()
+            .map(|artifacts| artifacts.welcome_messages.clone());
+
+        let description = self.process_commit(pending_commit.content, None).await?;
+
+        self.reissuable_welcomes = welcome_messages.unwrap_or_default();
+
+        Ok(description)
     }
 
     /// Returns true if a commit has been created but not yet applied
@@ -1266,13 +1634,56 @@ where
         self.pending_commit.is_some()
     }
 
+    /// Messages created by the pending commit, each of which can be sent
+    /// separately.
+    ///
+    /// The artifacts are kept in memory only. They are not part of the
+    /// group state written by [`Group::write_to_storage`], so this returns
+    /// `None` for a pending commit restored from storage, as well as when
+    /// there is no pending commit.
+    pub fn pending_commit_artifacts(&self) -> Option<&CommitArtifacts> {
+        self.pending_commit
+            .as_ref()
+            .and(self.pending_commit_artifacts.as_ref())
+    }
+
+    /// Welcome message for the member added with the key package referenced
+    /// by `key_package_ref`, to send again if the original one was lost.
+    ///
+    /// Only the welcome messages of the commit that started the current
+    /// epoch are available, and only if it was created and applied by this
+    /// member. They are part of the group state written by
+    /// [`Group::write_to_storage`]. If the ratchet tree extension isn't used,
+    /// the new member also needs the tree given by [`Group::export_tree`].
+    pub fn reissue_welcome(&self, key_package_ref: &KeyPackageRef) -> Result<MlsMessage, MlsError> {
+        self.reissuable_welcomes
+            .iter()
+            .flat_map(MlsMessage::split_welcome)
+            .find(|welcome| welcome.welcome_key_package_references() == [key_package_ref])
+            .ok_or(MlsError::WelcomeKeyPackageNotFound)
+    }
+
+    /// Current lifecycle state of the group.
+    pub fn lifecycle(&self) -> GroupLifecycle {
+        if self.removed {
+            GroupLifecycle::Removed
+        } else if self.state.pending_reinit.is_some() {
+            GroupLifecycle::PendingReInit
+        } else if self.pending_commit.is_some() {
+            GroupLifecycle::PendingCommit
+        } else {
+            GroupLifecycle::Active
+        }
+    }
+
     /// Clear the currently pending commit.
     ///
     /// This function will automatically be called in the event that a
     /// commit message is processed using [`Group::process_incoming_message`]
     /// before [`Group::apply_pending_commit`] is called.
     pub fn clear_pending_commit(&mut self) {
-        self.pending_commit = None
+        self.pending_commit = None;
+        self.pending_commit_artifacts = None;
     }
 
     /// Process an inbound message for this group.
@@ -1289,6 +1700,22 @@ where
         &mut self,
         message: MlsMessage,
     ) -> Result<ReceivedMessage, MlsError> {
+        let received_at = self.config.current_time();
+        self.record(&message, None, received_at);
+
+        self.process_incoming_message_at(message, received_at).await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn process_incoming_message_at(
+        &mut self,
+        message: MlsMessage,
+        received_at: Option<MlsTime>,
+    ) -> Result<ReceivedMessage, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
         if let Some(pending) = &self.pending_commit {
             let message_hash = CommitHash::compute(&self.cipher_suite_provider, &message).await?;
 
@@ -1299,13 +1726,23 @@ where
             }
         }
 
-        MessageProcessor::process_incoming_message(
+        let (message_epoch, wire_format) = (message.epoch(), message.wire_format());
+
+        let res = MessageProcessor::process_incoming_message(
             self,
             message,
             #[cfg(feature = "by_ref_proposal")]
             true,
         )
-        .await
+        .await;
+
+        #[cfg(feature = "private_message")]
+        let res =
+            res.and_then(|message| self.check_message_expiry(message, message_epoch, received_at));
+
+        self.after_processing(&res, message_epoch, wire_format, received_at);
+
+        res
     }
 
     /// Process an inbound message for this group, providing additional context
@@ -1330,14 +1767,185 @@ where
         message: MlsMessage,
         time: MlsTime,
     ) -> Result<ReceivedMessage, MlsError> {
-        MessageProcessor::process_incoming_message_with_time(
+        self.record(&message, Some(time), Some(time));
+
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
+        let (message_epoch, wire_format) = (message.epoch(), message.wire_format());
+
+        let res = MessageProcessor::process_incoming_message_with_time(
             self,
             message,
             #[cfg(feature = "by_ref_proposal")]
             true,
             Some(time),
         )
-        .await
+        .await;
+
+        #[cfg(feature = "private_message")]
+        let res =
+            res.and_then(|message| self.check_message_expiry(message, message_epoch, Some(time)));
+
+        self.after_processing(&res, message_epoch, wire_format, Some(time));
+
+        res
+    }
+
+    #[cfg(feature = "private_message")]
+    fn check_message_expiry(
+        &self,
+        message: ReceivedMessage,
+        message_epoch: Option<u64>,
+        time: Option<MlsTime>,
+    ) -> Result<ReceivedMessage, MlsError> {
+        let ReceivedMessage::ApplicationMessage(description) = &message else {
+            return Ok(message);
+        };
+
+        let Some(policy) = self.config.message_expiry_policy() else {
+            return Ok(message);
+        };
+
+        let Some(expires_at) = description.expires_at()? else {
+            return Ok(message);
+        };
+
+        let expiring = ExpiringMessage {
+            group_id: self.group_id(),
+            // Application messages always have an epoch, which can be a
+            // prior one.
+            epoch: message_epoch.unwrap_or_else(|| self.current_epoch()),
+            sender_index: description.sender_index,
+            expires_at,
+            received_at: time,
+        };
+
+        if policy.is_expired(&expiring) {
+            return Err(MlsError::MessageExpired);
+        }
+
+        Ok(message)
+    }
+
+    fn after_processing(
+        &mut self,
+        res: &Result<ReceivedMessage, MlsError>,
+        message_epoch: Option<u64>,
+        wire_format: WireFormat,
+        #[cfg_attr(not(feature = "by_ref_proposal"), allow(unused))] time: Option<MlsTime>,
+    ) {
+        match res {
+            Ok(ReceivedMessage::Commit(description)) => {
+                self.removed = description.lifecycle == GroupLifecycle::Removed;
+            }
+            #[cfg(feature = "by_ref_proposal")]
+            Ok(ReceivedMessage::Proposal(description)) => {
+                self.apply_external_removal_policy(description, time)
+            }
+            Err(error) => self.report_security_event(error, message_epoch, wire_format),
+            _ => {}
+        }
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn apply_external_removal_policy(
+        &mut self,
+        description: &ProposalMessageDescription,
+        time: Option<MlsTime>,
+    ) {
+        let (ProposalSender::External(sender_index), Proposal::Remove(remove)) =
+            (&description.sender, &description.proposal)
+        else {
+            return;
+        };
+
+        let Some(policy) = self.config.external_removal_policy() else {
+            return;
+        };
+
+        let Some(removed) = self.member_at_index(remove.to_remove()) else {
+            return;
+        };
+
+        let removal = ExternalRemoval {
+            group_id: self.group_id(),
+            epoch: self.current_epoch(),
+            sender_index: *sender_index,
+            removed,
+        };
+
+        let Some(delay) = policy.commit_delay(&removal) else {
+            return;
+        };
+
+        let received = time.map_or(0, |t| t.seconds_since_epoch());
+        let deadline = MlsTime::from(received.saturating_add(delay.as_secs()));
+
+        let deadline = match self.external_removal_deadline() {
+            Some(current) => current.min(deadline),
+            None => deadline,
+        };
+
+        self.external_removal_deadline = Some((self.current_epoch(), deadline));
+    }
+
+    /// Time by which removals proposed by external senders in the current
+    /// epoch should be committed, as decided by the configured
+    /// [`ExternalRemovalPolicy`].
+    ///
+    /// Deadlines are computed from the time a proposal was received, which is
+    /// the time given to [`Group::process_incoming_message_with_time`] or the
+    /// current system time. Without the `std` feature, proposals processed
+    /// with [`Group::process_incoming_message`] are due immediately.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn external_removal_deadline(&self) -> Option<MlsTime> {
+        self.external_removal_deadline
+            .filter(|(epoch, _)| *epoch == self.current_epoch())
+            .map(|(_, deadline)| deadline)
+    }
+
+    /// Commit all cached proposals if the [deadline](Group::external_removal_deadline)
+    /// for committing removals proposed by external senders has passed at
+    /// `now`.
+    ///
+    /// Returns `None` if no commit is due. Otherwise, the returned commit must
+    /// be handled as one created by [`Group::commit`].
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn commit_external_removals_if_due(
+        &mut self,
+        now: MlsTime,
+    ) -> Result<Option<CommitOutput>, MlsError> {
+        match self.external_removal_deadline() {
+            Some(deadline) if deadline <= now => self.commit(Vec::new()).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn report_security_event(
+        &self,
+        error: &MlsError,
+        message_epoch: Option<u64>,
+        wire_format: WireFormat,
+    ) {
+        let Some(sink) = self.config.security_event_sink() else {
+            return;
+        };
+
+        let Some(code) = SecurityEventCode::from_error(error) else {
+            return;
+        };
+
+        sink.on_event(&SecurityEvent {
+            code,
+            group_id: self.group_id(),
+            epoch: self.current_epoch(),
+            message_epoch,
+            wire_format,
+            error,
+        });
     }
 
     /// Find a group member by
@@ -1446,17 +2054,147 @@ where
         Ok(self.key_schedule.authentication_secret.clone().into())
     }
 
+    /// Create a proof that this member belongs to the group in the current
+    /// epoch, bound to the application-provided `context`.
+    ///
+    /// The proof can be verified by any party holding the public state of
+    /// the group at the current epoch. See [`MembershipProof`].
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn export_secret(
-        &self,
-        label: &[u8],
-        context: &[u8],
-        len: usize,
-    ) -> Result<Secret, MlsError> {
-        self.key_schedule
-            .export_secret(label, context, len, &self.cipher_suite_provider)
-            .await
-            .map(Into::into)
+    pub async fn membership_proof(&self, context: Vec<u8>) -> Result<MembershipProof, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
+        MembershipProof::new(
+            &self.cipher_suite_provider,
+            self.context(),
+            self.private_tree.self_index,
+            &self.signer,
+            context,
+        )
+        .await
+    }
+
+    /// Verify a [`MembershipProof`] created by a member of this group in the
+    /// current epoch, returning the member that created it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_membership_proof(
+        &self,
+        proof: &MembershipProof,
+    ) -> Result<Member, MlsError> {
+        proof
+            .verify(
+                &self.cipher_suite_provider,
+                self.context(),
+                self.current_epoch_tree(),
+            )
+            .await
+    }
+
+    /// Verify an [`ExternalNotice`] sent by an external sender of the group
+    /// in the current epoch, returning the sender and the content of the
+    /// notice.
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_external_notice(
+        &self,
+        notice: &ExternalNotice,
+    ) -> Result<(SigningIdentity, Vec<u8>), MlsError> {
+        notice
+            .verify(&self.cipher_suite_provider, self.context())
+            .await
+    }
+
+    /// Encrypt `payload` for the member at index `recipient`, so that it can
+    /// only be read by that member in the current epoch. See
+    /// [`MemberEnvelope`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn seal_for_member(
+        &self,
+        recipient: u32,
+        payload: &[u8],
+    ) -> Result<MemberEnvelope, MlsError> {
+        if self.removed {
+            return Err(MlsError::GroupUsedAfterRemoval);
+        }
+
+        let recipient = LeafIndex(recipient);
+        let recipient_leaf = self.current_epoch_tree().get_leaf_node(recipient)?;
+
+        MemberEnvelope::seal(
+            &self.cipher_suite_provider,
+            self.context(),
+            &self.key_schedule.authentication_secret,
+            self.private_tree.self_index,
+            &self.signer,
+            recipient,
+            &recipient_leaf.public_key,
+            payload,
+        )
+        .await
+    }
+
+    /// Open a [`MemberEnvelope`] sent to this member in the current epoch,
+    /// returning the member that sent it and the payload.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn open_member_envelope(
+        &self,
+        envelope: &MemberEnvelope,
+    ) -> Result<(Member, Vec<u8>), MlsError> {
+        let secret_key = self
+            .private_tree
+            .secret_keys
+            .first()
+            .and_then(Option::as_ref)
+            .ok_or(MlsError::InvalidTreeKemPrivateKey)?;
+
+        envelope
+            .open(
+                &self.cipher_suite_provider,
+                self.context(),
+                &self.key_schedule.authentication_secret,
+                self.current_epoch_tree(),
+                self.private_tree.self_index,
+                secret_key,
+            )
+            .await
+    }
+
+    /// Derive the [`VerificationCode`] of the members at `my_leaf` and
+    /// `their_leaf` in the current epoch.
+    ///
+    /// Both members get the same code regardless of the order of the
+    /// indexes, so that they can compare it out of band.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verification_code(
+        &self,
+        my_leaf: u32,
+        their_leaf: u32,
+    ) -> Result<VerificationCode, MlsError> {
+        let tree = self.current_epoch_tree();
+        let mine = tree.get_leaf_node(LeafIndex(my_leaf))?;
+        let theirs = tree.get_leaf_node(LeafIndex(their_leaf))?;
+
+        VerificationCode::derive(
+            &self.cipher_suite_provider,
+            &self.key_schedule.authentication_secret,
+            (LeafIndex(my_leaf), &mine.signing_identity),
+            (LeafIndex(their_leaf), &theirs.signing_identity),
+        )
+        .await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_secret(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        len: usize,
+    ) -> Result<Secret, MlsError> {
+        self.key_schedule
+            .export_secret(label, context, len, &self.cipher_suite_provider)
+            .await
+            .map(Into::into)
     }
 
     /// Export the current epoch's ratchet tree in serialized format.
@@ -1520,12 +2258,57 @@ where
         }
     }
 
+    /// Pre-shared keys used in recent epochs, oldest first.
+    ///
+    /// Only epochs whose key schedule included at least one pre-shared key are
+    /// listed, up to the 64 most recent ones, older entries being dropped.
+    ///
+    /// The history is only kept in memory. It is not persisted by
+    /// [`Group::write_to_storage`], so a group loaded from storage, with
+    /// [`Client::load_group`](crate::Client::load_group) or otherwise, starts
+    /// with an empty history.
+    #[cfg(feature = "psk")]
+    pub fn psk_usage_history(&self) -> impl Iterator<Item = &PskUsage> {
+        self.psk_usage.iter()
+    }
+
+    #[cfg(feature = "psk")]
+    fn record_psk_usage(&mut self, psks: &[PreSharedKeyID]) {
+        if psks.is_empty() {
+            return;
+        }
+
+        if self.psk_usage.len() == MAX_PSK_USAGE_HISTORY {
+            self.psk_usage.pop_front();
+        }
+
+        self.psk_usage.push_back(PskUsage {
+            epoch: self.current_epoch(),
+            psks: psks.iter().map(|id| UsedPsk::from(&id.key_id)).collect(),
+        });
+    }
+
     #[cfg(feature = "private_message")]
     pub(crate) fn encryption_options(&self) -> Result<EncryptionOptions, MlsError> {
-        self.config
+        let mut options = self
+            .config
             .mls_rules()
             .encryption_options(&self.roster(), self.group_context().extensions())
-            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        if let Some(padding_mode) = self.group_policy()?.and_then(|p| p.padding_mode()) {
+            options.padding_mode = padding_mode;
+        }
+
+        Ok(options)
+    }
+
+    /// The [`GroupPolicyExt`] of the group, if any.
+    pub fn group_policy(&self) -> Result<Option<GroupPolicyExt>, MlsError> {
+        self.group_context()
+            .extensions()
+            .get_as::<GroupPolicyExt>()
+            .map_err(Into::into)
     }
 
     #[cfg(not(feature = "psk"))]
@@ -1541,12 +2324,14 @@ where
             .secret_tree
             .next_message_key(
                 &self.cipher_suite_provider,
-                crate::tree_kem::node::NodeIndex::from(self.private_tree.self_index),
+                *self.private_tree.self_index.to_node_index(),
                 KeyType::Application,
             )
             .await
     }
 
+    /// Derive the application message key of generation `generation` for
+    /// the member at leaf index `sender`.
     #[cfg(feature = "secret_tree_access")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn derive_decryption_key(
@@ -1558,7 +2343,7 @@ where
             .secret_tree
             .message_key_generation(
                 &self.cipher_suite_provider,
-                crate::tree_kem::node::NodeIndex::from(sender),
+                *LeafIndex::new(sender).to_node_index(),
                 KeyType::Application,
                 generation,
             )
@@ -1723,7 +2508,7 @@ where
         };
 
         #[cfg(feature = "psk")]
-        let (psk, _) = self
+        let (psk, psk_ids) = self
             .get_psk(&provisional_state.applied_proposals.psks)
             .await?;
 
@@ -1792,6 +2577,11 @@ where
         }
 
         self.pending_commit = None;
+        self.pending_commit_artifacts = None;
+        self.reissuable_welcomes = Vec::new();
+
+        #[cfg(feature = "psk")]
+        self.record_psk_usage(&psk_ids);
 
         Ok(())
     }
@@ -1825,6 +2615,20 @@ where
             && self.pending_commit.is_none())
     }
 
+    fn self_index(&self) -> Option<LeafIndex> {
+        Some(self.private_tree.self_index)
+    }
+
+    fn current_time(&self) -> Option<MlsTime> {
+        self.config.current_time()
+    }
+
+    fn observe_commit(&mut self, auth_content: &AuthenticatedContent) {
+        if let Some(commits) = &mut self.observed_commits {
+            commits.push(auth_content.clone());
+        }
+    }
+
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64> {
         None
@@ -1859,10 +2663,14 @@ mod tests {
         mls_rules::CommitOptions,
         tree_kem::{
             leaf_node::{test_utils::get_test_capabilities, LeafNodeSource},
+            node::NodeIndex,
             UpdatePathNode,
         },
     };
 
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::identity::refresh::test_utils::TestRefresher;
+
     #[cfg(any(feature = "private_message", feature = "custom_proposal"))]
     use crate::group::mls_rules::DefaultMlsRules;
 
@@ -1875,7 +2683,10 @@ mod tests {
     use super::test_utils::test_group_custom_config;
 
     #[cfg(feature = "psk")]
-    use crate::{client::Client, psk::PreSharedKey};
+    use crate::{
+        client::Client,
+        psk::{self, PreSharedKey},
+    };
 
     #[cfg(any(feature = "by_ref_proposal", feature = "private_message"))]
     use crate::group::test_utils::random_bytes;
@@ -2195,6 +3006,62 @@ mod tests {
         assert_matches!(bob_group, Err(MlsError::RatchetTreeNotFound));
     }
 
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn welcome_with_invalid_psk_nonce_is_rejected() {
+        let mut test_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob_client, bob_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut welcome = test_group
+            .group
+            .commit_builder()
+            .add_member(bob_key_package.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .welcome_messages
+            .remove(0);
+
+        let MlsMessagePayload::Welcome(payload) = &mut welcome.payload else {
+            panic!("expected a welcome message");
+        };
+
+        let psk_id = PreSharedKeyID {
+            key_id: JustPreSharedKeyID::External(ExternalPskId::new(vec![1])),
+            psk_nonce: psk::test_utils::make_invalid_nonce(),
+        };
+
+        // The nonce is checked before the joiner secret is used.
+        let secrets = test_group
+            .group
+            .encrypt_group_secrets(
+                &bob_key_package.into_key_package().unwrap(),
+                LeafIndex(1),
+                &JoinerSecret::from(zeroize::Zeroizing::new(vec![0; 32])),
+                None,
+                vec![psk_id],
+                &payload.encrypted_group_info,
+            )
+            .await
+            .unwrap();
+
+        payload.secrets[0] = secrets;
+
+        let res = Group::join(
+            &welcome,
+            None,
+            bob_client.config,
+            bob_client.signer.unwrap(),
+        )
+        .await
+        .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::InvalidPskNonceLength));
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_group_context_ext_proposal_create() {
         let test_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
@@ -2927,6 +3794,24 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn subgroup_cannot_include_non_members() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        alice.join("bob").await;
+
+        let (_, key_pkg) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let res = alice
+            .group
+            .branch(b"subgroup".to_vec(), vec![key_pkg])
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::SubgroupRosterMismatch));
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn joining_group_fails_if_unsupported<F>(
         f: F,
@@ -3039,6 +3924,160 @@ mod tests {
         assert_matches!(res, Err(MlsError::KeyMissing(0)));
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn lost_welcome_is_reissued_in_same_epoch() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob, bob_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let (_, carol_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let bob_ref = bob_key_package
+            .key_package_reference(&alice_group.group.cipher_suite_provider)
+            .await
+            .unwrap()
+            .unwrap();
+
+        alice_group
+            .group
+            .commit_builder()
+            .add_member(bob_key_package)
+            .unwrap()
+            .add_member(carol_key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        // Welcomes are only available once the commit is applied
+        let res = alice_group.group.reissue_welcome(&bob_ref);
+        assert_matches!(res, Err(MlsError::WelcomeKeyPackageNotFound));
+
+        alice_group.group.apply_pending_commit().await.unwrap();
+
+        // Welcomes survive a reload of the group from storage.
+        alice_group.group = Group::from_snapshot(
+            alice_group.group.config.clone(),
+            alice_group.group.snapshot(),
+        )
+        .await
+        .unwrap();
+
+        let welcome = alice_group.group.reissue_welcome(&bob_ref).unwrap();
+        assert_eq!(welcome.welcome_key_package_references(), [&bob_ref]);
+
+        let tree = alice_group.group.export_tree().into_owned();
+        let (bob_group, _) = bob.join_group(Some(tree), &welcome).await.unwrap();
+
+        assert_eq!(
+            bob_group.epoch_authenticator().unwrap(),
+            alice_group.group.epoch_authenticator().unwrap()
+        );
+
+        alice_group.group.commit(vec![]).await.unwrap();
+        alice_group.group.apply_pending_commit().await.unwrap();
+
+        let res = alice_group.group.reissue_welcome(&bob_ref);
+        assert_matches!(res, Err(MlsError::WelcomeKeyPackageNotFound));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pending_commit_artifacts_are_linked_to_commit() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        assert!(alice_group.group.pending_commit_artifacts().is_none());
+
+        let (_, bob_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let output = alice_group
+            .group
+            .commit_builder()
+            .add_member(bob_key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let artifacts = alice_group.group.pending_commit_artifacts().unwrap();
+
+        let expected_id = alice_group
+            .group
+            .cipher_suite_provider
+            .hash(&output.commit_message.mls_encode_to_vec().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(artifacts.commit_id, expected_id);
+        assert_eq!(artifacts.commit_id, output.commit_id);
+        assert_eq!(artifacts.commit_message, output.commit_message);
+        assert_eq!(artifacts.welcome_messages, output.welcome_messages);
+        assert_eq!(artifacts.welcome_messages.len(), 1);
+
+        alice_group.group.apply_pending_commit().await.unwrap();
+
+        assert!(alice_group.group.pending_commit_artifacts().is_none());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pending_commit_artifacts_are_not_restored_from_storage() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        alice_group.group.commit(vec![]).await.unwrap();
+        assert!(alice_group.group.pending_commit_artifacts().is_some());
+
+        let restored = Group::from_snapshot(
+            alice_group.group.config.clone(),
+            alice_group.group.snapshot(),
+        )
+        .await
+        .unwrap();
+
+        assert!(restored.has_pending_commit());
+        assert!(restored.pending_commit_artifacts().is_none());
+    }
+
+    #[cfg(all(feature = "private_message", feature = "prior_epoch"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn historic_message_is_decrypted_without_mutation() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob_group, _) = alice_group.join("bob").await;
+
+        let message = alice_group
+            .group
+            .encrypt_application_message(b"foobar", b"aad".to_vec())
+            .await
+            .unwrap();
+
+        let res = bob_group.group.decrypt_historic(message.clone()).await;
+        assert_matches!(res, Err(MlsError::EpochNotFound));
+
+        let commit = alice_group.group.commit(vec![]).await.unwrap();
+        alice_group.group.apply_pending_commit().await.unwrap();
+
+        bob_group
+            .process_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let decrypted = bob_group
+                .group
+                .decrypt_historic(message.clone())
+                .await
+                .unwrap();
+
+            assert_eq!(decrypted.data(), b"foobar");
+            assert_eq!(decrypted.authenticated_data, b"aad");
+            assert_eq!(
+                decrypted.sender_index,
+                alice_group.group.current_member_index()
+            );
+        }
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn removing_requirements_allows_to_add() {
         let mut alice_group = test_group_custom(
@@ -3257,7 +4296,7 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(1, 0)));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3556,12 +4595,14 @@ mod tests {
      */
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
-    async fn committing_degenerate_path_succeeds() {
+    async fn degenerate_path_is_rejected() {
         let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 10).await;
 
         groups[0].group.commit_modifiers.modify_tree = |tree: &mut TreeKemPublic| {
-            tree.update_node(get_test_25519_key(1u8), 1).unwrap();
-            tree.update_node(get_test_25519_key(1u8), 3).unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex(1))
+                .unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex(3))
+                .unwrap();
         };
 
         groups[0].group.commit_modifiers.modify_leaf = |leaf, sk| {
@@ -3575,7 +4616,11 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert!(res.is_ok());
+        // The leaf of the committer and its parents all share the same key.
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateHpkeKey(NodeIndex(0), NodeIndex(1)))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3601,7 +4646,8 @@ mod tests {
         }
 
         groups[0].group.commit_modifiers.modify_tree = |tree: &mut TreeKemPublic| {
-            tree.update_node(get_test_25519_key(1u8), 1).unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex(1))
+                .unwrap();
         };
 
         groups[0].group.commit_modifiers.modify_path = |path: Vec<UpdatePathNode>| {
@@ -3706,6 +4752,69 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "secret_tree_access")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn decryption_key_is_derived_for_sender_leaf() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let key = groups[1].group.next_encryption_key().await.unwrap();
+        let derived = groups[0].group.derive_decryption_key(1, 0).await.unwrap();
+
+        assert_eq!(key, derived);
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expiring_credential_is_refreshed() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let expiring = groups[0]
+            .group
+            .current_member_signing_identity()
+            .unwrap()
+            .clone();
+
+        let refresher = TestRefresher {
+            cipher_suite: TEST_CIPHER_SUITE,
+            issued_at: MlsTime::from(1000),
+            lifetime: core::time::Duration::from_secs(3600),
+        };
+
+        let threshold = core::time::Duration::from_secs(600);
+
+        let update = groups[0]
+            .group
+            .propose_credential_refresh(&refresher, MlsTime::from(3999), threshold, vec![])
+            .await
+            .unwrap();
+
+        assert!(update.is_none());
+
+        let update = groups[0]
+            .group
+            .propose_credential_refresh(&refresher, MlsTime::from(4000), threshold, vec![])
+            .await
+            .unwrap()
+            .unwrap();
+
+        groups[1].process_message(update).await.unwrap();
+        let commit_output = groups[1].group.commit(vec![]).await.unwrap();
+
+        groups[0]
+            .process_message(commit_output.commit_message)
+            .await
+            .unwrap();
+
+        let member = groups[0].group.roster().member_with_index(0).unwrap();
+
+        assert_eq!(member.signing_identity.credential, expiring.credential);
+
+        assert_ne!(
+            member.signing_identity.signature_key,
+            expiring.signature_key
+        );
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn receiving_commit_with_old_adds_fails() {
@@ -3840,15 +4949,32 @@ mod tests {
             .commit_builder()
             .add_member(key_pkg)
             .unwrap()
-            .add_external_psk(psk_id)
+            .add_external_psk(psk_id.clone())
             .unwrap()
             .build()
             .await
             .unwrap();
 
-        bob.join_group(None, &commit.welcome_messages[0])
+        alice.apply_pending_commit().await.unwrap();
+
+        let (bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
             .await
             .unwrap();
+
+        let expected = vec![psk::PskUsage {
+            epoch: 1,
+            psks: vec![psk::UsedPsk::External(psk_id)],
+        }];
+
+        assert_eq!(
+            alice.psk_usage_history().cloned().collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            bob_group.psk_usage_history().cloned().collect::<Vec<_>>(),
+            expected
+        );
     }
 
     #[cfg(feature = "by_ref_proposal")]
@@ -4250,4 +5376,323 @@ mod tests {
         let res = groups[1].group.apply_pending_commit().await;
         assert_matches!(res, Err(MlsError::PendingCommitNotFound));
     }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn proposal_receive_order_does_not_change_commit_outcome() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let dave = test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "dave").await;
+        let eve = test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "eve").await;
+
+        let add_dave = groups[1].group.propose_add(dave, vec![]).await.unwrap();
+        let add_eve = groups[2].group.propose_add(eve, vec![]).await.unwrap();
+
+        let mut alice = groups.remove(0);
+
+        let mut alice_copy = TestGroup {
+            group: Group::from_snapshot(alice.group.config.clone(), alice.group.snapshot())
+                .await
+                .unwrap(),
+        };
+
+        alice.process_message(add_dave.clone()).await.unwrap();
+        alice.process_message(add_eve.clone()).await.unwrap();
+
+        alice_copy.process_message(add_eve).await.unwrap();
+        alice_copy.process_message(add_dave).await.unwrap();
+
+        let commit = alice.group.commit(vec![]).await.unwrap();
+        let commit_copy = alice_copy.group.commit(vec![]).await.unwrap();
+
+        let proposals = |commit: &CommitOutput| match commit
+            .commit_message
+            .clone()
+            .into_plaintext()
+            .unwrap()
+            .content
+            .content
+        {
+            Content::Commit(commit) => commit.proposals,
+            _ => panic!("not a commit"),
+        };
+
+        assert_eq!(proposals(&commit), proposals(&commit_copy));
+
+        alice.process_pending_commit().await.unwrap();
+        alice_copy.process_pending_commit().await.unwrap();
+
+        assert_eq!(
+            alice.group.roster().members(),
+            alice_copy.group.roster().members()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn lifecycle_follows_pending_commit() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE)
+            .await
+            .group;
+
+        assert_eq!(group.lifecycle(), GroupLifecycle::Active);
+
+        group.commit(vec![]).await.unwrap();
+        assert_eq!(group.lifecycle(), GroupLifecycle::PendingCommit);
+        assert!(!group.lifecycle().can_commit());
+
+        let update = group.apply_pending_commit().await.unwrap();
+        assert_eq!(update.lifecycle, GroupLifecycle::Active);
+        assert_eq!(group.lifecycle(), GroupLifecycle::Active);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn removed_member_cannot_use_group() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let commit = groups[0]
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let update = groups[2].process_message(commit).await.unwrap();
+
+        assert_matches!(
+            update,
+            ReceivedMessage::Commit(CommitMessageDescription {
+                lifecycle: GroupLifecycle::Removed,
+                ..
+            })
+        );
+
+        assert_eq!(groups[2].group.lifecycle(), GroupLifecycle::Removed);
+
+        let res = groups[2].group.commit(vec![]).await;
+        assert_matches!(res, Err(MlsError::GroupUsedAfterRemoval));
+
+        let restored =
+            Group::from_snapshot(groups[2].group.config.clone(), groups[2].group.snapshot())
+                .await
+                .unwrap();
+
+        assert_eq!(restored.lifecycle(), GroupLifecycle::Removed);
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<(u16, u64)>>);
+
+    #[cfg(feature = "std")]
+    impl crate::security_event::SecurityEventSink for RecordingSink {
+        fn on_event(&self, event: &SecurityEvent<'_>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((event.code.code(), event.epoch));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn security_event_is_emitted_for_invalid_signature() {
+        let sink = std::sync::Arc::new(RecordingSink::default());
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |config| {
+                config.0.settings.security_event_sink =
+                    Some(crate::security_event::SharedSecurityEventSink(sink.clone()))
+            })
+            .await
+            .unwrap();
+
+        alice.group.commit_modifiers.modify_leaf = |leaf, _| {
+            leaf.signature[0] ^= 1;
+            None
+        };
+
+        let commit_output = alice.group.commit(vec![]).await.unwrap();
+        let res = bob.process_message(commit_output.commit_message).await;
+
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![(SecurityEventCode::InvalidSignature.code(), 1)]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn downgrade_attempts_are_reported() {
+        let sink = std::sync::Arc::new(RecordingSink::default());
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |config| {
+                config.0.settings.security_event_sink =
+                    Some(crate::security_event::SharedSecurityEventSink(sink.clone()))
+            })
+            .await
+            .unwrap();
+
+        let mut commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        commit.version = ProtocolVersion::from(64);
+
+        let res = bob.process_message(commit).await;
+        assert_matches!(res, Err(MlsError::ProtocolVersionMismatch));
+
+        let mut expected = vec![(SecurityEventCode::DowngradeAttempt.code(), 1)];
+
+        let other_cipher_suite = TestCryptoProvider::all_supported_cipher_suites()
+            .into_iter()
+            .find(|cs| *cs != TEST_CIPHER_SUITE);
+
+        if let Some(cipher_suite) = other_cipher_suite {
+            let key_package =
+                test_key_package_message(TEST_PROTOCOL_VERSION, cipher_suite, "carol").await;
+
+            let res = bob.process_message(key_package).await;
+            assert_matches!(res, Err(MlsError::CipherSuiteMismatch));
+
+            expected.push((SecurityEventCode::DowngradeAttempt.code(), 1));
+        }
+
+        assert_eq!(*sink.0.lock().unwrap(), expected);
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn prior_epochs_can_be_purged() {
+        use mls_rs_core::group::GroupStateStorage;
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        for _ in 0..3 {
+            alice.group.commit(vec![]).await.unwrap();
+            alice.group.apply_pending_commit().await.unwrap();
+        }
+
+        let res = alice.group.purge_epochs_before(4).await;
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+
+        let summary = alice.group.purge_epochs_before(2).await.unwrap();
+        assert_eq!(summary.epoch_ids, vec![0, 1]);
+
+        let storage = alice.group.config.group_state_storage();
+        let group_id = alice.group.group_id().to_vec();
+
+        let epoch_1 = storage.epoch(&group_id, 1).await.unwrap();
+        assert_eq!(epoch_1, None);
+
+        let epoch_2 = storage.epoch(&group_id, 2).await.unwrap();
+        assert!(epoch_2.is_some());
+
+        let summary = alice.group.purge_epochs_before(2).await.unwrap();
+        assert!(summary.epoch_ids.is_empty());
+    }
+
+    #[cfg(all(feature = "private_message", feature = "prior_epoch"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expiring_message_has_the_epoch_it_was_sent_in() {
+        use super::message_expiry::SharedMessageExpiryPolicy;
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let sent_epoch = alice.group.current_epoch() + 1;
+
+        let policy = move |message: &ExpiringMessage<'_>| message.epoch != sent_epoch;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |config| {
+                config.0.settings.message_expiry_policy =
+                    Some(SharedMessageExpiryPolicy(alloc::sync::Arc::new(policy)))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(alice.group.current_epoch(), sent_epoch);
+
+        let message = alice
+            .group
+            .encrypt_application_message_with_expiry(b"foobar", MlsTime::from(1000), vec![])
+            .await
+            .unwrap();
+
+        let commit = alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.process_message(commit.commit_message).await.unwrap();
+
+        let res = bob
+            .group
+            .process_incoming_message_with_time(message, MlsTime::from(0))
+            .await;
+
+        assert_matches!(res, Ok(ReceivedMessage::ApplicationMessage(_)));
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expired_application_messages_are_rejected() {
+        use super::message_expiry::SharedMessageExpiryPolicy;
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let policy = |message: &ExpiringMessage<'_>| !matches!(message.received_at, Some(t) if t < message.expires_at);
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |config| {
+                config.0.settings.message_expiry_policy =
+                    Some(SharedMessageExpiryPolicy(alloc::sync::Arc::new(policy)))
+            })
+            .await
+            .unwrap();
+
+        for received_at in [999, 1000] {
+            let message = alice
+                .group
+                .encrypt_application_message_with_expiry(
+                    b"foobar",
+                    MlsTime::from(1000),
+                    b"aad".to_vec(),
+                )
+                .await
+                .unwrap();
+
+            let res = bob
+                .group
+                .process_incoming_message_with_time(message, MlsTime::from(received_at))
+                .await;
+
+            if received_at < 1000 {
+                let description =
+                    assert_matches!(res, Ok(ReceivedMessage::ApplicationMessage(d)) => d);
+                assert_eq!(description.data(), b"foobar");
+                assert_eq!(description.expires_at().unwrap(), Some(MlsTime::from(1000)));
+
+                let data =
+                    ExpiringAuthenticatedData::from_bytes(&description.authenticated_data).unwrap();
+                assert_eq!(data.unwrap().authenticated_data, b"aad");
+            } else {
+                assert_matches!(res, Err(MlsError::MessageExpired));
+            }
+        }
+
+        // Messages without an expiry are not subject to the policy.
+        let message = alice
+            .group
+            .encrypt_application_message(b"foobar", vec![])
+            .await
+            .unwrap();
+
+        let res = bob
+            .group
+            .process_incoming_message_with_time(message, MlsTime::from(2000))
+            .await;
+
+        assert_matches!(res, Ok(ReceivedMessage::ApplicationMessage(_)));
+    }
 }
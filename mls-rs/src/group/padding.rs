@@ -2,20 +2,23 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
 /// Padding used when sending an encrypted group message.
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::ffi_type)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MlsSize, MlsEncode, MlsDecode)]
 #[repr(u8)]
 pub enum PaddingMode {
     /// Step function based on the size of the message being sent.
     /// The amount of padding used will increase with the size of the original
     /// message.
     #[default]
-    StepFunction,
+    StepFunction = 0u8,
     /// No padding.
-    None,
+    None = 1u8,
 }
 
+#[cfg(feature = "private_message")]
 impl PaddingMode {
     pub(super) fn padded_size(&self, content_size: usize) -> usize {
         match self {
@@ -36,7 +39,7 @@ impl PaddingMode {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "private_message"))]
 mod tests {
     use super::PaddingMode;
 
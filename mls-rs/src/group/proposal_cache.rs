@@ -11,6 +11,7 @@ use super::{
 };
 use crate::{
     client::MlsError,
+    extension::{group_policy::GroupPolicyExt, rejoin_proof::verify_rejoin_proof},
     group::{
         proposal_filter::{ProposalApplier, ProposalBundle, ProposalSource},
         Proposal, Sender,
@@ -120,7 +121,8 @@ impl ProposalCache {
         sender: Sender,
         additional_proposals: Vec<Proposal>,
     ) -> ProposalBundle {
-        self.proposals
+        let mut proposals = self
+            .proposals
             .iter()
             .map(|(r, p)| {
                 (
@@ -134,7 +136,10 @@ impl ProposalCache {
                     .into_iter()
                     .map(|p| (p, sender, ProposalSource::ByValue)),
             )
-            .collect()
+            .collect::<ProposalBundle>();
+
+        proposals.sort_canonically();
+        proposals
     }
 
     pub fn resolve_for_commit(
@@ -295,6 +300,45 @@ impl GroupState {
             group_context.extensions = ext;
         }
 
+        if let Some(max_members) = group_context
+            .extensions
+            .get_as::<GroupPolicyExt>()?
+            .and_then(|policy| policy.max_members())
+        {
+            if applier_output.new_tree.occupied_leaf_count() > max_members {
+                return Err(MlsError::GroupSizeLimitExceeded(max_members));
+            }
+        }
+
+        if let Some(policy) = group_extensions
+            .get_as::<GroupPolicyExt>()?
+            .and_then(|policy| policy.rejoin_proof())
+        {
+            #[cfg(feature = "by_ref_proposal")]
+            let applied = &applier_output.applied_proposals;
+
+            #[cfg(not(feature = "by_ref_proposal"))]
+            let applied = &proposals;
+
+            let removal = applied.remove_proposals().first();
+
+            if let (Sender::NewMemberCommit, Some(removal), Some(new_leaf)) =
+                (sender, removal, external_leaf)
+            {
+                let old_leaf = self.public_tree.get_leaf_node(removal.proposal.to_remove)?;
+
+                verify_rejoin_proof(
+                    policy,
+                    &self.context.group_id,
+                    group_extensions,
+                    old_leaf,
+                    new_leaf,
+                    cipher_suite_provider,
+                )
+                .await?;
+            }
+        }
+
         #[cfg(feature = "by_ref_proposal")]
         let proposals = applier_output.applied_proposals;
 
@@ -635,7 +679,7 @@ mod tests {
     use crate::key_package::test_utils::test_key_package_with_signer;
     use crate::signer::Signable;
     use crate::tree_kem::leaf_node::LeafNode;
-    use crate::tree_kem::node::LeafIndex;
+    use crate::tree_kem::node::{LeafIndex, NodeIndex};
     use crate::tree_kem::TreeKemPublic;
     use crate::{
         client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
@@ -676,8 +720,8 @@ mod tests {
     use crate::{
         group::proposal::PreSharedKeyProposal,
         psk::{
-            ExternalPskId, JustPreSharedKeyID, PreSharedKeyID, PskGroupId, PskNonce,
-            ResumptionPSKUsage, ResumptionPsk,
+            test_utils::make_invalid_nonce, ExternalPskId, JustPreSharedKeyID, PreSharedKeyID,
+            PskGroupId, PskNonce, ResumptionPSKUsage, ResumptionPsk,
         },
     };
 
@@ -2202,7 +2246,7 @@ mod tests {
         })])
         .await;
 
-        assert_matches!(res, Err(MlsError::InvalidNodeIndex(20)));
+        assert_matches!(res, Err(MlsError::InvalidNodeIndex(NodeIndex(20))));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2216,7 +2260,7 @@ mod tests {
             .send()
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidNodeIndex(20)));
+        assert_matches!(res, Err(MlsError::InvalidNodeIndex(NodeIndex(20))));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2267,7 +2311,7 @@ mod tests {
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn receiving_psk_with_invalid_nonce_fails() {
-        let invalid_nonce = PskNonce(vec![0, 1, 2]);
+        let invalid_nonce = make_invalid_nonce();
         let (alice, tree) = new_tree("alice").await;
 
         let res = CommitReceiver::new(
@@ -2288,7 +2332,7 @@ mod tests {
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn sending_additional_psk_with_invalid_nonce_fails() {
-        let invalid_nonce = PskNonce(vec![0, 1, 2]);
+        let invalid_nonce = make_invalid_nonce();
         let (alice, tree) = new_tree("alice").await;
 
         let res = CommitSender::new(&tree, alice, test_cipher_suite_provider(TEST_CIPHER_SUITE))
@@ -2305,7 +2349,7 @@ mod tests {
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn sending_psk_with_invalid_nonce_filters_it_out() {
-        let invalid_nonce = PskNonce(vec![0, 1, 2]);
+        let invalid_nonce = make_invalid_nonce();
         let (alice, tree) = new_tree("alice").await;
         let proposal = Proposal::Psk(make_external_psk(b"foo", invalid_nonce));
 
@@ -2655,6 +2699,184 @@ mod tests {
         assert_eq!(processed_proposals.1.unused_proposals, vec![update_info]);
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn two_updates_from_bob() -> (
+        LeafIndex,
+        LeafIndex,
+        TreeKemPublic,
+        [(ProposalRef, Proposal); 2],
+    ) {
+        let (alice, mut tree) = new_tree("alice").await;
+        let bob = add_member(&mut tree, "bob").await;
+
+        let first = Proposal::Update(make_update_proposal("bob").await);
+        let first_ref = make_proposal_ref(&first, bob).await;
+
+        let second = Proposal::Update(make_update_proposal("bob").await);
+        let second_ref = make_proposal_ref(&second, bob).await;
+
+        (alice, bob, tree, [(first_ref, first), (second_ref, second)])
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sending_commits_last_update_from_sender() {
+        let (alice, bob, tree, [(first_ref, first), (second_ref, second)]) =
+            two_updates_from_bob().await;
+
+        let (committed, _) =
+            CommitSender::new(&tree, alice, test_cipher_suite_provider(TEST_CIPHER_SUITE))
+                .cache(first_ref.clone(), first, bob)
+                .cache(second_ref.clone(), second, bob)
+                .send()
+                .await
+                .unwrap();
+
+        // Received updates are all kept, and the one committed is the last
+        // in the canonical order of the commit.
+        assert_eq!(committed, vec![first_ref.max(second_ref).into()]);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_commit_of_any_cached_update_succeeds() {
+        let (alice, bob, tree, updates) = two_updates_from_bob().await;
+
+        for (committed_ref, _) in updates.clone() {
+            let mut receiver = CommitReceiver::new(
+                &tree,
+                alice,
+                alice,
+                test_cipher_suite_provider(TEST_CIPHER_SUITE),
+            );
+
+            for (r, p) in updates.clone() {
+                receiver = receiver.cache(r, p, bob);
+            }
+
+            receiver.receive([committed_ref]).await.unwrap();
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_two_updates_from_same_sender_fails() {
+        let (alice, bob, tree, updates) = two_updates_from_bob().await;
+
+        let mut cache = make_proposal_cache();
+
+        cache.extend(
+            updates
+                .iter()
+                .cloned()
+                .map(|(r, p)| (r, CachedProposal::new(p, bob.into()))),
+        );
+
+        let res = cache
+            .resolve_for_commit_default(
+                Sender::Member(*alice),
+                updates.into_iter().map(|(r, _)| r.into()).collect(),
+                None,
+                &ExtensionList::new(),
+                &BasicIdentityProvider,
+                &test_cipher_suite_provider(TEST_CIPHER_SUITE),
+                &tree,
+                &AlwaysFoundPskStorage,
+                pass_through_rules(),
+            )
+            .await;
+
+        assert_matches!(res, Err(MlsError::MoreThanOneProposalForLeaf(1)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sending_two_updates_from_same_sender_filters_one_out() {
+        let (alice, bob, tree, updates) = two_updates_from_bob().await;
+
+        let mut sender =
+            CommitSender::new(&tree, alice, test_cipher_suite_provider(TEST_CIPHER_SUITE));
+
+        sender.cache.extend(
+            updates
+                .iter()
+                .cloned()
+                .map(|(r, p)| (r, CachedProposal::new(p, bob.into()))),
+        );
+
+        let (committed, state) = sender.send().await.unwrap();
+
+        assert_eq!(committed.len(), 1);
+        assert_eq!(state.applied_proposals.update_senders, vec![bob]);
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn update_reusing_current_key() -> (LeafIndex, LeafIndex, TreeKemPublic, Proposal) {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (alice, mut tree) = new_tree("alice").await;
+
+        let (bob_leaf, _, bob_signer) = get_basic_test_node_sig_key(TEST_CIPHER_SUITE, "bob").await;
+
+        let bob = tree
+            .add_leaves(
+                vec![bob_leaf.clone()],
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await
+            .unwrap()[0];
+
+        let mut leaf_node = LeafNode {
+            leaf_node_source: LeafNodeSource::Update,
+            ..bob_leaf
+        };
+
+        leaf_node
+            .sign(
+                &cipher_suite_provider,
+                &bob_signer,
+                &(TEST_GROUP, *bob).into(),
+            )
+            .await
+            .unwrap();
+
+        let update = Proposal::Update(UpdateProposal { leaf_node });
+
+        (alice, bob, tree, update)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_update_reusing_current_key_fails() {
+        let (alice, bob, tree, update) = update_reusing_current_key().await;
+        let update_ref = make_proposal_ref(&update, bob).await;
+
+        let res = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .cache(update_ref.clone(), update, bob)
+        .receive([update_ref])
+        .await;
+
+        assert_matches!(res, Err(MlsError::ReusedLeafNode(1)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sending_update_reusing_current_key_filters_it_out() {
+        let (alice, bob, tree, update) = update_reusing_current_key().await;
+        let update_info = make_proposal_info(&update, bob).await;
+
+        let processed_proposals =
+            CommitSender::new(&tree, alice, test_cipher_suite_provider(TEST_CIPHER_SUITE))
+                .cache(update_info.proposal_ref().unwrap().clone(), update, bob)
+                .send()
+                .await
+                .unwrap();
+
+        assert_eq!(processed_proposals.0, Vec::new());
+
+        #[cfg(feature = "state_update")]
+        assert_eq!(processed_proposals.1.unused_proposals, vec![update_info]);
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn make_add_proposal() -> Box<AddProposal> {
         Box::new(AddProposal {
@@ -2796,7 +3018,7 @@ mod tests {
         .receive([add])
         .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(1, 2)));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2823,7 +3045,7 @@ mod tests {
         .send()
         .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(1, 2)));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
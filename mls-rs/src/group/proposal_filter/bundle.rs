@@ -32,6 +32,21 @@ use core::iter::empty;
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A collection of proposals.
+///
+/// Proposals are grouped by type and each type is applied as a whole, in the
+/// order defined by
+/// [RFC 9420](https://www.rfc-editor.org/rfc/rfc9420.html#section-12.4.2):
+/// group context extensions, updates, removals, additions and finally
+/// pre-shared keys. The order of proposals in a commit therefore only matters
+/// between proposals of the same type. For instance, the order of add
+/// proposals determines the leaves assigned to new members.
+///
+/// When a commit is created, the proposals of each type are put in the
+/// [canonical order](ProposalBundle::sort_canonically) so that the outcome
+/// does not depend on the order in which cached proposals were received.
+/// When a commit is processed, proposals are applied in the order they appear
+/// in the commit, which is not required to be canonical, as other
+/// implementations may order proposals differently.
 pub struct ProposalBundle {
     pub(crate) additions: Vec<ProposalInfo<AddProposal>>,
     #[cfg(feature = "by_ref_proposal")]
@@ -201,6 +216,39 @@ impl ProposalBundle {
         Ok(())
     }
 
+    /// Sort the proposals of each type in canonical order.
+    ///
+    /// Within each proposal type, proposals by reference come first, sorted
+    /// by [`ProposalRef`], followed by all other proposals in their current
+    /// relative order. Sorting is stable, so proposals by value keep the
+    /// order chosen by the committer.
+    pub fn sort_canonically(&mut self) {
+        #[cfg(feature = "by_ref_proposal")]
+        {
+            sort_canonically(&mut self.additions);
+            sort_canonically(&mut self.removals);
+            #[cfg(feature = "psk")]
+            sort_canonically(&mut self.psks);
+            sort_canonically(&mut self.reinitializations);
+            sort_canonically(&mut self.external_initializations);
+            sort_canonically(&mut self.group_context_extensions);
+            #[cfg(feature = "custom_proposal")]
+            sort_canonically(&mut self.custom_proposals);
+
+            if self.update_senders.len() == self.updates.len() {
+                let mut updates = core::mem::take(&mut self.updates)
+                    .into_iter()
+                    .zip(core::mem::take(&mut self.update_senders))
+                    .collect::<Vec<_>>();
+
+                updates.sort_by(|(a, _), (b, _)| canonical_key(a).cmp(&canonical_key(b)));
+                (self.updates, self.update_senders) = updates.into_iter().unzip();
+            } else {
+                sort_canonically(&mut self.updates);
+            }
+        }
+    }
+
     /// The number of proposals in the bundle
     pub fn length(&self) -> usize {
         let len = 0;
@@ -435,6 +483,19 @@ impl ProposalBundle {
     }
 }
 
+#[cfg(feature = "by_ref_proposal")]
+fn canonical_key<T>(proposal: &ProposalInfo<T>) -> (bool, Option<&ProposalRef>) {
+    match &proposal.source {
+        ProposalSource::ByReference(reference) => (false, Some(reference)),
+        _ => (true, None),
+    }
+}
+
+#[cfg(feature = "by_ref_proposal")]
+fn sort_canonically<T>(proposals: &mut [ProposalInfo<T>]) {
+    proposals.sort_by(|a, b| canonical_key(a).cmp(&canonical_key(b)))
+}
+
 impl FromIterator<(Proposal, Sender, ProposalSource)> for ProposalBundle {
     fn from_iter<I>(iter: I) -> Self
     where
@@ -71,6 +71,7 @@ where
             .map(leaf_index_of_update_sender)
             .collect::<Result<_, _>>()?;
 
+        let proposals = filter_out_extra_updates(strategy, proposals)?;
         let mut proposals = filter_out_removal_of_committer(strategy, commit_sender, proposals)?;
 
         filter_out_invalid_psks(
@@ -194,6 +195,10 @@ where
                         Err(e) => return Some(Err(e)),
                     };
 
+                    let not_reused = (leaf.public_key != old_leaf.public_key)
+                        .then_some(())
+                        .ok_or(MlsError::ReusedLeafNode(*sender_index));
+
                     let valid_successor = self
                         .identity_provider
                         .valid_successor(
@@ -205,7 +210,7 @@ where
                         .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
                         .and_then(|valid| valid.then_some(()).ok_or(MlsError::InvalidSuccessor));
 
-                    res.and(valid_successor)
+                    res.and(not_reused).and(valid_successor)
                 };
 
                 apply_strategy(strategy, p.is_by_reference(), res)
@@ -291,6 +296,42 @@ fn filter_out_update_for_committer(
     Ok(proposals)
 }
 
+/// Keep only one update from each sender, the last one in the order of the
+/// bundle, unless `strategy` doesn't allow ignoring the others.
+///
+/// All the updates received are cached, so that commits referencing any of
+/// them can be processed regardless of the order they were delivered in. A
+/// commit may only contain one of them though. When committing, the bundle is
+/// in [canonical order](crate::group::proposal_filter::ProposalBundle::sort_canonically),
+/// so the update kept is the one with the greatest
+/// [`ProposalRef`](crate::group::proposal_ref::ProposalRef). This is
+/// deterministic for a given set of cached updates, but it is not necessarily
+/// the update the sender sent last, as proposals carry no sequence number.
+fn filter_out_extra_updates(
+    strategy: FilterStrategy,
+    mut proposals: ProposalBundle,
+) -> Result<ProposalBundle, MlsError> {
+    let mut senders = Vec::new();
+
+    for i in (0..proposals.update_proposals().len()).rev() {
+        let sender = proposals.update_senders[i];
+        let p = &proposals.update_proposals()[i];
+
+        let res = (!senders.contains(&sender))
+            .then_some(())
+            .ok_or(MlsError::MoreThanOneProposalForLeaf(*sender));
+
+        if apply_strategy(strategy, p.is_by_reference(), res)? {
+            senders.push(sender);
+        } else {
+            proposals.remove::<UpdateProposal>(i);
+            proposals.update_senders.remove(i);
+        }
+    }
+
+    Ok(proposals)
+}
+
 fn filter_out_removal_of_committer(
     strategy: FilterStrategy,
     commit_sender: LeafIndex,
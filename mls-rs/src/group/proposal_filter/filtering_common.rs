@@ -395,7 +395,7 @@ where
                 })
         );
 
-        let nonce_length = p.proposal.psk.psk_nonce.0.len();
+        let nonce_length = p.proposal.psk.psk_nonce.as_bytes().len();
         let nonce_valid = nonce_length == kdf_extract_size;
 
         #[cfg(feature = "std")]
@@ -0,0 +1,223 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{client::MlsError, time::MlsTime, MlsMessage};
+
+use super::{snapshot::Snapshot, ClientConfig, Group};
+
+/// Message processed by a group while it was recording a [`GroupTranscript`].
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct TranscriptEntry {
+    message: MlsMessage,
+    time_sent: Option<MlsTime>,
+    received_at: Option<MlsTime>,
+}
+
+impl TranscriptEntry {
+    /// Message as it was received, still encrypted if it was a private
+    /// message.
+    pub fn message(&self) -> &MlsMessage {
+        &self.message
+    }
+
+    /// Time given to
+    /// [`Group::process_incoming_message_with_time`], if the message was
+    /// processed with it.
+    pub fn time_sent(&self) -> Option<MlsTime> {
+        self.time_sent
+    }
+
+    /// Time at which the message was processed, according to the
+    /// [`TimeProvider`](crate::time::TimeProvider) of the group.
+    pub fn received_at(&self) -> Option<MlsTime> {
+        self.received_at
+    }
+}
+
+/// State of a group together with all messages it processed afterwards.
+///
+/// A transcript is recorded between [`Group::start_recording`] and
+/// [`Group::stop_recording`], and can be replayed with
+/// [`Client::replay_transcript`](crate::Client::replay_transcript), for
+/// instance to reproduce exactly, in a test, how a group deployed in the
+/// field failed to process a message.
+///
+/// # Warning
+///
+/// A transcript contains the secrets of the group in the epoch in which
+/// recording started, as well as its signature key, which give access to all
+/// messages of the transcript and any later message. It must be protected
+/// like the [group state](crate::GroupStateStorage) itself.
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct GroupTranscript {
+    pub(crate) snapshot: Snapshot,
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Debug for GroupTranscript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let context = &self.snapshot.state.context;
+
+        f.debug_struct("GroupTranscript")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&context.group_id),
+            )
+            .field("epoch", &context.epoch)
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl GroupTranscript {
+    /// Identifier of the recorded group.
+    pub fn group_id(&self) -> &[u8] {
+        &self.snapshot.state.context.group_id
+    }
+
+    /// Epoch of the group when recording started.
+    pub fn epoch(&self) -> u64 {
+        self.snapshot.state.context.epoch
+    }
+
+    /// Messages processed by the group, in order.
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Serialize the transcript.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Deserialize a transcript serialized with
+    /// [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Start recording a [`GroupTranscript`] of the current state of the
+    /// group and of all messages it processes with
+    /// [`process_incoming_message`](Self::process_incoming_message) or
+    /// [`process_incoming_message_with_time`](Self::process_incoming_message_with_time).
+    ///
+    /// Commits created by this member are only recorded if they are applied
+    /// by processing them once they are delivered, rather than with
+    /// [`apply_pending_commit`](Self::apply_pending_commit). A recording in
+    /// progress is discarded.
+    pub fn start_recording(&mut self) {
+        self.transcript = Some(GroupTranscript {
+            snapshot: self.snapshot(),
+            entries: Vec::new(),
+        });
+    }
+
+    /// Stop recording, returning the transcript recorded since
+    /// [`start_recording`](Self::start_recording) was called, if it was.
+    pub fn stop_recording(&mut self) -> Option<GroupTranscript> {
+        self.transcript.take()
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        message: &MlsMessage,
+        time_sent: Option<MlsTime>,
+        received_at: Option<MlsTime>,
+    ) {
+        if let Some(transcript) = &mut self.transcript {
+            transcript.entries.push(TranscriptEntry {
+                message: message.clone(),
+                time_sent,
+                received_at,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_n_member_group,
+    };
+
+    #[cfg(feature = "private_message")]
+    use assert_matches::assert_matches;
+
+    #[cfg(feature = "private_message")]
+    use crate::{client::test_utils::TestClientBuilder, group::ReceivedMessage, time::MlsTime};
+
+    #[cfg(feature = "private_message")]
+    use super::GroupTranscript;
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn replaying_transcript_reproduces_group() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        groups[1].group.start_recording();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+        groups[0].group.apply_pending_commit().await.unwrap();
+        groups[1].process_message(commit).await.unwrap();
+
+        let message = groups[0]
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let time = MlsTime::from(1_000_000);
+
+        groups[1]
+            .group
+            .process_incoming_message_with_time(message, time)
+            .await
+            .unwrap();
+
+        let transcript = groups[1].group.stop_recording().unwrap();
+        assert_eq!(transcript.entries().len(), 2);
+        assert_eq!(transcript.entries()[1].time_sent(), Some(time));
+
+        let transcript = GroupTranscript::from_bytes(&transcript.to_bytes().unwrap()).unwrap();
+
+        let client = TestClientBuilder::new_for_test().build();
+        let (replayed, results) = client.replay_transcript(&transcript).await.unwrap();
+
+        assert_matches!(results[0], Ok(ReceivedMessage::Commit(_)));
+
+        assert_matches!(
+            &results[1],
+            Ok(ReceivedMessage::ApplicationMessage(m)) if m.data() == b"hello"
+        );
+
+        assert_eq!(replayed.current_epoch(), groups[1].group.current_epoch());
+
+        assert_eq!(
+            replayed.epoch_authenticator().unwrap(),
+            groups[1].group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn messages_are_not_recorded_by_default() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+        groups[1].process_message(commit).await.unwrap();
+
+        assert!(groups[1].group.stop_recording().is_none());
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::{self, Debug};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use mls_rs_core::identity::SigningIdentity;
+
+/// Replacement of a lost group by a new group, created with
+/// [`Client::recover_group`](crate::Client::recover_group).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GroupRecovery<'a> {
+    /// Identifier of the lost group.
+    pub lost_group_id: &'a [u8],
+    /// Last known epoch of the lost group.
+    pub lost_epoch: u64,
+    /// Signing identity of the member creating the new group.
+    pub creator: &'a SigningIdentity,
+}
+
+/// Policy deciding who may replace a lost group.
+///
+/// A policy can be configured with
+/// [`ClientBuilder::recovery_policy`](crate::client_builder::ClientBuilder::recovery_policy).
+/// It is called by [`Client::recover_group`](crate::Client::recover_group)
+/// with the signing identity of the client, and by
+/// [`Client::join_recovered_group`](crate::Client::join_recovered_group)
+/// with the signing identity of the creator of the new group. Without a
+/// policy, groups can't be recovered.
+///
+/// This trait is implemented for closures taking a [`GroupRecovery`] and
+/// returning a `bool`.
+pub trait RecoveryPolicy: Send + Sync {
+    /// Whether `recovery.creator` is allowed to replace the lost group, for
+    /// instance because they are an administrator of the application.
+    fn allows(&self, recovery: &GroupRecovery<'_>) -> bool;
+}
+
+impl<F> RecoveryPolicy for F
+where
+    F: Fn(&GroupRecovery<'_>) -> bool + Send + Sync,
+{
+    fn allows(&self, recovery: &GroupRecovery<'_>) -> bool {
+        self(recovery)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedRecoveryPolicy(pub(crate) Arc<dyn RecoveryPolicy>);
+
+impl Debug for SharedRecoveryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedRecoveryPolicy").finish()
+    }
+}
@@ -5,17 +5,19 @@
 use alloc::vec::Vec;
 
 use mls_rs_core::{
-    crypto::{CipherSuite, SignatureSecretKey},
+    crypto::{CipherSuite, CipherSuiteProvider, SignatureSecretKey},
+    error::IntoAnyError,
     extension::ExtensionList,
-    identity::SigningIdentity,
+    identity::{IdentityProvider, SigningIdentity},
     protocol_version::ProtocolVersion,
 };
 
-use crate::{client::MlsError, Client, Group, MlsMessage};
+use crate::{client::MlsError, tree_kem::node::NodeVec, Client, Group, MlsMessage};
 
 use super::{
-    proposal::ReInitProposal, ClientConfig, ExportedTree, JustPreSharedKeyID, MessageProcessor,
-    NewMemberInfo, PreSharedKeyID, PskGroupId, PskSecretInput, ResumptionPSKUsage, ResumptionPsk,
+    cipher_suite_provider, proposal::ReInitProposal, recovery::GroupRecovery, ClientConfig,
+    ExportedTree, JustPreSharedKeyID, MessageProcessor, NewMemberInfo, PreSharedKeyID, PskGroupId,
+    PskSecretInput, ReceivedMessage, ResumptionPSKUsage, ResumptionPsk,
 };
 
 struct ResumptionGroupParameters<'a> {
@@ -29,6 +31,8 @@ pub struct ReinitClient<C: ClientConfig + Clone> {
     client: Client<C>,
     reinit: ReInitProposal,
     psk_input: PskSecretInput,
+    old_roster: NodeVec,
+    old_extensions: ExtensionList,
 }
 
 impl<C> Group<C>
@@ -43,7 +47,9 @@ where
     /// as an existing group member. The identity value of each key package
     /// is determined using the
     /// [`IdentityProvider`](crate::IdentityProvider)
-    /// that is currently in use by this group instance.
+    /// that is currently in use by this group instance. This function fails
+    /// with [`MlsError::SubgroupRosterMismatch`] if a key package doesn't
+    /// match any member.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn branch(
         &self,
@@ -57,7 +63,7 @@ where
             extensions: &self.group_state().context.extensions,
         };
 
-        resumption_create_group(
+        let (group, welcome_messages) = resumption_create_group(
             self.config.clone(),
             new_key_packages,
             &new_group_params,
@@ -65,12 +71,19 @@ where
             self.current_member_signing_identity()?.clone(),
             self.signer.clone(),
             #[cfg(any(feature = "private_message", feature = "psk"))]
-            self.resumption_psk_input(ResumptionPSKUsage::Branch)?,
+            Some(self.resumption_psk_input(ResumptionPSKUsage::Branch)?),
         )
-        .await
+        .await?;
+
+        verify_subgroup_roster(self, &group).await?;
+
+        Ok((group, welcome_messages))
     }
 
     /// Join a subgroup that was created by [`Group::branch`].
+    ///
+    /// This fails with [`MlsError::SubgroupRosterMismatch`] if a member of
+    /// the subgroup is not a member of this group.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn join_subgroup(
         &self,
@@ -84,7 +97,7 @@ where
             extensions: &self.group_state().context.extensions,
         };
 
-        resumption_join_group(
+        let (group, new_member_info) = resumption_join_group(
             self.config.clone(),
             self.signer.clone(),
             welcome,
@@ -93,7 +106,11 @@ where
             false,
             self.resumption_psk_input(ResumptionPSKUsage::Branch)?,
         )
-        .await
+        .await?;
+
+        verify_subgroup_roster(self, &group).await?;
+
+        Ok((group, new_member_info))
     }
 
     /// Generate a [`ReinitClient`] that can be used to create or join a new group
@@ -108,6 +125,9 @@ where
     /// commit to the reinit proposal. The value of [identity](crate::IdentityProvider::identity)
     /// must be the same for `new_signing_identity` and the current identity in use by this
     /// group instance.
+    ///
+    /// The returned client checks that the members of the new group have the
+    /// same identities as the members of this group.
     pub fn get_reinit_client(
         self,
         new_signer: Option<SignatureSecretKey>,
@@ -119,6 +139,9 @@ where
             .map(Ok)
             .unwrap_or_else(|| self.current_member_signing_identity().cloned())?;
 
+        let old_roster = self.current_epoch_tree().nodes.clone();
+        let old_extensions = self.group_state().context.extensions.clone();
+
         let reinit = self
             .state
             .pending_reinit
@@ -140,6 +163,8 @@ where
             client,
             reinit,
             psk_input,
+            old_roster,
+            old_extensions,
         })
     }
 
@@ -171,10 +196,10 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
     /// Create the new group using new key packages of all group members, possibly
     /// generated by [`ReinitClient::generate_key_package`].
     ///
-    /// # Warning
-    ///
-    /// This function will fail if the number of members in the reinitialized
-    /// group is not the same as the prior group roster.
+    /// This function fails with [`MlsError::ReInitRosterMismatch`] if the
+    /// members of the new group don't have the same
+    /// [identities](crate::IdentityProvider::identity) as the members of the
+    /// reinitialized group.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn commit(
         self,
@@ -187,7 +212,7 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             extensions: self.reinit.new_group_context_extensions(),
         };
 
-        resumption_create_group(
+        let (group, welcome_messages) = resumption_create_group(
             self.client.config.clone(),
             new_key_packages,
             &new_group_params,
@@ -195,12 +220,21 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             self.client.signing_identity.unwrap().0,
             self.client.signer.unwrap(),
             #[cfg(any(feature = "private_message", feature = "psk"))]
-            self.psk_input,
+            Some(self.psk_input),
         )
-        .await
+        .await?;
+
+        verify_reinit_roster(&self.old_roster, &self.old_extensions, &group).await?;
+
+        Ok((group, welcome_messages))
     }
 
     /// Join a reinitialized group that was created by [`ReinitClient::commit`].
+    ///
+    /// Besides the parameters set by the [`ReInitProposal`], this verifies
+    /// that the members of the new group are the members of the
+    /// reinitialized group, and fails with [`MlsError::ReInitRosterMismatch`]
+    /// otherwise.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn join(
         self,
@@ -216,7 +250,7 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             extensions: reinit.new_group_context_extensions(),
         };
 
-        resumption_join_group(
+        let (group, new_member_info) = resumption_join_group(
             self.client.config,
             // This private field is created with `Some(x)` by `get_reinit_client`
             self.client.signer.unwrap(),
@@ -226,10 +260,379 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             true,
             self.psk_input,
         )
-        .await
+        .await?;
+
+        verify_reinit_roster(&self.old_roster, &self.old_extensions, &group).await?;
+
+        Ok((group, new_member_info))
+    }
+}
+
+/// Group replacing a reinitialized group, together with the old group kept
+/// readable for a while.
+///
+/// After a reinit, application messages sent in the last epochs of the old
+/// group may still arrive once members have moved to the new group. The
+/// bridge keeps the old group until the new group has advanced `window`
+/// epochs and routes these messages to it. All other messages are processed
+/// by the new group.
+///
+/// The old group is read-only: only application messages are processed by
+/// it, and messages of other types sent to it are rejected with
+/// [`MlsError::GroupUsedAfterReInit`]. If the old and new groups have the
+/// same identifier, messages are only routed to the old group if their epoch
+/// is newer than the current epoch of the new group.
+///
+/// Since [`Group::get_reinit_client`] consumes the group, the old group given
+/// to the bridge is usually a clone of it.
+#[derive(Clone)]
+pub struct ReinitBridge<C: ClientConfig + Clone> {
+    old_group: Option<Group<C>>,
+    new_group: Group<C>,
+    close_at_epoch: u64,
+}
+
+impl<C: ClientConfig + Clone> ReinitBridge<C> {
+    /// Bridge from `old_group`, which must have a pending reinit, to
+    /// `new_group`, created or joined with a [`ReinitClient`].
+    pub fn new(old_group: Group<C>, new_group: Group<C>, window: u64) -> Result<Self, MlsError> {
+        if old_group.state.pending_reinit.is_none() {
+            return Err(MlsError::PendingReInitNotFound);
+        }
+
+        let close_at_epoch = new_group.current_epoch().saturating_add(window);
+
+        Ok(Self {
+            old_group: Some(old_group),
+            new_group,
+            close_at_epoch,
+        })
+    }
+
+    /// Group replacing the reinitialized group.
+    pub fn new_group(&self) -> &Group<C> {
+        &self.new_group
+    }
+
+    /// Mutable access to the group replacing the reinitialized group, to
+    /// send messages and commits.
+    pub fn new_group_mut(&mut self) -> &mut Group<C> {
+        &mut self.new_group
+    }
+
+    /// Reinitialized group, if it is still readable.
+    pub fn old_group(&self) -> Option<&Group<C>> {
+        self.old_group.as_ref()
+    }
+
+    /// Stop routing messages to the old group before the end of the window.
+    pub fn close_old_group(&mut self) {
+        self.old_group = None;
+    }
+
+    /// Drop the old group and return the new group.
+    pub fn into_new_group(self) -> Group<C> {
+        self.new_group
+    }
+
+    /// Process an inbound message with the old group if it belongs to it,
+    /// and with the new group otherwise. See [`Group::process_incoming_message`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn process_incoming_message(
+        &mut self,
+        message: MlsMessage,
+    ) -> Result<ReceivedMessage, MlsError> {
+        if self.new_group.current_epoch() >= self.close_at_epoch {
+            self.old_group = None;
+        }
+
+        let new_group = &mut self.new_group;
+
+        let old_group = self
+            .old_group
+            .as_mut()
+            .filter(|old_group| is_old_group_message(old_group, new_group, &message));
+
+        let res = match old_group {
+            Some(_) if !message.is_application_message() => Err(MlsError::GroupUsedAfterReInit),
+            Some(old_group) => old_group.process_incoming_message(message).await,
+            None => new_group.process_incoming_message(message).await,
+        };
+
+        if self.new_group.current_epoch() >= self.close_at_epoch {
+            self.old_group = None;
+        }
+
+        res
+    }
+}
+
+fn is_old_group_message<C: ClientConfig + Clone>(
+    old_group: &Group<C>,
+    new_group: &Group<C>,
+    message: &MlsMessage,
+) -> bool {
+    let (Some(group_id), Some(epoch)) = (message.group_id(), message.epoch()) else {
+        return false;
+    };
+
+    group_id == old_group.group_id()
+        && epoch <= old_group.current_epoch()
+        && (group_id != new_group.group_id() || epoch > new_group.current_epoch())
+}
+
+/// Group whose secrets were lost by all of its members, to be replaced with
+/// [`Client::recover_group`].
+#[derive(Clone, Debug)]
+pub struct LostGroup<'a> {
+    group_id: Vec<u8>,
+    epoch: u64,
+    tree: ExportedTree<'a>,
+}
+
+impl<'a> LostGroup<'a> {
+    /// Lost group `group_id`, whose last known epoch is `epoch` and
+    /// ratchet tree is `tree`, as exported with [`Group::export_tree`].
+    pub fn new(group_id: Vec<u8>, epoch: u64, tree: ExportedTree<'a>) -> Self {
+        Self {
+            group_id,
+            epoch,
+            tree,
+        }
+    }
+
+    fn check_policy<C: ClientConfig>(
+        &self,
+        config: &C,
+        creator: &SigningIdentity,
+    ) -> Result<(), MlsError> {
+        let recovery = GroupRecovery {
+            lost_group_id: &self.group_id,
+            lost_epoch: self.epoch,
+            creator,
+        };
+
+        config
+            .recovery_policy()
+            .filter(|policy| policy.allows(&recovery))
+            .map(|_| ())
+            .ok_or(MlsError::RecoveryNotAllowed)
+    }
+}
+
+impl<C> Client<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a new group replacing `lost_group`, whose secrets were lost by
+    /// all of its members, with the same members as its ratchet tree.
+    ///
+    /// The members in the tree are identified in the same way as in
+    /// [`Group::branch`]: each of `key_packages` must have the same
+    /// [identity](crate::IdentityProvider::identity) as a member other than
+    /// this client, and every member of the lost group must be covered.
+    /// The new group uses `new_group_id`, or a random group ID, and
+    /// `group_context_extensions`. Members join it with
+    /// [`Client::join_recovered_group`].
+    ///
+    /// This fails with [`MlsError::RecoveryNotAllowed`] unless the
+    /// [`RecoveryPolicy`](crate::group::RecoveryPolicy) of this client allows
+    /// its signing identity to recover `lost_group`. The policy is meant to
+    /// restrict recovery to administrators of the application, and only for
+    /// groups that no member is able to
+    /// [reinitialize](Group::propose_reinit) anymore.
+    ///
+    /// # Warning
+    ///
+    /// The new group is a fresh group with no cryptographic continuity with
+    /// the lost group, whose secrets are gone. In particular:
+    ///
+    /// * Nothing authenticates the tree of the lost group. The new group is
+    ///   only as trustworthy as the source of the tree.
+    /// * The messages of the lost group can't be decrypted in the new group.
+    /// * Unlike a reinit, no pre-shared key binds the new group to the lost
+    ///   one, and its welcome messages are regular welcome messages.
+    ///   Joiners can't verify that the creator of the new group was a member
+    ///   of the lost group, only that their policy allows them to recover it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn recover_group(
+        &self,
+        lost_group: &LostGroup<'_>,
+        new_group_id: Option<Vec<u8>>,
+        group_context_extensions: ExtensionList,
+        key_packages: Vec<MlsMessage>,
+    ) -> Result<(Group<C>, Vec<MlsMessage>), MlsError> {
+        let (signing_identity, cipher_suite) = self.signing_identity()?;
+
+        lost_group.check_policy(&self.config, signing_identity)?;
+
+        let cipher_suite_provider =
+            cipher_suite_provider(self.config.crypto_provider(), cipher_suite)?;
+
+        let new_group_id = match new_group_id {
+            Some(group_id) => group_id,
+            None => cipher_suite_provider
+                .random_bytes_vec(cipher_suite_provider.kdf_extract_size())
+                .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?,
+        };
+
+        let new_group_params = ResumptionGroupParameters {
+            group_id: &new_group_id,
+            cipher_suite,
+            version: self.version,
+            extensions: &group_context_extensions,
+        };
+
+        let (group, welcome_messages) = resumption_create_group(
+            self.config.clone(),
+            key_packages,
+            &new_group_params,
+            signing_identity.clone(),
+            self.signer.clone().ok_or(MlsError::SignerNotFound)?,
+            None,
+        )
+        .await?;
+
+        verify_recovered_roster(lost_group, &group).await?;
+
+        Ok((group, welcome_messages))
+    }
+
+    /// Join a group that was created by [`Client::recover_group`] to replace
+    /// `lost_group`.
+    ///
+    /// This fails with [`MlsError::RecoveryNotAllowed`] if the
+    /// [`RecoveryPolicy`](crate::group::RecoveryPolicy) of this client
+    /// doesn't allow the creator of the new group to recover `lost_group`,
+    /// and with [`MlsError::RecoveryRosterMismatch`] if the members of the
+    /// new group are not the members of `lost_group`. See the warnings of
+    /// [`Client::recover_group`] before accepting to join.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn join_recovered_group(
+        &self,
+        lost_group: &LostGroup<'_>,
+        welcome: &MlsMessage,
+        tree_data: Option<ExportedTree<'_>>,
+    ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
+        let (group, new_member_info) = Group::<C>::from_welcome_message(
+            welcome,
+            tree_data,
+            self.config.clone(),
+            self.signer.clone().ok_or(MlsError::SignerNotFound)?,
+            None,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
+        )
+        .await?;
+
+        // The creator of a group is its first member.
+        let creator = group
+            .member_at_index(0)
+            .ok_or(MlsError::RecoveryNotAllowed)?;
+
+        lost_group.check_policy(&self.config, &creator.signing_identity)?;
+
+        verify_recovered_roster(lost_group, &group).await?;
+
+        Ok((group, new_member_info))
     }
 }
 
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_recovered_roster<C: ClientConfig + Clone>(
+    lost_group: &LostGroup<'_>,
+    group: &Group<C>,
+) -> Result<(), MlsError> {
+    let identity_provider = group.config.identity_provider();
+    let extensions = &group.group_state().context.extensions;
+
+    let lost_members =
+        roster_identities(&lost_group.tree.0, &identity_provider, extensions).await?;
+
+    let members = roster_identities(
+        &group.current_epoch_tree().nodes,
+        &identity_provider,
+        extensions,
+    )
+    .await?;
+
+    if lost_members == members {
+        Ok(())
+    } else {
+        Err(MlsError::RecoveryRosterMismatch)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_reinit_roster<C: ClientConfig + Clone>(
+    old_roster: &NodeVec,
+    old_extensions: &ExtensionList,
+    group: &Group<C>,
+) -> Result<(), MlsError> {
+    let identity_provider = group.config.identity_provider();
+
+    let old_members = roster_identities(old_roster, &identity_provider, old_extensions).await?;
+
+    if old_members == group_roster_identities(group).await? {
+        Ok(())
+    } else {
+        Err(MlsError::ReInitRosterMismatch)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_subgroup_roster<C: ClientConfig + Clone>(
+    group: &Group<C>,
+    subgroup: &Group<C>,
+) -> Result<(), MlsError> {
+    let members = group_roster_identities(group).await?;
+
+    let all_members = group_roster_identities(subgroup)
+        .await?
+        .iter()
+        .all(|identity| members.binary_search(identity).is_ok());
+
+    if all_members {
+        Ok(())
+    } else {
+        Err(MlsError::SubgroupRosterMismatch)
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn group_roster_identities<C: ClientConfig + Clone>(
+    group: &Group<C>,
+) -> Result<Vec<Vec<u8>>, MlsError> {
+    roster_identities(
+        &group.current_epoch_tree().nodes,
+        &group.config.identity_provider(),
+        &group.group_state().context.extensions,
+    )
+    .await
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn roster_identities<I: IdentityProvider>(
+    nodes: &NodeVec,
+    identity_provider: &I,
+    extensions: &ExtensionList,
+) -> Result<Vec<Vec<u8>>, MlsError> {
+    let mut identities = Vec::new();
+
+    for (_, leaf) in nodes.non_empty_leaves() {
+        let identity = identity_provider
+            .identity(&leaf.signing_identity, extensions)
+            .await
+            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+
+        identities.push(identity);
+    }
+
+    identities.sort_unstable();
+
+    Ok(identities)
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 async fn resumption_create_group<C: ClientConfig + Clone>(
     config: C,
@@ -237,7 +640,7 @@ async fn resumption_create_group<C: ClientConfig + Clone>(
     new_group_params: &ResumptionGroupParameters<'_>,
     signing_identity: SigningIdentity,
     signer: SignatureSecretKey,
-    psk_input: PskSecretInput,
+    psk_input: Option<PskSecretInput>,
 ) -> Result<(Group<C>, Vec<MlsMessage>), MlsError> {
     // Create a new group with new parameters
     let mut group = Group::new(
@@ -251,8 +654,8 @@ async fn resumption_create_group<C: ClientConfig + Clone>(
     )
     .await?;
 
-    // Install the resumption psk in the new group
-    group.previous_psk = Some(psk_input);
+    // Install the resumption psk, if any, in the new group
+    group.previous_psk = psk_input;
 
     // Create a commit that adds new key packages and uses the resumption PSK
     let mut commit = group.commit_builder();
@@ -282,8 +685,16 @@ async fn resumption_join_group<C: ClientConfig + Clone>(
 ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
     let psk_input = Some(psk_input);
 
-    let (group, new_member_info) =
-        Group::<C>::from_welcome_message(welcome, tree_data, config, signer, psk_input).await?;
+    let (group, new_member_info) = Group::<C>::from_welcome_message(
+        welcome,
+        tree_data,
+        config,
+        signer,
+        psk_input,
+        #[cfg(feature = "by_ref_proposal")]
+        None,
+    )
+    .await?;
 
     if group.protocol_version() != expected_new_group_params.version {
         Err(MlsError::ProtocolVersionMismatch)
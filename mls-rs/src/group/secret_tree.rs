@@ -53,7 +53,7 @@ struct TreeSecret(
 
 impl Debug for TreeSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        mls_rs_core::debug::pretty_bytes(&self.0)
+        mls_rs_core::debug::pretty_secret(&self.0)
             .named("TreeSecret")
             .fmt(f)
     }
@@ -135,6 +135,12 @@ impl<T: TreeIndex> TreeSecretsVec<T> {
     }
 }
 
+// The tree only stores the secrets that are still needed: a new tree holds nothing but the
+// encryption secret at its root, and nodes are derived lazily and deleted as soon as their
+// children are derived, per the deletion schedule of RFC 9420 section 9.2. The stored state is
+// therefore already the minimal one that preserves forward secrecy. Persisting only the
+// encryption secret and re-deriving ratchets on load would make keys of already processed
+// messages recoverable from storage, so it is deliberately not supported.
 #[derive(Clone, Debug, PartialEq, MlsEncode, MlsDecode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecretTree<T: TreeIndex> {
@@ -331,8 +337,8 @@ pub struct MessageKeyData {
 impl Debug for MessageKeyData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("MessageKeyData")
-            .field("nonce", &mls_rs_core::debug::pretty_bytes(&self.nonce))
-            .field("key", &mls_rs_core::debug::pretty_bytes(&self.key))
+            .field("nonce", &mls_rs_core::debug::pretty_secret(&self.nonce))
+            .field("key", &mls_rs_core::debug::pretty_secret(&self.key))
             .field("generation", &self.generation)
             .finish()
     }
@@ -544,7 +550,7 @@ impl SecretKeyRatchet {
             cipher_suite_provider,
             self.secret.as_ref(),
             label,
-            &self.generation.to_be_bytes(),
+            &self.generation.mls_encode_to_vec()?,
             Some(len),
         )
         .await
@@ -637,7 +643,6 @@ mod tests {
         crypto::test_utils::{
             test_cipher_suite_provider, try_test_cipher_suite_provider, TestCryptoProvider,
         },
-        tree_kem::node::NodeIndex,
     };
 
     #[cfg(not(mls_build_async))]
@@ -871,7 +876,7 @@ mod tests {
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn get_ratchet_data(
-        secret_tree: &mut SecretTree<NodeIndex>,
+        secret_tree: &mut SecretTree<u32>,
         cipher_suite: CipherSuite,
     ) -> Vec<Ratchet> {
         let provider = test_cipher_suite_provider(cipher_suite);
@@ -0,0 +1,307 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::identity::SigningIdentity;
+
+use crate::{client::MlsError, MlsMessage};
+
+use super::{ApplicationMessageDescription, ClientConfig, Group};
+
+const SEQUENCE_LABEL: &[u8] = b"mls-rs sequence number";
+
+/// Authenticated data of an application message that carries the sequence
+/// number of the message among the messages of its sender.
+///
+/// Like any authenticated data, the sequence number is covered by the AEAD
+/// and the signature of the sender. Messages carrying a sequence number are
+/// created and checked with a [`MessageSequencer`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[non_exhaustive]
+pub struct SequencedAuthenticatedData {
+    /// Sequence number of the message, starting at 0.
+    pub sequence: u64,
+    /// Authenticated data provided by the application.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub authenticated_data: Vec<u8>,
+}
+
+impl SequencedAuthenticatedData {
+    pub fn new(sequence: u64, authenticated_data: Vec<u8>) -> Self {
+        Self {
+            sequence,
+            authenticated_data,
+        }
+    }
+
+    /// Serialize to the authenticated data of a message.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        let mut bytes = SEQUENCE_LABEL.to_vec();
+        self.mls_encode(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parse the authenticated data of a message, returning `None` if it
+    /// carries no sequence number.
+    pub fn from_bytes(authenticated_data: &[u8]) -> Result<Option<Self>, MlsError> {
+        let Some(mut data) = authenticated_data.strip_prefix(SEQUENCE_LABEL) else {
+            return Ok(None);
+        };
+
+        Self::mls_decode(&mut data).map(Some).map_err(Into::into)
+    }
+}
+
+/// Application message accepted by [`MessageSequencer::check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SequencedMessage {
+    /// Sequence number of the message.
+    pub sequence: u64,
+    /// Number of messages of the sender that were skipped since the last
+    /// message received from it, for instance because they were lost.
+    pub skipped: u64,
+    /// Authenticated data provided by the application.
+    pub authenticated_data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+struct SenderSequence {
+    leaf_index: u32,
+    signing_identity: SigningIdentity,
+    last: u64,
+}
+
+/// Sequence numbers of the application messages of a group, giving replay
+/// and ordering protection at the application layer.
+///
+/// The sequencer numbers the messages sent by this member, binding the
+/// number into their authenticated data, and checks that the messages
+/// received from each other member carry strictly increasing numbers. Gaps
+/// are allowed, so lost messages don't prevent later ones from being
+/// accepted, but a message replayed or delivered after a later one is
+/// rejected.
+///
+/// Senders are identified by their leaf index and signing identity. When
+/// the signing identity at a leaf changes, for instance because a new member
+/// took the leaf of a removed one, its sequence restarts.
+///
+/// A sequencer is meant to be used for a single group, and must be
+/// persisted along with the group, using [`to_bytes`](Self::to_bytes),
+/// for the protection to hold across restarts.
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MessageSequencer {
+    next: u64,
+    senders: Vec<SenderSequence>,
+}
+
+impl MessageSequencer {
+    /// Create a sequencer for a group no messages were sent in yet.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sequence number of the next message sent by this member.
+    pub fn next_sequence(&self) -> u64 {
+        self.next
+    }
+
+    /// Encrypt an application message with
+    /// [`Group::encrypt_application_message`], binding the next sequence
+    /// number into its authenticated data.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn encrypt_application_message<C>(
+        &mut self,
+        group: &mut Group<C>,
+        message: &[u8],
+        authenticated_data: Vec<u8>,
+    ) -> Result<MlsMessage, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let authenticated_data =
+            SequencedAuthenticatedData::new(self.next, authenticated_data).to_bytes()?;
+
+        let message = group
+            .encrypt_application_message(message, authenticated_data)
+            .await?;
+
+        self.next += 1;
+
+        Ok(message)
+    }
+
+    /// Check the sequence number of an application message received in
+    /// `group`.
+    ///
+    /// [`MlsError::MissingSequenceNumber`] is returned for messages that
+    /// carry no sequence number, and [`MlsError::StaleSequenceNumber`] for
+    /// messages whose sequence number is not greater than the one of the
+    /// last message accepted from the same sender.
+    pub fn check<C>(
+        &mut self,
+        group: &Group<C>,
+        message: &ApplicationMessageDescription,
+    ) -> Result<SequencedMessage, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let sender = message.sender_index;
+
+        let data = SequencedAuthenticatedData::from_bytes(&message.authenticated_data)?
+            .ok_or(MlsError::MissingSequenceNumber(sender))?;
+
+        let signing_identity = group
+            .member_at_index(sender)
+            .map(|member| member.signing_identity);
+
+        let position = self.senders.iter().position(|s| s.leaf_index == sender);
+
+        let state = position.map(|i| &mut self.senders[i]).filter(|state| {
+            signing_identity
+                .as_ref()
+                .map_or(true, |identity| &state.signing_identity == identity)
+        });
+
+        let skipped = match state {
+            Some(state) if data.sequence <= state.last => {
+                return Err(MlsError::StaleSequenceNumber(sender, data.sequence));
+            }
+            Some(state) => {
+                let skipped = data.sequence - state.last - 1;
+                state.last = data.sequence;
+                skipped
+            }
+            None => {
+                let signing_identity = signing_identity.ok_or(MlsError::MemberNotFound)?;
+
+                let state = SenderSequence {
+                    leaf_index: sender,
+                    signing_identity,
+                    last: data.sequence,
+                };
+
+                match position {
+                    Some(i) => self.senders[i] = state,
+                    None => self.senders.push(state),
+                }
+
+                data.sequence
+            }
+        };
+
+        Ok(SequencedMessage {
+            sequence: data.sequence,
+            skipped,
+            authenticated_data: data.authenticated_data,
+        })
+    }
+
+    /// Serialize the sequencer, to persist it along with the group.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Deserialize a sequencer serialized with [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            test_utils::{test_n_member_group, TestGroup},
+            ApplicationMessageDescription, ReceivedMessage,
+        },
+        MlsMessage,
+    };
+
+    use super::{MessageSequencer, SequencedAuthenticatedData};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn receive(group: &mut TestGroup, message: MlsMessage) -> ApplicationMessageDescription {
+        match group.process_message(message).await.unwrap() {
+            ReceivedMessage::ApplicationMessage(description) => description,
+            _ => panic!("expected an application message"),
+        }
+    }
+
+    #[test]
+    fn sequence_round_trips_through_authenticated_data() {
+        let data = SequencedAuthenticatedData::new(42, b"aad".to_vec());
+        let bytes = data.to_bytes().unwrap();
+
+        assert_eq!(
+            SequencedAuthenticatedData::from_bytes(&bytes).unwrap(),
+            Some(data)
+        );
+
+        assert_eq!(
+            SequencedAuthenticatedData::from_bytes(b"aad").unwrap(),
+            None
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn out_of_order_messages_are_rejected() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let mut alice = MessageSequencer::new();
+        let mut bob = MessageSequencer::new();
+
+        let mut messages = vec![];
+
+        for _ in 0..3 {
+            let message = alice
+                .encrypt_application_message(&mut groups[0].group, b"hello", b"aad".to_vec())
+                .await
+                .unwrap();
+
+            messages.push(message);
+        }
+
+        assert_eq!(alice.next_sequence(), 3);
+
+        let first = receive(&mut groups[1], messages[0].clone()).await;
+        let first = bob.check(&groups[1].group, &first).unwrap();
+        assert_eq!((first.sequence, first.skipped), (0, 0));
+        assert_eq!(first.authenticated_data, b"aad");
+
+        let third = receive(&mut groups[1], messages[2].clone()).await;
+        let third = bob.check(&groups[1].group, &third).unwrap();
+        assert_eq!((third.sequence, third.skipped), (2, 1));
+
+        let mut bob = MessageSequencer::from_bytes(&bob.to_bytes().unwrap()).unwrap();
+
+        let second = receive(&mut groups[1], messages[1].clone()).await;
+        let res = bob.check(&groups[1].group, &second);
+        assert_matches!(res, Err(MlsError::StaleSequenceNumber(0, 1)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn messages_without_sequence_number_are_rejected() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let message = groups[0]
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let message = receive(&mut groups[1], message).await;
+        let res = MessageSequencer::new().check(&groups[1].group, &message);
+
+        assert_matches!(res, Err(MlsError::MissingSequenceNumber(0)));
+    }
+}
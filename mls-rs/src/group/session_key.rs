@@ -0,0 +1,302 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::secret::Secret;
+
+use crate::client::MlsError;
+
+use super::{ClientConfig, Group};
+
+const SESSION_KEY_LABEL: &[u8] = b"session key";
+
+/// Identifier of a [`SessionKey`].
+///
+/// The identifier is meant to be sent along with the data protected by the
+/// key, so that the receiver can derive the same key with
+/// [`SessionKeyRing::get`].
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionKeyId {
+    /// Epoch of the group the key was derived in.
+    pub epoch: u64,
+    /// Label of the sub-protocol using the key.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub label: Vec<u8>,
+    /// Generation of the key within the epoch.
+    pub generation: u32,
+}
+
+impl Debug for SessionKeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionKeyId")
+            .field("epoch", &self.epoch)
+            .field("label", &mls_rs_core::debug::pretty_bytes(&self.label))
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+/// Key of a sub-protocol run by the members of a group, such as a file
+/// transfer or a call.
+///
+/// The key is exported from the key schedule of the epoch in
+/// [`SessionKeyId::epoch`], with a context made of the label and generation
+/// of the key, so that all members derive the same key for the same
+/// identifier, and keys change with every epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionKey {
+    id: SessionKeyId,
+    key: Secret,
+}
+
+impl SessionKey {
+    /// Identifier of the key.
+    pub fn id(&self) -> &SessionKeyId {
+        &self.id
+    }
+
+    /// Secret key.
+    pub fn key(&self) -> &Secret {
+        &self.key
+    }
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct SessionKeyContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    label: &'a [u8],
+    generation: u32,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Derive the key of generation `generation` of the sub-protocol
+    /// identified by `label` in the current epoch.
+    ///
+    /// [`SessionKeyRing`] keeps track of the keys of a sub-protocol across
+    /// epochs.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn session_key(
+        &self,
+        label: &[u8],
+        generation: u32,
+        len: usize,
+    ) -> Result<SessionKey, MlsError> {
+        let context = SessionKeyContext { label, generation }.mls_encode_to_vec()?;
+        let key = self.export_secret(SESSION_KEY_LABEL, &context, len).await?;
+
+        let id = SessionKeyId {
+            epoch: self.current_epoch(),
+            label: label.to_vec(),
+            generation,
+        };
+
+        Ok(SessionKey { id, key })
+    }
+}
+
+/// Keys of a sub-protocol, rotating with the epochs of a group.
+///
+/// The ring derives a new key, of generation 0, the first time it is used in
+/// a new epoch. Within an epoch, the sub-protocol can move to the next
+/// generation with [`rotate`](Self::rotate), for instance when a call
+/// participant leaves. Keys of up to `retained_epochs` prior epochs are kept
+/// so that data protected with them, possibly received after a commit, can
+/// still be processed. Keys of prior epochs can't be derived anymore, so the
+/// receiving end only finds them if it used the ring in that epoch.
+#[derive(Clone, Debug)]
+pub struct SessionKeyRing {
+    label: Vec<u8>,
+    key_len: usize,
+    retained_epochs: u64,
+    keys: Vec<SessionKey>,
+}
+
+impl SessionKeyRing {
+    /// Create a ring for the sub-protocol identified by `label`, deriving
+    /// keys of `key_len` bytes.
+    pub fn new(label: Vec<u8>, key_len: usize, retained_epochs: u64) -> Self {
+        Self {
+            label,
+            key_len,
+            retained_epochs,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Label of the sub-protocol.
+    pub fn label(&self) -> &[u8] {
+        &self.label
+    }
+
+    /// Key of the last generation of the current epoch of `group`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn current<C>(&mut self, group: &Group<C>) -> Result<&SessionKey, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        match self.last_generation(group.current_epoch()) {
+            Some(i) => Ok(&self.keys[i]),
+            None => self.derive(group, 0).await,
+        }
+    }
+
+    /// Move to the next generation in the current epoch of `group`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn rotate<C>(&mut self, group: &Group<C>) -> Result<&SessionKey, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let generation = match self.last_generation(group.current_epoch()) {
+            Some(i) => self.keys[i].id.generation + 1,
+            None => 0,
+        };
+
+        self.derive(group, generation).await
+    }
+
+    /// Key with identifier `id`, typically received along with the data it
+    /// protects.
+    ///
+    /// Keys of the current epoch of `group` are derived as needed, while keys
+    /// of prior epochs must have been retained. Otherwise,
+    /// [`MlsError::SessionKeyNotFound`] is returned.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn get<C>(
+        &mut self,
+        group: &Group<C>,
+        id: &SessionKeyId,
+    ) -> Result<&SessionKey, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        if id.label != self.label {
+            return Err(MlsError::SessionKeyNotFound);
+        }
+
+        match self.keys.iter().position(|key| &key.id == id) {
+            Some(i) => Ok(&self.keys[i]),
+            None if id.epoch == group.current_epoch() => self.derive(group, id.generation).await,
+            None => Err(MlsError::SessionKeyNotFound),
+        }
+    }
+
+    fn last_generation(&self, epoch: u64) -> Option<usize> {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| key.id.epoch == epoch)
+            .max_by_key(|(_, key)| key.id.generation)
+            .map(|(i, _)| i)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn derive<C>(
+        &mut self,
+        group: &Group<C>,
+        generation: u32,
+    ) -> Result<&SessionKey, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let key = group
+            .session_key(&self.label, generation, self.key_len)
+            .await?;
+
+        let oldest = group.current_epoch().saturating_sub(self.retained_epochs);
+        self.keys.retain(|k| k.id.epoch >= oldest);
+        self.keys.push(key);
+
+        Ok(&self.keys[self.keys.len() - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::{test_n_member_group, TestGroup},
+    };
+
+    use super::SessionKeyRing;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn next_epoch(groups: &mut [TestGroup]) {
+        let commit = groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        groups[1]
+            .process_message(commit.commit_message)
+            .await
+            .unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_derive_the_same_keys() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let alice_key = groups[0].group.session_key(b"call", 0, 32).await.unwrap();
+        let bob_key = groups[1].group.session_key(b"call", 0, 32).await.unwrap();
+
+        assert_eq!(alice_key, bob_key);
+        assert_eq!(alice_key.key().as_bytes().len(), 32);
+
+        let other_label = groups[0].group.session_key(b"file", 0, 32).await.unwrap();
+        assert_ne!(alice_key.key(), other_label.key());
+
+        let other_generation = groups[0].group.session_key(b"call", 1, 32).await.unwrap();
+        assert_ne!(alice_key.key(), other_generation.key());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn ring_rotates_with_epochs() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let mut alice_ring = SessionKeyRing::new(b"call".to_vec(), 32, 1);
+        let mut bob_ring = SessionKeyRing::new(b"call".to_vec(), 32, 1);
+
+        let first = alice_ring.current(&groups[0].group).await.unwrap().clone();
+        assert_eq!(first.id().generation, 0);
+
+        let rotated = alice_ring.rotate(&groups[0].group).await.unwrap().clone();
+        assert_eq!(rotated.id().generation, 1);
+
+        let received = bob_ring.get(&groups[1].group, rotated.id()).await.unwrap();
+        assert_eq!(received, &rotated);
+
+        next_epoch(&mut groups).await;
+
+        let next = alice_ring.current(&groups[0].group).await.unwrap().clone();
+        assert_eq!(next.id().epoch, rotated.id().epoch + 1);
+        assert_eq!(next.id().generation, 0);
+
+        // Bob used the ring in the prior epoch, so the key was retained.
+        let late = bob_ring.get(&groups[1].group, rotated.id()).await.unwrap();
+        assert_eq!(late, &rotated);
+
+        let res = bob_ring.get(&groups[1].group, first.id()).await.map(|_| ());
+        assert_matches!(res, Err(MlsError::SessionKeyNotFound));
+
+        next_epoch(&mut groups).await;
+        bob_ring.current(&groups[1].group).await.unwrap();
+
+        let res = bob_ring
+            .get(&groups[1].group, rotated.id())
+            .await
+            .map(|_| ());
+        assert_matches!(res, Err(MlsError::SessionKeyNotFound));
+    }
+}
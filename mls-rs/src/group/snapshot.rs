@@ -10,12 +10,14 @@ use crate::{
         GroupState, InterimTranscriptHash, ReInitProposal, TreeKemPublic,
     },
     tree_kem::TreeKemPrivate,
+    MlsMessage,
 };
 
 #[cfg(feature = "by_ref_proposal")]
 use crate::{
     crypto::{HpkePublicKey, HpkeSecretKey},
     group::ProposalRef,
+    time::MlsTime,
 };
 
 #[cfg(feature = "by_ref_proposal")]
@@ -30,15 +32,23 @@ use mls_rs_core::identity::IdentityProvider;
 #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
 use std::collections::HashMap;
 
-#[cfg(all(feature = "by_ref_proposal", not(feature = "std")))]
 use alloc::vec::Vec;
 
 use super::{cipher_suite_provider, epoch::EpochSecrets, state_repo::GroupStateRepository};
 
-#[derive(Debug, PartialEq, Clone, MlsEncode, MlsDecode, MlsSize)]
+/// Version of the format of [`Snapshot`].
+///
+/// Version 2 allows the ratchet tree to be left out of the state and loaded
+/// from the tree checkpoints of the state repository, and records the
+/// removal, the reissuable welcome messages and the external removal
+/// deadline of the group. Snapshots of version 1 are still decoded, and
+/// written again in the current version.
+pub(crate) const SNAPSHOT_VERSION: u16 = 2;
+
+#[derive(Debug, PartialEq, Clone, MlsEncode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Snapshot {
-    version: u16,
+    pub(crate) version: u16,
     pub(crate) state: RawGroupState,
     private_tree: TreeKemPrivate,
     epoch_secrets: EpochSecrets,
@@ -49,6 +59,87 @@ pub(crate) struct Snapshot {
     pending_updates: Vec<(HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>))>,
     pending_commit: Option<CommitGeneration>,
     signer: SignatureSecretKey,
+    #[cfg_attr(feature = "serde", serde(with = "welcomes_serde"))]
+    reissuable_welcomes: Vec<MlsMessage>,
+    removed: bool,
+    #[cfg(feature = "by_ref_proposal")]
+    external_removal_deadline: Option<(u64, MlsTime)>,
+}
+
+// Snapshots of version 1 end with the signer. The fields added in version 2
+// take the value of a group that was never removed and has nothing pending,
+// and the ratchet tree is always held by the state.
+impl MlsDecode for Snapshot {
+    fn mls_decode(reader: &mut &[u8]) -> Result<Self, mls_rs_codec::Error> {
+        let version = u16::mls_decode(reader)?;
+
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(mls_rs_codec::Error::Custom(6));
+        }
+
+        let state = RawGroupState::mls_decode(reader)?;
+        let private_tree = TreeKemPrivate::mls_decode(reader)?;
+        let epoch_secrets = EpochSecrets::mls_decode(reader)?;
+        let key_schedule = KeySchedule::mls_decode(reader)?;
+        #[cfg(feature = "by_ref_proposal")]
+        let pending_updates = MlsDecode::mls_decode(reader)?;
+        let pending_commit = Option::mls_decode(reader)?;
+        let signer = SignatureSecretKey::mls_decode(reader)?;
+
+        let (reissuable_welcomes, removed) = if version >= 2 {
+            (Vec::mls_decode(reader)?, bool::mls_decode(reader)?)
+        } else {
+            (Vec::new(), false)
+        };
+
+        #[cfg(feature = "by_ref_proposal")]
+        let external_removal_deadline = if version >= 2 {
+            Option::mls_decode(reader)?
+        } else {
+            None
+        };
+
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            state,
+            private_tree,
+            epoch_secrets,
+            key_schedule,
+            #[cfg(feature = "by_ref_proposal")]
+            pending_updates,
+            pending_commit,
+            signer,
+            reissuable_welcomes,
+            removed,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline,
+        })
+    }
+}
+
+// Welcome messages have no serde implementation and are serialized as their
+// MLS encoding.
+#[cfg(feature = "serde")]
+mod welcomes_serde {
+    use alloc::vec::Vec;
+    use mls_rs_codec::{MlsDecode, MlsEncode};
+    use serde::{de::Error as _, ser::Error as _, Deserializer, Serializer};
+
+    use crate::MlsMessage;
+
+    pub fn serialize<S: Serializer>(v: &Vec<MlsMessage>, s: S) -> Result<S::Ok, S::Error> {
+        let bytes = v
+            .mls_encode_to_vec()
+            .map_err(|e| S::Error::custom(format_args!("{e:?}")))?;
+
+        mls_rs_core::vec_serde::serialize(&bytes, s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<MlsMessage>, D::Error> {
+        let bytes = mls_rs_core::vec_serde::deserialize(d)?;
+
+        Vec::mls_decode(&mut &*bytes).map_err(|e| D::Error::custom(format_args!("{e:?}")))
+    }
 }
 
 #[derive(Debug, MlsEncode, MlsDecode, MlsSize, PartialEq, Clone)]
@@ -151,9 +242,25 @@ where
     /// Write the current state of the group to the
     /// [`GroupStorageProvider`](crate::GroupStateStorage)
     /// that is currently in use by the group.
+    ///
+    /// If the group has a [`GroupPolicyExt`](crate::extension::group_policy::GroupPolicyExt)
+    /// limiting the retention of prior epochs, older epochs are then deleted,
+    /// which fails with [`MlsError::EpochDeletionUnsupported`] if the storage
+    /// doesn't support it.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn write_to_storage(&mut self) -> Result<(), MlsError> {
-        self.state_repo.write_to_storage(self.snapshot()).await
+        self.state_repo.write_to_storage(self.snapshot()).await?;
+
+        #[cfg(feature = "prior_epoch")]
+        if let Some(retention) = self.group_policy()?.and_then(|p| p.max_epoch_retention()) {
+            let oldest = self.current_epoch().saturating_sub(retention.into());
+
+            if oldest > 0 {
+                self.state_repo.delete_epochs_before(oldest).await?;
+            }
+        }
+
+        Ok(())
     }
 
     pub(crate) fn snapshot(&self) -> Snapshot {
@@ -165,13 +272,18 @@ where
             pending_updates: self.pending_updates.clone(),
             pending_commit: self.pending_commit.clone(),
             epoch_secrets: self.epoch_secrets.clone(),
-            version: 1,
+            version: SNAPSHOT_VERSION,
             signer: self.signer.clone(),
+            reissuable_welcomes: self.reissuable_welcomes.clone(),
+            removed: self.removed,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline: self.external_removal_deadline,
         }
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub(crate) async fn from_snapshot(config: C, snapshot: Snapshot) -> Result<Self, MlsError> {
+    #[cfg_attr(not(feature = "prior_epoch"), allow(unused_mut))]
+    pub(crate) async fn from_snapshot(config: C, mut snapshot: Snapshot) -> Result<Self, MlsError> {
         let cipher_suite_provider = cipher_suite_provider(
             config.crypto_provider(),
             snapshot.state.context.cipher_suite,
@@ -188,6 +300,20 @@ where
             None,
         )?;
 
+        #[cfg(feature = "prior_epoch")]
+        let mut state_repo =
+            state_repo.with_tree_checkpoint_interval(config.tree_checkpoint_interval());
+
+        // A state written with tree checkpointing doesn't hold the tree,
+        // which a group always has. Tree hashes aren't stored with the tree
+        // and are computed again.
+        #[cfg(feature = "prior_epoch")]
+        if snapshot.state.public_tree.nodes.is_empty() {
+            let mut tree = state_repo.load_tree(snapshot.state.context.epoch).await?;
+            tree.tree_hash(&cipher_suite_provider).await?;
+            snapshot.state.public_tree = tree;
+        }
+
         Ok(Group {
             config,
             state: snapshot
@@ -202,6 +328,15 @@ where
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: snapshot.pending_updates,
             pending_commit: snapshot.pending_commit,
+            // Commit artifacts are kept in memory only, see
+            // `Group::pending_commit_artifacts`.
+            pending_commit_artifacts: None,
+            reissuable_welcomes: snapshot.reissuable_welcomes,
+            removed: snapshot.removed,
+            transcript: None,
+            observed_commits: None,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline: snapshot.external_removal_deadline,
             #[cfg(test)]
             commit_modifiers: Default::default(),
             epoch_secrets: snapshot.epoch_secrets,
@@ -209,6 +344,8 @@ where
             cipher_suite_provider,
             #[cfg(feature = "psk")]
             previous_psk: None,
+            #[cfg(feature = "psk")]
+            psk_usage: Default::default(),
             signer: snapshot.signer,
         })
     }
@@ -250,8 +387,12 @@ pub(crate) mod test_utils {
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: Default::default(),
             pending_commit: None,
-            version: 1,
+            version: super::SNAPSHOT_VERSION,
             signer: vec![].into(),
+            reissuable_welcomes: vec![],
+            removed: false,
+            #[cfg(feature = "by_ref_proposal")]
+            external_removal_deadline: None,
         }
     }
 }
@@ -259,15 +400,47 @@ pub(crate) mod test_utils {
 #[cfg(test)]
 mod tests {
     use alloc::vec;
+    use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+    use mls_rs_core::crypto::SignatureSecretKey;
+
+    #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
+    use std::collections::HashMap;
+
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::crypto::{HpkePublicKey, HpkeSecretKey};
+
+    #[cfg(all(not(feature = "std"), feature = "by_ref_proposal"))]
+    use alloc::vec::Vec;
 
     use crate::{
         client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
         group::{
+            epoch::EpochSecrets,
+            key_schedule::KeySchedule,
             test_utils::{test_group, TestGroup},
-            Group,
+            CommitGeneration, Group,
         },
+        tree_kem::TreeKemPrivate,
     };
 
+    use super::{RawGroupState, Snapshot, SNAPSHOT_VERSION};
+
+    // Layout of the snapshots written before version 2.
+    #[derive(MlsEncode, MlsSize)]
+    struct SnapshotV1 {
+        version: u16,
+        state: RawGroupState,
+        private_tree: TreeKemPrivate,
+        epoch_secrets: EpochSecrets,
+        key_schedule: KeySchedule,
+        #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
+        pending_updates: HashMap<HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>)>,
+        #[cfg(all(not(feature = "std"), feature = "by_ref_proposal"))]
+        pending_updates: Vec<(HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>))>,
+        pending_commit: Option<CommitGeneration>,
+        signer: SignatureSecretKey,
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn snapshot_restore(group: TestGroup) {
         let snapshot = group.group.snapshot();
@@ -314,6 +487,49 @@ mod tests {
         snapshot_restore(group).await
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn snapshot_of_version_1_is_migrated() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        group.group.commit(vec![]).await.unwrap();
+
+        let snapshot = group.group.snapshot();
+
+        let v1 = SnapshotV1 {
+            version: 1,
+            state: snapshot.state.clone(),
+            private_tree: snapshot.private_tree.clone(),
+            epoch_secrets: snapshot.epoch_secrets.clone(),
+            key_schedule: snapshot.key_schedule.clone(),
+            #[cfg(feature = "by_ref_proposal")]
+            pending_updates: snapshot.pending_updates.clone(),
+            pending_commit: snapshot.pending_commit.clone(),
+            signer: snapshot.signer.clone(),
+        }
+        .mls_encode_to_vec()
+        .unwrap();
+
+        let migrated = Snapshot::from_bytes(&v1).unwrap();
+
+        assert_eq!(migrated.version, SNAPSHOT_VERSION);
+        assert!(migrated.reissuable_welcomes.is_empty());
+        assert!(!migrated.removed);
+        #[cfg(feature = "by_ref_proposal")]
+        assert_eq!(migrated.external_removal_deadline, None);
+
+        // The migrated snapshot is written in the current version.
+        let encoded = migrated.mls_encode_to_vec().unwrap();
+        assert_eq!(Snapshot::mls_decode(&mut &*encoded).unwrap(), migrated);
+
+        let mut restored = Group::from_snapshot(group.group.config.clone(), migrated)
+            .await
+            .unwrap();
+
+        assert!(Group::equal_group_state(&group.group, &restored));
+
+        restored.apply_pending_commit().await.unwrap();
+        assert_eq!(restored.current_epoch(), group.group.current_epoch() + 1);
+    }
+
     #[cfg(feature = "serde")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn serde() {
@@ -0,0 +1,359 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::error::IntoAnyError;
+
+use crate::{client::MlsError, crypto::CipherSuiteProvider};
+
+use super::{key_schedule::kdf_expand_with_label, ClientConfig, Group};
+
+const STATE_CHECK_LABEL: &[u8] = b"state check";
+const NONCE_LEN: usize = 16;
+
+/// Part of the state of a group compared by [`Group::check_state_response`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum GroupArtifact {
+    /// Hash of the ratchet tree, as recorded in the group context.
+    TreeHash,
+    /// Confirmed transcript hash, covering all commits since the group was
+    /// created.
+    TranscriptHash,
+    /// Extensions of the group context.
+    Extensions,
+    /// Key schedule of the epoch, checked through the epoch authenticator.
+    KeySchedule,
+}
+
+/// Result of comparing the states of two members of a group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDiagnosis {
+    /// Both members are in the same epoch and agree on all artifacts.
+    Consistent,
+    /// The members are in different epochs, so their artifacts can't be
+    /// compared. This is expected while a commit is being delivered.
+    EpochMismatch {
+        /// Epoch of the local member.
+        local: u64,
+        /// Epoch of the remote member.
+        remote: u64,
+    },
+    /// The members are in the same epoch but disagree on these artifacts,
+    /// in the order of [`GroupArtifact`].
+    Diverged(Vec<GroupArtifact>),
+}
+
+/// Digest of the state of a group in an epoch, exchanged by two members
+/// to detect whether their states diverged.
+///
+/// A member sends a challenge created with [`Group::state_challenge`], for
+/// instance in an application message. The other member diagnoses its own
+/// state against it and answers with
+/// [`Group::respond_to_state_challenge`], and the first member checks the
+/// answer with [`Group::check_state_response`]. The digest contains public
+/// hashes of the group context and a value derived from the epoch
+/// authenticator and the nonce of the challenge, which reveals nothing
+/// about the key schedule to non-members.
+#[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct StateDigest {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    nonce: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    tree_hash: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    transcript_hash: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    extensions_hash: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    key_schedule: Vec<u8>,
+}
+
+impl Debug for StateDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateDigest")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("nonce", &mls_rs_core::debug::pretty_bytes(&self.nonce))
+            .field(
+                "tree_hash",
+                &mls_rs_core::debug::pretty_bytes(&self.tree_hash),
+            )
+            .field(
+                "transcript_hash",
+                &mls_rs_core::debug::pretty_bytes(&self.transcript_hash),
+            )
+            .field(
+                "extensions_hash",
+                &mls_rs_core::debug::pretty_bytes(&self.extensions_hash),
+            )
+            .field(
+                "key_schedule",
+                &mls_rs_core::debug::pretty_bytes(&self.key_schedule),
+            )
+            .finish()
+    }
+}
+
+impl StateDigest {
+    /// Epoch of the member that created the digest.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Serialize the digest.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Deserialize a digest serialized with [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    fn diagnose(&self, remote: &StateDigest) -> StateDiagnosis {
+        if self.epoch != remote.epoch {
+            return StateDiagnosis::EpochMismatch {
+                local: self.epoch,
+                remote: remote.epoch,
+            };
+        }
+
+        let artifacts = [
+            (GroupArtifact::TreeHash, &self.tree_hash, &remote.tree_hash),
+            (
+                GroupArtifact::TranscriptHash,
+                &self.transcript_hash,
+                &remote.transcript_hash,
+            ),
+            (
+                GroupArtifact::Extensions,
+                &self.extensions_hash,
+                &remote.extensions_hash,
+            ),
+            (
+                GroupArtifact::KeySchedule,
+                &self.key_schedule,
+                &remote.key_schedule,
+            ),
+        ];
+
+        let diverged = artifacts
+            .into_iter()
+            .filter(|(_, local, remote)| local != remote)
+            .map(|(artifact, ..)| artifact)
+            .collect::<Vec<_>>();
+
+        if diverged.is_empty() {
+            StateDiagnosis::Consistent
+        } else {
+            StateDiagnosis::Diverged(diverged)
+        }
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a challenge, with a fresh nonce, for another member to compare
+    /// the state of the group with. See [`StateDigest`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn state_challenge(&self) -> Result<StateDigest, MlsError> {
+        let nonce = self
+            .cipher_suite_provider
+            .random_bytes_vec(NONCE_LEN)
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        self.state_digest(nonce).await
+    }
+
+    /// Compare the state of the group with a `challenge` created by another
+    /// member, returning the response to send back along with the diagnosis
+    /// of the local state.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn respond_to_state_challenge(
+        &self,
+        challenge: &StateDigest,
+    ) -> Result<(StateDigest, StateDiagnosis), MlsError> {
+        let response = self.state_digest(challenge.nonce.clone()).await?;
+        let diagnosis = response.diagnose(challenge);
+
+        Ok((response, diagnosis))
+    }
+
+    /// Compare the state of the group with the `response` of another member
+    /// to `challenge`, which was created by this member.
+    ///
+    /// The state is compared as it is now, which may be a later epoch than
+    /// the one in which `challenge` was created.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn check_state_response(
+        &self,
+        challenge: &StateDigest,
+        response: &StateDigest,
+    ) -> Result<StateDiagnosis, MlsError> {
+        if response.nonce != challenge.nonce {
+            return Err(MlsError::UnexpectedStateResponse);
+        }
+
+        let local = self.state_digest(challenge.nonce.clone()).await?;
+
+        if response.group_id != local.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        Ok(local.diagnose(response))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn state_digest(&self, nonce: Vec<u8>) -> Result<StateDigest, MlsError> {
+        let context = self.context();
+
+        let extensions_hash = self
+            .cipher_suite_provider
+            .hash(&context.extensions.mls_encode_to_vec()?)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let key_schedule = kdf_expand_with_label(
+            &self.cipher_suite_provider,
+            &self.key_schedule.authentication_secret,
+            STATE_CHECK_LABEL,
+            &nonce,
+            None,
+        )
+        .await?;
+
+        Ok(StateDigest {
+            group_id: context.group_id.clone(),
+            epoch: context.epoch,
+            nonce,
+            tree_hash: context.tree_hash.clone(),
+            transcript_hash: context.confirmed_transcript_hash.to_vec(),
+            extensions_hash,
+            key_schedule: key_schedule.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+    };
+
+    use super::{GroupArtifact, StateDiagnosis, StateDigest};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_in_sync_are_consistent() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let challenge = groups[0].group.state_challenge().await.unwrap();
+        let challenge = StateDigest::from_bytes(&challenge.to_bytes().unwrap()).unwrap();
+
+        let (response, diagnosis) = groups[1]
+            .group
+            .respond_to_state_challenge(&challenge)
+            .await
+            .unwrap();
+
+        assert_eq!(diagnosis, StateDiagnosis::Consistent);
+
+        let diagnosis = groups[0]
+            .group
+            .check_state_response(&challenge, &response)
+            .await
+            .unwrap();
+
+        assert_eq!(diagnosis, StateDiagnosis::Consistent);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_in_different_epochs_are_detected() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        let challenge = groups[0].group.state_challenge().await.unwrap();
+
+        let (_, diagnosis) = groups[1]
+            .group
+            .respond_to_state_challenge(&challenge)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            diagnosis,
+            StateDiagnosis::EpochMismatch {
+                local: 1,
+                remote: 2
+            }
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn diverging_artifacts_are_localized() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        groups[1].group.state.context.tree_hash[0] ^= 1;
+        groups[1].group.key_schedule.authentication_secret[0] ^= 1;
+
+        let challenge = groups[0].group.state_challenge().await.unwrap();
+
+        let (response, _) = groups[1]
+            .group
+            .respond_to_state_challenge(&challenge)
+            .await
+            .unwrap();
+
+        let diagnosis = groups[0]
+            .group
+            .check_state_response(&challenge, &response)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            diagnosis,
+            StateDiagnosis::Diverged(vec![GroupArtifact::TreeHash, GroupArtifact::KeySchedule])
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn response_to_another_challenge_is_rejected() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let challenge = groups[0].group.state_challenge().await.unwrap();
+        let other = groups[0].group.state_challenge().await.unwrap();
+
+        let (response, _) = groups[1]
+            .group
+            .respond_to_state_challenge(&other)
+            .await
+            .unwrap();
+
+        let res = groups[0]
+            .group
+            .check_state_response(&challenge, &response)
+            .await;
+
+        assert_matches!(res, Err(MlsError::UnexpectedStateResponse));
+    }
+}
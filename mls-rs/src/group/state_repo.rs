@@ -6,13 +6,17 @@ use crate::client::MlsError;
 use crate::{group::PriorEpoch, key_package::KeyPackageRef};
 
 use alloc::collections::VecDeque;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug};
+use core::mem;
 use mls_rs_codec::{MlsDecode, MlsEncode};
 use mls_rs_core::group::{EpochRecord, GroupState};
 use mls_rs_core::{error::IntoAnyError, group::GroupStateStorage, key_package::KeyPackageStorage};
 
 use super::snapshot::Snapshot;
+use super::tree_checkpoint::{StoredTree, TreeRecord};
+use crate::tree_kem::{node::NodeVec, TreeKemPublic};
 
 #[cfg(feature = "psk")]
 use crate::group::ResumptionPsk;
@@ -39,6 +43,8 @@ where
     group_id: Vec<u8>,
     storage: S,
     key_package_repo: K,
+    tree_checkpoint_interval: Option<u32>,
+    stored_tree: Option<StoredTree>,
 }
 
 impl<S, K> Debug for GroupStateRepository<S, K>
@@ -59,6 +65,7 @@ where
             )
             .field("storage", &self.storage)
             .field("key_package_repo", &self.key_package_repo)
+            .field("tree_checkpoint_interval", &self.tree_checkpoint_interval)
             .finish()
     }
 }
@@ -81,11 +88,22 @@ where
             pending_key_package_removal: key_package_to_remove,
             pending_commit: Default::default(),
             key_package_repo,
+            tree_checkpoint_interval: None,
+            stored_tree: None,
         })
     }
 
+    /// Store the ratchet tree apart from the group state, writing a full
+    /// tree every `interval` epochs and only the changes in between.
+    pub fn with_tree_checkpoint_interval(self, interval: Option<u32>) -> Self {
+        Self {
+            tree_checkpoint_interval: interval,
+            ..self
+        }
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn find_max_id(&self) -> Result<Option<u64>, MlsError> {
+    pub async fn find_max_id(&self) -> Result<Option<u64>, MlsError> {
         if let Some(max) = self.pending_commit.inserts.back().map(|e| e.epoch_id()) {
             Ok(Some(max))
         } else {
@@ -172,6 +190,33 @@ where
         .map_err(Into::into)
     }
 
+    /// Copy of the prior epoch `epoch_id`, read from the local caches or from
+    /// storage without caching it.
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn get_epoch(&self, epoch_id: u64) -> Result<Option<PriorEpoch>, MlsError> {
+        if let Some(min) = self.pending_commit.inserts.front().map(|e| e.epoch_id()) {
+            if epoch_id >= min {
+                return Ok(self
+                    .pending_commit
+                    .inserts
+                    .get((epoch_id - min) as usize)
+                    .cloned());
+            }
+        }
+
+        if let Some(i) = self.find_pending(epoch_id) {
+            return Ok(Some(self.pending_commit.updates[i].clone()));
+        }
+
+        self.storage
+            .epoch(&self.group_id, epoch_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .map(|epoch| PriorEpoch::mls_decode(&mut &*epoch).map_err(Into::into))
+            .transpose()
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn insert(&mut self, epoch: PriorEpoch) -> Result<(), MlsError> {
         if epoch.group_id() != self.group_id {
@@ -191,8 +236,48 @@ where
         Ok(())
     }
 
+    /// Write the pending changes and `group_snapshot` to storage in a single
+    /// transaction, along with the deletion of the key package used to join.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn write_to_storage(&mut self, group_snapshot: Snapshot) -> Result<(), MlsError> {
+        self.storage
+            .begin_transaction()
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        let res = match self.write_pending(group_snapshot).await {
+            Ok(()) => self
+                .storage
+                .commit_transaction()
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error())),
+            Err(e) => Err(e),
+        };
+
+        if res.is_err() {
+            // A failed commit may leave the transaction open. The error of
+            // the write or of the commit is more relevant than the one of the
+            // rollback, if any.
+            let _ = self.storage.rollback_transaction().await;
+        }
+
+        if res.is_err() {
+            // The tree records written may have been discarded. Starting over
+            // from a new checkpoint doesn't depend on them.
+            self.stored_tree = None;
+            return res;
+        }
+
+        self.pending_commit.inserts.clear();
+        self.pending_commit.updates.clear();
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn write_pending(&mut self, mut group_snapshot: Snapshot) -> Result<(), MlsError> {
+        let checkpoint = self.write_tree(&mut group_snapshot).await?;
+
         let inserts = self
             .pending_commit
             .inserts
@@ -217,6 +302,15 @@ where
             .await
             .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
 
+        // Older tree records are only needed until the state referencing
+        // the new checkpoint is written.
+        if let Some(epoch_id) = checkpoint {
+            self.storage
+                .delete_tree_records_before(&self.group_id, epoch_id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+        }
+
         if let Some(ref key_package_ref) = self.pending_key_package_removal {
             self.key_package_repo
                 .delete(key_package_ref)
@@ -224,12 +318,104 @@ where
                 .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
         }
 
-        self.pending_commit.inserts.clear();
-        self.pending_commit.updates.clear();
-
         Ok(())
     }
 
+    /// If tree checkpointing is enabled and supported by the storage, write
+    /// the ratchet tree of `snapshot` as a tree record and remove it from
+    /// `snapshot`. Returns the epoch of the full tree written, if any.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn write_tree(&mut self, snapshot: &mut Snapshot) -> Result<Option<u64>, MlsError> {
+        let Some(interval) = self.tree_checkpoint_interval else {
+            return Ok(None);
+        };
+
+        let epoch = snapshot.state.context.epoch;
+        let nodes = &snapshot.state.public_tree.nodes;
+        let record = StoredTree::next_record(self.stored_tree.as_ref(), epoch, nodes, interval);
+
+        if let Some(record) = &record {
+            let records = vec![EpochRecord::new(epoch, record.mls_encode_to_vec()?)];
+
+            let supported = self
+                .storage
+                .write_tree_records(&self.group_id, records)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            if !supported {
+                self.tree_checkpoint_interval = None;
+                return Ok(None);
+            }
+        }
+
+        let checkpoint = matches!(record, Some(TreeRecord::Checkpoint(_))).then_some(epoch);
+
+        let checkpoint_epoch = checkpoint
+            .or(self.stored_tree.as_ref().map(|t| t.checkpoint_epoch))
+            .unwrap_or(epoch);
+
+        self.stored_tree = Some(StoredTree {
+            epoch,
+            checkpoint_epoch,
+            nodes: mem::take(&mut snapshot.state.public_tree).nodes,
+        });
+
+        Ok(checkpoint)
+    }
+
+    /// Rebuild the ratchet tree of `epoch_id` from the stored tree records,
+    /// for a group state written with tree checkpointing.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn load_tree(&mut self, epoch_id: u64) -> Result<TreeKemPublic, MlsError> {
+        let mut deltas = Vec::new();
+        let mut record_epoch = epoch_id;
+
+        let mut nodes: NodeVec = loop {
+            let data = self
+                .storage
+                .tree_record(&self.group_id, record_epoch)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+                .ok_or(MlsError::TreeRecordNotFound(record_epoch))?;
+
+            match TreeRecord::mls_decode(&mut &*data)? {
+                TreeRecord::Checkpoint(nodes) => break nodes,
+                TreeRecord::Delta(delta) if delta.base_epoch < record_epoch => {
+                    record_epoch = delta.base_epoch;
+                    deltas.push(delta);
+                }
+                TreeRecord::Delta(_) => return Err(MlsError::InvalidEpoch),
+            }
+        };
+
+        for delta in deltas.into_iter().rev() {
+            delta.apply(&mut nodes)?;
+        }
+
+        self.stored_tree = Some(StoredTree {
+            epoch: epoch_id,
+            checkpoint_epoch: record_epoch,
+            nodes: nodes.clone(),
+        });
+
+        let mut tree = TreeKemPublic::new();
+        tree.nodes = nodes;
+
+        Ok(tree)
+    }
+
+    /// Delete the stored epochs older than `epoch_id`. Pending changes must
+    /// have been written to storage beforehand.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn delete_epochs_before(&mut self, epoch_id: u64) -> Result<Vec<u64>, MlsError> {
+        self.storage
+            .delete_epochs_before(&self.group_id, epoch_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .ok_or(MlsError::EpochDeletionUnsupported)
+    }
+
     #[cfg(any(feature = "psk", feature = "private_message"))]
     fn find_pending(&self, epoch_id: u64) -> Option<usize> {
         self.pending_commit
@@ -248,7 +434,7 @@ mod tests {
         client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
         group::{
             epoch::{test_utils::get_test_epoch_with_id, SenderDataSecret},
-            test_utils::{random_bytes, test_member, TEST_GROUP},
+            test_utils::{random_bytes, test_member, test_n_member_group, TEST_GROUP},
             PskGroupId, ResumptionPSKUsage,
         },
         storage_provider::in_memory::{InMemoryGroupStateStorage, InMemoryKeyPackageStorage},
@@ -570,4 +756,52 @@ mod tests {
 
         assert!(repo.key_package_repo.get(&key_package.reference).is_none());
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn ratchet_tree_is_rebuilt_from_checkpoints() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+        let group = &mut groups[0].group;
+
+        let mut repo = GroupStateRepository::new(
+            group.group_id().to_vec(),
+            InMemoryGroupStateStorage::new(),
+            InMemoryKeyPackageStorage::default(),
+            None,
+        )
+        .unwrap()
+        .with_tree_checkpoint_interval(Some(2));
+
+        // Epochs 2 and 4 are checkpoints, 3 and 5 deltas.
+        for _ in 0..4 {
+            group.commit(vec![]).await.unwrap();
+            group.apply_pending_commit().await.unwrap();
+            repo.write_to_storage(group.snapshot()).await.unwrap();
+        }
+
+        let epoch = group.current_epoch();
+        assert_eq!(epoch, 5);
+
+        let state = repo.storage.state(group.group_id()).await.unwrap().unwrap();
+        let snapshot = Snapshot::mls_decode(&mut &*state).unwrap();
+        assert!(snapshot.state.public_tree.nodes.is_empty());
+
+        let tree_epochs = repo.storage.lock()[group.group_id()]
+            .tree_data
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        assert_eq!(tree_epochs, vec![4, 5]);
+
+        let mut repo = GroupStateRepository::new(
+            group.group_id().to_vec(),
+            repo.storage,
+            InMemoryKeyPackageStorage::default(),
+            None,
+        )
+        .unwrap();
+
+        let tree = repo.load_tree(epoch).await.unwrap();
+        assert_eq!(tree.nodes, group.state.public_tree.nodes);
+    }
 }
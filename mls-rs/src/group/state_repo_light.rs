@@ -46,6 +46,26 @@ where
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn write_to_storage(&mut self, group_snapshot: Snapshot) -> Result<(), MlsError> {
+        self.storage
+            .begin_transaction()
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        match self.write_pending(group_snapshot).await {
+            Ok(()) => self
+                .storage
+                .commit_transaction()
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error())),
+            Err(e) => {
+                let _ = self.storage.rollback_transaction().await;
+                Err(e)
+            }
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn write_pending(&mut self, group_snapshot: Snapshot) -> Result<(), MlsError> {
         let group_state = GroupState {
             data: group_snapshot.mls_encode_to_vec()?,
             id: group_snapshot.state.context.group_id,
@@ -151,6 +151,24 @@ impl TestGroup {
 
         self.group.format_for_wire(auth_content).await.unwrap()
     }
+
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn make_ciphertext(&mut self, content: Content) -> MlsMessage {
+        let auth_content = AuthenticatedContent::new_signed(
+            &self.group.cipher_suite_provider,
+            &self.group.state.context,
+            Sender::Member(*self.group.private_tree.self_index),
+            content,
+            &self.group.signer,
+            WireFormat::PrivateMessage,
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+
+        self.group.format_for_wire(auth_content).await.unwrap()
+    }
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -467,6 +485,14 @@ impl MessageProcessor for GroupWithoutKeySchedule {
         self.inner.can_continue_processing(provisional_state)
     }
 
+    fn self_index(&self) -> Option<LeafIndex> {
+        MessageProcessor::self_index(&self.inner)
+    }
+
+    fn current_time(&self) -> Option<MlsTime> {
+        self.inner.current_time()
+    }
+
     #[cfg(feature = "private_message")]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn min_epoch_available(&self) -> Option<u64> {
@@ -57,6 +57,12 @@ impl ConfirmedTranscriptHash {
         interim_transcript_hash: &InterimTranscriptHash,
         content: &AuthenticatedContent,
     ) -> Result<Self, MlsError> {
+        let input = Self::hash_input(content)?;
+        Self::from_hash_input(cipher_suite_provider, interim_transcript_hash, &input).await
+    }
+
+    /// Encoded `ConfirmedTranscriptHashInput` of the commit `content`.
+    pub(crate) fn hash_input(content: &AuthenticatedContent) -> Result<Vec<u8>, MlsError> {
         #[derive(Debug, MlsSize, MlsEncode)]
         struct ConfirmedTranscriptHashInput<'a> {
             wire_format: WireFormat,
@@ -70,11 +76,16 @@ impl ConfirmedTranscriptHash {
             signature: &content.auth.signature,
         };
 
-        let hash_input = [
-            interim_transcript_hash.deref(),
-            input.mls_encode_to_vec()?.deref(),
-        ]
-        .concat();
+        input.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn from_hash_input<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        interim_transcript_hash: &InterimTranscriptHash,
+        input: &[u8],
+    ) -> Result<Self, MlsError> {
+        let hash_input = [interim_transcript_hash.deref(), input].concat();
 
         cipher_suite_provider
             .hash(&hash_input)
@@ -0,0 +1,190 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{
+    client::MlsError, crypto::CipherSuiteProvider, extension::ExternalSendersExt, signer::Signable,
+};
+
+#[cfg(all(feature = "external_client", feature = "by_ref_proposal"))]
+use crate::crypto::SignatureSecretKey;
+
+use super::GroupContext;
+
+/// Verification of the leaf signatures of ratchet trees imported when
+/// joining a group.
+///
+/// It is set with
+/// [`ClientBuilder::leaf_signature_verification`](crate::client_builder::ClientBuilder::leaf_signature_verification).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeafSignatureVerification {
+    /// Verify the signature, credential and capabilities of every leaf, as
+    /// required by the RFC.
+    #[default]
+    Always,
+    /// Skip the validation of the leaves of a tree for which a valid
+    /// [`TreeAttestation`] is given to
+    /// [`Client::join_group_with_tree_attestation`](crate::Client::join_group_with_tree_attestation).
+    ///
+    /// This makes joining large groups much cheaper for constrained clients,
+    /// at the cost of trusting the external sender that signed the
+    /// attestation to have validated all leaves. A malicious or compromised
+    /// external sender can make the client join a group containing leaves
+    /// that were forged by the committer, or whose credentials the client
+    /// would have rejected. The tree hash, parent hashes and the signature
+    /// of the group info are still verified.
+    SkipForTreesAttestedByExternalSender,
+}
+
+/// Statement by an external sender of a group that it validated all leaves
+/// of the ratchet tree of an epoch.
+///
+/// An attestation is created by the external sender with
+/// [`ExternalGroup::tree_attestation`](crate::external_client::ExternalGroup::tree_attestation)
+/// and signed with its signing key listed in the [`ExternalSendersExt`] of
+/// the group. It covers the tree hash of the epoch.
+#[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct TreeAttestation {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    tree_hash: Vec<u8>,
+    external_sender_index: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature: Vec<u8>,
+}
+
+impl Debug for TreeAttestation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TreeAttestation")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field(
+                "tree_hash",
+                &mls_rs_core::debug::pretty_bytes(&self.tree_hash),
+            )
+            .field("external_sender_index", &self.external_sender_index)
+            .field(
+                "signature",
+                &mls_rs_core::debug::pretty_bytes(&self.signature),
+            )
+            .finish()
+    }
+}
+
+impl TreeAttestation {
+    /// Identifier of the group the attested tree belongs to.
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Epoch of the attested tree.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Index of the signer in the [`ExternalSendersExt`] of the group.
+    pub fn external_sender_index(&self) -> u32 {
+        self.external_sender_index
+    }
+
+    #[cfg(all(feature = "external_client", feature = "by_ref_proposal"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+        external_sender_index: u32,
+        signer: &SignatureSecretKey,
+    ) -> Result<Self, MlsError> {
+        let mut attestation = Self {
+            group_id: group_context.group_id.clone(),
+            epoch: group_context.epoch,
+            tree_hash: group_context.tree_hash.clone(),
+            external_sender_index,
+            signature: Vec::new(),
+        };
+
+        attestation.sign(cipher_suite_provider, signer, &()).await?;
+
+        Ok(attestation)
+    }
+
+    /// Verify that the attestation covers the tree of `group_context` and is
+    /// signed by one of its external senders.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        group_context: &GroupContext,
+    ) -> Result<(), MlsError> {
+        if self.group_id != group_context.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        if self.epoch != group_context.epoch {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        if self.tree_hash != group_context.tree_hash {
+            return Err(MlsError::TreeHashMismatch);
+        }
+
+        let external_senders = group_context
+            .extensions
+            .get_as::<ExternalSendersExt>()?
+            .ok_or(MlsError::UnknownSigningIdentityForExternalSender)?;
+
+        let signer = external_senders
+            .allowed_senders
+            .get(self.external_sender_index as usize)
+            .ok_or(MlsError::UnknownSigningIdentityForExternalSender)?;
+
+        Signable::verify(self, cipher_suite_provider, &signer.signature_key, &()).await
+    }
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct TreeAttestationTBS<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    tree_hash: &'a [u8],
+    external_sender_index: u32,
+}
+
+impl<'a> Signable<'a> for TreeAttestation {
+    const SIGN_LABEL: &'static str = "TreeAttestationTBS";
+
+    type SigningContext = ();
+
+    fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn signable_content(
+        &self,
+        _context: &Self::SigningContext,
+    ) -> Result<Vec<u8>, mls_rs_codec::Error> {
+        TreeAttestationTBS {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            tree_hash: &self.tree_hash,
+            external_sender_index: self.external_sender_index,
+        }
+        .mls_encode_to_vec()
+    }
+
+    fn write_signature(&mut self, signature: Vec<u8>) {
+        self.signature = signature
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{
+    client::MlsError,
+    tree_kem::node::{Node, NodeVec},
+};
+
+/// Ratchet tree of an epoch, stored apart from the group state when tree
+/// checkpointing is enabled.
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+#[repr(u8)]
+pub(crate) enum TreeRecord {
+    /// Full tree.
+    Checkpoint(NodeVec) = 1u8,
+    /// Changes from the tree of an earlier epoch.
+    Delta(TreeDelta) = 2u8,
+}
+
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+struct NodeChange {
+    index: u32,
+    node: Option<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub(crate) struct TreeDelta {
+    pub(crate) base_epoch: u64,
+    len: u32,
+    changes: Vec<NodeChange>,
+}
+
+impl TreeDelta {
+    /// Changes from `base`, the tree of `base_epoch`, to `nodes`.
+    pub(crate) fn new(base_epoch: u64, base: &NodeVec, nodes: &NodeVec) -> Self {
+        let changes = nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, node)| base.get(*i) != Some(*node))
+            .map(|(i, node)| NodeChange {
+                index: i as u32,
+                node: node.clone(),
+            })
+            .collect();
+
+        Self {
+            base_epoch,
+            len: nodes.len() as u32,
+            changes,
+        }
+    }
+
+    /// Apply the changes to the tree of the base epoch.
+    pub(crate) fn apply(self, nodes: &mut NodeVec) -> Result<(), MlsError> {
+        nodes.resize(self.len as usize, None);
+
+        for change in self.changes {
+            let node = nodes
+                .get_mut(change.index as usize)
+                .ok_or(MlsError::InvalidTreeIndex)?;
+
+            *node = change.node;
+        }
+
+        Ok(())
+    }
+}
+
+/// Tree last written to, or loaded from, the tree records of a group.
+#[derive(Clone, Debug)]
+pub(crate) struct StoredTree {
+    pub(crate) epoch: u64,
+    pub(crate) checkpoint_epoch: u64,
+    pub(crate) nodes: NodeVec,
+}
+
+impl StoredTree {
+    /// Record to write for the tree `nodes` of `epoch`, or `None` if the
+    /// stored tree is already the one of `epoch`. A full tree is written
+    /// once `interval` epochs have passed since the last one.
+    pub(crate) fn next_record(
+        last: Option<&Self>,
+        epoch: u64,
+        nodes: &NodeVec,
+        interval: u32,
+    ) -> Option<TreeRecord> {
+        match last {
+            // The public tree only changes with the epoch.
+            Some(last) if last.epoch == epoch => None,
+            Some(last)
+                if last.epoch < epoch && epoch - last.checkpoint_epoch < u64::from(interval) =>
+            {
+                Some(TreeRecord::Delta(TreeDelta::new(
+                    last.epoch,
+                    &last.nodes,
+                    nodes,
+                )))
+            }
+            _ => Some(TreeRecord::Checkpoint(nodes.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_n_member_group,
+        tree_kem::node::NodeVec,
+    };
+
+    use super::{StoredTree, TreeDelta, TreeRecord};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn delta_rebuilds_tree() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 4).await;
+        let before = groups[0].group.state.public_tree.nodes.clone();
+
+        groups[0]
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        let after = groups[0].group.state.public_tree.nodes.clone();
+        let delta = TreeDelta::new(1, &before, &after);
+
+        assert!(delta.changes.len() < after.len());
+
+        let mut rebuilt = before.clone();
+        delta.apply(&mut rebuilt).unwrap();
+        assert_eq!(rebuilt, after);
+
+        // The tree can also shrink.
+        let truncated = NodeVec::from(before[..3].to_vec());
+        let delta = TreeDelta::new(2, &after, &truncated);
+        let mut rebuilt = after;
+        delta.apply(&mut rebuilt).unwrap();
+        assert_eq!(rebuilt, truncated);
+    }
+
+    #[test]
+    fn checkpoints_are_written_at_interval() {
+        let nodes = NodeVec::from(vec![None]);
+
+        let record = StoredTree::next_record(None, 5, &nodes, 3);
+        assert_matches!(record, Some(TreeRecord::Checkpoint(_)));
+
+        let last = StoredTree {
+            epoch: 5,
+            checkpoint_epoch: 5,
+            nodes: nodes.clone(),
+        };
+
+        assert_eq!(StoredTree::next_record(Some(&last), 5, &nodes, 3), None);
+
+        let record = StoredTree::next_record(Some(&last), 7, &nodes, 3);
+        assert_matches!(record, Some(TreeRecord::Delta(d)) if d.base_epoch == 5);
+
+        let record = StoredTree::next_record(Some(&last), 8, &nodes, 3);
+        assert_matches!(record, Some(TreeRecord::Checkpoint(_)));
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec;
+use alloc::vec::Vec;
+use mls_rs_core::{
+    error::IntoAnyError,
+    group::{Member, RosterUpdate},
+    identity::{IdentityProvider, SigningIdentity},
+};
+
+use crate::client::MlsError;
+
+use super::{ClientConfig, CommitOutput, Group, MessageProcessor};
+
+/// User of a group, holding one or more leaves.
+///
+/// Leaves are grouped by the
+/// [`IdentityProvider::user_identity`](mls_rs_core::identity::IdentityProvider::user_identity)
+/// of their signing identity, so that each device of a user holds its own
+/// leaf while the application deals with the user as a whole.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct User {
+    /// User identity shared by all devices.
+    pub identity: Vec<u8>,
+    /// Devices of the user, ordered by leaf index.
+    pub devices: Vec<Member>,
+}
+
+/// Changes to the users of a group made by a commit, derived from its
+/// [`RosterUpdate`] with [`Group::user_roster_update`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct UserRosterUpdate {
+    /// Identities of users whose first device was added.
+    pub added_users: Vec<Vec<u8>>,
+    /// Identities of users whose last device was removed.
+    pub removed_users: Vec<Vec<u8>>,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Current users of the group, ordered by the lowest leaf index of their
+    /// devices.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn users(&self) -> Result<Vec<User>, MlsError> {
+        let mut users: Vec<User> = Vec::new();
+
+        for member in self.roster().members_iter() {
+            let identity = self.user_identity(&member.signing_identity).await?;
+
+            match users.iter_mut().find(|user| user.identity == identity) {
+                Some(user) => user.devices.push(member),
+                None => users.push(User {
+                    identity,
+                    devices: vec![member],
+                }),
+            }
+        }
+
+        Ok(users)
+    }
+
+    /// Current devices of the user with identity `user_identity`, ordered by
+    /// leaf index.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn devices_of(&self, user_identity: &[u8]) -> Result<Vec<Member>, MlsError> {
+        let mut devices = Vec::new();
+
+        for member in self.roster().members_iter() {
+            if self.user_identity(&member.signing_identity).await? == user_identity {
+                devices.push(member);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// Commit the removal of all devices of the user with identity
+    /// `user_identity`.
+    ///
+    /// When removing the user of this member, only its other devices are
+    /// removed. Fails with [`MlsError::MemberNotFound`] if there is no
+    /// device to remove.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn remove_all_devices(
+        &mut self,
+        user_identity: &[u8],
+        authenticated_data: Vec<u8>,
+    ) -> Result<CommitOutput, MlsError> {
+        let self_index = self.current_member_index();
+
+        let devices = self
+            .devices_of(user_identity)
+            .await?
+            .into_iter()
+            .filter(|device| device.index != self_index)
+            .collect::<Vec<_>>();
+
+        if devices.is_empty() {
+            return Err(MlsError::MemberNotFound);
+        }
+
+        devices
+            .iter()
+            .try_fold(self.commit_builder(), |builder, device| {
+                builder.remove_member(device.index)
+            })?
+            .authenticated_data(authenticated_data)
+            .build()
+            .await
+    }
+
+    /// Changes to the users of the group made by the commit producing
+    /// `roster_update`.
+    ///
+    /// This must be called right after the commit is applied, while the
+    /// roster of the group still is the one resulting from the commit. A
+    /// user is added when all of its current devices were added by the
+    /// commit, and removed when a device was removed and none remains.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn user_roster_update(
+        &self,
+        roster_update: &RosterUpdate,
+    ) -> Result<UserRosterUpdate, MlsError> {
+        let mut current = Vec::new();
+
+        for member in self.roster().members_iter() {
+            let identity = self.user_identity(&member.signing_identity).await?;
+            current.push((member.index, identity));
+        }
+
+        let mut update = UserRosterUpdate::default();
+
+        for added in roster_update.added() {
+            let Some((_, identity)) = current.iter().find(|(index, _)| *index == added.index)
+            else {
+                continue;
+            };
+
+            let new_user = current
+                .iter()
+                .filter(|(_, other)| other == identity)
+                .all(|(index, _)| roster_update.added().iter().any(|m| m.index == *index));
+
+            if new_user && !update.added_users.contains(identity) {
+                update.added_users.push(identity.clone());
+            }
+        }
+
+        for removed in roster_update.removed() {
+            let identity = self.user_identity(&removed.signing_identity).await?;
+
+            let gone = !current.iter().any(|(_, other)| *other == identity);
+
+            if gone && !update.removed_users.contains(&identity) {
+                update.removed_users.push(identity);
+            }
+        }
+
+        Ok(update)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn user_identity(&self, signing_identity: &SigningIdentity) -> Result<Vec<u8>, MlsError> {
+        self.identity_provider()
+            .user_identity(signing_identity, &self.state.context.extensions)
+            .await
+            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(mls_build_async)]
+    use alloc::boxed::Box;
+    use alloc::{vec, vec::Vec};
+    use assert_matches::assert_matches;
+    use mls_rs_core::{
+        extension::ExtensionList,
+        identity::{CredentialType, IdentityProvider, SigningIdentity},
+        time::MlsTime,
+    };
+
+    use crate::{
+        client::{test_utils::TEST_CIPHER_SUITE, MlsError},
+        client_builder::{BaseConfig, ClientBuilder, WithCryptoProvider, WithIdentityProvider},
+        crypto::test_utils::TestCryptoProvider,
+        identity::{
+            basic::{BasicIdentityProvider, BasicIdentityProviderError},
+            test_utils::get_test_signing_identity,
+        },
+        Client, Group,
+    };
+
+    // Identities are of the form `user/device`.
+    #[derive(Clone, Debug)]
+    struct DeviceIdentityProvider(BasicIdentityProvider);
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl IdentityProvider for DeviceIdentityProvider {
+        type Error = BasicIdentityProviderError;
+
+        async fn validate_member(
+            &self,
+            signing_identity: &SigningIdentity,
+            timestamp: Option<MlsTime>,
+            extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            self.0
+                .validate_member(signing_identity, timestamp, extensions)
+                .await
+        }
+
+        async fn validate_external_sender(
+            &self,
+            signing_identity: &SigningIdentity,
+            timestamp: Option<MlsTime>,
+            extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            self.0
+                .validate_external_sender(signing_identity, timestamp, extensions)
+                .await
+        }
+
+        async fn identity(
+            &self,
+            signing_identity: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            self.0.identity(signing_identity, extensions).await
+        }
+
+        async fn user_identity(
+            &self,
+            signing_identity: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            let mut identity = self.0.identity(signing_identity, extensions).await?;
+            let user_len = identity.iter().position(|b| *b == b'/');
+            identity.truncate(user_len.unwrap_or(identity.len()));
+            Ok(identity)
+        }
+
+        async fn valid_successor(
+            &self,
+            predecessor: &SigningIdentity,
+            successor: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<bool, Self::Error> {
+            self.0
+                .valid_successor(predecessor, successor, extensions)
+                .await
+        }
+
+        fn supported_types(&self) -> Vec<CredentialType> {
+            self.0.supported_types()
+        }
+    }
+
+    type DeviceClientConfig = WithIdentityProvider<
+        DeviceIdentityProvider,
+        WithCryptoProvider<TestCryptoProvider, BaseConfig>,
+    >;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn device_client(name: &[u8]) -> Client<DeviceClientConfig> {
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, name).await;
+
+        ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(DeviceIdentityProvider(BasicIdentityProvider::new()))
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build()
+    }
+
+    // Alice's phone creates a group with her laptop and both of Bob's devices.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn devices_group() -> Group<DeviceClientConfig> {
+        let mut group = device_client(b"alice/phone")
+            .await
+            .create_group(ExtensionList::new())
+            .await
+            .unwrap();
+
+        let mut builder = group.commit_builder();
+
+        for name in [&b"bob/phone"[..], b"alice/laptop", b"bob/laptop"] {
+            let key_package = device_client(name)
+                .await
+                .generate_key_package_message()
+                .await
+                .unwrap();
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        builder.build().await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+
+        group
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn leaves_are_grouped_by_user() {
+        let group = devices_group().await;
+
+        let users = group.users().await.unwrap();
+
+        let users = users
+            .iter()
+            .map(|user| {
+                let indexes = user.devices.iter().map(|d| d.index).collect::<Vec<_>>();
+                (user.identity.as_slice(), indexes)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            users,
+            vec![(&b"alice"[..], vec![0, 2]), (&b"bob"[..], vec![1, 3])]
+        );
+
+        let devices = group.devices_of(b"bob").await.unwrap();
+        assert_eq!(devices.len(), 2);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn all_devices_of_user_are_removed() {
+        let mut group = devices_group().await;
+
+        group.remove_all_devices(b"bob", vec![]).await.unwrap();
+
+        #[allow(unused_variables)]
+        let description = group.apply_pending_commit().await.unwrap();
+
+        let devices = group.devices_of(b"bob").await.unwrap();
+        assert!(devices.is_empty());
+        assert_eq!(group.roster().members_iter().count(), 2);
+
+        #[cfg(feature = "state_update")]
+        {
+            let update = group
+                .user_roster_update(description.state_update.roster_update())
+                .await
+                .unwrap();
+
+            assert!(update.added_users.is_empty());
+            assert_eq!(update.removed_users, vec![b"bob".to_vec()]);
+        }
+
+        let res = group.remove_all_devices(b"bob", vec![]).await;
+        assert_matches!(res, Err(MlsError::MemberNotFound));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn own_device_is_not_removed() {
+        let mut group = devices_group().await;
+
+        group.remove_all_devices(b"alice", vec![]).await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+
+        let devices = group.devices_of(b"alice").await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].index, group.current_member_index());
+    }
+
+    #[cfg(feature = "state_update")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn new_device_of_existing_user_is_not_new_user() {
+        let mut group = devices_group().await;
+
+        let key_package = device_client(b"bob/tablet")
+            .await
+            .generate_key_package_message()
+            .await
+            .unwrap();
+
+        group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let description = group.apply_pending_commit().await.unwrap();
+
+        let update = group
+            .user_roster_update(description.state_update.roster_update())
+            .await
+            .unwrap();
+
+        assert_eq!(update, Default::default());
+
+        let devices = group.devices_of(b"bob").await.unwrap();
+        assert_eq!(devices.len(), 3);
+    }
+}
@@ -20,6 +20,9 @@ use crate::{
 #[cfg(feature = "by_ref_proposal")]
 use crate::extension::ExternalSendersExt;
 
+#[cfg(feature = "by_ref_proposal")]
+use super::TreeAttestation;
+
 use super::{
     framing::Sender, message_signature::AuthenticatedContent,
     transcript_hash::InterimTranscriptHash, ConfirmedTranscriptHash, EncryptedGroupSecrets,
@@ -60,7 +63,15 @@ pub(crate) async fn validate_group_info_member<C: CipherSuiteProvider>(
     cs: &C,
 ) -> Result<(), MlsError> {
     validate_group_info_common(msg_version, group_info, &self_state.public_tree, cs).await?;
+    group_info_matches_state(self_state, group_info)
+}
 
+// Kept out of the generic function above, as it doesn't depend on the cipher
+// suite provider.
+fn group_info_matches_state(
+    self_state: &GroupState,
+    group_info: &GroupInfo,
+) -> Result<(), MlsError> {
     let self_tree = ExportedTree::new_borrowed(&self_state.public_tree.nodes);
 
     if let Some(tree) = group_info.extensions.get_as::<RatchetTreeExt>()? {
@@ -84,6 +95,7 @@ pub(crate) async fn validate_group_info_joiner<C, I>(
     tree: Option<ExportedTree<'_>>,
     id_provider: &I,
     cs: &C,
+    #[cfg(feature = "by_ref_proposal")] tree_attestation: Option<&TreeAttestation>,
 ) -> Result<TreeKemPublic, MlsError>
 where
     C: CipherSuiteProvider,
@@ -99,10 +111,19 @@ where
     let mut tree =
         TreeKemPublic::import_node_data(tree.into(), id_provider, &context.extensions).await?;
 
+    let tree_validator = TreeValidator::new(cs, context, id_provider);
+
+    #[cfg(feature = "by_ref_proposal")]
+    let tree_validator = match tree_attestation {
+        Some(tree_attestation) => {
+            tree_attestation.verify(cs, context).await?;
+            tree_validator.without_leaf_validation()
+        }
+        None => tree_validator,
+    };
+
     // Verify the integrity of the ratchet tree
-    TreeValidator::new(cs, context, id_provider)
-        .validate(&mut tree)
-        .await?;
+    tree_validator.validate(&mut tree).await?;
 
     #[cfg(feature = "by_ref_proposal")]
     if let Some(ext_senders) = context.extensions.get_as::<ExternalSendersExt>()? {
@@ -0,0 +1,156 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display};
+use mls_rs_codec::{MlsEncode, MlsSize};
+use mls_rs_core::identity::SigningIdentity;
+
+use crate::{client::MlsError, crypto::CipherSuiteProvider, tree_kem::node::LeafIndex};
+
+use super::key_schedule::kdf_expand_with_label;
+
+const VERIFICATION_CODE_LABEL: &[u8] = b"verification code";
+const GROUP_LEN: usize = 5;
+const GROUP_COUNT: usize = 6;
+
+/// Short authentication string for a pair of members, to be compared out of
+/// band.
+///
+/// The code is created with
+/// [`Group::verification_code`](crate::Group::verification_code). It is
+/// derived from the epoch authenticator of the current epoch and the
+/// credentials and signature keys of both members, in the order of their
+/// leaf indexes, so that both members obtain the same code. Matching codes
+/// mean that the members agree on the state of the group and on each
+/// other's keys. The code changes with every epoch.
+///
+/// The [`Display`] implementation shows the code as 6 groups of 5 digits.
+#[derive(Clone, PartialEq, Eq)]
+pub struct VerificationCode(Vec<u8>);
+
+impl Debug for VerificationCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VerificationCode")
+            .field(&self.digits())
+            .finish()
+    }
+}
+
+impl Display for VerificationCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, group) in self.digits().iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            write!(f, "{group:05}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl VerificationCode {
+    /// Raw bytes of the code, for applications rendering it differently,
+    /// for instance as a QR code.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Groups of 5 digits of the code.
+    pub fn digits(&self) -> Vec<u32> {
+        self.0
+            .chunks(GROUP_LEN)
+            .map(|chunk| {
+                let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+                (value % 100_000) as u32
+            })
+            .collect()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn derive<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        epoch_authenticator: &[u8],
+        a: (LeafIndex, &SigningIdentity),
+        b: (LeafIndex, &SigningIdentity),
+    ) -> Result<Self, MlsError> {
+        let (first, second) = if a.0 <= b.0 { (a.1, b.1) } else { (b.1, a.1) };
+
+        let context = VerificationCodeContext { first, second }.mls_encode_to_vec()?;
+
+        let code = kdf_expand_with_label(
+            cipher_suite_provider,
+            epoch_authenticator,
+            VERIFICATION_CODE_LABEL,
+            &context,
+            Some(GROUP_LEN * GROUP_COUNT),
+        )
+        .await?;
+
+        Ok(Self(code.to_vec()))
+    }
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct VerificationCodeContext<'a> {
+    first: &'a SigningIdentity,
+    second: &'a SigningIdentity,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec};
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_n_member_group,
+    };
+
+    use super::VerificationCode;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn both_members_derive_the_same_code() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let alice = groups[0].group.current_member_index();
+        let bob = groups[1].group.current_member_index();
+        let carol = groups[2].group.current_member_index();
+
+        let alice_code = groups[0].group.verification_code(alice, bob).await.unwrap();
+        let bob_code = groups[1].group.verification_code(bob, alice).await.unwrap();
+
+        assert_eq!(alice_code, bob_code);
+
+        let carol_code = groups[2]
+            .group
+            .verification_code(carol, alice)
+            .await
+            .unwrap();
+        assert_ne!(alice_code, carol_code);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn code_changes_with_epoch() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let before = groups[0].group.verification_code(0, 1).await.unwrap();
+
+        groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        let after = groups[0].group.verification_code(0, 1).await.unwrap();
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn code_is_displayed_as_digit_groups() {
+        let code = VerificationCode(vec![0xff; 30]);
+
+        assert_eq!(code.digits(), vec![27775; 6]);
+        assert_eq!(format!("{code}"), "27775 27775 27775 27775 27775 27775");
+    }
+}
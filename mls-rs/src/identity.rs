@@ -5,6 +5,15 @@
 /// Basic credential identity provider.
 pub mod basic;
 
+/// Identity provider for application-defined credential types.
+pub mod registry;
+
+/// Expiry and refresh of short-lived credentials.
+pub mod refresh;
+
+/// Key transparency checks of member credentials.
+pub mod transparency;
+
 /// X.509 certificate identity provider.
 #[cfg(feature = "x509")]
 pub mod x509 {
@@ -0,0 +1,326 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+use mls_rs_core::{
+    crypto::SignatureSecretKey,
+    error::{AnyError, IntoAnyError},
+    extension::ExtensionList,
+    identity::{CredentialType, IdentityProvider, SigningIdentity},
+    time::MlsTime,
+};
+
+/// Expiration time of short-lived credentials, such as certificates issued
+/// in exchange for an OpenID Connect token.
+pub trait CredentialExpiry: Send + Sync {
+    /// Time after which the credential of `signing_identity` is no longer
+    /// valid, or `None` if it doesn't expire.
+    fn expiration(&self, signing_identity: &SigningIdentity) -> Result<Option<MlsTime>, AnyError>;
+}
+
+/// Source of refreshed credentials, used by
+/// [`Group::propose_credential_refresh`](crate::group::Group::propose_credential_refresh).
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait CredentialRefresher: CredentialExpiry {
+    /// Obtain a new credential for the holder of `signing_identity`, along
+    /// with the signing identity and secret key to use it with.
+    ///
+    /// The new credential must be a valid successor of the current one
+    /// according to the identity provider of the group.
+    async fn refresh(
+        &self,
+        signing_identity: &SigningIdentity,
+    ) -> Result<(SigningIdentity, SignatureSecretKey), AnyError>;
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+/// Error returned by an [`ExpiringCredentialProvider`].
+pub enum ExpiringCredentialError {
+    #[cfg_attr(
+        feature = "std",
+        error("credential expired at {0:?} and its grace period has elapsed")
+    )]
+    /// The credential expired longer than the grace period ago.
+    CredentialLapsed(MlsTime),
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the [`CredentialExpiry`].
+    ExpiryError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the wrapped identity provider.
+    InnerError(AnyError),
+}
+
+impl IntoAnyError for ExpiringCredentialError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Identity provider rejecting members whose credentials have expired
+/// longer than a grace period ago.
+///
+/// Members can't refresh their credential while they are offline, so a
+/// credential is still accepted for `grace_period` after its expiration,
+/// leaving them time to [refresh](crate::group::Group::propose_credential_refresh)
+/// it. Within the grace period, the wrapped provider validates the
+/// credential as of its expiration time. Validation without a timestamp is
+/// left to the wrapped provider.
+#[derive(Clone, Debug)]
+pub struct ExpiringCredentialProvider<P, E> {
+    inner: P,
+    expiry: E,
+    grace_period: Duration,
+}
+
+impl<P, E> ExpiringCredentialProvider<P, E> {
+    /// Wrap `inner`, using `expiry` to determine the expiration time of
+    /// credentials.
+    pub fn new(inner: P, expiry: E, grace_period: Duration) -> Self {
+        Self {
+            inner,
+            expiry,
+            grace_period,
+        }
+    }
+
+    /// Wrapped identity provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Grace period after the expiration of credentials.
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+}
+
+impl<P, E: CredentialExpiry> ExpiringCredentialProvider<P, E> {
+    fn validation_time(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+    ) -> Result<Option<MlsTime>, ExpiringCredentialError> {
+        let Some(timestamp) = timestamp else {
+            return Ok(None);
+        };
+
+        let expiration = self
+            .expiry
+            .expiration(signing_identity)
+            .map_err(ExpiringCredentialError::ExpiryError)?;
+
+        let Some(expiration) = expiration else {
+            return Ok(Some(timestamp));
+        };
+
+        let lapse = expiration
+            .seconds_since_epoch()
+            .saturating_add(self.grace_period.as_secs());
+
+        if timestamp.seconds_since_epoch() > lapse {
+            return Err(ExpiringCredentialError::CredentialLapsed(expiration));
+        }
+
+        Ok(Some(timestamp.min(expiration)))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<P, E> IdentityProvider for ExpiringCredentialProvider<P, E>
+where
+    P: IdentityProvider,
+    E: CredentialExpiry,
+{
+    type Error = ExpiringCredentialError;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        let timestamp = self.validation_time(signing_identity, timestamp)?;
+
+        self.inner
+            .validate_member(signing_identity, timestamp, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        let timestamp = self.validation_time(signing_identity, timestamp)?;
+
+        self.inner
+            .validate_external_sender(signing_identity, timestamp, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .identity(signing_identity, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    async fn user_identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .user_identity(signing_identity, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        self.inner
+            .valid_successor(predecessor, successor, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    async fn allow_shared_signature_key(
+        &self,
+        existing: &SigningIdentity,
+        new: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        self.inner
+            .allow_shared_signature_key(existing, new, extensions)
+            .await
+            .map_err(|e| ExpiringCredentialError::InnerError(e.into_any_error()))
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        self.inner.supported_types()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use core::time::Duration;
+
+    #[cfg(mls_build_async)]
+    use alloc::boxed::Box;
+
+    use mls_rs_core::{
+        crypto::{CipherSuite, SignatureSecretKey},
+        error::AnyError,
+        identity::SigningIdentity,
+        time::MlsTime,
+    };
+
+    use crate::identity::test_utils::get_test_signing_identity;
+
+    use super::{CredentialExpiry, CredentialRefresher};
+
+    /// Credentials expire `lifetime` after `issued_at`, and are refreshed with
+    /// a new signature key for the same identity.
+    pub struct TestRefresher {
+        pub cipher_suite: CipherSuite,
+        pub issued_at: MlsTime,
+        pub lifetime: Duration,
+    }
+
+    impl CredentialExpiry for TestRefresher {
+        fn expiration(
+            &self,
+            _signing_identity: &SigningIdentity,
+        ) -> Result<Option<MlsTime>, AnyError> {
+            let expiration = self.issued_at.seconds_since_epoch() + self.lifetime.as_secs();
+            Ok(Some(expiration.into()))
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl CredentialRefresher for TestRefresher {
+        async fn refresh(
+            &self,
+            signing_identity: &SigningIdentity,
+        ) -> Result<(SigningIdentity, SignatureSecretKey), AnyError> {
+            let (refreshed, secret_key) = get_test_signing_identity(self.cipher_suite, b"").await;
+
+            let refreshed =
+                SigningIdentity::new(signing_identity.credential.clone(), refreshed.signature_key);
+
+            Ok((refreshed, secret_key))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use assert_matches::assert_matches;
+    use mls_rs_core::{identity::IdentityProvider, time::MlsTime};
+
+    use crate::{
+        client::test_utils::TEST_CIPHER_SUITE,
+        identity::{basic::BasicIdentityProvider, test_utils::get_test_signing_identity},
+    };
+
+    use super::{test_utils::TestRefresher, ExpiringCredentialError, ExpiringCredentialProvider};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn lapsed_credentials_are_rejected() {
+        let refresher = TestRefresher {
+            cipher_suite: TEST_CIPHER_SUITE,
+            issued_at: MlsTime::from(1000),
+            lifetime: Duration::from_secs(100),
+        };
+
+        let provider = ExpiringCredentialProvider::new(
+            BasicIdentityProvider::new(),
+            refresher,
+            Duration::from_secs(50),
+        );
+
+        let (identity, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"alice").await;
+
+        for time in [1099, 1100, 1150] {
+            provider
+                .validate_member(&identity, Some(MlsTime::from(time)), None)
+                .await
+                .unwrap();
+        }
+
+        provider
+            .validate_member(&identity, None, None)
+            .await
+            .unwrap();
+
+        let res = provider
+            .validate_member(&identity, Some(MlsTime::from(1151)), None)
+            .await;
+
+        assert_matches!(res, Err(ExpiringCredentialError::CredentialLapsed(t)) if t == MlsTime::from(1100));
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt::{self, Debug};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+use mls_rs_core::{
+    error::{AnyError, IntoAnyError},
+    extension::ExtensionList,
+    identity::{Credential, CredentialType, CustomCredential, IdentityProvider, SigningIdentity},
+    time::MlsTime,
+};
+
+/// Validation of application-defined credentials of a given type.
+///
+/// Validators are registered with a [`CredentialRegistry`] for the
+/// [`CredentialType`] they handle, and only receive
+/// [`CustomCredential`]s of that type.
+pub trait CustomCredentialValidator: Send + Sync {
+    /// Determine if `credential`, used by `signing_identity`, is valid.
+    ///
+    /// Implementations are expected to check that the credential is bound to
+    /// the signature key of `signing_identity`. A `timestamp` is provided
+    /// when validation is time dependent, for instance for credentials that
+    /// can expire.
+    fn validate(
+        &self,
+        credential: &CustomCredential,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+    ) -> Result<(), AnyError>;
+
+    /// A unique identifier for the holder of `credential`.
+    fn identity(&self, credential: &CustomCredential) -> Result<Vec<u8>, AnyError>;
+
+    /// Identifier of the user holding `credential`, shared by all of their
+    /// devices. By default, this is the [`identity`](Self::identity).
+    fn user_identity(&self, credential: &CustomCredential) -> Result<Vec<u8>, AnyError> {
+        self.identity(credential)
+    }
+
+    /// Determine if the holder of `successor` can replace the holder of
+    /// `predecessor`. By default, this is the case if they have the same
+    /// [`identity`](Self::identity).
+    fn valid_successor(
+        &self,
+        predecessor: &CustomCredential,
+        successor: &CustomCredential,
+    ) -> Result<bool, AnyError> {
+        Ok(self.identity(predecessor)? == self.identity(successor)?)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+/// Error returned by a [`CredentialRegistry`].
+pub enum CredentialRegistryError {
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the validator registered for the credential type.
+    ValidatorError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the provider handling unregistered credential types.
+    FallbackError(AnyError),
+}
+
+impl IntoAnyError for CredentialRegistryError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Identity provider dispatching [custom credentials](Credential::Custom)
+/// to validators registered by credential type.
+///
+/// Credentials whose type has no registered validator, including basic and
+/// X.509 credentials, are handled by a fallback identity provider. The
+/// supported credential types, advertised in the capabilities of key
+/// packages and leaf nodes, are those of the fallback provider and the
+/// registered types.
+#[derive(Clone)]
+pub struct CredentialRegistry<P> {
+    fallback: P,
+    validators: BTreeMap<CredentialType, Arc<dyn CustomCredentialValidator>>,
+}
+
+impl<P: Debug> Debug for CredentialRegistry<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialRegistry")
+            .field("fallback", &self.fallback)
+            .field(
+                "credential_types",
+                &self.validators.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<P> CredentialRegistry<P> {
+    /// Create a registry without validators, handling all credentials with
+    /// `fallback`.
+    pub fn new(fallback: P) -> Self {
+        Self {
+            fallback,
+            validators: Default::default(),
+        }
+    }
+
+    /// Register `validator` for custom credentials of type
+    /// `credential_type`, replacing any previously registered validator.
+    pub fn with_validator<V>(mut self, credential_type: CredentialType, validator: V) -> Self
+    where
+        V: CustomCredentialValidator + 'static,
+    {
+        self.validators.insert(credential_type, Arc::new(validator));
+        self
+    }
+
+    /// Provider handling credentials without a registered validator.
+    pub fn fallback(&self) -> &P {
+        &self.fallback
+    }
+
+    fn custom<'a>(
+        &self,
+        signing_identity: &'a SigningIdentity,
+    ) -> Option<(&dyn CustomCredentialValidator, &'a CustomCredential)> {
+        let Credential::Custom(custom) = &signing_identity.credential else {
+            return None;
+        };
+
+        self.validators
+            .get(&custom.credential_type)
+            .map(|validator| (&**validator, custom))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<P: IdentityProvider> IdentityProvider for CredentialRegistry<P> {
+    type Error = CredentialRegistryError;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        match self.custom(signing_identity) {
+            Some((validator, credential)) => validator
+                .validate(credential, signing_identity, timestamp)
+                .map_err(CredentialRegistryError::ValidatorError),
+            None => self
+                .fallback
+                .validate_member(signing_identity, timestamp, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+        }
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        match self.custom(signing_identity) {
+            Some((validator, credential)) => validator
+                .validate(credential, signing_identity, timestamp)
+                .map_err(CredentialRegistryError::ValidatorError),
+            None => self
+                .fallback
+                .validate_external_sender(signing_identity, timestamp, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+        }
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        match self.custom(signing_identity) {
+            Some((validator, credential)) => validator
+                .identity(credential)
+                .map_err(CredentialRegistryError::ValidatorError),
+            None => self
+                .fallback
+                .identity(signing_identity, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+        }
+    }
+
+    async fn user_identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        match self.custom(signing_identity) {
+            Some((validator, credential)) => validator
+                .user_identity(credential)
+                .map_err(CredentialRegistryError::ValidatorError),
+            None => self
+                .fallback
+                .user_identity(signing_identity, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+        }
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        match (self.custom(predecessor), self.custom(successor)) {
+            (Some((validator, predecessor)), Some((_, successor)))
+                if predecessor.credential_type == successor.credential_type =>
+            {
+                validator
+                    .valid_successor(predecessor, successor)
+                    .map_err(CredentialRegistryError::ValidatorError)
+            }
+            (None, None) => self
+                .fallback
+                .valid_successor(predecessor, successor, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+            _ => Ok(false),
+        }
+    }
+
+    async fn allow_shared_signature_key(
+        &self,
+        existing: &SigningIdentity,
+        new: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        match (self.custom(existing), self.custom(new)) {
+            (None, None) => self
+                .fallback
+                .allow_shared_signature_key(existing, new, extensions)
+                .await
+                .map_err(|e| CredentialRegistryError::FallbackError(e.into_any_error())),
+            _ => Ok(false),
+        }
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        let mut types = self.fallback.supported_types();
+
+        types.extend(
+            self.validators
+                .keys()
+                .filter(|credential_type| !types.contains(credential_type))
+                .copied()
+                .collect::<Vec<_>>(),
+        );
+
+        types
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::{
+        crypto::CipherSuiteProvider,
+        error::{AnyError, IntoAnyError},
+        identity::{
+            BasicCredential, Credential, CredentialType, CustomCredential, IdentityProvider,
+            SigningIdentity,
+        },
+        time::MlsTime,
+    };
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client_builder::{ClientBuilder, MlsConfig},
+        crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
+        identity::basic::BasicIdentityProvider,
+        Client,
+    };
+
+    use super::{CredentialRegistry, CredentialRegistryError, CustomCredentialValidator};
+
+    const TOKEN_CREDENTIAL: CredentialType = CredentialType::new(0xF100);
+
+    #[derive(Debug)]
+    struct InvalidToken;
+
+    impl IntoAnyError for InvalidToken {}
+
+    // Token credentials are valid if they start with `token:`, and the rest
+    // of the data is the identity.
+    struct TokenValidator;
+
+    impl CustomCredentialValidator for TokenValidator {
+        fn validate(
+            &self,
+            credential: &CustomCredential,
+            _signing_identity: &SigningIdentity,
+            _timestamp: Option<MlsTime>,
+        ) -> Result<(), AnyError> {
+            self.identity(credential).map(|_| ())
+        }
+
+        fn identity(&self, credential: &CustomCredential) -> Result<Vec<u8>, AnyError> {
+            credential
+                .data
+                .strip_prefix(b"token:")
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| InvalidToken.into_any_error())
+        }
+    }
+
+    fn registry() -> CredentialRegistry<BasicIdentityProvider> {
+        CredentialRegistry::new(BasicIdentityProvider::new())
+            .with_validator(TOKEN_CREDENTIAL, TokenValidator)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn signing_identity(credential: Credential) -> SigningIdentity {
+        let (_, public_key) = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        SigningIdentity::new(credential, public_key)
+    }
+
+    fn token(data: &[u8]) -> Credential {
+        Credential::Custom(CustomCredential::new(TOKEN_CREDENTIAL, data.to_vec()))
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn registered_credentials_are_dispatched_to_validator() {
+        let registry = registry();
+
+        let valid = signing_identity(token(b"token:alice")).await;
+        let invalid = signing_identity(token(b"alice")).await;
+
+        registry.validate_member(&valid, None, None).await.unwrap();
+
+        let res = registry.validate_member(&invalid, None, None).await;
+        assert_matches!(res, Err(CredentialRegistryError::ValidatorError(_)));
+
+        let identity = registry
+            .identity(&valid, &Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(identity, b"alice");
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unregistered_credentials_use_fallback() {
+        let registry = registry();
+
+        let basic = BasicCredential::new(b"alice".to_vec()).into_credential();
+        let basic = signing_identity(basic).await;
+
+        registry.validate_member(&basic, None, None).await.unwrap();
+
+        let other_custom = Credential::Custom(CustomCredential::new(
+            CredentialType::new(0xF101),
+            b"token:alice".to_vec(),
+        ));
+
+        let other_custom = signing_identity(other_custom).await;
+
+        let res = registry.validate_member(&other_custom, None, None).await;
+        assert_matches!(res, Err(CredentialRegistryError::FallbackError(_)));
+
+        let token = signing_identity(token(b"token:alice")).await;
+
+        let successor = registry
+            .valid_successor(&basic, &token, &Default::default())
+            .await
+            .unwrap();
+
+        assert!(!successor);
+
+        assert_eq!(
+            registry.supported_types(),
+            [CredentialType::BASIC, TOKEN_CREDENTIAL]
+        );
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn token_client(name: &[u8]) -> Client<impl MlsConfig> {
+        let (secret_key, public_key) = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        let mut data = b"token:".to_vec();
+        data.extend_from_slice(name);
+
+        ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(registry())
+            .signing_identity(
+                SigningIdentity::new(token(&data), public_key),
+                secret_key,
+                TEST_CIPHER_SUITE,
+            )
+            .used_protocol_version(TEST_PROTOCOL_VERSION)
+            .build()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_credentials_are_used_in_groups() {
+        let alice = token_client(b"alice").await;
+        let bob = token_client(b"bob").await;
+
+        let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(bob.generate_key_package_message().await.unwrap())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        let alice_member = bob_group.roster().member_with_index(0).unwrap();
+
+        assert_eq!(
+            alice_member.signing_identity.credential,
+            token(b"token:alice")
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use mls_rs_core::{
+    error::{AnyError, IntoAnyError},
+    extension::ExtensionList,
+    identity::{CredentialType, IdentityProvider, SigningIdentity},
+    time::MlsTime,
+};
+
+/// Key transparency log consulted before accepting the credential of a
+/// member.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait KeyTransparencyLog: Send + Sync {
+    /// Determine if the log includes `signing_identity`, typically by
+    /// fetching an inclusion proof for its credential and signature key and
+    /// verifying it against a signed tree head of the log.
+    ///
+    /// Returns `false` if the log doesn't include the signing identity, and
+    /// an error if inclusion couldn't be determined, for instance because
+    /// the log is unreachable.
+    async fn verify_inclusion(&self, signing_identity: &SigningIdentity) -> Result<bool, AnyError>;
+}
+
+/// Handling of members whose inclusion in the key transparency log can't be
+/// determined.
+///
+/// Signing identities that the log doesn't include are always rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyTransparencyPolicy {
+    /// Reject the member.
+    #[default]
+    HardFail,
+    /// Accept the member. Its inclusion is checked again the next time it
+    /// is validated.
+    SoftFail,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+/// Error returned by a [`KeyTransparencyProvider`].
+pub enum KeyTransparencyError {
+    #[cfg_attr(feature = "std", error("signing identity is not included in the log"))]
+    /// The log doesn't include the signing identity.
+    NotIncluded,
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the [`KeyTransparencyLog`] with the
+    /// [`HardFail`](KeyTransparencyPolicy::HardFail) policy.
+    LogError(AnyError),
+    #[cfg_attr(feature = "std", error(transparent))]
+    /// Error returned by the wrapped identity provider.
+    InnerError(AnyError),
+}
+
+impl IntoAnyError for KeyTransparencyError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Identity provider consulting a key transparency log before accepting a
+/// member, for instance when validating an Add or Update proposal.
+///
+/// Members are first validated by the wrapped provider, then checked against
+/// the log. Signing identities found in the log are cached, up to
+/// `cache_capacity` of them, so that members seen in several groups or
+/// epochs don't trigger a lookup each time. External senders are only
+/// validated by the wrapped provider.
+#[derive(Clone, Debug)]
+pub struct KeyTransparencyProvider<P, L> {
+    inner: P,
+    log: L,
+    policy: KeyTransparencyPolicy,
+    cache_capacity: usize,
+    cache: Arc<Mutex<VecDeque<SigningIdentity>>>,
+}
+
+impl<P, L> KeyTransparencyProvider<P, L> {
+    /// Wrap `inner`, checking members against `log` with the `policy`.
+    pub fn new(inner: P, log: L, policy: KeyTransparencyPolicy, cache_capacity: usize) -> Self {
+        Self {
+            inner,
+            log,
+            policy,
+            cache_capacity,
+            cache: Default::default(),
+        }
+    }
+
+    /// Wrapped identity provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Handling of members whose inclusion can't be determined.
+    pub fn policy(&self) -> KeyTransparencyPolicy {
+        self.policy
+    }
+
+    /// Forget all cached inclusions, for instance after the log was found
+    /// to have been rolled back.
+    pub fn clear_cache(&self) {
+        self.lock().clear();
+    }
+
+    fn is_cached(&self, signing_identity: &SigningIdentity) -> bool {
+        self.lock().contains(signing_identity)
+    }
+
+    fn cache(&self, signing_identity: &SigningIdentity) {
+        if self.cache_capacity == 0 {
+            return;
+        }
+
+        let mut cache = self.lock();
+
+        if cache.len() >= self.cache_capacity {
+            cache.pop_front();
+        }
+
+        cache.push_back(signing_identity.clone());
+    }
+
+    #[cfg(feature = "std")]
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<SigningIdentity>> {
+        self.cache.lock().unwrap()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn lock(&self) -> spin::mutex::MutexGuard<'_, VecDeque<SigningIdentity>> {
+        self.cache.lock()
+    }
+}
+
+impl<P, L: KeyTransparencyLog> KeyTransparencyProvider<P, L> {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn check_inclusion(
+        &self,
+        signing_identity: &SigningIdentity,
+    ) -> Result<(), KeyTransparencyError> {
+        if self.is_cached(signing_identity) {
+            return Ok(());
+        }
+
+        match self.log.verify_inclusion(signing_identity).await {
+            Ok(true) => {
+                self.cache(signing_identity);
+                Ok(())
+            }
+            Ok(false) => Err(KeyTransparencyError::NotIncluded),
+            Err(_) if self.policy == KeyTransparencyPolicy::SoftFail => Ok(()),
+            Err(e) => Err(KeyTransparencyError::LogError(e)),
+        }
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<P, L> IdentityProvider for KeyTransparencyProvider<P, L>
+where
+    P: IdentityProvider,
+    L: KeyTransparencyLog,
+{
+    type Error = KeyTransparencyError;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .validate_member(signing_identity, timestamp, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))?;
+
+        self.check_inclusion(signing_identity).await
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .validate_external_sender(signing_identity, timestamp, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .identity(signing_identity, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))
+    }
+
+    async fn user_identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .user_identity(signing_identity, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        self.inner
+            .valid_successor(predecessor, successor, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))
+    }
+
+    async fn allow_shared_signature_key(
+        &self,
+        existing: &SigningIdentity,
+        new: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        self.inner
+            .allow_shared_signature_key(existing, new, extensions)
+            .await
+            .map_err(|e| KeyTransparencyError::InnerError(e.into_any_error()))
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        self.inner.supported_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(mls_build_async)]
+    use alloc::boxed::Box;
+    use alloc::{vec, vec::Vec};
+    use assert_matches::assert_matches;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use mls_rs_core::{
+        error::{AnyError, IntoAnyError},
+        identity::{IdentityProvider, SigningIdentity},
+    };
+
+    use crate::{
+        client::test_utils::TEST_CIPHER_SUITE,
+        identity::{basic::BasicIdentityProvider, test_utils::get_test_signing_identity},
+    };
+
+    use super::{
+        KeyTransparencyError, KeyTransparencyLog, KeyTransparencyPolicy, KeyTransparencyProvider,
+    };
+
+    #[derive(Debug)]
+    struct LogUnavailable;
+
+    impl IntoAnyError for LogUnavailable {}
+
+    #[derive(Default)]
+    struct TestLog {
+        included: Vec<SigningIdentity>,
+        available: bool,
+        lookups: AtomicUsize,
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl KeyTransparencyLog for &TestLog {
+        async fn verify_inclusion(
+            &self,
+            signing_identity: &SigningIdentity,
+        ) -> Result<bool, AnyError> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+
+            if !self.available {
+                return Err(LogUnavailable.into_any_error());
+            }
+
+            Ok(self.included.contains(signing_identity))
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_missing_from_log_are_rejected() {
+        let (alice, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"alice").await;
+        let (bob, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let log = TestLog {
+            included: vec![alice.clone()],
+            available: true,
+            ..Default::default()
+        };
+
+        let provider = KeyTransparencyProvider::new(
+            BasicIdentityProvider::new(),
+            &log,
+            KeyTransparencyPolicy::HardFail,
+            16,
+        );
+
+        for _ in 0..2 {
+            provider.validate_member(&alice, None, None).await.unwrap();
+        }
+
+        assert_eq!(log.lookups.load(Ordering::Relaxed), 1);
+
+        let res = provider.validate_member(&bob, None, None).await;
+        assert_matches!(res, Err(KeyTransparencyError::NotIncluded));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unavailable_log_follows_policy() {
+        let (alice, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"alice").await;
+        let log = TestLog::default();
+
+        let hard_fail = KeyTransparencyProvider::new(
+            BasicIdentityProvider::new(),
+            &log,
+            KeyTransparencyPolicy::HardFail,
+            16,
+        );
+
+        let res = hard_fail.validate_member(&alice, None, None).await;
+        assert_matches!(res, Err(KeyTransparencyError::LogError(_)));
+
+        let soft_fail = KeyTransparencyProvider::new(
+            BasicIdentityProvider::new(),
+            &log,
+            KeyTransparencyPolicy::SoftFail,
+            16,
+        );
+
+        for _ in 0..2 {
+            soft_fail.validate_member(&alice, None, None).await.unwrap();
+        }
+
+        // Soft failures aren't cached.
+        assert_eq!(log.lookups.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,399 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{
+    crypto::{CryptoProvider, SignatureSecretKey},
+    error::IntoAnyError,
+    extension::MlsExtension,
+    identity::IdentityProvider,
+    key_package::KeyPackageStorage,
+    time::MlsTime,
+};
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    extension::{built_in::LastResortExt, channel_binding::ChannelBindingExt},
+    group::Capabilities,
+    identity::SigningIdentity,
+    tree_kem::Lifetime,
+    Client, ExtensionList, MlsMessage,
+};
+
+use super::{validate_key_package_properties, KeyPackageGeneration, KeyPackageGenerator};
+
+/// Builder of a key package, created with
+/// [`Client::key_package_builder`].
+///
+/// Properties that are not set explicitly are taken from the configuration
+/// of the client. The key package is validated before being stored and
+/// returned by [`KeyPackageBuilder::build`].
+pub struct KeyPackageBuilder<'a, C> {
+    client: &'a Client<C>,
+    lifetime: Lifetime,
+    capabilities: Capabilities,
+    key_package_extensions: ExtensionList,
+    leaf_node_extensions: ExtensionList,
+    last_resort: bool,
+    channel_binding: Option<Vec<u8>>,
+    signer: Option<(SigningIdentity, SignatureSecretKey)>,
+}
+
+impl<'a, C: ClientConfig> KeyPackageBuilder<'a, C> {
+    pub(crate) fn new(client: &'a Client<C>) -> Self {
+        Self {
+            client,
+            lifetime: client.config.lifetime(),
+            capabilities: client.config.capabilities(),
+            key_package_extensions: client.config.key_package_extensions(),
+            leaf_node_extensions: client.config.leaf_node_extensions(),
+            last_resort: false,
+            channel_binding: None,
+            signer: None,
+        }
+    }
+
+    /// Make the key package valid for `duration_in_s` seconds from now.
+    pub fn lifetime(self, duration_in_s: u64) -> Result<Self, MlsError> {
+        let not_before = self.client.config.lifetime().not_before;
+
+        let not_after = not_before
+            .checked_add(duration_in_s)
+            .ok_or(MlsError::TimeOverflow)?;
+
+        Ok(Self {
+            lifetime: Lifetime::new(not_before, not_after),
+            ..self
+        })
+    }
+
+    /// Make the key package valid from `not_before` to `not_after`.
+    pub fn valid_between(self, not_before: MlsTime, not_after: MlsTime) -> Self {
+        Self {
+            lifetime: Lifetime::new(
+                not_before.seconds_since_epoch(),
+                not_after.seconds_since_epoch(),
+            ),
+            ..self
+        }
+    }
+
+    /// Replace the capabilities of the leaf node.
+    pub fn capabilities(self, capabilities: Capabilities) -> Self {
+        Self {
+            capabilities,
+            ..self
+        }
+    }
+
+    /// Set a key package extension, replacing any extension of the same type.
+    pub fn extension<T: MlsExtension>(mut self, extension: T) -> Result<Self, MlsError> {
+        self.key_package_extensions.set_from(extension)?;
+        Ok(self)
+    }
+
+    /// Replace the key package extensions.
+    pub fn extensions(self, key_package_extensions: ExtensionList) -> Self {
+        Self {
+            key_package_extensions,
+            ..self
+        }
+    }
+
+    /// Set a leaf node extension, replacing any extension of the same type.
+    pub fn leaf_node_extension<T: MlsExtension>(mut self, extension: T) -> Result<Self, MlsError> {
+        self.leaf_node_extensions.set_from(extension)?;
+        Ok(self)
+    }
+
+    /// Replace the leaf node extensions.
+    pub fn leaf_node_extensions(self, leaf_node_extensions: ExtensionList) -> Self {
+        Self {
+            leaf_node_extensions,
+            ..self
+        }
+    }
+
+    /// Mark the key package as a [last resort](LastResortExt) key package.
+    pub fn last_resort(self, last_resort: bool) -> Self {
+        Self {
+            last_resort,
+            ..self
+        }
+    }
+
+    /// Bind the key package to the channel it is uploaded over, given the
+    /// exporter value of the channel. See [`ChannelBindingExt`].
+    pub fn channel_binding(self, exporter_value: Vec<u8>) -> Self {
+        Self {
+            channel_binding: Some(exporter_value),
+            ..self
+        }
+    }
+
+    /// Sign the key package with `signer` instead of the signer of the
+    /// client. The cipher suite of the client is still used.
+    pub fn signer(self, signing_identity: SigningIdentity, signer: SignatureSecretKey) -> Self {
+        Self {
+            signer: Some((signing_identity, signer)),
+            ..self
+        }
+    }
+
+    /// Generate the key package and store its secret keys in the
+    /// [KeyPackageStorage](crate::KeyPackageStorage) of the client.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn build(self) -> Result<MlsMessage, MlsError> {
+        Ok(self.build_generation().await?.key_package_message())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn build_generation(self) -> Result<KeyPackageGeneration, MlsError> {
+        let config = &self.client.config;
+        let (client_identity, cipher_suite) = self.client.signing_identity()?;
+
+        let (signing_identity, signing_key) = match &self.signer {
+            Some((signing_identity, signer)) => (signing_identity, signer),
+            None => (
+                client_identity,
+                self.client
+                    .signer
+                    .as_ref()
+                    .ok_or(MlsError::SignerNotFound)?,
+            ),
+        };
+
+        let version = self.client.version;
+
+        let cipher_suite_provider = config
+            .crypto_provider()
+            .cipher_suite_provider(cipher_suite)
+            .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+
+        self.validate(config, signing_identity).await?;
+
+        let mut key_package_extensions = self.key_package_extensions;
+
+        if self.last_resort {
+            key_package_extensions.set_from(LastResortExt)?;
+        } else {
+            key_package_extensions.remove(LastResortExt::EXTENSION_TYPE);
+        }
+
+        if let Some(exporter_value) = &self.channel_binding {
+            key_package_extensions
+                .set_from(ChannelBindingExt::new(&cipher_suite_provider, exporter_value).await?)?;
+        }
+
+        let key_package_generator = KeyPackageGenerator {
+            protocol_version: version,
+            cipher_suite_provider: &cipher_suite_provider,
+            signing_key,
+            signing_identity,
+            identity_provider: &config.identity_provider(),
+        };
+
+        let key_pkg_gen = key_package_generator
+            .generate(
+                self.lifetime,
+                self.capabilities,
+                key_package_extensions,
+                self.leaf_node_extensions,
+            )
+            .await?;
+
+        // This detects a signer that doesn't match the signing identity.
+        validate_key_package_properties(&key_pkg_gen.key_package, version, &cipher_suite_provider)
+            .await?;
+
+        let (id, key_package_data) = key_pkg_gen.to_storage()?;
+
+        config
+            .key_package_repo()
+            .insert(id, key_package_data)
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+        Ok(key_pkg_gen)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn validate(
+        &self,
+        config: &C,
+        signing_identity: &SigningIdentity,
+    ) -> Result<(), MlsError> {
+        if self.lifetime.not_before >= self.lifetime.not_after {
+            return Err(MlsError::InvalidLifetime);
+        }
+
+        let (_, cipher_suite) = self.client.signing_identity()?;
+
+        if !self.capabilities.cipher_suites.contains(&cipher_suite) {
+            return Err(MlsError::UnsupportedCipherSuite(cipher_suite));
+        }
+
+        if !self
+            .capabilities
+            .protocol_versions
+            .contains(&self.client.version)
+        {
+            return Err(MlsError::UnsupportedProtocolVersion(self.client.version));
+        }
+
+        if let Some(ext) = self
+            .leaf_node_extensions
+            .iter()
+            .find(|ext| !self.capabilities.extensions.contains(&ext.extension_type))
+        {
+            return Err(MlsError::ExtensionNotInCapabilities(ext.extension_type));
+        }
+
+        config
+            .identity_provider()
+            .validate_member(signing_identity, None, None)
+            .await
+            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        crypto::test_utils::test_cipher_suite_provider,
+        extension::{built_in::LastResortExt, test_utils::TestExtension, MlsCodecExtension},
+        group::{test_utils::test_group, Capabilities},
+        identity::test_utils::get_test_signing_identity,
+        time::MlsTime,
+        tree_kem::{leaf_node::LeafNodeSource, Lifetime},
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_package_properties_are_configurable() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let message = client
+            .key_package_builder()
+            .valid_between(MlsTime::from(1000), MlsTime::from(2000))
+            .extension(TestExtension::from(1))
+            .unwrap()
+            .last_resort(true)
+            .build()
+            .await
+            .unwrap();
+
+        let key_package = message.into_key_package().unwrap();
+
+        assert_matches!(
+            key_package.leaf_node.leaf_node_source,
+            LeafNodeSource::KeyPackage(ref lifetime) if *lifetime == Lifetime::new(1000, 2000)
+        );
+
+        assert!(key_package
+            .extensions
+            .has_extension(LastResortExt::extension_type()));
+
+        assert_eq!(
+            key_package.extensions.get_as::<TestExtension>().unwrap(),
+            Some(TestExtension::from(1))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn invalid_key_packages_are_rejected_at_build_time() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let res = client
+            .key_package_builder()
+            .valid_between(MlsTime::from(2000), MlsTime::from(1000))
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidLifetime));
+
+        let res = client
+            .key_package_builder()
+            .capabilities(Capabilities {
+                cipher_suites: vec![],
+                ..Default::default()
+            })
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::UnsupportedCipherSuite(_)));
+
+        let res = client
+            .key_package_builder()
+            .leaf_node_extension(TestExtension::from(1))
+            .unwrap()
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::ExtensionNotInCapabilities(t)) if t == 42.into());
+
+        let (signing_identity, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+        let (_, other_signer) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let res = client
+            .key_package_builder()
+            .signer(signing_identity, other_signer)
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn last_resort_key_package_is_kept_after_join() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let key_package = bob
+            .key_package_builder()
+            .last_resort(true)
+            .build()
+            .await
+            .unwrap();
+
+        let reference = key_package
+            .clone()
+            .into_key_package()
+            .unwrap()
+            .to_reference(&test_cipher_suite_provider(TEST_CIPHER_SUITE))
+            .await
+            .unwrap();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        bob_group.write_to_storage().await.unwrap();
+
+        assert!(bob.key_package_store().get(&reference).is_some());
+    }
+}
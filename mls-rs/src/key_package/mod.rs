@@ -21,6 +21,9 @@ use mls_rs_codec::MlsEncode;
 use mls_rs_codec::MlsSize;
 use mls_rs_core::extension::ExtensionList;
 
+mod builder;
+pub use builder::KeyPackageBuilder;
+
 mod validator;
 pub(crate) use validator::*;
 
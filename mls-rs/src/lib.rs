@@ -34,6 +34,8 @@
 //! - Support for WASM builds.
 //! - Configurable storage for key packages, secrets and group state
 //!   via traits along with provided "in memory" and SQLite implementations.
+//! - Signing with keys held by remote services, with an AWS KMS implementation
+//!   that also protects external pre-shared keys.
 //! - Support for custom user proposal and extension types.
 //! - Ability to create user defined credentials with custom validation
 //!   routines that can bridge to existing credential schemes.
@@ -133,6 +135,10 @@ pub mod client_builder;
 mod client_config;
 /// Dependencies of [`CryptoProvider`] and [`CipherSuiteProvider`]
 pub mod crypto;
+/// Interface to delivery services, with reference implementations.
+#[cfg(feature = "delivery")]
+#[cfg_attr(docsrs, doc(cfg(feature = "delivery")))]
+pub mod delivery;
 /// Extension utilities and built-in extension types.
 pub mod extension;
 /// Tools to observe groups without being a member, useful
@@ -148,8 +154,18 @@ mod hash_reference;
 pub mod identity;
 mod iter;
 mod key_package;
+/// Requests to MIMI delivery services.
+#[cfg(feature = "mimi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mimi")))]
+pub mod mimi;
 /// Pre-shared key support.
 pub mod psk;
+/// Transport of MLS messages over QUIC datagrams.
+#[cfg(feature = "quic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "quic")))]
+pub mod quic;
+/// Reporting of protocol violations for security monitoring.
+pub mod security_event;
 mod signer;
 /// Storage providers to use with
 /// [`ClientBuilder`](client_builder::ClientBuilder).
@@ -157,7 +173,7 @@ pub mod storage_provider;
 
 pub use mls_rs_core::{
     crypto::{CipherSuiteProvider, CryptoProvider},
-    group::GroupStateStorage,
+    group::{GroupStateStorage, ReplayStore},
     identity::IdentityProvider,
     key_package::KeyPackageStorage,
     psk::PreSharedKeyStorage,
@@ -172,6 +188,8 @@ pub mod mls_rules {
         proposal_filter::{ProposalBundle, ProposalInfo, ProposalSource},
     };
 
+    pub use crate::group::padding::PaddingMode;
+
     #[cfg(feature = "by_ref_proposal")]
     pub use crate::group::proposal_ref::ProposalRef;
 }
@@ -195,12 +213,11 @@ pub mod error {
     pub use mls_rs_core::extension::ExtensionError;
 }
 
-/// WASM compatible timestamp.
-pub mod time {
-    pub use mls_rs_core::time::*;
-}
+/// WASM compatible timestamp and source of the current time.
+pub mod time;
 
 mod tree_kem;
+pub mod tree_math;
 
 pub use mls_rs_codec;
 
@@ -0,0 +1,323 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Mapping of MLS messages onto the HTTP API of a MIMI delivery service, as
+//! described in [draft-ietf-mimi-protocol].
+//!
+//! [`MimiEndpoints`] builds the [`DsRequest`] to send for each artifact
+//! produced by a [`Client`](crate::Client) or a [`Group`](crate::Group):
+//!
+//! | Artifact | Request |
+//! |----------|---------|
+//! | Key packages | `PUT {prefix}/keyMaterial/{user}` |
+//! | Commit with its welcome messages and group info | `POST {prefix}/update/{room}` |
+//! | Fan-out of a message to the members of a room | `POST {prefix}/notify/{room}` |
+//! | Group info allowing external commits | `PUT {prefix}/groupInfo/{room}` |
+//!
+//! MIMI is still a draft and leaves key package publication to the provider
+//! of each user. The paths above follow the draft, and the prefix is the one
+//! advertised by the directory of the delivery service. Sending the requests
+//! is left to the application.
+//!
+//! [draft-ietf-mimi-protocol]: https://datatracker.ietf.org/doc/draft-ietf-mimi-protocol/
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{client::MlsError, group::CommitOutput, MlsMessage, WireFormat};
+
+/// Media type of MLS messages, registered by RFC 9420.
+pub const MLS_MEDIA_TYPE: &str = "message/mls";
+
+/// Media type of the bodies that are not a single MLS message.
+pub const MIMI_MEDIA_TYPE: &str = "application/mimi-protocol";
+
+/// HTTP method of a [`DsRequest`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+}
+
+impl Method {
+    /// Name of the method in an HTTP request line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+        }
+    }
+}
+
+/// HTTP request to a MIMI delivery service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DsRequest {
+    pub method: Method,
+    /// Path of the request, with its segments percent-encoded.
+    pub path: String,
+    /// `Content-Type` of the body, if any.
+    pub content_type: Option<&'static str>,
+    pub body: Vec<u8>,
+}
+
+/// Body of a request submitting a commit to the hub of a room.
+///
+/// The hub fans out `commit` to the members of the room, `welcome_messages`
+/// to the new members and stores `group_info`, if any, for external joins.
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+#[non_exhaustive]
+pub struct HandshakeBundle {
+    pub commit: MlsMessage,
+    pub welcome_messages: Vec<MlsMessage>,
+    pub group_info: Option<MlsMessage>,
+}
+
+impl From<&CommitOutput> for HandshakeBundle {
+    fn from(output: &CommitOutput) -> Self {
+        Self {
+            commit: output.commit_message.clone(),
+            welcome_messages: output.welcome_messages.clone(),
+            group_info: output.external_commit_group_info.clone(),
+        }
+    }
+}
+
+impl HandshakeBundle {
+    /// Parse the body of a request built by [`MimiEndpoints::submit_commit`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+}
+
+/// Endpoints of a MIMI delivery service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MimiEndpoints {
+    prefix: String,
+}
+
+impl MimiEndpoints {
+    /// Endpoints under `prefix`, such as `/mimi/v1`.
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').into(),
+        }
+    }
+
+    /// Publish `key_packages` of `user`, to be claimed by the clients adding
+    /// `user` to a room.
+    pub fn publish_key_packages(
+        &self,
+        user: &str,
+        key_packages: &[MlsMessage],
+    ) -> Result<DsRequest, MlsError> {
+        if key_packages
+            .iter()
+            .any(|kp| kp.wire_format() != WireFormat::KeyPackage)
+        {
+            return Err(MlsError::UnexpectedMessageType);
+        }
+
+        Ok(DsRequest {
+            method: Method::Put,
+            path: self.path("keyMaterial", user),
+            content_type: Some(MIMI_MEDIA_TYPE),
+            body: key_packages.mls_encode_to_vec()?,
+        })
+    }
+
+    /// Claim a key package of `user` to add them to a room.
+    pub fn claim_key_package(&self, user: &str) -> DsRequest {
+        DsRequest {
+            method: Method::Get,
+            path: self.path("keyMaterial", user),
+            content_type: None,
+            body: Vec::new(),
+        }
+    }
+
+    /// Submit `commit_output` to the hub of `room`, which fans it out to the
+    /// members of the room.
+    pub fn submit_commit(
+        &self,
+        room: &str,
+        commit_output: &CommitOutput,
+    ) -> Result<DsRequest, MlsError> {
+        Ok(DsRequest {
+            method: Method::Post,
+            path: self.path("update", room),
+            content_type: Some(MIMI_MEDIA_TYPE),
+            body: HandshakeBundle::from(commit_output).mls_encode_to_vec()?,
+        })
+    }
+
+    /// Deliver `message` to the members of `room`. This is used by the hub
+    /// for the fan-out of commits, proposals, application messages and
+    /// welcome messages.
+    pub fn notify(&self, room: &str, message: &MlsMessage) -> Result<DsRequest, MlsError> {
+        Ok(DsRequest {
+            method: Method::Post,
+            path: self.path("notify", room),
+            content_type: Some(MLS_MEDIA_TYPE),
+            body: message.to_bytes()?,
+        })
+    }
+
+    /// Publish `group_info` of `room`, allowing clients to join it with an
+    /// external commit.
+    pub fn publish_group_info(
+        &self,
+        room: &str,
+        group_info: &MlsMessage,
+    ) -> Result<DsRequest, MlsError> {
+        if group_info.as_group_info().is_none() {
+            return Err(MlsError::UnexpectedMessageType);
+        }
+
+        Ok(DsRequest {
+            method: Method::Put,
+            path: self.path("groupInfo", room),
+            content_type: Some(MLS_MEDIA_TYPE),
+            body: group_info.to_bytes()?,
+        })
+    }
+
+    /// Fetch the group info of `room`, published with
+    /// [`MimiEndpoints::publish_group_info`].
+    pub fn fetch_group_info(&self, room: &str) -> DsRequest {
+        DsRequest {
+            method: Method::Get,
+            path: self.path("groupInfo", room),
+            content_type: None,
+            body: Vec::new(),
+        }
+    }
+
+    fn path(&self, endpoint: &str, target: &str) -> String {
+        let mut path = self.prefix.clone();
+
+        path.push('/');
+        path.push_str(endpoint);
+        path.push('/');
+
+        for byte in target.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                path.push(byte as char);
+            } else {
+                // Writing to a `String` can't fail.
+                let _ = write!(path, "%{byte:02X}");
+            }
+        }
+
+        path
+    }
+}
+
+/// Parse the key packages published with
+/// [`MimiEndpoints::publish_key_packages`].
+pub fn key_packages_from_bytes(bytes: &[u8]) -> Result<Vec<MlsMessage>, MlsError> {
+    Vec::<MlsMessage>::mls_decode(&mut &*bytes).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group_custom,
+        mls_rules::CommitOptions,
+    };
+
+    use super::*;
+
+    #[test]
+    fn targets_are_percent_encoded() {
+        let endpoints = MimiEndpoints::new("/mimi/v1/");
+
+        let request = endpoints.fetch_group_info("mimi://example.com/r/room 1");
+
+        assert_eq!(request.method, Method::Get);
+
+        assert_eq!(
+            request.path,
+            "/mimi/v1/groupInfo/mimi%3A%2F%2Fexample.com%2Fr%2Froom%201"
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_is_submitted_with_its_artifacts() {
+        let endpoints = MimiEndpoints::new("/mimi/v1");
+
+        let mut group = test_group_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            vec![],
+            None,
+            CommitOptions::default()
+                .with_allow_external_commit(true)
+                .into(),
+        )
+        .await;
+
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let request = endpoints
+            .publish_key_packages("bob@example.com", &[key_package.clone()])
+            .unwrap();
+
+        assert_eq!(request.path, "/mimi/v1/keyMaterial/bob%40example.com");
+        assert_eq!(
+            key_packages_from_bytes(&request.body).unwrap(),
+            [key_package.clone()]
+        );
+
+        let commit_output = group
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let request = endpoints.submit_commit("room", &commit_output).unwrap();
+
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.path, "/mimi/v1/update/room");
+
+        let bundle = HandshakeBundle::from_bytes(&request.body).unwrap();
+
+        assert_eq!(bundle.commit, commit_output.commit_message);
+        assert_eq!(bundle.welcome_messages, commit_output.welcome_messages);
+        assert!(bundle.group_info.is_some());
+
+        let request = endpoints
+            .publish_group_info("room", &bundle.group_info.unwrap())
+            .unwrap();
+
+        assert_eq!(request.content_type, Some(MLS_MEDIA_TYPE));
+
+        let res = endpoints.publish_group_info("room", &bundle.commit);
+        assert_matches!(res, Err(MlsError::UnexpectedMessageType));
+
+        let request = endpoints.notify("room", &bundle.commit).unwrap();
+
+        assert_eq!(request.path, "/mimi/v1/notify/room");
+        assert_eq!(
+            MlsMessage::from_bytes(&request.body).unwrap(),
+            bundle.commit
+        );
+    }
+}
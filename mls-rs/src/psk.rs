@@ -20,7 +20,10 @@ use core::fmt::{self, Debug};
 use crate::{client::MlsError, CipherSuiteProvider};
 
 #[cfg(feature = "psk")]
-use mls_rs_core::error::IntoAnyError;
+use mls_rs_core::{error::IntoAnyError, secret::Secret};
+
+#[cfg(feature = "psk")]
+use self::secret::{PskSecret, PskSecretInput};
 
 #[cfg(feature = "psk")]
 pub(crate) mod resolver;
@@ -76,13 +79,19 @@ impl Debug for PskGroupId {
     }
 }
 
+/// Nonce of a pre-shared key, as long as the output of the KDF of the cipher
+/// suite of the group using it.
+///
+/// The length of the nonces received in proposals and welcome messages is
+/// checked against the cipher suite of the group. Nonces built with
+/// [`PskNonce::new`] or [`PskNonce::random`] always have the right length.
 #[derive(Clone, Eq, Hash, PartialEq, PartialOrd, Ord, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub(crate) struct PskNonce(
+pub struct PskNonce(
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
     #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
-    pub Vec<u8>,
+    Vec<u8>,
 );
 
 impl Debug for PskNonce {
@@ -95,6 +104,18 @@ impl Debug for PskNonce {
 
 #[cfg(feature = "psk")]
 impl PskNonce {
+    /// Create a nonce from `nonce`, which must be `kdf_extract_size` bytes
+    /// long for `cipher_suite_provider`.
+    pub fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        nonce: Vec<u8>,
+    ) -> Result<Self, MlsError> {
+        let nonce = Self(nonce);
+        nonce.validate(cipher_suite_provider)?;
+        Ok(nonce)
+    }
+
+    /// Generate a random nonce for `cipher_suite_provider`.
     pub fn random<P: CipherSuiteProvider>(
         cipher_suite_provider: &P,
     ) -> Result<Self, <P as CipherSuiteProvider>::Error> {
@@ -102,6 +123,24 @@ impl PskNonce {
             cipher_suite_provider.kdf_extract_size(),
         )?))
     }
+
+    /// Raw bytes of the nonce.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Check the length of a nonce received for a group using
+    /// `cipher_suite_provider`.
+    pub(crate) fn validate<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+    ) -> Result<(), MlsError> {
+        if self.0.len() == cipher_suite_provider.kdf_extract_size() {
+            Ok(())
+        } else {
+            Err(MlsError::InvalidPskNonceLength)
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialOrd, PartialEq, MlsSize, MlsEncode, MlsDecode)]
@@ -123,6 +162,42 @@ pub(crate) enum ResumptionPSKUsage {
     Branch = 3u8,
 }
 
+/// Pre-shared key that was injected into the key schedule of an epoch.
+#[cfg(feature = "psk")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UsedPsk {
+    /// External pre-shared key.
+    External(ExternalPskId),
+    /// Resumption pre-shared key from `epoch` of the group `group_id`.
+    Resumption { group_id: Vec<u8>, epoch: u64 },
+}
+
+#[cfg(feature = "psk")]
+impl From<&JustPreSharedKeyID> for UsedPsk {
+    fn from(id: &JustPreSharedKeyID) -> Self {
+        match id {
+            JustPreSharedKeyID::External(id) => Self::External(id.clone()),
+            JustPreSharedKeyID::Resumption(r) => Self::Resumption {
+                group_id: r.psk_group_id.0.clone(),
+                epoch: r.psk_epoch,
+            },
+        }
+    }
+}
+
+/// Pre-shared keys used to enter an epoch, as reported by
+/// [`Group::psk_usage_history`](crate::Group::psk_usage_history).
+#[cfg(feature = "psk")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PskUsage {
+    /// Epoch whose key schedule used the pre-shared keys.
+    pub epoch: u64,
+    /// Pre-shared keys used, in the order they were combined.
+    pub psks: Vec<UsedPsk>,
+}
+
 #[cfg(feature = "psk")]
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode)]
 struct PSKLabel<'a> {
@@ -131,6 +206,51 @@ struct PSKLabel<'a> {
     count: u16,
 }
 
+/// External pre-shared key used as input to [`psk_secret`].
+#[cfg(feature = "psk")]
+#[derive(Clone, Debug)]
+pub struct ExternalPskInput {
+    /// Identifier of the pre-shared key.
+    pub id: ExternalPskId,
+    /// Nonce used with this pre-shared key, created for the cipher suite in
+    /// use.
+    pub nonce: PskNonce,
+    /// Value of the pre-shared key.
+    pub psk: PreSharedKey,
+}
+
+/// Compute the `psk_secret` combining `psks`, as defined in
+/// [RFC 9420 section 8.4](https://www.rfc-editor.org/rfc/rfc9420.html#section-8.4).
+///
+/// This is the value that MLS injects into the key schedule when PSK proposals
+/// are committed. It allows protocols built next to MLS to bind to the same
+/// pre-shared keys. The order of `psks` matters.
+#[cfg(feature = "psk")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn psk_secret<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    psks: &[ExternalPskInput],
+) -> Result<Secret, MlsError> {
+    let input = psks
+        .iter()
+        .map(|input| {
+            input.nonce.validate(cipher_suite_provider)?;
+
+            Ok(PskSecretInput {
+                id: PreSharedKeyID {
+                    key_id: JustPreSharedKeyID::External(input.id.clone()),
+                    psk_nonce: input.nonce.clone(),
+                },
+                psk: input.psk.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, MlsError>>()?;
+
+    PskSecret::calculate(&input, cipher_suite_provider)
+        .await
+        .map(|secret| secret.to_vec().into())
+}
+
 #[cfg(any(test, feature = "external_client"))]
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct AlwaysFoundPskStorage;
@@ -149,6 +269,8 @@ impl PreSharedKeyStorage for AlwaysFoundPskStorage {
 #[cfg(feature = "psk")]
 #[cfg(test)]
 pub(crate) mod test_utils {
+    use alloc::vec;
+
     use crate::crypto::test_utils::test_cipher_suite_provider;
 
     use super::PskNonce;
@@ -172,6 +294,12 @@ pub(crate) mod test_utils {
     pub(crate) fn make_nonce(cipher_suite: CipherSuite) -> PskNonce {
         PskNonce::random(&test_cipher_suite_provider(cipher_suite)).unwrap()
     }
+
+    /// Nonce too short for any cipher suite, as could be received from
+    /// another member.
+    pub(crate) fn make_invalid_nonce() -> PskNonce {
+        PskNonce(vec![0, 1, 2])
+    }
 }
 
 #[cfg(feature = "psk")]
@@ -185,6 +313,23 @@ mod tests {
 
     use super::test_utils::make_nonce;
 
+    #[cfg(not(mls_build_async))]
+    use crate::{client::MlsError, crypto::test_utils::test_cipher_suite_provider};
+
+    #[cfg(not(mls_build_async))]
+    use alloc::vec;
+
+    #[cfg(not(mls_build_async))]
+    use super::{
+        psk_secret,
+        secret::{PskSecret, PskSecretInput},
+        test_utils::{make_external_psk_id, make_invalid_nonce},
+        ExternalPskInput, JustPreSharedKeyID, PreSharedKeyID, PskNonce,
+    };
+
+    #[cfg(not(mls_build_async))]
+    use mls_rs_core::crypto::CipherSuiteProvider;
+
     #[test]
     fn random_generation_of_nonces_is_random() {
         let good = TestCryptoProvider::all_supported_cipher_suites()
@@ -198,4 +343,58 @@ mod tests {
 
         assert!(good);
     }
+
+    #[cfg(not(mls_build_async))]
+    #[test]
+    fn public_psk_secret_matches_key_schedule() {
+        for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
+            let cs = test_cipher_suite_provider(cipher_suite);
+
+            let input = ExternalPskInput {
+                id: make_external_psk_id(&cs),
+                nonce: make_nonce(cipher_suite),
+                psk: vec![1, 2, 3].into(),
+            };
+
+            let internal = PskSecretInput {
+                id: PreSharedKeyID {
+                    key_id: JustPreSharedKeyID::External(input.id.clone()),
+                    psk_nonce: input.nonce.clone(),
+                },
+                psk: input.psk.clone(),
+            };
+
+            let expected = PskSecret::calculate(&[internal], &cs).unwrap();
+            let computed = psk_secret(&cs, core::slice::from_ref(&input)).unwrap();
+
+            assert_eq!(&*computed, &*expected);
+
+            let bad_nonce = ExternalPskInput {
+                nonce: make_invalid_nonce(),
+                ..input
+            };
+
+            assert_matches::assert_matches!(
+                psk_secret(&cs, &[bad_nonce]),
+                Err(MlsError::InvalidPskNonceLength)
+            );
+        }
+    }
+
+    #[cfg(not(mls_build_async))]
+    #[test]
+    fn nonce_length_is_enforced() {
+        for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
+            let cs = test_cipher_suite_provider(cipher_suite);
+            let len = cs.kdf_extract_size();
+
+            let nonce = PskNonce::new(&cs, vec![7; len]).unwrap();
+            assert_eq!(nonce.as_bytes(), vec![7; len]);
+
+            assert_matches::assert_matches!(
+                PskNonce::new(&cs, vec![7; len - 1]),
+                Err(MlsError::InvalidPskNonceLength)
+            );
+        }
+    }
 }
@@ -36,7 +36,7 @@ pub(crate) struct PskSecret(Zeroizing<Vec<u8>>);
 
 impl Debug for PskSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        mls_rs_core::debug::pretty_bytes(&self.0)
+        mls_rs_core::debug::pretty_secret(&self.0)
             .named("PskSecret")
             .fmt(f)
     }
@@ -0,0 +1,376 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Transport of MLS messages over QUIC datagrams, as defined in [RFC 9221].
+//!
+//! QUIC datagrams are unreliable and limited in size by the
+//! `max_datagram_frame_size` transport parameter and the path MTU. Real-time
+//! applications using MLS for the key agreement of a media session can use
+//! [`DatagramSender`] to split each [`MlsMessage`] into datagrams of at most
+//! `max_datagram_size` bytes, and [`DatagramReceiver`] to reassemble them.
+//!
+//! Each datagram starts with a header of [`DATAGRAM_HEADER_SIZE`] bytes:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | Message id | 4 bytes |
+//! | Index of the chunk | 2 bytes |
+//! | Number of chunks | 2 bytes |
+//!
+//! All fields are big endian. Handshake messages, which are all messages but
+//! private application messages, are sent before application messages so that
+//! a commit isn't delayed by media traffic. Messages that have lost a chunk
+//! are never completed and are eventually dropped by the receiver;
+//! retransmission is left to the application.
+//!
+//! [RFC 9221]: https://www.rfc-editor.org/rfc/rfc9221
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+
+use crate::{client::MlsError, MlsMessage};
+
+/// Size of the header prepended to each datagram.
+pub const DATAGRAM_HEADER_SIZE: usize = 8;
+
+/// Default number of partially received messages kept by a
+/// [`DatagramReceiver`].
+pub const DEFAULT_MAX_PENDING_MESSAGES: usize = 64;
+
+/// Splits MLS messages into QUIC datagrams.
+#[derive(Clone, Debug)]
+pub struct DatagramSender {
+    max_datagram_size: usize,
+    next_message_id: u32,
+    handshake: VecDeque<Vec<u8>>,
+    application: VecDeque<Vec<u8>>,
+}
+
+impl DatagramSender {
+    /// Sender producing datagrams of at most `max_datagram_size` bytes.
+    pub fn new(max_datagram_size: usize) -> Result<Self, MlsError> {
+        check_datagram_size(max_datagram_size)?;
+
+        Ok(Self {
+            max_datagram_size,
+            next_message_id: 0,
+            handshake: VecDeque::new(),
+            application: VecDeque::new(),
+        })
+    }
+
+    /// Maximum size of the datagrams produced by this sender.
+    pub fn max_datagram_size(&self) -> usize {
+        self.max_datagram_size
+    }
+
+    /// Change the maximum size of the datagrams, for instance after the path
+    /// MTU changed. Datagrams already queued keep their size.
+    pub fn set_max_datagram_size(&mut self, max_datagram_size: usize) -> Result<(), MlsError> {
+        check_datagram_size(max_datagram_size)?;
+        self.max_datagram_size = max_datagram_size;
+        Ok(())
+    }
+
+    /// Split `message` into datagrams and queue them for sending.
+    pub fn push(&mut self, message: &MlsMessage) -> Result<(), MlsError> {
+        let bytes = message.to_bytes()?;
+        let chunk_size = self.max_datagram_size - DATAGRAM_HEADER_SIZE;
+
+        let count = u16::try_from((bytes.len() + chunk_size - 1) / chunk_size)
+            .map_err(|_| MlsError::MessageTooLargeForDatagrams)?;
+
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        let queue = if message.is_application_message() {
+            &mut self.application
+        } else {
+            &mut self.handshake
+        };
+
+        queue.extend(bytes.chunks(chunk_size).zip(0u16..).map(|(chunk, index)| {
+            let mut datagram = Vec::with_capacity(DATAGRAM_HEADER_SIZE + chunk.len());
+            datagram.extend_from_slice(&message_id.to_be_bytes());
+            datagram.extend_from_slice(&index.to_be_bytes());
+            datagram.extend_from_slice(&count.to_be_bytes());
+            datagram.extend_from_slice(chunk);
+            datagram
+        }));
+
+        Ok(())
+    }
+
+    /// Next datagram to send, if any. Datagrams of handshake messages are
+    /// returned before those of application messages.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.handshake
+            .pop_front()
+            .or_else(|| self.application.pop_front())
+    }
+
+    /// Number of datagrams waiting to be sent.
+    pub fn pending(&self) -> usize {
+        self.handshake.len() + self.application.len()
+    }
+}
+
+fn check_datagram_size(max_datagram_size: usize) -> Result<(), MlsError> {
+    if max_datagram_size <= DATAGRAM_HEADER_SIZE {
+        return Err(MlsError::DatagramSizeTooSmall(max_datagram_size));
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+struct PartialMessage {
+    // Chunks are stored as they are received, so that the memory used by a
+    // partial message is bounded by the datagrams actually received rather
+    // than by the number of chunks claimed in their header.
+    chunks: BTreeMap<usize, Vec<u8>>,
+    count: usize,
+}
+
+/// Reassembles MLS messages from QUIC datagrams produced by a
+/// [`DatagramSender`].
+#[derive(Clone, Debug)]
+pub struct DatagramReceiver {
+    max_pending_messages: usize,
+    pending: BTreeMap<u32, PartialMessage>,
+    arrival: VecDeque<u32>,
+}
+
+impl Default for DatagramReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatagramReceiver {
+    /// Receiver keeping up to [`DEFAULT_MAX_PENDING_MESSAGES`] partially
+    /// received messages.
+    pub fn new() -> Self {
+        Self {
+            max_pending_messages: DEFAULT_MAX_PENDING_MESSAGES,
+            pending: BTreeMap::new(),
+            arrival: VecDeque::new(),
+        }
+    }
+
+    /// Receiver keeping up to `max_pending_messages` partially received
+    /// messages. When the limit is reached, the oldest partial message is
+    /// dropped.
+    pub fn with_max_pending_messages(max_pending_messages: usize) -> Result<Self, MlsError> {
+        if max_pending_messages == 0 {
+            return Err(MlsError::NonZeroRetentionRequired);
+        }
+
+        Ok(Self {
+            max_pending_messages,
+            ..Self::new()
+        })
+    }
+
+    /// Process a received datagram, returning the message it completes, if
+    /// any.
+    pub fn receive(&mut self, datagram: &[u8]) -> Result<Option<MlsMessage>, MlsError> {
+        if datagram.len() < DATAGRAM_HEADER_SIZE {
+            return Err(MlsError::InvalidDatagram);
+        }
+
+        let (header, chunk) = datagram.split_at(DATAGRAM_HEADER_SIZE);
+
+        let message_id = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let index = usize::from(u16::from_be_bytes([header[4], header[5]]));
+        let count = usize::from(u16::from_be_bytes([header[6], header[7]]));
+
+        if index >= count {
+            return Err(MlsError::InvalidDatagram);
+        }
+
+        if count == 1 {
+            return MlsMessage::from_bytes(chunk).map(Some);
+        }
+
+        if !self.pending.contains_key(&message_id) {
+            if self.arrival.len() >= self.max_pending_messages {
+                if let Some(oldest) = self.arrival.pop_front() {
+                    self.pending.remove(&oldest);
+                }
+            }
+
+            self.arrival.push_back(message_id);
+
+            self.pending.insert(
+                message_id,
+                PartialMessage {
+                    chunks: BTreeMap::new(),
+                    count,
+                },
+            );
+        }
+
+        let partial = self
+            .pending
+            .get_mut(&message_id)
+            .ok_or(MlsError::InvalidDatagram)?;
+
+        if partial.count != count {
+            return Err(MlsError::InvalidDatagram);
+        }
+
+        partial
+            .chunks
+            .entry(index)
+            .or_insert_with(|| chunk.to_vec());
+
+        if partial.chunks.len() < count {
+            return Ok(None);
+        }
+
+        let partial = self
+            .pending
+            .remove(&message_id)
+            .ok_or(MlsError::InvalidDatagram)?;
+
+        self.arrival.retain(|id| *id != message_id);
+
+        let bytes = partial.chunks.into_values().flatten().collect::<Vec<_>>();
+
+        MlsMessage::from_bytes(&bytes).map(Some)
+    }
+
+    /// Number of partially received messages.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_group,
+    };
+
+    use super::*;
+
+    #[test]
+    fn datagram_size_must_fit_the_header() {
+        assert_matches!(
+            DatagramSender::new(DATAGRAM_HEADER_SIZE),
+            Err(MlsError::DatagramSizeTooSmall(DATAGRAM_HEADER_SIZE))
+        );
+
+        assert_matches!(DatagramSender::new(DATAGRAM_HEADER_SIZE + 1), Ok(_));
+    }
+
+    #[test]
+    fn malformed_datagrams_are_rejected() {
+        let mut receiver = DatagramReceiver::new();
+
+        assert_matches!(receiver.receive(&[0; 4]), Err(MlsError::InvalidDatagram));
+
+        assert_matches!(
+            receiver.receive(&[0, 0, 0, 0, 0, 2, 0, 2]),
+            Err(MlsError::InvalidDatagram)
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn messages_are_chunked_and_reassembled() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let application = group
+            .group
+            .encrypt_application_message(&[1; 500], vec![])
+            .await
+            .unwrap();
+
+        let commit = group.group.commit(vec![]).await.unwrap().commit_message;
+
+        let mut sender = DatagramSender::new(100).unwrap();
+
+        sender.push(&application).unwrap();
+        sender.push(&commit).unwrap();
+
+        let mut datagrams = Vec::new();
+
+        while let Some(datagram) = sender.pop() {
+            assert!(datagram.len() <= 100);
+            datagrams.push(datagram);
+        }
+
+        // Handshake messages come first.
+        let commit_chunks = (commit.to_bytes().unwrap().len() + 91) / 92;
+        assert!(datagrams[..commit_chunks]
+            .iter()
+            .all(|d| d[..4] == [0, 0, 0, 1]));
+
+        let mut receiver = DatagramReceiver::new();
+
+        // Datagrams may be duplicated and reordered.
+        let received = datagrams
+            .last()
+            .into_iter()
+            .chain(datagrams.iter().rev())
+            .filter_map(|datagram| receiver.receive(datagram).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(received, [application, commit]);
+        assert_eq!(receiver.pending(), 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn oldest_partial_message_is_dropped() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let mut sender = DatagramSender::new(100).unwrap();
+        let mut receiver = DatagramReceiver::with_max_pending_messages(1).unwrap();
+
+        for _ in 0..2 {
+            let message = group
+                .group
+                .encrypt_application_message(&[1; 200], vec![])
+                .await
+                .unwrap();
+
+            sender.push(&message).unwrap();
+        }
+
+        let datagrams = core::iter::from_fn(|| sender.pop()).collect::<Vec<_>>();
+        let (first, second) = datagrams.split_at(datagrams.len() / 2);
+
+        assert_eq!(receiver.receive(&first[0]).unwrap(), None);
+        assert_eq!(receiver.receive(&second[0]).unwrap(), None);
+        assert_eq!(receiver.pending(), 1);
+
+        let completed = first[1..]
+            .iter()
+            .filter_map(|datagram| receiver.receive(datagram).unwrap())
+            .count();
+
+        assert_eq!(completed, 0);
+    }
+
+    #[test]
+    fn claimed_chunk_count_is_not_allocated_upfront() {
+        let mut receiver = DatagramReceiver::new();
+
+        let datagram = [0, 0, 0, 7, 0xff, 0xfe, 0xff, 0xff, 1, 2, 3];
+        assert_eq!(receiver.receive(&datagram).unwrap(), None);
+
+        let partial = &receiver.pending[&7];
+        assert_eq!(partial.count, 0xffff);
+        assert_eq!(partial.chunks.len(), 1);
+
+        // Chunks of the same message must claim the same count.
+        let datagram = [0, 0, 0, 7, 0, 0, 0, 2, 1];
+        assert_matches!(receiver.receive(&datagram), Err(MlsError::InvalidDatagram));
+    }
+}
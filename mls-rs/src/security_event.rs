@@ -0,0 +1,143 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::{self, Debug};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use crate::{client::MlsError, group::framing::WireFormat};
+
+/// Stable, machine-readable classification of a protocol violation.
+///
+/// The numeric value of each code is part of the public API and will not
+/// change between releases, so it can be used directly as an event identifier
+/// in monitoring pipelines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u16)]
+#[non_exhaustive]
+pub enum SecurityEventCode {
+    /// A signature on a message, leaf node or group info did not verify.
+    InvalidSignature = 1,
+    /// The parent hash chain of a received update path is invalid.
+    InvalidParentHash = 2,
+    /// A message was received for a key that was already consumed, or was
+    /// found in the [`ReplayStore`](crate::ReplayStore), which indicates a
+    /// replayed message.
+    Replay = 3,
+    /// A message was received for an epoch that is not the current epoch and
+    /// is not available in storage.
+    EpochMismatch = 4,
+    /// The [`MlsRules`](crate::MlsRules) or
+    /// [`IdentityProvider`](crate::IdentityProvider) in use rejected the message.
+    PolicyRejection = 5,
+    /// The membership tag of a public message is invalid.
+    InvalidMembershipTag = 6,
+    /// The confirmation tag of a commit is invalid.
+    InvalidConfirmationTag = 7,
+    /// The protocol version or cipher suite of a message, or of a key
+    /// package or group info it carries, doesn't match the group context.
+    DowngradeAttempt = 8,
+}
+
+impl SecurityEventCode {
+    /// Numeric value of this code.
+    pub fn code(&self) -> u16 {
+        *self as u16
+    }
+
+    /// Classify an error returned while processing an incoming message.
+    ///
+    /// Returns `None` for errors that do not indicate a protocol violation,
+    /// such as storage failures.
+    pub fn from_error(error: &MlsError) -> Option<Self> {
+        match error {
+            MlsError::InvalidSignature => Some(Self::InvalidSignature),
+            MlsError::ParentHashMismatch(_) => Some(Self::InvalidParentHash),
+            MlsError::KeyMissing(_) | MlsError::ReplayedMessage => Some(Self::Replay),
+            MlsError::InvalidEpoch | MlsError::EpochNotFound => Some(Self::EpochMismatch),
+            MlsError::MlsRulesError(_) | MlsError::IdentityProviderError(_) => {
+                Some(Self::PolicyRejection)
+            }
+            MlsError::InvalidMembershipTag => Some(Self::InvalidMembershipTag),
+            MlsError::InvalidConfirmationTag => Some(Self::InvalidConfirmationTag),
+            MlsError::ProtocolVersionMismatch | MlsError::CipherSuiteMismatch => {
+                Some(Self::DowngradeAttempt)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Security event emitted when an incoming message is rejected.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SecurityEvent<'a> {
+    /// Classification of the violation.
+    pub code: SecurityEventCode,
+    /// Identifier of the group that received the message.
+    pub group_id: &'a [u8],
+    /// Current epoch of the group that received the message.
+    pub epoch: u64,
+    /// Epoch claimed by the message, if any.
+    pub message_epoch: Option<u64>,
+    /// Wire format of the message.
+    pub wire_format: WireFormat,
+    /// Error that caused the message to be rejected.
+    pub error: &'a MlsError,
+}
+
+/// Receiver of [`SecurityEvent`]s.
+///
+/// A sink can be configured with
+/// [`ClientBuilder::security_event_sink`](crate::client_builder::ClientBuilder::security_event_sink).
+/// It is called synchronously while the message is being processed and
+/// should therefore not block.
+pub trait SecurityEventSink: Send + Sync {
+    fn on_event(&self, event: &SecurityEvent<'_>);
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedSecurityEventSink(pub(crate) Arc<dyn SecurityEventSink>);
+
+impl Debug for SharedSecurityEventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedSecurityEventSink").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::MlsError;
+
+    use super::SecurityEventCode;
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(SecurityEventCode::InvalidSignature.code(), 1);
+        assert_eq!(SecurityEventCode::InvalidParentHash.code(), 2);
+        assert_eq!(SecurityEventCode::Replay.code(), 3);
+        assert_eq!(SecurityEventCode::EpochMismatch.code(), 4);
+        assert_eq!(SecurityEventCode::PolicyRejection.code(), 5);
+        assert_eq!(SecurityEventCode::InvalidMembershipTag.code(), 6);
+        assert_eq!(SecurityEventCode::InvalidConfirmationTag.code(), 7);
+        assert_eq!(SecurityEventCode::DowngradeAttempt.code(), 8);
+    }
+
+    #[test]
+    fn non_security_errors_are_not_classified() {
+        assert_eq!(
+            SecurityEventCode::from_error(&MlsError::PendingCommitNotFound),
+            None
+        );
+
+        assert_eq!(
+            SecurityEventCode::from_error(&MlsError::KeyMissing(3)),
+            Some(SecurityEventCode::Replay)
+        );
+    }
+}
@@ -10,15 +10,31 @@ use mls_rs_core::error::IntoAnyError;
 use crate::client::MlsError;
 use crate::crypto::{CipherSuiteProvider, SignaturePublicKey, SignatureSecretKey};
 
+/// Labels of the objects signed by the protocol. Applications can't use them
+/// with [`sign_with_label`] so that their signatures can never be mistaken for
+/// signatures of protocol objects.
+const PROTOCOL_SIGN_LABELS: &[&str] = &[
+    "ExternalNoticeTBS",
+    "FramedContentTBS",
+    "GroupArchiveTBS",
+    "GroupInfoTBS",
+    "KeyPackageTBS",
+    "LeafNodeTBS",
+    "MemberEnvelopeTBS",
+    "MembershipProofTBS",
+    "RejoinProofTBS",
+    "TreeAttestationTBS",
+];
+
 #[derive(Clone, MlsSize, MlsEncode)]
-struct SignContent {
+struct SignContent<'a> {
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
     label: Vec<u8>,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
-    content: Vec<u8>,
+    content: &'a [u8],
 }
 
-impl Debug for SignContent {
+impl Debug for SignContent<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SignContent")
             .field("label", &mls_rs_core::debug::pretty_bytes(&self.label))
@@ -27,8 +43,8 @@ impl Debug for SignContent {
     }
 }
 
-impl SignContent {
-    pub fn new(label: &str, content: Vec<u8>) -> Self {
+impl<'a> SignContent<'a> {
+    pub fn new(label: &str, content: &'a [u8]) -> Self {
         Self {
             label: [b"MLS 1.0 ", label.as_bytes()].concat(),
             content,
@@ -36,6 +52,79 @@ impl SignContent {
     }
 }
 
+/// Sign `content` with the `SignWithLabel` function of
+/// [RFC 9420](https://www.rfc-editor.org/rfc/rfc9420.html#section-5.1.2).
+///
+/// The signature covers the `SignContent` structure made of `label`, prefixed
+/// with "MLS 1.0 ", and `content`. Applications signing their own data, e.g.
+/// in a custom extension, should use a label unique to the signed object.
+/// Labels used by the protocol itself are rejected with
+/// [`MlsError::ReservedSignatureLabel`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn sign_with_label<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    signer: &SignatureSecretKey,
+    label: &str,
+    content: &[u8],
+) -> Result<Vec<u8>, MlsError> {
+    check_label(label)?;
+    sign_labeled(cipher_suite_provider, signer, label, content).await
+}
+
+/// Verify a signature produced by [`sign_with_label`], implementing the
+/// `VerifyWithLabel` function of
+/// [RFC 9420](https://www.rfc-editor.org/rfc/rfc9420.html#section-5.1.2).
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_with_label<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    public_key: &SignaturePublicKey,
+    signature: &[u8],
+    label: &str,
+    content: &[u8],
+) -> Result<(), MlsError> {
+    check_label(label)?;
+    verify_labeled(cipher_suite_provider, public_key, signature, label, content).await
+}
+
+fn check_label(label: &str) -> Result<(), MlsError> {
+    if PROTOCOL_SIGN_LABELS.contains(&label) {
+        return Err(MlsError::ReservedSignatureLabel);
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn sign_labeled<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    signer: &SignatureSecretKey,
+    label: &str,
+    content: &[u8],
+) -> Result<Vec<u8>, MlsError> {
+    let sign_content = SignContent::new(label, content);
+
+    cipher_suite_provider
+        .sign(signer, &sign_content.mls_encode_to_vec()?)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_labeled<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    public_key: &SignaturePublicKey,
+    signature: &[u8],
+    label: &str,
+    content: &[u8],
+) -> Result<(), MlsError> {
+    let sign_content = SignContent::new(label, content);
+
+    cipher_suite_provider
+        .verify(public_key, signature, &sign_content.mls_encode_to_vec()?)
+        .await
+        .map_err(|_| MlsError::InvalidSignature)
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
 #[cfg_attr(
@@ -62,12 +151,9 @@ pub(crate) trait Signable<'a> {
         signer: &SignatureSecretKey,
         context: &Self::SigningContext,
     ) -> Result<(), MlsError> {
-        let sign_content = SignContent::new(Self::SIGN_LABEL, self.signable_content(context)?);
-
-        let signature = signature_provider
-            .sign(signer, &sign_content.mls_encode_to_vec()?)
-            .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+        let content = self.signable_content(context)?;
+        let signature =
+            sign_labeled(signature_provider, signer, Self::SIGN_LABEL, &content).await?;
 
         self.write_signature(signature);
 
@@ -80,16 +166,16 @@ pub(crate) trait Signable<'a> {
         public_key: &SignaturePublicKey,
         context: &Self::SigningContext,
     ) -> Result<(), MlsError> {
-        let sign_content = SignContent::new(Self::SIGN_LABEL, self.signable_content(context)?);
-
-        signature_provider
-            .verify(
-                public_key,
-                self.signature(),
-                &sign_content.mls_encode_to_vec()?,
-            )
-            .await
-            .map_err(|_| MlsError::InvalidSignature)
+        let content = self.signable_content(context)?;
+
+        verify_labeled(
+            signature_provider,
+            public_key,
+            self.signature(),
+            Self::SIGN_LABEL,
+            &content,
+        )
+        .await
     }
 }
 
@@ -171,6 +257,10 @@ pub(crate) mod test_utils {
             };
 
             signable.verify(cs, &public, &vec![]).await.unwrap();
+
+            super::verify_with_label(cs, &public, &self.signature, &self.label, &self.content)
+                .await
+                .unwrap();
         }
     }
 }
@@ -354,4 +444,101 @@ mod tests {
 
         assert_matches!(res, Err(MlsError::InvalidSignature));
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sign_with_label_round_trips() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let (secret, public) = cipher_suite_provider
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        let content = random_bytes(32);
+
+        let signature = sign_with_label(&cipher_suite_provider, &secret, "AppTBS", &content)
+            .await
+            .unwrap();
+
+        verify_with_label(
+            &cipher_suite_provider,
+            &public,
+            &signature,
+            "AppTBS",
+            &content,
+        )
+        .await
+        .unwrap();
+
+        let res = verify_with_label(
+            &cipher_suite_provider,
+            &public,
+            &signature,
+            "OtherTBS",
+            &content,
+        )
+        .await;
+
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn protocol_labels_are_reserved() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let (secret, public) = cipher_suite_provider
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        for label in PROTOCOL_SIGN_LABELS {
+            let res = sign_with_label(&cipher_suite_provider, &secret, label, &[]).await;
+            assert_matches!(res, Err(MlsError::ReservedSignatureLabel));
+
+            let res = verify_with_label(&cipher_suite_provider, &public, &[], label, &[]).await;
+            assert_matches!(res, Err(MlsError::ReservedSignatureLabel));
+        }
+    }
+
+    #[test]
+    fn labels_of_signed_objects_are_reserved() {
+        use crate::{
+            extension::rejoin_proof::RejoinProofExt,
+            group::{
+                message_signature::AuthenticatedContent, GroupInfo, MemberEnvelope, MembershipProof,
+            },
+            tree_kem::leaf_node::LeafNode,
+            KeyPackage,
+        };
+
+        #[cfg_attr(
+            not(any(feature = "by_ref_proposal", feature = "private_message")),
+            allow(unused_mut)
+        )]
+        let mut labels = vec![
+            <AuthenticatedContent as Signable<'_>>::SIGN_LABEL,
+            <GroupInfo as Signable<'_>>::SIGN_LABEL,
+            <KeyPackage as Signable<'_>>::SIGN_LABEL,
+            <LeafNode as Signable<'_>>::SIGN_LABEL,
+            <MemberEnvelope as Signable<'_>>::SIGN_LABEL,
+            <MembershipProof as Signable<'_>>::SIGN_LABEL,
+            <RejoinProofExt as Signable<'_>>::SIGN_LABEL,
+        ];
+
+        #[cfg(feature = "by_ref_proposal")]
+        labels.extend([
+            <crate::group::ExternalNotice as Signable<'_>>::SIGN_LABEL,
+            <crate::group::TreeAttestation as Signable<'_>>::SIGN_LABEL,
+        ]);
+
+        #[cfg(feature = "private_message")]
+        labels.push(<crate::group::GroupArchive as Signable<'_>>::SIGN_LABEL);
+
+        for label in labels {
+            assert!(
+                PROTOCOL_SIGN_LABELS.contains(&label),
+                "{label} isn't reserved"
+            );
+        }
+    }
 }
@@ -2,10 +2,16 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+mod cached;
+mod encrypted;
+mod gc;
 /// Storage providers that operate completely in memory.
 pub mod in_memory;
 pub(crate) mod key_package;
 
+pub use cached::CachedGroupStateStorage;
+pub use encrypted::{EncryptedGroupStateStorage, RewrapTask};
+pub use gc::{GcOptions, GcReport};
 pub use key_package::*;
 
 #[cfg(feature = "sqlite")]
@@ -0,0 +1,452 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt::{self, Debug};
+use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use crate::client::MlsError;
+
+use super::in_memory::{InMemoryGroupData, InMemoryGroupStateStorage};
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+/// Group state storage keeping recently used data in memory on top of
+/// another storage.
+///
+/// The current state of each group and its most recent epochs are kept in
+/// memory. Writes are always made to the inner storage before the cache is
+/// updated, so the inner storage remains the source of truth. Writes made in
+/// a transaction are only cached once it is committed. Reads that miss the
+/// cache are forwarded to the inner storage.
+///
+/// All clones of an instance of this type share the same cache.
+#[derive(Clone)]
+pub struct CachedGroupStateStorage<S> {
+    inner: S,
+    cache: InMemoryGroupStateStorage,
+    group_limits: Arc<Mutex<BTreeMap<Vec<u8>, usize>>>,
+    transaction: Option<Vec<CachedWrite>>,
+}
+
+type CachedWrite = (GroupState, Vec<EpochRecord>, Vec<EpochRecord>);
+
+impl<S: Debug> Debug for CachedGroupStateStorage<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedGroupStateStorage")
+            .field("inner", &self.inner)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<S> CachedGroupStateStorage<S> {
+    /// Create a cache over `inner`, keeping the default number of epochs in
+    /// memory for each group.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            cache: InMemoryGroupStateStorage::new(),
+            group_limits: Default::default(),
+            transaction: None,
+        }
+    }
+
+    /// Set the number of epochs kept in memory for each group that does not
+    /// have a specific limit set with
+    /// [`set_group_max_cached_epochs`](Self::set_group_max_cached_epochs).
+    pub fn with_max_cached_epochs(self, max_cached_epochs: usize) -> Result<Self, MlsError> {
+        Ok(Self {
+            cache: self.cache.with_max_epoch_retention(max_cached_epochs)?,
+            ..self
+        })
+    }
+
+    /// Set the number of epochs kept in memory for `group_id`.
+    pub fn set_group_max_cached_epochs(
+        &self,
+        group_id: Vec<u8>,
+        max_cached_epochs: usize,
+    ) -> Result<(), MlsError> {
+        (max_cached_epochs > 0)
+            .then_some(())
+            .ok_or(MlsError::NonZeroRetentionRequired)?;
+
+        if let Some(data) = self.cache.lock().get_mut(group_id.as_slice()) {
+            data.trim_epochs(max_cached_epochs);
+        }
+
+        self.lock_limits().insert(group_id, max_cached_epochs);
+
+        Ok(())
+    }
+
+    /// Drop all cached data corresponding to `group_id`. The inner storage is
+    /// not modified.
+    pub fn evict(&self, group_id: &[u8]) {
+        self.cache.delete_group(group_id);
+    }
+
+    /// Access the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn max_cached_epochs(&self, group_id: &[u8]) -> usize {
+        self.lock_limits()
+            .get(group_id)
+            .copied()
+            .unwrap_or(self.cache.max_epoch_retention)
+    }
+
+    #[cfg(feature = "std")]
+    fn lock_limits(&self) -> std::sync::MutexGuard<'_, BTreeMap<Vec<u8>, usize>> {
+        self.group_limits.lock().unwrap()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn lock_limits(&self) -> spin::mutex::MutexGuard<'_, BTreeMap<Vec<u8>, usize>> {
+        self.group_limits.lock()
+    }
+
+    fn update_cache(
+        &self,
+        state: GroupState,
+        epoch_inserts: Vec<EpochRecord>,
+        epoch_updates: Vec<EpochRecord>,
+    ) {
+        let max_cached_epochs = self.max_cached_epochs(&state.id);
+        let mut cache = self.cache.lock();
+
+        let group_data = cache
+            .entry(state.id)
+            .or_insert_with(|| InMemoryGroupData::new(Vec::new()));
+
+        group_data.state_data = state.data;
+
+        epoch_inserts
+            .into_iter()
+            .for_each(|e| group_data.insert_epoch(e));
+
+        epoch_updates
+            .into_iter()
+            .for_each(|e| group_data.update_epoch(e));
+
+        group_data.trim_epochs(max_cached_epochs);
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<S> GroupStateStorage for CachedGroupStateStorage<S>
+where
+    S: GroupStateStorage,
+{
+    type Error = S::Error;
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let cached = self
+            .cache
+            .lock()
+            .get(group_id)
+            .map(|d| d.state_data.clone());
+
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let state = self.inner.state(group_id).await?;
+
+        if let Some(data) = &state {
+            self.cache
+                .lock()
+                .entry(group_id.to_vec())
+                .or_insert_with(|| InMemoryGroupData::new(data.clone()));
+        }
+
+        Ok(state)
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        let cached = self
+            .cache
+            .lock()
+            .get(group_id)
+            .and_then(|d| d.get_epoch(epoch_id).map(|e| e.data.clone()));
+
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        self.inner.epoch(group_id, epoch_id).await
+    }
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        epoch_inserts: Vec<EpochRecord>,
+        epoch_updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        let res = self
+            .inner
+            .write(state.clone(), epoch_inserts.clone(), epoch_updates.clone())
+            .await;
+
+        match (&res, &mut self.transaction) {
+            (Ok(()), None) => self.update_cache(state, epoch_inserts, epoch_updates),
+            // Until the transaction is committed, reads of the group are
+            // forwarded to the inner storage, which returns the data written
+            // by the transaction.
+            (Ok(()), Some(writes)) => {
+                self.cache.delete_group(&state.id);
+                writes.push((state, epoch_inserts, epoch_updates));
+            }
+            // The inner storage may or may not have been modified.
+            (Err(_), _) => self.evict(&state.id),
+        }
+
+        res
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        // Epochs are only ever appended to the cache by writes, which also
+        // append them to the inner storage, so the last cached epoch is the
+        // last stored epoch.
+        let cached = self
+            .cache
+            .lock()
+            .get(group_id)
+            .and_then(|d| d.epoch_data.back().map(|e| e.id));
+
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        self.inner.max_epoch_id(group_id).await
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        if let Some(data) = self.cache.lock().get_mut(group_id) {
+            data.delete_epochs_before(epoch_id);
+        }
+
+        self.inner.delete_epochs_before(group_id, epoch_id).await
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        self.inner.group_ids().await
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        self.evict(group_id);
+        self.inner.delete_group(group_id).await
+    }
+
+    // Tree records are only read when a group is loaded, so they aren't
+    // cached.
+    async fn tree_record(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.inner.tree_record(group_id, epoch_id).await
+    }
+
+    async fn write_tree_records(
+        &mut self,
+        group_id: &[u8],
+        records: Vec<EpochRecord>,
+    ) -> Result<bool, Self::Error> {
+        self.inner.write_tree_records(group_id, records).await
+    }
+
+    async fn delete_tree_records_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .delete_tree_records_before(group_id, epoch_id)
+            .await
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner.begin_transaction().await?;
+        self.transaction = Some(Vec::new());
+
+        Ok(())
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        let res = self.inner.commit_transaction().await;
+        let writes = self.transaction.take().unwrap_or_default();
+
+        if res.is_ok() {
+            writes
+                .into_iter()
+                .for_each(|(state, inserts, updates)| self.update_cache(state, inserts, updates));
+        } else {
+            // The cache may hold data read or deleted during the transaction,
+            // which may or may not have been committed.
+            self.cache.lock().clear();
+        }
+
+        res
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        // The cache may hold data read or deleted during the transaction,
+        // which is discarded.
+        self.transaction = None;
+        self.cache.lock().clear();
+        self.inner.rollback_transaction().await
+    }
+}
+
+#[cfg(all(test, feature = "prior_epoch"))]
+mod tests {
+    use alloc::{format, vec, vec::Vec};
+
+    use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+
+    use crate::{
+        group::test_utils::TEST_GROUP, storage_provider::in_memory::InMemoryGroupStateStorage,
+    };
+
+    use super::CachedGroupStateStorage;
+
+    fn test_epoch(epoch_id: u64) -> EpochRecord {
+        EpochRecord::new(epoch_id, format!("epoch {epoch_id}").as_bytes().to_vec())
+    }
+
+    fn test_snapshot(epoch_id: u64) -> GroupState {
+        GroupState {
+            id: TEST_GROUP.into(),
+            data: format!("snapshot {epoch_id}").as_bytes().to_vec(),
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn writes_go_through_to_inner_storage() {
+        let inner = InMemoryGroupStateStorage::new();
+        let mut storage = CachedGroupStateStorage::new(inner.clone());
+
+        storage
+            .write(
+                test_snapshot(1),
+                vec![test_epoch(0), test_epoch(1)],
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        let state = inner.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state, Some(test_snapshot(1).data));
+
+        let epoch = inner.epoch(TEST_GROUP, 0).await.unwrap();
+        assert_eq!(epoch, Some(test_epoch(0).data));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn old_epochs_are_read_from_inner_storage() {
+        let inner = InMemoryGroupStateStorage::new()
+            .with_max_epoch_retention(10)
+            .unwrap();
+
+        let mut storage = CachedGroupStateStorage::new(inner);
+
+        storage
+            .set_group_max_cached_epochs(TEST_GROUP.to_vec(), 1)
+            .unwrap();
+
+        let epochs = (0..5).map(test_epoch).collect::<Vec<_>>();
+
+        storage
+            .write(test_snapshot(4), epochs, Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage
+                .cache
+                .lock()
+                .get(TEST_GROUP)
+                .unwrap()
+                .epoch_data
+                .len(),
+            1
+        );
+
+        let epoch = storage.epoch(TEST_GROUP, 1).await.unwrap();
+        assert_eq!(epoch, Some(test_epoch(1).data));
+
+        let max_epoch_id = storage.max_epoch_id(TEST_GROUP).await.unwrap();
+        assert_eq!(max_epoch_id, Some(4));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn writes_are_cached_once_the_transaction_is_committed() {
+        let mut storage = CachedGroupStateStorage::new(InMemoryGroupStateStorage::new());
+
+        storage
+            .write(test_snapshot(0), vec![test_epoch(0)], Vec::new())
+            .await
+            .unwrap();
+
+        storage.begin_transaction().await.unwrap();
+
+        storage
+            .write(test_snapshot(1), vec![test_epoch(1)], Vec::new())
+            .await
+            .unwrap();
+
+        assert!(storage.cache.lock().get(TEST_GROUP).is_none());
+
+        let state = storage.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state, Some(test_snapshot(1).data));
+
+        storage.commit_transaction().await.unwrap();
+
+        assert_eq!(
+            storage.cache.lock().get(TEST_GROUP).unwrap().state_data,
+            test_snapshot(1).data
+        );
+
+        let max_epoch_id = storage.max_epoch_id(TEST_GROUP).await.unwrap();
+        assert_eq!(max_epoch_id, Some(1));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn state_is_loaded_from_inner_storage_after_eviction() {
+        let mut storage = CachedGroupStateStorage::new(InMemoryGroupStateStorage::new());
+
+        storage
+            .write(test_snapshot(0), vec![test_epoch(0)], Vec::new())
+            .await
+            .unwrap();
+
+        storage.evict(TEST_GROUP);
+        assert!(storage.cache.lock().get(TEST_GROUP).is_none());
+
+        let state = storage.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state, Some(test_snapshot(0).data));
+
+        assert!(storage.cache.lock().get(TEST_GROUP).is_some());
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::CipherSuiteProvider,
+    error::IntoAnyError,
+    group::{EpochRecord, GroupState, GroupStateStorage, MasterKeyProvider},
+};
+use zeroize::Zeroizing;
+
+use crate::client::MlsError;
+
+#[derive(Clone, MlsSize, MlsEncode, MlsDecode)]
+struct Envelope {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    key_id: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    wrapped_key: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    nonce: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    ciphertext: Vec<u8>,
+}
+
+// Authenticated with each record, so that records can't be swapped between
+// groups, or between the state and the epochs of a group.
+#[derive(MlsSize, MlsEncode)]
+struct RecordContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch_id: Option<u64>,
+}
+
+/// Group state storage encrypting the states and epochs it stores in
+/// another storage, with envelope encryption.
+///
+/// Each record is encrypted with a fresh data key, using the AEAD of the
+/// cipher suite of `P`, and the data key is stored wrapped by the current
+/// master key of a [`MasterKeyProvider`]. When the master key is rotated,
+/// new records are wrapped with the new master key while existing records
+/// remain readable, as long as the provider can still unwrap with the
+/// previous master key. Existing records are moved to the new master key
+/// progressively with [`rewrap_group`](Self::rewrap_group) or a
+/// [`RewrapTask`], which only re-wraps their data keys rather than
+/// re-encrypting them.
+///
+/// Ratchet tree records are not supported, so the states written to this
+/// storage always hold the full ratchet tree, even when tree checkpointing
+/// is enabled.
+#[derive(Clone)]
+pub struct EncryptedGroupStateStorage<S, K, P> {
+    inner: S,
+    master_keys: K,
+    cipher_suite_provider: P,
+}
+
+impl<S: Debug, K: Debug, P> Debug for EncryptedGroupStateStorage<S, K, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedGroupStateStorage")
+            .field("inner", &self.inner)
+            .field("master_keys", &self.master_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, K, P> EncryptedGroupStateStorage<S, K, P>
+where
+    S: GroupStateStorage,
+    K: MasterKeyProvider,
+    P: CipherSuiteProvider,
+{
+    /// Encrypt the records stored in `inner` with data keys wrapped by
+    /// `master_keys`.
+    pub fn new(inner: S, master_keys: K, cipher_suite_provider: P) -> Self {
+        Self {
+            inner,
+            master_keys,
+            cipher_suite_provider,
+        }
+    }
+
+    /// Access the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Access the master key provider.
+    pub fn master_keys(&self) -> &K {
+        &self.master_keys
+    }
+
+    /// Wrap the data keys of all stored records of `group_id` with the
+    /// current master key, returning the number of records that were
+    /// wrapped with another one.
+    ///
+    /// Records are read and written back through the inner storage, so this
+    /// must not run while the group is being used by a client.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn rewrap_group(&mut self, group_id: &[u8]) -> Result<usize, MlsError> {
+        let Some(state) = self.inner.state(group_id).await.map_err(storage_error)? else {
+            return Ok(0);
+        };
+
+        let mut rewrapped = 0;
+
+        let state = match self.rewrap(&state).await? {
+            Some(data) => {
+                rewrapped += 1;
+                data
+            }
+            None => state,
+        };
+
+        let mut epoch_updates = Vec::new();
+        let mut epoch_id = self
+            .inner
+            .max_epoch_id(group_id)
+            .await
+            .map_err(storage_error)?;
+
+        while let Some(id) = epoch_id {
+            let Some(data) = self
+                .inner
+                .epoch(group_id, id)
+                .await
+                .map_err(storage_error)?
+            else {
+                break;
+            };
+
+            if let Some(data) = self.rewrap(&data).await? {
+                epoch_updates.push(EpochRecord::new(id, data));
+            }
+
+            epoch_id = id.checked_sub(1);
+        }
+
+        if rewrapped == 0 && epoch_updates.is_empty() {
+            return Ok(0);
+        }
+
+        rewrapped += epoch_updates.len();
+
+        let state = GroupState {
+            id: group_id.to_vec(),
+            data: state,
+        };
+
+        self.inner
+            .write(state, vec![], epoch_updates)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(rewrapped)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn seal(
+        &self,
+        group_id: &[u8],
+        epoch_id: Option<u64>,
+        data: &[u8],
+    ) -> Result<Vec<u8>, MlsError> {
+        let cs = &self.cipher_suite_provider;
+
+        let data_key = Zeroizing::new(
+            cs.random_bytes_vec(cs.aead_key_size())
+                .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?,
+        );
+
+        let nonce = cs
+            .random_bytes_vec(cs.aead_nonce_size())
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let aad = RecordContext { group_id, epoch_id }.mls_encode_to_vec()?;
+
+        let ciphertext = cs
+            .aead_seal(&data_key, data, Some(&aad), &nonce)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let key_id = self.master_keys.current_key_id();
+
+        let wrapped_key = self
+            .master_keys
+            .wrap(&key_id, &data_key)
+            .await
+            .map_err(|e| MlsError::MasterKeyProviderError(e.into_any_error()))?;
+
+        let envelope = Envelope {
+            key_id,
+            wrapped_key,
+            nonce,
+            ciphertext,
+        };
+
+        envelope.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn open(
+        &self,
+        group_id: &[u8],
+        epoch_id: Option<u64>,
+        data: &[u8],
+    ) -> Result<Vec<u8>, MlsError> {
+        let envelope = Envelope::mls_decode(&mut &*data)?;
+
+        let data_key = self
+            .master_keys
+            .unwrap(&envelope.key_id, &envelope.wrapped_key)
+            .await
+            .map_err(|e| MlsError::MasterKeyProviderError(e.into_any_error()))?;
+
+        let aad = RecordContext { group_id, epoch_id }.mls_encode_to_vec()?;
+
+        let data = self
+            .cipher_suite_provider
+            .aead_open(&data_key, &envelope.ciphertext, Some(&aad), &envelope.nonce)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        Ok(data.to_vec())
+    }
+
+    // Returns the record with its data key wrapped with the current master
+    // key, or `None` if it already was.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn rewrap(&self, data: &[u8]) -> Result<Option<Vec<u8>>, MlsError> {
+        let mut envelope = Envelope::mls_decode(&mut &*data)?;
+        let key_id = self.master_keys.current_key_id();
+
+        if envelope.key_id == key_id {
+            return Ok(None);
+        }
+
+        let data_key = self
+            .master_keys
+            .unwrap(&envelope.key_id, &envelope.wrapped_key)
+            .await
+            .map_err(|e| MlsError::MasterKeyProviderError(e.into_any_error()))?;
+
+        envelope.wrapped_key = self
+            .master_keys
+            .wrap(&key_id, &data_key)
+            .await
+            .map_err(|e| MlsError::MasterKeyProviderError(e.into_any_error()))?;
+
+        envelope.key_id = key_id;
+
+        envelope.mls_encode_to_vec().map(Some).map_err(Into::into)
+    }
+}
+
+fn storage_error<E: IntoAnyError>(e: E) -> MlsError {
+    MlsError::GroupStorageError(e.into_any_error())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<S, K, P> GroupStateStorage for EncryptedGroupStateStorage<S, K, P>
+where
+    S: GroupStateStorage,
+    K: MasterKeyProvider,
+    P: CipherSuiteProvider,
+{
+    type Error = MlsError;
+
+    async fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.inner.state(group_id).await.map_err(storage_error)? {
+            Some(data) => self.open(group_id, None, &data).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        let data = self
+            .inner
+            .epoch(group_id, epoch_id)
+            .await
+            .map_err(storage_error)?;
+
+        match data {
+            Some(data) => self.open(group_id, Some(epoch_id), &data).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn write(
+        &mut self,
+        state: GroupState,
+        epoch_inserts: Vec<EpochRecord>,
+        epoch_updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        let data = self.seal(&state.id, None, &state.data).await?;
+
+        let mut inserts = Vec::with_capacity(epoch_inserts.len());
+
+        for epoch in epoch_inserts {
+            let data = self.seal(&state.id, Some(epoch.id), &epoch.data).await?;
+            inserts.push(EpochRecord::new(epoch.id, data));
+        }
+
+        let mut updates = Vec::with_capacity(epoch_updates.len());
+
+        for epoch in epoch_updates {
+            let data = self.seal(&state.id, Some(epoch.id), &epoch.data).await?;
+            updates.push(EpochRecord::new(epoch.id, data));
+        }
+
+        let state = GroupState { id: state.id, data };
+
+        self.inner
+            .write(state, inserts, updates)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        self.inner
+            .max_epoch_id(group_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        self.inner
+            .delete_epochs_before(group_id, epoch_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        self.inner.group_ids().await.map_err(storage_error)
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        self.inner
+            .delete_group(group_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner.begin_transaction().await.map_err(storage_error)
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner.commit_transaction().await.map_err(storage_error)
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .rollback_transaction()
+            .await
+            .map_err(storage_error)
+    }
+}
+
+/// Background task moving the records of a list of groups to the current
+/// master key of an [`EncryptedGroupStateStorage`], one group at a time.
+///
+/// The task holds no reference to the storage, so an application can run a
+/// [`step`](Self::step) whenever it is idle, for instance between the
+/// messages it processes, and skip the groups it is using.
+#[derive(Clone, Debug, Default)]
+pub struct RewrapTask {
+    pending: VecDeque<Vec<u8>>,
+    rewrapped: usize,
+}
+
+impl RewrapTask {
+    /// Create a task for the groups `group_ids`.
+    pub fn new<I: IntoIterator<Item = Vec<u8>>>(group_ids: I) -> Self {
+        Self {
+            pending: group_ids.into_iter().collect(),
+            rewrapped: 0,
+        }
+    }
+
+    /// Number of groups left to process.
+    pub fn remaining(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Number of records re-wrapped so far.
+    pub fn rewrapped(&self) -> usize {
+        self.rewrapped
+    }
+
+    /// Whether all groups were processed.
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Re-wrap the records of the next group with
+    /// [`EncryptedGroupStateStorage::rewrap_group`], returning the id of the
+    /// group, or `None` if the task is done. A group that fails stays at the
+    /// front of the queue, so the step can be retried.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn step<S, K, P>(
+        &mut self,
+        storage: &mut EncryptedGroupStateStorage<S, K, P>,
+    ) -> Result<Option<Vec<u8>>, MlsError>
+    where
+        S: GroupStateStorage,
+        K: MasterKeyProvider,
+        P: CipherSuiteProvider,
+    {
+        let Some(group_id) = self.pending.front() else {
+            return Ok(None);
+        };
+
+        self.rewrapped += storage.rewrap_group(group_id).await?;
+
+        Ok(self.pending.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(mls_build_async)]
+    use alloc::boxed::Box;
+    use alloc::{vec, vec::Vec};
+    use assert_matches::assert_matches;
+    use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage, MasterKeyProvider};
+    use zeroize::Zeroizing;
+
+    use crate::{
+        client::{test_utils::TEST_CIPHER_SUITE, MlsError},
+        crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
+        group::test_utils::TEST_GROUP,
+        storage_provider::in_memory::InMemoryGroupStateStorage,
+        CryptoProvider,
+    };
+
+    use super::{EncryptedGroupStateStorage, RewrapTask};
+
+    #[derive(Debug)]
+    struct RetiredKey;
+
+    impl mls_rs_core::error::IntoAnyError for RetiredKey {}
+
+    // Master key `n` wraps by XOR-ing with `n`. Keys below `oldest` are
+    // retired and can't unwrap anymore.
+    #[derive(Clone, Debug)]
+    struct TestMasterKeys {
+        current: u8,
+        oldest: u8,
+    }
+
+    impl TestMasterKeys {
+        fn xor(key_id: &[u8], data: &[u8]) -> Vec<u8> {
+            data.iter().map(|b| b ^ key_id[0]).collect()
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl MasterKeyProvider for TestMasterKeys {
+        type Error = RetiredKey;
+
+        fn current_key_id(&self) -> Vec<u8> {
+            vec![self.current]
+        }
+
+        async fn wrap(&self, key_id: &[u8], data_key: &[u8]) -> Result<Vec<u8>, Self::Error> {
+            Ok(Self::xor(key_id, data_key))
+        }
+
+        async fn unwrap(
+            &self,
+            key_id: &[u8],
+            wrapped_key: &[u8],
+        ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+            (key_id[0] >= self.oldest)
+                .then(|| Zeroizing::new(Self::xor(key_id, wrapped_key)))
+                .ok_or(RetiredKey)
+        }
+    }
+
+    type TestStorage = EncryptedGroupStateStorage<
+        InMemoryGroupStateStorage,
+        TestMasterKeys,
+        <TestCryptoProvider as CryptoProvider>::CipherSuiteProvider,
+    >;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_storage() -> TestStorage {
+        let mut storage = EncryptedGroupStateStorage::new(
+            InMemoryGroupStateStorage::new(),
+            TestMasterKeys {
+                current: 1,
+                oldest: 1,
+            },
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        );
+
+        let state = GroupState {
+            id: TEST_GROUP.to_vec(),
+            data: b"state".to_vec(),
+        };
+
+        let epochs = vec![
+            EpochRecord::new(0, b"epoch 0".to_vec()),
+            EpochRecord::new(1, b"epoch 1".to_vec()),
+        ];
+
+        storage.write(state, epochs, vec![]).await.unwrap();
+
+        storage
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn records_are_encrypted() {
+        let storage = test_storage().await;
+
+        let stored = storage.inner().state(TEST_GROUP).await.unwrap().unwrap();
+        assert!(!stored.windows(5).any(|w| w == b"state"));
+
+        let state = storage.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state.as_deref(), Some(b"state".as_slice()));
+
+        let epoch = storage.epoch(TEST_GROUP, 1).await.unwrap();
+        assert_eq!(epoch.as_deref(), Some(b"epoch 1".as_slice()));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn records_are_bound_to_their_location() {
+        let storage = test_storage().await;
+
+        let epoch = storage.inner().epoch(TEST_GROUP, 0).await.unwrap().unwrap();
+        let res = storage.open(TEST_GROUP, None, &epoch).await;
+
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn master_key_can_be_rotated_progressively() {
+        let mut storage = test_storage().await;
+
+        storage.master_keys.current = 2;
+
+        let state = storage.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state.as_deref(), Some(b"state".as_slice()));
+
+        let mut task = RewrapTask::new([TEST_GROUP.to_vec()]);
+
+        let group_id = task.step(&mut storage).await.unwrap();
+        assert_eq!(group_id.as_deref(), Some(TEST_GROUP));
+        assert_eq!(task.rewrapped(), 3);
+        assert!(task.is_done());
+
+        let group_id = task.step(&mut storage).await.unwrap();
+        assert_eq!(group_id, None);
+
+        storage.master_keys.oldest = 2;
+
+        let epoch = storage.epoch(TEST_GROUP, 0).await.unwrap();
+        assert_eq!(epoch.as_deref(), Some(b"epoch 0".as_slice()));
+
+        let rewrapped = storage.rewrap_group(TEST_GROUP).await.unwrap();
+        assert_eq!(rewrapped, 0);
+    }
+}
@@ -0,0 +1,554 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::MlsDecode;
+use mls_rs_core::{
+    crypto::HpkePublicKey, error::IntoAnyError, group::GroupStateStorage,
+    key_package::KeyPackageStorage,
+};
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    group::{snapshot::Snapshot, Group},
+    Client, KeyPackage,
+};
+
+/// Options of [`Client::gc_storage`].
+///
+/// By default, expired and consumed key packages are deleted, as well as the
+/// epochs beyond the retention set by the
+/// [`GroupPolicyExt`](crate::extension::group_policy::GroupPolicyExt) of each
+/// group. Orphaned groups are only deleted if explicitly requested.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GcOptions {
+    pub dry_run: bool,
+    pub max_epoch_retention: Option<u32>,
+    pub remove_orphaned_groups: bool,
+}
+
+impl GcOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only report what would be deleted, without modifying storage.
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
+    /// Keep `max_epoch_retention` prior epochs of every group, instead of
+    /// the retention set by the group policy of each group. Groups without
+    /// such a policy keep all their epochs unless this is set.
+    pub fn with_max_epoch_retention(self, max_epoch_retention: u32) -> Self {
+        Self {
+            max_epoch_retention: Some(max_epoch_retention),
+            ..self
+        }
+    }
+
+    /// Delete groups whose state can no longer be loaded.
+    pub fn with_remove_orphaned_groups(self, remove_orphaned_groups: bool) -> Self {
+        Self {
+            remove_orphaned_groups,
+            ..self
+        }
+    }
+}
+
+/// Records deleted by [`Client::gc_storage`], or that would have been in a
+/// dry run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GcReport {
+    /// Whether this is the report of a dry run.
+    pub dry_run: bool,
+    /// Ids of the deleted key packages.
+    pub key_packages: Vec<Vec<u8>>,
+    /// Ids of the deleted prior epochs, in increasing order, by group id.
+    pub epochs: Vec<(Vec<u8>, Vec<u64>)>,
+    /// Ids of the orphaned groups. They are only deleted if
+    /// [`GcOptions::remove_orphaned_groups`] is set.
+    pub orphaned_groups: Vec<Vec<u8>>,
+    /// Ids of the orphaned groups that were deleted. Groups are not deleted
+    /// in a dry run, or if the group state storage doesn't support
+    /// [`GroupStateStorage::delete_group`].
+    pub deleted_groups: Vec<Vec<u8>>,
+    /// Ids of the groups whose stored state failed to load, such as states
+    /// that can't be decoded or use a cipher suite that is not supported
+    /// anymore. They are skipped, and never deleted.
+    pub undecodable_groups: Vec<Vec<u8>>,
+}
+
+impl GcReport {
+    /// Whether nothing was, or would have been, deleted.
+    pub fn is_empty(&self) -> bool {
+        self.key_packages.is_empty() && self.epochs.is_empty() && self.orphaned_groups.is_empty()
+    }
+}
+
+impl<C> Client<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Delete the records of this client's storage that are no longer
+    /// needed, returning a report of what was deleted.
+    ///
+    /// The following records are collected:
+    ///
+    /// - Key packages that expired, according to the time provider of the
+    ///   client, or that were consumed by a group which was written to
+    ///   storage without its key package being deleted, e.g. because the
+    ///   application crashed in between. A key package is considered
+    ///   consumed when its leaf node is still the one of the client in a
+    ///   stored group.
+    /// - Prior epochs beyond the retention set by `options` or by the group
+    ///   policy of each group.
+    /// - Orphaned groups, from which the client was removed, or whose stored
+    ///   state doesn't contain the client in its tree anymore.
+    ///
+    /// Records can only be found if the storages in use enumerate them with
+    /// [`KeyPackageStorage::key_package_ids`] and
+    /// [`GroupStateStorage::group_ids`]. Groups that fail to load are
+    /// reported in [`GcReport::undecodable_groups`] rather than being
+    /// considered orphaned, and the collection continues with the other
+    /// groups.
+    ///
+    /// Groups must not be in use while their storage is collected, as
+    /// writing them to storage afterwards would restore deleted records.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn gc_storage(&self, options: GcOptions) -> Result<GcReport, MlsError> {
+        if options.max_epoch_retention == Some(0) {
+            return Err(MlsError::NonZeroRetentionRequired);
+        }
+
+        let mut group_storage = self.config.group_state_storage();
+
+        let mut report = GcReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+
+        let mut leaf_keys = Vec::new();
+
+        let group_ids = group_storage
+            .group_ids()
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        for group_id in group_ids {
+            let state = group_storage
+                .state(&group_id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            // The group may have been deleted since it was listed.
+            let Some(state) = state else {
+                continue;
+            };
+
+            let Ok(group) = self.group_for_gc(&state).await else {
+                report.undecodable_groups.push(group_id);
+                continue;
+            };
+
+            let leaf_node = match group.current_user_leaf_node() {
+                Ok(leaf_node) if !group.removed => leaf_node,
+                _ => {
+                    report.orphaned_groups.push(group_id);
+                    continue;
+                }
+            };
+
+            leaf_keys.push(leaf_node.public_key.clone());
+
+            #[cfg(feature = "prior_epoch")]
+            {
+                let retention = match options.max_epoch_retention {
+                    Some(retention) => Some(retention),
+                    None => group.group_policy()?.and_then(|p| p.max_epoch_retention()),
+                };
+
+                if let Some(retention) = retention {
+                    let oldest = group.current_epoch().saturating_sub(retention.into());
+
+                    let epoch_ids =
+                        collect_epochs(&mut group_storage, &group_id, oldest, options.dry_run)
+                            .await?;
+
+                    if !epoch_ids.is_empty() {
+                        report.epochs.push((group_id, epoch_ids));
+                    }
+                }
+            }
+        }
+
+        if options.remove_orphaned_groups && !options.dry_run {
+            for group_id in &report.orphaned_groups {
+                let deleted = group_storage
+                    .delete_group(group_id)
+                    .await
+                    .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+                if deleted {
+                    report.deleted_groups.push(group_id.clone());
+                }
+            }
+        }
+
+        report.key_packages = self
+            .collect_key_packages(&leaf_keys, options.dry_run)
+            .await?;
+
+        Ok(report)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn group_for_gc(&self, state: &[u8]) -> Result<Group<C>, MlsError> {
+        let snapshot = Snapshot::from_bytes(state)?;
+
+        Group::from_snapshot(self.config.clone(), snapshot).await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn collect_key_packages(
+        &self,
+        leaf_keys: &[HpkePublicKey],
+        dry_run: bool,
+    ) -> Result<Vec<Vec<u8>>, MlsError> {
+        let mut repo = self.config.key_package_repo();
+        let now = self.config.current_time().map(|t| t.seconds_since_epoch());
+
+        let ids = repo
+            .key_package_ids()
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+        let mut collected = Vec::new();
+
+        for id in ids {
+            let data = repo
+                .get(&id)
+                .await
+                .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+            let Some(data) = data else {
+                continue;
+            };
+
+            let expired = matches!(now, Some(now) if data.expiration < now);
+
+            let consumed = KeyPackage::mls_decode(&mut &*data.key_package_bytes)
+                .map_or(false, |key_package| {
+                    leaf_keys.contains(&key_package.leaf_node.public_key)
+                });
+
+            if !expired && !consumed {
+                continue;
+            }
+
+            if !dry_run {
+                repo.delete(&id)
+                    .await
+                    .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+            }
+
+            collected.push(id);
+        }
+
+        Ok(collected)
+    }
+}
+
+#[cfg(feature = "prior_epoch")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn collect_epochs<S: GroupStateStorage>(
+    storage: &mut S,
+    group_id: &[u8],
+    oldest: u64,
+    dry_run: bool,
+) -> Result<Vec<u64>, MlsError> {
+    if oldest == 0 {
+        return Ok(Vec::new());
+    }
+
+    if !dry_run {
+        return storage
+            .delete_epochs_before(group_id, oldest)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .ok_or(MlsError::EpochDeletionUnsupported);
+    }
+
+    let mut epoch_ids = Vec::new();
+
+    for epoch_id in (0..oldest).rev() {
+        let epoch = storage
+            .epoch(group_id, epoch_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        if epoch.is_none() {
+            break;
+        }
+
+        epoch_ids.push(epoch_id);
+    }
+
+    epoch_ids.reverse();
+
+    Ok(epoch_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use mls_rs_core::group::{GroupState, GroupStateStorage};
+
+    use crate::{
+        client::test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::TEST_GROUP,
+    };
+
+    use super::GcOptions;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expired_key_packages_are_collected() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let store = client.key_package_store();
+        let (id, mut data) = store.key_packages().pop().unwrap();
+
+        let report = client.gc_storage(GcOptions::new()).await.unwrap();
+        assert!(report.is_empty());
+
+        data.expiration = 0;
+        store.insert(id.clone(), data);
+
+        let report = client
+            .gc_storage(GcOptions::new().with_dry_run(true))
+            .await
+            .unwrap();
+
+        assert!(report.dry_run);
+        assert_eq!(report.key_packages, vec![id.clone()]);
+        assert!(store.get(&id).is_some());
+
+        let report = client.gc_storage(GcOptions::new()).await.unwrap();
+
+        assert_eq!(report.key_packages, vec![id.clone()]);
+        assert!(store.get(&id).is_none());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn consumed_key_packages_are_collected() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let (bob, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        group.apply_pending_commit().await.unwrap();
+
+        let store = bob.key_package_store();
+        let (id, data) = store.key_packages().pop().unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        bob_group.write_to_storage().await.unwrap();
+
+        // Simulate a crash between writing the group and deleting the key
+        // package.
+        store.insert(id.clone(), data);
+
+        let report = bob.gc_storage(GcOptions::new()).await.unwrap();
+
+        assert_eq!(report.key_packages, vec![id.clone()]);
+        assert!(report.orphaned_groups.is_empty());
+        assert!(store.get(&id).is_none());
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn epochs_beyond_retention_are_collected() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+
+        for _ in 0..3 {
+            group.commit(vec![]).await.unwrap();
+            group.apply_pending_commit().await.unwrap();
+        }
+
+        group.write_to_storage().await.unwrap();
+
+        let group_id = group.group_id().to_vec();
+        let storage = client.group_state_storage();
+
+        let res = client
+            .gc_storage(GcOptions::new().with_max_epoch_retention(0))
+            .await;
+
+        assert_matches::assert_matches!(
+            res,
+            Err(crate::client::MlsError::NonZeroRetentionRequired)
+        );
+
+        // Without retention policy, all epochs are kept.
+        let report = client.gc_storage(GcOptions::new()).await.unwrap();
+        assert!(report.epochs.is_empty());
+
+        let options = GcOptions::new().with_max_epoch_retention(1);
+
+        let report = client
+            .gc_storage(options.clone().with_dry_run(true))
+            .await
+            .unwrap();
+
+        assert_eq!(report.epochs, vec![(group_id.clone(), vec![0, 1])]);
+        let stored = storage.epoch(&group_id, 0).await.unwrap();
+        assert!(stored.is_some());
+
+        let report = client.gc_storage(options).await.unwrap();
+
+        assert_eq!(report.epochs, vec![(group_id.clone(), vec![0, 1])]);
+        let stored = storage.epoch(&group_id, 1).await.unwrap();
+        assert!(stored.is_none());
+
+        let stored = storage.epoch(&group_id, 2).await.unwrap();
+        assert!(stored.is_some());
+
+        client.load_group(&group_id).await.unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn orphaned_groups_are_only_deleted_on_request() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let (bob, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (mut group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        // A state of bob's that is still in use.
+        let mut other_group = bob.create_group(Default::default()).await.unwrap();
+        other_group.write_to_storage().await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        group
+            .process_incoming_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        group.write_to_storage().await.unwrap();
+
+        let group_id = group.group_id().to_vec();
+        let storage = bob.group_state_storage();
+
+        let report = bob.gc_storage(GcOptions::new()).await.unwrap();
+
+        assert_eq!(report.orphaned_groups, vec![group_id.clone()]);
+        assert!(report.deleted_groups.is_empty());
+        let stored = storage.state(&group_id).await.unwrap();
+        assert!(stored.is_some());
+
+        let options = GcOptions::new().with_remove_orphaned_groups(true);
+
+        let report = bob
+            .gc_storage(options.clone().with_dry_run(true))
+            .await
+            .unwrap();
+
+        assert_eq!(report.orphaned_groups, vec![group_id.clone()]);
+        assert!(report.deleted_groups.is_empty());
+        let stored = storage.state(&group_id).await.unwrap();
+        assert!(stored.is_some());
+
+        let report = bob.gc_storage(options).await.unwrap();
+
+        assert_eq!(report.orphaned_groups, vec![group_id.clone()]);
+        assert_eq!(report.deleted_groups, vec![group_id.clone()]);
+        let stored = storage.state(&group_id).await.unwrap();
+        assert!(stored.is_none());
+
+        let stored = storage.state(other_group.group_id()).await.unwrap();
+        assert!(stored.is_some());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn undecodable_groups_are_reported() {
+        let (client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let mut group = client.create_group(Default::default()).await.unwrap();
+        group.write_to_storage().await.unwrap();
+
+        let store = client.key_package_store();
+        let (id, mut data) = store.key_packages().pop().unwrap();
+        data.expiration = 0;
+        store.insert(id.clone(), data);
+
+        let mut storage = client.group_state_storage();
+
+        let corrupted = GroupState {
+            id: TEST_GROUP.to_vec(),
+            data: vec![0xff; 4],
+        };
+
+        storage.write(corrupted, vec![], vec![]).await.unwrap();
+
+        let options = GcOptions::new().with_remove_orphaned_groups(true);
+        let report = client.gc_storage(options).await.unwrap();
+
+        assert_eq!(report.undecodable_groups, vec![TEST_GROUP.to_vec()]);
+        assert!(report.orphaned_groups.is_empty());
+        assert!(report.deleted_groups.is_empty());
+
+        // The collection continued past the undecodable group.
+        assert_eq!(report.key_packages, vec![id]);
+
+        let stored = storage.state(TEST_GROUP).await.unwrap();
+        assert!(stored.is_some());
+    }
+}
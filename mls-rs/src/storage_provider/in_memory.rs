@@ -5,7 +5,9 @@
 mod group_state_storage;
 mod key_package_storage;
 mod psk_storage;
+mod replay_store;
 
 pub use group_state_storage::*;
 pub use key_package_storage::*;
 pub use psk_storage::*;
+pub use replay_store::*;
@@ -2,7 +2,7 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 
 #[cfg(target_has_atomic = "ptr")]
 use alloc::sync::Arc;
@@ -19,12 +19,13 @@ use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
 use portable_atomic_util::Arc;
 
 use crate::client::MlsError;
+use zeroize::Zeroize;
 
 #[cfg(feature = "std")]
 use std::collections::{hash_map::Entry, HashMap};
 
 #[cfg(not(feature = "std"))]
-use alloc::collections::{btree_map::Entry, BTreeMap};
+use alloc::collections::btree_map::Entry;
 
 #[cfg(feature = "std")]
 use std::sync::Mutex;
@@ -38,6 +39,7 @@ pub(crate) const DEFAULT_EPOCH_RETENTION_LIMIT: usize = 3;
 pub(crate) struct InMemoryGroupData {
     pub(crate) state_data: Vec<u8>,
     pub(crate) epoch_data: VecDeque<EpochRecord>,
+    pub(crate) tree_data: BTreeMap<u64, Vec<u8>>,
 }
 
 impl Debug for InMemoryGroupData {
@@ -48,6 +50,7 @@ impl Debug for InMemoryGroupData {
                 &mls_rs_core::debug::pretty_bytes(&self.state_data),
             )
             .field("epoch_data", &self.epoch_data)
+            .field("tree_data", &self.tree_data.keys())
             .finish()
     }
 }
@@ -57,6 +60,7 @@ impl InMemoryGroupData {
         InMemoryGroupData {
             state_data,
             epoch_data: Default::default(),
+            tree_data: Default::default(),
         }
     }
 
@@ -94,18 +98,37 @@ impl InMemoryGroupData {
             self.epoch_data.pop_front();
         }
     }
+
+    // Epochs are stored in increasing order, so deleted epochs are at the front.
+    pub fn delete_epochs_before(&mut self, epoch_id: u64) -> Vec<u64> {
+        let mut deleted = Vec::new();
+
+        while self.epoch_data.front().map_or(false, |e| e.id < epoch_id) {
+            if let Some(mut epoch) = self.epoch_data.pop_front() {
+                epoch.data.zeroize();
+                deleted.push(epoch.id);
+            }
+        }
+
+        deleted
+    }
 }
 
 #[derive(Clone)]
 /// In memory group state storage backed by a HashMap.
 ///
 /// All clones of an instance of this type share the same underlying HashMap.
+/// Transactions are specific to an instance: a rollback only restores the
+/// groups modified through it.
 pub struct InMemoryGroupStateStorage {
     #[cfg(feature = "std")]
     pub(crate) inner: Arc<Mutex<HashMap<Vec<u8>, InMemoryGroupData>>>,
     #[cfg(not(feature = "std"))]
     pub(crate) inner: Arc<Mutex<BTreeMap<Vec<u8>, InMemoryGroupData>>>,
     pub(crate) max_epoch_retention: usize,
+    /// Data of the groups modified during the current transaction, as it was
+    /// when the transaction started.
+    transaction: Option<BTreeMap<Vec<u8>, Option<InMemoryGroupData>>>,
 }
 
 impl Debug for InMemoryGroupStateStorage {
@@ -134,6 +157,7 @@ impl InMemoryGroupStateStorage {
         Self {
             inner: Default::default(),
             max_epoch_retention: DEFAULT_EPOCH_RETENTION_LIMIT,
+            transaction: None,
         }
     }
 
@@ -143,8 +167,8 @@ impl InMemoryGroupStateStorage {
             .ok_or(MlsError::NonZeroRetentionRequired)?;
 
         Ok(Self {
-            inner: self.inner,
             max_epoch_retention,
+            ..self
         })
     }
 
@@ -158,13 +182,30 @@ impl InMemoryGroupStateStorage {
         self.lock().remove(group_id);
     }
 
+    /// Save the data of `group_id` before its first modification in the
+    /// current transaction.
+    fn save_for_rollback(&mut self, group_id: &[u8]) {
+        let saved = self
+            .transaction
+            .as_ref()
+            .map_or(true, |saved| saved.contains_key(group_id));
+
+        if !saved {
+            let data = self.lock().get(group_id).cloned();
+
+            if let Some(saved) = &mut self.transaction {
+                saved.insert(group_id.to_vec(), data);
+            }
+        }
+    }
+
     #[cfg(feature = "std")]
-    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Vec<u8>, InMemoryGroupData>> {
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Vec<u8>, InMemoryGroupData>> {
         self.inner.lock().unwrap()
     }
 
     #[cfg(not(feature = "std"))]
-    fn lock(&self) -> spin::mutex::MutexGuard<'_, BTreeMap<Vec<u8>, InMemoryGroupData>> {
+    pub(crate) fn lock(&self) -> spin::mutex::MutexGuard<'_, BTreeMap<Vec<u8>, InMemoryGroupData>> {
         self.inner.lock()
     }
 }
@@ -207,6 +248,7 @@ impl GroupStateStorage for InMemoryGroupStateStorage {
         epoch_inserts: Vec<EpochRecord>,
         epoch_updates: Vec<EpochRecord>,
     ) -> Result<(), Self::Error> {
+        self.save_for_rollback(&state.id);
         let mut group_map = self.lock();
 
         let group_data = match group_map.entry(state.id) {
@@ -230,6 +272,98 @@ impl GroupStateStorage for InMemoryGroupStateStorage {
 
         Ok(())
     }
+
+    async fn delete_epochs_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u64>>, Self::Error> {
+        self.save_for_rollback(group_id);
+
+        Ok(Some(
+            self.lock()
+                .get_mut(group_id)
+                .map(|data| data.delete_epochs_before(epoch_id))
+                .unwrap_or_default(),
+        ))
+    }
+
+    async fn group_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.stored_groups())
+    }
+
+    async fn delete_group(&mut self, group_id: &[u8]) -> Result<bool, Self::Error> {
+        self.save_for_rollback(group_id);
+        Ok(self.lock().remove(group_id).is_some())
+    }
+
+    async fn tree_record(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .lock()
+            .get(group_id)
+            .and_then(|data| data.tree_data.get(&epoch_id).cloned()))
+    }
+
+    async fn write_tree_records(
+        &mut self,
+        group_id: &[u8],
+        records: Vec<EpochRecord>,
+    ) -> Result<bool, Self::Error> {
+        self.save_for_rollback(group_id);
+        let mut group_map = self.lock();
+
+        let group_data = group_map
+            .entry(group_id.to_vec())
+            .or_insert_with(|| InMemoryGroupData::new(Vec::new()));
+
+        group_data
+            .tree_data
+            .extend(records.into_iter().map(|r| (r.id, r.data)));
+
+        Ok(true)
+    }
+
+    async fn delete_tree_records_before(
+        &mut self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<(), Self::Error> {
+        self.save_for_rollback(group_id);
+
+        if let Some(data) = self.lock().get_mut(group_id) {
+            data.tree_data = data.tree_data.split_off(&epoch_id);
+        }
+
+        Ok(())
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.transaction = Some(BTreeMap::new());
+        Ok(())
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), Self::Error> {
+        self.transaction = None;
+        Ok(())
+    }
+
+    async fn rollback_transaction(&mut self) -> Result<(), Self::Error> {
+        let saved = self.transaction.take();
+        let mut group_map = self.lock();
+
+        for (group_id, data) in saved.into_iter().flatten() {
+            match data {
+                Some(data) => group_map.insert(group_id, data),
+                None => group_map.remove(&group_id),
+            };
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "prior_epoch"))]
@@ -351,4 +485,90 @@ mod tests {
         let expected = epoch_inserts.pop().unwrap();
         assert_eq!(stored.epoch_data[0], expected);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn epochs_can_be_deleted() {
+        let mut storage = test_storage(3).unwrap();
+
+        let epoch_inserts = vec![test_epoch(0), test_epoch(1), test_epoch(2)];
+
+        storage
+            .write(test_snapshot(2), epoch_inserts, vec![])
+            .await
+            .unwrap();
+
+        let deleted = storage.delete_epochs_before(TEST_GROUP, 2).await.unwrap();
+        assert_eq!(deleted, Some(vec![0, 1]));
+
+        let epoch_1 = storage.epoch(TEST_GROUP, 1).await.unwrap();
+        assert_eq!(epoch_1, None);
+
+        let epoch_2 = storage.epoch(TEST_GROUP, 2).await.unwrap();
+        assert!(epoch_2.is_some());
+
+        let deleted = storage.delete_epochs_before(b"other group", 2).await;
+        assert_eq!(deleted.unwrap(), Some(Vec::new()));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn rolled_back_writes_are_discarded() {
+        let mut storage = test_storage(3).unwrap();
+
+        storage
+            .write(test_snapshot(0), vec![test_epoch(0)], vec![])
+            .await
+            .unwrap();
+
+        storage.begin_transaction().await.unwrap();
+
+        storage
+            .write(test_snapshot(1), vec![test_epoch(1)], vec![])
+            .await
+            .unwrap();
+
+        storage
+            .write(
+                GroupState {
+                    id: b"other group".to_vec(),
+                    data: vec![],
+                },
+                vec![],
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        // Writes are visible during the transaction
+        let max_epoch_id = storage.max_epoch_id(TEST_GROUP).await.unwrap();
+        assert_eq!(max_epoch_id, Some(1));
+
+        storage.rollback_transaction().await.unwrap();
+
+        let max_epoch_id = storage.max_epoch_id(TEST_GROUP).await.unwrap();
+        assert_eq!(max_epoch_id, Some(0));
+
+        let state = storage.state(TEST_GROUP).await.unwrap();
+        assert_eq!(state, Some(test_snapshot(0).data));
+
+        let other_state = storage.state(b"other group").await.unwrap();
+        assert_eq!(other_state, None);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn committed_writes_are_kept() {
+        let mut storage = test_storage(3).unwrap();
+
+        storage.begin_transaction().await.unwrap();
+
+        storage
+            .write(test_snapshot(0), vec![test_epoch(0)], vec![])
+            .await
+            .unwrap();
+
+        storage.commit_transaction().await.unwrap();
+        storage.rollback_transaction().await.unwrap();
+
+        let max_epoch_id = storage.max_epoch_id(TEST_GROUP).await.unwrap();
+        assert_eq!(max_epoch_id, Some(0));
+    }
 }
@@ -117,4 +117,8 @@ impl KeyPackageStorage for InMemoryKeyPackageStorage {
     async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error> {
         Ok(self.get(id))
     }
+
+    async fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.lock().keys().cloned().collect())
+    }
 }
@@ -0,0 +1,178 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::{BTreeSet, VecDeque};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use mls_rs_core::group::ReplayStore;
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+use crate::client::MlsError;
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+pub(crate) const DEFAULT_REPLAY_STORE_SIZE: usize = 10_000;
+
+type Entry = (Vec<u8>, u64, Vec<u8>);
+
+#[derive(Debug, Default)]
+struct InMemoryReplayData {
+    entries: BTreeSet<Entry>,
+    order: VecDeque<Entry>,
+}
+
+#[derive(Clone, Debug)]
+/// In memory replay store keeping a bounded number of entries.
+///
+/// Once full, the oldest entries are forgotten first. All clones of an
+/// instance of this type share the same underlying storage.
+pub struct InMemoryReplayStore {
+    inner: Arc<Mutex<InMemoryReplayData>>,
+    max_entries: usize,
+}
+
+impl InMemoryReplayStore {
+    /// Create an empty replay store.
+    pub fn new() -> Self {
+        Self {
+            inner: Default::default(),
+            max_entries: DEFAULT_REPLAY_STORE_SIZE,
+        }
+    }
+
+    /// Keep at most `max_entries` entries.
+    pub fn with_max_entries(self, max_entries: usize) -> Result<Self, MlsError> {
+        (max_entries > 0)
+            .then_some(())
+            .ok_or(MlsError::NonZeroRetentionRequired)?;
+
+        Ok(Self {
+            inner: self.inner,
+            max_entries,
+        })
+    }
+
+    /// Number of entries currently stored.
+    pub fn len(&self) -> usize {
+        self.lock().order.len()
+    }
+
+    /// Determines if no entry is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[cfg(feature = "std")]
+    fn lock(&self) -> std::sync::MutexGuard<'_, InMemoryReplayData> {
+        self.inner.lock().unwrap()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn lock(&self) -> spin::mutex::MutexGuard<'_, InMemoryReplayData> {
+        self.inner.lock()
+    }
+}
+
+impl Default for InMemoryReplayStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl ReplayStore for InMemoryReplayStore {
+    type Error = Infallible;
+
+    async fn contains(
+        &self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<bool, Self::Error> {
+        let entry = (group_id.to_vec(), epoch, message_id.to_vec());
+        Ok(self.lock().entries.contains(&entry))
+    }
+
+    async fn insert(
+        &mut self,
+        group_id: &[u8],
+        epoch: u64,
+        message_id: &[u8],
+    ) -> Result<(), Self::Error> {
+        let entry = (group_id.to_vec(), epoch, message_id.to_vec());
+        let mut data = self.lock();
+
+        if !data.entries.insert(entry.clone()) {
+            return Ok(());
+        }
+
+        data.order.push_back(entry);
+
+        while data.order.len() > self.max_entries {
+            if let Some(oldest) = data.order.pop_front() {
+                data.entries.remove(&oldest);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs_core::group::ReplayStore;
+
+    use super::InMemoryReplayStore;
+    use crate::client::MlsError;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn oldest_entries_are_evicted() {
+        let mut store = InMemoryReplayStore::new().with_max_entries(2).unwrap();
+
+        store.insert(b"group", 0, b"a").await.unwrap();
+        store.insert(b"group", 0, b"b").await.unwrap();
+        store.insert(b"group", 0, b"b").await.unwrap();
+
+        assert_eq!(store.len(), 2);
+
+        let a = store.contains(b"group", 0, b"a").await.unwrap();
+        let a_in_other_epoch = store.contains(b"group", 1, b"a").await.unwrap();
+        let a_in_other_group = store.contains(b"other group", 0, b"a").await.unwrap();
+
+        assert!(a);
+        assert!(!a_in_other_epoch);
+        assert!(!a_in_other_group);
+
+        store.insert(b"group", 1, b"c").await.unwrap();
+
+        assert_eq!(store.len(), 2);
+
+        let a = store.contains(b"group", 0, b"a").await.unwrap();
+        let b = store.contains(b"group", 0, b"b").await.unwrap();
+        let c = store.contains(b"group", 1, b"c").await.unwrap();
+
+        assert!(!a);
+        assert!(b);
+        assert!(c);
+    }
+
+    #[test]
+    fn zero_entries_is_rejected() {
+        let res = InMemoryReplayStore::new().with_max_entries(0);
+        assert_matches!(res, Err(MlsError::NonZeroRetentionRequired));
+    }
+}
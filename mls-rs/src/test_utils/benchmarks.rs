@@ -1,5 +1,5 @@
 use mls_rs_codec::MlsEncode;
-use mls_rs_core::protocol_version::ProtocolVersion;
+use mls_rs_core::{crypto::CryptoProvider, protocol_version::ProtocolVersion};
 
 use crate::{
     cipher_suite::CipherSuite,
@@ -7,6 +7,11 @@ use crate::{
     group::{framing::MlsMessage, Group},
     identity::basic::BasicIdentityProvider,
     test_utils::{generate_basic_client, get_test_groups},
+    tree_kem::{
+        node::{LeafIndex, Node, NodeVec, Parent},
+        parent_hash::ParentHash,
+        TreeKemPublic,
+    },
 };
 
 pub use mls_rs_crypto_openssl::OpensslCryptoProvider as MlsCryptoProvider;
@@ -138,3 +143,64 @@ pub async fn join_group(cs: CipherSuite, group_info: MlsMessage) -> GroupStates<
 
     GroupStates { sender, receiver }
 }
+
+/// Ratchet tree with `leaf_count` identical leaves and all parent nodes set,
+/// used to benchmark the tree hash.
+#[derive(Clone)]
+pub struct TreeHashBench {
+    tree: TreeKemPublic,
+    cipher_suite_provider: <MlsCryptoProvider as CryptoProvider>::CipherSuiteProvider,
+}
+
+impl TreeHashBench {
+    pub fn new(cs: CipherSuite, leaf_count: u32) -> Self {
+        let crypto = MlsCryptoProvider::new();
+
+        let client =
+            generate_basic_client(cs, ProtocolVersion::MLS_10, 0, None, false, &crypto, None);
+
+        let group = client.create_group(Default::default()).unwrap();
+
+        let leaf = group
+            .current_epoch_tree()
+            .nodes
+            .borrow_as_leaf(LeafIndex(0))
+            .unwrap()
+            .clone();
+
+        let parent = Parent {
+            public_key: leaf.public_key.clone(),
+            parent_hash: ParentHash::empty(),
+            unmerged_leaves: vec![],
+        };
+
+        let nodes = (0..leaf_count * 2 - 1)
+            .map(|i| match i % 2 {
+                0 => Some(Node::Leaf(leaf.clone())),
+                _ => Some(Node::Parent(parent.clone())),
+            })
+            .collect::<Vec<_>>();
+
+        let mut tree = TreeKemPublic::new();
+        tree.nodes = NodeVec::from(nodes);
+
+        Self {
+            tree,
+            cipher_suite_provider: crypto.cipher_suite_provider(cs).unwrap(),
+        }
+    }
+
+    /// Compute the tree hash, hashing all nodes that are not cached yet.
+    pub fn tree_hash(&mut self) -> Vec<u8> {
+        self.tree.tree_hash(&self.cipher_suite_provider).unwrap()
+    }
+
+    /// Recompute the cached hashes on the direct paths of `leaves`.
+    pub fn update_leaves(&mut self, leaves: &[u32]) {
+        let leaves = leaves.iter().copied().map(LeafIndex).collect::<Vec<_>>();
+
+        self.tree
+            .update_hashes(&leaves, &self.cipher_suite_provider)
+            .unwrap()
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::{self, Debug};
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+pub use mls_rs_core::time::*;
+
+/// Source of the current time.
+///
+/// A provider can be configured with
+/// [`ClientBuilder::time_provider`](crate::client_builder::ClientBuilder::time_provider),
+/// in which case it is used wherever the system clock would otherwise be
+/// read, for instance to set the lifetime of key packages or to check the
+/// expiry of application messages. This lets tests, and replays of
+/// [recorded transcripts](crate::group::GroupTranscript), run at a fixed
+/// time.
+pub trait TimeProvider: Send + Sync {
+    /// Current time.
+    fn current_time(&self) -> MlsTime;
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedTimeProvider(pub(crate) Arc<dyn TimeProvider>);
+
+impl Debug for SharedTimeProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedTimeProvider").finish()
+    }
+}
+
+/// Time given by `provider`, or the system time if there is none. There is
+/// no system time without the `std` feature.
+pub(crate) fn current_time(provider: Option<Arc<dyn TimeProvider>>) -> Option<MlsTime> {
+    match provider {
+        Some(provider) => Some(provider.current_time()),
+        #[cfg(feature = "std")]
+        None => Some(MlsTime::now()),
+        #[cfg(not(feature = "std"))]
+        None => None,
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::{client::MlsError, crypto::HpkePublicKey};
+
+use super::{node::NodeIndex, TreeKemPublic};
+
+/// Node using each HPKE public key of a tree.
+///
+/// Reusing the key of a node in another node, be it a leaf or a parent, lets
+/// the holder of the secret key decrypt path secrets meant for the other
+/// node, so keys must be unique across the whole tree. The index is built
+/// when a tree is imported, and when an update path is received, from the
+/// nodes the path doesn't replace.
+#[derive(Debug, Default)]
+pub(crate) struct HpkeKeyIndex<'a> {
+    #[cfg(feature = "std")]
+    nodes: HashMap<&'a HpkePublicKey, NodeIndex>,
+    #[cfg(not(feature = "std"))]
+    nodes: BTreeMap<&'a HpkePublicKey, NodeIndex>,
+}
+
+impl<'a> HpkeKeyIndex<'a> {
+    /// Index the keys of all nodes of `tree` except those in `excluded`.
+    pub(crate) fn new(tree: &'a TreeKemPublic, excluded: &[NodeIndex]) -> Result<Self, MlsError> {
+        let mut index = Self::default();
+
+        for (i, node) in tree.nodes.iter().enumerate() {
+            let i = NodeIndex::new(i as u32);
+
+            if let Some(node) = node.as_ref().filter(|_| !excluded.contains(&i)) {
+                index.insert(node.public_key(), i)?;
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Record that `node` uses `key`, failing with
+    /// [`MlsError::DuplicateHpkeKey`] if another node already does.
+    pub(crate) fn insert(
+        &mut self,
+        key: &'a HpkePublicKey,
+        node: NodeIndex,
+    ) -> Result<(), MlsError> {
+        match self.nodes.insert(key, node) {
+            Some(other) => Err(MlsError::DuplicateHpkeKey(other, node)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{test_utils::TEST_CIPHER_SUITE, MlsError},
+        tree_kem::{
+            node::{Node, NodeIndex, Parent},
+            parent_hash::{test_utils::get_test_tree_fig_12, ParentHash},
+        },
+    };
+
+    use super::HpkeKeyIndex;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn parent_reusing_leaf_key_is_detected() {
+        let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+
+        HpkeKeyIndex::new(&tree, &[]).unwrap();
+
+        let leaf_key = tree.nodes[0].as_ref().unwrap().public_key().clone();
+
+        tree.nodes[1] = Some(Node::Parent(Parent {
+            public_key: leaf_key,
+            parent_hash: ParentHash::empty(),
+            unmerged_leaves: vec![],
+        }));
+
+        let res = HpkeKeyIndex::new(&tree, &[]);
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateHpkeKey(NodeIndex(0), NodeIndex(1)))
+        );
+
+        HpkeKeyIndex::new(&tree, &[NodeIndex(1)]).unwrap();
+    }
+}
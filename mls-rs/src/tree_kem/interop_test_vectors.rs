@@ -14,7 +14,10 @@ use crate::{
 };
 
 use super::{
-    node::NodeVec, test_utils::TreeWithSigners, tree_validator::TreeValidator, TreeKemPublic,
+    node::{NodeIndex, NodeVec},
+    test_utils::TreeWithSigners,
+    tree_validator::TreeValidator,
+    TreeKemPublic,
 };
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
@@ -52,7 +55,10 @@ impl ValidationTestCase {
         let resolutions = (0..tree_size)
             .map(
                 #[cfg_attr(coverage_nightly, coverage(off))]
-                |i| tree.nodes.get_resolution_index(i).unwrap(),
+                |i| {
+                    let resolution = tree.nodes.get_resolution_index(NodeIndex(i)).unwrap();
+                    resolution.into_iter().map(|n| *n).collect()
+                },
             )
             .collect();
 
@@ -113,7 +119,11 @@ async fn validation() {
             .iter()
             .enumerate()
             .for_each(|(i, res)| {
-                assert_eq!(&tree.nodes.get_resolution_index(i as u32).unwrap(), res)
+                let resolution = tree.nodes.get_resolution_index(NodeIndex(i as u32));
+                assert_eq!(
+                    resolution.unwrap(),
+                    res.iter().copied().map(NodeIndex).collect_vec()
+                )
             });
 
         let mut context = get_test_group_context(1, test_case.cipher_suite.into()).await;
@@ -12,7 +12,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 use itertools::Itertools;
 use mls_rs_codec::MlsEncode;
-use tree_math::{CopathNode, TreeIndex};
+use tree_math::CopathNode;
 
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
 use {crate::iter::ParallelIteratorExt, rayon::prelude::*};
@@ -181,7 +181,7 @@ impl<'a> TreeKem<'a> {
         cipher_suite: &P,
         excluding: &[LeafIndex],
     ) -> Result<Vec<UpdatePathNode>, MlsError> {
-        let excluding = excluding.iter().copied().map(NodeIndex::from);
+        let excluding = excluding.iter().copied().map(LeafIndex::to_node_index);
 
         #[cfg(feature = "std")]
         let excluding = excluding.collect::<HashSet<NodeIndex>>();
@@ -217,7 +217,7 @@ impl<'a> TreeKem<'a> {
         cipher_suite: &P,
         excluding: &[LeafIndex],
     ) -> Result<Vec<UpdatePathNode>, MlsError> {
-        let excluding = excluding.iter().copied().map(NodeIndex::from);
+        let excluding = excluding.iter().copied().map(LeafIndex::to_node_index);
 
         #[cfg(feature = "std")]
         let excluding = excluding.collect::<HashSet<NodeIndex>>();
@@ -255,10 +255,12 @@ impl<'a> TreeKem<'a> {
         let self_index = self.private_key.self_index;
 
         let lca_index =
-            tree_math::leaf_lca_level(self_index.into(), sender_index.into()) as usize - 2;
+            tree_math::leaf_lca_level(*self_index.to_node_index(), *sender_index.to_node_index())
+                as usize
+                - 2;
 
         let mut path = self.tree_kem_public.nodes.direct_copath(self_index);
-        let leaf = CopathNode::new(self_index.into(), 0);
+        let leaf = CopathNode::new(self_index.to_node_index(), NodeIndex::new(0));
         path.insert(0, leaf);
         let resolved_pos = self.find_resolved_pos(&path, lca_index)?;
 
@@ -356,7 +358,7 @@ impl<'a> TreeKem<'a> {
         let ctxts = ctxts.try_collect().await?;
 
         let path_index = copath_index
-            .parent_sibling(&self.tree_kem_public.total_leaf_count())
+            .parent_sibling(self.tree_kem_public.total_leaf_count())
             .ok_or(MlsError::ExpectedNode)?
             .parent;
 
@@ -401,7 +403,7 @@ impl<'a> TreeKem<'a> {
 
         let (ct_pos, _) = reso
             .iter()
-            .filter(|idx| **idx % 2 == 1 || !excluding.contains(&LeafIndex(**idx / 2)))
+            .filter(|idx| !idx.is_leaf() || !excluding.contains(&LeafIndex(***idx / 2)))
             .find_position(|idx| idx == &&resolved)
             .ok_or(MlsError::UpdateErrorNoSecretKey)?;
 
@@ -411,7 +413,7 @@ impl<'a> TreeKem<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{tree_math, TreeKem};
+    use super::{NodeIndex, TreeKem};
     use crate::{
         cipher_suite::CipherSuite,
         client::test_utils::TEST_CIPHER_SUITE,
@@ -432,7 +434,6 @@ mod tests {
     use alloc::{format, vec, vec::Vec};
     use mls_rs_codec::MlsEncode;
     use mls_rs_core::crypto::CipherSuiteProvider;
-    use tree_math::TreeIndex;
 
     // Verify that the tree is in the correct state after generating an update path
     fn verify_tree_update_path(
@@ -475,7 +476,7 @@ mod tests {
         }
 
         // Verify that we have a public keys up to the root
-        let root = tree.total_leaf_count().root();
+        let root = NodeIndex::root(tree.total_leaf_count());
         assert!(tree.nodes.borrow_node(root).unwrap().is_some());
     }
 
@@ -2,7 +2,10 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use crate::{client::MlsError, time::MlsTime};
+use crate::{
+    client::MlsError,
+    time::{MlsTime, TimeProvider},
+};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
 #[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode, Default)]
@@ -23,17 +26,26 @@ impl Lifetime {
     }
 
     pub fn seconds(s: u64) -> Result<Self, MlsError> {
-        #[cfg(feature = "std")]
-        let not_before = MlsTime::now().seconds_since_epoch();
-        #[cfg(not(feature = "std"))]
         // There is no clock on no_std, this is here just so that we can run tests.
-        let not_before = 3600u64;
+        let not_before = crate::time::current_time(None).map_or(3600, |t| t.seconds_since_epoch());
 
+        Self::seconds_from(not_before, s)
+    }
+
+    /// Lifetime of `s` seconds from the time given by `time_provider`.
+    pub fn seconds_with_time_provider(
+        s: u64,
+        time_provider: &dyn TimeProvider,
+    ) -> Result<Self, MlsError> {
+        Self::seconds_from(time_provider.current_time().seconds_since_epoch(), s)
+    }
+
+    fn seconds_from(not_before: u64, s: u64) -> Result<Self, MlsError> {
         let not_after = not_before.checked_add(s).ok_or(MlsError::TimeOverflow)?;
 
         Ok(Lifetime {
             // Subtract 1 hour to address time difference between machines
-            not_before: not_before - 3600,
+            not_before: not_before.saturating_sub(3600),
             not_after,
         })
     }
@@ -72,6 +84,20 @@ mod tests {
         assert_eq!(lifetime.not_after - lifetime.not_before, 3610);
     }
 
+    #[test]
+    fn test_seconds_with_time_provider() {
+        struct FixedTime;
+
+        impl TimeProvider for FixedTime {
+            fn current_time(&self) -> MlsTime {
+                MlsTime::from(1_000_000)
+            }
+        }
+
+        let lifetime = Lifetime::seconds_with_time_provider(10, &FixedTime).unwrap();
+        assert_eq!(lifetime, Lifetime::new(1_000_000 - 3600, 1_000_010));
+    }
+
     #[test]
     fn test_days() {
         let days = 2;
@@ -6,15 +6,15 @@ use alloc::vec::Vec;
 use core::{fmt::Debug, hash::Hash};
 use mls_rs_codec::{MlsDecode, MlsEncode};
 
-use super::node::LeafIndex;
+use super::node::{LeafIndex, NodeIndex};
 
 pub trait TreeIndex:
     Send + Sync + Eq + Clone + Debug + Default + MlsEncode + MlsDecode + Hash + Ord
 {
     fn root(&self) -> Self;
 
-    fn left_unchecked(&self) -> Self;
-    fn right_unchecked(&self) -> Self;
+    fn left(&self) -> Option<Self>;
+    fn right(&self) -> Option<Self>;
 
     fn parent_sibling(&self, leaf_count: &Self) -> Option<ParentSibling<Self>>;
     fn is_leaf(&self) -> bool;
@@ -23,16 +23,6 @@ pub trait TreeIndex:
     #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
     fn zero() -> Self;
 
-    #[cfg(any(feature = "secret_tree_access", feature = "private_message", test))]
-    fn left(&self) -> Option<Self> {
-        (!self.is_leaf()).then(|| self.left_unchecked())
-    }
-
-    #[cfg(any(feature = "secret_tree_access", feature = "private_message", test))]
-    fn right(&self) -> Option<Self> {
-        (!self.is_leaf()).then(|| self.right_unchecked())
-    }
-
     fn direct_copath(&self, leaf_count: &Self) -> Vec<CopathNode<Self>> {
         let root = leaf_count.root();
 
@@ -79,18 +69,23 @@ impl<T: Clone + PartialEq + Eq + core::fmt::Debug> ParentSibling<T> {
 macro_rules! impl_tree_stdint {
     ($t:ty) => {
         impl TreeIndex for $t {
+            /// Root of a tree with `self` leaves. `self` is expected to be a
+            /// power of two.
             fn root(&self) -> $t {
-                *self - 1
+                self.saturating_sub(1)
             }
 
-            /// Panicks if `x` is even in debug, overflows in release.
-            fn left_unchecked(&self) -> Self {
-                *self ^ (0x01 << (self.trailing_ones() - 1))
+            /// Left child of `self`, or `None` if it is a leaf.
+            fn left(&self) -> Option<Self> {
+                let half_width = <$t>::checked_shl(1, self.trailing_ones().checked_sub(1)?)?;
+                self.checked_sub(half_width)
             }
 
-            /// Panicks if `x` is even in debug, overflows in release.
-            fn right_unchecked(&self) -> Self {
-                *self ^ (0x03 << (self.trailing_ones() - 1))
+            /// Right child of `self`, or `None` if it is a leaf or if the
+            /// child would not fit in the index type.
+            fn right(&self) -> Option<Self> {
+                let half_width = <$t>::checked_shl(1, self.trailing_ones().checked_sub(1)?)?;
+                self.checked_add(half_width)
             }
 
             fn parent_sibling(&self, leaf_count: &Self) -> Option<ParentSibling<Self>> {
@@ -99,14 +94,20 @@ macro_rules! impl_tree_stdint {
                 }
 
                 let lvl = self.trailing_ones();
-                let p = (self & !(1 << (lvl + 1))) | (1 << lvl);
 
-                let s = if *self < p {
-                    p.right_unchecked()
-                } else {
-                    p.left_unchecked()
+                // Nodes at the highest level have no parent.
+                let (Some(level_bit), Some(parent_level_bit)) =
+                    (<$t>::checked_shl(1, lvl), <$t>::checked_shl(1, lvl + 1))
+                else {
+                    return None;
                 };
 
+                let p = (self & !parent_level_bit) | level_bit;
+
+                // Both children of `p` differ from it by `level_bit`, so they
+                // differ from each other by `parent_level_bit` only.
+                let s = self ^ parent_level_bit;
+
                 Some(ParentSibling::new(p, s))
             }
 
@@ -115,7 +116,7 @@ macro_rules! impl_tree_stdint {
             }
 
             fn is_in_tree(&self, root: &Self) -> bool {
-                *self <= 2 * root
+                root.checked_mul(2).map_or(true, |width| *self <= width)
             }
 
             #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
@@ -131,6 +132,41 @@ impl_tree_stdint!(u32);
 #[cfg(test)]
 impl_tree_stdint!(u64);
 
+/// Navigation in a tree of `u32` nodes with a leaf count taken as a plain
+/// number, as stored in [`NodeVec`](super::node::NodeVec).
+impl NodeIndex {
+    /// Root of a tree with `leaf_count` leaves.
+    pub(crate) fn root(leaf_count: u32) -> Self {
+        Self(leaf_count.root())
+    }
+
+    pub(crate) fn left(&self) -> Option<Self> {
+        self.0.left().map(Self)
+    }
+
+    pub(crate) fn right(&self) -> Option<Self> {
+        self.0.right().map(Self)
+    }
+
+    pub(crate) fn parent_sibling(&self, leaf_count: u32) -> Option<ParentSibling<Self>> {
+        self.0
+            .parent_sibling(&leaf_count)
+            .map(|ps| ParentSibling::new(Self(ps.parent), Self(ps.sibling)))
+    }
+
+    pub(crate) fn is_leaf(&self) -> bool {
+        self.0.is_leaf()
+    }
+
+    pub(crate) fn direct_copath(&self, leaf_count: u32) -> Vec<CopathNode<Self>> {
+        self.0
+            .direct_copath(&leaf_count)
+            .into_iter()
+            .map(|cp| CopathNode::new(Self(cp.path), Self(cp.copath)))
+            .collect()
+    }
+}
+
 pub fn leaf_lca_level(x: u32, y: u32) -> u32 {
     let mut xn = x;
     let mut yn = y;
@@ -145,7 +181,8 @@ pub fn leaf_lca_level(x: u32, y: u32) -> u32 {
     k
 }
 
-pub fn subtree(x: u32) -> (LeafIndex, LeafIndex) {
+pub fn subtree(x: NodeIndex) -> (LeafIndex, LeafIndex) {
+    let x = *x;
     let breadth = 1 << x.trailing_ones();
     (
         LeafIndex((x + 1 - breadth) >> 1),
@@ -380,4 +417,147 @@ mod tests {
             assert_eq!(item, &copath)
         }
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod properties {
+        use proptest::prelude::*;
+
+        use super::super::{ParentSibling, TreeIndex};
+
+        macro_rules! tree_math_properties {
+            ($name:ident, $t:ty) => {
+                mod $name {
+                    use super::*;
+
+                    const BITS: u32 = <$t>::BITS;
+
+                    // A tree with `2^log_n_leaves` leaves and a node in that tree.
+                    fn tree_and_node() -> impl Strategy<Value = ($t, $t)> {
+                        (0..BITS).prop_flat_map(|log_n_leaves| {
+                            let n_leaves: $t = 1 << log_n_leaves;
+                            (Just(n_leaves), 0..=2 * n_leaves.root())
+                        })
+                    }
+
+                    proptest! {
+                        #[test]
+                        fn root_is_at_top_level(log_n_leaves in 0..BITS) {
+                            let n_leaves: $t = 1 << log_n_leaves;
+                            let root = n_leaves.root();
+
+                            prop_assert_eq!(root.trailing_ones(), log_n_leaves);
+                            prop_assert!(root.is_in_tree(&root));
+                            prop_assert_eq!(root.parent_sibling(&n_leaves), None);
+                        }
+
+                        #[test]
+                        fn parent_has_node_and_sibling_as_children(
+                            (n_leaves, x) in tree_and_node()
+                        ) {
+                            let Some(ps) = x.parent_sibling(&n_leaves) else {
+                                prop_assert_eq!(x, n_leaves.root());
+                                return Ok(());
+                            };
+
+                            prop_assert!(!ps.parent.is_leaf());
+                            prop_assert!(ps.parent.is_in_tree(&n_leaves.root()));
+                            prop_assert_eq!(ps.parent.trailing_ones(), x.trailing_ones() + 1);
+
+                            let (left, right) = (ps.parent.left(), ps.parent.right());
+
+                            if x < ps.parent {
+                                prop_assert_eq!((left, right), (Some(x), Some(ps.sibling)));
+                            } else {
+                                prop_assert_eq!((left, right), (Some(ps.sibling), Some(x)));
+                            }
+
+                            prop_assert_eq!(
+                                ps.sibling.parent_sibling(&n_leaves),
+                                Some(ParentSibling::new(ps.parent, x))
+                            );
+                        }
+
+                        #[test]
+                        fn children_have_node_as_parent((n_leaves, x) in tree_and_node()) {
+                            let (Some(left), Some(right)) = (x.left(), x.right()) else {
+                                prop_assert!(x.is_leaf());
+                                return Ok(());
+                            };
+
+                            prop_assert_eq!(
+                                left.parent_sibling(&n_leaves),
+                                Some(ParentSibling::new(x, right))
+                            );
+
+                            prop_assert_eq!(
+                                right.parent_sibling(&n_leaves),
+                                Some(ParentSibling::new(x, left))
+                            );
+                        }
+
+                        #[test]
+                        fn direct_copath_leads_to_root((n_leaves, x) in tree_and_node()) {
+                            let root = n_leaves.root();
+                            let copath = x.direct_copath(&n_leaves);
+
+                            prop_assert_eq!(
+                                copath.len() as u32,
+                                root.trailing_ones() - x.trailing_ones()
+                            );
+
+                            let mut child = x;
+
+                            for node in copath {
+                                prop_assert_eq!(
+                                    child.parent_sibling(&n_leaves),
+                                    Some(ParentSibling::new(node.path, node.copath))
+                                );
+
+                                child = node.path;
+                            }
+
+                            prop_assert_eq!(child, root);
+                        }
+
+                        #[test]
+                        fn nodes_outside_tree_have_no_copath(
+                            (n_leaves, x) in tree_and_node(),
+                            offset in 1..=<$t>::MAX
+                        ) {
+                            let root = n_leaves.root();
+                            let outside = (2 * root).saturating_add(offset);
+
+                            prop_assert!(x.is_in_tree(&root));
+                            prop_assert!(!outside.is_in_tree(&root));
+                            prop_assert!(outside.direct_copath(&n_leaves).is_empty());
+                        }
+
+                        #[test]
+                        fn tree_math_does_not_panic(x in any::<$t>(), n_leaves in any::<$t>()) {
+                            let root = n_leaves.root();
+
+                            let _ = x.is_in_tree(&root);
+                            let _ = x.left();
+                            let _ = x.right();
+                            let _ = x.parent_sibling(&n_leaves);
+                        }
+
+                        #[test]
+                        fn children_do_not_wrap_around(x in any::<$t>()) {
+                            if let Some(left) = x.left() {
+                                prop_assert!(left < x);
+                            }
+
+                            if let Some(right) = x.right() {
+                                prop_assert!(right > x);
+                            }
+                        }
+                    }
+                }
+            };
+        }
+
+        tree_math_properties!(u32_index, u32);
+        tree_math_properties!(u64_index, u64);
+    }
 }
@@ -34,6 +34,7 @@ use crate::tree_kem::tree_hash::TreeHashes;
 
 mod capabilities;
 pub(crate) mod hpke_encryption;
+mod hpke_key_index;
 mod lifetime;
 pub(crate) mod math;
 pub mod node;
@@ -45,6 +46,7 @@ pub mod tree_validator;
 pub mod update_path;
 
 pub use capabilities::*;
+pub(crate) use hpke_key_index::HpkeKeyIndex;
 pub use lifetime::*;
 pub(crate) use private::*;
 pub use update_path::*;
@@ -121,6 +123,7 @@ impl TreeKemPublic {
             for (leaf_index, leaf) in self.nodes.non_empty_leaves() {
                 index_insert(
                     &mut self.index,
+                    &self.nodes,
                     leaf,
                     leaf_index,
                     identity_provider,
@@ -128,6 +131,8 @@ impl TreeKemPublic {
                 )
                 .await?;
             }
+        } else {
+            self.index.index_shared_signature_keys(&self.nodes);
         }
 
         Ok(())
@@ -296,12 +301,12 @@ impl TreeKemPublic {
         }
 
         #[cfg(feature = "tree_index")]
-        self.index.remove(&original_leaf_node, &original_identity);
+        self.index
+            .remove(sender, &original_leaf_node, &original_identity);
 
         index_insert(
             #[cfg(feature = "tree_index")]
             &mut self.index,
-            #[cfg(not(feature = "tree_index"))]
             &self.nodes,
             &update_path.leaf_node,
             sender,
@@ -359,7 +364,7 @@ impl TreeKemPublic {
                 let identity =
                     identity(&old_leaf.signing_identity, id_provider, extensions).await?;
 
-                self.index.remove(old_leaf, &identity);
+                self.index.remove(index, old_leaf, &identity);
             }
 
             if proposal_bundle.remove_proposals()[i].is_by_value() || !filter {
@@ -383,7 +388,7 @@ impl TreeKemPublic {
                         identity(&old_leaf.signing_identity, id_provider, extensions).await?;
 
                     #[cfg(feature = "tree_index")]
-                    self.index.remove(&old_leaf, &old_id);
+                    self.index.remove(index, &old_leaf, &old_id);
 
                     partial_updates.push((index, old_leaf, new_leaf, i));
                 }
@@ -406,8 +411,15 @@ impl TreeKemPublic {
         // all updates.
         for (index, old_leaf, new_leaf, i) in partial_updates.into_iter() {
             #[cfg(feature = "tree_index")]
-            let res =
-                index_insert(&mut self.index, &new_leaf, index, id_provider, extensions).await;
+            let res = index_insert(
+                &mut self.index,
+                &self.nodes,
+                &new_leaf,
+                index,
+                id_provider,
+                extensions,
+            )
+            .await;
 
             #[cfg(not(feature = "tree_index"))]
             let res = index_insert(&self.nodes, &new_leaf, index, id_provider, extensions).await;
@@ -424,8 +436,15 @@ impl TreeKemPublic {
                 updated_indices.push(index);
             } else {
                 #[cfg(feature = "tree_index")]
-                let res =
-                    index_insert(&mut self.index, &old_leaf, index, id_provider, extensions).await;
+                let res = index_insert(
+                    &mut self.index,
+                    &self.nodes,
+                    &old_leaf,
+                    index,
+                    id_provider,
+                    extensions,
+                )
+                .await;
 
                 #[cfg(not(feature = "tree_index"))]
                 let res =
@@ -539,7 +558,7 @@ impl TreeKemPublic {
                 let identity =
                     identity(&old_leaf.signing_identity, id_provider, extensions).await?;
 
-                self.index.remove(&old_leaf, &identity);
+                self.index.remove(index, &old_leaf, &identity);
             }
 
             #[cfg(not(feature = "tree_index"))]
@@ -586,7 +605,15 @@ impl TreeKemPublic {
         let index = self.nodes.next_empty_leaf(start.unwrap_or(LeafIndex(0)));
 
         #[cfg(feature = "tree_index")]
-        index_insert(&mut self.index, &leaf, index, id_provider, extensions).await?;
+        index_insert(
+            &mut self.index,
+            &self.nodes,
+            &leaf,
+            index,
+            id_provider,
+            extensions,
+        )
+        .await?;
 
         #[cfg(not(feature = "tree_index"))]
         index_insert(&self.nodes, &leaf, index, id_provider, extensions).await?;
@@ -940,18 +967,21 @@ mod tests {
     #[cfg(feature = "custom_proposal")]
     use crate::group::proposal::ProposalType;
 
-    use crate::identity::basic::BasicIdentityProvider;
+    use crate::identity::basic::{BasicIdentityProvider, BasicIdentityProviderError};
+    use crate::identity::{CredentialType, SigningIdentity};
     use crate::tree_kem::leaf_node::LeafNode;
     use crate::tree_kem::node::{LeafIndex, Node, NodeIndex, NodeTypeResolver, Parent};
     use crate::tree_kem::parent_hash::ParentHash;
     use crate::tree_kem::test_utils::{get_test_leaf_nodes, get_test_tree};
     use crate::tree_kem::{MlsError, TreeKemPublic};
+    use crate::{time::MlsTime, ExtensionList};
     use alloc::borrow::ToOwned;
     use alloc::vec;
     use alloc::vec::Vec;
     use assert_matches::assert_matches;
+    use mls_rs_core::identity::IdentityProvider;
 
-    #[cfg(feature = "by_ref_proposal")]
+    #[cfg(any(feature = "by_ref_proposal", mls_build_async))]
     use alloc::boxed::Box;
 
     #[cfg(feature = "by_ref_proposal")]
@@ -966,7 +996,6 @@ mod tests {
         key_package::test_utils::test_key_package,
     };
 
-    #[cfg(any(feature = "by_ref_proposal", feature = "custo_proposal"))]
     use crate::tree_kem::leaf_node::test_utils::get_basic_test_node;
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -1090,7 +1119,224 @@ mod tests {
             .add_leaves(key_packages, &BasicIdentityProvider, &cipher_suite_provider)
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(0, 3)));
+    }
+
+    #[derive(Clone, Debug)]
+    struct SharedSignatureKeyProvider;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl IdentityProvider for SharedSignatureKeyProvider {
+        type Error = BasicIdentityProviderError;
+
+        async fn validate_member(
+            &self,
+            signing_identity: &SigningIdentity,
+            timestamp: Option<MlsTime>,
+            extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            BasicIdentityProvider
+                .validate_member(signing_identity, timestamp, extensions)
+                .await
+        }
+
+        async fn validate_external_sender(
+            &self,
+            signing_identity: &SigningIdentity,
+            timestamp: Option<MlsTime>,
+            extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            BasicIdentityProvider
+                .validate_external_sender(signing_identity, timestamp, extensions)
+                .await
+        }
+
+        async fn identity(
+            &self,
+            signing_identity: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            BasicIdentityProvider
+                .identity(signing_identity, extensions)
+                .await
+        }
+
+        async fn allow_shared_signature_key(
+            &self,
+            _existing: &SigningIdentity,
+            _new: &SigningIdentity,
+            _extensions: &ExtensionList,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        async fn valid_successor(
+            &self,
+            predecessor: &SigningIdentity,
+            successor: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<bool, Self::Error> {
+            BasicIdentityProvider
+                .valid_successor(predecessor, successor, extensions)
+                .await
+        }
+
+        fn supported_types(&self) -> Vec<CredentialType> {
+            BasicIdentityProvider.supported_types()
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn shared_signature_key_requires_provider_approval() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = TreeKemPublic::new();
+
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+        let signature_key = leaf_nodes[0].signing_identity.signature_key.clone();
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let mut sharing_leaf = get_basic_test_node(TEST_CIPHER_SUITE, "D").await;
+        sharing_leaf.signing_identity.signature_key = signature_key;
+
+        let res = tree
+            .add_leaves(
+                vec![sharing_leaf.clone()],
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await;
+
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(0, 3)));
+
+        let added = tree
+            .add_leaves(
+                vec![sharing_leaf.clone()],
+                &SharedSignatureKeyProvider,
+                &cipher_suite_provider,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(added, vec![LeafIndex(3)]);
+
+        // Removing the leaf sharing the key doesn't make the key available.
+        tree.remove_leaves(added, &SharedSignatureKeyProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let res = tree
+            .add_leaves(
+                vec![sharing_leaf],
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await;
+
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(0, 3)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn shared_signature_key_survives_removal_of_first_leaf() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = TreeKemPublic::new();
+
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+        let signature_key = leaf_nodes[0].signing_identity.signature_key.clone();
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let mut sharing_leaf = get_basic_test_node(TEST_CIPHER_SUITE, "D").await;
+        sharing_leaf.signing_identity.signature_key = signature_key.clone();
+
+        tree.add_leaves(
+            vec![sharing_leaf],
+            &SharedSignatureKeyProvider,
+            &cipher_suite_provider,
+        )
+        .await
+        .unwrap();
+
+        // Leaf 3 still uses the key after the leaf that first used it is gone.
+        tree.remove_leaves(
+            vec![LeafIndex(0)],
+            &SharedSignatureKeyProvider,
+            &cipher_suite_provider,
+        )
+        .await
+        .unwrap();
+
+        let mut new_leaf = get_basic_test_node(TEST_CIPHER_SUITE, "E").await;
+        new_leaf.signing_identity.signature_key = signature_key;
+
+        let res = tree
+            .add_leaves(
+                vec![new_leaf],
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await;
+
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(3, 0)));
+    }
+
+    #[cfg(feature = "tree_index")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn shared_signature_key_is_indexed_in_decoded_tree() {
+        use mls_rs_codec::{MlsDecode, MlsEncode};
+
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = TreeKemPublic::new();
+
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+        let signature_key = leaf_nodes[0].signing_identity.signature_key.clone();
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let mut sharing_leaf = get_basic_test_node(TEST_CIPHER_SUITE, "D").await;
+        sharing_leaf.signing_identity.signature_key = signature_key.clone();
+
+        tree.add_leaves(
+            vec![sharing_leaf],
+            &SharedSignatureKeyProvider,
+            &cipher_suite_provider,
+        )
+        .await
+        .unwrap();
+
+        let mut tree = TreeKemPublic::mls_decode(&mut &*tree.mls_encode_to_vec().unwrap()).unwrap();
+
+        tree.initialize_index_if_necessary(&BasicIdentityProvider, &Default::default())
+            .await
+            .unwrap();
+
+        tree.remove_leaves(
+            vec![LeafIndex(0)],
+            &SharedSignatureKeyProvider,
+            &cipher_suite_provider,
+        )
+        .await
+        .unwrap();
+
+        let mut new_leaf = get_basic_test_node(TEST_CIPHER_SUITE, "E").await;
+        new_leaf.signing_identity.signature_key = signature_key;
+
+        let res = tree
+            .add_leaves(
+                vec![new_leaf],
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await;
+
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(3, 0)));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -1207,7 +1453,7 @@ mod tests {
 
         // Verify that the direct path has been cleared
         tree.nodes.direct_copath(LeafIndex(0)).iter().for_each(|n| {
-            assert!(tree.nodes[n.path as usize].is_none());
+            assert!(tree.nodes[*n.path as usize].is_none());
         });
     }
 
@@ -1317,7 +1563,7 @@ mod tests {
 
         // There should be a blank in the tree
         assert_eq!(
-            tree.nodes.get(NodeIndex::from(to_remove) as usize).unwrap(),
+            tree.nodes.get(*to_remove.to_node_index() as usize).unwrap(),
             &None
         );
     }
@@ -1353,7 +1599,7 @@ mod tests {
         // The location of key_packages[1] should now be blank
         let removed_location = tree
             .nodes
-            .get(NodeIndex::from(LeafIndex(2)) as usize)
+            .get(*LeafIndex(2).to_node_index() as usize)
             .unwrap();
 
         assert_eq!(removed_location, &None);
@@ -1374,7 +1620,7 @@ mod tests {
             )
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidNodeIndex(256)));
+        assert_matches!(res, Err(MlsError::InvalidNodeIndex(NodeIndex(256))));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -9,10 +9,11 @@ use crate::tree_kem::math as tree_math;
 use crate::tree_kem::parent_hash::ParentHash;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt::{self, Display};
 use core::hash::Hash;
 use core::ops::{Deref, DerefMut};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
-use tree_math::{CopathNode, TreeIndex};
+use tree_math::CopathNode;
 
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -22,6 +23,7 @@ pub(crate) struct Parent {
     pub unmerged_leaves: Vec<LeafIndex>,
 }
 
+/// Index of a leaf of the ratchet tree, i.e. of a member slot.
 #[derive(
     Clone, Copy, Debug, Ord, PartialEq, PartialOrd, Hash, Eq, MlsSize, MlsEncode, MlsDecode,
 )]
@@ -33,6 +35,12 @@ impl LeafIndex {
     pub fn new(i: u32) -> Self {
         Self(i)
     }
+
+    /// Index of the node holding this leaf in the array representation of
+    /// the tree.
+    pub(crate) fn to_node_index(self) -> NodeIndex {
+        NodeIndex(self.0 * 2)
+    }
 }
 
 impl Deref for LeafIndex {
@@ -43,19 +51,40 @@ impl Deref for LeafIndex {
     }
 }
 
-impl From<&LeafIndex> for NodeIndex {
-    fn from(leaf_index: &LeafIndex) -> Self {
-        leaf_index.0 * 2
+/// Index of a node in the array representation of the ratchet tree, where
+/// leaf `i` is node `2 * i`.
+///
+/// Unlike a plain `u32`, it can't be passed where a [`LeafIndex`] is expected,
+/// or the other way around. Convert between them with
+/// [`tree_math::node_index`](crate::tree_math::node_index) and
+/// [`tree_math::leaf_index`](crate::tree_math::leaf_index).
+#[derive(
+    Clone, Copy, Debug, Default, Ord, PartialEq, PartialOrd, Hash, Eq, MlsSize, MlsEncode, MlsDecode,
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+pub struct NodeIndex(pub(crate) u32);
+
+impl NodeIndex {
+    pub fn new(i: u32) -> Self {
+        Self(i)
     }
 }
 
-impl From<LeafIndex> for NodeIndex {
-    fn from(leaf_index: LeafIndex) -> Self {
-        leaf_index.0 * 2
+impl Deref for NodeIndex {
+    type Target = u32;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-pub(crate) type NodeIndex = u32;
+impl Display for NodeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[allow(clippy::large_enum_variant)]
@@ -190,10 +219,10 @@ impl NodeVec {
     }
 
     fn validate_index(&self, index: NodeIndex) -> Result<usize, MlsError> {
-        if (index as usize) >= self.len().next_power_of_two() {
+        if (*index as usize) >= self.len().next_power_of_two() {
             Err(MlsError::InvalidNodeIndex(index))
         } else {
-            Ok(index as usize)
+            Ok(*index as usize)
         }
     }
 
@@ -217,7 +246,7 @@ impl NodeVec {
             .enumerate()
             .skip(1)
             .step_by(2)
-            .map(|(i, n)| (i as NodeIndex, n))
+            .map(|(i, n)| (NodeIndex::new(i as u32), n))
             .filter_map(|(i, n)| n.as_parent().ok().map(|p| (i, p)))
     }
 
@@ -226,15 +255,16 @@ impl NodeVec {
     }
 
     pub fn direct_copath(&self, index: LeafIndex) -> Vec<CopathNode<NodeIndex>> {
-        NodeIndex::from(index).direct_copath(&self.total_leaf_count())
+        index.to_node_index().direct_copath(self.total_leaf_count())
     }
 
     // Section 8.4
     // The filtered direct path of a node is obtained from the node's direct path by removing
     // all nodes whose child on the nodes's copath has an empty resolution
     pub fn filtered(&self, index: LeafIndex) -> Result<Vec<bool>, MlsError> {
-        Ok(NodeIndex::from(index)
-            .direct_copath(&self.total_leaf_count())
+        Ok(index
+            .to_node_index()
+            .direct_copath(self.total_leaf_count())
             .into_iter()
             .map(|cp| self.is_resolution_empty(cp.copath))
             .collect())
@@ -247,12 +277,12 @@ impl NodeVec {
 
     #[inline]
     pub fn is_leaf(&self, index: NodeIndex) -> bool {
-        index % 2 == 0
+        index.is_leaf()
     }
 
     // Blank a previously filled leaf node, and return the existing leaf
     pub fn blank_leaf_node(&mut self, leaf_index: LeafIndex) -> Result<LeafNode, MlsError> {
-        let node_index = self.validate_index(leaf_index.into())?;
+        let node_index = self.validate_index(leaf_index.to_node_index())?;
 
         match self.get_mut(node_index).and_then(Option::take) {
             Some(Node::Leaf(l)) => Ok(l),
@@ -262,7 +292,7 @@ impl NodeVec {
 
     pub fn blank_direct_path(&mut self, leaf: LeafIndex) -> Result<(), MlsError> {
         for i in self.direct_copath(leaf) {
-            if let Some(n) = self.get_mut(i.path as usize) {
+            if let Some(n) = self.get_mut(*i.path as usize) {
                 *n = None
             }
         }
@@ -290,7 +320,7 @@ impl NodeVec {
     }
 
     pub fn borrow_as_leaf_mut(&mut self, index: LeafIndex) -> Result<&mut LeafNode, MlsError> {
-        let node_index = NodeIndex::from(index);
+        let node_index = index.to_node_index();
         let index = self.validate_index(node_index)?;
 
         self.get_mut(index)
@@ -299,7 +329,7 @@ impl NodeVec {
     }
 
     pub fn borrow_as_leaf(&self, index: LeafIndex) -> Result<&LeafNode, MlsError> {
-        let node_index = NodeIndex::from(index);
+        let node_index = index.to_node_index();
         self.borrow_node(node_index).and_then(|n| n.as_leaf())
     }
 
@@ -334,15 +364,15 @@ impl NodeVec {
         let mut resolution = vec![];
 
         while let Some(index) = indexes.pop() {
-            if let Some(Some(node)) = self.get(index as usize) {
+            if let Some(Some(node)) = self.get(*index as usize) {
                 resolution.push(index);
 
                 if let Node::Parent(p) = node {
-                    resolution.extend(p.unmerged_leaves.iter().map(NodeIndex::from));
+                    resolution.extend(p.unmerged_leaves.iter().map(|leaf| leaf.to_node_index()));
                 }
-            } else if !index.is_leaf() {
-                indexes.push(index.right_unchecked());
-                indexes.push(index.left_unchecked());
+            } else if let (Some(left), Some(right)) = (index.left(), index.right()) {
+                indexes.push(right);
+                indexes.push(left);
             }
         }
 
@@ -358,7 +388,7 @@ impl NodeVec {
         let mut resolution_len = 0;
 
         while let Some(index) = indexes.pop() {
-            if let Some(Some(node)) = self.get(index as usize) {
+            if let Some(Some(node)) = self.get(*index as usize) {
                 if Some(index) == to_find || to_find.is_none() {
                     return Some(resolution_len);
                 }
@@ -366,11 +396,11 @@ impl NodeVec {
                 resolution_len += 1;
 
                 if let Node::Parent(p) = node {
-                    indexes.extend(p.unmerged_leaves.iter().map(NodeIndex::from));
+                    indexes.extend(p.unmerged_leaves.iter().map(|leaf| leaf.to_node_index()));
                 }
-            } else if !index.is_leaf() {
-                indexes.push(index.right_unchecked());
-                indexes.push(index.left_unchecked());
+            } else if let (Some(left), Some(right)) = (index.left(), index.right()) {
+                indexes.push(right);
+                indexes.push(left);
             }
         }
 
@@ -382,11 +412,11 @@ impl NodeVec {
     }
 
     pub(crate) fn next_empty_leaf(&self, start: LeafIndex) -> LeafIndex {
-        let mut n = NodeIndex::from(start) as usize;
+        let mut n = *start.to_node_index() as usize;
 
         while n < self.len() {
             if self.0[n].is_none() {
-                return LeafIndex((n as u32) >> 1);
+                return LeafIndex(n as u32 / 2);
             }
 
             n += 2;
@@ -398,7 +428,7 @@ impl NodeVec {
     /// If `index` fits in the current tree, inserts `leaf` at `index`. Else, inserts `leaf` as the
     /// last leaf
     pub fn insert_leaf(&mut self, index: LeafIndex, leaf: LeafNode) {
-        let node_index = (*index as usize) << 1;
+        let node_index = *index.to_node_index() as usize;
 
         if node_index > self.len() {
             self.push(None);
@@ -479,7 +509,7 @@ mod tests {
     async fn test_direct_path() {
         let test_vec = get_test_node_vec().await;
         // Tree math is already tested in that module, just ensure equality
-        let expected = 0.direct_copath(&4);
+        let expected = NodeIndex(0).direct_copath(4);
         let actual = test_vec.direct_copath(LeafIndex(0));
         assert_eq!(actual, expected);
     }
@@ -497,11 +527,11 @@ mod tests {
         let mut test_vec = get_test_node_vec().await;
 
         // If the node is a leaf it should fail
-        assert!(test_vec.borrow_as_parent_mut(0).is_err());
+        assert!(test_vec.borrow_as_parent_mut(NodeIndex(0)).is_err());
 
         // If the node index is out of range it should fail
         assert!(test_vec
-            .borrow_as_parent_mut(test_vec.len() as u32)
+            .borrow_as_parent_mut(NodeIndex(test_vec.len() as u32))
             .is_err());
 
         // Otherwise it should succeed
@@ -511,20 +541,26 @@ mod tests {
             unmerged_leaves: vec![LeafIndex(2)],
         };
 
-        assert_eq!(test_vec.borrow_as_parent_mut(5).unwrap(), &mut expected);
+        assert_eq!(
+            test_vec.borrow_as_parent_mut(NodeIndex(5)).unwrap(),
+            &mut expected
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_get_resolution() {
         let test_vec = get_test_node_vec().await;
 
-        let resolution_node_5 = test_vec.get_resolution_index(5).unwrap();
-        let resolution_node_2 = test_vec.get_resolution_index(2).unwrap();
-        let resolution_node_3 = test_vec.get_resolution_index(3).unwrap();
+        let resolution_node_5 = test_vec.get_resolution_index(NodeIndex(5)).unwrap();
+        let resolution_node_2 = test_vec.get_resolution_index(NodeIndex(2)).unwrap();
+        let resolution_node_3 = test_vec.get_resolution_index(NodeIndex(3)).unwrap();
 
-        assert_eq!(&resolution_node_5, &[5, 4]);
+        assert_eq!(&resolution_node_5, &[NodeIndex(5), NodeIndex(4)]);
         assert!(resolution_node_2.is_empty());
-        assert_eq!(&resolution_node_3, &[0, 5, 4]);
+        assert_eq!(
+            &resolution_node_3,
+            &[NodeIndex(0), NodeIndex(5), NodeIndex(4)]
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -534,7 +570,7 @@ mod tests {
 
         let expected = test_vec[5].as_parent_mut().unwrap();
         let actual = test_vec2
-            .borrow_or_fill_node_as_parent(5, &Vec::new().into())
+            .borrow_or_fill_node_as_parent(NodeIndex(5), &Vec::new().into())
             .unwrap();
 
         assert_eq!(actual, expected);
@@ -551,7 +587,7 @@ mod tests {
         };
 
         let actual = test_vec
-            .borrow_or_fill_node_as_parent(1, &vec![0u8; 4].into())
+            .borrow_or_fill_node_as_parent(NodeIndex(1), &vec![0u8; 4].into())
             .unwrap();
 
         assert_eq!(actual, &mut expected);
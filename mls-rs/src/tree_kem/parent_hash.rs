@@ -4,26 +4,22 @@
 
 use crate::client::MlsError;
 use crate::crypto::{CipherSuiteProvider, HpkePublicKey};
-use crate::tree_kem::math as tree_math;
 use crate::tree_kem::node::{LeafIndex, Node, NodeIndex};
 use crate::tree_kem::TreeKemPublic;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec::Vec;
 use core::{
-    fmt::{self, Debug},
+    fmt::{self, Debug, Display},
     ops::Deref,
 };
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use mls_rs_core::error::IntoAnyError;
-use tree_math::TreeIndex;
 
 use super::leaf_node::LeafNodeSource;
 
 #[cfg(feature = "std")]
 use std::collections::HashSet;
 
-#[cfg(not(feature = "std"))]
-use alloc::collections::BTreeSet;
-
 #[derive(Clone, Debug, MlsSize, MlsEncode)]
 struct ParentHashInput<'a> {
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
@@ -99,6 +95,149 @@ impl ParentHash {
     }
 }
 
+/// Rule of the parent hash verification of RFC 9420, section 7.9.2, that a
+/// tree violates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParentHashRule {
+    /// The parent hash of a leaf updated by a commit doesn't match the parent
+    /// hash computed from the rest of its update path.
+    LeafMismatch,
+    /// A node matches the parent hash of its parent, but the unmerged leaves
+    /// of the parent don't match the resolution of the child on the side of
+    /// the node.
+    UnmergedLeavesMismatch,
+    /// A parent node is covered by more than one parent hash chain.
+    CoveredTwice,
+    /// A parent node isn't covered by any parent hash chain.
+    NotCovered,
+}
+
+/// Child of a parent node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildSide {
+    Left,
+    Right,
+}
+
+/// Violation of a parent hash rule.
+///
+/// Node indices refer to the array representation of the ratchet tree, where
+/// leaf `i` is node `2 * i`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParentHashViolation {
+    /// Rule that is violated.
+    pub rule: ParentHashRule,
+    /// Node whose parent hash is invalid, or parent node that isn't covered
+    /// when no node has a parent hash checked against it.
+    pub node_index: NodeIndex,
+    /// Parent node the parent hash of `node_index` was checked against and
+    /// the side of `node_index` under it, if any. For
+    /// [`ParentHashRule::NotCovered`] and [`ParentHashRule::CoveredTwice`],
+    /// this is the parent node that isn't covered or is covered twice.
+    pub parent: Option<(NodeIndex, ChildSide)>,
+    /// Parent hash stored in `node_index`, or empty if not applicable.
+    pub expected: Vec<u8>,
+    /// Parent hash computed from the tree, or empty if not applicable.
+    pub computed: Vec<u8>,
+}
+
+impl ParentHashViolation {
+    fn new(rule: ParentHashRule, node_index: NodeIndex) -> Self {
+        Self {
+            rule,
+            node_index,
+            parent: None,
+            expected: Vec::new(),
+            computed: Vec::new(),
+        }
+    }
+
+    fn with_parent(self, parent: NodeIndex, child: NodeIndex) -> Self {
+        let side = if child < parent {
+            ChildSide::Left
+        } else {
+            ChildSide::Right
+        };
+
+        Self {
+            parent: Some((parent, side)),
+            ..self
+        }
+    }
+
+    fn with_hashes(self, expected: &[u8], computed: &[u8]) -> Self {
+        Self {
+            expected: expected.to_vec(),
+            computed: computed.to_vec(),
+            ..self
+        }
+    }
+}
+
+impl Display for ParentHashViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at node {}", self.rule, self.node_index)?;
+
+        if let Some((parent, side)) = self.parent {
+            write!(f, ", {side:?} child of node {parent}")?;
+        }
+
+        if !self.expected.is_empty() || !self.computed.is_empty() {
+            f.write_str(", expected ")?;
+            write_truncated_hex(f, &self.expected)?;
+            f.write_str(", computed ")?;
+            write_truncated_hex(f, &self.computed)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_truncated_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    const MAX_BYTES: usize = 8;
+
+    bytes
+        .iter()
+        .take(MAX_BYTES)
+        .try_for_each(|byte| write!(f, "{byte:02x}"))?;
+
+    if bytes.len() > MAX_BYTES {
+        f.write_str("...")?;
+    }
+
+    Ok(())
+}
+
+/// Result of the verification of all the parent hashes of a ratchet tree.
+///
+/// Unlike the verification performed when joining a group, which stops at
+/// the first violation, the report lists all of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    violations: Vec<ParentHashViolation>,
+}
+
+impl ValidationReport {
+    /// Whether the tree satisfies all parent hash rules.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Violations found in the tree.
+    pub fn violations(&self) -> &[ParentHashViolation] {
+        &self.violations
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), MlsError> {
+        match self.violations.into_iter().next() {
+            Some(violation) => Err(MlsError::ParentHashMismatch(violation)),
+            None => Ok(()),
+        }
+    }
+}
+
 impl Node {
     fn get_parent_hash(&self) -> Option<ParentHash> {
         match self {
@@ -131,7 +270,7 @@ impl TreeKemPublic {
                 cipher_suite_provider,
                 &parent.public_key,
                 &hash,
-                &self.tree_hashes.current[node.copath as usize],
+                &self.tree_hashes.current[*node.copath as usize],
             )
             .await?;
 
@@ -164,7 +303,13 @@ impl TreeKemPublic {
             // in the local tree
             if let LeafNodeSource::Commit(parent_hash) = &leaf.leaf_node_source {
                 if !leaf_hash.matches(parent_hash) {
-                    return Err(MlsError::ParentHashMismatch);
+                    let violation = ParentHashViolation::new(
+                        ParentHashRule::LeafMismatch,
+                        index.to_node_index(),
+                    )
+                    .with_hashes(parent_hash, &leaf_hash);
+
+                    return Err(MlsError::ParentHashMismatch(violation));
                 }
             } else {
                 return Err(MlsError::InvalidLeafNodeSource);
@@ -182,30 +327,42 @@ impl TreeKemPublic {
         &self,
         cipher_suite_provider: &P,
     ) -> Result<(), MlsError> {
+        self.parent_hash_report(cipher_suite_provider)
+            .await?
+            .into_result()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn parent_hash_report<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+    ) -> Result<ValidationReport, MlsError> {
         let original_hashes = self.compute_original_hashes(cipher_suite_provider).await?;
 
-        let nodes_to_validate = self
+        let mut nodes_to_validate = self
             .nodes
             .non_empty_parents()
-            .map(|(node_index, _)| node_index);
+            .map(|(node_index, _)| node_index)
+            .collect::<BTreeSet<_>>();
 
-        #[cfg(feature = "std")]
-        let mut nodes_to_validate = nodes_to_validate.collect::<HashSet<_>>();
-        #[cfg(not(feature = "std"))]
-        let mut nodes_to_validate = nodes_to_validate.collect::<BTreeSet<_>>();
+        let mut violations = Vec::new();
+
+        // Parent hash mismatches of chains that end below each parent node, used
+        // to explain why a node isn't covered.
+        let mut mismatches = BTreeMap::new();
 
         let num_leaves = self.total_leaf_count();
 
         // For each leaf l, validate all non-blank nodes on the chain from l up the tree.
         for (leaf_index, _) in self.nodes.non_empty_leaves() {
-            let mut n = NodeIndex::from(leaf_index);
+            let mut n = leaf_index.to_node_index();
 
-            while let Some(mut ps) = n.parent_sibling(&num_leaves) {
+            'chain: while let Some(mut ps) = n.parent_sibling(num_leaves) {
                 // Find the first non-blank ancestor p of n and p's co-path child s.
                 while self.nodes.is_blank(ps.parent)? {
                     // If we reached the root, we're done with this chain.
-                    let Some(ps_parent) = ps.parent.parent_sibling(&num_leaves) else {
-                        return Ok(());
+                    let Some(ps_parent) = ps.parent.parent_sibling(num_leaves) else {
+                        break 'chain;
                     };
 
                     ps = ps_parent;
@@ -224,59 +381,88 @@ impl TreeKemPublic {
                     cipher_suite_provider,
                     &p_parent.public_key,
                     &p_parent.parent_hash,
-                    &original_hashes[ps.sibling as usize],
+                    &original_hashes[*ps.sibling as usize],
                 )
                 .await?;
 
-                if n_node.get_parent_hash() == Some(calculated) {
-                    // Check that "n is in the resolution of c, and the intersection of p's unmerged_leaves with the subtree
-                    // under c is equal to the resolution of c with n removed".
-                    let Some(cp) = ps.sibling.parent_sibling(&num_leaves) else {
-                        return Err(MlsError::ParentHashMismatch);
-                    };
+                let n_parent_hash = n_node.get_parent_hash();
 
-                    let c = cp.sibling;
-                    let c_resolution = self.nodes.get_resolution_index(c)?.into_iter();
-
-                    #[cfg(feature = "std")]
-                    let mut c_resolution = c_resolution.collect::<HashSet<_>>();
-                    #[cfg(not(feature = "std"))]
-                    let mut c_resolution = c_resolution.collect::<BTreeSet<_>>();
-
-                    let p_unmerged_in_c_subtree = self
-                        .unmerged_in_subtree(ps.parent, c)?
-                        .iter()
-                        .copied()
-                        .map(|x| *x * 2);
-
-                    #[cfg(feature = "std")]
-                    let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<HashSet<_>>();
-                    #[cfg(not(feature = "std"))]
-                    let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<BTreeSet<_>>();
-
-                    if c_resolution.remove(&n)
-                        && c_resolution == p_unmerged_in_c_subtree
-                        && nodes_to_validate.remove(&ps.parent)
-                    {
-                        // If n's parent_hash field matches and p has not been validated yet, mark p as validated and continue.
-                        n = ps.parent;
-                    } else {
-                        // If p is validated for the second time, the check fails ("all non-blank parent nodes are covered by exactly one such chain").
-                        return Err(MlsError::ParentHashMismatch);
-                    }
-                } else {
+                if n_parent_hash.as_ref() != Some(&calculated) {
                     // If n's parent_hash field doesn't match, we're done with this chain.
+                    mismatches.entry(ps.parent).or_insert_with(|| {
+                        ParentHashViolation::new(ParentHashRule::NotCovered, n)
+                            .with_parent(ps.parent, n)
+                            .with_hashes(
+                                n_parent_hash.as_deref().map_or(&[][..], Vec::as_slice),
+                                &calculated,
+                            )
+                    });
+
+                    break;
+                }
+
+                // Check that "n is in the resolution of c, and the intersection of p's unmerged_leaves with the subtree
+                // under c is equal to the resolution of c with n removed".
+                let Some(cp) = ps.sibling.parent_sibling(num_leaves) else {
+                    violations.push(
+                        ParentHashViolation::new(ParentHashRule::UnmergedLeavesMismatch, n)
+                            .with_parent(ps.parent, n),
+                    );
+
+                    break;
+                };
+
+                let c = cp.sibling;
+                let c_resolution = self.nodes.get_resolution_index(c)?.into_iter();
+
+                #[cfg(feature = "std")]
+                let mut c_resolution = c_resolution.collect::<HashSet<_>>();
+                #[cfg(not(feature = "std"))]
+                let mut c_resolution = c_resolution.collect::<BTreeSet<_>>();
+
+                let p_unmerged_in_c_subtree = self
+                    .unmerged_in_subtree(ps.parent, c)?
+                    .iter()
+                    .copied()
+                    .map(LeafIndex::to_node_index);
+
+                #[cfg(feature = "std")]
+                let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<HashSet<_>>();
+                #[cfg(not(feature = "std"))]
+                let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<BTreeSet<_>>();
+
+                if !c_resolution.remove(&n) || c_resolution != p_unmerged_in_c_subtree {
+                    violations.push(
+                        ParentHashViolation::new(ParentHashRule::UnmergedLeavesMismatch, n)
+                            .with_parent(ps.parent, n),
+                    );
+
                     break;
                 }
+
+                if !nodes_to_validate.remove(&ps.parent) {
+                    // If p is validated for the second time, the check fails ("all non-blank parent nodes are covered by exactly one such chain").
+                    violations.push(
+                        ParentHashViolation::new(ParentHashRule::CoveredTwice, n)
+                            .with_parent(ps.parent, n),
+                    );
+
+                    break;
+                }
+
+                // If n's parent_hash field matches and p has not been validated yet, mark p as validated and continue.
+                n = ps.parent;
             }
         }
 
         // The check passes iff all non-blank nodes are validated.
-        if nodes_to_validate.is_empty() {
-            Ok(())
-        } else {
-            Err(MlsError::ParentHashMismatch)
-        }
+        violations.extend(nodes_to_validate.into_iter().map(|node_index| {
+            mismatches
+                .remove(&node_index)
+                .unwrap_or_else(|| ParentHashViolation::new(ParentHashRule::NotCovered, node_index))
+        }));
+
+        Ok(ValidationReport { violations })
     }
 }
 
@@ -360,6 +546,7 @@ pub(crate) mod test_utils {
 
 #[cfg(test)]
 mod tests {
+    use super::test_utils::{get_test_tree_fig_12, test_parent_node};
     use super::*;
     use crate::client::test_utils::TEST_CIPHER_SUITE;
     use crate::crypto::test_utils::test_cipher_suite_provider;
@@ -367,8 +554,39 @@ mod tests {
     use crate::tree_kem::leaf_node::LeafNodeSource;
     use crate::tree_kem::test_utils::TreeWithSigners;
     use crate::tree_kem::MlsError;
+    use alloc::vec;
     use assert_matches::assert_matches;
 
+    // The input is hashed in its RFC 9420 encoding. Changing this value
+    // breaks interoperability and existing groups.
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn parent_hash_input_is_pinned() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let public_key = HpkePublicKey::from(vec![1, 2]);
+        let parent_hash = ParentHash::from(vec![3]);
+
+        let input = ParentHashInput {
+            public_key: &public_key,
+            parent_hash: &parent_hash,
+            original_sibling_tree_hash: &[4, 5, 6],
+        };
+
+        assert_eq!(
+            hex::encode(input.mls_encode_to_vec().unwrap()),
+            "020102010303040506"
+        );
+
+        let hash = ParentHash::new(&cs, &public_key, &parent_hash, &[4, 5, 6])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hex::encode(&*hash),
+            "03da43fa0d6126c0a3a87d8e5a041a74735b6caa376622348c5b3d005f5f09ce"
+        );
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_missing_parent_hash() {
         let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
@@ -412,7 +630,15 @@ mod tests {
             )
             .await;
 
-        assert_matches!(invalid_parent_hash_res, Err(MlsError::ParentHashMismatch));
+        assert_matches!(
+            invalid_parent_hash_res,
+            Err(MlsError::ParentHashMismatch(ParentHashViolation {
+                rule: ParentHashRule::LeafMismatch,
+                node_index: NodeIndex(0),
+                expected,
+                ..
+            })) if expected == hex!("f00d")
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -426,6 +652,72 @@ mod tests {
             .validate_parent_hashes(&test_cipher_suite_provider(TEST_CIPHER_SUITE))
             .await;
 
-        assert_matches!(res, Err(MlsError::ParentHashMismatch));
+        assert_matches!(res, Err(MlsError::ParentHashMismatch(_)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn all_parent_hash_violations_are_reported() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut test_tree = TreeWithSigners::make_full_tree(8, &cs).await.tree;
+
+        let report = test_tree.parent_hash_report(&cs).await.unwrap();
+        assert!(report.is_valid());
+
+        // Replacing node 5 breaks the chain of leaf D, the only one covering
+        // nodes 5 and 3.
+        test_tree.nodes[5] = Some(test_parent_node(TEST_CIPHER_SUITE, vec![]).await);
+
+        let report = test_tree.parent_hash_report(&cs).await.unwrap();
+
+        let uncovered = report
+            .violations()
+            .iter()
+            .inspect(|violation| assert_eq!(violation.rule, ParentHashRule::NotCovered))
+            .map(|violation| violation.parent.map_or(violation.node_index, |(p, _)| p))
+            .collect::<Vec<_>>();
+
+        assert_eq!(uncovered, [NodeIndex(3), NodeIndex(5)]);
+
+        // Node 3 is reported through node 1, whose parent hash doesn't match it.
+        let node_3 = &report.violations()[0];
+        assert_eq!(node_3.node_index, NodeIndex(1));
+        assert_eq!(node_3.parent, Some((NodeIndex(3), ChildSide::Left)));
+        assert_ne!(node_3.expected, node_3.computed);
+
+        // Node 5 is reported through leaf C, whose parent hash doesn't match it.
+        let node_5 = &report.violations()[1];
+        assert_eq!(node_5.node_index, NodeIndex(4));
+        assert_eq!(node_5.parent, Some((NodeIndex(5), ChildSide::Left)));
+        assert_ne!(node_5.expected, node_5.computed);
+
+        let res = test_tree.validate_parent_hashes(&cs).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ParentHashMismatch(violation)) if violation == *node_3
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chain_ending_at_blank_root_does_not_skip_validation() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut test_tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+
+        // The chain of the first leaf reaches the blank root.
+        test_tree.nodes[1] = None;
+        test_tree.nodes[3] = None;
+        test_tree.nodes[7] = None;
+
+        test_tree.nodes[5] = Some(test_parent_node(TEST_CIPHER_SUITE, vec![]).await);
+
+        let report = test_tree.parent_hash_report(&cs).await.unwrap();
+
+        assert_eq!(report.violations().len(), 1);
+        assert_eq!(report.violations()[0].node_index, NodeIndex(4));
+        assert_eq!(
+            report.violations()[0].parent,
+            Some((NodeIndex(5), ChildSide::Left))
+        );
+        assert_eq!(report.violations()[0].rule, ParentHashRule::NotCovered);
     }
 }
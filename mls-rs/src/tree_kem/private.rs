@@ -49,7 +49,11 @@ impl TreeKemPrivate {
         // Identify the lowest common
         // ancestor of the leaves at index and at GroupInfo.signer_index. Set the private key
         // for this node to the private key derived from the path_secret.
-        let lca_index = leaf_lca_level(self.self_index.into(), signer_index.into()) as usize - 2;
+        let lca_index = leaf_lca_level(
+            *self.self_index.to_node_index(),
+            *signer_index.to_node_index(),
+        ) as usize
+            - 2;
 
         // For each parent of the common ancestor, up to the root of the tree, derive a new
         // path secret and set the private key for the node to the private key derived from the
@@ -123,7 +127,7 @@ mod tests {
                 default_properties, get_basic_test_node, get_basic_test_node_sig_key,
             },
             math::TreeIndex,
-            node::LeafIndex,
+            node::{LeafIndex, NodeIndex},
         },
     };
 
@@ -259,7 +263,7 @@ mod tests {
         // Sabotage the public tree
         public_tree
             .nodes
-            .borrow_as_parent_mut(public_tree.total_leaf_count().root())
+            .borrow_as_parent_mut(NodeIndex::root(public_tree.total_leaf_count()))
             .unwrap()
             .public_key = random_bytes(32).into();
 
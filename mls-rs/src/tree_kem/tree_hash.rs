@@ -3,13 +3,14 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use super::leaf_node::LeafNode;
-use super::node::{LeafIndex, NodeVec};
+use super::node::{LeafIndex, NodeIndex, NodeVec};
 use super::tree_math::BfsIterTopDown;
 use crate::client::MlsError;
 use crate::crypto::CipherSuiteProvider;
 use crate::tree_kem::math as tree_math;
 use crate::tree_kem::node::Parent;
 use crate::tree_kem::TreeKemPublic;
+#[cfg(any(mls_build_async, not(feature = "rayon")))]
 use alloc::collections::VecDeque;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -120,6 +121,17 @@ impl TreeKemPublic {
         Ok(())
     }
 
+    // Compute all hashes again, discarding the ones computed so far, e.g. to
+    // check that a stored tree matches its stored hashes.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn recompute_tree_hash<P: CipherSuiteProvider>(
+        &mut self,
+        cipher_suite_provider: &P,
+    ) -> Result<Vec<u8>, MlsError> {
+        self.tree_hashes.current.clear();
+        self.tree_hash(cipher_suite_provider).await
+    }
+
     // Initialize all hashes after creating / importing a tree.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn initialize_hashes<P>(&mut self, cipher_suite_provider: &P) -> Result<(), MlsError>
@@ -145,8 +157,8 @@ impl TreeKemPublic {
 
     pub(crate) fn unmerged_in_subtree(
         &self,
-        node_unmerged: u32,
-        subtree_root: u32,
+        node_unmerged: NodeIndex,
+        subtree_root: NodeIndex,
     ) -> Result<&[LeafIndex], MlsError> {
         let unmerged = &self.nodes.borrow_as_parent(node_unmerged)?.unmerged_leaves;
         let (left, right) = tree_math::subtree(subtree_root);
@@ -161,7 +173,11 @@ impl TreeKemPublic {
         Ok(&unmerged[start..end])
     }
 
-    fn different_unmerged(&self, ancestor: u32, descendant: u32) -> Result<bool, MlsError> {
+    fn different_unmerged(
+        &self,
+        ancestor: NodeIndex,
+        descendant: NodeIndex,
+    ) -> Result<bool, MlsError> {
         Ok(!self.nodes.is_blank(ancestor)?
             && !self.nodes.is_blank(descendant)?
             && self.unmerged_in_subtree(ancestor, descendant)?
@@ -174,18 +190,18 @@ impl TreeKemPublic {
         cipher_suite: &P,
     ) -> Result<Vec<TreeHash>, MlsError> {
         let num_leaves = self.nodes.total_leaf_count() as usize;
-        let root = (num_leaves as u32).root();
+        let root = NodeIndex::root(num_leaves as u32);
 
         // The value `filtered_sets[n]` is a list of all ancestors `a` of `n` s.t. we have to compute
         // the tree hash of `n` with the unmerged leaves of `a` filtered out.
         let mut filtered_sets = vec![vec![]; num_leaves * 2 - 1];
-        filtered_sets[root as usize].push(root);
+        filtered_sets[*root as usize].push(root);
         let mut tree_hashes = vec![vec![]; num_leaves * 2 - 1];
 
         let bfs_iter = BfsIterTopDown::new(num_leaves).skip(1);
 
         for n in bfs_iter {
-            let Some(ps) = (n as u32).parent_sibling(&(num_leaves as u32)) else {
+            let Some(ps) = NodeIndex::new(n as u32).parent_sibling(num_leaves as u32) else {
                 break;
             };
 
@@ -194,18 +210,18 @@ impl TreeKemPublic {
             // Clippy's suggestion `filtered_sets[n].clone_from(&filtered_sets[p as usize])` is wrong and does not compile
             #[allow(clippy::assigning_clones)]
             {
-                filtered_sets[n] = filtered_sets[p as usize].clone();
+                filtered_sets[n] = filtered_sets[*p as usize].clone();
             }
 
-            if self.different_unmerged(*filtered_sets[p as usize].last().unwrap(), p)? {
+            if self.different_unmerged(*filtered_sets[*p as usize].last().unwrap(), p)? {
                 filtered_sets[n].push(p);
 
                 // Compute tree hash of `n` without unmerged leaves of `p`. This also computes the tree hash
                 // for any descendants of `n` added to `filtered_sets` later via `clone`.
-                let (start_leaf, end_leaf) = tree_math::subtree(n as u32);
+                let (start_leaf, end_leaf) = tree_math::subtree(NodeIndex::new(n as u32));
 
                 tree_hash(
-                    &mut tree_hashes[p as usize],
+                    &mut tree_hashes[*p as usize],
                     &self.nodes,
                     Some((*start_leaf..*end_leaf).map(LeafIndex).collect_vec()),
                     &self.nodes.borrow_as_parent(p)?.unmerged_leaves,
@@ -253,7 +269,7 @@ impl TreeKemPublic {
                     self.tree_hashes.current[i].clone()
                 }
             } else {
-                tree_hashes[*a as usize][i].clone()
+                tree_hashes[**a as usize][i].clone()
             }
         }
 
@@ -261,6 +277,7 @@ impl TreeKemPublic {
     }
 }
 
+#[cfg(any(mls_build_async, not(feature = "rayon")))]
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 async fn tree_hash<P: CipherSuiteProvider>(
     hashes: &mut Vec<TreeHash>,
@@ -285,26 +302,31 @@ async fn tree_hash<P: CipherSuiteProvider>(
 
         hashes[2 * **l as usize] = TreeHash(hash_for_leaf(*l, leaf, cipher_suite_provider).await?);
 
-        if let Some(ps) = (2 * **l).parent_sibling(&num_leaves) {
+        if let Some(ps) = l.to_node_index().parent_sibling(num_leaves) {
             node_queue.push_back(ps.parent);
         }
     }
 
     while let Some(n) = node_queue.pop_front() {
+        let (left, right) = n
+            .left()
+            .zip(n.right())
+            .ok_or(MlsError::LeafNodeNoChildren)?;
+
         let hash = TreeHash(
             hash_for_parent(
                 nodes.borrow_as_parent(n).ok(),
                 cipher_suite_provider,
                 filtered_leaves,
-                &hashes[n.left_unchecked() as usize],
-                &hashes[n.right_unchecked() as usize],
+                &hashes[*left as usize],
+                &hashes[*right as usize],
             )
             .await?,
         );
 
-        hashes[n as usize] = hash;
+        hashes[*n as usize] = hash;
 
-        if let Some(ps) = n.parent_sibling(&num_leaves) {
+        if let Some(ps) = n.parent_sibling(num_leaves) {
             node_queue.push_back(ps.parent);
         }
     }
@@ -312,6 +334,126 @@ async fn tree_hash<P: CipherSuiteProvider>(
     Ok(())
 }
 
+// Subtrees with fewer leaves are hashed on the current thread, as spawning a
+// task costs more than hashing them.
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+const PARALLEL_MIN_SUBTREE_LEAVES: usize = 64;
+
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+fn tree_hash<P: CipherSuiteProvider>(
+    hashes: &mut Vec<TreeHash>,
+    nodes: &NodeVec,
+    leaves_to_update: Option<Vec<LeafIndex>>,
+    filtered_leaves: &[LeafIndex],
+    num_leaves: u32,
+    cipher_suite_provider: &P,
+) -> Result<(), MlsError> {
+    let mut leaves_to_update =
+        leaves_to_update.unwrap_or_else(|| (0..num_leaves).map(LeafIndex).collect::<Vec<_>>());
+
+    leaves_to_update.retain(|l| **l < num_leaves);
+    leaves_to_update.sort_unstable();
+    leaves_to_update.dedup();
+
+    // Resize the array in case the tree was extended or truncated
+    hashes.resize(num_leaves as usize * 2 - 1, TreeHash::default());
+
+    hash_subtree(
+        hashes,
+        NodeIndex::root(num_leaves),
+        &leaves_to_update,
+        nodes,
+        filtered_leaves,
+        cipher_suite_provider,
+    )
+}
+
+// Recompute the hashes on the direct paths of the sorted `leaves` in the
+// subtree rooted at `node`, whose hashes are `hashes`. The subtree of a node
+// is a contiguous range of node indices centered on the node, so the hashes
+// of the left and right subtrees can be computed in parallel. Hashes outside
+// the direct paths are reused from the cache.
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+fn hash_subtree<P: CipherSuiteProvider>(
+    hashes: &mut [TreeHash],
+    node: NodeIndex,
+    leaves: &[LeafIndex],
+    nodes: &NodeVec,
+    filtered_leaves: &[LeafIndex],
+    cipher_suite_provider: &P,
+) -> Result<(), MlsError> {
+    if leaves.is_empty() {
+        return Ok(());
+    }
+
+    let subtree_leaves = (hashes.len() + 1) / 2;
+    let (left_hashes, rest) = hashes.split_at_mut(hashes.len() / 2);
+    let (hash, right_hashes) = rest.split_at_mut(1);
+
+    if node.is_leaf() {
+        let leaf_index = LeafIndex(*node / 2);
+
+        let leaf = (!filtered_leaves.contains(&leaf_index))
+            .then_some(nodes.borrow_as_leaf(leaf_index).ok())
+            .flatten();
+
+        hash[0] = TreeHash(hash_for_leaf(leaf_index, leaf, cipher_suite_provider)?);
+
+        return Ok(());
+    }
+
+    let (left, right) = node
+        .left()
+        .zip(node.right())
+        .ok_or(MlsError::LeafNodeNoChildren)?;
+    let (left_leaves, right_leaves) = leaves.split_at(leaves.partition_point(|l| **l <= *node / 2));
+
+    let mut hash_left = || {
+        hash_subtree(
+            left_hashes,
+            left,
+            left_leaves,
+            nodes,
+            filtered_leaves,
+            cipher_suite_provider,
+        )
+    };
+
+    let mut hash_right = || {
+        hash_subtree(
+            right_hashes,
+            right,
+            right_leaves,
+            nodes,
+            filtered_leaves,
+            cipher_suite_provider,
+        )
+    };
+
+    let parallel = !left_leaves.is_empty()
+        && !right_leaves.is_empty()
+        && subtree_leaves >= PARALLEL_MIN_SUBTREE_LEAVES;
+
+    let (left, right) = if parallel {
+        rayon::join(hash_left, hash_right)
+    } else {
+        (hash_left(), hash_right())
+    };
+
+    left?;
+    right?;
+
+    hash[0] = TreeHash(hash_for_parent(
+        nodes.borrow_as_parent(node).ok(),
+        cipher_suite_provider,
+        filtered_leaves,
+        &left_hashes[left_hashes.len() / 2],
+        &right_hashes[right_hashes.len() / 2],
+    )?);
+
+    Ok(())
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 async fn hash_for_leaf<P: CipherSuiteProvider>(
     leaf_index: LeafIndex,
@@ -365,7 +507,11 @@ mod tests {
         cipher_suite::CipherSuite,
         crypto::test_utils::{test_cipher_suite_provider, try_test_cipher_suite_provider},
         identity::basic::BasicIdentityProvider,
-        tree_kem::{node::NodeVec, parent_hash::test_utils::get_test_tree_fig_12},
+        tree_kem::{
+            leaf_node::test_utils::get_basic_test_node,
+            node::{Node, NodeVec},
+            parent_hash::test_utils::get_test_tree_fig_12,
+        },
     };
 
     use super::*;
@@ -434,4 +580,133 @@ mod tests {
             assert_eq!(calculated_hash, one_case.tree_hash);
         }
     }
+
+    // The inputs are hashed in their RFC 9420 encoding. Changing these values
+    // breaks interoperability and existing groups.
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tree_hash_inputs_are_pinned() {
+        let cs = test_cipher_suite_provider(CipherSuite::P256_AES128);
+
+        let leaf = TreeHashInput::Leaf(LeafNodeHashInput {
+            leaf_index: LeafIndex(3),
+            leaf_node: None,
+        });
+
+        assert_eq!(
+            hex::encode(leaf.mls_encode_to_vec().unwrap()),
+            "010000000300"
+        );
+
+        let leaf_hash = hash_for_leaf(LeafIndex(3), None, &cs).await.unwrap();
+
+        assert_eq!(
+            hex::encode(leaf_hash),
+            "caf009ad02a57a48feb8d64d055509e9a81edba8d325e442cfe75e006a520006"
+        );
+
+        let parent = Parent {
+            public_key: vec![1, 2].into(),
+            parent_hash: vec![3].into(),
+            unmerged_leaves: vec![LeafIndex(1)],
+        };
+
+        let input = TreeHashInput::Parent(ParentNodeTreeHashInput {
+            parent_node: Some(&parent),
+            left_hash: &[0xaa],
+            right_hash: &[0xbb],
+        });
+
+        assert_eq!(
+            hex::encode(input.mls_encode_to_vec().unwrap()),
+            "02010201020103040000000101aa01bb"
+        );
+
+        let hash = hash_for_parent(Some(&parent), &cs, &[], &[0xaa], &[0xbb])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hex::encode(hash),
+            "d4d9bf9ed66070dfc9b9be2734e4bb4dea182d131ce132989ec0546535b71f16"
+        );
+
+        // Filtered leaves are removed from the unmerged leaves.
+        let hash = hash_for_parent(Some(&parent), &cs, &[LeafIndex(1)], &[0xaa], &[0xbb])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hex::encode(hash),
+            "70a5a7fef04e4c2af64d8fc9e30005632e01c90c17f10705234a434d279902a8"
+        );
+    }
+
+    // Hash all nodes level by level, without the cache.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn reference_tree_hash<P: CipherSuiteProvider>(tree: &TreeKemPublic, cs: &P) -> Vec<u8> {
+        let num_leaves = tree.total_leaf_count();
+        let mut hashes = vec![vec![]; num_leaves as usize * 2 - 1];
+
+        for l in 0..num_leaves {
+            let leaf = tree.nodes.borrow_as_leaf(LeafIndex(l)).ok();
+            hashes[2 * l as usize] = hash_for_leaf(LeafIndex(l), leaf, cs).await.unwrap();
+        }
+
+        for level in 1..=num_leaves.trailing_zeros() {
+            for n in (0..num_leaves * 2 - 1).filter(|n| n.trailing_ones() == level) {
+                hashes[n as usize] = hash_for_parent(
+                    tree.nodes.borrow_as_parent(NodeIndex(n)).ok(),
+                    cs,
+                    &[],
+                    &hashes[n.left().unwrap() as usize],
+                    &hashes[n.right().unwrap() as usize],
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        hashes[num_leaves.root() as usize].clone()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn large_tree_hash_matches_reference() {
+        let cs = test_cipher_suite_provider(CipherSuite::P256_AES128);
+        let leaf = get_basic_test_node(CipherSuite::P256_AES128, "leaf").await;
+
+        let parent = Parent {
+            public_key: vec![1, 2].into(),
+            parent_hash: vec![3].into(),
+            unmerged_leaves: vec![],
+        };
+
+        // 300 leaves with some blanks, so that subtrees are large enough to be
+        // hashed in parallel.
+        let nodes = (0..599)
+            .map(|i| match i % 6 {
+                0 | 2 => Some(Node::Leaf(leaf.clone())),
+                1 => Some(Node::Parent(parent.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut tree = TreeKemPublic::new();
+        tree.nodes = NodeVec::from(nodes);
+
+        let tree_hash = tree.tree_hash(&cs).await.unwrap();
+        let reference = reference_tree_hash(&tree, &cs).await;
+        assert_eq!(tree_hash, reference);
+
+        // Incremental updates on both sides of the root reuse the cached hashes.
+        tree.nodes[560] = None;
+        tree.nodes[400] = Some(Node::Leaf(leaf.clone()));
+
+        tree.update_hashes(&[LeafIndex(280), LeafIndex(200)], &cs)
+            .await
+            .unwrap();
+
+        let tree_hash = tree.tree_hash(&cs).await.unwrap();
+        let reference = reference_tree_hash(&tree, &cs).await;
+        assert_eq!(tree_hash, reference);
+    }
 }
@@ -46,7 +46,7 @@ impl Debug for Identifier {
 #[cfg(all(feature = "tree_index", feature = "std"))]
 #[derive(Clone, Debug, Default, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 pub struct TreeIndex {
-    credential_signature_key: HashMap<SignaturePublicKey, LeafIndex>,
+    credential_signature_key: SignatureKeyIndex,
     hpke_key: HashMap<HpkePublicKey, LeafIndex>,
     identities: HashMap<Identifier, LeafIndex>,
     credential_type_counters: HashMap<CredentialType, TypeCounter>,
@@ -57,7 +57,7 @@ pub struct TreeIndex {
 #[cfg(all(feature = "tree_index", not(feature = "std")))]
 #[derive(Clone, Debug, Default, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 pub struct TreeIndex {
-    credential_signature_key: BTreeMap<SignaturePublicKey, LeafIndex>,
+    credential_signature_key: SignatureKeyIndex,
     hpke_key: BTreeMap<HpkePublicKey, LeafIndex>,
     identities: BTreeMap<Identifier, LeafIndex>,
     credential_type_counters: BTreeMap<CredentialType, TypeCounter>,
@@ -65,10 +65,91 @@ pub struct TreeIndex {
     proposal_type_counter: BTreeMap<ProposalType, u32>,
 }
 
+/// Leaves using each signature key, in the order they were indexed.
+///
+/// Only the first leaf using each key is encoded, with the layout of a map
+/// from keys to leaves, and the other leaves sharing it are indexed again by
+/// [`TreeIndex::index_shared_signature_keys`] when the tree is imported.
+#[cfg(feature = "tree_index")]
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SignatureKeyIndex {
+    #[cfg(feature = "std")]
+    leaves: HashMap<SignaturePublicKey, Vec<LeafIndex>>,
+    #[cfg(not(feature = "std"))]
+    leaves: BTreeMap<SignaturePublicKey, Vec<LeafIndex>>,
+}
+
+#[cfg(feature = "tree_index")]
+impl SignatureKeyIndex {
+    /// First leaf other than `leaf` using `key`.
+    fn owner(&self, key: &SignaturePublicKey, leaf: LeafIndex) -> Option<LeafIndex> {
+        self.leaves.get(key)?.iter().copied().find(|i| *i != leaf)
+    }
+
+    fn insert(&mut self, key: SignaturePublicKey, leaf: LeafIndex) {
+        let leaves = self.leaves.entry(key).or_default();
+
+        if !leaves.contains(&leaf) {
+            leaves.push(leaf);
+        }
+    }
+
+    fn remove(&mut self, key: &SignaturePublicKey, leaf: LeafIndex) {
+        let Some(leaves) = self.leaves.get_mut(key) else {
+            return;
+        };
+
+        leaves.retain(|i| *i != leaf);
+
+        if leaves.is_empty() {
+            self.leaves.remove(key);
+        }
+    }
+
+    fn owners(&self) -> impl Iterator<Item = (&SignaturePublicKey, &LeafIndex)> + Clone {
+        self.leaves
+            .iter()
+            .filter_map(|(key, leaves)| Some((key, leaves.first()?)))
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsSize for SignatureKeyIndex {
+    fn mls_encoded_len(&self) -> usize {
+        mls_rs_codec::iter::mls_encoded_len(self.owners())
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsEncode for SignatureKeyIndex {
+    fn mls_encode(&self, writer: &mut Vec<u8>) -> Result<(), mls_rs_codec::Error> {
+        mls_rs_codec::iter::mls_encode(self.owners(), writer)
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsDecode for SignatureKeyIndex {
+    fn mls_decode(reader: &mut &[u8]) -> Result<Self, mls_rs_codec::Error> {
+        #[cfg(feature = "std")]
+        let owners = HashMap::<SignaturePublicKey, LeafIndex>::mls_decode(reader)?;
+
+        #[cfg(not(feature = "std"))]
+        let owners = BTreeMap::<SignaturePublicKey, LeafIndex>::mls_decode(reader)?;
+
+        Ok(Self {
+            leaves: owners
+                .into_iter()
+                .map(|(key, leaf)| (key, vec![leaf]))
+                .collect(),
+        })
+    }
+}
+
 #[cfg(feature = "tree_index")]
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 pub(super) async fn index_insert<I: IdentityProvider>(
     tree_index: &mut TreeIndex,
+    nodes: &NodeVec,
     new_leaf: &LeafNode,
     new_leaf_idx: LeafIndex,
     id_provider: &I,
@@ -79,7 +160,25 @@ pub(super) async fn index_insert<I: IdentityProvider>(
         .await
         .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
 
-    tree_index.insert(new_leaf_idx, new_leaf, new_id)
+    let signature_key_owner = tree_index
+        .credential_signature_key
+        .owner(&new_leaf.signing_identity.signature_key, new_leaf_idx);
+
+    if let Some(i) = signature_key_owner {
+        let leaf = nodes
+            .borrow_as_leaf(i)
+            .map_err(|_| MlsError::DuplicateSignatureKey(*i, *new_leaf_idx))?;
+
+        check_shared_signature_key((i, leaf), (new_leaf_idx, new_leaf), id_provider, extensions)
+            .await?;
+    }
+
+    tree_index.insert(
+        new_leaf_idx,
+        new_leaf,
+        new_id,
+        signature_key_owner.is_some(),
+    )
 }
 
 #[cfg(not(feature = "tree_index"))]
@@ -97,11 +196,17 @@ pub(super) async fn index_insert<I: IdentityProvider>(
         .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
 
     for (i, leaf) in nodes.non_empty_leaves().filter(|(i, _)| i != &new_leaf_idx) {
-        (new_leaf.public_key != leaf.public_key)
-            .then_some(())
-            .ok_or(MlsError::DuplicateLeafData(*i))?;
+        if new_leaf.signing_identity.signature_key == leaf.signing_identity.signature_key {
+            check_shared_signature_key(
+                (i, leaf),
+                (new_leaf_idx, new_leaf),
+                id_provider,
+                extensions,
+            )
+            .await?;
+        }
 
-        (new_leaf.signing_identity.signature_key != leaf.signing_identity.signature_key)
+        (new_leaf.public_key != leaf.public_key)
             .then_some(())
             .ok_or(MlsError::DuplicateLeafData(*i))?;
 
@@ -135,6 +240,27 @@ pub(super) async fn index_insert<I: IdentityProvider>(
     Ok(())
 }
 
+/// Fail with [`MlsError::DuplicateSignatureKey`] unless the identity provider
+/// allows `new` to use the signature key of `existing`.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn check_shared_signature_key<I: IdentityProvider>(
+    (existing_idx, existing): (LeafIndex, &LeafNode),
+    (new_idx, new): (LeafIndex, &LeafNode),
+    id_provider: &I,
+    extensions: &ExtensionList,
+) -> Result<(), MlsError> {
+    id_provider
+        .allow_shared_signature_key(
+            &existing.signing_identity,
+            &new.signing_identity,
+            extensions,
+        )
+        .await
+        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?
+        .then_some(())
+        .ok_or(MlsError::DuplicateSignatureKey(*existing_idx, *new_idx))
+}
+
 #[cfg(feature = "tree_index")]
 impl TreeIndex {
     pub fn new() -> Self {
@@ -150,14 +276,16 @@ impl TreeIndex {
         index: LeafIndex,
         leaf_node: &LeafNode,
         identity: Vec<u8>,
+        shared_signature_key: bool,
     ) -> Result<(), MlsError> {
-        let old_leaf_count = self.credential_signature_key.len();
+        let old_leaf_count = self.identities.len();
 
-        let pub_key = leaf_node.signing_identity.signature_key.clone();
-        let credential_entry = self.credential_signature_key.entry(pub_key);
+        let pub_key = &leaf_node.signing_identity.signature_key;
 
-        if let Entry::Occupied(entry) = credential_entry {
-            return Err(MlsError::DuplicateLeafData(**entry.get()));
+        if let Some(owner) = self.credential_signature_key.owner(pub_key, index) {
+            if !shared_signature_key {
+                return Err(MlsError::DuplicateSignatureKey(*owner, *index));
+            }
         }
 
         let hpke_entry = self.hpke_key.entry(leaf_node.public_key.clone());
@@ -227,9 +355,10 @@ impl TreeIndex {
         }
 
         identity_entry.or_insert(index);
-        credential_entry.or_insert(index);
         hpke_entry.or_insert(index);
 
+        self.credential_signature_key.insert(pub_key.clone(), index);
+
         Ok(())
     }
 
@@ -237,14 +366,26 @@ impl TreeIndex {
         self.identities.get(&Identifier(identity.to_vec())).copied()
     }
 
-    pub fn remove(&mut self, leaf_node: &LeafNode, identity: &[u8]) {
+    /// Index the leaves of `nodes` sharing a signature key with a leaf that
+    /// used it first, which are not encoded with the index.
+    pub(crate) fn index_shared_signature_keys(&mut self, nodes: &NodeVec) {
+        for (i, leaf) in nodes.non_empty_leaves() {
+            self.credential_signature_key
+                .insert(leaf.signing_identity.signature_key.clone(), i);
+        }
+    }
+
+    /// Remove `leaf_node`, at `index` in the tree, from the index.
+    pub fn remove(&mut self, index: LeafIndex, leaf_node: &LeafNode, identity: &[u8]) {
         let existed = self
             .identities
             .remove(&Identifier(identity.to_vec()))
             .is_some();
 
+        // Another leaf sharing the signature key becomes its owner, so that
+        // leaves added later are still checked against it.
         self.credential_signature_key
-            .remove(&leaf_node.signing_identity.signature_key);
+            .remove(&leaf_node.signing_identity.signature_key, index);
 
         self.hpke_key.remove(&leaf_node.public_key);
 
@@ -302,7 +443,7 @@ impl TreeIndex {
 
     #[cfg(test)]
     pub fn len(&self) -> usize {
-        self.credential_signature_key.len()
+        self.credential_signature_key.leaves.len()
     }
 }
 
@@ -354,6 +495,7 @@ mod tests {
                     d.index,
                     &d.leaf_node,
                     get_test_client_identity(&d.leaf_node),
+                    false,
                 )
                 .unwrap()
         });
@@ -365,15 +507,15 @@ mod tests {
     async fn test_insert() {
         let (test_data, test_index) = test_setup().await;
 
-        assert_eq!(test_index.credential_signature_key.len(), test_data.len());
+        assert_eq!(test_index.len(), test_data.len());
         assert_eq!(test_index.hpke_key.len(), test_data.len());
 
         test_data.into_iter().enumerate().for_each(|(i, d)| {
             let pub_key = d.leaf_node.signing_identity.signature_key;
 
             assert_eq!(
-                test_index.credential_signature_key.get(&pub_key),
-                Some(&LeafIndex(i as u32))
+                test_index.credential_signature_key.leaves.get(&pub_key),
+                Some(&vec![LeafIndex(i as u32)])
             );
 
             assert_eq!(
@@ -393,13 +535,13 @@ mod tests {
         new_key_package.signing_identity = test_data[1].leaf_node.signing_identity.clone();
 
         let res = test_index.insert(
-            test_data[1].index,
+            LeafIndex(10),
             &new_key_package,
             get_test_client_identity(&new_key_package),
+            false,
         );
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(index))
-                        if index == *test_data[1].index);
+        assert_matches!(res, Err(MlsError::DuplicateSignatureKey(1, 10)));
 
         assert_eq!(before_error, test_index);
     }
@@ -417,6 +559,7 @@ mod tests {
             test_data[1].index,
             &new_leaf_node,
             get_test_client_identity(&new_leaf_node),
+            false,
         );
 
         assert_matches!(res, Err(MlsError::DuplicateLeafData(index))
@@ -430,20 +573,19 @@ mod tests {
         let (test_data, mut test_index) = test_setup().await;
 
         test_index.remove(
+            test_data[1].index,
             &test_data[1].leaf_node,
             &get_test_client_identity(&test_data[1].leaf_node),
         );
 
-        assert_eq!(
-            test_index.credential_signature_key.len(),
-            test_data.len() - 1
-        );
+        assert_eq!(test_index.len(), test_data.len() - 1);
 
         assert_eq!(test_index.hpke_key.len(), test_data.len() - 1);
 
         assert_eq!(
             test_index
                 .credential_signature_key
+                .leaves
                 .get(&test_data[1].leaf_node.signing_identity.signature_key),
             None
         );
@@ -454,6 +596,31 @@ mod tests {
         );
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn signature_keys_are_encoded_with_their_first_leaf() {
+        let (test_data, mut test_index) = test_setup().await;
+
+        let signature_key = &test_data[1].leaf_node.signing_identity.signature_key;
+
+        test_index
+            .credential_signature_key
+            .insert(signature_key.clone(), LeafIndex(10));
+
+        let encoded = test_index
+            .credential_signature_key
+            .mls_encode_to_vec()
+            .unwrap();
+
+        #[cfg(feature = "std")]
+        let owners = HashMap::<SignaturePublicKey, LeafIndex>::mls_decode(&mut &*encoded).unwrap();
+
+        #[cfg(not(feature = "std"))]
+        let owners = BTreeMap::<SignaturePublicKey, LeafIndex>::mls_decode(&mut &*encoded).unwrap();
+
+        assert_eq!(owners.len(), test_data.len());
+        assert_eq!(owners.get(signature_key), Some(&LeafIndex(1)));
+    }
+
     #[cfg(feature = "custom_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn custom_proposals() {
@@ -485,19 +652,19 @@ mod tests {
         let mut test_index = TreeIndex::new();
 
         test_index
-            .insert(test_data_1.index, &test_data_1.leaf_node, vec![0])
+            .insert(test_data_1.index, &test_data_1.leaf_node, vec![0], false)
             .unwrap();
 
         assert_eq!(test_index.count_supporting_proposal(test_proposal_id), 1);
 
         test_index
-            .insert(test_data_2.index, &test_data_2.leaf_node, vec![1])
+            .insert(test_data_2.index, &test_data_2.leaf_node, vec![1], false)
             .unwrap();
 
         assert_eq!(test_index.count_supporting_proposal(test_proposal_id), 2);
         assert_eq!(test_index.count_supporting_proposal(other_proposal_id), 1);
 
-        test_index.remove(&test_data_2.leaf_node, &[1]);
+        test_index.remove(test_data_2.index, &test_data_2.leaf_node, &[1]);
 
         assert_eq!(test_index.count_supporting_proposal(test_proposal_id), 1);
         assert_eq!(test_index.count_supporting_proposal(other_proposal_id), 0);
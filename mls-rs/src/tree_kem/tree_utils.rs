@@ -9,7 +9,7 @@ use core::borrow::BorrowMut;
 use debug_tree::TreeBuilder;
 
 use super::node::{NodeIndex, NodeVec};
-use crate::{client::MlsError, tree_kem::math::TreeIndex};
+use crate::client::MlsError;
 
 pub(crate) fn build_tree(
     tree: &mut TreeBuilder,
@@ -28,7 +28,7 @@ pub(crate) fn build_tree(
     // Parent Leaf
     let mut parent_tag = format!("{blank_tag}Parent ({idx})");
 
-    if nodes.total_leaf_count().root() == idx {
+    if NodeIndex::root(nodes.total_leaf_count()) == idx {
         parent_tag = format!("{blank_tag}Root ({idx})");
     }
 
@@ -53,9 +53,13 @@ pub(crate) fn build_tree(
 
     let mut branch = tree.add_branch(&parent_tag);
 
-    //This cannot panic, as we already checked that idx is not a leaf
-    build_tree(tree, nodes, idx.left_unchecked())?;
-    build_tree(tree, nodes, idx.right_unchecked())?;
+    let (left, right) = idx
+        .left()
+        .zip(idx.right())
+        .ok_or(MlsError::LeafNodeNoChildren)?;
+
+    build_tree(tree, nodes, left)?;
+    build_tree(tree, nodes, right)?;
 
     branch.release();
 
@@ -65,7 +69,7 @@ pub(crate) fn build_tree(
 pub(crate) fn build_ascii_tree(nodes: &NodeVec) -> String {
     let leaves_count: u32 = nodes.total_leaf_count();
     let mut tree = TreeBuilder::new();
-    build_tree(tree.borrow_mut(), nodes, leaves_count.root()).unwrap();
+    build_tree(tree.borrow_mut(), nodes, NodeIndex::root(leaves_count)).unwrap();
     tree.string()
 }
 
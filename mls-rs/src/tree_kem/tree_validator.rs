@@ -7,15 +7,13 @@ use std::collections::HashSet;
 
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
-use tree_math::TreeIndex;
 
-use super::node::{Node, NodeIndex};
+use super::node::Node;
 use crate::client::MlsError;
 use crate::crypto::CipherSuiteProvider;
 use crate::group::GroupContext;
 use crate::iter::wrap_impl_iter;
-use crate::tree_kem::math as tree_math;
-use crate::tree_kem::{leaf_node_validator::LeafNodeValidator, TreeKemPublic};
+use crate::tree_kem::{leaf_node_validator::LeafNodeValidator, HpkeKeyIndex, TreeKemPublic};
 use mls_rs_core::identity::IdentityProvider;
 
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
@@ -33,6 +31,7 @@ where
     leaf_node_validator: LeafNodeValidator<'a, C, CSP>,
     group_id: &'a [u8],
     cipher_suite_provider: &'a CSP,
+    validate_leaves: bool,
 }
 
 impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP> {
@@ -50,6 +49,17 @@ impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP
             ),
             group_id: &context.group_id,
             cipher_suite_provider,
+            validate_leaves: true,
+        }
+    }
+
+    /// Skip the validation of the leaves, which were validated by a trusted
+    /// party.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn without_leaf_validation(self) -> Self {
+        Self {
+            validate_leaves: false,
+            ..self
         }
     }
 
@@ -61,7 +71,13 @@ impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP
             .await?;
 
         self.validate_no_trailing_blanks(tree)?;
-        self.validate_leaves(tree).await?;
+
+        HpkeKeyIndex::new(tree, &[])?;
+
+        if self.validate_leaves {
+            self.validate_leaves(tree).await?;
+        }
+
         validate_unmerged(tree)
     }
 
@@ -128,9 +144,9 @@ fn validate_unmerged(tree: &TreeKemPublic) -> Result<(), MlsError> {
     let leaf_count = tree.total_leaf_count();
 
     for (index, _) in tree.nodes.non_empty_leaves() {
-        let mut n = NodeIndex::from(index);
+        let mut n = index.to_node_index();
 
-        while let Some(ps) = n.parent_sibling(&leaf_count) {
+        while let Some(ps) = n.parent_sibling(leaf_count) {
             if tree.nodes.is_blank(ps.parent)? {
                 n = ps.parent;
                 continue;
@@ -139,7 +155,7 @@ fn validate_unmerged(tree: &TreeKemPublic) -> Result<(), MlsError> {
             let parent_node = tree.nodes.borrow_as_parent(ps.parent)?;
 
             if parent_node.unmerged_leaves.contains(&index) {
-                unmerged_sets[ps.parent as usize].retain(|i| i != &index);
+                unmerged_sets[*ps.parent as usize].retain(|i| i != &index);
 
                 n = ps.parent;
             } else {
@@ -171,7 +187,7 @@ mod tests {
         tree_kem::{
             kem::TreeKem,
             leaf_node::test_utils::{default_properties, get_basic_test_node},
-            node::{LeafIndex, Node, Parent},
+            node::{LeafIndex, Node, NodeIndex, Parent},
             parent_hash::{test_utils::get_test_tree_fig_12, ParentHash},
             test_utils::get_test_tree,
         },
@@ -269,7 +285,7 @@ mod tests {
         for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
             let mut test_tree = get_valid_tree(cipher_suite).await;
 
-            let parent_node = test_tree.nodes.borrow_as_parent_mut(1).unwrap();
+            let parent_node = test_tree.nodes.borrow_as_parent_mut(NodeIndex(1)).unwrap();
             parent_node.parent_hash = ParentHash::from(random_bytes(32));
 
             let cipher_suite_provider = test_cipher_suite_provider(cipher_suite);
@@ -281,7 +297,7 @@ mod tests {
 
             let res = validator.validate(&mut test_tree).await;
 
-            assert_matches!(res, Err(MlsError::ParentHashMismatch));
+            assert_matches!(res, Err(MlsError::ParentHashMismatch(_)));
         }
     }
 
@@ -333,7 +349,10 @@ mod tests {
         let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
 
         // Make D with direct path [3, 7] unmerged at 7 but not 3
-        tree.nodes.borrow_as_parent_mut(3).unwrap().unmerged_leaves = vec![];
+        tree.nodes
+            .borrow_as_parent_mut(NodeIndex(3))
+            .unwrap()
+            .unmerged_leaves = vec![];
 
         assert_matches!(
             validate_unmerged(&tree),
@@ -346,7 +365,10 @@ mod tests {
         let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
 
         // Add leaf E from the right subtree of the root to unmerged leaves of node 1 on the left
-        tree.nodes.borrow_as_parent_mut(1).unwrap().unmerged_leaves = vec![LeafIndex(4)];
+        tree.nodes
+            .borrow_as_parent_mut(NodeIndex(1))
+            .unwrap()
+            .unmerged_leaves = vec![LeafIndex(4)];
 
         assert_matches!(
             validate_unmerged(&tree),
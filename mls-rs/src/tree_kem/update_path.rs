@@ -10,6 +10,7 @@ use super::{
     leaf_node::LeafNode,
     leaf_node_validator::{LeafNodeValidator, ValidationContext},
     node::LeafIndex,
+    HpkeKeyIndex,
 };
 use crate::{
     client::MlsError,
@@ -100,6 +101,26 @@ pub(crate) async fn validate_update_path<C: IdentityProvider, CSP: CipherSuitePr
         i += 1;
     }
 
+    // The new keys replace those of the sender and its direct path, and must
+    // not be used anywhere else in the tree.
+    let sender_node = sender.to_node_index();
+    let direct_path = state.public_tree.nodes.direct_copath(sender);
+
+    let replaced = direct_path
+        .iter()
+        .map(|n| n.path)
+        .chain([sender_node])
+        .collect::<Vec<_>>();
+
+    let mut key_index = HpkeKeyIndex::new(&state.public_tree, &replaced)?;
+    key_index.insert(&path.leaf_node.public_key, sender_node)?;
+
+    for (node, n) in unfiltered_nodes.iter().zip(&direct_path) {
+        if let Some(node) = node {
+            key_index.insert(&node.public_key, n.path)?;
+        }
+    }
+
     Ok(ValidatedUpdatePath {
         leaf_node: path.leaf_node,
         nodes: unfiltered_nodes,
@@ -120,7 +141,7 @@ mod tests {
     use crate::tree_kem::leaf_node::test_utils::default_properties;
     use crate::tree_kem::leaf_node::test_utils::get_basic_test_node_sig_key;
     use crate::tree_kem::leaf_node::LeafNodeSource;
-    use crate::tree_kem::node::LeafIndex;
+    use crate::tree_kem::node::{LeafIndex, NodeIndex};
     use crate::tree_kem::parent_hash::ParentHash;
     use crate::tree_kem::test_utils::{get_test_leaf_nodes, get_test_tree};
     use crate::tree_kem::validate_update_path;
@@ -148,7 +169,7 @@ mod tests {
             .await
             .unwrap();
 
-        let node = UpdatePathNode {
+        let node = || UpdatePathNode {
             public_key: random_bytes(32).into(),
             encrypted_path_secret: vec![HpkeCiphertext {
                 kem_output: random_bytes(32),
@@ -158,7 +179,7 @@ mod tests {
 
         UpdatePath {
             leaf_node,
-            nodes: vec![node.clone(), node],
+            nodes: vec![node(), node()],
         }
     }
 
@@ -271,4 +292,33 @@ mod tests {
 
         assert_matches!(validated, Err(MlsError::SameHpkeKey(_)));
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn validating_path_fails_with_key_used_elsewhere_in_tree() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut update_path = test_update_path(TEST_CIPHER_SUITE, "creator").await;
+        let state = test_provisional_state(TEST_CIPHER_SUITE).await;
+
+        update_path.nodes[1].public_key = state
+            .public_tree
+            .get_leaf_node(LeafIndex(3))
+            .unwrap()
+            .public_key
+            .clone();
+
+        let validated = validate_update_path(
+            &BasicIdentityProvider,
+            &cipher_suite_provider,
+            update_path,
+            &state,
+            LeafIndex(0),
+            None,
+        )
+        .await;
+
+        assert_matches!(
+            validated,
+            Err(MlsError::DuplicateHpkeKey(NodeIndex(6), NodeIndex(3)))
+        );
+    }
 }
@@ -0,0 +1,171 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Arithmetic on the array representation of ratchet trees, as described in
+//! [RFC 9420 Appendix C](https://www.rfc-editor.org/rfc/rfc9420.html#name-array-based-trees).
+//!
+//! Nodes of a tree are numbered from left to right, leaves having even
+//! indices and parent nodes odd ones. These functions are the ones used by
+//! groups internally, and let delivery services and other tools working on
+//! [exported trees](crate::group::ExportedTree) navigate them in the same way.
+//!
+//! Functions taking a `leaf_count` accept the number of leaves of a tree as
+//! sent on the wire, from which trailing blank leaves may have been removed.
+//! It is rounded up to the next power of two, that is the number of leaves of
+//! the complete tree.
+
+use alloc::vec::Vec;
+
+use crate::tree_kem::math::TreeIndex;
+
+pub use crate::tree_kem::node::{LeafIndex, NodeIndex};
+
+fn complete_leaf_count(leaf_count: u32) -> Option<u32> {
+    leaf_count
+        .checked_next_power_of_two()
+        .filter(|_| leaf_count > 0)
+}
+
+fn in_tree(node: NodeIndex, leaf_count: u32) -> Option<u32> {
+    complete_leaf_count(leaf_count).filter(|leaf_count| node.0.is_in_tree(&leaf_count.root()))
+}
+
+/// Number of nodes in a tree with `leaf_count` leaves.
+pub fn node_width(leaf_count: u32) -> u32 {
+    leaf_count.saturating_mul(2).saturating_sub(1)
+}
+
+/// Level of `node` in the tree, leaves being at level 0.
+pub fn level(node: NodeIndex) -> u32 {
+    node.trailing_ones()
+}
+
+/// Whether `node` is a leaf.
+pub fn is_leaf(node: NodeIndex) -> bool {
+    node.is_leaf()
+}
+
+/// Index of the node holding the leaf `leaf`.
+pub fn node_index(leaf: LeafIndex) -> NodeIndex {
+    leaf.to_node_index()
+}
+
+/// Index of the leaf held by `node`, if it is a leaf.
+pub fn leaf_index(node: NodeIndex) -> Option<LeafIndex> {
+    node.is_leaf().then(|| LeafIndex::new(*node / 2))
+}
+
+/// Root of a tree with `leaf_count` leaves, or `None` for an empty tree.
+pub fn root(leaf_count: u32) -> Option<NodeIndex> {
+    complete_leaf_count(leaf_count).map(NodeIndex::root)
+}
+
+/// Left child of `node`, or `None` if it is a leaf.
+pub fn left(node: NodeIndex) -> Option<NodeIndex> {
+    node.left()
+}
+
+/// Right child of `node`, or `None` if it is a leaf.
+pub fn right(node: NodeIndex) -> Option<NodeIndex> {
+    node.right()
+}
+
+/// Parent of `node` in a tree with `leaf_count` leaves, or `None` if `node`
+/// is the root or is not in the tree.
+pub fn parent(node: NodeIndex, leaf_count: u32) -> Option<NodeIndex> {
+    let leaf_count = in_tree(node, leaf_count)?;
+    node.parent_sibling(leaf_count).map(|ps| ps.parent)
+}
+
+/// Sibling of `node` in a tree with `leaf_count` leaves, or `None` if `node`
+/// is the root or is not in the tree.
+pub fn sibling(node: NodeIndex, leaf_count: u32) -> Option<NodeIndex> {
+    let leaf_count = in_tree(node, leaf_count)?;
+    node.parent_sibling(leaf_count).map(|ps| ps.sibling)
+}
+
+/// Direct path of `node` in a tree with `leaf_count` leaves, from its parent
+/// up to the root.
+pub fn direct_path(node: NodeIndex, leaf_count: u32) -> Vec<NodeIndex> {
+    in_tree(node, leaf_count)
+        .map(|leaf_count| {
+            node.direct_copath(leaf_count)
+                .into_iter()
+                .map(|n| n.path)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Copath of `node` in a tree with `leaf_count` leaves, i.e. the siblings of
+/// `node` and of the nodes of its direct path except the root.
+pub fn copath(node: NodeIndex, leaf_count: u32) -> Vec<NodeIndex> {
+    in_tree(node, leaf_count)
+        .map(|leaf_count| {
+            node.direct_copath(leaf_count)
+                .into_iter()
+                .map(|n| n.copath)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn n(i: u32) -> NodeIndex {
+        NodeIndex::new(i)
+    }
+
+    fn nodes<const N: usize>(indices: [u32; N]) -> Vec<NodeIndex> {
+        indices.into_iter().map(n).collect()
+    }
+
+    #[test]
+    fn navigates_tree_with_four_leaves() {
+        assert_eq!(node_width(4), 7);
+        assert_eq!(root(4), Some(n(3)));
+
+        assert_eq!(left(n(3)), Some(n(1)));
+        assert_eq!(right(n(3)), Some(n(5)));
+        assert_eq!(left(n(0)), None);
+
+        assert_eq!(parent(n(0), 4), Some(n(1)));
+        assert_eq!(sibling(n(0), 4), Some(n(2)));
+        assert_eq!(parent(n(5), 4), Some(n(3)));
+        assert_eq!(sibling(n(5), 4), Some(n(1)));
+        assert_eq!(parent(n(3), 4), None);
+
+        assert_eq!(direct_path(n(0), 4), nodes([1, 3]));
+        assert_eq!(copath(n(0), 4), nodes([2, 5]));
+        assert!(direct_path(n(3), 4).is_empty());
+    }
+
+    #[test]
+    fn leaf_count_is_rounded_up() {
+        assert_eq!(root(3), Some(n(3)));
+        assert_eq!(root(5), Some(n(7)));
+        assert_eq!(direct_path(n(4), 3), nodes([5, 3]));
+    }
+
+    #[test]
+    fn nodes_outside_the_tree_have_no_relatives() {
+        assert_eq!(root(0), None);
+        assert_eq!(parent(n(8), 4), None);
+        assert_eq!(sibling(n(0), 0), None);
+        assert!(direct_path(n(8), 4).is_empty());
+        assert!(copath(n(8), 4).is_empty());
+    }
+
+    #[test]
+    fn converts_leaf_indices() {
+        assert_eq!(node_index(LeafIndex::new(2)), n(4));
+        assert_eq!(leaf_index(n(4)), Some(LeafIndex::new(2)));
+        assert_eq!(leaf_index(n(3)), None);
+    }
+}
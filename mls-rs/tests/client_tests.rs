@@ -8,6 +8,10 @@ use mls_rs::client_builder::MlsConfig;
 use mls_rs::error::MlsError;
 use mls_rs::group::proposal::Proposal;
 use mls_rs::group::ReceivedMessage;
+#[cfg(all(feature = "psk", feature = "private_message", feature = "prior_epoch"))]
+use mls_rs::group::ReinitBridge;
+#[cfg(feature = "psk")]
+use mls_rs::group::{GroupRecovery, LostGroup};
 use mls_rs::identity::SigningIdentity;
 use mls_rs::mls_rules::CommitOptions;
 use mls_rs::ExtensionList;
@@ -726,6 +730,216 @@ async fn reinit_works() {
         .unwrap();
 }
 
+#[cfg(all(feature = "psk", feature = "private_message", feature = "prior_epoch"))]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn reinit_bridge_reads_late_messages() {
+    let version = ProtocolVersion::MLS_10;
+    let cipher_suite = CipherSuite::P256_AES128;
+
+    let mut groups = get_test_groups(version, cipher_suite, 2, false).await;
+    let mut bob_group = groups.pop().unwrap();
+    let mut alice_group = groups.pop().unwrap();
+
+    // Alice sends a message that Bob only receives after the reinit
+    let late_message = alice_group
+        .encrypt_application_message(b"late", Vec::new())
+        .await
+        .unwrap();
+
+    let reinit_proposal_message = alice_group
+        .propose_reinit(
+            None,
+            version,
+            cipher_suite,
+            ExtensionList::default(),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+
+    bob_group
+        .process_incoming_message(reinit_proposal_message)
+        .await
+        .unwrap();
+
+    let commit = bob_group.commit(Vec::new()).await.unwrap().commit_message;
+    bob_group.apply_pending_commit().await.unwrap();
+    alice_group.process_incoming_message(commit).await.unwrap();
+
+    let bob_old_group = bob_group.clone();
+    let bob2 = bob_group.get_reinit_client(None, None).unwrap();
+    let alice2 = alice_group.get_reinit_client(None, None).unwrap();
+
+    let kp = bob2.generate_key_package().await.unwrap();
+    let (mut alice_group, welcome) = alice2.commit(vec![kp]).await.unwrap();
+    let (bob_group, _) = bob2.join(&welcome[0], None).await.unwrap();
+
+    let mut bridge = ReinitBridge::new(bob_old_group, bob_group, 1).unwrap();
+
+    let received = bridge.process_incoming_message(late_message).await.unwrap();
+    assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"late");
+
+    let message = alice_group
+        .encrypt_application_message(b"new", Vec::new())
+        .await
+        .unwrap();
+
+    let received = bridge.process_incoming_message(message).await.unwrap();
+    assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"new");
+
+    // The old group is closed once the new group advances past the window
+    let commit = alice_group.commit(Vec::new()).await.unwrap().commit_message;
+    alice_group.apply_pending_commit().await.unwrap();
+
+    bridge.process_incoming_message(commit).await.unwrap();
+    assert!(bridge.old_group().is_none());
+}
+
+#[cfg(feature = "psk")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn reinit_requires_same_members() {
+    let version = ProtocolVersion::MLS_10;
+    let cipher_suite = CipherSuite::P256_AES128;
+
+    let mut groups = get_test_groups(version, cipher_suite, 3, false).await;
+
+    let commit = groups[0]
+        .commit_builder()
+        .reinit(None, version, cipher_suite, ExtensionList::default())
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+        .commit_message;
+
+    groups[0].apply_pending_commit().await.unwrap();
+
+    for group in groups.iter_mut().skip(1) {
+        group
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+    }
+
+    let mut reinit_clients = groups
+        .into_iter()
+        .map(|group| group.get_reinit_client(None, None).unwrap());
+
+    let alice2 = reinit_clients.next().unwrap();
+    let bob2 = reinit_clients.next().unwrap();
+
+    // Carol is left out of the new group
+    let kp = bob2.generate_key_package().await.unwrap();
+    let res = alice2.commit(vec![kp]).await;
+
+    assert_matches!(res, Err(MlsError::ReInitRosterMismatch));
+}
+
+#[cfg(feature = "psk")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn recovery_works() {
+    let version = ProtocolVersion::MLS_10;
+    let cipher_suite = CipherSuite::P256_AES128;
+
+    let groups = get_test_groups(version, cipher_suite, 3, false).await;
+
+    let lost_group_id = groups[0].group_id().to_vec();
+
+    let lost_group = LostGroup::new(
+        lost_group_id.clone(),
+        groups[0].current_epoch(),
+        groups[0].export_tree().into_owned(),
+    );
+
+    // All members lose their secrets and come back with new signature keys
+    drop(groups);
+
+    let alice = generate_client(cipher_suite, version, 0, false).await;
+
+    // Without a recovery policy, groups can't be recovered
+    let res = alice
+        .recover_group(&lost_group, None, ExtensionList::default(), Vec::new())
+        .await;
+
+    assert_matches!(res, Err(MlsError::RecoveryNotAllowed));
+
+    // Only Alice is an administrator allowed to recover groups
+    let admin = alice.signing_identity().unwrap().0.clone();
+    let policy = move |recovery: &GroupRecovery<'_>| recovery.creator == &admin;
+
+    let mut clients = vec![alice];
+
+    for id in 1..3 {
+        clients.push(generate_client(cipher_suite, version, id, false).await);
+    }
+
+    let clients = clients
+        .iter()
+        .map(|client| client.to_builder().recovery_policy(policy.clone()).build())
+        .collect::<Vec<_>>();
+
+    let bob_kp = clients[1].generate_key_package_message().await.unwrap();
+    let carol_kp = clients[2].generate_key_package_message().await.unwrap();
+
+    let res = clients[1]
+        .recover_group(&lost_group, None, ExtensionList::default(), Vec::new())
+        .await;
+
+    assert_matches!(res, Err(MlsError::RecoveryNotAllowed));
+
+    let res = clients[0]
+        .recover_group(&lost_group, None, ExtensionList::default(), vec![bob_kp])
+        .await;
+
+    assert_matches!(res, Err(MlsError::RecoveryRosterMismatch));
+
+    let bob_kp = clients[1].generate_key_package_message().await.unwrap();
+
+    let (mut alice_group, welcome) = clients[0]
+        .recover_group(
+            &lost_group,
+            None,
+            ExtensionList::default(),
+            vec![bob_kp, carol_kp],
+        )
+        .await
+        .unwrap();
+
+    assert_ne!(alice_group.group_id(), lost_group_id);
+
+    // Joiners whose policy doesn't allow Alice to recover the group refuse to
+    // join
+    let res = clients[1]
+        .to_builder()
+        .recovery_policy(|_: &GroupRecovery<'_>| false)
+        .build()
+        .join_recovered_group(&lost_group, &welcome[0], None)
+        .await;
+
+    assert_matches!(res, Err(MlsError::RecoveryNotAllowed));
+
+    let (mut bob_group, _) = clients[1]
+        .join_recovered_group(&lost_group, &welcome[0], None)
+        .await
+        .unwrap();
+
+    let (mut carol_group, _) = clients[2]
+        .join_recovered_group(&lost_group, &welcome[0], None)
+        .await
+        .unwrap();
+
+    // They can talk
+    let commit = alice_group.commit(Vec::new()).await.unwrap().commit_message;
+    alice_group.apply_pending_commit().await.unwrap();
+
+    bob_group
+        .process_incoming_message(commit.clone())
+        .await
+        .unwrap();
+
+    carol_group.process_incoming_message(commit).await.unwrap();
+}
+
 #[cfg(feature = "by_ref_proposal")]
 #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
 async fn external_joiner_can_process_siblings_update() {
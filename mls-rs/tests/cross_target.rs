@@ -0,0 +1,103 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Smoke test of the main code paths, run on every supported target by
+//! `cargo xtask cross-test`. It relies on the platform time source, through
+//! key package lifetimes, and on the platform random number generator,
+//! through the crypto provider, so that targets where either is missing fail
+//! here rather than in applications.
+
+use assert_matches::assert_matches;
+use cfg_if::cfg_if;
+use mls_rs::client_builder::MlsConfig;
+use mls_rs::group::ReceivedMessage;
+use mls_rs::time::MlsTime;
+use mls_rs::{CipherSuite, Client, CryptoProvider, ProtocolVersion};
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use mls_rs_crypto_webcrypto::WebCryptoProvider as TestCryptoProvider;
+    } else {
+        use mls_rs_crypto_openssl::OpensslCryptoProvider as TestCryptoProvider;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as futures_test;
+
+#[cfg(all(mls_build_async, not(target_arch = "wasm32")))]
+use futures_test::test as futures_test;
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn generate_client(cipher_suite: CipherSuite, id: usize) -> Client<impl MlsConfig> {
+    mls_rs::test_utils::generate_basic_client(
+        cipher_suite,
+        ProtocolVersion::MLS_10,
+        id,
+        None,
+        true,
+        &TestCryptoProvider::default(),
+        None,
+    )
+    .await
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn create_join_commit_protect(cipher_suite: CipherSuite) {
+    let alice = generate_client(cipher_suite, 0).await;
+    let bob = generate_client(cipher_suite, 1).await;
+
+    let mut alice_group = alice.create_group(Default::default()).await.unwrap();
+
+    let bob_key_package = bob.generate_key_package_message().await.unwrap();
+
+    let commit = alice_group
+        .commit_builder()
+        .add_member(bob_key_package)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    alice_group.apply_pending_commit().await.unwrap();
+
+    let (mut bob_group, _) = bob
+        .join_group(None, &commit.welcome_messages[0])
+        .await
+        .unwrap();
+
+    let commit = bob_group.commit(Vec::new()).await.unwrap();
+    bob_group.apply_pending_commit().await.unwrap();
+
+    let res = alice_group
+        .process_incoming_message_with_time(commit.commit_message, MlsTime::now())
+        .await
+        .unwrap();
+
+    assert_matches!(res, ReceivedMessage::Commit(_));
+
+    let message = alice_group
+        .encrypt_application_message(b"hello", Vec::new())
+        .await
+        .unwrap();
+
+    let res = bob_group.process_incoming_message(message).await.unwrap();
+
+    assert_matches!(res, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello");
+
+    assert_eq!(
+        alice_group.epoch_authenticator().unwrap(),
+        bob_group.epoch_authenticator().unwrap()
+    );
+}
+
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn cross_target_smoke_test() {
+    for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
+        create_join_commit_protect(cipher_suite).await;
+    }
+}
@@ -0,0 +1,548 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Reference flows running groups end to end over the in-memory delivery
+//! service. Each test is a complete example of how an application drives one
+//! of the major parts of the library: members only ever exchange messages
+//! through the delivery service.
+
+use assert_matches::assert_matches;
+use cfg_if::cfg_if;
+use mls_rs::client_builder::MlsConfig;
+use mls_rs::delivery::{DeliveryService, InMemoryDeliveryService};
+use mls_rs::error::MlsError;
+use mls_rs::group::ReceivedMessage;
+use mls_rs::{CipherSuite, Client, ExtensionList, Group, MlsMessage, ProtocolVersion};
+
+#[cfg(feature = "external_client")]
+use mls_rs::external_client::{
+    builder::MlsConfig as ExternalMlsConfig, ExternalClient, ExternalGroup,
+};
+#[cfg(feature = "external_client")]
+use mls_rs::identity::basic::BasicIdentityProvider;
+#[cfg(feature = "psk")]
+use mls_rs::identity::SigningIdentity;
+#[cfg(feature = "psk")]
+use mls_rs::test_utils::get_test_basic_credential;
+#[cfg(feature = "psk")]
+use mls_rs::{CipherSuiteProvider, CryptoProvider};
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use mls_rs_crypto_webcrypto::WebCryptoProvider as TestCryptoProvider;
+    } else {
+        use mls_rs_crypto_openssl::OpensslCryptoProvider as TestCryptoProvider;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as futures_test;
+
+#[cfg(all(mls_build_async, not(target_arch = "wasm32")))]
+use futures_test::test as futures_test;
+
+const CIPHER_SUITE: CipherSuite = CipherSuite::P256_AES128;
+const VERSION: ProtocolVersion = ProtocolVersion::MLS_10;
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn generate_client(cipher_suite: CipherSuite, id: usize) -> Client<impl MlsConfig> {
+    mls_rs::test_utils::generate_basic_client(
+        cipher_suite,
+        VERSION,
+        id,
+        None,
+        false,
+        &TestCryptoProvider::default(),
+        None,
+    )
+    .await
+}
+
+/// Group member reading and writing the messages of its group through the
+/// delivery service.
+struct Member<C: MlsConfig> {
+    group: Group<C>,
+    delivery: InMemoryDeliveryService,
+    /// Number of messages of the current epoch already read.
+    cursor: usize,
+}
+
+impl<C: MlsConfig> Member<C> {
+    fn new(group: Group<C>, delivery: &InMemoryDeliveryService) -> Self {
+        Self {
+            group,
+            delivery: delivery.clone(),
+            cursor: 0,
+        }
+    }
+
+    /// Join the group of the welcome message sent to `identity`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn join(client: &Client<C>, identity: &[u8], delivery: &InMemoryDeliveryService) -> Self {
+        let welcome = delivery
+            .clone()
+            .fetch_welcomes(identity)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let (group, _) = client.join_group(None, &welcome).await.unwrap();
+
+        Self::new(group, delivery)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn send(&mut self, message: MlsMessage) {
+        assert!(self.delivery.send_to_group(message).await.unwrap());
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn send_text(&mut self, text: &str) {
+        let message = self
+            .group
+            .encrypt_application_message(text.as_bytes(), Vec::new())
+            .await
+            .unwrap();
+
+        self.send(message).await;
+    }
+
+    /// Send the pending commit `commit`, returning whether the delivery
+    /// service accepted it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn send_commit(&mut self, commit: MlsMessage) -> bool {
+        let accepted = self.delivery.send_to_group(commit).await.unwrap();
+        self.commit_sent(accepted).await;
+        accepted
+    }
+
+    /// Apply the pending commit if it was accepted, or discard it so that
+    /// the messages of the current epoch can be read.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn commit_sent(&mut self, accepted: bool) {
+        if accepted {
+            self.group.apply_pending_commit().await.unwrap();
+            self.cursor = 0;
+        } else {
+            self.group.clear_pending_commit();
+        }
+    }
+
+    /// Process all messages accepted since the last call, following the
+    /// group through the commits it reads.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn receive(&mut self) -> Vec<ReceivedMessage> {
+        let mut received = Vec::new();
+
+        loop {
+            let epoch = self.group.current_epoch();
+
+            let messages = self
+                .delivery
+                .fetch_messages(self.group.group_id(), epoch..epoch + 1)
+                .await
+                .unwrap();
+
+            let Some(message) = messages.into_iter().nth(self.cursor) else {
+                return received;
+            };
+
+            self.cursor += 1;
+
+            match self.group.process_incoming_message(message).await {
+                Ok(message) => received.push(message),
+                // Messages sent by this member are also relayed back to it.
+                Err(MlsError::CantProcessMessageFromSelf) => continue,
+                Err(e) => panic!("failed to process message: {e:?}"),
+            }
+
+            if self.group.current_epoch() != epoch {
+                self.cursor = 0;
+            }
+        }
+    }
+
+    /// Texts of the application messages among the messages received.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn receive_texts(&mut self) -> Vec<String> {
+        self.receive()
+            .await
+            .into_iter()
+            .filter_map(|message| match message {
+                ReceivedMessage::ApplicationMessage(message) => {
+                    Some(String::from_utf8(message.data().to_vec()).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Create a group for `creator` and add the clients whose key packages are
+/// published under `identities` with a single commit.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn create_group<C: MlsConfig>(
+    creator: &Client<C>,
+    identities: &[Vec<u8>],
+    delivery: &mut InMemoryDeliveryService,
+) -> Member<C> {
+    let group = creator.create_group(ExtensionList::new()).await.unwrap();
+    let mut member = Member::new(group, delivery);
+
+    let mut builder = member.group.commit_builder();
+
+    for identity in identities {
+        let key_package = delivery.fetch_key_package(identity).await.unwrap().unwrap();
+        builder = builder.add_member(key_package).unwrap();
+    }
+
+    let output = builder.build().await.unwrap();
+
+    assert!(member.send_commit(output.commit_message).await);
+
+    // By default, all new members are sent the same welcome message.
+    for identity in identities {
+        delivery
+            .send_welcome(identity, output.welcome_messages[0].clone())
+            .await
+            .unwrap();
+    }
+
+    member
+}
+
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn two_party_chat() {
+    let mut delivery = InMemoryDeliveryService::new();
+
+    let alice = generate_client(CIPHER_SUITE, 1).await;
+    let bob = generate_client(CIPHER_SUITE, 2).await;
+
+    // Bob makes himself reachable by publishing a key package.
+    let key_package = bob.generate_key_package_message().await.unwrap();
+
+    delivery
+        .publish_key_package(b"bob", key_package)
+        .await
+        .unwrap();
+
+    // Alice creates a group with Bob, who joins with the welcome message.
+    let mut alice = create_group(&alice, &[b"bob".to_vec()], &mut delivery).await;
+    let mut bob = Member::join(&bob, b"bob", &delivery).await;
+
+    alice.send_text("hi bob").await;
+    assert_eq!(bob.receive_texts().await, ["hi bob"]);
+
+    bob.send_text("hi alice").await;
+    bob.send_text("how are you?").await;
+    assert_eq!(alice.receive_texts().await, ["hi alice", "how are you?"]);
+
+    // Bob updates his keys, and they keep talking in the new epoch.
+    let commit = bob.group.commit(Vec::new()).await.unwrap().commit_message;
+    assert!(bob.send_commit(commit).await);
+
+    bob.send_text("new keys").await;
+
+    let received = alice.receive().await;
+    assert_eq!(received.len(), 2);
+    assert_matches!(received[0], ReceivedMessage::Commit(_));
+    assert_matches!(
+        &received[1],
+        ReceivedMessage::ApplicationMessage(m) if m.data() == b"new keys"
+    );
+
+    assert_eq!(alice.group.current_epoch(), bob.group.current_epoch());
+}
+
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn large_fan_out_group() {
+    const MEMBERS: usize = 32;
+
+    let mut delivery = InMemoryDeliveryService::new();
+
+    let creator = generate_client(CIPHER_SUITE, 0).await;
+
+    let mut clients = Vec::new();
+    let mut identities = Vec::new();
+
+    for i in 1..MEMBERS {
+        let client = generate_client(CIPHER_SUITE, i).await;
+        let identity = format!("member-{i}").into_bytes();
+
+        let key_package = client.generate_key_package_message().await.unwrap();
+
+        delivery
+            .publish_key_package(&identity, key_package)
+            .await
+            .unwrap();
+
+        clients.push(client);
+        identities.push(identity);
+    }
+
+    // All members are added with one commit and join from the same welcome.
+    let mut creator = create_group(&creator, &identities, &mut delivery).await;
+    let mut members = Vec::new();
+
+    for (client, identity) in clients.iter().zip(&identities) {
+        members.push(Member::join(client, identity, &delivery).await);
+    }
+
+    assert_eq!(creator.group.roster().members().len(), MEMBERS);
+
+    // A broadcast reaches every member.
+    creator.send_text("welcome everyone").await;
+
+    for member in &mut members {
+        assert_eq!(member.receive_texts().await, ["welcome everyone"]);
+    }
+
+    // Two members commit concurrently. Only the first commit is accepted,
+    // and the other member catches up before committing again.
+    let first = members[3].group.commit(Vec::new()).await.unwrap();
+    let second = members[7].group.commit(Vec::new()).await.unwrap();
+
+    assert!(members[3].send_commit(first.commit_message).await);
+    assert!(!members[7].send_commit(second.commit_message).await);
+
+    let received = members[7].receive().await;
+    assert_matches!(received[..], [ReceivedMessage::Commit(_)]);
+
+    let retry = members[7].group.commit(Vec::new()).await.unwrap();
+    assert!(members[7].send_commit(retry.commit_message).await);
+
+    // Everyone ends up in the same epoch.
+    creator.receive().await;
+
+    for member in &mut members {
+        member.receive().await;
+
+        assert_eq!(member.group.current_epoch(), creator.group.current_epoch());
+
+        assert_eq!(
+            member.group.epoch_authenticator().unwrap(),
+            creator.group.epoch_authenticator().unwrap()
+        );
+    }
+
+    assert_eq!(delivery.group_epoch(creator.group.group_id()), Some(3));
+}
+
+/// Server observing a group as an external client and relaying only the
+/// messages it validated.
+#[cfg(feature = "external_client")]
+struct ValidatingServer<C: ExternalMlsConfig> {
+    group: ExternalGroup<C>,
+    delivery: InMemoryDeliveryService,
+}
+
+#[cfg(feature = "external_client")]
+impl<C: ExternalMlsConfig> ValidatingServer<C> {
+    /// Validate `message` against the group state tracked by the server and
+    /// relay it if it is valid, returning whether the message was relayed.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn relay(&mut self, message: MlsMessage) -> bool {
+        if self
+            .group
+            .process_incoming_message(message.clone())
+            .await
+            .is_err()
+        {
+            return false;
+        }
+
+        // A commit valid for the group state of the server is always for the
+        // epoch the delivery service expects.
+        assert!(self.delivery.send_to_group(message).await.unwrap());
+
+        true
+    }
+}
+
+#[cfg(feature = "external_client")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn server_validated_group() {
+    let mut delivery = InMemoryDeliveryService::new();
+
+    let alice = generate_client(CIPHER_SUITE, 1).await;
+    let bob = generate_client(CIPHER_SUITE, 2).await;
+    let carol = generate_client(CIPHER_SUITE, 3).await;
+
+    let key_package = bob.generate_key_package_message().await.unwrap();
+
+    delivery
+        .publish_key_package(b"bob", key_package)
+        .await
+        .unwrap();
+
+    let mut alice = create_group(&alice, &[b"bob".to_vec()], &mut delivery).await;
+    let mut bob = Member::join(&bob, b"bob", &delivery).await;
+
+    // The server starts observing the group from a group info of Alice.
+    let server = ExternalClient::builder()
+        .identity_provider(BasicIdentityProvider::new())
+        .crypto_provider(TestCryptoProvider::default())
+        .build();
+
+    let group_info = alice.group.group_info_message(true).await.unwrap();
+
+    let mut server = ValidatingServer {
+        group: server.observe_group(group_info, None).await.unwrap(),
+        delivery: delivery.clone(),
+    };
+
+    // Alice adds Carol through the server.
+    let key_package = carol.generate_key_package_message().await.unwrap();
+
+    let output = alice
+        .group
+        .commit_builder()
+        .add_member(key_package)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    // Bob commits concurrently, but his commit is for a stale epoch by the
+    // time it reaches the server.
+    let stale = bob.group.commit(Vec::new()).await.unwrap().commit_message;
+
+    let accepted = server.relay(output.commit_message).await;
+    alice.commit_sent(accepted).await;
+    assert!(accepted);
+
+    let accepted = server.relay(stale).await;
+    bob.commit_sent(accepted).await;
+    assert!(!accepted);
+
+    delivery
+        .send_welcome(b"carol", output.welcome_messages[0].clone())
+        .await
+        .unwrap();
+
+    let mut carol = Member::join(&carol, b"carol", &delivery).await;
+
+    bob.receive().await;
+    assert_eq!(bob.group.current_epoch(), alice.group.current_epoch());
+    assert_eq!(server.group.roster().members().len(), 3);
+
+    // Application messages are relayed without the server reading them.
+    let message = carol
+        .group
+        .encrypt_application_message(b"hi all", Vec::new())
+        .await
+        .unwrap();
+
+    assert!(server.relay(message).await);
+
+    assert_eq!(alice.receive_texts().await, ["hi all"]);
+    assert_eq!(bob.receive_texts().await, ["hi all"]);
+}
+
+#[cfg(feature = "psk")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn reinit_upgrade() {
+    let Some(new_cipher_suite) = CipherSuite::all().find(|cs| {
+        cs != &CIPHER_SUITE && TestCryptoProvider::all_supported_cipher_suites().contains(cs)
+    }) else {
+        return;
+    };
+
+    let mut delivery = InMemoryDeliveryService::new();
+
+    let alice_client = generate_client(CIPHER_SUITE, 1).await;
+    let bob_client = generate_client(CIPHER_SUITE, 2).await;
+
+    let key_package = bob_client.generate_key_package_message().await.unwrap();
+
+    delivery
+        .publish_key_package(b"bob", key_package)
+        .await
+        .unwrap();
+
+    let mut alice = create_group(&alice_client, &[b"bob".to_vec()], &mut delivery).await;
+    let mut bob = Member::join(&bob_client, b"bob", &delivery).await;
+
+    // Alice proposes to move the group to a new cipher suite and Bob commits
+    // the proposal, which ends the group.
+    let proposal = alice
+        .group
+        .propose_reinit(
+            None,
+            VERSION,
+            new_cipher_suite,
+            ExtensionList::default(),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+
+    alice.send(proposal).await;
+
+    assert_matches!(bob.receive().await[..], [ReceivedMessage::Proposal(_)]);
+
+    let commit = bob.group.commit(Vec::new()).await.unwrap().commit_message;
+    assert!(bob.send_commit(commit).await);
+
+    assert_matches!(alice.receive().await[..], [ReceivedMessage::Commit(_)]);
+    assert!(alice.group.commit(Vec::new()).await.is_err());
+
+    // Both members get new signing keys for the new cipher suite.
+    let cs = TestCryptoProvider::new()
+        .cipher_suite_provider(new_cipher_suite)
+        .unwrap();
+
+    let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+    let identity = SigningIdentity::new(get_test_basic_credential(b"1".to_vec()), public_key);
+
+    let alice_reinit = alice
+        .group
+        .get_reinit_client(Some(secret_key), Some(identity))
+        .unwrap();
+
+    let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+    let identity = SigningIdentity::new(get_test_basic_credential(b"2".to_vec()), public_key);
+
+    let bob_reinit = bob
+        .group
+        .get_reinit_client(Some(secret_key), Some(identity))
+        .unwrap();
+
+    // Bob publishes a key package for the new group, Alice creates it and
+    // Bob joins.
+    let key_package = bob_reinit.generate_key_package().await.unwrap();
+
+    delivery
+        .publish_key_package(b"bob", key_package)
+        .await
+        .unwrap();
+
+    let key_package = delivery.fetch_key_package(b"bob").await.unwrap().unwrap();
+    let (group, welcome) = alice_reinit.commit(vec![key_package]).await.unwrap();
+
+    delivery
+        .send_welcome(b"bob", welcome[0].clone())
+        .await
+        .unwrap();
+
+    let welcome = delivery
+        .fetch_welcomes(b"bob")
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let (bob_group, _) = bob_reinit.join(&welcome, None).await.unwrap();
+
+    let mut alice = Member::new(group, &delivery);
+    let mut bob = Member::new(bob_group, &delivery);
+
+    assert_eq!(bob.group.cipher_suite(), new_cipher_suite);
+
+    alice.send_text("upgraded").await;
+    assert_eq!(bob.receive_texts().await, ["upgraded"]);
+}
@@ -0,0 +1,260 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Development tasks for the workspace, run with `cargo xtask <task>`.
+//!
+//! `cargo xtask cross-test [target...]` runs the `cross_target` test of
+//! `mls-rs` on each of the given targets, or on all of them if none is
+//! given. The targets and the tools they require are:
+//!
+//! * `native`: the host toolchain.
+//! * `wasm32`: `wasm-pack` and a headless Chrome.
+//! * `ios-sim`: `cargo-dinghy` and a booted iOS simulator.
+//! * `android`: `cross`, which runs the test in an emulator.
+//!
+//! `cargo xtask size [target...]` builds `mls-rs-uniffi` with the
+//! `release-mobile` profile for each of the given targets, or for `ios` and
+//! `android` if none is given, and prints the size of the resulting
+//! libraries. The targets are:
+//!
+//! * `ios`: `aarch64-apple-ios`, built with the host toolchain.
+//! * `android`: `aarch64-linux-android`, built with `cross`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+const TEST: &str = "cross_target";
+const FEATURES: &str = "test_util";
+
+#[derive(Clone, Copy, Debug)]
+enum Target {
+    Native,
+    Wasm32,
+    IosSim,
+    Android,
+}
+
+impl Target {
+    const ALL: [Target; 4] = [Self::Native, Self::Wasm32, Self::IosSim, Self::Android];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|target| target.name() == name)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Wasm32 => "wasm32",
+            Self::IosSim => "ios-sim",
+            Self::Android => "android",
+        }
+    }
+
+    fn command(&self, workspace: &Path) -> Command {
+        let cargo_test_args = ["-p", "mls-rs", "--features", FEATURES, "--test", TEST];
+
+        match self {
+            Self::Native => {
+                let mut command = Command::new(cargo());
+
+                command.arg("test").args(cargo_test_args);
+                command
+            }
+            Self::Wasm32 => {
+                let mut command = Command::new("wasm-pack");
+
+                command
+                    .current_dir(workspace.join("mls-rs"))
+                    .args(["test", "--headless", "--chrome", "--release"])
+                    .args(["--test", TEST, "--features", FEATURES]);
+
+                command
+            }
+            Self::IosSim => {
+                let mut command = Command::new(cargo());
+
+                command
+                    .args(["dinghy", "-p", "auto-ios-aarch64-sim", "test"])
+                    .args(cargo_test_args);
+
+                command
+            }
+            Self::Android => {
+                let mut command = Command::new("cross");
+
+                command
+                    .args(["test", "--target", "aarch64-linux-android"])
+                    .args(cargo_test_args);
+
+                command
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MobileTarget {
+    Ios,
+    Android,
+}
+
+impl MobileTarget {
+    const ALL: [MobileTarget; 2] = [Self::Ios, Self::Android];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|target| target.name() == name)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ios => "ios",
+            Self::Android => "android",
+        }
+    }
+
+    fn triple(&self) -> &'static str {
+        match self {
+            Self::Ios => "aarch64-apple-ios",
+            Self::Android => "aarch64-linux-android",
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = match self {
+            Self::Ios => Command::new(cargo()),
+            Self::Android => Command::new("cross"),
+        };
+
+        command
+            .args(["build", "-p", "mls-rs-uniffi", "--profile", SIZE_PROFILE])
+            .args(["--target", self.triple()]);
+
+        command
+    }
+}
+
+const SIZE_PROFILE: &str = "release-mobile";
+const SIZE_LIBRARIES: [&str; 3] = [
+    "libmls_rs_uniffi.a",
+    "libmls_rs_uniffi.so",
+    "libmls_rs_uniffi.dylib",
+];
+
+fn cargo() -> String {
+    env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask is in the workspace root")
+        .to_path_buf()
+}
+
+fn cross_test(names: &[String]) -> Result<(), String> {
+    let targets = if names.is_empty() {
+        Target::ALL.to_vec()
+    } else {
+        names
+            .iter()
+            .map(|name| Target::parse(name).ok_or_else(|| format!("unknown target `{name}`")))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let workspace = workspace_root();
+    let mut failed = Vec::new();
+
+    for target in targets {
+        eprintln!("cross-test: running on {}", target.name());
+
+        let mut command = target.command(&workspace);
+
+        if command.get_current_dir().is_none() {
+            command.current_dir(&workspace);
+        }
+
+        match command.status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => failed.push(format!("{} ({status})", target.name())),
+            Err(e) => failed.push(format!("{} ({e})", target.name())),
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("cross-test failed on: {}", failed.join(", ")))
+    }
+}
+
+fn size(names: &[String]) -> Result<(), String> {
+    let targets = if names.is_empty() {
+        MobileTarget::ALL.to_vec()
+    } else {
+        names
+            .iter()
+            .map(|name| MobileTarget::parse(name).ok_or_else(|| format!("unknown target `{name}`")))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let workspace = workspace_root();
+
+    for target in targets {
+        eprintln!("size: building for {}", target.name());
+
+        let status = target
+            .command()
+            .current_dir(&workspace)
+            .status()
+            .map_err(|e| format!("size failed on {}: {e}", target.name()))?;
+
+        if !status.success() {
+            return Err(format!("size failed on {} ({status})", target.name()));
+        }
+
+        let dir = workspace
+            .join("target")
+            .join(target.triple())
+            .join(SIZE_PROFILE);
+
+        for library in SIZE_LIBRARIES {
+            if let Ok(metadata) = fs::metadata(dir.join(library)) {
+                println!("{} {library}: {} bytes", target.name(), metadata.len());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn usage() -> String {
+    let targets = Target::ALL.map(|target| target.name()).join(", ");
+    let mobile_targets = MobileTarget::ALL.map(|target| target.name()).join(", ");
+
+    format!(
+        "usage: cargo xtask cross-test [target...]\n       cargo xtask size [target...]\n\
+         cross-test targets: {targets}\nsize targets: {mobile_targets}"
+    )
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let res = match args.split_first() {
+        Some((task, names)) if task == "cross-test" => cross_test(names),
+        Some((task, names)) if task == "size" => size(names),
+        _ => Err(usage()),
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}